@@ -0,0 +1,57 @@
+//! How long `todo list` and the TUI take to start on a store of 10,000 todos, on each
+//! backend, run as the built binary so the startup is timed too:
+//! `cargo bench --bench startup`. The TUI is timed in its plain mode, which with nothing
+//! on stdin loads the store, prints the first page and quits. Like the suites in `tests/`,
+//! this only runs on Unix
+
+#[path = "../tests/common/mod.rs"]
+mod common;
+
+use common::Sandbox;
+use criterion::{criterion_group, criterion_main, Criterion};
+use rusqlite::{params, Connection};
+use std::{fs, time::Duration};
+
+const TODOS: usize = 10_000;
+
+/// A sandbox whose JSON store holds `TODOS` todos
+fn json_store() -> Sandbox {
+    let sandbox = Sandbox::new();
+    let todos: Vec<serde_json::Value> = (1..=TODOS)
+        .map(|id| serde_json::json!({ "id": id, "text": format!("todo {}", id), "done": false, "due_date": null, "reminder": null }))
+        .collect();
+    fs::create_dir_all(sandbox.data_dir()).unwrap();
+    fs::write(sandbox.json_path(), serde_json::json!({ "version": 3, "todos": todos }).to_string()).unwrap();
+    sandbox
+}
+
+/// A sandbox whose database holds `TODOS` todos
+fn database() -> Sandbox {
+    let sandbox = Sandbox::new();
+    sandbox.ok(&["--sqlite", "list"]);
+    let mut conn = Connection::open(sandbox.db_path()).unwrap();
+    let tx = conn.transaction().unwrap();
+    for id in 1..=TODOS {
+        tx.execute("INSERT INTO todos (id, text, done, uuid) VALUES (?1, ?2, 0, ?3)", params![id, format!("todo {}", id), id.to_string()])
+            .unwrap();
+    }
+    tx.commit().unwrap();
+    sandbox
+}
+
+fn startup(c: &mut Criterion) {
+    let mut group = c.benchmark_group("startup");
+    group.sample_size(10).measurement_time(Duration::from_secs(10));
+    for (backend, sandbox, flags) in [("json", json_store(), &[][..]), ("sqlite", database(), &["--sqlite"][..])] {
+        let list = [flags, &["list", "--format", "{id}"]].concat();
+        assert_eq!(sandbox.ok(&list).stdout.lines().count(), TODOS);
+        group.bench_function(format!("list/{}", backend), |b| b.iter(|| sandbox.ok(&list)));
+        let tui = [flags, &["tui", "--plain"]].concat();
+        sandbox.ok(&tui);
+        group.bench_function(format!("tui/{}", backend), |b| b.iter(|| sandbox.ok(&tui)));
+    }
+    group.finish();
+}
+
+criterion_group!(benches, startup);
+criterion_main!(benches);
@@ -0,0 +1,140 @@
+use crate::{dates::Due, reminders, Todo};
+use chrono::{Datelike, Duration, Local, NaiveDate, NaiveDateTime, NaiveTime, Weekday};
+use std::collections::BTreeMap;
+
+/// What the agenda needs from a todo, so the CLI and the TUI can group their own types
+pub trait Item {
+    fn id(&self) -> usize;
+    fn is_done(&self) -> bool;
+    fn due(&self) -> Option<Due>;
+    /// When the reminder fires, in local time
+    fn reminder_at(&self) -> Option<NaiveDateTime>;
+}
+
+impl Item for Todo {
+    fn id(&self) -> usize {
+        self.id
+    }
+
+    fn is_done(&self) -> bool {
+        self.done
+    }
+
+    fn due(&self) -> Option<Due> {
+        Due::parse(self.due_date.as_deref()?)
+    }
+
+    fn reminder_at(&self) -> Option<NaiveDateTime> {
+        reminders::parse_reminder(self.reminder.as_deref()?).map(|at| at.with_timezone(&Local).naive_local())
+    }
+}
+
+/// One day of an agenda with the pending todos due on it, earliest due time first
+pub struct Day<'a, T> {
+    pub date: NaiveDate,
+    pub todos: Vec<&'a T>,
+}
+
+/// Pending todos that are already late and the rest of those due today, earliest first
+pub struct Today<'a, T> {
+    pub overdue: Vec<&'a T>,
+    pub due_today: Vec<&'a T>,
+}
+
+/// The first day of the week containing `day`, for weeks starting on `first`
+pub fn week_start(day: NaiveDate, first: Weekday) -> NaiveDate {
+    let back = (day.weekday().num_days_from_monday() + 7 - first.num_days_from_monday()) % 7;
+    day - Duration::days(back as i64)
+}
+
+/// Whether `todo` is still pending past its deadline
+pub fn is_overdue<T: Item>(todo: &T, now: NaiveDateTime) -> bool {
+    !todo.is_done() && todo.due().is_some_and(|due| due.is_overdue(now))
+}
+
+/// Pending todos not yet overdue that fall due within `within` of `now`, earliest first.
+/// One with only a date counts from the start of its day, so tomorrow's are soon tonight
+pub fn due_soon<T: Item>(todos: &[T], now: NaiveDateTime, within: Duration) -> Vec<(Due, &T)> {
+    pending_by_due(todos)
+        .into_iter()
+        .filter(|(due, _)| !due.is_overdue(now) && due.date.and_time(due.time.unwrap_or(NaiveTime::MIN)) <= now + within)
+        .collect()
+}
+
+/// Pending todos with a due date, earliest deadline first
+fn pending_by_due<T: Item>(todos: &[T]) -> Vec<(Due, &T)> {
+    let mut due: Vec<(Due, &T)> = todos
+        .iter()
+        .filter(|t| !t.is_done())
+        .filter_map(|t| Some((t.due()?, t)))
+        .collect();
+    due.sort_by_key(|(due, todo)| (due.deadline(), todo.id()));
+    due
+}
+
+/// Pending todos due in the week that starts on `start`, grouped by day; every day of
+/// the week is present even when nothing is due on it
+pub fn week<T: Item>(todos: &[T], start: NaiveDate) -> Vec<Day<'_, T>> {
+    days(todos, start, 7)
+}
+
+/// Pending todos due in the `count` days from `start`, grouped as `week` does
+pub fn days<T: Item>(todos: &[T], start: NaiveDate, count: i64) -> Vec<Day<'_, T>> {
+    let mut by_day = by_day(todos);
+    (0..count)
+        .map(|offset| {
+            let date = start + Duration::days(offset);
+            Day { date, todos: by_day.remove(&date).unwrap_or_default() }
+        })
+        .collect()
+}
+
+/// Pending todos with a due date by the day they are due, each day's earliest deadline
+/// first
+pub fn by_day<T: Item>(todos: &[T]) -> BTreeMap<NaiveDate, Vec<&T>> {
+    let mut days: BTreeMap<NaiveDate, Vec<&T>> = BTreeMap::new();
+    for (due, todo) in pending_by_due(todos) {
+        days.entry(due.date).or_default().push(todo);
+    }
+    days
+}
+
+/// Saturday or Sunday
+pub fn is_weekend(date: NaiveDate) -> bool {
+    matches!(date.weekday(), Weekday::Sat | Weekday::Sun)
+}
+
+/// The days from `from` on with more than `max` pending todos due, leaving out Saturdays
+/// and Sundays when `weekdays_only` is set
+pub fn crowded<T: Item>(todos: &[T], from: NaiveDate, max: usize, weekdays_only: bool) -> Vec<Day<'_, T>> {
+    by_day(todos)
+        .into_iter()
+        .filter(|(date, due)| *date >= from && due.len() > max && !(weekdays_only && is_weekend(*date)))
+        .map(|(date, todos)| Day { date, todos })
+        .collect()
+}
+
+/// What needs doing now: todos past their deadline, then those still due later today
+pub fn today<T: Item>(todos: &[T], now: NaiveDateTime) -> Today<'_, T> {
+    let (overdue, rest): (Vec<_>, Vec<_>) = pending_by_due(todos).into_iter().partition(|(_, todo)| is_overdue(*todo, now));
+    Today {
+        overdue: overdue.into_iter().map(|(_, todo)| todo).collect(),
+        due_today: rest
+            .into_iter()
+            .filter(|(due, _)| due.date == now.date())
+            .map(|(_, todo)| todo)
+            .collect(),
+    }
+}
+
+/// The next `limit` reminders still to fire on pending todos, soonest first
+pub fn next_reminders<T: Item>(todos: &[T], now: NaiveDateTime, limit: usize) -> Vec<&T> {
+    let mut upcoming: Vec<(NaiveDateTime, &T)> = todos
+        .iter()
+        .filter(|t| !t.is_done())
+        .filter_map(|t| Some((t.reminder_at()?, t)))
+        .filter(|(at, _)| *at >= now)
+        .collect();
+    upcoming.sort_by_key(|(at, todo)| (*at, todo.id()));
+    upcoming.into_iter().take(limit).map(|(_, todo)| todo).collect()
+}
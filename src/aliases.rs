@@ -0,0 +1,125 @@
+use std::collections::BTreeMap;
+
+/// Options that come before the subcommand and take a value as the next argument
+const VALUE_OPTIONS: [&str; 2] = ["--db", "--profile"];
+
+/// Position of the subcommand among the arguments, skipping the program name and any
+/// options in front of it
+fn command_position(args: &[String]) -> Option<usize> {
+    let mut i = 1;
+    while let Some(arg) = args.get(i) {
+        if arg == "--" {
+            return None;
+        }
+        if !arg.starts_with('-') {
+            return Some(i);
+        }
+        i += if VALUE_OPTIONS.contains(&arg.as_str()) { 2 } else { 1 };
+    }
+    None
+}
+
+/// Split an alias into arguments at whitespace, keeping quoted stretches together
+pub fn split(value: &str) -> Result<Vec<String>, String> {
+    let mut words = Vec::new();
+    let mut word: Option<String> = None;
+    let mut quote: Option<char> = None;
+    for c in value.chars() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), c) => word.get_or_insert_with(String::new).push(c),
+            (None, '"' | '\'') => {
+                quote = Some(c);
+                word.get_or_insert_with(String::new);
+            }
+            (None, c) if c.is_whitespace() => words.extend(word.take()),
+            (None, c) => word.get_or_insert_with(String::new).push(c),
+        }
+    }
+    if quote.is_some() {
+        return Err("unterminated quote".to_string());
+    }
+    words.extend(word);
+    Ok(words)
+}
+
+/// Replace a user alias in the subcommand position with what it stands for, the way git
+/// does: built-in commands always win, and the arguments after the alias are kept after
+/// its expansion. Aliases may use other aliases, but not themselves
+pub fn expand(
+    mut args: Vec<String>,
+    aliases: &BTreeMap<String, String>,
+    is_builtin: impl Fn(&str) -> bool,
+) -> Result<Vec<String>, String> {
+    let mut seen: Vec<String> = Vec::new();
+    while let Some(i) = command_position(&args) {
+        let name = &args[i];
+        let Some(value) = aliases.get(name).filter(|_| !is_builtin(name)) else {
+            break;
+        };
+        if seen.contains(name) {
+            seen.push(name.clone());
+            return Err(format!("alias loop: {}", seen.join(" → ")));
+        }
+        let words = split(value).map_err(|e| format!("alias '{}': {}", name, e))?;
+        if words.is_empty() {
+            return Err(format!("alias '{}' is empty", name));
+        }
+        seen.push(name.clone());
+        args.splice(i..=i, words);
+    }
+    Ok(args)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(line: &str) -> Vec<String> {
+        line.split(' ').map(str::to_string).collect()
+    }
+
+    fn aliases(pairs: &[(&str, &str)]) -> BTreeMap<String, String> {
+        pairs.iter().map(|(name, value)| (name.to_string(), value.to_string())).collect()
+    }
+
+    fn expanded(line: &str, pairs: &[(&str, &str)]) -> Result<String, String> {
+        expand(args(line), &aliases(pairs), |name| name == "list").map(|args| args.join(" "))
+    }
+
+    #[test]
+    fn a_multi_word_alias_keeps_the_arguments_after_it() {
+        let pairs = [("t", "list --filter 'tag:work and not done'")];
+        assert_eq!(
+            expand(args("todo t --tree"), &aliases(&pairs), |name| name == "list").unwrap(),
+            ["todo", "list", "--filter", "tag:work and not done", "--tree"]
+        );
+    }
+
+    #[test]
+    fn options_before_the_command_are_skipped() {
+        let pairs = [("t", "list --tree"), ("work", "t")];
+        assert_eq!(expanded("todo --sqlite --db x.db t --done", &pairs), Ok("todo --sqlite --db x.db list --tree --done".to_string()));
+        assert_eq!(expanded("todo --profile work t", &pairs), Ok("todo --profile work list --tree".to_string()));
+        assert_eq!(expanded("todo work", &pairs), Ok("todo list --tree".to_string()));
+        assert_eq!(expanded("todo add t", &pairs), Ok("todo add t".to_string()));
+    }
+
+    #[test]
+    fn built_in_commands_win() {
+        assert_eq!(expanded("todo list", &[("list", "add x")]), Ok("todo list".to_string()));
+    }
+
+    #[test]
+    fn loops_and_empty_aliases_are_errors() {
+        assert_eq!(expanded("todo a", &[("a", "b"), ("b", "a")]), Err("alias loop: a → b → a".to_string()));
+        assert_eq!(expanded("todo a", &[("a", "a --x")]), Err("alias loop: a → a".to_string()));
+        assert_eq!(expanded("todo a", &[("a", " ")]), Err("alias 'a' is empty".to_string()));
+        assert_eq!(expanded("todo a", &[("a", "list 'x")]), Err("alias 'a': unterminated quote".to_string()));
+    }
+
+    #[test]
+    fn quotes_keep_words_together() {
+        assert_eq!(split(r#"add "buy milk" 'and eggs' "" x"#), Ok(vec!["add".into(), "buy milk".into(), "and eggs".into(), String::new(), "x".into()]));
+    }
+}
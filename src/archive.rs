@@ -0,0 +1,100 @@
+use crate::{config::Config, dates, last, messages::{self, Msg}, now_timestamp, store::Store, trash, Todo};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::{fs, io, path::PathBuf, sync::OnceLock};
+
+/// A done todo moved out of the store by the auto-archive sweep
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Archived {
+    /// RFC 3339 timestamp
+    pub archived_at: String,
+    #[serde(flatten)]
+    pub todo: Todo,
+}
+
+/// The archive file for the backend in use, set once at startup
+static PATH: OnceLock<PathBuf> = OnceLock::new();
+
+pub fn set_path(path: PathBuf) {
+    let _ = PATH.set(path);
+}
+
+/// What is in the archive, oldest first
+pub fn read() -> Vec<Archived> {
+    PATH.get()
+        .and_then(|path| fs::read_to_string(path).ok())
+        .and_then(|data| serde_json::from_str(&data).ok())
+        .unwrap_or_default()
+}
+
+/// Add `todos` to the end of the archive, by a rename so a reader never sees it half written
+fn append(todos: &[Todo]) -> io::Result<()> {
+    let Some(path) = PATH.get() else {
+        return Ok(());
+    };
+    let mut archived = read();
+    let archived_at = now_timestamp();
+    archived.extend(todos.iter().map(|todo| Archived { archived_at: archived_at.clone(), todo: todo.clone() }));
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir.to_path_buf(),
+        _ => PathBuf::from("."),
+    };
+    fs::create_dir_all(&dir)?;
+    let file = tempfile::NamedTempFile::new_in(&dir)?;
+    serde_json::to_writer_pretty(&file, &archived)?;
+    file.persist(path)?;
+    Ok(())
+}
+
+/// Whether the sweeps are switched on and haven't run in the last day
+pub fn sweep_due(config: &Config) -> bool {
+    if config.auto_archive_done_after.is_none() && config.auto_trash_empty_after.is_none() {
+        return false;
+    }
+    last::is_due(last::swept_at(), chrono::Duration::days(1))
+}
+
+/// Move todos done longer ago than auto_archive_done_after into the archive, and empty what
+/// was deleted longer ago than auto_trash_empty_after from the trash, saying what went.
+/// Pending todos, and done ones without a completion time, are never touched. The caller
+/// holds the store's lock and has checked `sweep_due`
+pub fn sweep(store: &mut dyn Store, config: &Config) -> crate::store::Result<()> {
+    let now = Utc::now();
+    if let Some(age) = config.auto_archive_done_after() {
+        let cutoff = now - age;
+        let expired: Vec<Todo> = store
+            .todos()?
+            .into_iter()
+            .filter(|t| {
+                t.done
+                    && t.completed_at
+                        .as_deref()
+                        .and_then(|at| DateTime::parse_from_rfc3339(at).ok())
+                        .is_some_and(|at| at < cutoff)
+            })
+            .collect();
+        if !expired.is_empty() {
+            // Archived first, so a failure part way leaves a copy rather than nothing
+            match append(&expired) {
+                Ok(()) => {
+                    let ids: Vec<usize> = expired.iter().map(|t| t.id).collect();
+                    store.delete(&ids)?;
+                    let age = dates::format_duration(age);
+                    messages::say(Msg::Archived { count: ids.len(), age: &age, label: store.label() });
+                }
+                Err(e) => messages::say(Msg::ArchiveNotWritten { error: &e }),
+            }
+        }
+    }
+    if let Some(age) = config.auto_trash_empty_after() {
+        match trash::empty_before(now - age) {
+            Ok(0) => {}
+            Ok(emptied) => messages::say(Msg::TrashSwept { count: emptied, age: &dates::format_duration(age) }),
+            Err(e) => messages::say(Msg::TrashNotSwept { error: &e }),
+        }
+    }
+    if let Err(e) = last::record_sweep() {
+        messages::say(Msg::CouldNotNote { what: "the archive sweep ran", error: &e });
+    }
+    Ok(())
+}
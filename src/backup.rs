@@ -0,0 +1,128 @@
+use crate::{reminders, Todo, SCHEMA_VERSION};
+use chrono::Local;
+use flate2::{read::GzDecoder, write::GzEncoder, Compression};
+use serde::{Deserialize, Serialize};
+use std::{
+    fs::{self, File},
+    io::{self, Read},
+    path::{Path, PathBuf},
+};
+
+const MANIFEST_NAME: &str = "manifest.json";
+const TODOS_NAME: &str = "todos.json";
+
+/// Describes the contents of a backup bundle so it can be restored onto either backend
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Manifest {
+    pub schema_version: u32,
+    pub backend: String,
+    pub created_at: String,
+    pub todo_count: usize,
+}
+
+pub struct Bundle {
+    pub manifest: Manifest,
+    pub todos: Vec<Todo>,
+}
+
+/// Write the todos and a manifest into a gzipped tarball at `path`
+pub fn write_bundle(path: &Path, todos: &[Todo], backend: &str) -> io::Result<()> {
+    let manifest = Manifest {
+        schema_version: SCHEMA_VERSION,
+        backend: backend.to_string(),
+        created_at: Local::now().to_rfc3339(),
+        todo_count: todos.len(),
+    };
+
+    let file = File::create(path)?;
+    let mut builder = tar::Builder::new(GzEncoder::new(file, Compression::default()));
+    append_json(&mut builder, MANIFEST_NAME, &manifest)?;
+    append_json(&mut builder, TODOS_NAME, &todos)?;
+    builder.into_inner()?.finish()?;
+    Ok(())
+}
+
+fn append_json<W: io::Write, T: Serialize + ?Sized>(
+    builder: &mut tar::Builder<W>,
+    name: &str,
+    value: &T,
+) -> io::Result<()> {
+    let data = serde_json::to_vec_pretty(value)?;
+    let mut header = tar::Header::new_gnu();
+    header.set_size(data.len() as u64);
+    header.set_mode(0o644);
+    header.set_mtime(Local::now().timestamp() as u64);
+    header.set_cksum();
+    builder.append_data(&mut header, name, data.as_slice())
+}
+
+/// Read a bundle written by `write_bundle`, refusing ones from a newer schema and
+/// bringing ones from an older schema up to this one
+pub fn read_bundle(path: &Path) -> io::Result<Bundle> {
+    let file = File::open(path)?;
+    let mut archive = tar::Archive::new(GzDecoder::new(file));
+
+    let mut manifest: Option<Manifest> = None;
+    let mut todos: Option<Vec<Todo>> = None;
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        let name = entry.path()?.to_string_lossy().into_owned();
+        let mut data = String::new();
+        entry.read_to_string(&mut data)?;
+        match name.as_str() {
+            MANIFEST_NAME => manifest = Some(serde_json::from_str(&data)?),
+            TODOS_NAME => todos = Some(serde_json::from_str(&data)?),
+            _ => {}
+        }
+    }
+
+    let manifest = manifest.ok_or_else(|| invalid("bundle has no manifest"))?;
+    if manifest.schema_version > SCHEMA_VERSION {
+        return Err(invalid(&format!(
+            "bundle uses schema version {}, this build only understands up to {}",
+            manifest.schema_version, SCHEMA_VERSION
+        )));
+    }
+    let mut todos = todos.ok_or_else(|| invalid("bundle has no todos"))?;
+    if manifest.schema_version < 12 {
+        reminders::carry_recurrence(&mut todos);
+    }
+    Ok(Bundle { manifest, todos })
+}
+
+/// Copy the file at `path` next to itself with a timestamped suffix, returning the copy's path
+pub fn safety_copy(path: &Path) -> io::Result<Option<String>> {
+    if !path.exists() {
+        return Ok(None);
+    }
+    let copy = format!(
+        "{}.pre-restore-{}",
+        path.display(),
+        Local::now().format("%Y%m%d%H%M%S")
+    );
+    std::fs::copy(path, &copy)?;
+    Ok(Some(copy))
+}
+
+/// The safety copies made of the file at `path`, newest first
+pub fn safety_copies(path: &Path) -> Vec<PathBuf> {
+    let (Some(dir), Some(name)) = (path.parent(), path.file_name()) else {
+        return vec![];
+    };
+    let dir = if dir.as_os_str().is_empty() { Path::new(".") } else { dir };
+    let prefix = format!("{}.pre-restore-", name.to_string_lossy());
+    let mut copies: Vec<PathBuf> = fs::read_dir(dir)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter(|entry| entry.file_name().to_string_lossy().starts_with(&prefix))
+        .map(|entry| entry.path())
+        .collect();
+    // The suffix is a timestamp that sorts as it reads
+    copies.sort_by(|a, b| b.cmp(a));
+    copies
+}
+
+fn invalid(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg.to_string())
+}
@@ -0,0 +1,170 @@
+use crate::{dates::{self, DateDisplay, Due}, last, next_id, now_timestamp, reminders, validate_datetime, Todo};
+use chrono::Local;
+use std::collections::HashSet;
+
+const HEADER: &str = "\
+# One todo per line: id<TAB>[ ] or [x]<TAB>text<TAB>due YYYY-MM-DD [HH:MM]<TAB>reminder YYYY-MM-DD HH:MM
+# Edit fields in place, delete a line to delete its todo, or add a line without an id
+# (just the text is enough) to create one. Lines starting with # are ignored.
+";
+
+/// The whole list as an editable buffer, with reminders in local time
+pub fn render(todos: &[Todo]) -> String {
+    let mut out = String::from(HEADER);
+    for todo in todos {
+        let reminder = todo.reminder.as_deref().map(local).unwrap_or_default();
+        out.push_str(&format!(
+            "{}\t{}\t{}\t{}\t{}\n",
+            todo.id,
+            if todo.done { "[x]" } else { "[ ]" },
+            todo.text,
+            todo.due_date.as_deref().unwrap_or(""),
+            reminder
+        ));
+    }
+    out
+}
+
+/// The result of applying an edited buffer, ready to be saved in one go
+pub struct Plan {
+    pub todos: Vec<Todo>,
+    pub updated: usize,
+    pub created: usize,
+    pub deleted: Vec<Todo>,
+}
+
+/// Fields of one buffer line; `id` is None for a new todo
+struct Line {
+    id: Option<usize>,
+    done: bool,
+    text: String,
+    due_date: Option<String>,
+    reminder: Option<String>,
+}
+
+/// Apply an edited buffer to `todos`. Any malformed line rejects the whole edit, with one
+/// message per problem prefixed by its line number, so nothing is ever half applied
+pub fn apply(todos: &[Todo], buffer: &str) -> Result<Plan, Vec<String>> {
+    let mut errors = Vec::new();
+    let mut lines = Vec::new();
+    let mut seen = HashSet::new();
+    for (index, raw) in buffer.lines().enumerate() {
+        let number = index + 1;
+        if raw.trim().is_empty() || raw.starts_with('#') {
+            continue;
+        }
+        match parse_line(raw) {
+            Ok(line) => {
+                if let Some(id) = line.id {
+                    if !todos.iter().any(|t| t.id == id) {
+                        errors.push(format!("line {}: there is no todo with id {}", number, id));
+                    } else if !seen.insert(id) {
+                        errors.push(format!("line {}: id {} appears more than once", number, id));
+                    }
+                }
+                lines.push(line);
+            }
+            Err(e) => errors.push(format!("line {}: {}", number, e)),
+        }
+    }
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+
+    let mut plan = Plan { todos: Vec::new(), updated: 0, created: 0, deleted: Vec::new() };
+    for todo in todos {
+        match lines.iter().find(|l| l.id == Some(todo.id)) {
+            Some(line) => {
+                let edited = edit(todo, line);
+                if !same(todo, &edited) {
+                    plan.updated += 1;
+                }
+                plan.todos.push(edited);
+            }
+            None => plan.deleted.push(todo.clone()),
+        }
+    }
+    for line in lines.iter().filter(|l| l.id.is_none()) {
+        let blank = Todo {
+            id: next_id(&plan.todos).max(next_id(todos)),
+            created_at: Some(now_timestamp()),
+            uuid: crate::uuid::new_v4(),
+            ..Default::default()
+        };
+        plan.todos.push(edit(&blank, line));
+        plan.created += 1;
+    }
+    Ok(plan)
+}
+
+fn parse_line(raw: &str) -> Result<Line, String> {
+    let fields: Vec<&str> = raw.split('\t').map(str::trim).collect();
+    if fields.len() == 1 {
+        return Ok(Line { id: None, done: false, text: fields[0].to_string(), due_date: None, reminder: None });
+    }
+    if fields.len() > 5 {
+        return Err(format!("expected at most 5 tab-separated fields, found {}", fields.len()));
+    }
+    let field = |i: usize| fields.get(i).copied().unwrap_or("");
+
+    let id = match field(0) {
+        "" => None,
+        id => Some(last::parse_number(id)?),
+    };
+    let done = match field(1) {
+        "" | "[ ]" => false,
+        "[x]" | "[X]" => true,
+        other => return Err(format!("status '{}' should be [ ] or [x]", other)),
+    };
+    let text = field(2).to_string();
+    if text.is_empty() {
+        return Err("the text is empty".to_string());
+    }
+    let due_date = match field(3) {
+        "" => None,
+        due => {
+            let due = Due::parse(due).ok_or_else(|| format!("due date '{}' is not in YYYY-MM-DD [HH:MM] format", due))?;
+            dates::check_window(due.date, Local::now().date_naive()).map_err(|e| format!("due date: {}", e))?;
+            Some(due.to_stored())
+        }
+    };
+    let reminder = match field(4) {
+        "" => None,
+        reminder => {
+            let (date, time) = reminder.split_once(' ').unwrap_or((reminder, ""));
+            let local = validate_datetime(date, time.trim())
+                .map_err(|_| format!("reminder '{}' is not in YYYY-MM-DD HH:MM format", reminder))?;
+            dates::check_window(local.date(), Local::now().date_naive()).map_err(|e| format!("reminder: {}", e))?;
+            Some(reminders::to_stored(local))
+        }
+    };
+    Ok(Line { id, done, text, due_date, reminder })
+}
+
+/// `todo` with a line's fields applied, keeping timestamps and repeat rules consistent
+fn edit(todo: &Todo, line: &Line) -> Todo {
+    let mut edited = todo.clone();
+    edited.text = line.text.clone();
+    edited.due_date = line.due_date.clone();
+    if line.done != todo.done {
+        edited.done = line.done;
+        edited.completed_at = line.done.then(now_timestamp);
+    }
+    // An untouched reminder round-trips through local time to the minute; keep the stored
+    // value so its original offset and seconds aren't rewritten
+    if todo.reminder.as_deref().map(local) != line.reminder.as_deref().map(local) {
+        edited.reminder = line.reminder.clone();
+        if edited.reminder.is_none() {
+            edited.reminder_repeat = None;
+        }
+    }
+    edited
+}
+
+fn local(reminder: &str) -> String {
+    dates::display_reminder(reminder, DateDisplay::default())
+}
+
+fn same(a: &Todo, b: &Todo) -> bool {
+    a.text == b.text && a.done == b.done && a.due_date == b.due_date && a.reminder == b.reminder
+}
@@ -0,0 +1,69 @@
+use crate::Todo;
+use chrono::{DateTime, Duration, Local, NaiveDate};
+use clap::ValueEnum;
+use serde::{Serialize, Serializer};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum Output {
+    Text,
+    Json,
+}
+
+/// How many todos were still open at the end of a day
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Day {
+    #[serde(serialize_with = "iso_date")]
+    pub date: NaiveDate,
+    pub open: usize,
+}
+
+fn iso_date<S: Serializer>(date: &NaiveDate, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.collect_str(&date.format(crate::dates::ISO_DATE))
+}
+
+/// Widest bar, in columns, for the day with the most open todos
+const BAR_WIDTH: usize = 40;
+
+/// Partial blocks for the fractional end of a bar, in eighths
+const EIGHTHS: [char; 8] = [' ', '▏', '▎', '▍', '▌', '▋', '▊', '▉'];
+
+fn local_date(timestamp: Option<&str>) -> Option<NaiveDate> {
+    let datetime = DateTime::parse_from_rfc3339(timestamp?).ok()?;
+    Some(datetime.with_timezone(&Local).date_naive())
+}
+
+/// Whether the todo was open at the end of `date`. Todos without a creation time are taken
+/// to have existed all along, and done ones without a completion time to have been closed
+/// before any day in question
+fn open_at(todo: &Todo, date: NaiveDate) -> bool {
+    let created = local_date(todo.created_at.as_deref()).is_none_or(|created| created <= date);
+    let closed = todo.done && local_date(todo.completed_at.as_deref()).is_none_or(|completed| completed <= date);
+    created && !closed
+}
+
+/// Open counts for each of the `days` days ending with `today`, oldest first
+pub fn series<'a>(todos: impl IntoIterator<Item = &'a Todo> + Clone, today: NaiveDate, days: u32) -> Vec<Day> {
+    (0..i64::from(days))
+        .rev()
+        .map(|back| {
+            let date = today - Duration::days(back);
+            let open = todos.clone().into_iter().filter(|todo| open_at(todo, date)).count();
+            Day { date, open }
+        })
+        .collect()
+}
+
+/// One line per day: the date, a bar scaled to the busiest day and the count
+pub fn render(days: &[Day]) -> Vec<String> {
+    let max = days.iter().map(|day| day.open).max().unwrap_or(0).max(1);
+    days.iter()
+        .map(|day| {
+            let eighths = day.open * BAR_WIDTH * 8 / max;
+            let mut bar = "█".repeat(eighths / 8);
+            if !eighths.is_multiple_of(8) {
+                bar.push(EIGHTHS[eighths % 8]);
+            }
+            format!("{} {:<width$} {}", day.date.format("%m-%d"), bar, day.open, width = BAR_WIDTH)
+        })
+        .collect()
+}
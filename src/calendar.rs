@@ -0,0 +1,94 @@
+use crate::agenda::Day;
+use chrono::{Datelike, Months, NaiveDate, Weekday};
+use console::style;
+use std::cmp::Ordering;
+
+/// Width of a day's cell, inside its borders; seven of them fit in 80 columns
+const CELL: usize = 10;
+
+/// The first day of the month holding `day`
+pub fn month_start(day: NaiveDate) -> NaiveDate {
+    day.with_day(1).unwrap_or(day)
+}
+
+/// How many days the month starting on `first` has
+pub fn days_in_month(first: NaiveDate) -> i64 {
+    first.checked_add_months(Months::new(1)).map_or(31, |next| (next - first).num_days())
+}
+
+/// The month `days` covers as a grid of weeks starting on `first_weekday`, each day with
+/// how many todos are due on it, red once the day has passed and yellow for today
+pub fn month<T>(days: &[Day<T>], first_weekday: Weekday, today: NaiveDate) -> Vec<String> {
+    let Some(first) = days.first().map(|day| day.date) else {
+        return vec![];
+    };
+    let rule = format!("+{}", format!("{}+", "-".repeat(CELL)).repeat(7));
+    let title = format!("{:^width$}", first.format("%B %Y").to_string(), width = rule.len());
+    let mut lines = vec![title.trim_end().to_string(), rule.clone()];
+    let mut weekday = first_weekday;
+    let mut names = Vec::new();
+    for _ in 0..7 {
+        names.push(format!("{:^CELL$}", weekday.to_string()));
+        weekday = weekday.succ();
+    }
+    lines.push(format!("|{}|", names.join("|")));
+    lines.push(rule.clone());
+
+    let lead = (first.weekday().num_days_from_monday() + 7 - first_weekday.num_days_from_monday()) % 7;
+    let mut cells: Vec<Option<&Day<T>>> = (0..lead).map(|_| None).chain(days.iter().map(Some)).collect();
+    cells.resize(cells.len().div_ceil(7) * 7, None);
+    for week in cells.chunks(7) {
+        let row: Vec<String> = week.iter().map(|day| month_cell(*day, today)).collect();
+        lines.push(format!("|{}|", row.join("|")));
+        lines.push(rule.clone());
+    }
+    lines
+}
+
+/// The week `days` covers as a single row, each day with how many todos are due on it
+pub fn strip<T>(days: &[Day<T>], today: NaiveDate) -> Vec<String> {
+    let rule = format!("+{}", format!("{}+", "-".repeat(CELL)).repeat(days.len()));
+    let names: Vec<String> = days
+        .iter()
+        .map(|day| {
+            let marker = if day.date == today { "*" } else { "" };
+            format!("{:^CELL$}", format!("{} {}{}", day.date.format("%a"), day.date.day(), marker))
+        })
+        .collect();
+    let counts: Vec<String> = days
+        .iter()
+        .map(|day| match day.todos.len() {
+            0 => format!("{:^CELL$}", "-"),
+            count => {
+                let count = count.to_string();
+                let left = (CELL - count.len()) / 2;
+                let right = CELL - count.len() - left;
+                format!("{}{}{}", " ".repeat(left), colored(count, day.date, today), " ".repeat(right))
+            }
+        })
+        .collect();
+    vec![rule.clone(), format!("|{}|", names.join("|")), format!("|{}|", counts.join("|")), rule]
+}
+
+/// A day of the month grid: its number, starred if it is today, and its count in brackets
+fn month_cell<T>(day: Option<&Day<T>>, today: NaiveDate) -> String {
+    let Some(day) = day else {
+        return " ".repeat(CELL);
+    };
+    let number = format!(" {:>2}{}", day.date.day(), if day.date == today { "*" } else { " " });
+    if day.todos.is_empty() {
+        return format!("{:<CELL$}", number);
+    }
+    let count = format!("({})", day.todos.len());
+    let gap = CELL.saturating_sub(number.len() + count.len() + 1);
+    format!("{}{}{} ", number, " ".repeat(gap), colored(count, day.date, today))
+}
+
+/// `text` coloured for whether `date` has passed, is today or is still to come
+fn colored(text: String, date: NaiveDate, today: NaiveDate) -> String {
+    match date.cmp(&today) {
+        Ordering::Less => style(text).red().to_string(),
+        Ordering::Equal => style(text).yellow().bold().to_string(),
+        Ordering::Greater => style(text).green().to_string(),
+    }
+}
@@ -0,0 +1,57 @@
+use crate::messages::{self, Msg};
+use crossterm::terminal;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+/// Set once Ctrl-C has been pressed
+static CANCELLED: AtomicBool = AtomicBool::new(false);
+/// How many writes are under way that Ctrl-C must not cut short
+static HOLDS: AtomicUsize = AtomicUsize::new(0);
+
+/// What a process stopped by Ctrl-C exits with, as a shell reports SIGINT
+const EXIT_CODE: i32 = 130;
+
+/// Exit cleanly on Ctrl-C: at once, unless a write is under way, in which case as soon as
+/// it is done. Open SQLite transactions are rolled back from the journal by the next
+/// connection, so stopping between statements leaves the database as it was
+pub fn install() {
+    let result = ctrlc::set_handler(|| {
+        CANCELLED.store(true, Ordering::SeqCst);
+        if HOLDS.load(Ordering::SeqCst) == 0 {
+            exit();
+        }
+    });
+    if let Err(e) = result {
+        messages::say(Msg::NoCtrlC { error: &e });
+    }
+}
+
+/// Whether Ctrl-C has been pressed, for loops that should stop at the end of a step
+pub fn requested() -> bool {
+    CANCELLED.load(Ordering::SeqCst)
+}
+
+/// Keeps Ctrl-C from stopping the process until it is dropped
+pub struct Hold(());
+
+/// Put off Ctrl-C until the returned hold is dropped, around a write that must finish
+pub fn hold() -> Hold {
+    HOLDS.fetch_add(1, Ordering::SeqCst);
+    Hold(())
+}
+
+impl Drop for Hold {
+    fn drop(&mut self) {
+        if HOLDS.fetch_sub(1, Ordering::SeqCst) == 1 && requested() {
+            exit();
+        }
+    }
+}
+
+/// Put the terminal back as it was, in case a prompt or raw mode had it, and exit
+fn exit() -> ! {
+    let _ = terminal::disable_raw_mode();
+    let _ = console::Term::stderr().show_cursor();
+    eprintln!();
+    messages::say(Msg::Cancelled);
+    std::process::exit(EXIT_CODE);
+}
@@ -0,0 +1,114 @@
+use crate::{last, now_timestamp, tag_summary, Todo};
+use std::collections::HashMap;
+
+/// The todos by id, so two versions of a large store can be matched up without a search
+/// through one for each todo of the other
+pub fn by_id(todos: &[Todo]) -> HashMap<usize, &Todo> {
+    todos.iter().map(|todo| (todo.id, todo)).collect()
+}
+
+/// Describe how `after` differs from `before`, one line per change, e.g.
+/// "would mark #4 'buy milk' done"
+pub fn describe(before: &[Todo], after: &[Todo]) -> Vec<String> {
+    let mut lines = Vec::new();
+    let (old_ids, new_ids) = (by_id(before), by_id(after));
+    for old in before {
+        match new_ids.get(&old.id) {
+            Some(new) => describe_update(old, new, &mut lines),
+            None => lines.push(format!("would delete #{} '{}'", old.id, old.text)),
+        }
+    }
+    for new in after.iter().filter(|t| !old_ids.contains_key(&t.id)) {
+        lines.push(format!("would add #{} '{}'", new.id, new.text));
+    }
+    lines
+}
+
+fn describe_update(old: &Todo, new: &Todo, lines: &mut Vec<String>) {
+    if old == new {
+        return;
+    }
+    let start = lines.len();
+    let name = format!("#{} '{}'", old.id, old.text);
+    if old.text != new.text {
+        lines.push(format!("would change the text of {} to '{}'", name, new.text));
+    }
+    if old.done != new.done {
+        lines.push(format!("would mark {} {}", name, if new.done { "done" } else { "pending" }));
+    }
+    if old.due_date != new.due_date {
+        lines.push(match &new.due_date {
+            Some(due) => format!("would set the due date of {} to {}", name, due),
+            None => format!("would clear the due date of {}", name),
+        });
+    }
+    if old.reminder != new.reminder {
+        lines.push(match &new.reminder {
+            Some(reminder) => format!("would set the reminder of {} to {}", name, reminder),
+            None => format!("would clear the reminder of {}", name),
+        });
+    }
+    if old.recurrence != new.recurrence {
+        lines.push(match &new.recurrence {
+            Some(recurrence) => format!("would make {} repeat {}", name, recurrence),
+            None => format!("would stop {} repeating", name),
+        });
+    }
+    if old.tags != new.tags {
+        lines.push(if new.tags.is_empty() {
+            format!("would clear the tags of {}", name)
+        } else {
+            format!("would set the tags of {} to {}", name, tag_summary(&new.tags))
+        });
+    }
+    if old.priority != new.priority {
+        lines.push(match &new.priority {
+            Some(priority) => format!("would set the priority of {} to {}", name, priority),
+            None => format!("would clear the priority of {}", name),
+        });
+    }
+    if lines.len() == start {
+        lines.push(format!("would update {}", name));
+    }
+}
+
+/// Set `updated_at` to now on each todo in `after` that is new or differs from how `before`
+/// holds it, returning their ids. Run once per command, so every command keeps it up to date
+/// on either backend without setting it itself
+pub fn stamp(before: &[Todo], after: &mut [Todo]) -> Vec<usize> {
+    let now = now_timestamp();
+    let before = by_id(before);
+    let mut stamped = Vec::new();
+    for new in after.iter_mut() {
+        if before.get(&new.id) != Some(&&*new) {
+            new.updated_at = Some(now.clone());
+            stamped.push(new.id);
+        }
+    }
+    stamped
+}
+
+/// The ids of the last todo `after` adds, completes and changes compared with `before`
+pub fn touched(before: &[Todo], after: &[Todo]) -> last::State {
+    let mut state = last::State::default();
+    let before = by_id(before);
+    for new in after {
+        match before.get(&new.id).copied() {
+            None => {
+                state.added = Some(new.id);
+                state.modified = Some(new.id);
+                if new.done {
+                    state.done = Some(new.id);
+                }
+            }
+            Some(old) if old != new => {
+                state.modified = Some(new.id);
+                if new.done && !old.done {
+                    state.done = Some(new.id);
+                }
+            }
+            Some(_) => {}
+        }
+    }
+    state
+}
@@ -0,0 +1,324 @@
+use super::{
+    due::{auto_reminder, warn_due_after_parent},
+    can_ask, not_found, todo_text, Output,
+};
+use crate::{
+    config,
+    dates::{self, DateDisplay, Due},
+    messages::Msg,
+    now_timestamp,
+    priority::Priority,
+    quick_add::{infer_due, parse_due, parse_quick_add, Meta},
+    reminders::{self, AutoRemind},
+    saved_templates,
+    store::{Result, Store},
+    template::Template,
+    text::{escape, same_text},
+    uuid, Todo,
+};
+use chrono::{DateTime, Local};
+
+/// What `add` does when a done todo already has the new todo's text
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Repeat {
+    /// Ask on a terminal; without one, add a new todo and point the done one out
+    Ask,
+    /// Reopen the done todo, so its notes and history stay with it; `clear_dates` drops
+    /// its old due date and reminder
+    Reopen { clear_dates: bool },
+    /// Add a new todo anyway
+    New,
+}
+
+/// Add a todo with `text`, reporting its id, or only the id when `print_id` is set. Quick-add
+/// markers in the text set its due date, tags and priority; with `infer_dates` (or the
+/// config's `infer_dates`) a date phrase in the text sets the due date when no marker did.
+/// A done todo with the same text is reopened instead when `repeat` says so
+pub fn run(
+    store: &mut dyn Store,
+    text: &[String],
+    print_id: bool,
+    parent: Option<usize>,
+    infer_dates: bool,
+    repeat: Repeat,
+) -> Result<Output> {
+    let todos = store.todos()?;
+    if let Some(parent) = parent
+        && !todos.iter().any(|t| t.id == parent)
+    {
+        return Ok(not_found(parent, &todos, &[]));
+    }
+    let mut out = Output::default();
+    let Some(text) = todo_text(&text.join(" "), &mut out) else {
+        return Ok(out);
+    };
+    let today = Local::now().date_naive();
+    let (text, mut meta) = parse_quick_add(&text, today);
+    for warning in &meta.warnings {
+        out.msg(Msg::Warned { warning });
+    }
+    let Some(text) = todo_text(&text, &mut out) else {
+        return Ok(out);
+    };
+    let mut inferred = None;
+    if meta.due.is_none() && (infer_dates || config::load().infer_dates.unwrap_or(false)) {
+        inferred = infer_due(&text, today);
+        meta.due = inferred.as_ref().map(|inferred| inferred.due);
+    }
+    let inherited = meta.due.is_none().then(|| parent_due(&todos, parent)).flatten();
+    if inherited.is_some() {
+        meta.due = inherited;
+    }
+    if let Some(same) = todos.iter().find(|t| !t.done && same_text(&t.text, &text)) {
+        out.msg(Msg::SameText { id: same.id });
+    } else if let Some(done) = todos.iter().rev().find(|t| t.done && same_text(&t.text, &text)) {
+        match decide(repeat, done, &mut out) {
+            Some(Repeat::Reopen { clear_dates }) => {
+                reopen(store, done.clone(), clear_dates, &meta, print_id, &mut out)?;
+                return Ok(out);
+            }
+            Some(_) => {}
+            None => {
+                out.warn("Nothing added");
+                return Ok(out);
+            }
+        }
+    }
+    let mut todo = Todo {
+        text,
+        due_date: meta.due.map(|due| due.to_stored()),
+        tags: meta.tags.clone(),
+        priority: meta.priority.map(|p| p.as_str().to_string()),
+        created_at: Some(now_timestamp()),
+        uuid: uuid::new_v4(),
+        parent,
+        ..Default::default()
+    };
+    let id = store.insert(todo.clone())?;
+    if print_id {
+        out.say(id.to_string());
+    } else {
+        match parent {
+            Some(parent) => out.msg(Msg::AddedSubtask { id, parent, label: store.label() }),
+            None => out.msg(Msg::Added { id, label: store.label() }),
+        }
+        if let Some(summary) = summary(&meta) {
+            out.say(format!("   {}", summary));
+        }
+        if let Some(inferred) = inferred {
+            out.say(format!("   (due date read from \"{}\"; change it with `todo due {} ...`)", inferred.phrase, id));
+        }
+        if let (Some(parent), Some(_)) = (parent, inherited) {
+            out.say(format!("   (due date taken from #{}; change it with `todo due {} ...`)", parent, id));
+        }
+    }
+    if parent.is_some() && inherited.is_none() {
+        warn_due_after_parent(store, id, &mut out)?;
+    }
+    if let Some(due) = meta.due
+        && let Some(reminder) = auto_reminder(id, due, &mut out)
+    {
+        todo.id = id;
+        todo.reminder = Some(reminder);
+        store.update(&todo)?;
+    }
+    Ok(out)
+}
+
+/// The due date of todo `parent`, which a subtask added under it has unless given its own
+fn parent_due(todos: &[Todo], parent: Option<usize>) -> Option<Due> {
+    let parent = todos.iter().find(|t| Some(t.id) == parent)?;
+    Due::parse(parent.due_date.as_deref()?)
+}
+
+/// Read a `--var key=value`
+pub fn parse_var(value: &str) -> std::result::Result<(String, String), String> {
+    match value.split_once('=') {
+        Some((key, value)) if !key.trim().is_empty() => Ok((key.trim().to_string(), value.to_string())),
+        _ => Err(format!("'{}' should look like key=value", value)),
+    }
+}
+
+/// Add a todo from the template saved as `name`, with its placeholders filled in from
+/// `vars`. It is due as far ahead as the template says and reminded as far before that,
+/// falling back to the config's auto_remind
+pub fn run_template(
+    store: &mut dyn Store,
+    name: &str,
+    vars: &[(String, String)],
+    print_id: bool,
+    parent: Option<usize>,
+) -> Result<Output> {
+    let todos = store.todos()?;
+    if let Some(parent) = parent
+        && !todos.iter().any(|t| t.id == parent)
+    {
+        return Ok(not_found(parent, &todos, &[]));
+    }
+    let mut out = Output::default();
+    let Some(saved) = saved_templates::lookup(name) else {
+        out.msg(Msg::NoTemplate { name });
+        let names: Vec<String> = saved_templates::all().into_keys().collect();
+        if !names.is_empty() {
+            out.warn(format!("   Known templates: {}", names.join(", ")));
+        }
+        out.fail();
+        return Ok(out);
+    };
+    let template = match Template::parse_open(&saved.text) {
+        Ok(template) => template,
+        Err(e) => {
+            out.msg(Msg::TemplateTextInvalid { name, error: &e });
+            out.fail();
+            return Ok(out);
+        }
+    };
+    let value = |field: &str| vars.iter().rev().find(|(key, _)| key == field).map(|(_, value)| value.as_str());
+    let missing: Vec<&str> = template.fields().into_iter().filter(|field| value(field).is_none()).collect();
+    if !missing.is_empty() {
+        let names: Vec<String> = missing.iter().map(|field| format!("{{{}}}", field)).collect();
+        out.msg(Msg::MissingVars { names: &names.join(", "), first: missing[0] });
+        out.fail();
+        return Ok(out);
+    }
+    let Some(text) = todo_text(&template.render(|field| value(field).unwrap_or_default().to_string()), &mut out) else {
+        return Ok(out);
+    };
+    let today = Local::now().date_naive();
+    let due = match saved.due.as_deref() {
+        Some(offset) => match parse_due(offset.trim_start_matches('+'), today) {
+            Some(due) => Some(due),
+            None => {
+                out.msg(Msg::IgnoringTemplateDue { offset });
+                None
+            }
+        },
+        None => parent_due(&todos, parent),
+    };
+    let meta = Meta {
+        due,
+        tags: saved.tags.clone(),
+        priority: saved.priority.as_deref().and_then(Priority::parse),
+        ..Default::default()
+    };
+    let mut todo = Todo {
+        text,
+        due_date: due.map(|due| due.to_stored()),
+        tags: saved.tags,
+        priority: saved.priority,
+        created_at: Some(now_timestamp()),
+        uuid: uuid::new_v4(),
+        parent,
+        ..Default::default()
+    };
+    let id = store.insert(todo.clone())?;
+    if print_id {
+        out.say(id.to_string());
+    } else {
+        out.msg(Msg::AddedFromTemplate { id, template: name, label: store.label() });
+        if let Some(summary) = summary(&meta) {
+            out.say(format!("   {}", summary));
+        }
+    }
+    if parent.is_some() {
+        warn_due_after_parent(store, id, &mut out)?;
+    }
+    let Some(due) = due else {
+        return Ok(out);
+    };
+    let reminder = match saved.reminder.as_deref().map(AutoRemind::parse) {
+        Some(Ok(rule)) if rule.reminder_for(due) <= Local::now().naive_local() => {
+            out.msg(Msg::TemplateReminderPassed { id });
+            None
+        }
+        Some(Ok(rule)) => {
+            let stored = reminders::to_stored(rule.reminder_for(due));
+            out.msg(Msg::ReminderSetAt { id, at: &dates::display_reminder(&stored, DateDisplay::default()) });
+            Some(stored)
+        }
+        Some(Err(e)) => {
+            out.msg(Msg::IgnoringTemplateReminder { error: &e });
+            None
+        }
+        None => auto_reminder(id, due, &mut out),
+    };
+    if reminder.is_some() {
+        todo.id = id;
+        todo.reminder = reminder;
+        store.update(&todo)?;
+    }
+    Ok(out)
+}
+
+/// Whether to reopen `done` or add a new todo, asking when `repeat` leaves it open and there
+/// is a terminal to ask on; `None` when the question was cancelled
+fn decide(repeat: Repeat, done: &Todo, out: &mut Output) -> Option<Repeat> {
+    if repeat != Repeat::Ask {
+        return Some(repeat);
+    }
+    if !can_ask() {
+        out.msg(Msg::DoneSameText { id: done.id });
+        return Some(Repeat::New);
+    }
+    out.flush();
+    let mut choices = vec![(format!("Reopen #{}", done.id), Repeat::Reopen { clear_dates: false })];
+    if done.due_date.is_some() || done.reminder.is_some() {
+        choices[0].0.push_str(", keeping its due date and reminder");
+        choices.push((format!("Reopen #{}, clearing its due date and reminder", done.id), Repeat::Reopen { clear_dates: true }));
+    }
+    choices.push(("Add a new todo".to_string(), Repeat::New));
+    let items: Vec<&str> = choices.iter().map(|(item, _)| item.as_str()).collect();
+    let completed = done
+        .completed_at
+        .as_deref()
+        .and_then(|at| DateTime::parse_from_rfc3339(at).ok())
+        .map_or(String::new(), |at| format!(" on {}", at.with_timezone(&Local).format("%Y-%m-%d")));
+    dialoguer::Select::new()
+        .with_prompt(format!("Todo {} '{}' was done{}; reopen it?", done.id, escape(&done.text), completed))
+        .items(&items)
+        .default(0)
+        .interact_opt()
+        .ok()
+        .flatten()
+        .map(|choice| choices[choice].1)
+}
+
+/// Mark `todo` pending again, with whatever the quick-add markers in the new text set
+fn reopen(store: &mut dyn Store, mut todo: Todo, clear_dates: bool, meta: &Meta, print_id: bool, out: &mut Output) -> Result<()> {
+    todo.done = false;
+    todo.completed_at = None;
+    if clear_dates {
+        todo.due_date = None;
+        todo.reminder = None;
+    }
+    if print_id {
+        out.say(todo.id.to_string());
+    } else {
+        out.msg(Msg::Reopened { id: todo.id, label: store.label() });
+        if let Some(summary) = summary(meta) {
+            out.say(format!("   {}", summary));
+        }
+    }
+    if let Some(due) = meta.due {
+        todo.due_date = Some(due.to_stored());
+        todo.reminder = auto_reminder(todo.id, due, out);
+    }
+    for tag in &meta.tags {
+        if !todo.tags.contains(tag) {
+            todo.tags.push(tag.clone());
+        }
+    }
+    if let Some(priority) = meta.priority {
+        todo.priority = Some(priority.as_str().to_string());
+    }
+    store.update(&todo)
+}
+
+/// What the quick-add markers set, e.g. "due 2025-07-04, #work, priority high"
+fn summary(meta: &Meta) -> Option<String> {
+    let due = meta.due.map(|due| format!("due {}", dates::display_date(&due.to_stored(), DateDisplay::default())));
+    let tags = (!meta.tags.is_empty()).then(|| meta.tags.iter().map(|t| format!("#{}", t)).collect::<Vec<_>>().join(" "));
+    let priority = meta.priority.map(|p| format!("priority {}", p.as_str()));
+    let parts: Vec<String> = [due, tags, priority].into_iter().flatten().collect();
+    (!parts.is_empty()).then(|| parts.join(", "))
+}
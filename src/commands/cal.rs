@@ -0,0 +1,36 @@
+use super::{week::render_day, Output};
+use crate::{
+    agenda, calendar, config, doctor,
+    store::{Result, Store},
+    store_id_width,
+};
+use chrono::{Local, NaiveDate};
+
+/// A month, or with `week` this week, as a calendar of how many todos are due each day,
+/// followed by the todos themselves
+pub fn run(store: &mut dyn Store, month: Option<NaiveDate>, week: bool) -> Result<Output> {
+    let todos = store.todos()?;
+    doctor::warn_unreadable(&todos);
+    let mut out = Output::default();
+    let today = Local::now().date_naive();
+    let first_weekday = config::load().first_weekday();
+    let (days, grid) = if week {
+        let days = agenda::days(&todos, agenda::week_start(today, first_weekday), 7);
+        let grid = calendar::strip(&days, today);
+        (days, grid)
+    } else {
+        let first = month.unwrap_or_else(|| calendar::month_start(today));
+        let days = agenda::days(&todos, first, calendar::days_in_month(first));
+        let grid = calendar::month(&days, first_weekday, today);
+        (days, grid)
+    };
+    for line in grid {
+        out.say(line);
+    }
+    let id_width = store_id_width(&todos);
+    for day in days.iter().filter(|day| !day.todos.is_empty()) {
+        out.say("");
+        render_day(&mut out, day, id_width, today);
+    }
+    Ok(out)
+}
@@ -0,0 +1,105 @@
+use super::Output;
+use crate::{
+    agenda::{self, Day},
+    dates::{self, DateDisplay, Due},
+    doctor, format_todo, store_id_width,
+    store::{Result, Store},
+    Todo,
+};
+use chrono::{Duration, Local, NaiveDate};
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+/// A day with more todos due than the limit, as `--json` prints it
+#[derive(Serialize)]
+struct Conflict<'a> {
+    date: String,
+    count: usize,
+    todos: Vec<&'a Todo>,
+    /// Moves that bring the day down to the limit
+    moves: Vec<Move>,
+}
+
+/// A todo to give a later due date, and the command that does it
+#[derive(Serialize)]
+struct Move {
+    id: usize,
+    due: String,
+    command: String,
+}
+
+/// The days from today on with more than `max_per_day` pending todos due, each with its
+/// todos and the `todo due` commands that would spread the excess over the next days with
+/// room. With `weekdays_only`, weekends are neither checked nor moved onto
+pub fn run(store: &mut dyn Store, max_per_day: usize, weekdays_only: bool, json: bool) -> Result<Output> {
+    let todos = store.todos()?;
+    doctor::warn_unreadable(&todos);
+    let mut out = Output::default();
+    let today = Local::now().date_naive();
+    let crowded = agenda::crowded(&todos, today, max_per_day, weekdays_only);
+    let mut load: BTreeMap<NaiveDate, usize> = agenda::by_day(&todos).into_iter().map(|(date, due)| (date, due.len())).collect();
+    let conflicts: Vec<Conflict> = crowded
+        .iter()
+        .map(|day| Conflict {
+            date: day.date.format("%Y-%m-%d").to_string(),
+            count: day.todos.len(),
+            todos: day.todos.clone(),
+            moves: spread(day, max_per_day, weekdays_only, &mut load),
+        })
+        .collect();
+
+    if json {
+        out.say(serde_json::to_string_pretty(&conflicts).unwrap_or_default());
+        return Ok(out);
+    }
+    if conflicts.is_empty() {
+        let days = if weekdays_only { "weekday" } else { "day" };
+        let todos = if max_per_day == 1 { "todo" } else { "todos" };
+        out.say(format!("No {} has more than {} pending {} due", days, max_per_day, todos));
+        return Ok(out);
+    }
+    let id_width = store_id_width(&todos);
+    for (i, (day, conflict)) in crowded.iter().zip(&conflicts).enumerate() {
+        if i > 0 {
+            out.say("");
+        }
+        out.say(format!(
+            "{} {}: {} due, {} over the limit of {}",
+            day.date.format("%a"),
+            dates::format_date(day.date),
+            conflict.count,
+            conflict.count - max_per_day,
+            max_per_day
+        ));
+        for todo in &day.todos {
+            out.say(format!("  {}", format_todo(todo, id_width, None, false, DateDisplay::default())));
+        }
+        out.say("  Spread them with:");
+        for step in &conflict.moves {
+            out.say(format!("    {}", step.command));
+        }
+    }
+    Ok(out)
+}
+
+/// Moves for the latest-due todos of `day` beyond `max`, each to the first later day
+/// `load` leaves room on, which is then counted as holding it
+fn spread(day: &Day<Todo>, max: usize, weekdays_only: bool, load: &mut BTreeMap<NaiveDate, usize>) -> Vec<Move> {
+    let excess = day.todos.len().saturating_sub(max);
+    let mut moves = Vec::new();
+    for todo in &day.todos[day.todos.len() - excess..] {
+        let mut date = day.date + Duration::days(1);
+        while load.get(&date).copied().unwrap_or(0) >= max || (weekdays_only && agenda::is_weekend(date)) {
+            date += Duration::days(1);
+        }
+        *load.entry(date).or_default() += 1;
+        let time = todo.due_date.as_deref().and_then(Due::parse).and_then(|due| due.time);
+        let due = Due { date, time }.to_stored();
+        let command = match time {
+            Some(time) => format!("todo due {} {} {}", todo.id, date.format("%Y-%m-%d"), time.format("%H:%M")),
+            None => format!("todo due {} {}", todo.id, date.format("%Y-%m-%d")),
+        };
+        moves.push(Move { id: todo.id, due, command });
+    }
+    moves
+}
@@ -0,0 +1,106 @@
+use super::{not_found, Output};
+use crate::{
+    confirm,
+    dates::Due,
+    filter::{self, Filter},
+    messages::Msg,
+    store::{Result, Store},
+    validate_date, Todo,
+};
+use chrono::NaiveDate;
+
+/// Delete the todo with `id`
+pub fn run(store: &mut dyn Store, id: usize, hint: &[String]) -> Result<Output> {
+    if store.delete(&[id])? == 0 {
+        return Ok(not_found(id, &store.todos()?, hint));
+    }
+    let mut out = Output::default();
+    out.msg(Msg::Deleted { id, label: store.label() });
+    Ok(out)
+}
+
+/// Delete every todo the flag form of `delete` picks out, asking first before wiping
+/// everything unless `force` is set
+pub fn run_bulk(
+    store: &mut dyn Store,
+    done: bool,
+    all: bool,
+    before: Option<&str>,
+    filter: Option<&str>,
+    force: bool,
+) -> Result<Output> {
+    let mut out = Output::default();
+    let todos = store.todos()?;
+    let Some(bulk) = BulkDelete::from_flags(done, all, before, filter, force, todos.len(), &mut out) else {
+        return Ok(out);
+    };
+    let ids: Vec<usize> = todos.iter().filter(|todo| bulk.matches(todo)).map(|todo| todo.id).collect();
+    let deleted = store.delete(&ids)?;
+    out.msg(Msg::DeletedMany { count: deleted, label: store.label() });
+    Ok(out)
+}
+
+/// Which todos a flag form of `delete` removes
+enum BulkDelete {
+    Done,
+    All,
+    Before(NaiveDate),
+    Filter(Filter),
+}
+
+impl BulkDelete {
+    /// Validate the flags, asking for confirmation before wiping everything
+    fn from_flags(
+        done: bool,
+        all: bool,
+        before: Option<&str>,
+        filter: Option<&str>,
+        force: bool,
+        count: usize,
+        out: &mut Output,
+    ) -> Option<BulkDelete> {
+        if let Some(expr) = filter {
+            return match filter::parse(expr) {
+                Ok(filter) => Some(BulkDelete::Filter(filter)),
+                Err(e) => {
+                    out.msg(Msg::InvalidFilter { error: &e.render(expr) });
+                    out.fail();
+                    None
+                }
+            };
+        }
+        if let Some(date) = before {
+            return match validate_date(date) {
+                Ok(cutoff) => Some(BulkDelete::Before(cutoff)),
+                Err(_) => {
+                    out.msg(Msg::InvalidDateFormat);
+                    None
+                }
+            };
+        }
+        if all {
+            if !force && !confirm(&format!("Delete all {} todos?", count)) {
+                out.say("Nothing deleted");
+                return None;
+            }
+            return Some(BulkDelete::All);
+        }
+        done.then_some(BulkDelete::Done)
+    }
+
+    fn matches(&self, todo: &Todo) -> bool {
+        match self {
+            BulkDelete::Done => todo.done,
+            BulkDelete::All => true,
+            BulkDelete::Before(cutoff) => {
+                !todo.done
+                    && todo
+                        .due_date
+                        .as_deref()
+                        .and_then(Due::parse)
+                        .is_some_and(|due| due.date < *cutoff)
+            }
+            BulkDelete::Filter(filter) => filter.matches(todo),
+        }
+    }
+}
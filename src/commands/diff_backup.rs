@@ -0,0 +1,108 @@
+use super::Output;
+use crate::{
+    store::{Result, Store},
+    text, Todo,
+};
+use serde::Serialize;
+use serde_json::{Map, Value};
+
+/// How the store differs from a backup, as `--json` prints it
+#[derive(Serialize, Default)]
+struct Diff<'a> {
+    backup: &'a str,
+    /// In the store but not the backup
+    added: Vec<&'a Todo>,
+    /// In the backup but not the store
+    removed: Vec<&'a Todo>,
+    changed: Vec<Changed>,
+}
+
+#[derive(Serialize)]
+struct Changed {
+    id: usize,
+    uuid: String,
+    text: String,
+    /// Each field that differs, with its value in the backup and in the store
+    fields: Map<String, Value>,
+}
+
+/// Compare the store with the todos in a backup, named `name`, printing what has been added,
+/// removed and changed since. Todos are matched by UUID, or by id where they have none
+pub fn run(store: &mut dyn Store, backup: &[Todo], name: &str, json: bool) -> Result<Output> {
+    let todos = store.todos()?;
+    let same = |a: &Todo, b: &Todo| match (a.uuid.is_empty(), b.uuid.is_empty()) {
+        (false, false) => a.uuid == b.uuid,
+        _ => a.id == b.id,
+    };
+    let mut diff = Diff { backup: name, ..Default::default() };
+    for old in backup {
+        match todos.iter().find(|t| same(t, old)) {
+            Some(new) if new != old => diff.changed.push(Changed {
+                id: new.id,
+                uuid: new.uuid.clone(),
+                text: new.text.clone(),
+                fields: fields(old, new),
+            }),
+            Some(_) => {}
+            None => diff.removed.push(old),
+        }
+    }
+    diff.added = todos.iter().filter(|new| !backup.iter().any(|old| same(new, old))).collect();
+
+    let mut out = Output::default();
+    if json {
+        out.say(serde_json::to_string_pretty(&diff).unwrap_or_default());
+        return Ok(out);
+    }
+    out.say(format!("Changes since {}{}:", name, store.label()));
+    if diff.added.is_empty() && diff.removed.is_empty() && diff.changed.is_empty() {
+        out.say("   None; the store matches the backup");
+        return Ok(out);
+    }
+    for todo in &diff.added {
+        out.say(format!("+ #{} {}", todo.id, text::escape(&todo.text)));
+    }
+    for todo in &diff.removed {
+        out.say(format!("- #{} {}", todo.id, text::escape(&todo.text)));
+    }
+    for changed in &diff.changed {
+        out.say(format!("~ #{} {}", changed.id, text::escape(&changed.text)));
+        for (field, values) in &changed.fields {
+            out.say(format!("    {}: {} → {}", field, shown(&values["before"]), shown(&values["after"])));
+        }
+    }
+    out.say(format!(
+        "{} added, {} removed, {} changed",
+        diff.added.len(),
+        diff.removed.len(),
+        diff.changed.len()
+    ));
+    Ok(out)
+}
+
+/// The fields that differ between `old` and `new`, each with its value before and after
+fn fields(old: &Todo, new: &Todo) -> Map<String, Value> {
+    let (Ok(Value::Object(old)), Ok(Value::Object(new))) = (serde_json::to_value(old), serde_json::to_value(new)) else {
+        return Map::new();
+    };
+    let mut fields = Map::new();
+    let keys = old.keys().chain(new.keys().filter(|key| !old.contains_key(*key)));
+    for key in keys {
+        let before = old.get(key).cloned().unwrap_or(Value::Null);
+        let after = new.get(key).cloned().unwrap_or(Value::Null);
+        if before != after {
+            fields.insert(key.clone(), serde_json::json!({ "before": before, "after": after }));
+        }
+    }
+    fields
+}
+
+/// A field's value as it reads in a line of text
+fn shown(value: &Value) -> String {
+    match value {
+        Value::Null => "(none)".to_string(),
+        Value::String(s) => text::escape(s).into_owned(),
+        Value::Array(items) if items.is_empty() => "(none)".to_string(),
+        other => other.to_string(),
+    }
+}
@@ -0,0 +1,60 @@
+use super::Output;
+use crate::{
+    confirm,
+    doctor::{self, Finding},
+    messages::Msg,
+    store::{Result, Store},
+    Todo,
+};
+use chrono::Utc;
+
+/// Check every todo for values that can't be read, along with `findings` the backend made
+/// about its own storage, and with `fix` apply the fixes doctor proposes. Fails if it
+/// found anything
+pub fn run(store: &mut dyn Store, fix: bool, mut findings: Vec<Finding>) -> Result<Output> {
+    let mut out = Output::default();
+    let mut todos = store.todos()?;
+    findings.extend(doctor::check(&todos, Utc::now()));
+    if fix && apply_fixes(&mut todos, &findings, &mut out) {
+        store.replace(todos)?;
+    }
+    if findings.is_empty() {
+        out.msg(Msg::NoProblems);
+        return Ok(out);
+    }
+    for finding in &findings {
+        out.msg(Msg::Finding { finding: &finding.describe() });
+    }
+    out.msg(Msg::Problems { count: findings.len() });
+    out.fail();
+    Ok(out)
+}
+
+/// Apply every fix doctor proposed, asking once before any that discard data.
+/// Returns whether anything changed.
+fn apply_fixes(todos: &mut [Todo], findings: &[Finding], out: &mut Output) -> bool {
+    let destructive = findings
+        .iter()
+        .filter(|f| f.fix.as_ref().is_some_and(|fix| fix.is_destructive()))
+        .count();
+    let allow_destructive = destructive == 0 || {
+        out.flush();
+        confirm(&format!("Clear {} unreadable value(s)?", destructive))
+    };
+
+    let mut changed = false;
+    for finding in findings {
+        let (Some(id), Some(fix)) = (finding.id, &finding.fix) else {
+            continue;
+        };
+        if fix.is_destructive() && !allow_destructive {
+            continue;
+        }
+        if let Some(todo) = todos.iter_mut().find(|t| t.id == id) {
+            fix.apply(todo);
+            out.msg(Msg::Fixed { id });
+            changed = true;
+        }
+    }
+    changed
+}
@@ -0,0 +1,177 @@
+use super::{can_ask, not_found, Output};
+use crate::{
+    confirm,
+    dates::{self, DateDisplay, Due},
+    filter::Filter,
+    messages::Msg,
+    now_timestamp, reminders,
+    store::{Result, Store},
+    subtasks, text, uuid, Todo,
+};
+use chrono::{Local, NaiveDate, Utc};
+
+/// Mark a todo done, keeping `message` as its completion note, and queue up the next
+/// occurrence of a recurring one. Its reminder is cancelled unless `keep_reminder` is set
+pub fn run(store: &mut dyn Store, id: usize, message: &[String], hint: &[String], keep_reminder: bool) -> Result<Output> {
+    let todos = store.todos()?;
+    let Some(todo) = todos.iter().find(|t| t.id == id) else {
+        return Ok(not_found(id, &todos, hint));
+    };
+    let mut out = Output::default();
+    let announcement = Msg::MarkedDone { id: None, label: store.label() }.to_string();
+    complete(store, todo.clone(), message, announcement, keep_reminder, &mut out)?;
+    if !todo.done {
+        offer_parent(store, todo.parent, keep_reminder, &mut out)?;
+    }
+    Ok(out)
+}
+
+/// Mark every pending todo `filter` matches done, after listing them and asking unless
+/// `force` is set. Failing to match anything is an error, so scripts notice
+pub fn run_bulk(
+    store: &mut dyn Store,
+    filter: &Filter,
+    message: &[String],
+    force: bool,
+    keep_reminder: bool,
+) -> Result<Output> {
+    let todos = store.todos()?;
+    let matched: Vec<&Todo> = todos.iter().filter(|t| !t.done && filter.matches(t)).collect();
+    let mut out = Output::default();
+    if matched.is_empty() {
+        out.msg(Msg::NothingMatched);
+        out.fail();
+        return Ok(out);
+    }
+    if !force {
+        out.say(format!("{} pending todos match:", matched.len()));
+        for todo in &matched {
+            out.say(format!("   #{} {}", todo.id, text::escape(&todo.text)));
+        }
+        out.flush();
+        if !confirm(&format!("Mark these {} todos done?", matched.len())) {
+            out.say(if can_ask() { "Nothing marked done" } else { "Nothing marked done; pass --force to skip asking" });
+            return Ok(out);
+        }
+    }
+
+    // Recurring todos need their next occurrence added, and reminders cancelling, so
+    // those go one at a time
+    let note = (!message.is_empty()).then(|| message.join("\n"));
+    let pending = Filter::Done(false).and(filter.clone());
+    let completed = if matched.iter().all(|t| t.recurrence.is_none() && (keep_reminder || t.reminder.is_none())) {
+        store.complete_matching(&pending, &now_timestamp(), note.as_deref())?
+    } else {
+        None
+    };
+    let count = match completed {
+        Some(count) => {
+            for todo in &matched {
+                out.msg(Msg::Completed { id: todo.id, text: &text::escape(&todo.text) });
+            }
+            count
+        }
+        None => {
+            for todo in &matched {
+                let announcement = Msg::Completed { id: todo.id, text: &text::escape(&todo.text) }.to_string();
+                complete(store, (*todo).clone(), message, announcement, keep_reminder, &mut out)?;
+            }
+            matched.len()
+        }
+    };
+    out.msg(Msg::CompletedMany { count, label: store.label() });
+    Ok(out)
+}
+
+/// Store `todo` as done, announce it, and add the next occurrence if it recurs. Unless
+/// `keep_reminder` is set, a pending todo's reminder is cleared, saying so if it was still
+/// to go off
+fn complete(
+    store: &mut dyn Store,
+    mut todo: Todo,
+    message: &[String],
+    announcement: String,
+    keep_reminder: bool,
+    out: &mut Output,
+) -> Result<()> {
+    let next = (!todo.done).then(|| next_occurrence(&todo, Local::now().date_naive())).flatten();
+    let cancel = !todo.done && !keep_reminder;
+    let cancelled = reminders::next_reminder(&todo, Utc::now()).filter(|_| cancel);
+    if cancel {
+        todo.reminder = None;
+        todo.reminder_repeat = None;
+    }
+    if !todo.done || todo.completed_at.is_none() {
+        todo.completed_at = Some(now_timestamp());
+    }
+    todo.done = true;
+    if !message.is_empty() {
+        todo.completion_note = Some(message.join("\n"));
+    }
+    store.update(&todo)?;
+    out.say(announcement);
+    if let Some(at) = cancelled {
+        out.msg(Msg::ReminderCancelled { at: &dates::display_datetime(&at, DateDisplay::default()) });
+    }
+    if let Some(next) = next {
+        let id = store.insert(next.clone())?;
+        let due = dates::display_date(next.due_date.as_deref().unwrap_or_default(), DateDisplay::default());
+        out.msg(Msg::NextOccurrence { id, due: &due, label: store.label() });
+    }
+    Ok(())
+}
+
+/// Once the last subtask of a pending parent is done, offer to finish the parent as well,
+/// and its own parent in turn. Without a terminal to ask on, just point the parent out
+fn offer_parent(store: &mut dyn Store, mut parent: Option<usize>, keep_reminder: bool, out: &mut Output) -> Result<()> {
+    while let Some(id) = parent {
+        let todos = store.todos()?;
+        let Some(todo) = todos.iter().find(|t| t.id == id && !t.done) else {
+            break;
+        };
+        if !subtasks::progress(&todos, false).get(&id).is_some_and(|p| p.is_complete()) {
+            break;
+        }
+        if !can_ask() {
+            out.say(format!("   All subtasks of #{} are done; finish it with `todo done {}`", id, id));
+            break;
+        }
+        out.flush();
+        if !confirm(&format!("All subtasks complete — mark #{} done?", id)) {
+            break;
+        }
+        let announcement = Msg::MarkedDone { id: Some(id), label: store.label() }.to_string();
+        complete(store, todo.clone(), &[], announcement, keep_reminder, out)?;
+        parent = todo.parent;
+    }
+    Ok(())
+}
+
+/// The todo that follows a recurring one completed on `today`: a pending copy due on the
+/// next day its recurrence lands on, with any reminder moved by as many days. Only todos
+/// with a due date recur
+fn next_occurrence(todo: &Todo, today: NaiveDate) -> Option<Todo> {
+    let rule = reminders::RepeatRule::parse(todo.recurrence.as_deref()?)?;
+    let due = Due::parse(todo.due_date.as_deref()?)?;
+    let date = rule.next_date(due.date, today)?;
+    let shift = date - due.date;
+    let reminder = todo
+        .reminder
+        .as_deref()
+        .and_then(reminders::parse_reminder)
+        .map(|at| reminders::shift_reminder(at, shift));
+    Some(Todo {
+        id: 0,
+        text: todo.text.clone(),
+        due_date: Some(Due { date, ..due }.to_stored()),
+        reminder_repeat: reminder.as_ref().and(todo.reminder_repeat.clone()),
+        reminder,
+        created_at: Some(now_timestamp()),
+        recurrence: todo.recurrence.clone(),
+        tags: todo.tags.clone(),
+        priority: todo.priority.clone(),
+        uuid: uuid::new_v4(),
+        parent: todo.parent,
+        ..Default::default()
+    })
+}
@@ -0,0 +1,133 @@
+use super::{can_ask, not_found, Output};
+use crate::{
+    config, confirm,
+    dates::{self, DateDisplay, Due},
+    doctor,
+    messages::Msg,
+    reminders,
+    store::{Result, Store},
+    subtasks, validate_due, Todo,
+};
+use chrono::{Local, Weekday};
+
+/// Set a todo's due date, deriving a reminder from `auto_remind` when it has none. With
+/// `end_of_week`, a week such as w29 means its Friday. When a due date moves, the pending
+/// subtasks that have one move by as many days with `cascade`, or if the user agrees
+pub fn run(
+    store: &mut dyn Store,
+    id: usize,
+    date: &str,
+    time: Option<&str>,
+    no_auto_remind: bool,
+    end_of_week: bool,
+    cascade: bool,
+) -> Result<Output> {
+    let mut out = Output::default();
+    let week_day = if end_of_week { Weekday::Fri } else { Weekday::Mon };
+    let Some(due) = validate_due(date, time, week_day) else {
+        out.msg(Msg::InvalidDueFormat);
+        out.fail();
+        return Ok(out);
+    };
+    if let Err(e) = dates::check_window(due.date, Local::now().date_naive()) {
+        out.msg(Msg::DueNotSet { error: &e });
+        out.fail();
+        return Ok(out);
+    }
+    let todos = store.todos()?;
+    let Some(todo) = todos.iter().find(|t| t.id == id) else {
+        return Ok(not_found(id, &todos, &[]));
+    };
+    let mut todo = todo.clone();
+    let before = todo.due_date.as_deref().and_then(Due::parse);
+    todo.due_date = Some(due.to_stored());
+    out.msg(Msg::DueSet { id, label: store.label() });
+    if !no_auto_remind && todo.reminder.is_none() {
+        todo.reminder = auto_reminder(id, due, &mut out);
+    }
+    store.update(&todo)?;
+    if let Some(before) = before {
+        shift_subtasks(store, &todos, id, (due.date - before.date).num_days(), cascade, &mut out)?;
+    }
+    warn_due_after_parent(store, id, &mut out)?;
+    Ok(out)
+}
+
+/// Move the due dates of the pending subtasks below `id` that have one by `days`, as its
+/// own moved, keeping their times of day. Without `cascade` the user is asked first, and
+/// without a terminal to ask on they are only pointed out
+fn shift_subtasks(
+    store: &mut dyn Store,
+    todos: &[Todo],
+    id: usize,
+    days: i64,
+    cascade: bool,
+    out: &mut Output,
+) -> Result<()> {
+    if days == 0 {
+        return Ok(());
+    }
+    let below = subtasks::descendants(todos, id);
+    let moving: Vec<(&Todo, Due)> = todos
+        .iter()
+        .filter(|t| below.contains(&t.id) && !t.done)
+        .filter_map(|t| Some((t, Due::parse(t.due_date.as_deref()?)?)))
+        .collect();
+    if moving.is_empty() {
+        return Ok(());
+    }
+    let by = match days {
+        1 => "1 day later".to_string(),
+        -1 => "1 day earlier".to_string(),
+        2.. => format!("{} days later", days),
+        _ => format!("{} days earlier", -days),
+    };
+    if !cascade {
+        if !can_ask() {
+            out.say(format!("   Pass --cascade to move its subtasks' due dates {} too", by));
+            return Ok(());
+        }
+        out.flush();
+        if !confirm(&format!("Move the due dates of {} subtasks {} too?", moving.len(), by)) {
+            return Ok(());
+        }
+    }
+    for (todo, due) in &moving {
+        let mut todo = (*todo).clone();
+        todo.due_date = Some(due.shifted(days).to_stored());
+        store.update(&todo)?;
+    }
+    out.msg(Msg::SubtasksMoved { count: moving.len(), by: &by, label: store.label() });
+    Ok(())
+}
+
+/// Warn about pending subtasks now due after their parent, where `id` is either
+pub(super) fn warn_due_after_parent(store: &mut dyn Store, id: usize, out: &mut Output) -> Result<()> {
+    let todos = store.todos()?;
+    for (child, parent) in doctor::due_after_parent(&todos) {
+        if child.id == id || parent.id == id {
+            out.msg(Msg::DueAfterParent {
+                id: child.id,
+                due: &dates::display_date(child.due_date.as_deref().unwrap_or_default(), DateDisplay::default()),
+                parent: parent.id,
+                parent_due: &dates::display_date(parent.due_date.as_deref().unwrap_or_default(), DateDisplay::default()),
+            });
+        }
+    }
+    Ok(())
+}
+
+/// The reminder `auto_remind` derives for a todo newly due on `due`, announced as it is set.
+/// It is never put in the past: a time that has already gone falls back to an hour from now
+pub(super) fn auto_reminder(id: usize, due: Due, out: &mut Output) -> Option<String> {
+    let rule = config::load().auto_remind()?;
+    let mut at = rule.reminder_for(due);
+    let now = Local::now().naive_local();
+    if at <= now {
+        at = now + chrono::Duration::hours(1);
+        out.msg(Msg::AutoReminderPassed { id });
+    }
+    let stored = reminders::to_stored(at);
+    out.msg(Msg::ReminderSetAt { id, at: &dates::display_reminder(&stored, DateDisplay::default()) });
+    Some(stored)
+}
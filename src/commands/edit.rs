@@ -0,0 +1,283 @@
+use super::{not_found, todo_text, Output};
+use crate::{
+    batch, confirm, dates, diff, messages::Msg, now_timestamp, paths, reminders,
+    store::{Result, Store},
+    text, valid_tag, validate_due, validate_reminder, Todo,
+};
+use chrono::{Local, Weekday};
+use serde::{Deserialize, Serialize};
+use std::fs;
+
+/// One todo as the TOML document `edit --full` opens; a missing key clears that field
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct FullEdit {
+    text: String,
+    #[serde(default)]
+    done: bool,
+    due_date: Option<String>,
+    reminder: Option<String>,
+    tags: Option<Vec<String>>,
+    notes: Option<String>,
+}
+
+/// Explains the document above its keys; kept when the user saves, and ignored by the parser
+const FULL_EDIT_HEADER: &str = "\
+# Change any field and save. Remove a line to clear that field.
+# due_date = \"YYYY-MM-DD\" or \"YYYY-MM-DD HH:MM\" (or any date `due` accepts)
+# reminder = \"YYYY-MM-DD\" or \"YYYY-MM-DD HH:MM\"
+# tags = [\"work\", \"home\"]    notes = \"...\"
+";
+
+/// Start of the lines reporting what was wrong with the last save
+const FULL_EDIT_ERROR: &str = "# ❌ ";
+
+/// Edit one todo's text in `$EDITOR`
+pub fn run(store: &mut dyn Store, id: usize, hint: &[String]) -> Result<Output> {
+    let todos = store.todos()?;
+    let Some(todo) = todos.iter().find(|t| t.id == id) else {
+        return Ok(not_found(id, &todos, hint));
+    };
+    let mut out = Output::default();
+    match edit_in_editor(&todo.text, "todo_edit") {
+        EditOutcome::Changed(text) => {
+            let Some(text) = todo_text(&text, &mut out) else {
+                return Ok(out);
+            };
+            out.msg(Msg::Updated { id: None, label: store.label() });
+            out.say(format!("   {}", diff::word_diff(&todo.text, &text)));
+            store.update(&Todo { text, ..todo.clone() })?;
+        }
+        outcome => report_unapplied_edit(&outcome, &mut out),
+    }
+    Ok(out)
+}
+
+/// Edit every field of one todo in `$EDITOR` at once, as a TOML document. A document that
+/// doesn't parse or holds an invalid field is opened again with the problems noted at the top,
+/// rather than thrown away
+pub fn run_full(store: &mut dyn Store, id: usize, hint: &[String]) -> Result<Output> {
+    let todos = store.todos()?;
+    let Some(todo) = todos.iter().find(|t| t.id == id) else {
+        return Ok(not_found(id, &todos, hint));
+    };
+    let mut out = Output::default();
+    let mut buffer = full_document(todo);
+    let updated = loop {
+        let edited = match edit_in_editor(&buffer, "todo_full") {
+            EditOutcome::Changed(edited) => edited,
+            outcome => {
+                report_unapplied_edit(&outcome, &mut out);
+                return Ok(out);
+            }
+        };
+        match apply_full(todo, &edited) {
+            Ok(updated) => break updated,
+            Err(errors) => {
+                let kept: Vec<&str> = edited.lines().filter(|line| !line.starts_with(FULL_EDIT_ERROR)).collect();
+                let notes: Vec<String> =
+                    errors.iter().flat_map(|error| error.lines()).map(|line| format!("{}{}", FULL_EDIT_ERROR, line)).collect();
+                buffer = format!("{}\n{}\n", notes.join("\n"), kept.join("\n"));
+            }
+        }
+    };
+    let changed = changed_fields(todo, &updated);
+    if changed.is_empty() {
+        out.say("Nothing changed; nothing saved");
+        return Ok(out);
+    }
+    out.msg(Msg::Updated { id: Some(id), label: store.label() });
+    out.say(format!("   Changed: {}", changed.join(", ")));
+    store.update(&updated)?;
+    Ok(out)
+}
+
+fn full_document(todo: &Todo) -> String {
+    let reminder = todo
+        .reminder
+        .as_deref()
+        .and_then(reminders::parse_reminder)
+        .map(|at| at.with_timezone(&Local).format("%Y-%m-%d %H:%M").to_string());
+    let document = FullEdit {
+        text: todo.text.clone(),
+        done: todo.done,
+        due_date: todo.due_date.clone(),
+        reminder,
+        tags: (!todo.tags.is_empty()).then(|| todo.tags.clone()),
+        notes: todo.completion_note.clone(),
+    };
+    format!("{}\n{}", FULL_EDIT_HEADER, toml::to_string(&document).unwrap_or_default())
+}
+
+/// `todo` with the edited document's fields, each checked as the command that sets it would;
+/// every problem found otherwise
+fn apply_full(todo: &Todo, edited: &str) -> std::result::Result<Todo, Vec<String>> {
+    let document: FullEdit = toml::from_str(edited).map_err(|e| vec![e.to_string()])?;
+    let mut errors = Vec::new();
+    let mut updated = todo.clone();
+    match text::normalize(&document.text) {
+        Ok(normalized) => updated.text = normalized.text,
+        Err(e) => errors.push(format!("text: {}", e)),
+    }
+    if document.done != todo.done {
+        updated.done = document.done;
+        updated.completed_at = document.done.then(now_timestamp);
+    }
+    updated.due_date = None;
+    if let Some(value) = document.due_date.as_deref().filter(|value| !value.trim().is_empty()) {
+        let (date, time) = split_date_time(value);
+        match validate_due(date, time, Weekday::Mon) {
+            Some(due) => match dates::check_window(due.date, Local::now().date_naive()) {
+                Ok(()) => updated.due_date = Some(due.to_stored()),
+                Err(e) => errors.push(format!("due_date: {}", e)),
+            },
+            None => errors.push(format!("due_date: '{}' is not a date, optionally with HH:MM", value)),
+        }
+    }
+    updated.reminder = None;
+    if let Some(value) = document.reminder.as_deref().filter(|value| !value.trim().is_empty()) {
+        let (date, time) = split_date_time(value);
+        match validate_reminder(date, time) {
+            Ok(at) => match dates::check_window(at.date(), Local::now().date_naive()) {
+                Ok(()) => updated.reminder = Some(reminders::to_stored(at)),
+                Err(e) => errors.push(format!("reminder: {}", e)),
+            },
+            Err(e) => errors.push(format!("reminder: '{}' is not YYYY-MM-DD [HH:MM]: {}", value, e)),
+        }
+    }
+    if updated.reminder.is_none() {
+        updated.reminder_repeat = None;
+    }
+    updated.tags = Vec::new();
+    for tag in document.tags.unwrap_or_default() {
+        let tag = tag.trim().trim_start_matches('#').to_lowercase();
+        if !valid_tag(&tag) {
+            errors.push(format!("tags: '{}' can't be empty or contain spaces, commas or ():=<>\"", tag));
+        } else if !updated.tags.contains(&tag) {
+            updated.tags.push(tag);
+        }
+    }
+    updated.completion_note = document.notes.filter(|notes| !notes.trim().is_empty());
+    if errors.is_empty() { Ok(updated) } else { Err(errors) }
+}
+
+/// "2025-07-04 14:00" as its date and time
+fn split_date_time(value: &str) -> (&str, Option<&str>) {
+    match value.trim().split_once(char::is_whitespace) {
+        Some((date, time)) => (date, Some(time.trim())),
+        None => (value.trim(), None),
+    }
+}
+
+/// The names of the fields `edit --full` shows that differ between `old` and `new`
+fn changed_fields(old: &Todo, new: &Todo) -> Vec<&'static str> {
+    let fields = [
+        ("text", old.text != new.text),
+        ("done", old.done != new.done),
+        ("due_date", old.due_date != new.due_date),
+        ("reminder", old.reminder != new.reminder),
+        ("tags", old.tags != new.tags),
+        ("notes", old.completion_note != new.completion_note),
+    ];
+    fields.into_iter().filter(|(_, changed)| *changed).map(|(name, _)| name).collect()
+}
+
+/// Edit the whole list in `$EDITOR` at once
+pub fn run_batch(store: &mut dyn Store) -> Result<Output> {
+    let mut out = Output::default();
+    if let Some(plan) = batch_edit(&store.todos()?, &mut out) {
+        out.msg(Msg::EditedMany { updated: plan.updated, added: plan.created, deleted: plan.deleted.len(), label: store.label() });
+        store.replace(plan.todos)?;
+    }
+    Ok(out)
+}
+
+/// What happened when the user was handed a todo's text in `$EDITOR`
+enum EditOutcome {
+    /// The editor exited non-zero (e.g. `:cq`) or could not be started
+    Aborted(String),
+    Unchanged,
+    Empty,
+    Changed(String),
+}
+
+/// Open `$EDITOR` on `original` and only report a change when the editor exited cleanly
+fn edit_in_editor(original: &str, prefix: &str) -> EditOutcome {
+    let tmp_path = match paths::edit_buffer(prefix) {
+        Ok(path) => path,
+        Err(e) => return EditOutcome::Aborted(format!("could not create a temp file: {}", e)),
+    };
+    if let Err(e) = fs::write(&tmp_path, original) {
+        return EditOutcome::Aborted(format!("could not write {}: {}", tmp_path.display(), e));
+    }
+
+    let editor = paths::editor();
+    match paths::editor_command(&tmp_path).status() {
+        Ok(status) if status.success() => {}
+        Ok(status) => return EditOutcome::Aborted(format!("{} exited with {}", editor, status)),
+        Err(e) => return EditOutcome::Aborted(format!("could not start {}: {}", editor, e)),
+    }
+
+    let updated = match fs::read_to_string(&tmp_path) {
+        Ok(updated) => updated,
+        Err(e) => return EditOutcome::Aborted(format!("could not read {}: {}", tmp_path.display(), e)),
+    };
+    let updated = updated.trim();
+    if updated == original.trim() {
+        EditOutcome::Unchanged
+    } else if updated.is_empty() {
+        EditOutcome::Empty
+    } else {
+        EditOutcome::Changed(updated.to_string())
+    }
+}
+
+/// Let the user edit the whole list in `$EDITOR`, returning the changes to save once they
+/// parse cleanly and any deletions have been confirmed
+fn batch_edit(todos: &[Todo], out: &mut Output) -> Option<batch::Plan> {
+    let buffer = match edit_in_editor(&batch::render(todos), "todo_batch") {
+        EditOutcome::Changed(buffer) => buffer,
+        EditOutcome::Empty => String::new(),
+        outcome => {
+            report_unapplied_edit(&outcome, out);
+            return None;
+        }
+    };
+    let plan = match batch::apply(todos, &buffer) {
+        Ok(plan) => plan,
+        Err(errors) => {
+            for error in errors {
+                out.msg(Msg::Failed { error: &error });
+            }
+            out.warn("Nothing was changed");
+            return None;
+        }
+    };
+    if !plan.deleted.is_empty() {
+        // Printed straight away, since the user needs to see them to answer the prompt
+        println!("These todos will be deleted:");
+        for todo in &plan.deleted {
+            println!("  #{} {}", todo.id, todo.text);
+        }
+        if !confirm(&format!("Delete {} todos?", plan.deleted.len())) {
+            out.say("Nothing was changed");
+            return None;
+        }
+    }
+    Some(plan)
+}
+
+fn report_unapplied_edit(outcome: &EditOutcome, out: &mut Output) {
+    match outcome {
+        EditOutcome::Aborted(reason) => {
+            out.msg(Msg::EditAborted { reason });
+            out.fail();
+        }
+        EditOutcome::Unchanged => out.say("Text unchanged; nothing saved"),
+        EditOutcome::Empty => {
+            out.msg(Msg::EmptyEdit);
+            out.fail();
+        }
+        EditOutcome::Changed(_) => {}
+    }
+}
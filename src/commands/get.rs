@@ -0,0 +1,66 @@
+use super::{not_found, Output};
+use crate::{
+    fuzzy,
+    messages::Msg,
+    store::{Result, Store},
+    Todo,
+};
+
+/// Fields `get` can print
+pub const FIELDS: &[&str] = &[
+    "id", "text", "done", "due", "reminder", "repeat", "recurrence", "tags", "priority", "note", "created_at", "completed_at", "updated_at", "parent", "uuid",
+];
+
+/// Print one field of a todo as it is stored, with nothing around it, for scripts. A missing
+/// todo fails with exit status 1; an unknown field, or one the todo doesn't have, fails
+/// with 2, the latter printing nothing
+pub fn run(store: &mut dyn Store, id: usize, field: &str) -> Result<Output> {
+    let mut out = Output::default();
+    let field = field.to_lowercase();
+    if !FIELDS.contains(&field.as_str()) {
+        unknown_field(&field, &mut out);
+        out.fail_with(2);
+        return Ok(out);
+    }
+    let todos = store.todos()?;
+    let Some(todo) = todos.iter().find(|t| t.id == id) else {
+        return Ok(not_found(id, &todos, &[]));
+    };
+    match value(todo, &field) {
+        Some(value) => out.say(value),
+        None => out.fail_with(2),
+    }
+    Ok(out)
+}
+
+/// `field` of `todo`, or `None` when it is unset; tags are one per line
+fn value(todo: &Todo, field: &str) -> Option<String> {
+    match field {
+        "id" => Some(todo.id.to_string()),
+        "text" => Some(todo.text.clone()),
+        "done" => Some(todo.done.to_string()),
+        "due" => todo.due_date.clone(),
+        "reminder" => todo.reminder.clone(),
+        "repeat" => todo.reminder_repeat.clone(),
+        "recurrence" => todo.recurrence.clone(),
+        "tags" => (!todo.tags.is_empty()).then(|| todo.tags.join("\n")),
+        "priority" => todo.priority.clone(),
+        "note" => todo.completion_note.clone(),
+        "created_at" => todo.created_at.clone(),
+        "completed_at" => todo.completed_at.clone(),
+        "updated_at" => todo.updated_at.clone(),
+        "parent" => todo.parent.map(|parent| parent.to_string()),
+        "uuid" => (!todo.uuid.is_empty()).then(|| todo.uuid.clone()),
+        _ => None,
+    }
+}
+
+/// Report a field `get` doesn't know, suggesting the nearest one
+fn unknown_field(field: &str, out: &mut Output) {
+    let nearest = FIELDS
+        .iter()
+        .map(|known| (fuzzy::levenshtein(field, known), known))
+        .min()
+        .filter(|(distance, _)| *distance <= 2);
+    out.msg(Msg::UnknownField { field, nearest: nearest.map(|(_, known)| *known), fields: &FIELDS.join(", ") });
+}
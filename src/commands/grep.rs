@@ -0,0 +1,127 @@
+use super::Output;
+use crate::{
+    store::{Result, Store},
+    text, Todo,
+};
+use console::style;
+use std::ops::Range;
+
+/// Todos whose text or completion note holds `pattern`, ignoring case, laid out like
+/// ripgrep: the todo as a heading, then each matching note line numbered with a colon and
+/// its neighbours with a dash. `ids_only` prints just the ids, to pipe into another command.
+/// Fails when nothing matches, as grep does
+pub fn run(store: &mut dyn Store, pattern: &str, ids_only: bool) -> Result<Output> {
+    let needle = pattern.to_lowercase();
+    let mut out = Output::default();
+    let mut found = false;
+    for todo in store.todos()? {
+        let text = text::escape(&todo.text);
+        let text_matches = matches(&text, &needle);
+        let note_matches = todo.completion_note.as_deref().is_some_and(|note| note.lines().any(|line| !matches(line, &needle).is_empty()));
+        if text_matches.is_empty() && !note_matches {
+            continue;
+        }
+        if ids_only {
+            out.say(todo.id.to_string());
+        } else {
+            if found {
+                out.say("");
+            }
+            out.say(format!("{} {}", style(format!("#{}", todo.id)).magenta().bold(), highlight(&text, &text_matches)));
+            note_lines(&todo, &needle, &mut out);
+        }
+        found = true;
+    }
+    if !found {
+        if !ids_only {
+            out.say("No todos match");
+        }
+        out.fail();
+    }
+    Ok(out)
+}
+
+/// The matching lines of `todo`'s note with a line either side, read a line at a time so
+/// a long note is never copied whole; `--` marks a gap between groups
+fn note_lines(todo: &Todo, needle: &str, out: &mut Output) {
+    let Some(note) = &todo.completion_note else {
+        return;
+    };
+    let context = |number: usize, line: &str| format!("{}{}", style(format!("{}-", number)).green(), line);
+    let mut previous: Option<&str> = None;
+    // The number of the last line printed, and whether it matched
+    let mut printed: Option<(usize, bool)> = None;
+    for (index, line) in note.lines().enumerate() {
+        let number = index + 1;
+        let found = matches(line, needle);
+        let follows = printed.is_some_and(|(last, _)| last + 1 == number);
+        if !found.is_empty() {
+            let first = match previous {
+                Some(_) if !follows => number - 1,
+                _ => number,
+            };
+            if printed.is_some_and(|(last, _)| last + 1 < first) {
+                out.say(style("--").dim().to_string());
+            }
+            if let Some(before) = previous.filter(|_| first < number) {
+                out.say(context(first, before));
+            }
+            out.say(format!("{}{}", style(format!("{}:", number)).green(), highlight(line, &found)));
+            printed = Some((number, true));
+        } else if follows && printed.is_some_and(|(_, matched)| matched) {
+            out.say(context(number, line));
+            printed = Some((number, false));
+        }
+        previous = Some(line);
+    }
+}
+
+/// Where `needle`, already lowercase, occurs in `haystack` ignoring case, as byte ranges
+/// of `haystack` so they can be highlighted in place
+fn matches(haystack: &str, needle: &str) -> Vec<Range<usize>> {
+    if needle.is_empty() {
+        return vec![];
+    }
+    let mut found = Vec::new();
+    let mut starts = haystack.char_indices();
+    while let Some((start, _)) = starts.next() {
+        let mut wanted = needle.chars();
+        let mut end = start;
+        let mut rest = haystack[start..].char_indices();
+        let matched = loop {
+            let Some(want) = wanted.next() else {
+                break true;
+            };
+            let Some((offset, c)) = rest.next() else {
+                break false;
+            };
+            // A character whose lowercase is several characters has to match them all
+            let mut lower = c.to_lowercase();
+            if lower.next() != Some(want) || !lower.all(|l| wanted.next() == Some(l)) {
+                break false;
+            }
+            end = start + offset + c.len_utf8();
+        };
+        if matched {
+            found.push(start..end);
+            // Matches don't overlap, as in ripgrep
+            while starts.clone().next().is_some_and(|(next, _)| next < end) {
+                starts.next();
+            }
+        }
+    }
+    found
+}
+
+/// `line` with the `found` ranges picked out, which shows only when colour is on
+fn highlight(line: &str, found: &[Range<usize>]) -> String {
+    let mut highlighted = String::new();
+    let mut from = 0;
+    for range in found {
+        highlighted.push_str(&line[from..range.start]);
+        highlighted.push_str(&style(&line[range.clone()]).red().bold().to_string());
+        from = range.end;
+    }
+    highlighted.push_str(&line[from..]);
+    highlighted
+}
@@ -0,0 +1,22 @@
+use super::Output;
+use crate::{
+    agenda::{self, Item},
+    filter::Filter,
+    store::{Result, Store},
+};
+use chrono::{Local, NaiveDate};
+
+/// Whether any todo passes every check given, told only by the exit status
+pub fn run(store: &mut dyn Store, overdue: bool, due: Option<NaiveDate>, filter: Option<&Filter>) -> Result<Output> {
+    let now = Local::now().naive_local();
+    let found = store.todos()?.iter().any(|todo| {
+        (!overdue || agenda::is_overdue(todo, now))
+            && due.is_none_or(|date| !todo.done && todo.due().is_some_and(|d| d.date == date))
+            && filter.is_none_or(|f| f.matches(todo))
+    });
+    let mut out = Output::default();
+    if !found {
+        out.fail();
+    }
+    Ok(out)
+}
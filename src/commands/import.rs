@@ -0,0 +1,130 @@
+use super::Output;
+use crate::{
+    cancel,
+    dates::{self, Due},
+    exchange,
+    messages::Msg,
+    next_id, now_timestamp,
+    store::{Result, Store},
+    text, uuid, Todo,
+};
+use chrono::Local;
+use std::{fs, path::Path};
+
+/// Add the todos in the `format` file at `path`. With `date_sanity`, a file with a due date
+/// far outside the date window imports nothing
+pub fn run(
+    store: &mut dyn Store,
+    path: &Path,
+    format: exchange::Format,
+    headings_as_tags: bool,
+    partial: bool,
+    date_sanity: bool,
+) -> Result<Output> {
+    let mut out = Output::default();
+    let Some(imported) = read(path, format, headings_as_tags, partial, date_sanity, &mut out) else {
+        out.fail();
+        return Ok(out);
+    };
+    let details = details(&imported);
+    let mut todos = store.todos()?;
+    let first = todos.len();
+    let count = add_imported(&mut todos, imported);
+    // Ctrl-C waits until the import is kept whole or dropped whole, then exits
+    let _hold = cancel::hold();
+    if store.insert_all(todos.split_off(first))? {
+        out.msg(Msg::Imported { count, details: &details, from: path, label: store.label() });
+    }
+    Ok(out)
+}
+
+/// Parse an import file, reporting the lines that were skipped. A Google Tasks export with
+/// tasks that can't be read imports nothing, unless `partial` takes the rest
+fn read(
+    path: &Path,
+    format: exchange::Format,
+    headings_as_tags: bool,
+    partial: bool,
+    date_sanity: bool,
+    out: &mut Output,
+) -> Option<Vec<exchange::Imported>> {
+    let parsed = match fs::read_to_string(path).map(|input| exchange::parse(format, &input, headings_as_tags)) {
+        Ok(Ok(parsed)) => parsed,
+        Ok(Err(e)) => {
+            out.msg(Msg::CouldNotRead { what: &path.display(), error: &e });
+            return None;
+        }
+        Err(e) => {
+            out.msg(Msg::CouldNotRead { what: &path.display(), error: &e });
+            return None;
+        }
+    };
+    let unit = if format == exchange::Format::GoogleTasks { "task" } else { "line" };
+    for skip in &parsed.skipped {
+        out.msg(Msg::SkippedLine { unit, line: skip.line, reason: &skip.reason });
+    }
+    if parsed.other_headings > 0 {
+        out.msg(Msg::SkippedHeadings { count: parsed.other_headings });
+    }
+    if format == exchange::Format::GoogleTasks && !parsed.skipped.is_empty() && !partial {
+        out.msg(Msg::UnreadableTasks { count: parsed.skipped.len() });
+        return None;
+    }
+    let today = Local::now().date_naive();
+    let absurd: Vec<(&str, String)> = parsed
+        .todos
+        .iter()
+        .filter_map(|item| {
+            let due = Due::parse(item.due_date.as_deref()?)?;
+            dates::check_window(due.date, today).err().map(|e| (item.text.as_str(), e))
+        })
+        .collect();
+    if date_sanity && !absurd.is_empty() {
+        for (text, e) in &absurd {
+            out.msg(Msg::DueLooksWrong { text: &text::escape(text), error: e });
+        }
+        out.msg(Msg::AbsurdDates);
+        return None;
+    }
+    Some(parsed.todos)
+}
+
+/// How many of the imported todos are done and how many are subtasks, for the summary
+fn details(imported: &[exchange::Imported]) -> String {
+    let done = imported.iter().filter(|item| item.done).count();
+    let subtasks = imported.iter().filter(|item| item.parent.is_some()).count();
+    match subtasks {
+        0 => format!("{} done", done),
+        _ => format!("{} done, {} subtasks", done, subtasks),
+    }
+}
+
+/// Append imported items as new todos, returning how many were added
+fn add_imported(todos: &mut Vec<Todo>, imported: Vec<exchange::Imported>) -> usize {
+    let count = imported.len();
+    let first = todos.len();
+    let mut parents = Vec::new();
+    for item in imported {
+        let now = now_timestamp();
+        parents.push(item.parent);
+        todos.push(Todo {
+            id: next_id(todos),
+            text: item.text,
+            done: item.done,
+            due_date: item.due_date,
+            completed_at: item.completed_at.or_else(|| item.done.then(|| now.clone())),
+            created_at: Some(now),
+            completion_note: item.notes,
+            tags: item.tags,
+            priority: item.priority,
+            // Keep an exported todo's identity, unless it is already here
+            uuid: item.uuid.filter(|u| !todos.iter().any(|t| &t.uuid == u)).unwrap_or_else(uuid::new_v4),
+            ..Default::default()
+        });
+    }
+    // A subtask may come before its parent in the file, so they are linked once every id is known
+    for (offset, parent) in parents.into_iter().enumerate() {
+        todos[first + offset].parent = parent.map(|index| todos[first + index].id);
+    }
+    count
+}
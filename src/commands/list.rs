@@ -0,0 +1,205 @@
+use super::Output;
+use crate::{
+    agenda, config, dates::DateDisplay, doctor, events, filter::Filter, format_todo, lead_time, list_field, output_width, stale_filter,
+    messages::Msg,
+    store_id_width,
+    store::{Result, Store},
+    subtasks,
+    template::Template,
+    text, urgency, Todo,
+};
+use chrono::{DateTime, Local, Utc};
+use clap::ValueEnum;
+
+/// How the rows are arranged, beyond one per todo in id order
+#[derive(Clone, Copy, Default)]
+pub struct Layout {
+    pub tree: Option<Tree>,
+    /// Overdue todos first, in a block of their own, listed flat even in a tree
+    pub pin_overdue: bool,
+    pub sort: Sort,
+}
+
+/// The order todos are listed in
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum Sort {
+    #[default]
+    Id,
+    /// Quickest from creation to done first; todos not done, or missing either time, last
+    LeadTime,
+    /// Most urgent first, by the score `urgency` explains; done todos last
+    Urgency,
+    /// Alphabetically by text, ignoring case and accents
+    Text,
+}
+
+/// Nest subtasks under their parents, each parent showing how many of them are done
+#[derive(Clone, Copy)]
+pub struct Tree {
+    /// Count every descendant towards a parent's progress, not only its direct subtasks
+    pub recursive: bool,
+    /// Leave out subtasks nested deeper than this; 0 shows only the top level
+    pub depth: Option<usize>,
+}
+
+/// List the todos `filter` lets through, one per line or as `template` lays them out
+pub fn run(
+    store: &mut dyn Store,
+    wrap: bool,
+    filter: Option<&Filter>,
+    display: DateDisplay,
+    template: Option<&Template>,
+    layout: Layout,
+) -> Result<Output> {
+    let todos = store.todos()?;
+    let mut out = Output::default();
+    render(&mut out, &todos, wrap, filter, display, template, layout);
+    Ok(out)
+}
+
+/// How many todos `filter` lets through, as a bare number for scripts
+pub fn count(store: &mut dyn Store, filter: Option<&Filter>) -> Result<Output> {
+    let count = store.todos()?.iter().filter(|t| filter.is_none_or(|f| f.matches(t))).count();
+    let mut out = Output::default();
+    out.say(count.to_string());
+    Ok(out)
+}
+
+/// The todos `filter` lets through that changed after `cutoff`, each followed by what the
+/// events log says was done to it. One whose events were pruned, or never logged, still
+/// shows when its updated_at is after the cutoff
+pub fn changed_since(
+    store: &mut dyn Store,
+    cutoff: DateTime<Utc>,
+    wrap: bool,
+    filter: Option<&Filter>,
+    display: DateDisplay,
+) -> Result<Output> {
+    let todos = store.todos()?;
+    let mut out = Output::default();
+    let events = match events::read() {
+        Ok((events, _)) => events,
+        Err(e) => {
+            out.msg(Msg::ReopensUncounted { error: &e });
+            vec![]
+        }
+    };
+    let after_cutoff = |at: &str| DateTime::parse_from_rfc3339(at).is_ok_and(|at| at > cutoff);
+    let recent: Vec<&events::Event> = events.iter().filter(|event| after_cutoff(&event.at)).collect();
+    let width = output_width();
+    let id_width = store_id_width(&todos);
+    let mut shown = 0;
+    for todo in todos.iter().filter(|t| filter.is_none_or(|f| f.matches(t))) {
+        let theirs: Vec<&events::Event> = recent.iter().copied().filter(|event| event.id == todo.id).collect();
+        if theirs.is_empty() && !todo.updated_at.as_deref().is_some_and(after_cutoff) {
+            continue;
+        }
+        let what = if theirs.is_empty() {
+            "changed; the events log has no details".to_string()
+        } else {
+            events::summary(&theirs)
+        };
+        out.say(format_todo(todo, id_width, width, wrap, display));
+        // Under the text, past the status and id
+        out.say(format!("{:indent$}↳ {}", "", what, indent = id_width + 6));
+        shown += 1;
+    }
+    if shown == 0 {
+        out.say(format!("No todos changed since {}", cutoff.with_timezone(&Local).format("%Y-%m-%d %H:%M")));
+    }
+    Ok(out)
+}
+
+/// One line per todo, with stale ones dimmed; shared with `search`
+pub fn render(
+    out: &mut Output,
+    todos: &[Todo],
+    wrap: bool,
+    filter: Option<&Filter>,
+    display: DateDisplay,
+    template: Option<&Template>,
+    layout: Layout,
+) {
+    let Layout { tree, pin_overdue, sort } = layout;
+    doctor::warn_unreadable(todos);
+    let width = output_width();
+    // Over the whole store rather than what is shown, so filtering doesn't shift the columns
+    let id_width = store_id_width(todos);
+    let stale = stale_filter(config::load().stale_after());
+    let now = Utc::now();
+    let progress = subtasks::progress(todos, tree.is_some_and(|tree| tree.recursive));
+    let local_now = Local::now().naive_local();
+    let (pinned, mut shown): (Vec<&Todo>, Vec<&Todo>) = todos
+        .iter()
+        .filter(|t| filter.is_none_or(|f| f.matches(t)))
+        .partition(|t| pin_overdue && agenda::is_overdue(*t, local_now));
+    if sort == Sort::LeadTime {
+        shown.sort_by_key(|todo| lead_time(todo).map_or((1, chrono::Duration::zero()), |lead| (0, lead)));
+    }
+    if sort == Sort::Text {
+        // Stable, so todos with the same text stay in id order
+        shown.sort_by(|a, b| text::collate(&a.text, &b.text));
+    }
+    if sort == Sort::Urgency {
+        let weights = config::load().urgency;
+        let score = |todo: &Todo| urgency::score(todo, local_now, &weights);
+        // Stable, so equal scores stay in id order
+        shown.sort_by(|a, b| a.done.cmp(&b.done).then(score(b).total_cmp(&score(a))));
+    }
+    let rows = match tree {
+        Some(tree) => {
+            warn_broken(out, &subtasks::broken(todos));
+            subtasks::tree(&shown, tree.depth)
+        }
+        None => (0..shown.len())
+            .map(|index| subtasks::Row { index, depth: 0, prefix: String::new(), continuation: String::new() })
+            .collect(),
+    };
+    let pinned_rows = pinned.iter().map(|todo| (*todo, String::new(), String::new()));
+    let rest_rows = rows.into_iter().map(|row| (shown[row.index], row.prefix, row.continuation));
+    if !pinned.is_empty() && template.is_none() {
+        out.say(console::style(Msg::Overdue { count: pinned.len() }.to_string()).red().bold().to_string());
+    }
+    for (n, (todo, indent, continuation)) in pinned_rows.chain(rest_rows).enumerate() {
+        // A blank line closes the overdue block, unless a template is laying out the lines
+        if n == pinned.len() && n > 0 && template.is_none() {
+            out.say("");
+        }
+        let progress = progress.get(&todo.id).copied();
+        let indent_width = indent.chars().count();
+        if let Some(template) = template {
+            out.say(format!("{}{}", indent, template.render(|field| list_field(todo, field, display, now, progress))));
+            continue;
+        }
+        let line = match progress.filter(|_| tree.is_some()) {
+            Some(progress) => {
+                // Every subtask done rolls up to a tick, as the parent may be ready to close
+                let progress = if progress.is_complete() {
+                    format!("[{}/{} ✓]", progress.done, progress.total)
+                } else {
+                    progress.to_string()
+                };
+                let todo = Todo { text: format!("{} {}", todo.text, progress), ..todo.clone() };
+                format_todo(&todo, id_width, width.map(|w| w.saturating_sub(indent_width)), wrap, display)
+            }
+            None => format_todo(todo, id_width, width.map(|w| w.saturating_sub(indent_width)), wrap, display),
+        };
+        let line = format!("{}{}", indent, line.replace('\n', &format!("\n{}", continuation)));
+        if stale.matches(todo) {
+            out.say(console::style(line).dim().to_string());
+        } else {
+            out.say(line);
+        }
+    }
+}
+
+/// Say which parent ids the tree had to ignore, as they can only come from a hand edit
+fn warn_broken(out: &mut Output, broken: &subtasks::Broken) {
+    for (id, parent) in &broken.missing {
+        out.msg(Msg::MissingParent { id: *id, parent: *parent });
+    }
+    if !broken.looped.is_empty() {
+        let ids: Vec<String> = broken.looped.iter().map(|id| format!("#{}", id)).collect();
+        out.msg(Msg::ParentLoop { ids: &ids.join(", ") });
+    }
+}
@@ -0,0 +1,72 @@
+use super::{can_ask, Output};
+use crate::{
+    dates::DateDisplay,
+    format_todo, json_file,
+    merge::{self, Side, Strategy},
+    messages::Msg,
+    reminders,
+    store::{Result, Store},
+    Todo,
+};
+use std::{fs, path::Path};
+
+/// Merge the JSON store at `path` into this one, asking about conflicts when no strategy is
+/// given
+pub fn run(store: &mut dyn Store, path: &Path, strategy: Option<Strategy>) -> Result<Output> {
+    let mut out = Output::default();
+    let mut theirs: Vec<Todo> = match fs::read_to_string(path).map(|data| json_file::parse_todos(&data)) {
+        Ok(Ok(theirs)) => theirs,
+        Ok(Err(e)) => {
+            out.msg(Msg::NotAStore { path, error: &e });
+            out.fail();
+            return Ok(out);
+        }
+        Err(e) => {
+            out.msg(Msg::CouldNotRead { what: &path.display(), error: &e });
+            out.fail();
+            return Ok(out);
+        }
+    };
+    if strategy.is_none() && !can_ask() {
+        out.msg(Msg::MergeNeedsStrategy);
+        out.fail();
+        return Ok(out);
+    }
+    reminders::migrate_reminders(&mut theirs);
+    let mut todos = store.todos()?;
+    let summary = merge::merge(&mut todos, theirs, |ours, theirs| match strategy {
+        Some(strategy) => strategy.pick(ours, theirs),
+        None => ask_side(ours, theirs),
+    });
+    if summary.added + summary.took_theirs > 0 {
+        store.replace(todos)?;
+    }
+    out.msg(Msg::Merged {
+        from: path,
+        added: summary.added,
+        identical: summary.identical,
+        kept_ours: summary.kept_ours,
+        took_theirs: summary.took_theirs,
+        label: store.label(),
+    });
+    Ok(out)
+}
+
+/// Show both copies of a conflicting todo and ask which to keep, keeping ours if the
+/// prompt is cancelled
+fn ask_side(ours: &Todo, theirs: &Todo) -> Side {
+    let display = DateDisplay::default();
+    let items = [
+        format!("Ours:   {}", format_todo(ours, 0, None, false, display)),
+        format!("Theirs: {}", format_todo(&Todo { id: ours.id, ..theirs.clone() }, 0, None, false, display)),
+    ];
+    let choice = dialoguer::Select::new()
+        .with_prompt(format!("Todo {} was changed in both stores; keep which copy?", ours.id))
+        .items(&items)
+        .default(0)
+        .interact();
+    match choice {
+        Ok(1) => Side::Theirs,
+        _ => Side::Ours,
+    }
+}
@@ -0,0 +1,262 @@
+pub mod add;
+pub mod cal;
+pub mod conflicts;
+pub mod delete;
+pub mod diff_backup;
+pub mod doctor;
+pub mod done;
+pub mod due;
+pub mod edit;
+pub mod get;
+pub mod grep;
+pub mod has;
+pub mod import;
+pub mod list;
+pub mod merge;
+pub mod plan;
+pub mod priority;
+pub mod template;
+pub mod remind;
+pub mod repeat;
+pub mod restore;
+pub mod search;
+pub mod stats;
+pub mod tag;
+pub mod today;
+pub mod trash;
+pub mod upcoming;
+pub mod week;
+
+use crate::{
+    config::Config,
+    fuzzy,
+    messages::{Level, Msg},
+    store::{Result, Store},
+    text, Todo,
+};
+use std::{
+    io::{self, IsTerminal},
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+/// Set while the TUI runs a command typed at its `:` prompt, whose screen leaves nowhere
+/// to put a question
+static NO_QUESTIONS: AtomicBool = AtomicBool::new(false);
+
+pub fn set_no_questions(on: bool) {
+    NO_QUESTIONS.store(on, Ordering::Relaxed);
+}
+
+/// Whether a command may stop to ask the user something: there is a terminal to ask on,
+/// and the TUI isn't drawn over it
+pub fn can_ask() -> bool {
+    !NO_QUESTIONS.load(Ordering::Relaxed) && io::stdin().is_terminal()
+}
+
+/// Whether a command may save what it changes
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum WriteMode {
+    Write,
+    /// --dry-run: report the changes instead of saving them
+    DryRun,
+    /// `readonly` in the config or TODO_READONLY: any change is an error
+    ReadOnly,
+}
+
+impl WriteMode {
+    pub fn new(config: &Config, dry_run: bool) -> WriteMode {
+        if dry_run {
+            WriteMode::DryRun
+        } else if config.readonly() {
+            WriteMode::ReadOnly
+        } else {
+            WriteMode::Write
+        }
+    }
+}
+
+/// Whether a command may go on to make `change`, a "would ..." line, to one of the files
+/// kept beside the store. If `mode` says not, `out` reports the change as a dry run's or
+/// fails the command as read-only mode's refusal
+pub fn may_write(mode: WriteMode, change: impl FnOnce() -> String, out: &mut Output) -> bool {
+    match mode {
+        WriteMode::Write => true,
+        WriteMode::DryRun => {
+            out.msg(Msg::DryRun);
+            out.say(format!("  {}", change()));
+            false
+        }
+        WriteMode::ReadOnly => {
+            out.msg(Msg::ReadOnlyStore);
+            out.fail();
+            false
+        }
+    }
+}
+
+/// What a command has to report, in the order it happened. Commands are written once
+/// against a `Store` for both backends and leave printing this to the caller
+#[derive(Default)]
+pub struct Output {
+    lines: Vec<Line>,
+    /// What the process exits with once the output is printed; 0 unless the command failed
+    exit_code: i32,
+}
+
+enum Line {
+    Stdout(String),
+    Stderr(String),
+}
+
+impl Output {
+    /// A line for stdout
+    pub fn say(&mut self, line: impl Into<String>) {
+        self.lines.push(Line::Stdout(line.into()));
+    }
+
+    /// A line for stderr: a warning, or an error the command gives up on quietly
+    pub fn warn(&mut self, line: impl Into<String>) {
+        self.lines.push(Line::Stderr(line.into()));
+    }
+
+    /// A message from the catalog, on stdout or stderr as its level says
+    pub fn msg(&mut self, msg: Msg) {
+        if msg.level().on_stderr() {
+            self.warn(msg.to_string());
+        } else {
+            self.say(msg.to_string());
+        }
+    }
+
+    /// Make the process exit non-zero once the output is printed
+    pub fn fail(&mut self) {
+        self.fail_with(1);
+    }
+
+    /// Make the process exit with `code`, for failures scripts need to tell apart
+    pub fn fail_with(&mut self, code: i32) {
+        self.exit_code = code;
+    }
+
+    /// Print what has been said so far, so it comes before a question put to the user
+    pub fn flush(&mut self) {
+        for line in self.lines.drain(..) {
+            match line {
+                Line::Stdout(line) => println!("{}", line),
+                Line::Stderr(line) => eprintln!("{}", line),
+            }
+        }
+    }
+
+    /// Everything said run into one line, for the TUI's status line, with how it went:
+    /// an error if the command failed, a warning if it said anything on stderr
+    pub fn summary(&self) -> Option<(Level, String)> {
+        let text: Vec<&str> = self
+            .lines
+            .iter()
+            .flat_map(|line| match line {
+                Line::Stdout(line) | Line::Stderr(line) => line.lines(),
+            })
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .collect();
+        if text.is_empty() {
+            return None;
+        }
+        let level = if self.exit_code != 0 {
+            Level::Error
+        } else if self.lines.iter().any(|line| matches!(line, Line::Stderr(_))) {
+            Level::Warning
+        } else {
+            Level::Success
+        };
+        Some((level, text.join("  ")))
+    }
+
+    /// Whether the command said nothing at all
+    pub fn is_empty(&self) -> bool {
+        self.lines.is_empty()
+    }
+
+    /// What the process exits with once the output is printed
+    pub fn exit_code(&self) -> i32 {
+        self.exit_code
+    }
+
+    /// Print the lines, then exit non-zero if the command failed
+    pub fn emit(mut self) {
+        self.flush();
+        if self.exit_code != 0 {
+            crate::shell::exit(self.exit_code);
+        }
+    }
+}
+
+/// An unknown id, reported with nearby ids and fuzzy text matches
+pub fn not_found(id: usize, todos: &[Todo], hint: &[String]) -> Output {
+    let mut out = Output::default();
+    out.msg(Msg::NotFound { id });
+
+    let matches = fuzzy::matching_todos(todos, hint);
+    if !matches.is_empty() {
+        out.warn("   Did you mean:");
+        for todo in matches {
+            out.warn(format!("     #{} {}", todo.id, text::escape(&todo.text)));
+        }
+    } else {
+        let nearest = fuzzy::nearest_ids(id, todos, 3);
+        if !nearest.is_empty() {
+            let ids: Vec<String> = nearest.iter().map(|id| format!("#{}", id)).collect();
+            out.warn(format!("   Nearest existing ids: {}", ids.join(", ")));
+        }
+    }
+    out.fail();
+    out
+}
+
+/// Run `command` on the pending todo whose text is exactly `text`, for scripts that know a
+/// todo's text but not its id. No match fails with exit status 1; several list the
+/// candidates and fail with 2 without running `command`, unless `first` takes the oldest
+pub fn with_text(
+    store: &mut dyn Store,
+    text: &str,
+    first: bool,
+    command: impl FnOnce(&mut dyn Store, usize) -> Result<Output>,
+) -> Result<Output> {
+    let todos = store.todos()?;
+    let matches: Vec<&Todo> = todos.iter().filter(|t| !t.done && t.text == text).collect();
+    let mut out = Output::default();
+    match matches.as_slice() {
+        [] => {
+            out.msg(Msg::NoPendingText { text: &text::escape(text) });
+            out.fail();
+        }
+        // In id order, and a new todo always gets a higher id than any before it, so the first is the oldest
+        [only, ..] if first || matches.len() == 1 => return command(store, only.id),
+        _ => {
+            out.msg(Msg::AmbiguousText { count: matches.len(), text: &text::escape(text) });
+            for todo in matches {
+                out.warn(format!("   #{} {}", todo.id, text::escape(&todo.text)));
+            }
+            out.fail_with(2);
+        }
+    }
+    Ok(out)
+}
+
+/// Todo text from the command line or an editor, made to fit on one line; `None` after
+/// reporting text that can't be stored
+fn todo_text(raw: &str, out: &mut Output) -> Option<String> {
+    match text::normalize(raw) {
+        Ok(normalized) => {
+            if normalized.joined_lines > 1 {
+                out.msg(Msg::JoinedLines { count: normalized.joined_lines });
+            }
+            Some(normalized.text)
+        }
+        Err(e) => {
+            out.msg(Msg::Failed { error: &Msg::NotSaved { error: &e } });
+            None
+        }
+    }
+}
@@ -0,0 +1,135 @@
+use super::{due::auto_reminder, Output};
+use crate::{
+    agenda, burndown, config,
+    dates::{self, Due},
+    messages::Msg,
+    store::{Result, Store},
+    text, todo_age, Todo,
+};
+use chrono::{Duration, Local, NaiveDate, Utc};
+use crossterm::{
+    event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers},
+    terminal,
+};
+use std::io::{self, IsTerminal, Write};
+
+/// What a key pressed for a todo asks for
+enum Choice {
+    Due(NaiveDate),
+    Skip,
+    Quit,
+}
+
+/// Go through the pending todos without a due date, oldest first, giving each a day this
+/// week or next at a single key press. Each choice is saved as it is made; the week's
+/// load is charted at the end
+pub fn run(store: &mut dyn Store) -> Result<Output> {
+    let mut out = Output::default();
+    if !io::stdin().is_terminal() {
+        out.msg(Msg::PlanNeedsTerminal);
+        out.fail();
+        return Ok(out);
+    }
+    let undated: Vec<Todo> = store.todos()?.into_iter().filter(|t| !t.done && t.due_date.is_none()).collect();
+    if undated.is_empty() {
+        out.say("Every pending todo has a due date");
+        return Ok(out);
+    }
+    let today = Local::now().date_naive();
+    let start = agenda::week_start(today, config::load().first_weekday());
+    let days: Vec<NaiveDate> = (0..7).map(|offset| start + Duration::days(offset)).collect();
+    let keys: Vec<String> = days
+        .iter()
+        .enumerate()
+        .filter(|(_, day)| **day >= today)
+        .map(|(n, day)| format!("{} {}", n + 1, day.format("%a")))
+        .collect();
+    out.msg(Msg::Planning { count: undated.len(), week: &dates::format_date(start) });
+    out.say(format!("   {} • n next week • s skip • q quit", keys.join(" • ")));
+    out.flush();
+
+    let (mut planned, mut skipped) = (0, 0);
+    for mut todo in undated {
+        let age = todo_age(&todo, Utc::now()).map_or(String::new(), |age| format!(" ({} old)", dates::format_duration(age)));
+        let tags: String = todo.tags.iter().map(|tag| format!(" #{}", tag)).collect();
+        print!("#{} {}{}{} ", todo.id, text::escape(&todo.text), age, tags);
+        let _ = io::stdout().flush();
+        let choice = match choose(&days, today) {
+            Ok(choice) => choice,
+            Err(e) => {
+                println!();
+                out.msg(Msg::CouldNotRead { what: &"a key", error: &e });
+                out.fail();
+                break;
+            }
+        };
+        let date = match choice {
+            Choice::Due(date) => date,
+            Choice::Skip => {
+                println!("skipped");
+                skipped += 1;
+                continue;
+            }
+            Choice::Quit => {
+                println!();
+                break;
+            }
+        };
+        println!("→ {} {}", date.format("%a"), dates::format_date(date));
+        let due = Due { date, time: None };
+        todo.due_date = Some(due.to_stored());
+        if todo.reminder.is_none() {
+            todo.reminder = auto_reminder(todo.id, due, &mut out);
+            out.flush();
+        }
+        store.update(&todo)?;
+        planned += 1;
+    }
+
+    out.msg(Msg::Planned { count: planned, skipped, label: store.label() });
+    let todos = store.todos()?;
+    let load: Vec<burndown::Day> = agenda::week(&todos, start)
+        .iter()
+        .map(|day| burndown::Day { date: day.date, open: day.todos.len() })
+        .collect();
+    out.say("Due this week:");
+    for line in burndown::render(&load) {
+        out.say(line);
+    }
+    Ok(out)
+}
+
+/// Wait for a key that means something: 1 to 7 for a day this week that hasn't passed,
+/// n, s, or q, Esc or Ctrl-C to stop
+fn choose(days: &[NaiveDate], today: NaiveDate) -> io::Result<Choice> {
+    loop {
+        let key = read_key()?;
+        let choice = match key.code {
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => Choice::Quit,
+            KeyCode::Char(c @ '1'..='7') => match days[c as usize - '1' as usize] {
+                day if day >= today => Choice::Due(day),
+                _ => continue,
+            },
+            KeyCode::Char('n') => Choice::Due(days[0] + Duration::weeks(1)),
+            KeyCode::Char('s') => Choice::Skip,
+            KeyCode::Char('q') | KeyCode::Esc => Choice::Quit,
+            _ => continue,
+        };
+        return Ok(choice);
+    }
+}
+
+/// The next key pressed. The terminal is in raw mode only while waiting, so Ctrl-C comes in
+/// as a key rather than killing the process, and the terminal is always put back
+fn read_key() -> io::Result<KeyEvent> {
+    terminal::enable_raw_mode()?;
+    let key = loop {
+        match event::read() {
+            Ok(Event::Key(key)) if key.kind == KeyEventKind::Press => break Ok(key),
+            Ok(_) => continue,
+            Err(e) => break Err(e),
+        }
+    };
+    terminal::disable_raw_mode()?;
+    key
+}
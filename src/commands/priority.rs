@@ -0,0 +1,23 @@
+use super::{not_found, Output};
+use crate::{
+    messages::Msg,
+    priority::Priority,
+    store::{Result, Store},
+};
+
+/// Set a todo's priority, or clear it when `level` is `None`
+pub fn run(store: &mut dyn Store, id: usize, level: Option<Priority>) -> Result<Output> {
+    let todos = store.todos()?;
+    let Some(todo) = todos.iter().find(|t| t.id == id) else {
+        return Ok(not_found(id, &todos, &[]));
+    };
+    let mut todo = todo.clone();
+    todo.priority = level.map(|l| l.as_str().to_string());
+    store.update(&todo)?;
+    let mut out = Output::default();
+    match level {
+        Some(level) => out.msg(Msg::PrioritySet { id, level: level.as_str(), label: store.label() }),
+        None => out.msg(Msg::PriorityCleared { id, label: store.label() }),
+    }
+    Ok(out)
+}
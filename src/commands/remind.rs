@@ -0,0 +1,43 @@
+use super::{not_found, Output};
+use crate::{dates, messages::Msg, reminders::{self, RepeatRule}, store::{Result, Store}, validate_reminder};
+use chrono::Local;
+
+/// Set a todo's reminder, replacing any repeat rule it had with `repeat`
+pub fn run(store: &mut dyn Store, id: usize, date: &str, time: Option<&str>, repeat: Option<RepeatRule>) -> Result<Output> {
+    let mut out = Output::default();
+    let Ok(datetime) = validate_reminder(date, time) else {
+        out.msg(Msg::InvalidReminderFormat);
+        out.fail();
+        return Ok(out);
+    };
+    if let Err(e) = dates::check_window(datetime.date(), Local::now().date_naive()) {
+        out.msg(Msg::ReminderNotSet { error: &e });
+        out.fail();
+        return Ok(out);
+    }
+    let todos = store.todos()?;
+    let Some(todo) = todos.iter().find(|t| t.id == id) else {
+        return Ok(not_found(id, &todos, &[]));
+    };
+    let mut todo = todo.clone();
+    todo.reminder = Some(reminders::to_stored(datetime));
+    todo.reminder_repeat = repeat.map(|r| r.to_string());
+    store.update(&todo)?;
+    out.msg(Msg::ReminderSet { id, label: store.label() });
+    Ok(out)
+}
+
+/// Clear a todo's reminder along with its repeat rule
+pub fn clear(store: &mut dyn Store, id: usize, hint: &[String]) -> Result<Output> {
+    let todos = store.todos()?;
+    let Some(todo) = todos.iter().find(|t| t.id == id) else {
+        return Ok(not_found(id, &todos, hint));
+    };
+    let mut todo = todo.clone();
+    todo.reminder = None;
+    todo.reminder_repeat = None;
+    store.update(&todo)?;
+    let mut out = Output::default();
+    out.msg(Msg::ReminderCleared { id, label: store.label() });
+    Ok(out)
+}
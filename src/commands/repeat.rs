@@ -0,0 +1,28 @@
+use super::{not_found, Output};
+use crate::{
+    messages::Msg,
+    reminders::RepeatRule,
+    store::{Result, Store},
+};
+
+/// Make a todo recur by `rule`, or stop it recurring when that is `None`
+pub fn run(store: &mut dyn Store, id: usize, rule: Option<RepeatRule>) -> Result<Output> {
+    let todos = store.todos()?;
+    let Some(todo) = todos.iter().find(|t| t.id == id) else {
+        return Ok(not_found(id, &todos, &[]));
+    };
+    let mut todo = todo.clone();
+    todo.recurrence = rule.map(|r| r.to_string());
+    store.update(&todo)?;
+    let mut out = Output::default();
+    match rule {
+        Some(rule) => {
+            out.msg(Msg::Repeats { id, rule: &rule.to_string(), label: store.label() });
+            if todo.due_date.is_none() {
+                out.say("   It has no due date, so it recurs only once one is set with `todo due`");
+            }
+        }
+        None => out.msg(Msg::NoLongerRepeats { id, label: store.label() }),
+    }
+    Ok(out)
+}
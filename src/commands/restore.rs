@@ -0,0 +1,49 @@
+use super::Output;
+use crate::{
+    backup,
+    messages::Msg,
+    reminders,
+    store::{Result, Store, SQLITE_LABEL},
+};
+use std::{io, path::Path};
+
+/// Swap every todo for those in the backup bundle at `path`, refusing to overwrite a store
+/// that has todos unless `force` is set. `safety_copy` copies the store aside first,
+/// returning where to, and nothing is restored if it can't
+pub fn run(
+    store: &mut dyn Store,
+    path: &Path,
+    force: bool,
+    safety_copy: impl FnOnce() -> io::Result<Option<String>>,
+) -> Result<Output> {
+    let mut out = Output::default();
+    let mut bundle = match backup::read_bundle(path) {
+        Ok(bundle) => bundle,
+        Err(e) => {
+            out.msg(Msg::BackupUnreadable { error: &e });
+            out.fail();
+            return Ok(out);
+        }
+    };
+    let existing = store.todos()?.len();
+    if existing > 0 && !force {
+        let name = if store.label() == SQLITE_LABEL { "database" } else { "store" };
+        out.msg(Msg::NotEmpty { count: existing, store: name });
+        out.fail();
+        return Ok(out);
+    }
+    match safety_copy() {
+        Ok(Some(copy)) => out.msg(Msg::SafetyCopy { path: &copy }),
+        Ok(None) => {}
+        Err(e) => {
+            out.msg(Msg::SafetyCopyFailed { error: &e });
+            out.fail();
+            return Ok(out);
+        }
+    }
+    reminders::migrate_reminders(&mut bundle.todos);
+    let count = bundle.todos.len();
+    store.replace(bundle.todos)?;
+    out.msg(Msg::Restored { count, backend: &bundle.manifest.backend, label: store.label() });
+    Ok(out)
+}
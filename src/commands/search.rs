@@ -0,0 +1,27 @@
+use super::Output;
+use crate::{
+    date_display, filter,
+    messages::Msg,
+    store::{Result, Store},
+};
+
+/// The todos matching a `search` query, or a note that nothing did
+pub fn run(store: &mut dyn Store, query: &[String]) -> Result<Output> {
+    let mut out = Output::default();
+    let expr = query.join(" ");
+    let filter = match filter::parse(&expr) {
+        Ok(filter) => filter,
+        Err(e) => {
+            out.msg(Msg::InvalidFilter { error: &e.render(&expr) });
+            out.fail();
+            return Ok(out);
+        }
+    };
+    let todos = store.todos()?;
+    if todos.iter().any(|t| filter.matches(t)) {
+        super::list::render(&mut out, &todos, false, Some(&filter), date_display(false, false), None, Default::default());
+    } else {
+        out.say("No todos match");
+    }
+    Ok(out)
+}
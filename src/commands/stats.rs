@@ -0,0 +1,84 @@
+use super::Output;
+use crate::{
+    config, dates, goal_progress, goals, lead_time,
+    messages::Msg,
+    stale_filter,
+    store::{Result, Store},
+    text, todo_age, Todo,
+};
+use chrono::Utc;
+use std::collections::BTreeMap;
+
+/// How many todos there are, how long the pending ones have waited, how long the done ones
+/// took, and how the goals stand
+pub fn run(store: &mut dyn Store) -> Result<Output> {
+    let todos = store.todos()?;
+    let mut out = Output::default();
+    let now = Utc::now();
+    let pending: Vec<&Todo> = todos.iter().filter(|t| !t.done).collect();
+    out.msg(Msg::Stats { count: todos.len(), pending: pending.len(), done: todos.len() - pending.len() });
+
+    let ages: Vec<(&Todo, chrono::Duration)> = pending
+        .iter()
+        .filter_map(|todo| todo_age(todo, now).map(|age| (*todo, age)))
+        .collect();
+    if let Some((oldest, max_age)) = ages.iter().max_by_key(|(_, age)| *age) {
+        let total: chrono::Duration = ages.iter().map(|(_, age)| *age).sum();
+        let average = total / ages.len() as i32;
+        out.say(format!("   Average pending age: {}", dates::format_duration(average)));
+        out.say(format!("   Oldest pending:      {} (#{} {})", dates::format_duration(*max_age), oldest.id, text::escape(&oldest.text)));
+        let stale = stale_filter(config::load().stale_after());
+        let stale_count = pending.iter().filter(|t| stale.matches(t)).count();
+        if stale_count > 0 {
+            out.say(format!("   Stale:               {}", stale_count));
+        }
+    }
+    lead_times(&mut out, &todos);
+    let progress = goal_progress(&todos, None);
+    if !progress.is_empty() {
+        out.say("   Goals:");
+        for line in goals::render(&progress) {
+            out.say(format!("     {}", line));
+        }
+    }
+    Ok(out)
+}
+
+/// Median and mean time from creation to completion, overall and for each tag. Todos
+/// missing either time are left out
+fn lead_times(out: &mut Output, todos: &[Todo]) {
+    let mut overall = Vec::new();
+    let mut by_tag: BTreeMap<&str, Vec<chrono::Duration>> = BTreeMap::new();
+    for todo in todos {
+        let Some(lead) = lead_time(todo) else {
+            continue;
+        };
+        overall.push(lead);
+        for tag in &todo.tags {
+            by_tag.entry(tag).or_default().push(lead);
+        }
+    }
+    if overall.is_empty() {
+        return;
+    }
+    out.say(format!("   Lead time:           {}", lead_summary(&mut overall)));
+    for (tag, leads) in &mut by_tag {
+        out.say(format!("     #{:<18} {}", tag, lead_summary(leads)));
+    }
+}
+
+/// "median 2d 4h, mean 3d 1h over 5 todos"
+fn lead_summary(leads: &mut [chrono::Duration]) -> String {
+    leads.sort();
+    let middle = leads.len() / 2;
+    let median = if leads.len().is_multiple_of(2) { (leads[middle - 1] + leads[middle]) / 2 } else { leads[middle] };
+    let mean = leads.iter().copied().sum::<chrono::Duration>() / leads.len() as i32;
+    let noun = if leads.len() == 1 { "todo" } else { "todos" };
+    format!(
+        "median {}, mean {} over {} {}",
+        dates::format_duration(median),
+        dates::format_duration(mean),
+        leads.len(),
+        noun
+    )
+}
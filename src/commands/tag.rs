@@ -0,0 +1,37 @@
+use super::{not_found, Output};
+use crate::{
+    messages::Msg,
+    normalize_tags,
+    store::{Result, Store},
+    tag_summary,
+};
+
+/// Add `tags` to a todo, or take them off with `remove`
+pub fn run(store: &mut dyn Store, id: usize, tags: &[String], remove: bool) -> Result<Output> {
+    let mut out = Output::default();
+    let tags = match normalize_tags(tags.iter().map(String::as_str)) {
+        Ok(tags) => tags,
+        Err(e) => {
+            out.msg(Msg::Failed { error: &e });
+            out.fail();
+            return Ok(out);
+        }
+    };
+    let todos = store.todos()?;
+    let Some(todo) = todos.iter().find(|t| t.id == id) else {
+        return Ok(not_found(id, &todos, &[]));
+    };
+    let mut todo = todo.clone();
+    if remove {
+        todo.tags.retain(|t| !tags.contains(t));
+    } else {
+        for tag in tags {
+            if !todo.tags.contains(&tag) {
+                todo.tags.push(tag);
+            }
+        }
+    }
+    store.update(&todo)?;
+    out.msg(Msg::TagsSet { id, tags: &tag_summary(&todo.tags), label: store.label() });
+    Ok(out)
+}
@@ -0,0 +1,82 @@
+use super::{may_write, not_found, Output, WriteMode};
+use crate::{
+    dates::Due,
+    messages::Msg,
+    reminders::parse_reminder,
+    saved_templates::{self, SavedTemplate},
+    store::{Result, Store},
+    template::Template,
+    text::escape,
+};
+use chrono::{DateTime, Local};
+
+/// Save todo `id` as a template under `name`: its text (or `text` in its place), tags and
+/// priority, with its due date and reminder kept as offsets so each use lands as far ahead.
+/// The templates file is only written as `mode` allows
+pub fn save(store: &mut dyn Store, name: &str, id: usize, text: Option<&str>, mode: WriteMode) -> Result<Output> {
+    let mut out = Output::default();
+    if !saved_templates::valid_name(name) {
+        out.msg(Msg::BadTemplateName);
+        out.fail();
+        return Ok(out);
+    }
+    let todos = store.todos()?;
+    let Some(todo) = todos.iter().find(|t| t.id == id) else {
+        return Ok(not_found(id, &todos, &[]));
+    };
+    let text = text.unwrap_or(&todo.text);
+    let fields = match Template::parse_open(text) {
+        Ok(template) => template.fields().iter().map(|f| format!("{{{}}}", f)).collect::<Vec<_>>(),
+        Err(e) => {
+            out.msg(Msg::BadTemplateSource { error: &e });
+            out.fail();
+            return Ok(out);
+        }
+    };
+    let due = todo.due_date.as_deref().and_then(Due::parse);
+    let created = todo
+        .created_at
+        .as_deref()
+        .and_then(|at| DateTime::parse_from_rfc3339(at).ok())
+        .map(|at| at.with_timezone(&Local).date_naive());
+    let due_offset = match (due, created) {
+        (Some(due), Some(created)) => Some(format!("+{}d", (due.date - created).num_days().max(0))),
+        _ => None,
+    };
+    let reminder_offset = match (due, todo.reminder.as_deref().and_then(parse_reminder)) {
+        (Some(due), Some(reminder)) => {
+            let reminder = reminder.with_timezone(&Local).naive_local();
+            let days = (due.date - reminder.date()).num_days();
+            (days >= 0).then(|| format!("-{}d {}", days, reminder.format("%H:%M")))
+        }
+        _ => None,
+    };
+    let template = SavedTemplate {
+        text: text.to_string(),
+        tags: todo.tags.clone(),
+        priority: todo.priority.clone(),
+        due: due_offset,
+        reminder: reminder_offset,
+    };
+    let details: Vec<String> = [
+        (!fields.is_empty()).then(|| format!("placeholders {}", fields.join(", "))),
+        template.due.as_ref().map(|due| format!("due {}", due)),
+        template.reminder.as_ref().map(|reminder| format!("reminder {}", reminder)),
+    ]
+    .into_iter()
+    .flatten()
+    .collect();
+    if !may_write(mode, || format!("would save todo {} as template '{}'", id, name), &mut out) {
+        return Ok(out);
+    }
+    if let Err(e) = saved_templates::save(name, template) {
+        out.msg(Msg::CouldNotSave { what: "template", error: &e });
+        out.fail();
+        return Ok(out);
+    }
+    out.msg(Msg::TemplateSaved { name, text: &escape(text) });
+    if !details.is_empty() {
+        out.say(format!("   {}", details.join(", ")));
+    }
+    Ok(out)
+}
@@ -0,0 +1,33 @@
+use super::Output;
+use crate::{
+    agenda,
+    dates::DateDisplay,
+    doctor, format_todo,
+    messages::Msg,
+    store::{Result, Store},
+    store_id_width,
+};
+use chrono::Local;
+
+/// The overdue todos and those due today, or a note that there are none
+pub fn run(store: &mut dyn Store) -> Result<Output> {
+    let todos = store.todos()?;
+    doctor::warn_unreadable(&todos);
+    let mut out = Output::default();
+    let agenda = agenda::today(&todos, Local::now().naive_local());
+    if agenda.overdue.is_empty() && agenda.due_today.is_empty() {
+        out.msg(Msg::NothingDueToday);
+        return Ok(out);
+    }
+    let id_width = store_id_width(&todos);
+    for (heading, items) in [("Overdue", &agenda.overdue), ("Today", &agenda.due_today)] {
+        if items.is_empty() {
+            continue;
+        }
+        out.say(format!("{}:", heading));
+        for todo in items {
+            out.say(format!("  {}", format_todo(todo, id_width, None, false, DateDisplay::default())));
+        }
+    }
+    Ok(out)
+}
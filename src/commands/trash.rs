@@ -0,0 +1,39 @@
+use super::Output;
+use crate::{
+    messages::Msg,
+    store::{Result, Store},
+    text, trash,
+};
+
+/// Put the todo deleted with `id` back, under that id unless another todo has taken it
+/// since, in which case it gets a new one and the change is pointed out
+pub fn restore(store: &mut dyn Store, id: usize) -> Result<Output> {
+    let mut out = Output::default();
+    // The latest deletion, should an id have been deleted more than once
+    let trashed = match trash::read() {
+        Ok(trashed) => trashed,
+        Err(e) => {
+            out.msg(Msg::TrashUnreadable { error: &e });
+            out.fail();
+            return Ok(out);
+        }
+    };
+    let Some(trashed) = trashed.into_iter().rev().find(|t| t.todo.id == id) else {
+        out.msg(Msg::NotInTrash { id });
+        out.fail();
+        return Ok(out);
+    };
+    let todos = store.todos()?;
+    match todos.iter().find(|t| t.id == id) {
+        Some(holder) => {
+            let new_id = store.insert(trashed.todo)?;
+            out.msg(Msg::UntrashedAs { id, new_id, label: store.label() });
+            out.msg(Msg::IdTaken { id, text: &text::escape(&holder.text), new_id });
+        }
+        None => {
+            store.insert_with_id(trashed.todo)?;
+            out.msg(Msg::Untrashed { id, label: store.label() });
+        }
+    }
+    Ok(out)
+}
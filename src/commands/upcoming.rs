@@ -0,0 +1,84 @@
+use super::Output;
+use crate::{
+    config,
+    dates::{self, DateDisplay},
+    doctor,
+    messages::Msg,
+    reminders,
+    store::{Result, Store},
+    template::Template,
+    text, urgency, Todo,
+};
+use chrono::{Local, Utc};
+
+/// Line format for `upcoming --short` when no --format is given
+pub const SHORT_FORMAT: &str = "{time} {text}";
+
+/// Placeholders an `upcoming --format` template may use
+const FIELDS: &[&str] = &["time", "date", "text", "id"];
+
+/// The coming reminders, soonest first or most urgent first with `by_urgency`, or only the
+/// first when `next` is set. With a template an empty list is a failure, so scripts can tell
+/// there is nothing to show
+pub fn run(store: &mut dyn Store, display: DateDisplay, next: bool, template: Option<&str>, by_urgency: bool) -> Result<Output> {
+    let todos = store.todos()?;
+    doctor::warn_unreadable(&todos);
+    let mut out = Output::default();
+    let now = Utc::now();
+    let mut upcoming: Vec<_> = todos.iter()
+        .filter_map(|todo| reminders::next_reminder(todo, now).map(|dt| (todo, dt)))
+        .collect();
+
+    upcoming.sort_by_key(|(_, dt)| *dt);
+    if by_urgency {
+        let weights = config::load().urgency;
+        let local_now = Local::now().naive_local();
+        let score = |todo: &Todo| urgency::score(todo, local_now, &weights);
+        // Stable, so equally urgent todos stay soonest first
+        upcoming.sort_by(|(a, _), (b, _)| score(b).total_cmp(&score(a)));
+    }
+    if next {
+        upcoming.truncate(1);
+    }
+
+    if let Some(template) = template {
+        let template = match Template::parse(template, FIELDS) {
+            Ok(template) => template,
+            Err(e) => {
+                out.msg(Msg::InvalidFormat { error: &e });
+                out.fail();
+                return Ok(out);
+            }
+        };
+        if upcoming.is_empty() {
+            out.fail();
+        }
+        for (todo, dt) in upcoming {
+            let (date, time) = dates::display_parts(&dt, display);
+            out.say(template.render(|field| match field {
+                "time" => time.clone(),
+                "date" => date.clone(),
+                "text" => text::escape(&todo.text).into_owned(),
+                "id" => todo.id.to_string(),
+                _ => String::new(),
+            }));
+        }
+    } else if upcoming.is_empty() {
+        out.say("No upcoming reminders");
+    } else {
+        out.say("Upcoming reminders:");
+        for (todo, dt) in upcoming {
+            match todo.reminder_repeat.as_deref() {
+                Some(repeat) => out.say(format!(
+                    "[{}] {} - Due: {} (repeats {})",
+                    todo.id,
+                    text::escape(&todo.text),
+                    dates::display_datetime(&dt, display),
+                    repeat
+                )),
+                None => out.say(format!("[{}] {} - Due: {}", todo.id, text::escape(&todo.text), dates::display_datetime(&dt, display))),
+            }
+        }
+    }
+    Ok(out)
+}
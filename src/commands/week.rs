@@ -0,0 +1,35 @@
+use super::Output;
+use crate::{
+    agenda, config,
+    dates::{self, DateDisplay},
+    doctor, format_todo,
+    store::{Result, Store},
+    store_id_width, Todo,
+};
+use chrono::{Duration, Local, NaiveDate};
+
+/// The week agenda, starting on the configured first weekday, `offset` weeks from this one
+pub fn run(store: &mut dyn Store, offset: i64) -> Result<Output> {
+    let todos = store.todos()?;
+    doctor::warn_unreadable(&todos);
+    let mut out = Output::default();
+    let today = Local::now().date_naive();
+    let start = agenda::week_start(today, config::load().first_weekday()) + Duration::weeks(offset);
+    let id_width = store_id_width(&todos);
+    for day in agenda::week(&todos, start) {
+        render_day(&mut out, &day, id_width, today);
+        if day.todos.is_empty() {
+            out.say("  -");
+        }
+    }
+    Ok(out)
+}
+
+/// A day of an agenda as a heading with its todos below
+pub fn render_day(out: &mut Output, day: &agenda::Day<Todo>, id_width: usize, today: NaiveDate) {
+    let marker = if day.date == today { " (today)" } else { "" };
+    out.say(format!("{} {}{}", day.date.format("%a"), dates::format_date(day.date), marker));
+    for todo in &day.todos {
+        out.say(format!("  {}", format_todo(todo, id_width, None, false, DateDisplay::default())));
+    }
+}
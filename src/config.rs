@@ -0,0 +1,336 @@
+use crate::{dates, json_file, messages::{self, Msg}, paths, reminders::AutoRemind, urgency};
+use chrono::{NaiveTime, Weekday};
+use serde::Deserialize;
+use std::{collections::BTreeMap, fs, path::Path, sync::OnceLock, time::Duration};
+
+const DEFAULT_BUSY_TIMEOUT_MS: u64 = 5000;
+const DEFAULT_STALE_AFTER_DAYS: i64 = 30;
+const DEFAULT_REMINDER_HOUR: u32 = 9;
+const DEFAULT_REMINDER_COMMAND_TIMEOUT_SECS: u64 = 10;
+const DEFAULT_NAG_EVERY_MINUTES: i64 = 60;
+const DEFAULT_DUE_SOON_HOURS: i64 = 24;
+
+/// User settings read from `config.toml`; every field is optional
+#[derive(Deserialize, Debug, Default)]
+#[serde(default)]
+pub struct Config {
+    /// How long SQLite waits on a lock held by another process before giving up
+    pub busy_timeout_ms: Option<u64>,
+    /// Show dates in `list` and `upcoming` relative to today without passing --relative-dates
+    pub relative_dates: Option<bool>,
+    /// What a bare `todo` runs: `tui` (the default) or `list`
+    pub default_command: Option<DefaultCommand>,
+    /// Storage to use without --sqlite: `json` (the default) or `sqlite`; also read as
+    /// default_backend
+    #[serde(alias = "default_backend")]
+    pub backend: Option<Backend>,
+    /// Layout a new JSON store starts in: "json" (the default), one document, or "jsonl",
+    /// a line per todo that `add` only appends to; `todo migrate --to` moves an existing one
+    pub json_format: Option<json_file::Format>,
+    /// Show overdue todos above the rest in `list` and the TUI without passing --pin-overdue
+    pub pin_overdue: Option<bool>,
+    /// Append every change to events.ndjson beside the store, for `todo history`
+    pub events_log: Option<bool>,
+    /// After a command, say how many todos are overdue in a dim line of its own
+    pub nag: Option<bool>,
+    /// The least time between two nags (e.g. "2h"), so scripts running many commands
+    /// don't repeat it; an hour by default
+    pub nag_every: Option<String>,
+    /// After a command that changes todos, name those falling due within due_soon_window;
+    /// on by default
+    pub due_soon: Option<bool>,
+    /// How far ahead due_soon looks (e.g. "2d"); 24 hours by default
+    pub due_soon_window: Option<String>,
+    /// Ring the terminal bell when a reminder goes off while the TUI is open; on by default
+    pub tui_bell: Option<bool>,
+    /// Lead messages with an emoji, like "✅ Todo 3 added!"; on by default
+    pub emoji: Option<bool>,
+    /// Pending todos older than this (e.g. "30d") are dimmed in `list` and the TUI
+    pub stale_after: Option<String>,
+    /// Move todos done longer ago than this (e.g. "30d") into archive.json beside the store;
+    /// checked at most once a day, by the first command that may save
+    pub auto_archive_done_after: Option<String>,
+    /// Empty todos deleted longer ago than this (e.g. "90d") from the trash, on the same
+    /// once-a-day check as auto_archive_done_after
+    pub auto_trash_empty_after: Option<String>,
+    /// Read a due date from phrases like "on friday" in `add` text without passing --infer-dates
+    pub infer_dates: Option<bool>,
+    /// Reminder added when `due` sets a date on a todo without one, e.g. "-1d 09:00" or "-2h"
+    pub auto_remind: Option<String>,
+    /// strftime pattern for showing dates, e.g. "%d.%m.%Y"; storage stays YYYY-MM-DD
+    pub date_format: Option<String>,
+    /// How many years back a due date or reminder may be set before it is taken for a
+    /// typo'd year; 5 unless set
+    pub date_window_past_years: Option<u32>,
+    /// How many years ahead one may be set; 50 unless set
+    pub date_window_future_years: Option<u32>,
+    /// Pad ids in lists and the TUI to at least this many digits, beyond the widest id in
+    /// the store that they are always padded to
+    pub id_width: Option<usize>,
+    /// Day the `week` agenda and `cal` start weeks on, `monday` (the default) through `sunday`
+    pub first_weekday: Option<FirstWeekday>,
+    /// Time used when a reminder is given only a date, e.g. "09:00" (the default)
+    pub default_reminder_time: Option<String>,
+    /// Refuse to change the todo list; `TODO_READONLY=1` does the same for one shell
+    pub readonly: Option<bool>,
+    /// Named filter expressions for `list NAME` and the TUI, e.g. `urgent = "priority:high and not done"`
+    pub filters: BTreeMap<String, String>,
+    /// Shorthands expanded in place of the command, e.g. `t = "list --tag work"`; extra
+    /// arguments follow the expansion. Built-in commands can't be redefined
+    pub alias: BTreeMap<String, String>,
+    /// Line template for `list` when --format isn't given, e.g. "{status_icon} {id} {text}"
+    pub list_format: Option<String>,
+    /// Shell command `watch` runs for each reminder that fires, e.g. "paplay ding.ogg";
+    /// TODO_ID and TODO_TEXT are set in its environment
+    pub reminder_command: Option<String>,
+    /// How long reminder_command, or sending a reminder to one of notify_targets, may take
+    /// before it is stopped
+    pub reminder_command_timeout_secs: Option<u64>,
+    /// Where `notify` and `watch` send reminders besides the terminal: "desktop",
+    /// "ntfy:<topic URL>" or "email:<address>"
+    pub notify_targets: Vec<String>,
+    /// Shell command printing the ntfy access token, e.g. "pass show ntfy", so the token
+    /// isn't kept in this file
+    pub ntfy_token_command: Option<String>,
+    /// Shell command that sends an email given on stdin, headers and all; "sendmail -t" by default
+    pub email_command: Option<String>,
+    /// Weights for the urgency score `list --sort urgency` and `upcoming --by-urgency` order
+    /// by, e.g. `[urgency]` with `due = 12.0`, `priority = 6.0`, `age = 2.0` and `overdue = 4.0`
+    pub urgency: urgency::Weights,
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum DefaultCommand {
+    Tui,
+    List,
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Backend {
+    Json,
+    Sqlite,
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum FirstWeekday {
+    Monday,
+    Tuesday,
+    Wednesday,
+    Thursday,
+    Friday,
+    Saturday,
+    Sunday,
+}
+
+impl From<FirstWeekday> for Weekday {
+    fn from(day: FirstWeekday) -> Weekday {
+        match day {
+            FirstWeekday::Monday => Weekday::Mon,
+            FirstWeekday::Tuesday => Weekday::Tue,
+            FirstWeekday::Wednesday => Weekday::Wed,
+            FirstWeekday::Thursday => Weekday::Thu,
+            FirstWeekday::Friday => Weekday::Fri,
+            FirstWeekday::Saturday => Weekday::Sat,
+            FirstWeekday::Sunday => Weekday::Sun,
+        }
+    }
+}
+
+impl Config {
+    pub fn busy_timeout(&self) -> Duration {
+        Duration::from_millis(self.busy_timeout_ms.unwrap_or(DEFAULT_BUSY_TIMEOUT_MS))
+    }
+
+    pub fn reminder_command_timeout(&self) -> Duration {
+        Duration::from_secs(self.reminder_command_timeout_secs.unwrap_or(DEFAULT_REMINDER_COMMAND_TIMEOUT_SECS))
+    }
+
+    /// auto_archive_done_after, or `None` when it is unset or can't be read
+    pub fn auto_archive_done_after(&self) -> Option<chrono::Duration> {
+        sweep_age("auto_archive_done_after", self.auto_archive_done_after.as_deref())
+    }
+
+    /// auto_trash_empty_after, or `None` when it is unset or can't be read
+    pub fn auto_trash_empty_after(&self) -> Option<chrono::Duration> {
+        sweep_age("auto_trash_empty_after", self.auto_trash_empty_after.as_deref())
+    }
+
+    pub fn nag_every(&self) -> chrono::Duration {
+        let default = chrono::Duration::minutes(DEFAULT_NAG_EVERY_MINUTES);
+        match self.nag_every.as_deref().map(dates::parse_duration) {
+            Some(Ok(every)) => every,
+            Some(Err(e)) => {
+                messages::say(Msg::IgnoringSetting { key: "nag_every", file: &paths::config_path(), error: &e });
+                default
+            }
+            None => default,
+        }
+    }
+
+    /// How far ahead to look for todos due soon, or `None` when due_soon is off
+    pub fn due_soon_window(&self) -> Option<chrono::Duration> {
+        if !self.due_soon.unwrap_or(true) {
+            return None;
+        }
+        let default = chrono::Duration::hours(DEFAULT_DUE_SOON_HOURS);
+        match self.due_soon_window.as_deref().map(dates::parse_duration) {
+            Some(Ok(window)) => Some(window),
+            Some(Err(e)) => {
+                messages::say(Msg::IgnoringSetting { key: "due_soon_window", file: &paths::config_path(), error: &e });
+                Some(default)
+            }
+            None => Some(default),
+        }
+    }
+
+    pub fn stale_after(&self) -> chrono::Duration {
+        let default = chrono::Duration::days(DEFAULT_STALE_AFTER_DAYS);
+        match self.stale_after.as_deref().map(dates::parse_duration) {
+            Some(Ok(threshold)) => threshold,
+            Some(Err(e)) => {
+                messages::say(Msg::IgnoringSetting { key: "stale_after", file: &paths::config_path(), error: &e });
+                default
+            }
+            None => default,
+        }
+    }
+
+    pub fn auto_remind(&self) -> Option<AutoRemind> {
+        match AutoRemind::parse(self.auto_remind.as_deref()?) {
+            Ok(rule) => Some(rule),
+            Err(e) => {
+                messages::say(Msg::IgnoringSetting { key: "auto_remind", file: &paths::config_path(), error: &e });
+                None
+            }
+        }
+    }
+
+    /// Install `date_format` for display and input, warning and keeping YYYY-MM-DD if it is
+    /// invalid, and the window of years dates may be set in
+    pub fn apply_date_format(&self) {
+        if let Some(format) = &self.date_format
+            && let Err(e) = dates::set_date_format(format.clone())
+        {
+            messages::say(Msg::IgnoringSetting { key: "date_format", file: &paths::config_path(), error: &e });
+        }
+        dates::set_window(self.date_window_past_years, self.date_window_future_years);
+    }
+
+    pub fn default_reminder_time(&self) -> NaiveTime {
+        let default = NaiveTime::from_hms_opt(DEFAULT_REMINDER_HOUR, 0, 0).unwrap();
+        match self.default_reminder_time.as_deref().map(|t| NaiveTime::parse_from_str(t.trim(), "%H:%M")) {
+            Some(Ok(time)) => time,
+            Some(Err(_)) => {
+                messages::say(Msg::IgnoringSetting {
+                    key: "default_reminder_time",
+                    file: &paths::config_path(),
+                    error: &"expected HH:MM",
+                });
+                default
+            }
+            None => default,
+        }
+    }
+
+    pub fn first_weekday(&self) -> Weekday {
+        self.first_weekday.map_or(Weekday::Mon, Weekday::from)
+    }
+
+    pub fn readonly(&self) -> bool {
+        let env = std::env::var("TODO_READONLY")
+            .is_ok_and(|value| !matches!(value.trim().to_lowercase().as_str(), "" | "0" | "false" | "no"));
+        env || self.readonly.unwrap_or(false)
+    }
+
+    pub fn use_sqlite(&self) -> bool {
+        self.backend == Some(Backend::Sqlite)
+    }
+}
+
+/// The config file, with the profile's own settings over it. It is read once, the first
+/// time it is asked for, so a problem with it is reported once however often it is used
+pub fn load() -> &'static Config {
+    static CONFIG: OnceLock<Config> = OnceLock::new();
+    CONFIG.get_or_init(read)
+}
+
+/// Read the config files, falling back to defaults for a file that is missing or
+/// unreadable. Only `readonly` survives an unreadable file, and fails closed: set to
+/// anything but `false`, it holds
+fn read() -> Config {
+    let mut table = read_table(&paths::config_path());
+    if let Some(path) = paths::profile_config_path() {
+        // Entries of [alias] and [filters] are merged one by one; anything else is replaced
+        for (key, value) in read_table(&path) {
+            match (table.get_mut(&key), value) {
+                (Some(toml::Value::Table(ours)), toml::Value::Table(theirs)) => ours.extend(theirs),
+                (_, value) => {
+                    table.insert(key, value);
+                }
+            }
+        }
+    }
+    let readonly = table.get("readonly").map(|value| value.as_bool() != Some(false));
+    toml::Value::Table(table).try_into().unwrap_or_else(|e| {
+        let mut files = paths::config_path().display().to_string();
+        if let Some(path) = paths::profile_config_path() {
+            files = format!("{} and {}", files, path.display());
+        }
+        messages::say(Msg::Ignoring { what: &files, error: &e });
+        Config { readonly, ..Config::default() }
+    })
+}
+
+fn read_table(path: &Path) -> toml::Table {
+    let Ok(data) = fs::read_to_string(path) else {
+        return toml::Table::new();
+    };
+    data.parse().unwrap_or_else(|e| {
+        messages::say(Msg::Ignoring { what: &path.display(), error: &e });
+        salvage_readonly(&data)
+    })
+}
+
+/// The `readonly` setting of a file that isn't valid TOML, read line by line: a line that
+/// sets it to anything but `false` keeps the store read-only
+fn salvage_readonly(data: &str) -> toml::Table {
+    let mut table = toml::Table::new();
+    let set = data
+        .lines()
+        .filter_map(|line| line.split_once('='))
+        .filter(|(key, _)| key.trim() == "readonly")
+        .any(|(_, value)| value.split('#').next().unwrap_or("").trim() != "false");
+    if set {
+        table.insert("readonly".to_string(), toml::Value::Boolean(true));
+    }
+    table
+}
+
+/// A sweep's age threshold from the config, warning about one that can't be read
+fn sweep_age(key: &str, value: Option<&str>) -> Option<chrono::Duration> {
+    match dates::parse_duration(value?) {
+        Ok(age) => Some(age),
+        Err(e) => {
+            messages::say(Msg::IgnoringSetting { key, file: &paths::config_path(), error: &e });
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn an_unparsable_file_keeps_readonly_unless_it_is_false() {
+        for data in ["readonly = true\n[", "  readonly=1\n[", "readonly = \"no\" # typo\n[", "readonly = tru\n["] {
+            assert_eq!(salvage_readonly(data).get("readonly"), Some(&toml::Value::Boolean(true)), "{:?}", data);
+        }
+        for data in ["readonly = false\n[", "readonly = false # for now\n[", "read_only = true\n[", "[\n"] {
+            assert!(salvage_readonly(data).is_empty(), "{:?}", data);
+        }
+    }
+}
@@ -0,0 +1,357 @@
+use crate::{fuzzy, reminders};
+use chrono::{
+    format::{ParseError, StrftimeItems, Item},
+    DateTime, Datelike, Duration, Local, Months, NaiveDate, NaiveDateTime, NaiveTime, Utc,
+};
+use std::sync::OnceLock;
+
+/// How dates are stored, and always accepted as input whatever the display format
+pub const ISO_DATE: &str = "%Y-%m-%d";
+
+/// The `date_format` from the config, set once at startup
+static DATE_FORMAT: OnceLock<String> = OnceLock::new();
+
+/// Show dates with `format` (a strftime pattern) instead of YYYY-MM-DD
+pub fn set_date_format(format: String) -> Result<(), String> {
+    if StrftimeItems::new(&format).any(|item| matches!(item, Item::Error)) {
+        return Err(format!("'{}' is not a valid strftime format", format));
+    }
+    let _ = DATE_FORMAT.set(format);
+    Ok(())
+}
+
+/// How many years back and forward a date may be set, from the config; set once at startup
+static WINDOW: OnceLock<(u32, u32)> = OnceLock::new();
+
+const DEFAULT_WINDOW: (u32, u32) = (5, 50);
+
+/// Accept dates set from `past` years ago to `future` years ahead, each 5 and 50 unless given
+pub fn set_window(past: Option<u32>, future: Option<u32>) {
+    let _ = WINDOW.set((past.unwrap_or(DEFAULT_WINDOW.0), future.unwrap_or(DEFAULT_WINDOW.1)));
+}
+
+/// Check a date being set is within the configured years of `today`, as one far outside
+/// them is nearly always a typo'd year that would wreck sorting and the calendar. The error
+/// shows the date as read, with the nearest year in the window it could have meant
+pub fn check_window(date: NaiveDate, today: NaiveDate) -> Result<(), String> {
+    let (past, future) = WINDOW.get().copied().unwrap_or(DEFAULT_WINDOW);
+    let earliest = today.checked_sub_months(Months::new(past * 12)).unwrap_or(NaiveDate::MIN);
+    let latest = today.checked_add_months(Months::new(future * 12)).unwrap_or(NaiveDate::MAX);
+    let problem = if date < earliest {
+        format!("more than {} years ago", past)
+    } else if date > latest {
+        format!("more than {} years ahead", future)
+    } else {
+        return Ok(());
+    };
+    let typed = format!("{:04}", date.year());
+    let meant = (earliest.year()..=latest.year())
+        .filter_map(|year| date.with_year(year))
+        .filter(|candidate| (earliest..=latest).contains(candidate))
+        .map(|candidate| (fuzzy::levenshtein(&typed, &format!("{:04}", candidate.year())), (candidate - today).num_days().abs(), candidate))
+        .filter(|(distance, _, _)| *distance <= 2)
+        .min();
+    Err(match meant {
+        Some((_, _, meant)) => format!("did you mean {}? got {}, {}", meant.format(ISO_DATE), date.format(ISO_DATE), problem),
+        None => format!("{} is {}", date.format(ISO_DATE), problem),
+    })
+}
+
+fn date_format() -> &'static str {
+    DATE_FORMAT.get().map_or(ISO_DATE, String::as_str)
+}
+
+/// A date in the configured display format
+pub fn format_date(date: NaiveDate) -> String {
+    date.format(date_format()).to_string()
+}
+
+/// Read a date typed by the user. YYYY-MM-DD is tried first so it always means the same
+/// thing; the configured display format is only a fallback, which keeps input unambiguous
+/// even when that format could be read more than one way
+pub fn parse_date(value: &str) -> Result<NaiveDate, ParseError> {
+    NaiveDate::parse_from_str(value, ISO_DATE).or_else(|iso_err| match DATE_FORMAT.get() {
+        Some(format) => NaiveDate::parse_from_str(value, format).map_err(|_| iso_err),
+        None => Err(iso_err),
+    })
+}
+
+/// Describe `date` relative to `today`: "today", "tomorrow", "in 5 days", "3 days ago",
+/// stepping up to weeks, months and years the further away it is
+pub fn humanize_date(date: NaiveDate, today: NaiveDate) -> String {
+    let days = (date - today).num_days();
+    match days {
+        0 => "today".to_string(),
+        1 => "tomorrow".to_string(),
+        -1 => "yesterday".to_string(),
+        _ => {
+            let (count, unit) = span(date, today, days.unsigned_abs());
+            let amount = if count == 1 {
+                format!("1 {}", unit)
+            } else {
+                format!("{} {}s", count, unit)
+            };
+            if days > 0 {
+                format!("in {}", amount)
+            } else {
+                format!("{} ago", amount)
+            }
+        }
+    }
+}
+
+/// The gap between two dates `days` apart in the coarsest unit that still reads naturally.
+/// Months are counted on the calendar so they stay right across month and year boundaries
+fn span(date: NaiveDate, today: NaiveDate, days: u64) -> (u32, &'static str) {
+    if days < 14 {
+        return (days as u32, "day");
+    }
+    if days < 60 {
+        return ((days / 7) as u32, "week");
+    }
+    let (earlier, later) = if date < today { (date, today) } else { (today, date) };
+    let mut months = (later.year() - earlier.year()) * 12 + later.month() as i32 - earlier.month() as i32;
+    if later.day() < earlier.day() {
+        months -= 1;
+    }
+    let months = months as u32;
+    if months < 12 { (months, "month") } else { (months / 12, "year") }
+}
+
+/// How dates are shown: relative to a given day or in the configured format, and reminder
+/// times in local time or UTC
+#[derive(Clone, Copy, Debug, Default)]
+pub struct DateDisplay {
+    pub relative_to: Option<NaiveDate>,
+    pub utc: bool,
+}
+
+/// A due date, stored as `YYYY-MM-DD` or, when it has a time of day, `YYYY-MM-DD HH:MM`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Due {
+    pub date: NaiveDate,
+    pub time: Option<NaiveTime>,
+}
+
+/// When a date-only due date stops being on time
+const END_OF_DAY: NaiveTime = NaiveTime::from_hms_opt(23, 59, 59).unwrap();
+
+impl Due {
+    /// Read either stored form
+    pub fn parse(value: &str) -> Option<Due> {
+        let value = value.trim();
+        if let Ok(date) = NaiveDate::parse_from_str(value, ISO_DATE) {
+            return Some(Due { date, time: None });
+        }
+        let datetime = NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M").ok()?;
+        Some(Due { date: datetime.date(), time: Some(datetime.time()) })
+    }
+
+    pub fn to_stored(self) -> String {
+        match self.time {
+            Some(time) => format!("{} {}", self.date.format(ISO_DATE), time.format("%H:%M")),
+            None => self.date.format(ISO_DATE).to_string(),
+        }
+    }
+
+    /// The last moment the todo is on time: its due time, or the end of the day when it
+    /// only has a date
+    pub fn deadline(self) -> NaiveDateTime {
+        self.date.and_time(self.time.unwrap_or(END_OF_DAY))
+    }
+
+    pub fn is_overdue(self, now: NaiveDateTime) -> bool {
+        self.deadline() < now
+    }
+
+    /// The same time of day `days` days later, or earlier when negative
+    pub fn shifted(self, days: i64) -> Due {
+        Due { date: self.date + Duration::days(days), ..self }
+    }
+}
+
+/// A stored due date for display, relative to the display's day when set and the date parses
+pub fn display_date(date: &str, display: DateDisplay) -> String {
+    match (display.relative_to, Due::parse(date)) {
+        (Some(today), Some(due)) => match due.time {
+            Some(time) => format!("{} {}", humanize_date(due.date, today), time.format("%H:%M")),
+            None => humanize_date(due.date, today),
+        },
+        (None, Some(due)) => match due.time {
+            Some(time) => format!("{} {}", format_date(due.date), time.format("%H:%M")),
+            None => format_date(due.date),
+        },
+        _ => date.to_string(),
+    }
+}
+
+/// The date and time parts of a reminder for display, in local time (or UTC with a
+/// suffix on the time), with the date relative when asked
+pub fn display_parts(datetime: &DateTime<Utc>, display: DateDisplay) -> (String, String) {
+    let (wall, zone) = if display.utc {
+        (datetime.naive_utc(), " UTC")
+    } else {
+        (datetime.with_timezone(&Local).naive_local(), "")
+    };
+    let date = match display.relative_to {
+        Some(today) => humanize_date(wall.date(), today),
+        None => format_date(wall.date()),
+    };
+    (date, format!("{}{}", wall.format("%H:%M"), zone))
+}
+
+/// A reminder time for display, see `display_parts`
+pub fn display_datetime(datetime: &DateTime<Utc>, display: DateDisplay) -> String {
+    let (date, time) = display_parts(datetime, display);
+    format!("{} {}", date, time)
+}
+
+/// A stored reminder string for display, falling back to the raw value if it does not parse
+pub fn display_reminder(reminder: &str, display: DateDisplay) -> String {
+    match reminders::parse_reminder(reminder) {
+        Some(parsed) => display_datetime(&parsed, display),
+        None => reminder.to_string(),
+    }
+}
+
+/// The most years a span may cover, so that one taken from or added to now is still a time
+const MAX_SPAN_YEARS: i64 = 10_000;
+
+/// Parse a span such as "90m", "12h", "30d" or "2w"
+pub fn parse_duration(value: &str) -> Result<Duration, String> {
+    let value = value.trim();
+    let split = value.find(|c: char| !c.is_ascii_digit()).unwrap_or(value.len());
+    let (amount, unit) = value.split_at(split);
+    let amount: i64 = amount
+        .parse()
+        .map_err(|_| format!("'{}' should be a number followed by m, h, d or w", value))?;
+    let span = match unit {
+        "m" => Duration::try_minutes(amount),
+        "h" => Duration::try_hours(amount),
+        "d" => Duration::try_days(amount),
+        "w" => Duration::try_weeks(amount),
+        _ => return Err(format!("unknown unit '{}' in '{}'; use m, h, d or w", unit, value)),
+    };
+    span.filter(|span| span.num_days() <= MAX_SPAN_YEARS * 366)
+        .ok_or_else(|| format!("'{}' is more than {} years", value, MAX_SPAN_YEARS))
+}
+
+/// A span of time in its two largest units, e.g. "4d 3h", "2h 5m" or "25m"; used for how
+/// long todos have waited and how long they took
+pub fn format_duration(span: Duration) -> String {
+    let total = span.num_minutes().max(0);
+    let (days, hours, minutes) = (total / (24 * 60), total / 60 % 24, total % 60);
+    match (days, hours, minutes) {
+        (0, 0, _) => format!("{}m", minutes),
+        (0, _, 0) => format!("{}h", hours),
+        (0, _, _) => format!("{}h {}m", hours, minutes),
+        (_, 0, _) => format!("{}d", days),
+        _ => format!("{}d {}h", days, hours),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn day(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    #[test]
+    fn window_edges_are_inside() {
+        let today = day(2025, 7, 1);
+        assert!(check_window(day(2020, 7, 1), today).is_ok());
+        assert!(check_window(day(2075, 7, 1), today).is_ok());
+        assert!(check_window(day(2020, 6, 30), today).is_err());
+        assert!(check_window(day(2075, 7, 2), today).is_err());
+    }
+
+    #[test]
+    fn window_suggests_the_year_meant() {
+        let e = check_window(day(205, 7, 1), day(2025, 6, 1)).unwrap_err();
+        assert_eq!(e, "did you mean 2025-07-01? got 0205-07-01, more than 5 years ago");
+        let e = check_window(day(9999, 12, 31), day(2025, 6, 1)).unwrap_err();
+        assert_eq!(e, "9999-12-31 is more than 50 years ahead");
+    }
+
+    #[test]
+    fn spans() {
+        assert_eq!(parse_duration("90m"), Ok(Duration::minutes(90)));
+        assert_eq!(parse_duration(" 2w "), Ok(Duration::days(14)));
+        assert!(parse_duration("3y").is_err());
+        assert!(parse_duration("d").is_err());
+        assert_eq!(parse_duration("99999999999d"), Err("'99999999999d' is more than 10000 years".to_string()));
+        assert!(parse_duration("99999999999999999w").is_err());
+    }
+
+    #[test]
+    fn durations_show_their_two_largest_units() {
+        assert_eq!(format_duration(Duration::zero()), "0m");
+        assert_eq!(format_duration(Duration::minutes(25)), "25m");
+        assert_eq!(format_duration(Duration::minutes(60)), "1h");
+        assert_eq!(format_duration(Duration::minutes(125)), "2h 5m");
+        assert_eq!(format_duration(Duration::days(4) + Duration::minutes(59)), "4d");
+        assert_eq!(format_duration(Duration::days(4) + Duration::hours(3) + Duration::minutes(59)), "4d 3h");
+        assert_eq!(format_duration(Duration::seconds(59)), "0m");
+        // Clock skew can leave a completion before its creation
+        assert_eq!(format_duration(Duration::hours(-3)), "0m");
+    }
+
+    #[test]
+    fn humanized_dates_count_from_today() {
+        let today = day(2025, 7, 3);
+        let cases = [
+            (day(2025, 7, 3), "today"),
+            (day(2025, 7, 4), "tomorrow"),
+            (day(2025, 7, 2), "yesterday"),
+            (day(2025, 7, 8), "in 5 days"),
+            (day(2025, 6, 30), "3 days ago"),
+            (day(2025, 7, 16), "in 13 days"),
+            (day(2025, 7, 17), "in 2 weeks"),
+            (day(2025, 5, 20), "6 weeks ago"),
+            (day(2025, 10, 3), "in 3 months"),
+            (day(2025, 10, 2), "in 2 months"),
+            (day(2026, 7, 3), "in 1 year"),
+            (day(2030, 1, 1), "in 4 years"),
+        ];
+        for (date, expected) in cases {
+            assert_eq!(humanize_date(date, today), expected, "{}", date);
+        }
+    }
+
+    #[test]
+    fn humanized_dates_cross_the_year() {
+        assert_eq!(humanize_date(day(2026, 1, 1), day(2025, 12, 31)), "tomorrow");
+        assert_eq!(humanize_date(day(2025, 12, 31), day(2026, 1, 1)), "yesterday");
+        assert_eq!(humanize_date(day(2026, 1, 5), day(2025, 12, 29)), "in 7 days");
+        assert_eq!(humanize_date(day(2026, 2, 28), day(2025, 11, 30)), "in 2 months");
+        assert_eq!(humanize_date(day(2025, 11, 30), day(2026, 2, 28)), "2 months ago");
+    }
+
+    #[test]
+    fn due_dates_are_stored_with_or_without_a_time() {
+        let nine = NaiveTime::from_hms_opt(9, 5, 0).unwrap();
+        assert_eq!(Due::parse("2025-07-01"), Some(Due { date: day(2025, 7, 1), time: None }));
+        assert_eq!(Due::parse(" 2025-07-01 09:05 "), Some(Due { date: day(2025, 7, 1), time: Some(nine) }));
+        for bad in ["2025-07-01T09:05", "2025-07-01 9:5pm", "2025-02-30", "01.07.2025", ""] {
+            assert_eq!(Due::parse(bad), None, "{}", bad);
+        }
+        for stored in ["2025-07-01", "2025-07-01 09:05", "2025-12-31 23:59"] {
+            assert_eq!(Due::parse(stored).unwrap().to_stored(), stored);
+        }
+    }
+
+    #[test]
+    fn a_date_only_due_date_is_on_time_until_the_day_ends() {
+        let due = Due::parse("2025-07-01").unwrap();
+        let at = |value: &str| NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M:%S").unwrap();
+        assert!(!due.is_overdue(at("2025-07-01 00:00:00")));
+        assert!(!due.is_overdue(at("2025-07-01 23:59:59")));
+        assert!(due.is_overdue(at("2025-07-02 00:00:00")));
+        let timed = Due::parse("2025-07-01 17:00").unwrap();
+        assert!(!timed.is_overdue(at("2025-07-01 17:00:00")));
+        assert!(timed.is_overdue(at("2025-07-01 17:00:01")));
+        assert!(due.deadline() > timed.deadline());
+    }
+}
@@ -0,0 +1,216 @@
+use crate::{config::Config, messages::{self, Msg}, text, Todo};
+use std::{
+    cell::OnceCell,
+    io::Write,
+    process::{Command, Stdio},
+    thread,
+    time::{Duration, Instant},
+};
+
+const DEFAULT_EMAIL_COMMAND: &str = "sendmail -t";
+
+/// Somewhere a reminder is sent besides the terminal
+pub trait Notifier {
+    /// The target as configured, to say which one failed
+    fn name(&self) -> &str;
+    /// Send the reminder for `todo`, which came due `at`
+    fn send(&self, todo: &Todo, at: &str) -> Result<(), String>;
+}
+
+/// A desktop notification, through notify-send, or osascript on macOS
+struct Desktop {
+    timeout: Duration,
+}
+
+/// A POST to an ntfy topic, made with curl
+struct Ntfy {
+    name: String,
+    url: String,
+    token_command: Option<String>,
+    /// Fetched on the first send, as a password manager may ask for a passphrase
+    token: OnceCell<Result<String, String>>,
+    timeout: Duration,
+}
+
+/// An email handed to email_command on stdin, headers included
+struct Email {
+    name: String,
+    to: String,
+    command: String,
+    timeout: Duration,
+}
+
+/// reminder_command, run with TODO_ID and TODO_TEXT set
+struct Shell {
+    command: String,
+    timeout: Duration,
+}
+
+impl Notifier for Desktop {
+    fn name(&self) -> &str {
+        "desktop"
+    }
+
+    fn send(&self, todo: &Todo, at: &str) -> Result<(), String> {
+        let title = format!("⏰ Todo #{}", todo.id);
+        let body = format!("{} ({})", text::escape(&todo.text), at);
+        let mut command = if cfg!(target_os = "macos") {
+            let mut command = Command::new("osascript");
+            // Passed as arguments rather than spliced into the script, so quotes need no escaping
+            command.args(["-e", "on run argv", "-e", "display notification (item 2 of argv) with title (item 1 of argv)"]);
+            command.args(["-e", "end run", &title, &body]);
+            command
+        } else {
+            let mut command = Command::new("notify-send");
+            command.args([&title, &body]);
+            command
+        };
+        run(&mut command, None, self.timeout)
+    }
+}
+
+impl Notifier for Ntfy {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn send(&self, todo: &Todo, at: &str) -> Result<(), String> {
+        let token = match &self.token_command {
+            Some(token_command) => Some(
+                self.token
+                    .get_or_init(|| token_from(token_command))
+                    .as_ref()
+                    .map_err(|e| format!("ntfy_token_command failed: {}", e))?,
+            ),
+            None => None,
+        };
+        let mut command = Command::new("curl");
+        command
+            .args(["--silent", "--show-error", "--fail", "--max-time", &self.timeout.as_secs().to_string()])
+            .args(["--header", &format!("Title: Todo #{}", todo.id), "--header", "Tags: alarm_clock"])
+            .args(["--data-binary", &format!("{} ({})", todo.text, at)]);
+        // The token goes in on stdin so it never shows up in the process list
+        let header = token.as_ref().map(|token| format!("Authorization: Bearer {}\n", token));
+        if header.is_some() {
+            command.args(["--header", "@-"]);
+        }
+        command.arg(&self.url);
+        run(&mut command, header.as_deref(), self.timeout)
+    }
+}
+
+impl Notifier for Email {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn send(&self, todo: &Todo, at: &str) -> Result<(), String> {
+        let text = text::escape(&todo.text);
+        let message = format!("To: {}\nSubject: Reminder: {}\n\n#{} {}\nDue {}\n", self.to, text, todo.id, text, at);
+        run(&mut shell(&self.command), Some(&message), self.timeout)
+    }
+}
+
+impl Notifier for Shell {
+    fn name(&self) -> &str {
+        "reminder_command"
+    }
+
+    fn send(&self, todo: &Todo, _at: &str) -> Result<(), String> {
+        let mut command = shell(&self.command);
+        command.env("TODO_ID", todo.id.to_string()).env("TODO_TEXT", &todo.text);
+        run(&mut command, None, self.timeout)
+    }
+}
+
+/// The notify_targets from the config, warning about and skipping any that can't be read
+pub fn targets(config: &Config) -> Vec<Box<dyn Notifier>> {
+    let timeout = config.reminder_command_timeout();
+    let mut notifiers: Vec<Box<dyn Notifier>> = Vec::new();
+    for target in &config.notify_targets {
+        let notifier: Box<dyn Notifier> = if target == "desktop" {
+            Box::new(Desktop { timeout })
+        } else if let Some(url) = target.strip_prefix("ntfy:").filter(|url| url.starts_with("http")) {
+            Box::new(Ntfy {
+                name: target.clone(),
+                url: url.to_string(),
+                token_command: config.ntfy_token_command.clone(),
+                token: OnceCell::new(),
+                timeout,
+            })
+        } else if let Some(to) = target.strip_prefix("email:").filter(|to| to.contains('@') && !to.contains(char::is_whitespace)) {
+            let command = config.email_command.clone().unwrap_or_else(|| DEFAULT_EMAIL_COMMAND.to_string());
+            Box::new(Email { name: target.clone(), to: to.to_string(), command, timeout })
+        } else {
+            messages::say(Msg::BadNotifyTarget { target });
+            continue;
+        };
+        notifiers.push(notifier);
+    }
+    notifiers
+}
+
+/// reminder_command from the config as a target of its own, for `watch`
+pub fn reminder_command(config: &Config) -> Option<Box<dyn Notifier>> {
+    let command = config.reminder_command.clone()?;
+    Some(Box::new(Shell { command, timeout: config.reminder_command_timeout() }))
+}
+
+/// Send the reminder for `todo` to every target in turn, reporting the ones that fail
+pub fn deliver(notifiers: &[Box<dyn Notifier>], todo: &Todo, at: &str) {
+    for notifier in notifiers {
+        if let Err(e) = notifier.send(todo, at) {
+            messages::say(Msg::NotDelivered { via: notifier.name(), id: todo.id, error: &e });
+        }
+    }
+}
+
+/// The first line `command` prints, for a secret kept out of the config file
+fn token_from(command: &str) -> Result<String, String> {
+    let output = shell(command)
+        .stdin(Stdio::inherit())
+        .stderr(Stdio::inherit())
+        .output()
+        .map_err(|e| format!("could not start it: {}", e))?;
+    if !output.status.success() {
+        return Err(format!("it exited with {}", output.status));
+    }
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    match stdout.lines().next().map(str::trim) {
+        Some(token) if !token.is_empty() => Ok(token.to_string()),
+        _ => Err("it printed nothing".to_string()),
+    }
+}
+
+fn shell(command: &str) -> Command {
+    let (shell, flag) = if cfg!(windows) { ("cmd", "/C") } else { ("sh", "-c") };
+    let mut cmd = Command::new(shell);
+    cmd.arg(flag).arg(command);
+    cmd
+}
+
+/// Run `command` with `input` on its stdin and wait for it, killing it after `timeout`
+fn run(command: &mut Command, input: Option<&str>, timeout: Duration) -> Result<(), String> {
+    let mut child = command
+        .stdin(if input.is_some() { Stdio::piped() } else { Stdio::null() })
+        .spawn()
+        .map_err(|e| format!("could not start it: {}", e))?;
+    if let (Some(input), Some(mut stdin)) = (input, child.stdin.take()) {
+        // Dropped straight after, closing stdin so the command sees the end of its input
+        stdin.write_all(input.as_bytes()).map_err(|e| format!("could not write to it: {}", e))?;
+    }
+    let deadline = Instant::now() + timeout;
+    loop {
+        match child.try_wait() {
+            Ok(Some(status)) if status.success() => return Ok(()),
+            Ok(Some(status)) => return Err(format!("it exited with {}", status)),
+            Ok(None) if Instant::now() >= deadline => {
+                let _ = child.kill();
+                let _ = child.wait();
+                return Err(format!("it was still running after {}s and was stopped", timeout.as_secs()));
+            }
+            Ok(None) => thread::sleep(Duration::from_millis(50)),
+            Err(e) => return Err(e.to_string()),
+        }
+    }
+}
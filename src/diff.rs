@@ -0,0 +1,30 @@
+use console::{colors_enabled, style};
+use similar::{ChangeTag, TextDiff};
+use crate::text;
+
+/// A word-level diff of an edited text: removed words in red and added ones in green, or
+/// marked `[-old-]` and `{+new+}` when colors are off
+pub fn word_diff(old: &str, new: &str) -> String {
+    let color = colors_enabled();
+    let (old, new) = (text::escape(old), text::escape(new));
+    TextDiff::from_words(&*old, &*new)
+        .iter_all_changes()
+        .map(|change| {
+            let value = change.value();
+            match (change.tag(), color) {
+                (ChangeTag::Equal, _) => value.to_string(),
+                // Keep the whitespace between words outside the markers
+                (_, _) if value.trim().is_empty() => value.to_string(),
+                (ChangeTag::Delete, true) => style(value).red().strikethrough().to_string(),
+                (ChangeTag::Insert, true) => style(value).green().to_string(),
+                (ChangeTag::Delete, false) => format!("[-{}-]", value),
+                (ChangeTag::Insert, false) => format!("{{+{}+}}", value),
+            }
+        })
+        .collect()
+}
+
+/// A one-line summary of an edit for places that cannot show colors, like the TUI
+pub fn summary(old: &str, new: &str) -> String {
+    format!("text changed: '{}' → '{}'", text::escape(old), text::escape(new))
+}
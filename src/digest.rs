@@ -0,0 +1,206 @@
+use crate::{dates::Due, goals, messages::{self, Msg}, Todo};
+use chrono::{DateTime, Duration, Local, NaiveDate};
+use clap::ValueEnum;
+use std::{
+    io::{self, Write},
+    process::{Command, Stdio},
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum Period {
+    Day,
+    Week,
+}
+
+impl Period {
+    fn days(self) -> i64 {
+        match self {
+            Period::Day => 1,
+            Period::Week => 7,
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            Period::Day => "Daily",
+            Period::Week => "Weekly",
+        }
+    }
+}
+
+/// Everything a digest shows, computed from the todos as they stand at `now`
+pub struct Report<'a> {
+    pub period: Period,
+    pub today: NaiveDate,
+    pub completed: Vec<&'a Todo>,
+    pub added: Vec<&'a Todo>,
+    pub overdue: Vec<&'a Todo>,
+    pub due_next: Vec<&'a Todo>,
+    /// Progress on each goal in its current period, for `run` to fill in
+    pub goals: Vec<goals::Progress>,
+}
+
+fn parse_timestamp(value: Option<&str>) -> Option<DateTime<Local>> {
+    value
+        .and_then(|v| DateTime::parse_from_rfc3339(v).ok())
+        .map(|dt| dt.with_timezone(&Local))
+}
+
+fn parse_due(todo: &Todo) -> Option<Due> {
+    todo.due_date.as_deref().and_then(Due::parse)
+}
+
+/// Build the report for the period ending at `now`; pure so it can be run against any snapshot
+pub fn build_report(todos: &[Todo], period: Period, now: DateTime<Local>) -> Report<'_> {
+    let since = now - Duration::days(period.days());
+    let today = now.date_naive();
+    let horizon = today + Duration::days(period.days());
+    let in_window = |ts: Option<&str>| parse_timestamp(ts).is_some_and(|dt| dt > since && dt <= now);
+
+    Report {
+        period,
+        today,
+        completed: todos
+            .iter()
+            .filter(|t| t.done && in_window(t.completed_at.as_deref()))
+            .collect(),
+        added: todos
+            .iter()
+            .filter(|t| in_window(t.created_at.as_deref()))
+            .collect(),
+        overdue: todos
+            .iter()
+            .filter(|t| !t.done && parse_due(t).is_some_and(|d| d.is_overdue(now.naive_local())))
+            .collect(),
+        due_next: todos
+            .iter()
+            .filter(|t| {
+                !t.done
+                    && parse_due(t).is_some_and(|d| !d.is_overdue(now.naive_local()) && d.date <= horizon)
+            })
+            .collect(),
+        goals: vec![],
+    }
+}
+
+impl Report<'_> {
+    fn title(&self) -> String {
+        format!("{} todo digest for {}", self.period.label(), self.today)
+    }
+
+    fn sections(&self) -> [(&'static str, &Vec<&Todo>); 4] {
+        [
+            ("Completed", &self.completed),
+            ("Added", &self.added),
+            ("Overdue", &self.overdue),
+            ("Due soon", &self.due_next),
+        ]
+    }
+}
+
+fn describe(todo: &Todo) -> String {
+    let mut line = match todo.due_date.as_deref() {
+        Some(due) => format!("#{} {} (due {})", todo.id, todo.text, due),
+        None => format!("#{} {}", todo.id, todo.text),
+    };
+    if todo.done
+        && let Some(note) = &todo.completion_note
+    {
+        line.push_str(&format!(" — {}", note.replace('\n', "; ")));
+    }
+    line
+}
+
+pub fn render_text(report: &Report) -> String {
+    let mut out = format!("{}\n", report.title());
+    for (heading, items) in report.sections() {
+        out.push_str(&format!("\n{} ({})\n", heading, items.len()));
+        if items.is_empty() {
+            out.push_str("  nothing\n");
+        }
+        for todo in items {
+            out.push_str(&format!("  - {}\n", describe(todo)));
+        }
+    }
+    if !report.goals.is_empty() {
+        out.push_str("\nGoals\n");
+        for line in goals::render(&report.goals) {
+            out.push_str(&format!("  {}\n", line));
+        }
+    }
+    out
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+pub fn render_html(report: &Report) -> String {
+    let mut out = format!(
+        "<html><body>\n<h1>{}</h1>\n",
+        escape_html(&report.title())
+    );
+    for (heading, items) in report.sections() {
+        out.push_str(&format!("<h2>{} ({})</h2>\n", heading, items.len()));
+        if items.is_empty() {
+            out.push_str("<p>nothing</p>\n");
+            continue;
+        }
+        out.push_str("<ul>\n");
+        for todo in items {
+            out.push_str(&format!("<li>{}</li>\n", escape_html(&describe(todo))));
+        }
+        out.push_str("</ul>\n");
+    }
+    if !report.goals.is_empty() {
+        out.push_str("<h2>Goals</h2>\n<pre>\n");
+        for line in goals::render(&report.goals) {
+            out.push_str(&format!("{}\n", escape_html(&line)));
+        }
+        out.push_str("</pre>\n");
+    }
+    out.push_str("</body></html>\n");
+    out
+}
+
+/// Pipe the rendered report through `sendmail -t`
+pub fn send_mail(to: &str, report: &Report, body: &str, html: bool) -> io::Result<()> {
+    let content_type = if html { "text/html" } else { "text/plain" };
+    let message = format!(
+        "To: {}\nSubject: {}\nContent-Type: {}; charset=utf-8\n\n{}",
+        to,
+        report.title(),
+        content_type,
+        body
+    );
+
+    let mut child = Command::new("sendmail")
+        .arg("-t")
+        .stdin(Stdio::piped())
+        .spawn()?;
+    if let Some(stdin) = child.stdin.as_mut() {
+        stdin.write_all(message.as_bytes())?;
+    }
+    let status = child.wait()?;
+    if !status.success() {
+        return Err(io::Error::other(format!("sendmail exited with {}", status)));
+    }
+    Ok(())
+}
+
+/// Render the digest and either print it or mail it
+pub fn run(todos: &[Todo], period: Period, html: bool, mail: Option<String>, goals: Vec<goals::Progress>) {
+    let mut report = build_report(todos, period, Local::now());
+    report.goals = goals;
+    let body = if html { render_html(&report) } else { render_text(&report) };
+    match mail {
+        Some(to) => match send_mail(&to, &report, &body, html) {
+            Ok(()) => messages::say(Msg::DigestSent { to: &to }),
+            Err(e) => messages::say(Msg::DigestNotSent { error: &e }),
+        },
+        None => print!("{}", body),
+    }
+}
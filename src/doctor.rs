@@ -0,0 +1,393 @@
+use crate::{dates::Due, messages::{self, Msg}, priority::Priority, reminders::{self, RepeatRule}, Todo};
+use chrono::{DateTime, Local, NaiveDate, NaiveDateTime, NaiveTime, SecondsFormat, Utc};
+use std::{
+    collections::HashSet,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+const DATE_FORMATS: &[&str] = &["%Y-%m-%d", "%Y/%m/%d", "%Y.%m.%d"];
+const DATETIME_FORMATS: &[&str] = &[
+    "%Y-%m-%d %H:%M",
+    "%Y-%m-%dT%H:%M",
+    "%Y-%m-%d %H:%M:%S",
+    "%Y-%m-%dT%H:%M:%S",
+    "%Y/%m/%d %H:%M",
+];
+/// Time given to reminders that were stored as a bare date
+const DATE_ONLY_REMINDER_TIME: NaiveTime = NaiveTime::from_hms_opt(9, 0, 0).unwrap();
+
+/// A repair doctor knows how to make
+#[derive(Debug, Clone)]
+pub enum Fix {
+    SetDue(Option<String>),
+    SetReminder(Option<String>),
+    ClearRepeat,
+    ClearRecurrence,
+    ClearCompletedAt,
+    ClearPriority,
+    /// Rewrite a SQLite `done` value as the 0 or 1 it was read as
+    SetDone(bool),
+}
+
+impl Fix {
+    /// Fixes that throw data away need the user's confirmation first
+    pub fn is_destructive(&self) -> bool {
+        matches!(self, Fix::SetDue(None) | Fix::SetReminder(None))
+    }
+
+    pub fn apply(&self, todo: &mut Todo) {
+        match self {
+            Fix::SetDue(value) => todo.due_date = value.clone(),
+            Fix::SetReminder(value) => {
+                todo.reminder = value.clone();
+                if value.is_none() {
+                    todo.reminder_repeat = None;
+                }
+            }
+            Fix::ClearRepeat => todo.reminder_repeat = None,
+            Fix::ClearRecurrence => todo.recurrence = None,
+            Fix::ClearCompletedAt => todo.completed_at = None,
+            Fix::ClearPriority => todo.priority = None,
+            Fix::SetDone(done) => todo.done = *done,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Finding {
+    pub id: Option<usize>,
+    pub problem: String,
+    pub fix: Option<Fix>,
+}
+
+impl Finding {
+    fn new(id: usize, problem: String, fix: Option<Fix>) -> Finding {
+        Finding { id: Some(id), problem, fix }
+    }
+
+    pub fn describe(&self) -> String {
+        let subject = match self.id {
+            Some(id) => format!("#{}", id),
+            None => "store".to_string(),
+        };
+        let fix = match &self.fix {
+            Some(Fix::SetDue(Some(value))) | Some(Fix::SetReminder(Some(value))) => {
+                format!(" (fix: rewrite as {})", value)
+            }
+            Some(Fix::SetDue(None)) | Some(Fix::SetReminder(None)) => " (fix: clear it)".to_string(),
+            Some(Fix::ClearRepeat) | Some(Fix::ClearRecurrence) | Some(Fix::ClearCompletedAt) | Some(Fix::ClearPriority) => {
+                " (fix: clear it)".to_string()
+            }
+            Some(Fix::SetDone(done)) => format!(" (fix: rewrite as {})", u8::from(*done)),
+            None => String::new(),
+        };
+        format!("{}: {}{}", subject, self.problem, fix)
+    }
+}
+
+fn normalize_date(value: &str) -> Option<String> {
+    DATE_FORMATS
+        .iter()
+        .find_map(|format| NaiveDate::parse_from_str(value.trim(), format).ok())
+        .map(|date| date.format("%Y-%m-%d").to_string())
+}
+
+/// Canonical stored form of a due date: `YYYY-MM-DD`, or `YYYY-MM-DD HH:MM` when it has a time
+fn normalize_due(value: &str) -> Option<String> {
+    if let Some(dt) = DATETIME_FORMATS
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(value.trim(), format).ok())
+    {
+        return Some(dt.format("%Y-%m-%d %H:%M").to_string());
+    }
+    normalize_date(value)
+}
+
+/// Canonical stored form of a reminder: RFC 3339 to the second, with looser local
+/// date/time spellings read as local time
+fn normalize_reminder(value: &str) -> Option<String> {
+    let value = value.trim();
+    if let Ok(dt) = DateTime::parse_from_rfc3339(value) {
+        return Some(dt.to_rfc3339_opts(SecondsFormat::Secs, false));
+    }
+    if let Some(dt) = DATETIME_FORMATS
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(value, format).ok())
+    {
+        return Some(reminders::to_stored(dt));
+    }
+    let date = NaiveDate::parse_from_str(&normalize_date(value)?, "%Y-%m-%d").ok()?;
+    Some(reminders::to_stored(date.and_time(DATE_ONLY_REMINDER_TIME)))
+}
+
+/// Audit every todo, returning one finding per problem
+pub fn check(todos: &[Todo], now: DateTime<Utc>) -> Vec<Finding> {
+    let mut findings = Vec::new();
+    let mut seen = HashSet::new();
+
+    for todo in todos {
+        if todo.id == 0 || !seen.insert(todo.id) {
+            findings.push(Finding::new(todo.id, "duplicate or zero id".to_string(), None));
+        }
+
+        if let Some(due) = &todo.due_date {
+            match normalize_due(due) {
+                Some(normal) if &normal == due => {}
+                Some(normal) => findings.push(Finding::new(
+                    todo.id,
+                    format!("due date '{}' is not in YYYY-MM-DD [HH:MM] format", due),
+                    Some(Fix::SetDue(Some(normal))),
+                )),
+                None => findings.push(Finding::new(
+                    todo.id,
+                    format!("due date '{}' is not a date", due),
+                    Some(Fix::SetDue(None)),
+                )),
+            }
+        }
+
+        if let Some(reminder) = &todo.reminder {
+            match normalize_reminder(reminder) {
+                Some(normal) if &normal == reminder => {}
+                Some(normal) => findings.push(Finding::new(
+                    todo.id,
+                    format!("reminder '{}' is not an RFC 3339 timestamp", reminder),
+                    Some(Fix::SetReminder(Some(normal))),
+                )),
+                None => findings.push(Finding::new(
+                    todo.id,
+                    format!("reminder '{}' is not a date and time", reminder),
+                    Some(Fix::SetReminder(None)),
+                )),
+            }
+        }
+
+        if let Some(repeat) = &todo.reminder_repeat {
+            if RepeatRule::parse(repeat).is_none() {
+                findings.push(Finding::new(
+                    todo.id,
+                    format!("unknown repeat rule '{}'", repeat),
+                    Some(Fix::ClearRepeat),
+                ));
+            } else if todo.reminder.is_none() {
+                findings.push(Finding::new(
+                    todo.id,
+                    "repeat rule without a reminder".to_string(),
+                    Some(Fix::ClearRepeat),
+                ));
+            }
+        }
+
+        if let Some(recurrence) = &todo.recurrence
+            && RepeatRule::recurrence_from_arg(recurrence).is_err()
+        {
+            findings.push(Finding::new(
+                todo.id,
+                format!("unknown recurrence '{}'", recurrence),
+                Some(Fix::ClearRecurrence),
+            ));
+        }
+
+        if let Some(priority) = &todo.priority
+            && Priority::parse(priority).is_none()
+        {
+            findings.push(Finding::new(
+                todo.id,
+                format!("unknown priority '{}'", priority),
+                Some(Fix::ClearPriority),
+            ));
+        }
+
+        let reminder = todo.reminder.as_deref().and_then(reminders::parse_reminder);
+        if todo.done && reminder.is_some_and(|r| r > now) {
+            findings.push(Finding::new(
+                todo.id,
+                "done but still has a future reminder, which will go off".to_string(),
+                Some(Fix::SetReminder(None)),
+            ));
+        }
+
+        let created = todo
+            .created_at
+            .as_deref()
+            .and_then(|c| DateTime::parse_from_rfc3339(c).ok())
+            .map(|c| c.with_timezone(&Utc));
+        if let (Some(reminder), Some(created)) = (reminder, created)
+            && reminder < created
+            && todo.reminder_repeat.is_none()
+        {
+            findings.push(Finding::new(
+                todo.id,
+                format!("reminder {} is before the todo was created", reminder.with_timezone(&Local).format("%Y-%m-%d %H:%M")),
+                None,
+            ));
+        }
+
+        if !todo.done && todo.completed_at.is_some() {
+            findings.push(Finding::new(
+                todo.id,
+                "pending but has a completion time".to_string(),
+                Some(Fix::ClearCompletedAt),
+            ));
+        }
+    }
+
+    for (child, parent) in due_after_parent(todos) {
+        findings.push(Finding::new(
+            child.id,
+            format!(
+                "due {} but its parent #{} is due {}",
+                child.due_date.as_deref().unwrap_or_default(),
+                parent.id,
+                parent.due_date.as_deref().unwrap_or_default()
+            ),
+            None,
+        ));
+    }
+
+    findings
+}
+
+/// Pending subtasks due after their pending parent, paired with it
+pub fn due_after_parent(todos: &[Todo]) -> Vec<(&Todo, &Todo)> {
+    let deadline = |todo: &Todo| todo.due_date.as_deref().and_then(Due::parse).map(Due::deadline);
+    todos
+        .iter()
+        .filter(|child| !child.done)
+        .filter_map(|child| {
+            let parent = todos.iter().find(|t| Some(t.id) == child.parent && !t.done)?;
+            (deadline(child)? > deadline(parent)?).then_some((child, parent))
+        })
+        .collect()
+}
+
+/// Findings for columns the database is missing compared with the expected schema
+pub fn check_columns(actual: &[String], expected: &[&str]) -> Vec<Finding> {
+    expected
+        .iter()
+        .filter(|column| !actual.iter().any(|a| a == *column))
+        .map(|column| Finding {
+            id: None,
+            problem: format!("database is missing the '{}' column", column),
+            fix: None,
+        })
+        .collect()
+}
+
+/// What a SQLite `done` value other than 0 or 1 plainly means, if anything: NULL, other
+/// numbers and words like "yes" or "false" get written there by other tools. Takes the
+/// value as reported, text in quotes
+pub fn coerce_done(raw: &str) -> Option<bool> {
+    match raw.trim_matches('\'').trim().to_lowercase().as_str() {
+        "null" | "" | "false" | "f" | "no" | "n" | "off" | "pending" => Some(false),
+        "true" | "t" | "yes" | "y" | "on" | "done" | "x" => Some(true),
+        number => number.parse::<f64>().ok().map(|n| n != 0.0),
+    }
+}
+
+/// Findings for the `done` values in the database other than 0 and 1, given as the id of
+/// the row and the value as text
+pub fn check_done_column(dirty: &[(usize, String)]) -> Vec<Finding> {
+    dirty
+        .iter()
+        .map(|(id, raw)| match coerce_done(raw) {
+            Some(done) => Finding::new(
+                *id,
+                format!("done column holds {} rather than {}", raw, u8::from(done)),
+                Some(Fix::SetDone(done)),
+            ),
+            // Any save rewrites it as read, so the fix loses nothing more
+            None => Finding::new(
+                *id,
+                format!("done column holds {}, which is neither true nor false; read as pending", raw),
+                Some(Fix::SetDone(false)),
+            ),
+        })
+        .collect()
+}
+
+/// Set by --quiet to keep `warn_unreadable` silent
+static QUIET: AtomicBool = AtomicBool::new(false);
+/// Whether `warn_unreadable` has already spoken during this run
+static WARNED: AtomicBool = AtomicBool::new(false);
+/// Whether `warn_dirty_done` has, as the database is read several times a run
+static WARNED_DONE: AtomicBool = AtomicBool::new(false);
+
+pub fn set_quiet() {
+    QUIET.store(true, Ordering::Relaxed);
+}
+
+/// Warn, once per run, about rows whose `done` column held something other than 0 or 1,
+/// naming each and what it was read as
+pub fn warn_dirty_done(dirty: &[(usize, String)]) {
+    if dirty.is_empty() || QUIET.load(Ordering::Relaxed) || WARNED_DONE.swap(true, Ordering::Relaxed) {
+        return;
+    }
+    let rows: Vec<String> = dirty
+        .iter()
+        .map(|(id, raw)| {
+            let read = match coerce_done(raw) {
+                Some(true) => "done",
+                Some(false) => "pending",
+                None => "pending, as it means neither",
+            };
+            format!("todo {}'s done column holds {} (read as {})", id, raw, read)
+        })
+        .collect();
+    messages::say(Msg::RunDoctor { problems: &rows.join(", "), fix: true });
+}
+
+/// Warn, once per run, about pending todos whose due date or reminder can't be read.
+/// Views built on dates leave those todos out, so without this a mistyped reminder would
+/// silently never fire
+pub fn warn_unreadable(todos: &[Todo]) {
+    if QUIET.load(Ordering::Relaxed) || WARNED.load(Ordering::Relaxed) {
+        return;
+    }
+    let mut problems = Vec::new();
+    for todo in todos.iter().filter(|t| !t.done) {
+        if let Some(due) = todo.due_date.as_deref()
+            && Due::parse(due).is_none()
+        {
+            problems.push(format!("todo {} has an invalid due date '{}'", todo.id, due));
+        }
+        if let Some(reminder) = todo.reminder.as_deref()
+            && reminders::parse_reminder(reminder).is_none()
+        {
+            problems.push(format!("todo {} has an invalid reminder '{}'", todo.id, reminder));
+        }
+    }
+    if !problems.is_empty() {
+        WARNED.store(true, Ordering::Relaxed);
+        messages::say(Msg::RunDoctor { problems: &problems.join(", "), fix: false });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn common_ways_of_writing_true_and_false_are_understood() {
+        for raw in ["NULL", "''", "'false'", "'No'", "'off'", "'pending'", "0.0", "-0"] {
+            assert_eq!(coerce_done(raw), Some(false), "{}", raw);
+        }
+        for raw in ["2", "-1", "0.5", "'yes'", "'TRUE'", "' Y '", "'done'", "'x'", "'1'"] {
+            assert_eq!(coerce_done(raw), Some(true), "{}", raw);
+        }
+        for raw in ["'maybe'", "a blob", "'1 day'"] {
+            assert_eq!(coerce_done(raw), None, "{}", raw);
+        }
+    }
+
+    #[test]
+    fn each_dirty_done_value_is_a_finding_with_the_value_to_write() {
+        let findings = check_done_column(&[(1, "2".to_string()), (4, "'maybe'".to_string())]);
+        assert_eq!(findings.len(), 2);
+        assert_eq!(findings[0].id, Some(1));
+        assert_eq!(findings[0].problem, "done column holds 2 rather than 1");
+        assert!(matches!(findings[0].fix, Some(Fix::SetDone(true))));
+        assert_eq!(findings[1].problem, "done column holds 'maybe', which is neither true nor false; read as pending");
+        assert!(matches!(findings[1].fix, Some(Fix::SetDone(false))));
+    }
+}
@@ -0,0 +1,227 @@
+use crate::{changes, now_timestamp, text, Todo};
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::{
+    fs::{self, OpenOptions},
+    io::{self, Write},
+    path::PathBuf,
+    sync::OnceLock,
+};
+
+/// One change to one todo, a line of the events log
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Event {
+    /// RFC 3339 timestamp
+    pub at: String,
+    /// The subcommand that made the change, e.g. "done" or "tui"
+    pub command: String,
+    pub id: usize,
+    pub kind: Kind,
+    /// The fields that changed, as they were; every set field of a deleted todo
+    #[serde(default, skip_serializing_if = "Map::is_empty")]
+    pub before: Map<String, Value>,
+    /// The fields that changed, as they are now; every set field of an added todo
+    #[serde(default, skip_serializing_if = "Map::is_empty")]
+    pub after: Map<String, Value>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Kind {
+    Added,
+    Changed,
+    /// A done todo marked pending again
+    Reopened,
+    Deleted,
+}
+
+/// The log for the backend in use, set once at startup
+static PATH: OnceLock<PathBuf> = OnceLock::new();
+
+pub fn set_path(path: PathBuf) {
+    let _ = PATH.set(path);
+}
+
+/// The changes from `before` to `after`, one event per todo added, changed or deleted
+pub fn diff(before: &[Todo], after: &[Todo], command: &str) -> Vec<Event> {
+    let at = now_timestamp();
+    let event = |id, kind, before, after| Event { at: at.clone(), command: command.to_string(), id, kind, before, after };
+    let mut events = Vec::new();
+    let (old_ids, new_ids) = (changes::by_id(before), changes::by_id(after));
+    for old in before {
+        match new_ids.get(&old.id) {
+            Some(&new) if new != old => {
+                let (was, is) = (fields(old), fields(new));
+                let kind = if old.done && !new.done { Kind::Reopened } else { Kind::Changed };
+                events.push(event(old.id, kind, changed(&was, &is), changed(&is, &was)));
+            }
+            Some(_) => {}
+            None => events.push(event(old.id, Kind::Deleted, set_fields(old), Map::new())),
+        }
+    }
+    for new in after.iter().filter(|t| !old_ids.contains_key(&t.id)) {
+        events.push(event(new.id, Kind::Added, Map::new(), set_fields(new)));
+    }
+    events
+}
+
+/// The fields of `a` that `b` doesn't hold the same value for
+fn changed(a: &Map<String, Value>, b: &Map<String, Value>) -> Map<String, Value> {
+    a.iter()
+        .filter(|(key, value)| b.get(*key) != Some(*value))
+        .map(|(key, value)| (key.clone(), value.clone()))
+        .collect()
+}
+
+/// A todo's fields, less `updated_at`: the event's own time already says when it changed
+fn fields(todo: &Todo) -> Map<String, Value> {
+    match serde_json::to_value(todo) {
+        Ok(Value::Object(mut fields)) => {
+            fields.remove("updated_at");
+            fields
+        }
+        _ => Map::new(),
+    }
+}
+
+/// A todo's fields without the empty ones, as a snapshot of what it held
+fn set_fields(todo: &Todo) -> Map<String, Value> {
+    fields(todo)
+        .into_iter()
+        .filter(|(_, value)| !matches!(value, Value::Null | Value::Bool(false)) && value != "" && value != &Value::Array(vec![]))
+        .collect()
+}
+
+/// Append `events` to the log in a single write, so lines from commands run side by side
+/// never interleave. Nothing is written when no log path was set
+pub fn append(events: &[Event]) -> io::Result<()> {
+    let Some(path) = PATH.get() else {
+        return Ok(());
+    };
+    if events.is_empty() {
+        return Ok(());
+    }
+    let mut lines = String::new();
+    for event in events {
+        lines.push_str(&serde_json::to_string(event)?);
+        lines.push('\n');
+    }
+    if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        fs::create_dir_all(dir)?;
+    }
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    file.write_all(lines.as_bytes())
+}
+
+/// Every event in the log, oldest first, and how many lines couldn't be read
+pub fn read() -> io::Result<(Vec<Event>, usize)> {
+    let Some(path) = PATH.get() else {
+        return Ok((vec![], 0));
+    };
+    let data = match fs::read_to_string(path) {
+        Ok(data) => data,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok((vec![], 0)),
+        Err(e) => return Err(e),
+    };
+    let mut events = Vec::new();
+    let mut unreadable = 0;
+    for line in data.lines().filter(|line| !line.trim().is_empty()) {
+        match serde_json::from_str(line) {
+            Ok(event) => events.push(event),
+            Err(_) => unreadable += 1,
+        }
+    }
+    Ok((events, unreadable))
+}
+
+/// An event as one line of a timeline, e.g.
+/// "2025-07-04 09:12  done    #3  done: false → true"
+pub fn describe(event: &Event) -> String {
+    let at = DateTime::parse_from_rfc3339(&event.at)
+        .map(|at| at.with_timezone(&Local).format("%Y-%m-%d %H:%M").to_string())
+        .unwrap_or_else(|_| event.at.clone());
+    let what = match event.kind {
+        Kind::Added => format!("added {}", quoted_text(&event.after)),
+        Kind::Deleted => format!("deleted {}", quoted_text(&event.before)),
+        Kind::Changed => changes(event),
+        Kind::Reopened => format!("reopened; {}", changes(event)),
+    };
+    format!("{}  {:<8}#{:<4} {}", at, event.command, event.id, what)
+}
+
+/// Each field the event changed, e.g. "done: false → true; due_date: none → 2025-07-04"
+fn changes(event: &Event) -> String {
+    event
+        .after
+        .keys()
+        .chain(event.before.keys().filter(|key| !event.after.contains_key(*key)))
+        .map(|key| format!("{}: {} → {}", key, show(event.before.get(key)), show(event.after.get(key))))
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
+/// What `events` did to one todo in a few words, each said once in the order it happened,
+/// e.g. "text edited, due date set"
+pub fn summary(events: &[&Event]) -> String {
+    let mut said: Vec<String> = Vec::new();
+    for event in events {
+        let phrases = match event.kind {
+            Kind::Added => vec!["added".to_string()],
+            Kind::Deleted => vec!["deleted".to_string()],
+            Kind::Reopened => std::iter::once("reopened".to_string()).chain(field_changes(event)).collect(),
+            Kind::Changed => field_changes(event),
+        };
+        for phrase in phrases {
+            if !said.contains(&phrase) {
+                said.push(phrase);
+            }
+        }
+    }
+    said.join(", ")
+}
+
+/// Each field a change event touched, as "due date set", "tags changed" and so on
+fn field_changes(event: &Event) -> Vec<String> {
+    let keys = event.after.keys().chain(event.before.keys().filter(|key| !event.after.contains_key(*key)));
+    keys.filter_map(|key| {
+        let value = event.after.get(key);
+        let set_or_cleared = |what: &str| {
+            let cleared = matches!(value, None | Some(Value::Null));
+            format!("{} {}", what, if cleared { "cleared" } else { "set" })
+        };
+        Some(match key.as_str() {
+            "text" => "text edited".to_string(),
+            // Done the other way is a reopen, which the event's kind already says
+            "done" if value == Some(&Value::Bool(true)) => "marked done".to_string(),
+            "done" | "completed_at" => return None,
+            "due_date" => set_or_cleared("due date"),
+            "reminder" => set_or_cleared("reminder"),
+            "reminder_repeat" => set_or_cleared("repeat"),
+            "recurrence" => set_or_cleared("recurrence"),
+            "priority" => set_or_cleared("priority"),
+            "completion_note" => "note edited".to_string(),
+            "tags" => "tags changed".to_string(),
+            "parent" => match value.and_then(Value::as_u64) {
+                Some(parent) => format!("moved under #{}", parent),
+                None => "moved to the top level".to_string(),
+            },
+            other => format!("{} changed", other.replace('_', " ")),
+        })
+    })
+    .collect()
+}
+
+fn quoted_text(fields: &Map<String, Value>) -> String {
+    format!("'{}'", text::escape(fields.get("text").and_then(Value::as_str).unwrap_or("")))
+}
+
+fn show(value: Option<&Value>) -> String {
+    match value {
+        None | Some(Value::Null) => "none".to_string(),
+        Some(Value::String(s)) => text::escape(s).into_owned(),
+        Some(Value::Array(items)) if items.is_empty() => "none".to_string(),
+        Some(Value::Array(items)) => items.iter().map(|item| show(Some(item))).collect::<Vec<_>>().join(", "),
+        Some(value) => value.to_string(),
+    }
+}
@@ -0,0 +1,727 @@
+use crate::{dates::Due, priority::Priority, reminders, subtasks, valid_tag, Todo, TAG_FORBIDDEN};
+use chrono::{DateTime, Local, NaiveDate, NaiveTime};
+use clap::ValueEnum;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+
+/// File formats `import` reads and `export` writes
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum Format {
+    Markdown,
+    /// Emacs org-mode headings
+    Org,
+    /// Tasks.json from a Google Takeout export; import only
+    GoogleTasks,
+}
+
+/// How `export` sorts todos under headings
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum GroupBy {
+    /// A heading per tag, each todo under its first one
+    Tag,
+}
+
+/// A todo read from an import file, not yet given an id
+pub struct Imported {
+    pub text: String,
+    pub done: bool,
+    pub due_date: Option<String>,
+    pub tags: Vec<String>,
+    pub priority: Option<String>,
+    /// The todo's identity when it was exported by this app
+    pub uuid: Option<String>,
+    /// Kept as the completion note, which is where a todo's notes live
+    pub notes: Option<String>,
+    /// RFC 3339 timestamp, when the file says when it was done
+    pub completed_at: Option<String>,
+    /// Index in the imported todos of the one this is a subtask of
+    pub parent: Option<usize>,
+}
+
+/// What an import file held
+pub struct Parsed {
+    pub todos: Vec<Imported>,
+    pub skipped: Vec<Skipped>,
+    /// Org headings without TODO or DONE, which are passed over without a warning each
+    pub other_headings: usize,
+}
+
+/// Hidden in rendered Markdown, so a re-imported todo keeps its identity
+const UUID_COMMENT_START: &str = "<!-- uuid:";
+const UUID_COMMENT_END: &str = "-->";
+
+/// A line the importer could not use, numbered from 1; for Google Tasks, the task's place
+/// in the file, counting every list's tasks
+pub struct Skipped {
+    pub line: usize,
+    pub reason: String,
+}
+
+/// The todos in an import file; an error when the file as a whole can't be read
+pub fn parse(format: Format, input: &str, headings_as_tags: bool) -> Result<Parsed, String> {
+    match format {
+        Format::Markdown => {
+            let (todos, skipped) = parse_markdown(input, headings_as_tags);
+            Ok(Parsed { todos, skipped, other_headings: 0 })
+        }
+        Format::Org => Ok(parse_org(input, headings_as_tags)),
+        Format::GoogleTasks => parse_google_tasks(input, headings_as_tags),
+    }
+}
+
+pub fn render(format: Format, todos: &[Todo], group_by: Option<GroupBy>) -> String {
+    let render_todos = match format {
+        Format::Markdown => render_markdown,
+        Format::Org => render_org,
+        Format::GoogleTasks => unreachable!("export turns google-tasks away"),
+    };
+    let Some(GroupBy::Tag) = group_by else {
+        return render_todos(todos, 1);
+    };
+    let mut groups: BTreeMap<&str, Vec<Todo>> = BTreeMap::new();
+    let mut untagged = Vec::new();
+    for todo in todos {
+        match todo.tags.first() {
+            Some(tag) => groups.entry(tag).or_default().push(todo.clone()),
+            None => untagged.push(todo.clone()),
+        }
+    }
+    let heading = match format {
+        Format::Markdown => "#",
+        Format::Org => "*",
+        Format::GoogleTasks => unreachable!("export turns google-tasks away"),
+    };
+    let mut out = String::new();
+    let groups = groups.into_iter().chain((!untagged.is_empty()).then_some(("untagged", untagged)));
+    for (name, todos) in groups {
+        out.push_str(&format!("{} {}\n", heading, name));
+        out.push_str(&render_todos(&todos, 2));
+    }
+    out
+}
+
+/// Read `- [ ]` / `- [x]` items with optional trailing `#tags` and `(due: YYYY-MM-DD)`.
+/// An item indented under another is its subtask; headings become tags when asked
+fn parse_markdown(input: &str, headings_as_tags: bool) -> (Vec<Imported>, Vec<Skipped>) {
+    let mut imported = Vec::new();
+    let mut skipped = Vec::new();
+    // Open headings as (level, tag), outermost first
+    let mut headings: Vec<(usize, String)> = Vec::new();
+    // Items later ones may be nested under, as (indent, index in `imported`), outermost first
+    let mut items: Vec<(usize, usize)> = Vec::new();
+
+    for (index, line) in input.lines().enumerate() {
+        let indent = indent_width(line);
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        if let Some((level, title)) = heading(line) {
+            items.clear();
+            headings.retain(|(open, _)| *open < level);
+            let tag = heading_tag(title);
+            if !tag.is_empty() {
+                headings.push((level, tag));
+            }
+            continue;
+        }
+        let Some((done, rest)) = checklist_item(line) else {
+            skipped.push(Skipped { line: index + 1, reason: "not a checklist item".to_string() });
+            continue;
+        };
+        match parse_item(rest) {
+            Ok(mut item) => {
+                if headings_as_tags {
+                    for (_, tag) in headings.iter().rev() {
+                        if !item.tags.contains(tag) {
+                            item.tags.insert(0, tag.clone());
+                        }
+                    }
+                }
+                items.retain(|(open, _)| *open < indent);
+                let parent = items.last().map(|(_, parent)| *parent);
+                items.push((indent, imported.len()));
+                imported.push(Imported { done, parent, ..item });
+            }
+            Err(reason) => skipped.push(Skipped { line: index + 1, reason }),
+        }
+    }
+
+    (imported, skipped)
+}
+
+/// How far a line is indented, with a tab counting as four spaces
+fn indent_width(line: &str) -> usize {
+    line.chars()
+        .take_while(|c| c.is_whitespace())
+        .map(|c| if c == '\t' { 4 } else { 1 })
+        .sum()
+}
+
+/// `(level, title)` for an ATX heading such as `## Errands`
+fn heading(line: &str) -> Option<(usize, &str)> {
+    let level = line.chars().take_while(|&c| c == '#').count();
+    let title = line[level..].strip_prefix(' ')?;
+    (1..=6).contains(&level).then_some((level, title.trim()))
+}
+
+/// A heading turned into a tag: lowercase, spaces as dashes, anything else unusable dropped
+fn heading_tag(title: &str) -> String {
+    title
+        .to_lowercase()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join("-")
+        .chars()
+        .filter(|&c| c != '#' && !TAG_FORBIDDEN.contains(c))
+        .collect()
+}
+
+/// Whether a list item is checked, and the text after its checkbox
+fn checklist_item(line: &str) -> Option<(bool, &str)> {
+    let rest = line
+        .strip_prefix("- ")
+        .or_else(|| line.strip_prefix("* "))
+        .or_else(|| line.strip_prefix("+ "))?
+        .trim_start();
+    let (done, rest) = if let Some(rest) = rest.strip_prefix("[ ]") {
+        (false, rest)
+    } else {
+        (true, rest.strip_prefix("[x]").or_else(|| rest.strip_prefix("[X]"))?)
+    };
+    Some((done, rest.trim()))
+}
+
+/// Split an item into its text, `(due: ...)` annotation, trailing `#tags` and the UUID
+/// comment `render_markdown` adds; the item is left pending
+fn parse_item(rest: &str) -> Result<Imported, String> {
+    let mut text = rest.trim();
+    let mut uuid = None;
+    if let Some(before) = text.strip_suffix(UUID_COMMENT_END)
+        && let Some((before, value)) = before.rsplit_once(UUID_COMMENT_START)
+    {
+        uuid = Some(value.trim().to_string());
+        text = before.trim_end();
+    }
+    let mut due_date = None;
+    if text.ends_with(')')
+        && let Some(start) = text.rfind("(due:")
+    {
+        let value = text[start + "(due:".len()..text.len() - 1].trim();
+        let Some(due) = Due::parse(value) else {
+            return Err(format!("due date '{}' is not in YYYY-MM-DD [HH:MM] format", value));
+        };
+        due_date = Some(due.to_stored());
+        text = text[..start].trim_end();
+    }
+
+    let mut tags = Vec::new();
+    while let Some((before, last)) = text.rsplit_once(char::is_whitespace) {
+        let Some(tag) = last.strip_prefix('#').map(str::to_lowercase) else {
+            break;
+        };
+        if !valid_tag(&tag) {
+            break;
+        }
+        if !tags.contains(&tag) {
+            tags.insert(0, tag);
+        }
+        text = before.trim_end();
+    }
+
+    if text.is_empty() {
+        return Err("item has no text".to_string());
+    }
+    Ok(Imported {
+        text: text.to_string(),
+        done: false,
+        due_date,
+        tags,
+        priority: None,
+        uuid,
+        notes: None,
+        completed_at: None,
+        parent: None,
+    })
+}
+
+/// One `- [ ]` line per todo, in the form `parse_markdown` reads back, with subtasks
+/// indented under their parent. Markdown headings carry their own level, so the items are
+/// the same under one as without
+fn render_markdown(todos: &[Todo], _level: usize) -> String {
+    let mut out = String::new();
+    for row in subtasks::tree(todos, None) {
+        let todo = &todos[row.index];
+        out.push_str(&"  ".repeat(row.depth));
+        out.push_str(if todo.done { "- [x] " } else { "- [ ] " });
+        out.push_str(&todo.text);
+        for tag in &todo.tags {
+            out.push_str(" #");
+            out.push_str(tag);
+        }
+        if let Some(due) = &todo.due_date {
+            out.push_str(&format!(" (due: {})", due));
+        }
+        if !todo.uuid.is_empty() {
+            out.push_str(&format!(" {}{} {}", UUID_COMMENT_START, todo.uuid, UUID_COMMENT_END));
+        }
+        out.push('\n');
+    }
+    out
+}
+
+/// Read `* TODO` and `* DONE` headings, at any level, with their `[#A]` priority, trailing
+/// `:tags:`, `DEADLINE` and `:ID:` property. Other headings are counted and passed over,
+/// or become tags when asked, as headings do in Markdown
+fn parse_org(input: &str, headings_as_tags: bool) -> Parsed {
+    let mut parsed = Parsed { todos: Vec::new(), skipped: Vec::new(), other_headings: 0 };
+    let mut headings: Vec<(usize, String)> = Vec::new();
+    // The todo whose body is being read, and the line its heading is on
+    let mut current: Option<(usize, Imported)> = None;
+
+    for (index, line) in input.lines().enumerate() {
+        let Some((level, title)) = org_heading(line) else {
+            let line = line.trim();
+            if let Some((number, todo)) = &mut current
+                && let Err(reason) = org_body_line(line, todo)
+            {
+                parsed.skipped.push(Skipped { line: *number, reason });
+                current = None;
+            }
+            continue;
+        };
+        parsed.todos.extend(current.take().map(|(_, todo)| todo));
+        headings.retain(|(open, _)| *open < level);
+        let (keyword, rest) = title.split_once(' ').unwrap_or((title, ""));
+        let done = match keyword {
+            "TODO" => false,
+            "DONE" => true,
+            _ => {
+                parsed.other_headings += 1;
+                let tag = heading_tag(title);
+                if !tag.is_empty() {
+                    headings.push((level, tag));
+                }
+                continue;
+            }
+        };
+        let mut item = match org_title(rest) {
+            Ok(item) => item,
+            Err(reason) => {
+                parsed.skipped.push(Skipped { line: index + 1, reason });
+                continue;
+            }
+        };
+        if headings_as_tags {
+            for (_, tag) in headings.iter().rev() {
+                if !item.tags.contains(tag) {
+                    item.tags.insert(0, tag.clone());
+                }
+            }
+        }
+        current = Some((index + 1, Imported { done, ..item }));
+    }
+    parsed.todos.extend(current.map(|(_, todo)| todo));
+    parsed
+}
+
+/// `(level, title)` for a heading such as `** TODO Call the bank`
+fn org_heading(line: &str) -> Option<(usize, &str)> {
+    let level = line.chars().take_while(|&c| c == '*').count();
+    let title = line[level..].strip_prefix(' ')?;
+    (level > 0).then_some((level, title.trim()))
+}
+
+/// Split what follows the keyword into its priority cookie, text and trailing tags
+fn org_title(rest: &str) -> Result<Imported, String> {
+    let mut text = rest.trim();
+    let mut priority = None;
+    if let Some(after) = text.strip_prefix("[#")
+        && let Some((cookie, after)) = after.split_once(']')
+    {
+        priority = match cookie {
+            "A" => Some(Priority::High),
+            "B" => Some(Priority::Medium),
+            "C" => Some(Priority::Low),
+            _ => return Err(format!("priority [#{}] is not A, B or C", cookie)),
+        };
+        text = after.trim_start();
+    }
+    let mut tags = Vec::new();
+    if let Some((before, last)) = text.rsplit_once(char::is_whitespace)
+        && last.len() > 1
+        && last.starts_with(':')
+        && last.ends_with(':')
+    {
+        for tag in last.split(':').filter(|tag| !tag.is_empty()).map(str::to_lowercase) {
+            if !valid_tag(&tag) {
+                return Err(format!("tag '{}' can't contain ():=<>\" or commas", tag));
+            }
+            if !tags.contains(&tag) {
+                tags.push(tag);
+            }
+        }
+        text = before.trim_end();
+    }
+    if text.is_empty() {
+        return Err("heading has no text".to_string());
+    }
+    Ok(Imported {
+        text: text.to_string(),
+        done: false,
+        due_date: None,
+        tags,
+        priority: priority.map(|p| p.as_str().to_string()),
+        uuid: None,
+        notes: None,
+        completed_at: None,
+        parent: None,
+    })
+}
+
+/// Take the due date from a `DEADLINE: <...>` planning line and the UUID from an `:ID:`
+/// property; anything else under a todo's heading is its notes, which aren't imported
+fn org_body_line(line: &str, todo: &mut Imported) -> Result<(), String> {
+    if let Some(id) = line.strip_prefix(":ID:") {
+        todo.uuid = Some(id.trim().to_string());
+    } else if ["CLOSED:", "DEADLINE:", "SCHEDULED:"].iter().any(|keyword| line.starts_with(keyword))
+        && let Some((_, after)) = line.split_once("DEADLINE:")
+        && let Some(value) = after.trim_start().strip_prefix('<')
+        && let Some((value, _)) = value.split_once('>')
+    {
+        // <2025-07-01 Tue 14:00 +1w>: the weekday and any repeater aren't needed
+        let mut parts = value.split_whitespace();
+        let date = parts.next().unwrap_or_default();
+        let time = parts.find(|part| NaiveTime::parse_from_str(part, "%H:%M").is_ok());
+        let value = time.map_or(date.to_string(), |time| format!("{} {}", date, time));
+        let Some(due) = Due::parse(&value) else {
+            return Err(format!("deadline '{}' is not a date", value));
+        };
+        todo.due_date = Some(due.to_stored());
+    }
+    Ok(())
+}
+
+/// An org heading per todo at `level`, in the form `parse_org` reads back, with the
+/// reminder as SCHEDULED, the completion time as CLOSED and the completion note as the body
+fn render_org(todos: &[Todo], level: usize) -> String {
+    let mut out = String::new();
+    let indent = " ".repeat(level + 1);
+    for todo in todos {
+        out.push_str(&format!("{} {} ", "*".repeat(level), if todo.done { "DONE" } else { "TODO" }));
+        let cookie = match todo.priority.as_deref().and_then(Priority::parse) {
+            Some(Priority::High) => Some("A"),
+            Some(Priority::Medium) => Some("B"),
+            Some(Priority::Low) => Some("C"),
+            None => None,
+        };
+        if let Some(cookie) = cookie {
+            out.push_str(&format!("[#{}] ", cookie));
+        }
+        out.push_str(&todo.text);
+        if !todo.tags.is_empty() {
+            out.push_str(&format!(" :{}:", todo.tags.join(":")));
+        }
+        out.push('\n');
+
+        let mut planning = Vec::new();
+        if let Some(completed) = todo.completed_at.as_deref().and_then(|at| DateTime::parse_from_rfc3339(at).ok()) {
+            let completed = completed.with_timezone(&Local).naive_local();
+            planning.push(format!("CLOSED: [{}]", org_timestamp(completed.date(), Some(completed.time()))));
+        }
+        if let Some(due) = todo.due_date.as_deref().and_then(Due::parse) {
+            planning.push(format!("DEADLINE: <{}>", org_timestamp(due.date, due.time)));
+        }
+        if let Some(reminder) = todo.reminder.as_deref().and_then(reminders::parse_reminder) {
+            let reminder = reminder.with_timezone(&Local).naive_local();
+            planning.push(format!("SCHEDULED: <{}>", org_timestamp(reminder.date(), Some(reminder.time()))));
+        }
+        if !planning.is_empty() {
+            out.push_str(&format!("{}{}\n", indent, planning.join(" ")));
+        }
+        if !todo.uuid.is_empty() {
+            out.push_str(&format!("{0}:PROPERTIES:\n{0}:ID: {1}\n{0}:END:\n", indent, todo.uuid));
+        }
+        if let Some(note) = &todo.completion_note {
+            out.push_str(&format!("{}{}\n", indent, note));
+        }
+    }
+    out
+}
+
+/// The inside of an org timestamp, e.g. "2025-07-01 Tue" or "2025-07-01 Tue 14:00"
+fn org_timestamp(date: NaiveDate, time: Option<NaiveTime>) -> String {
+    let day = date.format("%Y-%m-%d %a").to_string();
+    match time {
+        Some(time) => format!("{} {}", day, time.format("%H:%M")),
+        None => day,
+    }
+}
+
+/// Tasks.json as Google Takeout writes it: the task lists, each with its tasks
+#[derive(Deserialize)]
+struct GoogleTaskLists {
+    #[serde(default)]
+    items: Vec<GoogleTaskList>,
+}
+
+#[derive(Deserialize)]
+struct GoogleTaskList {
+    #[serde(default)]
+    title: String,
+    #[serde(default)]
+    items: Vec<GoogleTask>,
+}
+
+#[derive(Deserialize)]
+struct GoogleTask {
+    #[serde(default)]
+    id: String,
+    #[serde(default)]
+    title: String,
+    notes: Option<String>,
+    /// "needsAction" or "completed"
+    #[serde(default)]
+    status: String,
+    /// RFC 3339 timestamp, of which only the date is meaningful
+    due: Option<String>,
+    completed: Option<String>,
+    /// The id of the task this is a subtask of
+    parent: Option<String>,
+    #[serde(default)]
+    deleted: bool,
+}
+
+/// Read a Google Tasks export. Subtasks keep their parent when it is imported too; deleted
+/// tasks are left out, and list names become tags when asked
+fn parse_google_tasks(input: &str, lists_as_tags: bool) -> Result<Parsed, String> {
+    let lists: GoogleTaskLists =
+        serde_json::from_str(input).map_err(|e| format!("not a Google Tasks export: {}", e))?;
+    let mut todos = Vec::new();
+    let mut skipped = Vec::new();
+    // Google's task ids, and the parent id of each imported task, to link subtasks up after
+    let mut indexes: BTreeMap<String, usize> = BTreeMap::new();
+    let mut parents: Vec<Option<String>> = Vec::new();
+    let mut position = 0;
+    for list in lists.items {
+        let tag = heading_tag(&list.title);
+        for task in list.items {
+            position += 1;
+            if task.deleted {
+                continue;
+            }
+            let skip = |reason: &str| Skipped { line: position, reason: format!("task '{}' {}", task.title, reason) };
+            let text = task.title.split_whitespace().collect::<Vec<_>>().join(" ");
+            if text.is_empty() {
+                skipped.push(Skipped { line: position, reason: "task has no title".to_string() });
+                continue;
+            }
+            let due_date = match task.due.as_deref().map(DateTime::parse_from_rfc3339) {
+                // Google keeps due dates at midnight UTC, so the date is read as it is written
+                Some(Ok(due)) => Some(due.date_naive().format("%Y-%m-%d").to_string()),
+                Some(Err(_)) => {
+                    skipped.push(skip("has an unreadable due date"));
+                    continue;
+                }
+                None => None,
+            };
+            let done = task.status == "completed";
+            let completed_at = task
+                .completed
+                .as_deref()
+                .filter(|_| done)
+                .and_then(|at| DateTime::parse_from_rfc3339(at).ok())
+                .map(|at| at.to_rfc3339_opts(chrono::SecondsFormat::Secs, false));
+            if !task.id.is_empty() {
+                indexes.insert(task.id, todos.len());
+            }
+            parents.push(task.parent);
+            todos.push(Imported {
+                text,
+                done,
+                due_date,
+                tags: if lists_as_tags && !tag.is_empty() { vec![tag.clone()] } else { vec![] },
+                priority: None,
+                uuid: None,
+                notes: task.notes.filter(|notes| !notes.trim().is_empty()),
+                completed_at,
+                parent: None,
+            });
+        }
+    }
+    for (todo, parent) in todos.iter_mut().zip(parents) {
+        todo.parent = parent.and_then(|id| indexes.get(&id).copied());
+    }
+    Ok(Parsed { todos, skipped, other_headings: 0 })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn markdown_items_nest_under_the_item_above_them() {
+        let input = "- [ ] trip\n  - [ ] flights\n    - [x] compare prices\n  - [ ] hotel\n\t- [ ] visa\n- [ ] groceries\n";
+        let (todos, skipped) = parse_markdown(input, false);
+        assert!(skipped.is_empty());
+        let parents: Vec<(&str, Option<usize>)> = todos.iter().map(|t| (t.text.as_str(), t.parent)).collect();
+        assert_eq!(
+            parents,
+            [
+                ("trip", None),
+                ("flights", Some(0)),
+                ("compare prices", Some(1)),
+                ("hotel", Some(0)),
+                ("visa", Some(3)),
+                ("groceries", None),
+            ]
+        );
+    }
+
+    #[test]
+    fn a_heading_ends_the_nesting() {
+        let (todos, _) = parse_markdown("- [ ] trip\n# Home\n  - [ ] laundry\n", false);
+        assert_eq!(todos[1].parent, None);
+    }
+
+    #[test]
+    fn markdown_items_keep_their_due_date_tags_and_skipped_line_numbers() {
+        let input = "# Work Stuff\n- [X] report #q3 (due: 2025-07-01 17:00)\nnotes\n- [ ] (due: 2025-07-01)\n- [ ] review (due: 2025-02-30)\n";
+        let (todos, skipped) = parse_markdown(input, true);
+        assert_eq!(todos.len(), 1);
+        assert_eq!(todos[0].text, "report");
+        assert!(todos[0].done);
+        assert_eq!(todos[0].due_date.as_deref(), Some("2025-07-01 17:00"));
+        assert_eq!(todos[0].tags, ["work-stuff", "q3"]);
+        let skipped: Vec<(usize, &str)> = skipped.iter().map(|s| (s.line, s.reason.as_str())).collect();
+        assert_eq!(
+            skipped,
+            [
+                (3, "not a checklist item"),
+                (4, "item has no text"),
+                (5, "due date '2025-02-30' is not in YYYY-MM-DD [HH:MM] format"),
+            ]
+        );
+    }
+
+    #[test]
+    fn rendered_markdown_reads_back_the_same() {
+        let todos = vec![
+            Todo { id: 1, text: "trip".to_string(), tags: vec!["travel".to_string()], uuid: "a".to_string(), ..Default::default() },
+            Todo { id: 2, text: "groceries".to_string(), due_date: Some("2025-07-01".to_string()), ..Default::default() },
+            Todo { id: 3, text: "flights".to_string(), done: true, parent: Some(1), ..Default::default() },
+        ];
+        let rendered = render(Format::Markdown, &todos, None);
+        assert_eq!(
+            rendered,
+            "- [ ] trip #travel <!-- uuid:a -->\n  - [x] flights\n- [ ] groceries (due: 2025-07-01)\n"
+        );
+        let (read, skipped) = parse_markdown(&rendered, false);
+        assert!(skipped.is_empty());
+        let read: Vec<_> = read.iter().map(|t| (t.text.as_str(), t.done, t.due_date.as_deref(), t.tags.clone(), t.uuid.as_deref(), t.parent)).collect();
+        assert_eq!(
+            read,
+            [
+                ("trip", false, None, vec!["travel".to_string()], Some("a"), None),
+                ("flights", true, None, vec![], None, Some(0)),
+                ("groceries", false, Some("2025-07-01"), vec![], None, None),
+            ]
+        );
+    }
+
+    #[test]
+    fn org_todo_headings_keep_their_keyword_priority_tags_and_deadline() {
+        let input = "\
+* Work
+** TODO [#A] Send report :q3:Urgent:
+   DEADLINE: <2025-07-01 Tue 17:00 +1w>
+   :PROPERTIES:
+   :ID: abc
+   :END:
+   some notes
+** DONE Book room
+   CLOSED: [2025-06-30 Mon 10:00] DEADLINE: <2025-06-30 Mon>
+* Notes
+";
+        let parsed = parse_org(input, true);
+        assert!(parsed.skipped.is_empty());
+        assert_eq!(parsed.other_headings, 2);
+        let read: Vec<_> = parsed
+            .todos
+            .iter()
+            .map(|t| (t.text.as_str(), t.done, t.priority.as_deref(), t.tags.clone(), t.due_date.as_deref(), t.uuid.as_deref()))
+            .collect();
+        assert_eq!(
+            read,
+            [
+                ("Send report", false, Some("high"), vec!["work".to_string(), "q3".to_string(), "urgent".to_string()], Some("2025-07-01 17:00"), Some("abc")),
+                ("Book room", true, None, vec!["work".to_string()], Some("2025-06-30"), None),
+            ]
+        );
+    }
+
+    #[test]
+    fn org_headings_that_cant_be_read_are_skipped_by_line() {
+        let input = "* TODO [#Z] odd priority\n* TODO [#A]\n* TODO fine\n  DEADLINE: <2025-02-30 Sun>\n* DONE kept\n";
+        let parsed = parse_org(input, false);
+        let skipped: Vec<(usize, &str)> = parsed.skipped.iter().map(|s| (s.line, s.reason.as_str())).collect();
+        assert_eq!(
+            skipped,
+            [(1, "priority [#Z] is not A, B or C"), (2, "heading has no text"), (3, "deadline '2025-02-30' is not a date")]
+        );
+        assert_eq!(parsed.todos.len(), 1);
+        assert_eq!(parsed.todos[0].text, "kept");
+    }
+
+    #[test]
+    fn rendered_org_reads_back_the_same() {
+        let todos = vec![
+            Todo {
+                id: 1,
+                text: "send report".to_string(),
+                tags: vec!["work".to_string(), "q3".to_string()],
+                due_date: Some("2025-07-01 17:00".to_string()),
+                priority: Some("high".to_string()),
+                uuid: "a".to_string(),
+                ..Default::default()
+            },
+            Todo { id: 2, text: "water plants".to_string(), done: true, completion_note: Some("all of them".to_string()), ..Default::default() },
+        ];
+        let rendered = render(Format::Org, &todos, None);
+        assert_eq!(
+            rendered,
+            "\
+* TODO [#A] send report :work:q3:
+  DEADLINE: <2025-07-01 Tue 17:00>
+  :PROPERTIES:
+  :ID: a
+  :END:
+* DONE water plants
+  all of them
+"
+        );
+        let parsed = parse_org(&rendered, false);
+        assert!(parsed.skipped.is_empty());
+        let read: Vec<_> = parsed.todos.iter().map(|t| (t.text.as_str(), t.done, t.due_date.as_deref(), t.tags.clone(), t.priority.as_deref(), t.uuid.as_deref())).collect();
+        assert_eq!(
+            read,
+            [
+                ("send report", false, Some("2025-07-01 17:00"), vec!["work".to_string(), "q3".to_string()], Some("high"), Some("a")),
+                ("water plants", true, None, vec![], None, None),
+            ]
+        );
+    }
+
+    #[test]
+    fn org_grouped_by_tag_nests_under_a_heading_per_first_tag() {
+        let todos = vec![
+            Todo { id: 1, text: "report".to_string(), tags: vec!["work".to_string(), "q3".to_string()], ..Default::default() },
+            Todo { id: 2, text: "groceries".to_string(), ..Default::default() },
+            Todo { id: 3, text: "laundry".to_string(), tags: vec!["home".to_string()], ..Default::default() },
+        ];
+        assert_eq!(
+            render(Format::Org, &todos, Some(GroupBy::Tag)),
+            "* home\n** TODO laundry :home:\n* work\n** TODO report :work:q3:\n* untagged\n** TODO groceries\n"
+        );
+    }
+}
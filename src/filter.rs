@@ -0,0 +1,427 @@
+use crate::{dates::{self, Due}, priority::Priority, Todo};
+use chrono::{DateTime, Local, NaiveDate, Utc};
+
+/// A parsed filter expression such as `tag:work and not done and due<2025-07-01`
+#[derive(Debug, Clone)]
+pub enum Filter {
+    And(Box<Filter>, Box<Filter>),
+    Or(Box<Filter>, Box<Filter>),
+    Not(Box<Filter>),
+    Tag(String),
+    Done(bool),
+    Text(String),
+    Due(Cmp, NaiveDate),
+    NoDue,
+    Priority(Cmp, Priority),
+    /// Created before this instant; built by `list --stale` rather than parsed
+    CreatedBefore(DateTime<Utc>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Cmp {
+    Eq,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+impl Cmp {
+    fn holds<T: Ord>(self, left: T, right: T) -> bool {
+        match self {
+            Cmp::Eq => left == right,
+            Cmp::Lt => left < right,
+            Cmp::Le => left <= right,
+            Cmp::Gt => left > right,
+            Cmp::Ge => left >= right,
+        }
+    }
+}
+
+impl Filter {
+    /// Shorthand for `tag:<tag>`, used by `--tag`
+    pub fn tag(tag: &str) -> Filter {
+        Filter::Tag(tag.trim_start_matches('#').to_lowercase())
+    }
+
+    pub fn and(self, other: Filter) -> Filter {
+        Filter::And(Box::new(self), Box::new(other))
+    }
+
+    pub fn matches(&self, todo: &Todo) -> bool {
+        match self {
+            Filter::And(a, b) => a.matches(todo) && b.matches(todo),
+            Filter::Or(a, b) => a.matches(todo) || b.matches(todo),
+            Filter::Not(inner) => !inner.matches(todo),
+            Filter::Tag(tag) => todo.tags.iter().any(|t| t.to_lowercase() == *tag),
+            Filter::Done(done) => todo.done == *done,
+            Filter::Text(needle) => todo.text.to_lowercase().contains(needle),
+            Filter::Due(cmp, date) => todo
+                .due_date
+                .as_deref()
+                .and_then(Due::parse)
+                .is_some_and(|due| cmp.holds(due.date, *date)),
+            Filter::NoDue => todo.due_date.is_none(),
+            Filter::Priority(cmp, level) => todo
+                .priority
+                .as_deref()
+                .and_then(Priority::parse)
+                .is_some_and(|p| cmp.holds(p, *level)),
+            Filter::CreatedBefore(cutoff) => todo
+                .created_at
+                .as_deref()
+                .and_then(|c| DateTime::parse_from_rfc3339(c).ok())
+                .is_some_and(|created| created < *cutoff),
+        }
+    }
+}
+
+/// Why an expression could not be parsed, and the character offset of the token at fault
+#[derive(Debug)]
+pub struct FilterError {
+    pub message: String,
+    pub position: usize,
+}
+
+impl FilterError {
+    fn at(token: &Token, message: String) -> FilterError {
+        FilterError { message, position: token.pos }
+    }
+
+    /// The message followed by the expression with a caret under the offending token
+    pub fn render(&self, input: &str) -> String {
+        format!("{}\n    {}\n    {}^", self.message, input, " ".repeat(self.position))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Kind {
+    Word(String),
+    Quoted(String),
+    Op(Cmp),
+    LParen,
+    RParen,
+    End,
+}
+
+#[derive(Debug, Clone)]
+struct Token {
+    kind: Kind,
+    pos: usize,
+}
+
+impl Token {
+    fn describe(&self) -> String {
+        match &self.kind {
+            Kind::Word(w) => format!("'{}'", w),
+            Kind::Quoted(q) => format!("\"{}\"", q),
+            Kind::Op(_) => "a comparison".to_string(),
+            Kind::LParen => "'('".to_string(),
+            Kind::RParen => "')'".to_string(),
+            Kind::End => "the end of the expression".to_string(),
+        }
+    }
+
+    fn is_keyword(&self, keyword: &str) -> bool {
+        matches!(&self.kind, Kind::Word(w) if w.eq_ignore_ascii_case(keyword))
+    }
+}
+
+fn tokenize(input: &str) -> Result<Vec<Token>, FilterError> {
+    let chars: Vec<char> = input.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        let pos = i;
+        let kind = match c {
+            c if c.is_whitespace() => {
+                i += 1;
+                continue;
+            }
+            '(' => {
+                i += 1;
+                Kind::LParen
+            }
+            ')' => {
+                i += 1;
+                Kind::RParen
+            }
+            ':' | '=' => {
+                i += 1;
+                Kind::Op(Cmp::Eq)
+            }
+            '<' | '>' => {
+                let or_equal = chars.get(i + 1) == Some(&'=');
+                i += if or_equal { 2 } else { 1 };
+                Kind::Op(match (c, or_equal) {
+                    ('<', false) => Cmp::Lt,
+                    ('<', true) => Cmp::Le,
+                    ('>', false) => Cmp::Gt,
+                    _ => Cmp::Ge,
+                })
+            }
+            '"' => {
+                let end = chars[i + 1..].iter().position(|&ch| ch == '"').ok_or(FilterError {
+                    message: "unterminated quote".to_string(),
+                    position: pos,
+                })?;
+                let text = chars[i + 1..i + 1 + end].iter().collect();
+                i += end + 2;
+                Kind::Quoted(text)
+            }
+            _ => {
+                let start = i;
+                while i < chars.len() && !chars[i].is_whitespace() && !"():=<>\"".contains(chars[i]) {
+                    i += 1;
+                }
+                Kind::Word(chars[start..i].iter().collect())
+            }
+        };
+        tokens.push(Token { kind, pos });
+    }
+    tokens.push(Token { kind: Kind::End, pos: chars.len() });
+    Ok(tokens)
+}
+
+/// Parse a filter expression. Conditions are `field:value` or `field<value` (also `<=`, `>`,
+/// `>=`) for the fields tag, done, text, due and priority, combined with `and`, `or`, `not`
+/// and parentheses. A bare word matches the todo's text, and bare `done` means `done:true`.
+/// Relative dates such as `today` or `+3d` count from the day the expression is parsed
+pub fn parse(input: &str) -> Result<Filter, FilterError> {
+    parse_on(input, Local::now().date_naive())
+}
+
+/// `parse`, with relative dates counted from `today`
+fn parse_on(input: &str, today: NaiveDate) -> Result<Filter, FilterError> {
+    let tokens = tokenize(input)?;
+    let mut parser = Parser { tokens, next: 0, today };
+    let filter = parser.or()?;
+    let trailing = parser.peek();
+    if trailing.kind != Kind::End {
+        return Err(FilterError::at(
+            trailing,
+            format!("expected 'and', 'or' or the end of the expression, found {}", trailing.describe()),
+        ));
+    }
+    Ok(filter)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    next: usize,
+    today: NaiveDate,
+}
+
+impl Parser {
+    fn peek(&self) -> &Token {
+        &self.tokens[self.next]
+    }
+
+    fn advance(&mut self) -> Token {
+        let token = self.tokens[self.next].clone();
+        if token.kind != Kind::End {
+            self.next += 1;
+        }
+        token
+    }
+
+    fn or(&mut self) -> Result<Filter, FilterError> {
+        let mut filter = self.and()?;
+        while self.peek().is_keyword("or") {
+            self.advance();
+            filter = Filter::Or(Box::new(filter), Box::new(self.and()?));
+        }
+        Ok(filter)
+    }
+
+    fn and(&mut self) -> Result<Filter, FilterError> {
+        let mut filter = self.unary()?;
+        while self.peek().is_keyword("and") {
+            self.advance();
+            filter = Filter::And(Box::new(filter), Box::new(self.unary()?));
+        }
+        Ok(filter)
+    }
+
+    fn unary(&mut self) -> Result<Filter, FilterError> {
+        if self.peek().is_keyword("not") {
+            self.advance();
+            return Ok(Filter::Not(Box::new(self.unary()?)));
+        }
+        self.primary()
+    }
+
+    fn primary(&mut self) -> Result<Filter, FilterError> {
+        let token = self.advance();
+        match &token.kind {
+            Kind::LParen => {
+                let filter = self.or()?;
+                let close = self.advance();
+                if close.kind != Kind::RParen {
+                    return Err(FilterError::at(&close, format!("expected ')', found {}", close.describe())));
+                }
+                Ok(filter)
+            }
+            Kind::Word(w) if token.is_keyword("and") || token.is_keyword("or") => {
+                Err(FilterError::at(&token, format!("expected a condition before '{}'", w)))
+            }
+            Kind::Word(field) if matches!(self.peek().kind, Kind::Op(_)) => {
+                let Kind::Op(cmp) = self.advance().kind else { unreachable!() };
+                let value = self.advance();
+                let text = match &value.kind {
+                    Kind::Word(v) | Kind::Quoted(v) => v.clone(),
+                    _ => {
+                        return Err(FilterError::at(
+                            &value,
+                            format!("expected a value for '{}', found {}", field, value.describe()),
+                        ));
+                    }
+                };
+                condition(field, cmp, &text, &token, &value, self.today)
+            }
+            Kind::Word(w) if w.eq_ignore_ascii_case("done") => Ok(Filter::Done(true)),
+            Kind::Word(w) | Kind::Quoted(w) => Ok(Filter::Text(w.to_lowercase())),
+            _ => Err(FilterError::at(&token, format!("expected a condition, found {}", token.describe()))),
+        }
+    }
+}
+
+fn condition(
+    field: &str,
+    cmp: Cmp,
+    value: &str,
+    field_token: &Token,
+    value_token: &Token,
+    today: NaiveDate,
+) -> Result<Filter, FilterError> {
+    let equality_only = |name: &str| {
+        if cmp == Cmp::Eq {
+            Ok(())
+        } else {
+            Err(FilterError {
+                message: format!("'{}' can only be matched with ':'", name),
+                position: field_token.pos + field.chars().count(),
+            })
+        }
+    };
+    match field.to_lowercase().as_str() {
+        "tag" => {
+            equality_only("tag")?;
+            Ok(Filter::tag(value))
+        }
+        "text" => {
+            equality_only("text")?;
+            Ok(Filter::Text(value.to_lowercase()))
+        }
+        "done" => {
+            equality_only("done")?;
+            match value.to_lowercase().as_str() {
+                "true" | "yes" => Ok(Filter::Done(true)),
+                "false" | "no" => Ok(Filter::Done(false)),
+                _ => Err(FilterError::at(value_token, format!("expected true or false, found '{}'", value))),
+            }
+        }
+        "due" if value.eq_ignore_ascii_case("none") => {
+            equality_only("due:none")?;
+            Ok(Filter::NoDue)
+        }
+        "due" => day(value, today).map(|date| Filter::Due(cmp, date)).ok_or_else(|| {
+            FilterError::at(
+                value_token,
+                format!("expected a date (YYYY-MM-DD, today, tomorrow or a span such as +3d or -1w), found '{}'", value),
+            )
+        }),
+        "priority" => Priority::parse(value)
+            .map(|level| Filter::Priority(cmp, level))
+            .ok_or_else(|| FilterError::at(value_token, format!("expected low, medium or high, found '{}'", value))),
+        _ => Err(FilterError::at(
+            field_token,
+            format!("unknown field '{}' (expected tag, done, text, due or priority)", field),
+        )),
+    }
+}
+
+/// The day a due condition names: a date, today, tomorrow, yesterday, or a span of days or
+/// weeks from today such as +3d, -1w or 2w
+fn day(value: &str, today: NaiveDate) -> Option<NaiveDate> {
+    if let Ok(date) = dates::parse_date(value) {
+        return Some(date);
+    }
+    match value.to_lowercase().as_str() {
+        "today" => Some(today),
+        "tomorrow" => today.succ_opt(),
+        "yesterday" => today.pred_opt(),
+        span => {
+            let (sign, span) = match span.strip_prefix('-') {
+                Some(span) => (-1, span),
+                None => (1, span.strip_prefix('+').unwrap_or(span)),
+            };
+            if !span.ends_with(['d', 'w']) {
+                return None;
+            }
+            today.checked_add_signed(dates::parse_duration(span).ok()? * sign)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(value: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(value, "%Y-%m-%d").unwrap()
+    }
+
+    fn todo(due: Option<&str>, done: bool) -> Todo {
+        Todo { text: "task".to_string(), due_date: due.map(str::to_string), done, ..Default::default() }
+    }
+
+    #[test]
+    fn relative_dates_count_from_today() {
+        let today = date("2026-10-16");
+        let cases = [
+            ("due:today", "2026-10-16"),
+            ("due:tomorrow", "2026-10-17"),
+            ("due:yesterday", "2026-10-15"),
+            ("due:+3d", "2026-10-19"),
+            ("due:3d", "2026-10-19"),
+            ("due:-1w", "2026-10-09"),
+            ("due:+2w", "2026-10-30"),
+            ("due:2026-12-01", "2026-12-01"),
+        ];
+        for (input, expected) in cases {
+            match parse_on(input, today) {
+                Ok(Filter::Due(Cmp::Eq, day)) => assert_eq!(day, date(expected), "{}", input),
+                other => panic!("{} parsed as {:?}", input, other),
+            }
+        }
+    }
+
+    #[test]
+    fn due_soon_and_pending_matches_only_those_due_within_three_days() {
+        let filter = parse_on("due<+3d and not done", date("2026-10-16")).unwrap();
+        assert!(filter.matches(&todo(Some("2026-10-01"), false)));
+        assert!(filter.matches(&todo(Some("2026-10-18 17:00"), false)));
+        assert!(!filter.matches(&todo(Some("2026-10-19"), false)));
+        assert!(!filter.matches(&todo(Some("2026-10-17"), true)));
+        assert!(!filter.matches(&todo(None, false)));
+    }
+
+    #[test]
+    fn due_from_today_on_leaves_out_overdue_todos() {
+        let filter = parse_on("due>=today", date("2026-10-16")).unwrap();
+        assert!(filter.matches(&todo(Some("2026-10-16"), false)));
+        assert!(filter.matches(&todo(Some("2027-01-01"), true)));
+        assert!(!filter.matches(&todo(Some("2026-10-15"), false)));
+    }
+
+    #[test]
+    fn a_due_value_that_is_not_a_date_points_at_itself() {
+        for (input, position) in [("due<+3x", 4), ("due>=soon", 5), ("not due:+d", 8)] {
+            let error = parse_on(input, date("2026-10-16")).unwrap_err();
+            assert!(error.message.starts_with("expected a date"), "{}: {}", input, error.message);
+            assert_eq!(error.position, position, "{}", input);
+        }
+    }
+}
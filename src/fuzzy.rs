@@ -0,0 +1,106 @@
+use crate::Todo;
+
+/// Edit distance between two strings, counted in characters
+pub fn levenshtein(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1; b.len() + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let cost = if ca == *cb { 0 } else { 1 };
+            current[j + 1] = (previous[j] + cost)
+                .min(previous[j + 1] + 1)
+                .min(current[j] + 1);
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+/// Similarity in `0.0..=1.0`, where 1.0 means the lowercased strings are identical
+pub fn similarity(a: &str, b: &str) -> f64 {
+    let a = a.to_lowercase();
+    let b = b.to_lowercase();
+    let longest = a.chars().count().max(b.chars().count());
+    if longest == 0 {
+        return 1.0;
+    }
+    1.0 - levenshtein(&a, &b) as f64 / longest as f64
+}
+
+const WORD_THRESHOLD: f64 = 0.75;
+
+/// Whether any of the hint words closely matches a word in `text`
+fn text_matches(text: &str, hint: &[String]) -> bool {
+    hint.iter().any(|wanted| {
+        text.split_whitespace()
+            .any(|word| similarity(word, wanted) >= WORD_THRESHOLD)
+    })
+}
+
+/// Existing ids closest to the one that wasn't found, nearest first
+pub fn nearest_ids(id: usize, todos: &[Todo], limit: usize) -> Vec<usize> {
+    let mut ids: Vec<usize> = todos.iter().map(|t| t.id).collect();
+    ids.sort_by_key(|candidate| (candidate.abs_diff(id), *candidate));
+    ids.truncate(limit);
+    ids
+}
+
+/// Pending todos whose text fuzzily matches any of the hint words
+pub fn matching_todos<'a>(todos: &'a [Todo], hint: &[String]) -> Vec<&'a Todo> {
+    if hint.is_empty() {
+        return vec![];
+    }
+    todos
+        .iter()
+        .filter(|t| !t.done && text_matches(&t.text, hint))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn todo(id: usize, text: &str, done: bool) -> Todo {
+        Todo { id, text: text.to_string(), done, ..Default::default() }
+    }
+
+    #[test]
+    fn levenshtein_counts_characters() {
+        assert_eq!(levenshtein("", ""), 0);
+        assert_eq!(levenshtein("milk", ""), 4);
+        assert_eq!(levenshtein("milk", "milk"), 0);
+        assert_eq!(levenshtein("milk", "mlik"), 2);
+        assert_eq!(levenshtein("kitten", "sitting"), 3);
+        assert_eq!(levenshtein("café", "cafe"), 1);
+    }
+
+    #[test]
+    fn similarity_ignores_case_and_scales_by_the_longer_word() {
+        assert_eq!(similarity("Milk", "milk"), 1.0);
+        assert_eq!(similarity("", ""), 1.0);
+        assert_eq!(similarity("milk", "silk"), 0.75);
+        assert_eq!(similarity("milk", "bread"), 0.0);
+    }
+
+    #[test]
+    fn nearest_ids_are_closest_first_and_lower_on_a_tie() {
+        let todos: Vec<Todo> = [1, 3, 4, 9].iter().map(|&id| todo(id, "x", false)).collect();
+        assert_eq!(nearest_ids(5, &todos, 3), [4, 3, 1]);
+        assert_eq!(nearest_ids(6, &todos, 2), [4, 3]);
+        assert_eq!(nearest_ids(42, &todos, 10), [9, 4, 3, 1]);
+    }
+
+    #[test]
+    fn matching_todos_are_pending_and_close_to_a_hint_word() {
+        let todos = [todo(1, "buy milk", false), todo(2, "buy silk", true), todo(3, "call mum", false), todo(4, "Milky way", false)];
+        let ids = |hint: &[&str]| -> Vec<usize> {
+            let hint: Vec<String> = hint.iter().map(|word| word.to_string()).collect();
+            matching_todos(&todos, &hint).iter().map(|t| t.id).collect()
+        };
+        assert_eq!(ids(&["milk"]), [1, 4]);
+        assert_eq!(ids(&["MILK", "mum"]), [1, 3, 4]);
+        assert_eq!(ids(&["mlik"]), Vec::<usize>::new());
+        assert_eq!(ids(&[]), Vec::<usize>::new());
+    }
+}
@@ -0,0 +1,168 @@
+use crate::{agenda, events::Event, messages::{self, Msg}, paths, Todo};
+use chrono::{DateTime, Datelike, Local, Months, NaiveDate, Weekday};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::{collections::BTreeMap, fmt, fs, io};
+
+/// How often a goal's count starts over
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Per {
+    Day,
+    Week,
+    Month,
+}
+
+/// So many completions of todos carrying a tag in each day, week or month
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Goal {
+    pub count: u32,
+    pub per: Per,
+}
+
+impl fmt::Display for Goal {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let per = match self.per {
+            Per::Day => "day",
+            Per::Week => "week",
+            Per::Month => "month",
+        };
+        write!(f, "{}/{}", self.count, per)
+    }
+}
+
+/// A goal as typed, e.g. "3/week"
+pub fn parse(value: &str) -> Result<Goal, String> {
+    let invalid = || format!("'{}' isn't a goal; use COUNT/PERIOD, e.g. 3/week, with day, week or month", value);
+    let (count, per) = value.split_once('/').ok_or_else(invalid)?;
+    let count: u32 = count.trim().parse().ok().filter(|&n| n > 0).ok_or_else(invalid)?;
+    let per = match per.trim().to_lowercase().as_str() {
+        "day" | "d" => Per::Day,
+        "week" | "w" | "wk" => Per::Week,
+        "month" | "m" | "mo" => Per::Month,
+        _ => return Err(invalid()),
+    };
+    Ok(Goal { count, per })
+}
+
+/// The first day of the period holding `today`, and the first day of the next
+fn period(per: Per, today: NaiveDate, first_weekday: Weekday) -> (NaiveDate, NaiveDate) {
+    match per {
+        Per::Day => (today, today + chrono::Duration::days(1)),
+        Per::Week => {
+            let start = agenda::week_start(today, first_weekday);
+            (start, start + chrono::Duration::weeks(1))
+        }
+        Per::Month => {
+            let start = today.with_day(1).unwrap_or(today);
+            (start, start + Months::new(1))
+        }
+    }
+}
+
+/// How far a tag's goal has come in the current period
+pub struct Progress {
+    pub tag: String,
+    pub goal: Goal,
+    pub done: usize,
+}
+
+fn local_date(timestamp: &str) -> Option<NaiveDate> {
+    Some(DateTime::parse_from_rfc3339(timestamp).ok()?.with_timezone(&Local).date_naive())
+}
+
+/// Progress on each goal for the period holding `today`. A todo counts once however
+/// often it was reopened and done again, unless `reopens` holds the events log, whose
+/// completions of it are then each counted
+pub fn progress(
+    goals: &BTreeMap<String, Goal>,
+    todos: &[Todo],
+    reopens: Option<&[Event]>,
+    today: NaiveDate,
+    first_weekday: Weekday,
+) -> Vec<Progress> {
+    goals
+        .iter()
+        .map(|(tag, goal)| {
+            let (start, end) = period(goal.per, today, first_weekday);
+            let within = |at: &str| local_date(at).is_some_and(|date| start <= date && date < end);
+            let done = todos
+                .iter()
+                .filter(|todo| todo.tags.contains(tag))
+                .map(|todo| {
+                    let now_done = todo.done && todo.completed_at.as_deref().is_some_and(within);
+                    let logged = reopens.map_or(0, |events| {
+                        events
+                            .iter()
+                            .filter(|e| e.id == todo.id && e.after.get("done") == Some(&Value::Bool(true)) && within(&e.at))
+                            .count()
+                    });
+                    // The log may have started after the todo was done
+                    logged.max(usize::from(now_done))
+                })
+                .sum();
+            Progress { tag: tag.clone(), goal: *goal, done }
+        })
+        .collect()
+}
+
+/// Widest progress bar, in columns
+const BAR_WIDTH: usize = 20;
+
+impl Progress {
+    /// e.g. "#workout  ██████████████░░░░░░  2/3 this week"
+    pub fn render(&self, tag_width: usize) -> String {
+        let target = self.goal.count as usize;
+        let filled = self.done.min(target) * BAR_WIDTH / target;
+        let bar = format!("{}{}", "█".repeat(filled), "░".repeat(BAR_WIDTH - filled));
+        let this = match self.goal.per {
+            Per::Day => "today",
+            Per::Week => "this week",
+            Per::Month => "this month",
+        };
+        let mark = if self.done >= target { " ✓" } else { "" };
+        format!("{:<width$}  {}  {}/{} {}{}", format!("#{}", self.tag), bar, self.done, target, this, mark, width = tag_width + 1)
+    }
+}
+
+/// Each progress line, with the tags padded to line up the bars
+pub fn render(progress: &[Progress]) -> Vec<String> {
+    let width = progress.iter().map(|p| p.tag.chars().count()).max().unwrap_or(0);
+    progress.iter().map(|p| p.render(width)).collect()
+}
+
+/// Every goal set with `goal set`, by tag
+pub fn read() -> BTreeMap<String, Goal> {
+    let Ok(data) = fs::read_to_string(paths::goals_path()) else {
+        return BTreeMap::new();
+    };
+    serde_json::from_str(&data).unwrap_or_else(|e| {
+        messages::say(Msg::Ignoring { what: &paths::goals_path().display(), error: &e });
+        BTreeMap::new()
+    })
+}
+
+/// Set the goal for `tag`, replacing any it had
+pub fn set(tag: &str, goal: Goal) -> io::Result<()> {
+    let mut goals = read();
+    goals.insert(tag.to_string(), goal);
+    write(&goals)
+}
+
+/// Remove the goal for `tag`, returning whether there was one
+pub fn delete(tag: &str) -> io::Result<bool> {
+    let mut goals = read();
+    if goals.remove(tag).is_none() {
+        return Ok(false);
+    }
+    write(&goals)?;
+    Ok(true)
+}
+
+fn write(goals: &BTreeMap<String, Goal>) -> io::Result<()> {
+    let path = paths::goals_path();
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(path, serde_json::to_string_pretty(goals)?)
+}
@@ -0,0 +1,16 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// The lowest id a new todo may get, raised from what the store and the trash have
+/// handed out before so that no id is given out twice, even once its todo is gone
+static FLOOR: AtomicUsize = AtomicUsize::new(1);
+
+/// Keep new todos at `next` or above; the floor is only ever raised
+pub fn set_floor(next: usize) {
+    FLOOR.fetch_max(next, Ordering::Relaxed);
+}
+
+/// The id for a new todo when `highest` is the highest id in use
+pub fn next_after(highest: usize) -> usize {
+    (highest + 1).max(FLOOR.load(Ordering::Relaxed))
+}
+
@@ -0,0 +1,337 @@
+use crate::{messages::{self, Msg}, reminders, Todo};
+use clap::ValueEnum;
+use serde::{de::Error as _, Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::{
+    fs::{self, File},
+    io::{self, Read, Seek, SeekFrom},
+    path::Path,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+/// The JSON store's format: 1 was a bare array of todos, 2 wraps it as
+/// `{ "version": 2, "todos": [...] }` so later formats can be told apart, and 3 gives
+/// todos a `recurrence` apart from their reminder's repeat rule. A store also notes the
+/// `next_id` it has reached, which readers that don't know the field ignore
+pub const VERSION: u32 = 3;
+
+/// How a JSON store lays its todos out: `json` as one document, or `jsonl` as a header
+/// line, `{"format":"jsonl","version":3}`, then a line per todo, so adding one only
+/// appends a line
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum Format {
+    Json,
+    Jsonl,
+}
+
+impl Format {
+    /// The store's file name in this layout
+    pub fn file_name(self) -> &'static str {
+        match self {
+            Format::Json => "todos.json",
+            Format::Jsonl => "todos.jsonl",
+        }
+    }
+
+    /// The layout the store at `path` is written in, going by its extension
+    pub fn of(path: &Path) -> Format {
+        match path.extension() {
+            Some(extension) if extension == "jsonl" => Format::Jsonl,
+            _ => Format::Json,
+        }
+    }
+}
+
+/// The todos a JSON store holds and the format it was written in
+pub struct Stored {
+    pub todos: Vec<Todo>,
+    pub version: u32,
+    /// Bytes of a JSONL store's last line that an append cut off part way, left out of
+    /// `todos`; 0 when there was none
+    pub torn: usize,
+    /// The id the store's next new todo gets at the least, 0 when it doesn't say, so ids
+    /// given to todos since deleted for good aren't handed out again
+    pub next_id: usize,
+}
+
+#[derive(Serialize)]
+struct Envelope<'a> {
+    version: u32,
+    next_id: usize,
+    todos: &'a [Todo],
+}
+
+/// Read a JSON store in any format. One from a newer version is read as well as it can
+/// be, fields this version doesn't know being dropped, so `Stored::version` must be
+/// checked before writing it back. One from an older version is brought up to this one
+pub fn parse(data: &str) -> serde_json::Result<Stored> {
+    let mut stored = parse_as_written(data)?;
+    if stored.version < 3 {
+        reminders::carry_recurrence(&mut stored.todos);
+    }
+    Ok(stored)
+}
+
+/// The todos of a JSON store in any format, as they were written
+fn parse_as_written(data: &str) -> serde_json::Result<Stored> {
+    if let Some(version) = header(data) {
+        return parse_lines(data, version);
+    }
+    match serde_json::from_str(data)? {
+        Value::Array(todos) => {
+            Ok(Stored { todos: serde_json::from_value(Value::Array(todos))?, version: 1, torn: 0, next_id: 0 })
+        }
+        Value::Object(mut envelope) => {
+            let version = envelope
+                .get("version")
+                .and_then(Value::as_u64)
+                .ok_or_else(|| serde_json::Error::custom("no format version"))?;
+            let todos = envelope.remove("todos").ok_or_else(|| serde_json::Error::custom("no todos"))?;
+            let version = u32::try_from(version).unwrap_or(u32::MAX);
+            let next_id = next_id(&envelope);
+            Ok(Stored { todos: serde_json::from_value(todos)?, version, torn: 0, next_id })
+        }
+        _ => Err(serde_json::Error::custom("expected a list of todos")),
+    }
+}
+
+/// The format version a JSONL store's header line gives, if `data` starts with one
+fn header(data: &str) -> Option<u32> {
+    header_fields(data)?.get("version")?.as_u64().map(|v| u32::try_from(v).unwrap_or(u32::MAX))
+}
+
+/// The fields of a JSONL store's header line, if `data` starts with one
+fn header_fields(data: &str) -> Option<Map<String, Value>> {
+    let Value::Object(header) = serde_json::from_str(data.lines().next()?).ok()? else {
+        return None;
+    };
+    (header.get("format").and_then(Value::as_str) == Some("jsonl")).then_some(header)
+}
+
+/// The `next_id` an envelope or header gives, 0 when it gives none
+fn next_id(fields: &Map<String, Value>) -> usize {
+    fields.get("next_id").and_then(Value::as_u64).map_or(0, |id| id as usize)
+}
+
+/// The todos on the lines after a JSONL store's header. A last line with no newline that
+/// doesn't parse is what an append cut off part way leaves, so it is left out rather than
+/// failing the whole store
+fn parse_lines(data: &str, version: u32) -> serde_json::Result<Stored> {
+    let mut todos = Vec::new();
+    let mut torn = 0;
+    let mut lines = data.split_inclusive('\n').enumerate().skip(1).peekable();
+    while let Some((number, line)) = lines.next() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        match serde_json::from_str(line) {
+            Ok(todo) => todos.push(todo),
+            Err(_) if lines.peek().is_none() && !line.ends_with('\n') => torn = line.len(),
+            Err(e) => return Err(serde_json::Error::custom(format!("line {}: {}", number + 1, e))),
+        }
+    }
+    let next_id = header_fields(data).map_or(0, |header| next_id(&header));
+    Ok(Stored { todos, version, torn, next_id })
+}
+
+/// The format a JSON store was written in, without reading its todos
+pub fn version(data: &str) -> Option<u32> {
+    if let Some(version) = header(data) {
+        return Some(version);
+    }
+    match serde_json::from_str(data).ok()? {
+        Value::Array(_) => Some(1),
+        Value::Object(envelope) => envelope.get("version")?.as_u64().map(|v| u32::try_from(v).unwrap_or(u32::MAX)),
+        _ => None,
+    }
+}
+
+/// Just the todos of a JSON store in any format
+pub fn parse_todos(data: &str) -> serde_json::Result<Vec<Todo>> {
+    parse(data).map(|stored| stored.todos)
+}
+
+/// The todos as a JSON store in the current format, laid out as `format`
+pub fn to_string(todos: &[Todo], format: Format) -> serde_json::Result<String> {
+    match format {
+        Format::Json => serde_json::to_string_pretty(&Envelope { version: VERSION, next_id: crate::next_id(todos), todos }),
+        Format::Jsonl => {
+            let mut data =
+                format!("{{\"format\":\"jsonl\",\"version\":{},\"next_id\":{}}}\n", VERSION, crate::next_id(todos));
+            for todo in todos {
+                data.push_str(&line(todo)?);
+            }
+            Ok(data)
+        }
+    }
+}
+
+/// `todo` as a line of a JSONL store
+pub fn line(todo: &Todo) -> serde_json::Result<String> {
+    Ok(serde_json::to_string(todo)? + "\n")
+}
+
+/// Set when the todos loaded differ from how the file holds them, as when ids were
+/// repaired, UUIDs filled in or an older format read, so the next save must write the
+/// whole file even if no command changed anything
+static REWRITE: AtomicBool = AtomicBool::new(false);
+
+pub fn set_rewrite(rewrite: bool) {
+    REWRITE.store(rewrite, Ordering::Relaxed);
+}
+
+pub fn must_rewrite() -> bool {
+    REWRITE.load(Ordering::Relaxed)
+}
+
+/// Whether todos added since loading the store at `path` can just be appended to it: it is
+/// a JSONL store in the current format that ends on a whole line, and holds what was loaded
+pub fn can_append(path: &Path) -> bool {
+    Format::of(path) == Format::Jsonl && !REWRITE.load(Ordering::Relaxed) && appendable(path).unwrap_or(false)
+}
+
+/// Whether the file at `path` starts with a current JSONL header and ends on a whole line,
+/// reading only its ends
+fn appendable(path: &Path) -> io::Result<bool> {
+    let mut file = File::open(path)?;
+    let mut start = [0; 64];
+    let read = file.read(&mut start)?;
+    let Some(end) = start[..read].iter().position(|&b| b == b'\n') else {
+        return Ok(false);
+    };
+    if std::str::from_utf8(&start[..end]).ok().and_then(header) != Some(VERSION) {
+        return Ok(false);
+    }
+    let mut last = [0];
+    file.seek(SeekFrom::End(-1))?;
+    file.read_exact(&mut last)?;
+    Ok(last[0] == b'\n')
+}
+
+/// The id of the todo on the last line of the JSONL store at `path`, the highest on file
+/// since todos are appended in id order
+pub fn last_id(path: &Path) -> io::Result<Option<usize>> {
+    let data = fs::read_to_string(path)?;
+    let todos = data.split_once('\n').map_or("", |(_, todos)| todos);
+    let Some(line) = todos.lines().rev().find(|line| !line.trim().is_empty()) else {
+        return Ok(None);
+    };
+    let todo: Value = serde_json::from_str(line)?;
+    Ok(todo.get("id").and_then(Value::as_u64).map(|id| id as usize))
+}
+
+/// Why a store in format `version` mustn't be overwritten by this version, if it mustn't
+pub fn too_new(version: u32) -> Option<String> {
+    (version > VERSION).then(|| {
+        format!(
+            "file was written by a newer version of todo (format {}; this one writes {}); upgrade todo to change it",
+            version, VERSION
+        )
+    })
+}
+
+/// Whether `warn_too_new` has already spoken during this run
+static WARNED: AtomicBool = AtomicBool::new(false);
+
+/// Warn, once per run, that the store at `path` is from a newer version, so it can be read
+/// but changes to it won't be saved
+pub fn warn_too_new(path: &Path, version: u32) {
+    if let Some(why) = too_new(version)
+        && !WARNED.swap(true, Ordering::Relaxed)
+    {
+        messages::say(Msg::NewerFormat { path, why: &why });
+    }
+}
+
+/// Warn that a partly written last line of the store at `path` was left out
+pub fn warn_torn(path: &Path, bytes: usize) {
+    if bytes > 0 {
+        messages::say(Msg::TornLine { path, bytes });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn todo(id: usize) -> Todo {
+        Todo { id, text: format!("todo {}", id), ..Default::default() }
+    }
+
+    #[test]
+    fn last_id_is_the_last_line_of_a_jsonl_store() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("todos.jsonl");
+        fs::write(&path, to_string(&[], Format::Jsonl).unwrap()).unwrap();
+        assert_eq!(last_id(&path).unwrap(), None);
+        fs::write(&path, to_string(&[todo(1), todo(4)], Format::Jsonl).unwrap() + "\n").unwrap();
+        assert_eq!(last_id(&path).unwrap(), Some(4));
+        assert!(last_id(&dir.path().join("missing.jsonl")).is_err());
+    }
+
+    #[test]
+    fn both_layouts_read_back_what_they_wrote() {
+        let mut full = todo(2);
+        full.due_date = Some("2030-01-01 09:00".to_string());
+        full.reminder = Some("2029-12-31T09:00:00+01:00".to_string());
+        full.reminder_repeat = Some("daily".to_string());
+        full.recurrence = Some("mon,wed,fri".to_string());
+        full.tags = vec!["home".to_string(), "errands".to_string()];
+        full.priority = Some("high".to_string());
+        full.uuid = "6f1c1c52-3c1a-4b8e-9d0e-1f2a3b4c5d6e".to_string();
+        full.parent = Some(1);
+        full.text = "say \"hi\"\nthen leave".to_string();
+        let todos = vec![todo(1), full];
+        for format in [Format::Json, Format::Jsonl] {
+            let data = to_string(&todos, format).unwrap();
+            assert_eq!(version(&data), Some(VERSION));
+            let stored = parse(&data).unwrap();
+            assert_eq!((stored.todos, stored.version, stored.torn), (todos.clone(), VERSION, 0), "{:?}", format);
+        }
+    }
+
+    #[test]
+    fn older_formats_are_brought_up_to_this_one() {
+        let repeating = r#"{"id": 1, "text": "pay rent", "done": false, "due_date": "2030-01-01", "reminder": null, "reminder_repeat": "weekly"}"#;
+        let undated = r#"{"id": 2, "text": "stretch", "done": false, "due_date": null, "reminder": null, "reminder_repeat": "daily"}"#;
+        let stores = [
+            (format!("[{}, {}]", repeating, undated), 1),
+            (format!(r#"{{"version": 2, "todos": [{}, {}]}}"#, repeating, undated), 2),
+            (format!("{{\"format\":\"jsonl\",\"version\":2}}\n{}\n{}\n", repeating, undated), 2),
+        ];
+        for (data, written) in stores {
+            assert_eq!(version(&data), Some(written));
+            let stored = parse(&data).unwrap();
+            assert_eq!(stored.version, written);
+            assert_eq!(stored.todos[0].recurrence.as_deref(), Some("weekly"), "{}", data);
+            assert_eq!(stored.todos[1].recurrence, None, "{}", data);
+            assert_eq!(stored.todos[1].reminder_repeat.as_deref(), Some("daily"));
+            assert_eq!(too_new(stored.version), None);
+        }
+    }
+
+    #[test]
+    fn a_newer_format_is_read_but_not_to_be_written() {
+        let data = r#"{"version": 99, "todos": [{"id": 1, "text": "buy milk", "done": false, "due_date": null, "reminder": null, "colour": "red"}]}"#;
+        let stored = parse(data).unwrap();
+        assert_eq!(stored.version, 99);
+        assert_eq!(stored.todos, vec![Todo { text: "buy milk".to_string(), ..todo(1) }]);
+        assert!(too_new(stored.version).unwrap().contains("format 99"));
+        assert_eq!(too_new(VERSION), None);
+        let jsonl = "{\"format\":\"jsonl\",\"version\":4294967296}\n";
+        assert_eq!(parse(jsonl).unwrap().version, u32::MAX);
+    }
+
+    #[test]
+    fn a_cut_off_last_line_is_left_out() {
+        let whole = to_string(&[todo(1), todo(2)], Format::Jsonl).unwrap();
+        let cut = &whole[..whole.len() - 10];
+        let stored = parse(cut).unwrap();
+        assert_eq!(stored.todos, vec![todo(1)]);
+        assert_eq!(stored.torn, cut.len() - whole.find("\n{\"id\":2").unwrap() - 1);
+        let broken = whole.replacen("{\"id\":1", "{\"id\":", 1);
+        assert!(parse(&broken).err().unwrap().to_string().starts_with("line 2"));
+        assert!(parse(r#"{"todos": []}"#).is_err());
+    }
+}
@@ -0,0 +1,185 @@
+use crate::now_timestamp;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::{fs, io, path::PathBuf, sync::OnceLock};
+
+/// Stands for the id of the last todo added wherever an id is expected
+pub const LAST_ADDED: &str = "@last";
+
+/// The ids of the todos most recently added, completed and changed, kept so scripts
+/// can act on a todo without capturing its id
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq, Eq)]
+pub struct State {
+    pub added: Option<usize>,
+    pub done: Option<usize>,
+    pub modified: Option<usize>,
+    /// When the auto-archive sweep last ran, as an RFC 3339 timestamp
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub swept_at: Option<String>,
+    /// When the overdue nag was last shown, as an RFC 3339 timestamp
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub nagged_at: Option<String>,
+    /// What was answered when offered the todos at the old hard-coded JSON path
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub legacy_json: Option<Answer>,
+}
+
+/// An answer to a question asked only once
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Answer {
+    Accepted,
+    Declined,
+}
+
+impl State {
+    /// This state with the ids set in `newer` replacing its own
+    fn updated(self, newer: State) -> State {
+        State {
+            added: newer.added.or(self.added),
+            done: newer.done.or(self.done),
+            modified: newer.modified.or(self.modified),
+            swept_at: newer.swept_at.or(self.swept_at),
+            nagged_at: newer.nagged_at.or(self.nagged_at),
+            legacy_json: newer.legacy_json.or(self.legacy_json),
+        }
+    }
+}
+
+/// The state file for the backend in use, set once at startup
+static PATH: OnceLock<PathBuf> = OnceLock::new();
+
+pub fn set_path(path: PathBuf) {
+    let _ = PATH.set(path);
+}
+
+pub fn read() -> State {
+    PATH.get()
+        .and_then(|path| fs::read_to_string(path).ok())
+        .and_then(|data| serde_json::from_str(&data).ok())
+        .unwrap_or_default()
+}
+
+/// Fold the ids a command touched into the state file. The file is replaced by a rename so
+/// a concurrent reader never sees it half written
+pub fn record(touched: State) -> io::Result<()> {
+    if touched == State::default() {
+        return Ok(());
+    }
+    write(&read().updated(touched))
+}
+
+/// When the auto-archive sweep last ran, if it ever has
+pub fn swept_at() -> Option<DateTime<Utc>> {
+    parse_stamp(read().swept_at)
+}
+
+/// Note that the auto-archive sweep has just run
+pub fn record_sweep() -> io::Result<()> {
+    record(State { swept_at: Some(now_timestamp()), ..State::default() })
+}
+
+/// When the overdue nag was last shown, if it ever has been
+pub fn nagged_at() -> Option<DateTime<Utc>> {
+    parse_stamp(read().nagged_at)
+}
+
+/// Note that the overdue nag has just been shown
+pub fn record_nag() -> io::Result<()> {
+    record(State { nagged_at: Some(now_timestamp()), ..State::default() })
+}
+
+/// What was answered when offered the todos at the old hard-coded JSON path, if it ever
+/// was offered
+pub fn legacy_json() -> Option<Answer> {
+    read().legacy_json
+}
+
+pub fn record_legacy_json(answer: Answer) -> io::Result<()> {
+    record(State { legacy_json: Some(answer), ..State::default() })
+}
+
+/// Whether at least `every` has passed since `last`, or it never happened; what keeps
+/// the once-in-a-while jobs from running on every command
+pub fn is_due(last: Option<DateTime<Utc>>, every: chrono::Duration) -> bool {
+    last.is_none_or(|at| Utc::now() - at >= every)
+}
+
+fn parse_stamp(stamp: Option<String>) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(&stamp?).ok().map(|at| at.with_timezone(&Utc))
+}
+
+fn write(state: &State) -> io::Result<()> {
+    let Some(path) = PATH.get() else {
+        return Ok(());
+    };
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir.to_path_buf(),
+        _ => PathBuf::from("."),
+    };
+    fs::create_dir_all(&dir)?;
+    let file = tempfile::NamedTempFile::new_in(&dir)?;
+    serde_json::to_writer_pretty(&file, state)?;
+    file.persist(path)?;
+    Ok(())
+}
+
+/// Parse an id argument, resolving `@last` to the last todo added
+pub fn parse_id(value: &str) -> Result<usize, String> {
+    if value.trim() == LAST_ADDED {
+        return read()
+            .added
+            .ok_or_else(|| format!("{} has no todo to refer to yet; add one first", LAST_ADDED));
+    }
+    parse_number(value).map_err(|_| format!("'{}' is not a todo id (a number, or {})", value, LAST_ADDED))
+}
+
+/// Parse an id as it may be copied from the rendered output: "#42", "42:" and " 42 "
+/// all read as 42
+pub fn parse_number(value: &str) -> Result<usize, String> {
+    let number = value.trim();
+    let number = number.strip_prefix('#').unwrap_or(number);
+    let number = number.strip_suffix(':').unwrap_or(number);
+    number.trim().parse().map_err(|_| format!("'{}' is not a todo id", value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    #[test]
+    fn a_job_that_never_ran_is_due() {
+        assert!(is_due(None, Duration::minutes(30)));
+    }
+
+    #[test]
+    fn a_job_is_due_again_once_its_window_has_passed() {
+        let every = Duration::minutes(30);
+        assert!(!is_due(Some(Utc::now()), every));
+        assert!(!is_due(Some(Utc::now() - Duration::minutes(29)), every));
+        assert!(is_due(Some(Utc::now() - every), every));
+        assert!(is_due(Some(Utc::now() - Duration::hours(5)), every));
+    }
+
+    #[test]
+    fn a_time_in_the_future_holds_the_job_back() {
+        assert!(!is_due(Some(Utc::now() + Duration::hours(1)), Duration::minutes(30)));
+    }
+
+    #[test]
+    fn unreadable_stamps_read_as_never() {
+        assert_eq!(parse_stamp(Some("yesterday".to_string())), None);
+        assert!(parse_stamp(Some("2025-07-01T09:00:00+02:00".to_string())).is_some());
+    }
+
+    #[test]
+    fn ids_read_as_they_are_printed() {
+        for value in ["42", "#42", " #42 ", "42:", "#42:", "# 42"] {
+            assert_eq!(parse_number(value), Ok(42), "{:?}", value);
+        }
+        for value in ["", "#", "42::", "##42", "forty-two", "-1"] {
+            assert!(parse_number(value).is_err(), "{:?}", value);
+        }
+    }
+}
@@ -0,0 +1,45 @@
+use std::{
+    fs::{self, File, OpenOptions},
+    io,
+    path::{Path, PathBuf},
+    sync::{Mutex, MutexGuard},
+};
+
+/// The lock files this process holds. A lock file is locked per open file, so taking one
+/// the process already holds would wait on itself; it is handed out again instead
+static HELD: Mutex<Vec<PathBuf>> = Mutex::new(Vec::new());
+
+/// An exclusive lock on a file, released when it is dropped. Only the first of nested
+/// locks on the same file holds it open
+pub struct Lock {
+    held: Option<(File, PathBuf)>,
+}
+
+impl Drop for Lock {
+    fn drop(&mut self) {
+        if let Some((_, path)) = &self.held {
+            held().retain(|p| p != path);
+        }
+    }
+}
+
+fn held() -> MutexGuard<'static, Vec<PathBuf>> {
+    HELD.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Take an exclusive lock on the file at `path`, waiting for any other holder. The lock is
+/// released when the returned lock is dropped
+pub fn exclusive(path: &Path) -> io::Result<Lock> {
+    if held().iter().any(|p| p == path) {
+        return Ok(Lock { held: None });
+    }
+    if let Some(dir) = path.parent()
+        && !dir.as_os_str().is_empty()
+    {
+        fs::create_dir_all(dir)?;
+    }
+    let file = OpenOptions::new().create(true).truncate(false).write(true).open(path)?;
+    file.lock()?;
+    held().push(path.to_path_buf());
+    Ok(Lock { held: Some((file, path.to_path_buf())) })
+}
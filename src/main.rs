@@ -1,25 +1,100 @@
+mod agenda;
+mod aliases;
+mod archive;
+mod backup;
+mod batch;
+mod burndown;
+mod calendar;
+mod cancel;
+mod changes;
+mod commands;
+mod config;
+mod dates;
+mod delivery;
+mod diff;
+mod digest;
+mod doctor;
+mod events;
+mod exchange;
+mod filter;
+mod fuzzy;
+mod goals;
+mod ids;
+mod json_file;
+mod last;
+mod merge;
+mod messages;
+mod lock;
+mod notify;
+mod paths;
+mod plain;
+mod priority;
+mod quick_add;
+mod reminders;
+mod saved_filters;
+mod saved_templates;
+mod shell;
+mod store;
+mod subtasks;
+mod text;
+mod trash;
+mod template;
 mod tui;
+mod watch;
+mod uuid;
+mod urgency;
 
-use clap::{Parser, Subcommand};
-use rusqlite::{params, Connection};
+use clap::{ArgGroup, CommandFactory, FromArgMatches, Parser, Subcommand, ValueEnum};
+use rusqlite::{params, types::ValueRef, Connection, OptionalExtension};
 use serde::{Deserialize, Serialize};
 use std::{
-    fs::{self, File},
+    cell::RefCell,
+    collections::{BTreeMap, HashMap},
+    fs,
     io,
-    path::Path,
+    path::{Path, PathBuf},
+    rc::Rc,
+    time::Duration,
 };
+use commands::WriteMode;
+use messages::Msg;
+use store::{JsonStore, SqliteStore, Store};
+use template::Template;
 use tui::Todo as TuiTodo;
-use std::io::Write;
-use chrono::{Local, NaiveDate, NaiveDateTime, NaiveTime};
+use dates::{DateDisplay, Due};
+use filter::Filter;
+use std::io::{IsTerminal, Write};
+use chrono::{DateTime, Local, NaiveDate, NaiveDateTime, NaiveTime, SecondsFormat, Utc, Weekday};
 use chrono::format::ParseError;
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
 struct Todo {
     id: usize,
     text: String,
     done: bool,
-    due_date: Option<String>,  // ISO 8601 format: YYYY-MM-DD
-    reminder: Option<String>,  // ISO 8601 format: YYYY-MM-DD HH:MM
+    due_date: Option<String>,  // YYYY-MM-DD, or YYYY-MM-DD HH:MM when due at a set time
+    reminder: Option<String>,  // RFC 3339 timestamp with the offset it was set in
+    #[serde(default)]
+    created_at: Option<String>,  // RFC 3339 timestamp
+    #[serde(default)]
+    completed_at: Option<String>,  // RFC 3339 timestamp
+    #[serde(default)]
+    reminder_repeat: Option<String>,  // hourly, daily, weekly, weekdays, weekends or days like mon,wed,fri
+    #[serde(default)]
+    recurrence: Option<String>,  // a repeat rule as for reminder_repeat; completing the todo adds the next one
+    #[serde(default)]
+    completion_note: Option<String>,
+    #[serde(default)]
+    tags: Vec<String>,  // lowercase, without the leading #
+    #[serde(default)]
+    priority: Option<String>,  // low, medium or high
+    #[serde(default)]
+    uuid: String,  // random v4 UUID; unlike the id, the same in every store the todo is copied to
+    #[serde(default)]
+    parent: Option<usize>,  // id of the todo this is a subtask of
+    #[serde(default)]
+    updated_at: Option<String>,  // RFC 3339 timestamp of the last command to change it
 }
 
 #[derive(Parser)]
@@ -29,479 +104,3759 @@ struct Cli {
     #[arg(long, help = "Use SQLite instead of JSON")]
     sqlite: bool,
 
+    /// SQLite database to use instead of todos.db in the data directory; implies --sqlite. `:memory:` starts
+    /// from an empty database that only lives as long as the command, so nothing it
+    /// does is kept once it exits
+    #[arg(long, value_name = "PATH")]
+    db: Option<PathBuf>,
+
+    /// Check the command and show what it would change without saving anything
+    #[arg(long, global = true)]
+    dry_run: bool,
+
+    /// Don't warn about todos whose due date or reminder can't be read
+    #[arg(long, short, global = true)]
+    quiet: bool,
+
+    /// Use this profile's todos and settings instead of the default ones; TODO_PROFILE
+    /// does the same for one shell
+    #[arg(long, global = true, value_name = "NAME")]
+    profile: Option<String>,
+
+    /// Print each SQL statement the command runs to stderr, with how long it took and its
+    /// query plan; SQLite only
+    #[arg(long, global = true)]
+    explain: bool,
+
+    /// Copy the todos at the JSON store's old hard-coded path into the current store, which
+    /// must be empty, without asking; the old file is kept under a new name
+    #[arg(long)]
+    migrate_legacy: bool,
+
     #[command(subcommand)]
-    command: Commands,
+    command: Option<Commands>,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum LastKind {
+    Added,
+    Done,
+    Modified,
+}
+
+#[derive(Subcommand)]
+enum FilterAction {
+    /// Save a filter expression under a name, replacing any saved one of the same name
+    Save {
+        /// Letters, digits, - and _
+        name: String,
+        /// The filter expression (see `search --help`)
+        #[arg(required = true)]
+        expr: Vec<String>,
+    },
+    /// Show every named filter, from the config and saved ones
+    List,
+    /// Delete a saved filter
+    Delete {
+        name: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum TemplateAction {
+    /// Save a todo as a template, replacing any saved one of the same name; its due date
+    /// and reminder are kept relative to the day it was added
+    Save {
+        /// Letters, digits, - and _
+        name: String,
+        /// The todo to copy (an id or @last)
+        #[arg(value_parser = last::parse_id)]
+        id: usize,
+        /// Use this text instead of the todo's, with {placeholders} for `add --var` to fill
+        #[arg(long)]
+        text: Option<String>,
+    },
+    /// Show every saved template
+    List,
+    /// Delete a saved template
+    Delete {
+        name: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum TrashAction {
+    /// Show the deleted todos, oldest deletion first
+    List,
+    /// Put a deleted todo back, under its old id unless another todo has taken it since
+    Restore {
+        /// The id it had when it was deleted
+        #[arg(value_parser = last::parse_number)]
+        id: usize,
+    },
+    /// Delete everything in the trash for good
+    Empty {
+        /// Don't ask first
+        #[arg(long)]
+        force: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum GoalAction {
+    /// Aim for so many completions of todos with a tag each day, week or month,
+    /// replacing any goal the tag had
+    Set {
+        tag: String,
+        /// COUNT/PERIOD, e.g. 3/week; weeks start on first_weekday from the config
+        #[arg(value_parser = goals::parse)]
+        goal: goals::Goal,
+    },
+    /// Show how far each goal has come this day, week or month
+    Status {
+        /// Count a todo reopened and done again once per completion, from the events log
+        #[arg(long)]
+        count_reopens: bool,
+    },
+    /// Drop the goal for a tag
+    Delete {
+        tag: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum ProfileAction {
+    /// Show every profile, marking the one in use
+    List,
+    /// Make a new, empty profile
+    Create {
+        /// Letters, digits, - and _
+        name: String,
+    },
 }
 
 #[derive(Subcommand)]
 enum Commands {
     /// Add a new todo item
+    #[command(visible_alias = "a")]
     Add { 
-        /// The text content of the todo
-        text: Vec<String> 
+        /// The text content of the todo; markers anywhere in it set details instead:
+        /// due:<date> or !<date> (YYYY-MM-DD, today, tomorrow, fri, 3d, 2w), @tag, and
+        /// p1/p2/p3 for high/medium/low priority; write \@tag to keep one as text
+        text: Vec<String>,
+        /// Print only the new todo's id, for capturing in scripts
+        #[arg(long)]
+        print_id: bool,
+        /// Add it as a subtask of this todo (an id or @last)
+        #[arg(long, value_name = "ID", value_parser = last::parse_id)]
+        parent: Option<usize>,
+        /// Set the due date from a phrase in the text such as "tomorrow", "on friday",
+        /// "by the 1st" or "july 3"; the text is kept as typed
+        #[arg(long)]
+        infer_dates: bool,
+        /// When a done todo has the same text, reopen it instead of asking
+        #[arg(long, conflicts_with = "new")]
+        reopen: bool,
+        /// With --reopen, drop the done todo's old due date and reminder
+        #[arg(long, requires = "reopen")]
+        clear_dates: bool,
+        /// When a done todo has the same text, add a new todo without asking
+        #[arg(long)]
+        new: bool,
+        /// Add a todo from this saved template instead of typing its text
+        #[arg(long, value_name = "NAME", conflicts_with_all = ["text", "infer_dates", "reopen", "new"])]
+        template: Option<String>,
+        /// A value for one of the template's placeholders; repeat for each
+        #[arg(long = "var", value_name = "KEY=VALUE", value_parser = commands::add::parse_var, requires = "template")]
+        var: Vec<(String, String)>,
     },
     /// Mark a todo as done
+    #[command(visible_alias = "x")]
+    #[command(group(ArgGroup::new("target").required(true).multiple(true).args(["id", "tag", "filter", "text"])))]
     Done { 
-        /// The ID of the todo to mark as done
-        id: usize 
+        /// The ID of the todo to mark as done, or @last for the last one added
+        #[arg(value_parser = last::parse_id)]
+        id: Option<usize>,
+        /// Mark the pending todo with exactly this text done instead; exits 1 if there is
+        /// none and 2 if there are several
+        #[arg(long, conflicts_with_all = ["id", "tag", "filter"])]
+        text: Option<String>,
+        /// With --text, take the oldest of several matches
+        #[arg(long, requires = "text")]
+        first: bool,
+        /// Mark every pending todo with this tag done instead; repeat to require several
+        #[arg(long, conflicts_with = "id")]
+        tag: Vec<String>,
+        /// Mark every pending todo matching a filter expression done instead (see `search --help`)
+        #[arg(long, value_name = "EXPR", conflicts_with = "id")]
+        filter: Option<String>,
+        /// Don't ask before marking todos matched by --tag or --filter done
+        #[arg(long)]
+        force: bool,
+        /// Record how it was resolved; repeat to add more lines
+        #[arg(short, long = "message", value_name = "MESSAGE")]
+        message: Vec<String>,
+        /// Leave its reminder set, so it still goes off; by default it is cancelled
+        #[arg(long)]
+        keep_reminder: bool,
+        /// Words from the todo's text, used to suggest matches if the id is wrong
+        hint: Vec<String>,
     },
-    /// Edit a todo's text content
+    /// Edit a todo's text, or with --full every field at once
+    #[command(group(ArgGroup::new("target").required(true).args(["id", "all"])))]
     Edit { 
-        /// The ID of the todo to edit
-        id: usize 
+        /// The ID of the todo to edit, or @last for the last one added
+        #[arg(value_parser = last::parse_id)]
+        id: Option<usize>,
+        /// Edit every todo at once, one per line, in $EDITOR
+        #[arg(long)]
+        all: bool,
+        /// Edit the text, due date, reminder, done, tags and notes together, as a TOML
+        /// document in $EDITOR
+        #[arg(long, conflicts_with = "all")]
+        full: bool,
+        /// Words from the todo's text, used to suggest matches if the id is wrong
+        hint: Vec<String>,
     },
     /// Delete a todo
+    #[command(visible_aliases = ["d", "rm"])]
+    #[command(group(ArgGroup::new("target").required(true).args(["id", "done", "all", "before", "filter", "text"])))]
     Delete { 
-        /// The ID of the todo to delete
-        id: usize 
+        /// The ID of the todo to delete, or @last for the last one added
+        #[arg(value_parser = last::parse_id)]
+        id: Option<usize>,
+        /// Delete the pending todo with exactly this text; exits 1 if there is none and 2 if
+        /// there are several
+        #[arg(long)]
+        text: Option<String>,
+        /// With --text, take the oldest of several matches
+        #[arg(long, requires = "text")]
+        first: bool,
+        /// Delete every completed todo
+        #[arg(long)]
+        done: bool,
+        /// Delete every todo
+        #[arg(long)]
+        all: bool,
+        /// Delete pending todos due before this date (YYYY-MM-DD, or the configured date_format)
+        #[arg(long, value_name = "DATE")]
+        before: Option<String>,
+        /// Delete every todo matching a filter expression (see `search --help`)
+        #[arg(long, value_name = "EXPR")]
+        filter: Option<String>,
+        /// Skip the confirmation prompt for --all
+        #[arg(long)]
+        force: bool,
+        /// Words from the todo's text, used to suggest matches if the id is wrong
+        hint: Vec<String>,
+    },
+    /// Print one field of a todo and nothing else, for scripts: id, text, done (true or
+    /// false), due, reminder, repeat (the reminder's), recurrence, tags (one per line),
+    /// priority, note, created_at, completed_at, parent or uuid. Exits with 1 if there is no such todo and with 2,
+    /// printing nothing, if the field isn't set
+    Get {
+        /// The ID of the todo, or @last for the last one added
+        #[arg(value_parser = last::parse_id)]
+        id: usize,
+        /// The field to print
+        field: String,
+    },
+    /// Show every field of a single todo
+    Show {
+        /// The ID of the todo, or @last for the last one added
+        #[arg(value_parser = last::parse_id)]
+        id: usize,
+        /// Show reminder times in UTC instead of local time
+        #[arg(long)]
+        utc: bool,
+        /// Print the todo as stored, as JSON, with its urgency score while it is pending
+        #[arg(long, conflicts_with = "utc")]
+        json: bool,
     },
     /// List all todos
-    List,
+    #[command(visible_alias = "ls")]
+    List {
+        /// Only show todos matching this saved filter (see `filter list`)
+        name: Option<String>,
+        /// Wrap long text under its id instead of truncating it to the terminal width
+        #[arg(long)]
+        wrap: bool,
+        /// Only show todos matching a filter expression (see `search --help`)
+        #[arg(long, value_name = "EXPR")]
+        filter: Option<String>,
+        /// Only show todos with this tag; repeat to require several
+        #[arg(long)]
+        tag: Vec<String>,
+        /// Only show pending todos created longer ago than this, e.g. 30d, 2w or 12h
+        #[arg(long, value_name = "AGE", value_parser = dates::parse_duration)]
+        stale: Option<chrono::Duration>,
+        /// Show dates as "tomorrow", "in 5 days" and so on instead of absolute dates
+        #[arg(long)]
+        relative_dates: bool,
+        /// Show reminder times in UTC instead of local time
+        #[arg(long)]
+        utc: bool,
+        /// Print each todo with this template instead, e.g. "{id}. {text} [{due}]"; `{{` and
+        /// `}}` are literal braces. Placeholders: {id}, {text}, {done}, {status_icon},
+        /// {due}, {due_date}, {due_relative}, {reminder}, {reminder_repeat}, {recurrence},
+        /// {created_at}, {completed_at}, {updated_at}, {age_days}, {completion_note}, {tags}, {priority}, {uuid},
+        /// {progress} (subtasks done, e.g. [2/5]) and {urgency} (see `list --sort urgency`).
+        /// Defaults to list_format from the config
+        #[arg(long, value_name = "TEMPLATE")]
+        format: Option<String>,
+        /// Nest subtasks under their parents and show how many of each parent's are done
+        #[arg(long)]
+        tree: bool,
+        /// Count all of a parent's descendants towards its progress, not just its subtasks
+        #[arg(long, requires = "tree")]
+        recursive: bool,
+        /// Leave out subtasks nested deeper than this; 0 shows only top-level todos
+        #[arg(long, value_name = "N", requires = "tree")]
+        depth: Option<usize>,
+        /// Show overdue todos in a block of their own above the rest
+        #[arg(long)]
+        pin_overdue: bool,
+        /// Print only how many todos the filters let through
+        #[arg(long, conflicts_with_all = ["wrap", "format", "tree", "pin_overdue"])]
+        count: bool,
+        /// Only show completed todos
+        #[arg(long)]
+        done: bool,
+        /// Order to list todos in
+        #[arg(long, value_enum, default_value_t = commands::list::Sort::Id, conflicts_with = "tree")]
+        sort: commands::list::Sort,
+        /// Only show todos changed after this, a date or a span back from now like 2d, each
+        /// with what was done to it
+        #[arg(long, value_name = "WHEN", value_parser = parse_cutoff, conflicts_with_all = ["count", "format", "tree", "pin_overdue", "sort"])]
+        changed_since: Option<DateTime<Utc>>,
+    },
+    /// Exit 0 if any todo passes every check given and 1 otherwise, printing nothing;
+    /// e.g. `todo has --overdue && notify-send "overdue todos"`
+    #[command(group(ArgGroup::new("check").required(true).multiple(true).args(["overdue", "due", "filter", "tag"])))]
+    Has {
+        /// A pending todo is past its deadline
+        #[arg(long)]
+        overdue: bool,
+        /// A pending todo is due on this day: YYYY-MM-DD, today, tomorrow, a weekday or 3d
+        #[arg(long, value_name = "DATE", value_parser = parse_day)]
+        due: Option<NaiveDate>,
+        /// A todo matches a filter expression (see `search --help`)
+        #[arg(long, value_name = "EXPR")]
+        filter: Option<String>,
+        /// A todo has this tag; repeat to require several
+        #[arg(long)]
+        tag: Vec<String>,
+    },
     /// Open the interactive terminal user interface
-    Tui,
+    Tui {
+        /// Start on this saved filter instead of showing every todo
+        #[arg(long, value_name = "NAME")]
+        filter: Option<String>,
+        /// Keep overdue todos at the top of the list
+        #[arg(long)]
+        pin_overdue: bool,
+        /// Print the list and read commands a line at a time, for screen readers and
+        /// terminals the full screen doesn't work in; `p` switches to it from the TUI
+        #[arg(long)]
+        plain: bool,
+        /// Keep the list in step with changes other commands save while it is open; rows
+        /// changed here and not yet saved keep your changes
+        #[arg(long, conflicts_with = "plain")]
+        watch: bool,
+    },
     /// Set a due date for a todo
     Due { 
-        /// The ID of the todo
+        /// The ID of the todo, or @last for the last one added
+        #[arg(value_parser = last::parse_id)]
         id: usize,
-        /// Due date in YYYY-MM-DD format
-        date: String 
+        /// Due date in YYYY-MM-DD format or the configured date_format, or today, tomorrow,
+        /// a weekday, a span like 3d, an ISO week like w29, a quarter like q3, eom or eoy
+        date: String,
+        /// Optional time of day in HH:MM format; without one the todo is due by the end of the day
+        time: Option<String>,
+        /// Don't add the reminder configured by `auto_remind`
+        #[arg(long)]
+        no_auto_remind: bool,
+        /// Make a week like w29 mean its Friday rather than its Monday
+        #[arg(long)]
+        end_of_week: bool,
+        /// Move the due dates of its pending subtasks by as many days, without asking
+        #[arg(long)]
+        cascade: bool,
     },
     /// Set a reminder for a todo
     Remind { 
-        /// The ID of the todo
+        /// The ID of the todo, or @last for the last one added
+        #[arg(value_parser = last::parse_id)]
         id: usize,
-        /// Date in YYYY-MM-DD format
+        /// Date in YYYY-MM-DD format, or the configured date_format
         date: String,
-        /// Time in HH:MM format (24-hour)
-        time: String 
+        /// Time in HH:MM format (24-hour); without one, default_reminder_time from the
+        /// config is used (09:00 unless set)
+        time: Option<String>,
+        /// Keep reminding until the todo is done: hourly, daily, weekly, weekdays, weekends
+        /// or days such as mon,wed,fri
+        #[arg(long, value_parser = reminders::RepeatRule::from_arg)]
+        repeat: Option<reminders::RepeatRule>,
     },
     /// List upcoming reminders
-    Upcoming,
+    Upcoming {
+        /// Only show the next reminder
+        #[arg(long)]
+        next: bool,
+        /// One plain line per reminder ("14:30 call dentist"), for status bars and prompts.
+        /// Prints nothing and exits with 1 when there are no upcoming reminders
+        #[arg(long)]
+        short: bool,
+        /// Template for --short lines, with {time}, {date}, {text} and {id} placeholders
+        /// (`{{` and `}}` for literal braces); implies --short
+        #[arg(long, value_name = "TEMPLATE")]
+        format: Option<String>,
+        /// Show dates as "tomorrow", "in 5 days" and so on instead of absolute dates
+        #[arg(long)]
+        relative_dates: bool,
+        /// Show reminder times in UTC instead of local time
+        #[arg(long)]
+        utc: bool,
+        /// Most urgent todo first (see `list --sort urgency`) rather than soonest reminder
+        #[arg(long)]
+        by_urgency: bool,
+    },
     /// Clear a reminder from a todo
+    #[command(visible_alias = "cr")]
     ClearReminder {
-        /// The ID of the todo
-        id: usize
+        /// The ID of the todo, or @last for the last one added
+        #[arg(value_parser = last::parse_id)]
+        id: usize,
+        /// Words from the todo's text, used to suggest matches if the id is wrong
+        hint: Vec<String>,
+    },
+    /// Add tags to a todo, or remove them with --remove
+    Tag {
+        /// The ID of the todo, or @last for the last one added
+        #[arg(value_parser = last::parse_id)]
+        id: usize,
+        /// Tags to add or remove; a leading # is optional
+        #[arg(required = true)]
+        tags: Vec<String>,
+        /// Remove the tags instead of adding them
+        #[arg(long)]
+        remove: bool,
+    },
+    /// Set or clear a todo's priority
+    #[command(group(ArgGroup::new("change").required(true).args(["level", "clear"])))]
+    Priority {
+        /// The ID of the todo, or @last for the last one added
+        #[arg(value_parser = last::parse_id)]
+        id: usize,
+        /// The new priority
+        #[arg(value_enum)]
+        level: Option<priority::Priority>,
+        /// Remove the priority
+        #[arg(long)]
+        clear: bool,
+    },
+    /// Make a todo recur, or stop it recurring with --clear
+    ///
+    /// Completing a recurring todo that has a due date adds its next occurrence, due on
+    /// the next day the rule lands on; a reminder moves along with it. This is apart
+    /// from `remind --repeat`, which only repeats the reminder
+    #[command(group(ArgGroup::new("change").required(true).args(["rule", "clear"])))]
+    Repeat {
+        /// The ID of the todo, or @last for the last one added
+        #[arg(value_parser = last::parse_id)]
+        id: usize,
+        /// daily, weekly, weekdays, weekends or days such as mon,wed,fri
+        #[arg(value_parser = reminders::RepeatRule::recurrence_from_arg)]
+        rule: Option<reminders::RepeatRule>,
+        /// Stop the todo recurring
+        #[arg(long)]
+        clear: bool,
+    },
+    /// Print reminders that have come due since the last run, for running from cron, and
+    /// send them to notify_targets from the config
+    Notify {
+        /// Let this todo's reminder fire again, e.g. after snoozing it
+        #[arg(long, value_name = "ID", value_parser = last::parse_id)]
+        reset: Option<usize>,
+    },
+    /// Keep running and print reminders as they come due; shares `notify`'s record of what
+    /// was already announced. Sends each one to notify_targets and runs reminder_command
+    Watch {
+        /// Seconds between checks
+        #[arg(long, default_value_t = 30, value_parser = clap::value_parser!(u64).range(1..))]
+        interval: u64,
+        /// Ring the terminal bell too, e.g. over SSH where there are no desktop notifications
+        #[arg(long)]
+        bell: bool,
+    },
+    /// Print the id of the todo last added, completed or changed
+    Last {
+        #[arg(value_enum)]
+        which: LastKind,
+    },
+    /// List, restore and empty deleted todos
+    Trash {
+        #[command(subcommand)]
+        action: TrashAction,
+    },
+    /// Set per-tag goals, like 3 workouts a week, and see how they're going
+    Goal {
+        #[command(subcommand)]
+        action: GoalAction,
+    },
+    /// Show the changes recorded in the events log (see events_log in the config), oldest first
+    History {
+        /// Only show the changes to this todo
+        #[arg(value_parser = last::parse_number)]
+        id: Option<usize>,
+    },
+    /// List the aliases defined under [alias] in the config
+    Alias,
+    /// Show which backend is in use and where its files are
+    Where,
+    /// Show which backend is in use and whether --db, --sqlite or the config chose it
+    Backend,
+    /// Rewrite the JSON store in another layout: json, one document, or jsonl, a line per
+    /// todo that `add` only appends to
+    Migrate {
+        #[arg(long, value_enum)]
+        to: json_file::Format,
+    },
+    /// Save, list and delete named filters
+    Filter {
+        #[command(subcommand)]
+        action: FilterAction,
+    },
+    /// Save, list and delete todo templates, for `add --template`
+    Template {
+        #[command(subcommand)]
+        action: TemplateAction,
+    },
+    /// List and create profiles, separate todo lists that each have their own settings
+    Profile {
+        #[command(subcommand)]
+        action: ProfileAction,
+    },
+    /// Find todos matching a filter expression
+    ///
+    /// Conditions are tag:NAME, text:WORDS, done:true|false, priority:LEVEL and
+    /// due:DATE (or due:none); due and priority also take <, <=, > and >=. A DATE is
+    /// YYYY-MM-DD, today, tomorrow, yesterday or a span from today such as +3d or -1w.
+    /// Combine them with and, or, not and parentheses, e.g.
+    /// "tag:work and not done and due<+3d". A bare word matches the text.
+    Search {
+        /// The filter expression
+        #[arg(required = true)]
+        query: Vec<String>,
+    },
+    /// Find todos whose text or completion note holds a phrase, ignoring case, showing
+    /// the matching note lines with a line either side
+    Grep {
+        /// The phrase to look for
+        #[arg(value_parser = clap::builder::NonEmptyStringValueParser::new())]
+        pattern: String,
+        /// Print only the ids of the matching todos, one per line, e.g. to pipe into `todo done`
+        #[arg(long)]
+        ids_only: bool,
+    },
+    /// Show counts and how long pending todos have been waiting
+    Stats {
+        /// Chart how many todos were open at the end of each recent day
+        #[arg(long)]
+        burndown: bool,
+        /// Only count todos with this tag; repeat to require several
+        #[arg(long, requires = "burndown")]
+        tag: Vec<String>,
+        /// How many days the burndown covers, ending today; at most ten years
+        #[arg(long, default_value_t = 14, value_parser = clap::value_parser!(u32).range(1..=3650), requires = "burndown")]
+        days: u32,
+        /// Print the burndown as a chart, or as JSON for plotting elsewhere
+        #[arg(long, value_enum, default_value_t = burndown::Output::Text, requires = "burndown")]
+        output: burndown::Output,
+    },
+    /// Show overdue todos and those due today
+    Today,
+    /// Give pending todos without a due date a day this week or next, one key press each:
+    /// 1 to 7 for the days of this week, n for next week, s to skip and q to stop
+    Plan,
+    /// Show pending todos due this week, day by day
+    Week {
+        /// Show the week this many weeks ahead (or back, if negative), up to 100 years either way
+        #[arg(long, default_value_t = 0, allow_negative_numbers = true, value_parser = clap::value_parser!(i64).range(-5218..=5218))]
+        offset: i64,
+    },
+    /// List the days from today on with more pending todos due than a limit, with the
+    /// todos due on each and the `todo due` commands that would spread them out
+    Conflicts {
+        /// Most pending todos a day should have due
+        #[arg(long, default_value_t = 5, value_parser = clap::value_parser!(u32).range(1..))]
+        max_per_day: u32,
+        /// Leave Saturdays and Sundays out, neither checking them nor moving todos onto them
+        #[arg(long)]
+        weekdays_only: bool,
+        /// Print the days as JSON, with each suggested move
+        #[arg(long)]
+        json: bool,
+    },
+    /// Show a month as a calendar with how many pending todos are due each day
+    Cal {
+        /// The month to show, as YYYY-MM; this month if left out
+        #[arg(value_parser = parse_month)]
+        month: Option<NaiveDate>,
+        /// Show this week as a single row of days instead
+        #[arg(long, conflicts_with = "month")]
+        week: bool,
+    },
+    /// Add todos from a file, such as a Markdown checklist
+    Import {
+        /// The file to read
+        path: PathBuf,
+        /// The file's format
+        #[arg(long, value_enum, default_value = "markdown")]
+        format: exchange::Format,
+        /// Tag items with the headings, or Google Tasks list names, they appear under
+        #[arg(long)]
+        headings_as_tags: bool,
+        /// Import the tasks of a Google Tasks export that can be read even when some can't;
+        /// without it such an export imports nothing
+        #[arg(long)]
+        partial: bool,
+        /// Import due dates however far in the past or future they are, rather than
+        /// refusing the file (see date_window_past_years in the config)
+        #[arg(long)]
+        no_date_sanity: bool,
+    },
+    /// Write every todo out in another format, such as a Markdown checklist
+    Export {
+        /// The format to write
+        #[arg(long, value_enum, default_value = "markdown")]
+        format: exchange::Format,
+        /// Write to this file instead of standard output
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+        /// Put the todos under headings, e.g. one per tag
+        #[arg(long, value_enum)]
+        group_by: Option<exchange::GroupBy>,
+    },
+    /// Package the whole store into a portable .tar.gz bundle
+    Backup {
+        /// Where to write the bundle
+        path: PathBuf
+    },
+    /// Restore the store from a bundle created by `backup`
+    Restore {
+        /// The bundle to restore from
+        path: PathBuf,
+        /// Overwrite existing todos (a safety copy is made first)
+        #[arg(long)]
+        force: bool
+    },
+    /// Show what has been added, removed and changed since a backup: one of the safety
+    /// copies `restore` makes, or a bundle made by `backup`
+    DiffBackup {
+        /// Which safety copy, counting back from 1 for the most recent
+        #[arg(default_value_t = 1, conflicts_with = "bundle")]
+        n: usize,
+        /// Compare with this bundle instead
+        #[arg(long, value_name = "PATH")]
+        bundle: Option<PathBuf>,
+        /// Print the differences as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Merge another JSON store, such as todos.json from another machine, into this one
+    ///
+    /// Todos are matched by when they were created. Identical ones are skipped, ones only
+    /// the other store has are added, and for ones changed on both sides you are asked
+    /// which copy to keep unless a strategy is given.
+    #[command(group(ArgGroup::new("strategy").args(["ours", "theirs", "newest"])))]
+    Merge {
+        /// The other store's todos.json
+        path: PathBuf,
+        /// Keep this store's copy of todos changed on both sides
+        #[arg(long)]
+        ours: bool,
+        /// Take the other store's copy of todos changed on both sides
+        #[arg(long)]
+        theirs: bool,
+        /// Take whichever copy was created or completed more recently
+        #[arg(long)]
+        newest: bool,
+    },
+    /// Type commands at a prompt without `todo` in front, the todos read once and saved
+    /// after each change; exit, quit or Ctrl-D leaves
+    Shell,
+    /// Check the store for malformed or inconsistent data
+    Doctor {
+        /// Repair what can be repaired (asks before clearing unreadable values)
+        #[arg(long)]
+        fix: bool,
+    },
+    /// Summarize what was completed, added, overdue and coming up
+    Digest {
+        /// How far back (and ahead) the report looks
+        #[arg(long, value_enum, default_value = "week")]
+        period: digest::Period,
+        /// Render the report as HTML instead of plain text
+        #[arg(long)]
+        html: bool,
+        /// Mail the report to this address via sendmail instead of printing it
+        #[arg(long)]
+        mail: Option<String>,
     },
 }
 
-const FILE_PATH: &str = "/home/varun/Projects/todo/todos.json";
+/// `--db` value for a database that lives in memory for the life of the process
+const MEMORY_DB: &str = ":memory:";
+/// What `--profile` and `profile list` call the profile that uses the original paths
+const DEFAULT_PROFILE: &str = "default";
+/// Version of the todo record layout, recorded in backup manifests
+const SCHEMA_VERSION: u32 = 12;
 
 fn main() {
-    let cli = Cli::parse();
+    let args: Vec<String> = std::env::args().collect();
+    // Every path, the config's included, depends on the profile, so it is settled first
+    if let Err(e) = paths::set_profile(peek_profile(&args)) {
+        messages::say(Msg::Failed { error: &e });
+        std::process::exit(1);
+    }
+    cancel::install();
+    let config = config::load();
+    config.apply_date_format();
+    messages::set_emoji(config.emoji.unwrap_or(true));
+    paths::set_json_format(config.json_format.unwrap_or(json_file::Format::Json));
+    let is_builtin = |name: &str| Cli::command().find_subcommand(name).is_some();
+    let args = match aliases::expand(args, &config.alias, is_builtin) {
+        Ok(args) => args,
+        Err(e) => {
+            messages::say(Msg::Failed { error: &e });
+            std::process::exit(1);
+        }
+    };
+    // Ids are parsed with the arguments and @last needs the backend's state file, so look
+    // for --sqlite and --db before clap does
+    match peek_db(&args, config.use_sqlite()) {
+        Some(db) => {
+            if let Some(path) = db_side_file(&db, "last.json") {
+                last::set_path(path);
+            }
+            if let Some(path) = db_side_file(&db, "events.ndjson") {
+                events::set_path(path);
+            }
+            if let Some(path) = db_side_file(&db, "trash.json") {
+                trash::set_path(path);
+            }
+            if let Some(path) = db_side_file(&db, "archive.json") {
+                archive::set_path(path);
+            }
+        }
+        None => {
+            last::set_path(paths::last_path());
+            events::set_path(paths::events_path());
+            trash::set_path(paths::trash_path());
+            archive::set_path(paths::archive_path());
+        }
+    }
+    // Read once here, so a deleted todo can come back under its own id. A trash that can't
+    // be read is reported by the commands that read or write it
+    if let Ok(highest) = trash::max_id() {
+        ids::set_floor(highest + 1);
+    }
+    let matches = Cli::command().get_matches_from(args);
+    let cli = Cli::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
+    if cli.quiet {
+        doctor::set_quiet();
+    }
+    // What the events log records as having made each change
+    let command_name = matches.subcommand_name().map(str::to_string).unwrap_or_else(|| {
+        match config.default_command {
+            Some(config::DefaultCommand::List) => "list",
+            Some(config::DefaultCommand::Tui) | None => "tui",
+        }
+        .to_string()
+    });
+    let command = cli.command.unwrap_or(match config.default_command {
+        Some(config::DefaultCommand::List) => Commands::List { name: None, wrap: false, filter: None, tag: vec![], stale: None, relative_dates: false, utc: false, format: None, tree: false, recursive: false, depth: None, pin_overdue: false, count: false, done: false, sort: commands::list::Sort::Id, changed_since: None },
+        Some(config::DefaultCommand::Tui) | None => Commands::Tui { filter: None, pin_overdue: false, plain: false, watch: false },
+    });
+
+    let sqlite = cli.sqlite || cli.db.is_some() || config.use_sqlite();
+    if let Some(profile) = paths::profile()
+        && !paths::data_dir().is_dir()
+        && !matches!(command, Commands::Profile { .. })
+    {
+        messages::say(Msg::NoSuchProfile { name: profile });
+        std::process::exit(1);
+    }
+    let mode = WriteMode::new(config, cli.dry_run);
+    let Some(command) = run_storeless(command, mode, sqlite, cli.sqlite, cli.db.as_deref(), config) else {
+        return;
+    };
+    let session = Session::new(config, &command, cli.quiet, cli.dry_run);
 
-    if cli.sqlite {
-        let mut conn = init_db();
-        handle_sqlite_commands(&mut conn, cli.command);
+    if sqlite {
+        let chosen = cli.db.is_some();
+        let db = cli.db.unwrap_or_else(paths::db_path);
+        let busy_timeout = config.busy_timeout();
+        let result = init_db(&db, busy_timeout).and_then(|mut conn| {
+            if cli.explain {
+                store::explain(&mut conn, &db);
+            }
+            if session.mode == WriteMode::Write && archive::sweep_due(config) {
+                // IMMEDIATE takes the write lock up front, as the JSON sweep takes the lock file
+                conn.execute_batch("BEGIN IMMEDIATE")?;
+                archive::sweep(&mut SqliteStore::new(&mut conn), config)?;
+                conn.execute_batch("COMMIT")?;
+            }
+            if cli.migrate_legacy {
+                messages::say(Msg::JsonOnly { flag: "--migrate-legacy" });
+            }
+            let before = load_todos_from_sqlite(&conn)?;
+            if before.is_empty() && !chosen {
+                if paths::profile().is_none() {
+                    hint_legacy_db(&db);
+                }
+                hint_json_store(cli.sqlite);
+            }
+            if let Commands::Shell = command {
+                sqlite_shell(&mut conn, &db, config, cli.quiet, cli.dry_run);
+                return Ok(());
+            }
+            run_sqlite_command(&mut conn, command, &command_name, &db, before, &session)
+        });
+        if let Err(e) = result {
+            report_db_error(&e, busy_timeout);
+            std::process::exit(1);
+        }
     } else {
-        let mut todos = load_todos();
-        handle_json_commands(cli.command, &mut todos);
-        save_todos(&todos).unwrap();
+        if cli.explain {
+            messages::say(Msg::SqliteOnly { flag: "--explain" });
+        }
+        let _lock = locks_store(&command, session.mode).then(lock_json_store);
+        if session.mode == WriteMode::Write && archive::sweep_due(config) {
+            sweep_json(config);
+        }
+        let mut todos = match load_todos() {
+            Ok(todos) => todos,
+            // Restoring a backup replaces the store, keeping a copy of it as it was
+            Err(e) if matches!(command, Commands::Restore { .. }) => {
+                messages::say(Msg::Warned { warning: &e });
+                vec![]
+            }
+            Err(e) => {
+                messages::say(Msg::StoreUnreadable { error: &e });
+                shell::exit(1);
+            }
+        };
+        if todos.is_empty() {
+            if paths::profile().is_none() && offer_legacy_json(cli.migrate_legacy, session.mode == WriteMode::Write) {
+                todos = load_todos_or_exit();
+            }
+            if todos.is_empty() {
+                hint_sqlite_store();
+            }
+        } else if cli.migrate_legacy {
+            messages::say(Msg::LegacyNotEmpty);
+        }
+        if let Commands::Shell = command {
+            return json_shell(todos, config, cli.quiet, cli.dry_run);
+        }
+        run_json_command(command, &command_name, &mut todos, &session);
     }
 }
 
-fn format_todo(todo: &Todo) -> String {
-    let status = if todo.done { "✓" } else { " " };
-    let due_date = todo.due_date.as_deref().unwrap_or("No due date");
-    let reminder = todo.reminder.as_deref().unwrap_or("No reminder");
-    format!("[{}] {}: {} (Due: {}, Reminder: {})", status, todo.id, todo.text, due_date, reminder)
+/// Run the commands that never read the todos, returning any other command to run against
+/// the store. They run before it is opened, so they stay quick and keep working when it
+/// can't be opened. Those that change a file save it only as `mode` allows
+fn run_storeless(
+    command: Commands,
+    mode: WriteMode,
+    sqlite: bool,
+    sqlite_flag: bool,
+    db: Option<&Path>,
+    config: &config::Config,
+) -> Option<Commands> {
+    match command {
+        Commands::Profile { action } => handle_profile_command(action, mode),
+        Commands::Alias => print_aliases(),
+        Commands::Filter { action } => handle_filter_command(action, mode),
+        Commands::Template { action: TemplateAction::List } => print_templates(),
+        Commands::Template { action: TemplateAction::Delete { name } } => delete_template(&name, mode),
+        Commands::Last { which } => print_last(which),
+        Commands::History { id } => print_history(id),
+        Commands::Trash { action: TrashAction::List } => print_trash(),
+        Commands::Trash { action: TrashAction::Empty { force } } => empty_trash(force, mode),
+        Commands::Goal { action: GoalAction::Set { tag, goal } } => set_goal(&tag, goal, mode),
+        Commands::Goal { action: GoalAction::Delete { tag } } => delete_goal(&tag, mode),
+        Commands::Backend => print_backend(sqlite_flag, db, config.backend),
+        Commands::Where if sqlite => print_where("sqlite", &db.map_or_else(paths::db_path, Path::to_path_buf)),
+        Commands::Where => print_where("json", &paths::json_path()),
+        Commands::Migrate { .. } if sqlite => {
+            messages::say(Msg::MigrateNeedsJson);
+            shell::exit(1);
+        }
+        Commands::Migrate { to } => migrate_json(to, mode),
+        command => return Some(command),
+    }
+    None
 }
 
-fn handle_json_commands(cmd: Commands, todos: &mut Vec<Todo>) {
-    match cmd {
-        Commands::Add { text } => {
-            let id = todos.len() + 1;
-            let joined = text.join(" ");
-            todos.push(Todo {
-                id,
-                text: joined,
-                done: false,
-                due_date: None,
-                reminder: None,
-            });
-            println!("✅ Todo added!");
-        }
-        Commands::Done { id } => {
-            if let Some(todo) = todos.iter_mut().find(|t| t.id == id) {
-                todo.done = true;
-                println!("🎉 Todo marked as done!");
-            } else {
-                eprintln!("❌ Todo with id {} not found", id);
-            }
-        }
-        Commands::Edit { id } => {
-            if let Some(todo) = todos.iter_mut().find(|t| t.id == id) {
-                let tmp_path = "/tmp/todo_edit.txt";
-                fs::write(tmp_path, &todo.text).expect("Failed to write temp file");
-
-                let editor = std::env::var("EDITOR").unwrap_or_else(|_| "nano".to_string());
-                std::process::Command::new(editor)
-                    .arg(tmp_path)
-                    .status()
-                    .expect("Failed to open editor");
-
-                let updated_text = fs::read_to_string(tmp_path).expect("Failed to read file");
-                todo.text = updated_text.trim().to_string();
-                println!("📝 Todo updated!");
-            } else {
-                eprintln!("❌ Todo with id {} not found", id);
-            }
-        }
-        Commands::Delete { id } => {
-            let len_before = todos.len();
-            todos.retain(|todo| todo.id != id);
-            for (i, todo) in todos.iter_mut().enumerate() {
-                todo.id = i + 1;
-            }
-            if todos.len() < len_before {
-                println!("🗑️ Deleted todo with id {}", id);
-            } else {
-                eprintln!("❌ Todo with id {} not found", id);
-            }
-        }
-        Commands::List => {
-            for todo in todos.iter() {
-                println!("{}", format_todo(todo));
-            }
-        }
-        Commands::Tui => {
-            handle_tui_command_json(todos);
-        }
-        Commands::Due { id, date } => {
-            match validate_date(&date) {
-                Ok(_) => {
-                    if let Some(todo) = todos.iter_mut().find(|t| t.id == id) {
-                        todo.due_date = Some(date);
-                        println!("📅 Due date set for todo {}!", id);
-                    } else {
-                        eprintln!("❌ Todo with id {} not found", id);
-                    }
-                }
-                Err(_) => eprintln!("❌ Invalid date format. Please use YYYY-MM-DD"),
-            }
+/// What goes on around each command once the store is open, the same for a line of
+/// `todo shell` as for a run of the binary
+struct Session<'a> {
+    config: &'a config::Config,
+    mode: WriteMode,
+    log_events: bool,
+    /// How far ahead the due-soon note looks, if a change is to be followed by one
+    due_soon: Option<chrono::Duration>,
+    nag: bool,
+}
+
+impl<'a> Session<'a> {
+    fn new(config: &'a config::Config, command: &Commands, quiet: bool, dry_run: bool) -> Session<'a> {
+        let notes = notes_allowed(command, quiet);
+        Session {
+            config,
+            mode: WriteMode::new(config, dry_run),
+            log_events: config.events_log.unwrap_or(false),
+            due_soon: config.due_soon_window().filter(|_| notes),
+            nag: notes && config.nag.unwrap_or(false),
         }
-        Commands::Remind { id, date, time } => {
-            match validate_datetime(&date, &time) {
-                Ok(datetime) => {
-                    if let Some(todo) = todos.iter_mut().find(|t| t.id == id) {
-                        todo.reminder = Some(format_datetime(&datetime));
-                        println!("⏰ Reminder set for todo {}!", id);
-                    } else {
-                        eprintln!("❌ Todo with id {} not found", id);
-                    }
-                }
-                Err(_) => eprintln!("❌ Invalid date/time format. Please use YYYY-MM-DD HH:MM"),
-            }
-        }
-        Commands::Upcoming => {
-            let now = Local::now().naive_local();
-            let mut upcoming: Vec<_> = todos.iter()
-                .filter(|todo| !todo.done)
-                .filter_map(|todo| {
-                    todo.reminder.as_ref().and_then(|reminder| {
-                        NaiveDateTime::parse_from_str(reminder, "%Y-%m-%d %H:%M")
-                            .ok()
-                            .map(|dt| (todo, dt))
-                    })
-                })
-                .filter(|(_, dt)| *dt > now)
-                .collect();
-            
-            upcoming.sort_by(|(_, a), (_, b)| a.cmp(b));
-            
-            if upcoming.is_empty() {
-                println!("No upcoming reminders");
-            } else {
-                println!("Upcoming reminders:");
-                for (todo, dt) in upcoming {
-                    println!("[{}] {} - Due: {}", todo.id, todo.text, format_datetime(&dt));
+    }
+}
+
+/// Whether `command` holds the JSON store's lock from reading the todos until it has saved
+/// them, so no other process saves in between and has its changes written over. Commands
+/// that wait on the user for as long as they like take it only to save, so they don't hold
+/// up every other command meanwhile: the TUI, which then rebases its changes on what is on
+/// disk, `watch`, the editor, and the shell, which takes it a line at a time
+fn locks_store(command: &Commands, mode: WriteMode) -> bool {
+    mode == WriteMode::Write && !matches!(command, Commands::Tui { .. } | Commands::Watch { .. } | Commands::Edit { .. } | Commands::Shell)
+}
+
+/// Lock the JSON store, or exit if it can't be
+fn lock_json_store() -> lock::Lock {
+    lock::exclusive(&paths::lock_path()).unwrap_or_else(|e| {
+        messages::say(Msg::CouldNotLock { error: &e });
+        shell::exit(1);
+    })
+}
+
+/// Run a command against the JSON file's todos and save them, as `session` allows.
+/// `name` is what the events log records as having made the changes. What the command
+/// reports is printed once its changes are saved, so it never claims one that wasn't
+fn run_json_command(command: Commands, name: &str, todos: &mut Vec<Todo>, session: &Session) {
+    let mut before = todos.clone();
+    let output = handle_json_commands(command, todos, &mut before, session.mode);
+    match session.mode {
+        WriteMode::Write => {
+            changes::stamp(&before, todos);
+            match save_todos(&before, todos) {
+                Ok(()) => {}
+                // A newer version's file can still be read; only changing it is refused
+                Err(e) if e.kind() == io::ErrorKind::Unsupported && before == *todos => {}
+                Err(e) => {
+                    messages::say(Msg::CouldNotSave { what: "the todos", error: &e });
+                    shell::exit(1);
                 }
             }
+            finish_saved(output, &before, todos, name, session, &json_notify_files());
         }
-        Commands::ClearReminder { id } => {
-            if let Some(todo) = todos.iter_mut().find(|t| t.id == id) {
-                todo.reminder = None;
-                println!("🗑️ Reminder cleared for todo {}!", id);
-            } else {
-                eprintln!("❌ Todo with id {} not found", id);
+        mode => {
+            finish_unsaved(output, &before, todos, mode);
+            if session.nag && mode == WriteMode::ReadOnly {
+                nag_overdue(todos, session.config);
             }
         }
     }
 }
 
-fn handle_sqlite_commands(conn: &mut Connection, cmd: Commands) {
-    match cmd {
-        Commands::Add { text } => {
-            let joined = text.join(" ");
-            conn.execute("INSERT INTO todos (text, done) VALUES (?1, 0)", params![joined])
-                .unwrap();
-            println!("✅ Todo added (SQLite)!");
-        }
-        Commands::Done { id } => {
-            let affected = conn
-                .execute("UPDATE todos SET done = 1 WHERE id = ?1", params![id])
-                .unwrap();
-            if affected > 0 {
-                println!("🎉 Todo marked as done (SQLite)!");
-            } else {
-                eprintln!("❌ Todo with id {} not found", id);
-            }
-        }
-        Commands::Edit { id } => {
-            let mut stmt = conn
-                .prepare("SELECT text FROM todos WHERE id = ?1")
-                .unwrap();
-            let mut rows = stmt.query(params![id]).unwrap();
-            if let Some(row) = rows.next().unwrap() {
-                let current_text: String = row.get(0).unwrap();
-                let tmp_path = "/tmp/todo_sqlite_edit.txt";
-                fs::write(tmp_path, &current_text).unwrap();
-
-                let editor = std::env::var("EDITOR").unwrap_or_else(|_| "nano".to_string());
-                std::process::Command::new(editor)
-                    .arg(tmp_path)
-                    .status()
-                    .expect("Failed to open editor");
-
-                let new_text = fs::read_to_string(tmp_path).unwrap();
-                conn.execute(
-                    "UPDATE todos SET text = ?1 WHERE id = ?2",
-                    params![new_text.trim(), id],
-                )
-                .unwrap();
-                println!("📝 Todo updated (SQLite)!");
-            } else {
-                eprintln!("❌ Todo with id {} not found", id);
-            }
-        }
-        Commands::Delete { id } => {
-            let affected = conn
-                .execute("DELETE FROM todos WHERE id = ?1", params![id])
-                .unwrap();
-            if affected > 0 {
-                println!("🗑️ Deleted todo with id {} (SQLite)", id);
-            } else {
-                eprintln!("❌ Todo with id {} not found", id);
-            }
-        }
-        Commands::List => {
-            let todos = load_todos_from_sqlite(conn);
-            for todo in todos {
-                println!("{}", format_todo(&todo));
-            }
-        }
-        Commands::Tui => {
-            let todos = load_todos_from_sqlite(conn);
-            let todos_for_tui: Vec<TuiTodo> = todos
-                .iter()
-                .map(|t| TuiTodo {
-                    id: t.id,
-                    text: t.text.clone(),
-                    done: t.done,
-                    due_date: t.due_date.clone(),
-                    reminder: t.reminder.clone(),
-                })
-                .collect();
-
-            match tui::run_tui(todos_for_tui) {
-                Ok(updated_todos) => {
-                    let todos: Vec<Todo> = updated_todos
-                        .into_iter()
-                        .enumerate()
-                        .map(|(i, t)| Todo {
-                            id: i + 1,
-                            text: t.text,
-                            done: t.done,
-                            due_date: t.due_date,
-                            reminder: t.reminder,
-                        })
-                        .collect();
-
-                    save_todos_to_sqlite(conn, &todos);
-                }
-                Err(e) => eprintln!("TUI Error: {}", e),
-            }
-        }
-        Commands::Due { id, date } => {
-            match validate_date(&date) {
-                Ok(_) => {
-                    let affected = conn
-                        .execute("UPDATE todos SET due_date = ?1 WHERE id = ?2", params![date, id])
-                        .unwrap();
-                    if affected > 0 {
-                        println!("📅 Due date set for todo {} (SQLite)!", id);
-                    } else {
-                        eprintln!("❌ Todo with id {} not found", id);
-                    }
-                }
-                Err(_) => eprintln!("❌ Invalid date format. Please use YYYY-MM-DD"),
-            }
-        }
-        Commands::Remind { id, date, time } => {
-            match validate_datetime(&date, &time) {
-                Ok(datetime) => {
-                    let datetime_str = format_datetime(&datetime);
-                    let affected = conn
-                        .execute("UPDATE todos SET reminder = ?1 WHERE id = ?2", params![datetime_str, id])
-                        .unwrap();
-                    if affected > 0 {
-                        println!("⏰ Reminder set for todo {} (SQLite)!", id);
-                    } else {
-                        eprintln!("❌ Todo with id {} not found", id);
-                    }
-                }
-                Err(_) => eprintln!("❌ Invalid date/time format. Please use YYYY-MM-DD HH:MM"),
-            }
-        }
-        Commands::Upcoming => {
-            let todos = load_todos_from_sqlite(conn);
-            let now = Local::now().naive_local();
-            let mut upcoming: Vec<_> = todos.iter()
-                .filter(|todo| !todo.done)
-                .filter_map(|todo| {
-                    todo.reminder.as_ref().and_then(|reminder| {
-                        NaiveDateTime::parse_from_str(reminder, "%Y-%m-%d %H:%M")
-                            .ok()
-                            .map(|dt| (todo, dt))
-                    })
-                })
-                .filter(|(_, dt)| *dt > now)
-                .collect();
-            
-            upcoming.sort_by(|(_, a), (_, b)| a.cmp(b));
-            
-            if upcoming.is_empty() {
-                println!("No upcoming reminders");
-            } else {
-                println!("Upcoming reminders:");
-                for (todo, dt) in upcoming {
-                    println!("[{}] {} - Due: {}", todo.id, todo.text, format_datetime(&dt));
+/// Run a command against the database, as `session` allows; `before` holds its todos
+fn run_sqlite_command(
+    conn: &mut Connection,
+    command: Commands,
+    name: &str,
+    db: &Path,
+    mut before: Vec<Todo>,
+    session: &Session,
+) -> rusqlite::Result<()> {
+    if session.mode == WriteMode::Write {
+        let output = handle_sqlite_commands(conn, command, db, &mut before, session.mode)?;
+        let mut after = load_todos_from_sqlite(conn)?;
+        stamp_sqlite(conn, &before, &mut after)?;
+        finish_saved(output, &before, &after, name, session, &db_notify_files(db));
+        return Ok(());
+    }
+    // Run the command inside a transaction that is always rolled back, then look
+    // at what it would have left behind
+    conn.execute_batch("BEGIN")?;
+    let result = handle_sqlite_commands(conn, command, db, &mut before, session.mode)
+        .and_then(|output| Ok((output, load_todos_from_sqlite(conn)?)));
+    conn.execute_batch("ROLLBACK")?;
+    let (output, after) = result?;
+    finish_unsaved(output, &before, &after, session.mode);
+    if session.nag && session.mode == WriteMode::ReadOnly {
+        nag_overdue(&before, session.config);
+    }
+    Ok(())
+}
+
+/// Print what a command reported once its changes are saved, note them and follow them
+/// with the due-soon note and the overdue nag, then exit non-zero if the command failed
+fn finish_saved(mut output: commands::Output, before: &[Todo], after: &[Todo], name: &str, session: &Session, files: &notify::Files) {
+    let code = output.exit_code();
+    output.flush();
+    record_changes(before, after, name, session.log_events, files);
+    if code != 0 {
+        shell::exit(code);
+    }
+    if let Some(window) = session.due_soon
+        && before != after
+    {
+        note_due_soon(after, window);
+    }
+    if session.nag {
+        nag_overdue(after, session.config);
+    }
+}
+
+/// A line typed into `todo shell` as the command it names, with the name the events log
+/// records and whether it was given --dry-run. Commands that don't need the store are run
+/// here, as a dry run if the shell is one, and `None` returned for them as for lines that
+/// don't parse
+fn shell_command(
+    words: Vec<String>,
+    config: &config::Config,
+    sqlite: bool,
+    db: Option<&Path>,
+    dry_run: bool,
+) -> Option<(Commands, String, bool)> {
+    let is_builtin = |name: &str| Cli::command().find_subcommand(name).is_some();
+    let args = match aliases::expand(std::iter::once("todo".to_string()).chain(words).collect(), &config.alias, is_builtin) {
+        Ok(args) => args,
+        Err(e) => {
+            messages::say(Msg::Failed { error: &e });
+            return None;
+        }
+    };
+    let matches = match Cli::command().try_get_matches_from(args) {
+        Ok(matches) => matches,
+        Err(e) => {
+            let _ = e.print();
+            return None;
+        }
+    };
+    let cli = match Cli::from_arg_matches(&matches) {
+        Ok(cli) => cli,
+        Err(e) => {
+            let _ = e.print();
+            return None;
+        }
+    };
+    if cli.sqlite || cli.db.is_some() || cli.profile.is_some() {
+        messages::say(Msg::ShellOnlyFlags);
+    }
+    let name = matches.subcommand_name()?.to_string();
+    let mode = WriteMode::new(config, dry_run || cli.dry_run);
+    match run_storeless(cli.command?, mode, sqlite, false, db, config)? {
+        Commands::Shell => {
+            messages::say(Msg::AlreadyInShell);
+            None
+        }
+        command => Some((command, name, cli.dry_run)),
+    }
+}
+
+/// `todo shell` on the JSON file, keeping its todos between commands and saving after
+/// each. They are read again first if another process saved the file in the meantime
+fn json_shell(mut todos: Vec<Todo>, config: &config::Config, quiet: bool, dry_run: bool) {
+    let path = paths::json_path();
+    let mut seen = watch::file_fingerprint(&path);
+    shell::run(todos_ids(&todos), |words| {
+        let Some((command, name, line_dry_run)) = shell_command(words, config, false, None, dry_run) else {
+            return todos_ids(&todos);
+        };
+        let session = Session::new(config, &command, quiet, dry_run || line_dry_run);
+        let _lock = locks_store(&command, session.mode).then(lock_json_store);
+        let fingerprint = watch::file_fingerprint(&path);
+        if fingerprint != seen {
+            match load_todos() {
+                Ok(read) => todos = read,
+                Err(e) => {
+                    messages::say(Msg::StoreUnreadable { error: &e });
+                    return todos_ids(&todos);
                 }
             }
+            messages::say(Msg::ReadAgain);
         }
-        Commands::ClearReminder { id } => {
-            let affected = conn
-                .execute("UPDATE todos SET reminder = NULL WHERE id = ?1", params![id])
-                .unwrap();
-            if affected > 0 {
-                println!("🗑️ Reminder cleared for todo {} (SQLite)!", id);
-            } else {
-                eprintln!("❌ Todo with id {} not found", id);
+        let kept = todos.clone();
+        let failed = shell::catch(|| run_json_command(command, &name, &mut todos, &session)).is_err();
+        if session.mode != WriteMode::Write {
+            todos = kept;
+        } else if failed {
+            // A failed command keeps what it saved, but a failed save leaves the file as it was
+            if let Ok(read) = load_todos() {
+                todos = read;
             }
         }
-    }
+        seen = watch::file_fingerprint(&path);
+        todos_ids(&todos)
+    });
+}
+
+/// `todo shell` on the database at `db`
+fn sqlite_shell(conn: &mut Connection, db: &Path, config: &config::Config, quiet: bool, dry_run: bool) {
+    let busy_timeout = config.busy_timeout();
+    let ids = |conn: &Connection| load_todos_from_sqlite(conn).map(|todos| todos_ids(&todos)).unwrap_or_default();
+    shell::run(ids(conn), |words| {
+        let Some((command, name, line_dry_run)) = shell_command(words, config, true, Some(db), dry_run) else {
+            return ids(conn);
+        };
+        let session = Session::new(config, &command, quiet, dry_run || line_dry_run);
+        let result = shell::catch(|| {
+            load_todos_from_sqlite(conn).and_then(|before| run_sqlite_command(conn, command, &name, db, before, &session))
+        });
+        if let Ok(Err(e)) = result {
+            report_db_error(&e, busy_timeout);
+        }
+        // A dry run that failed part way never got to roll back
+        if !conn.is_autocommit() {
+            let _ = conn.execute_batch("ROLLBACK");
+        }
+        ids(conn)
+    });
+}
+
+fn todos_ids(todos: &[Todo]) -> Vec<usize> {
+    todos.iter().map(|t| t.id).collect()
+}
+
+/// Whether `command` may be followed by the overdue nag or the due-soon note: never for
+/// --quiet, output that isn't a terminal, JSON meant for another program, or the TUI
+fn notes_allowed(command: &Commands, quiet: bool) -> bool {
+    let json = matches!(
+        command,
+        Commands::Show { json: true, .. }
+            | Commands::Stats { output: burndown::Output::Json, .. }
+            | Commands::DiffBackup { json: true, .. }
+            | Commands::Conflicts { json: true, .. }
+    );
+    !quiet && io::stdout().is_terminal() && !json && !matches!(command, Commands::Tui { .. })
 }
 
-fn handle_tui_command_json(todos: &mut Vec<Todo>) {
-    let todos_for_tui: Vec<TuiTodo> = todos
+/// How many todos `note_due_soon` names before summing up the rest
+const DUE_SOON_SHOWN: usize = 3;
+
+/// Name the pending todos falling due within `window` in one line, after a command that
+/// changed something
+fn note_due_soon(todos: &[Todo], window: chrono::Duration) {
+    let now = Local::now().naive_local();
+    let soon = agenda::due_soon(todos, now, window);
+    if soon.is_empty() {
+        return;
+    }
+    let mut names: Vec<String> = soon
         .iter()
-        .map(|t| TuiTodo {
-            id: t.id,
-            text: t.text.clone(),
-            done: t.done,
-            due_date: t.due_date.clone(),
-            reminder: t.reminder.clone(),
+        .take(DUE_SOON_SHOWN)
+        .map(|(due, todo)| {
+            let when = dates::humanize_date(due.date, now.date());
+            let when = match due.time {
+                Some(time) => format!("{} {}", when, time.format("%H:%M")),
+                None => when,
+            };
+            format!("#{} '{}' ({})", todo.id, text::escape(&todo.text), when)
         })
         .collect();
+    if soon.len() > DUE_SOON_SHOWN {
+        names.push(format!("+{} more", soon.len() - DUE_SOON_SHOWN));
+    }
+    messages::say(Msg::DueSoon { names: &names.join(", ") });
+}
 
-    match tui::run_tui(todos_for_tui) {
-        Ok(updated_todos) => {
-            todos.clear();
-            for (i, t) in updated_todos.into_iter().enumerate() {
-                todos.push(Todo {
-                    id: i + 1,
-                    text: t.text,
-                    done: t.done,
-                    due_date: t.due_date,
-                    reminder: t.reminder,
-                });
-            }
-            save_todos(todos).unwrap();
+/// Say how many todos are overdue in a dim line, unless it was said less than nag_every ago
+fn nag_overdue(todos: &[Todo], config: &config::Config) {
+    if !last::is_due(last::nagged_at(), config.nag_every()) {
+        return;
+    }
+    let now = Local::now().naive_local();
+    let overdue = todos.iter().filter(|todo| agenda::is_overdue(*todo, now)).count();
+    if overdue == 0 {
+        return;
+    }
+    println!("{}", console::style(format!("({} overdue — `todo today`)", overdue)).dim());
+    if let Err(e) = last::record_nag() {
+        messages::say(Msg::CouldNotNote { what: "the overdue nag was shown", error: &e });
+    }
+}
+
+/// The profile from --profile or TODO_PROFILE. The config and its aliases belong to the
+/// profile, so this is needed before clap can be given the expanded arguments; "default"
+/// names the default profile, to step out of TODO_PROFILE
+fn peek_profile(args: &[String]) -> Option<String> {
+    let mut profile = None;
+    let mut args = args.iter().skip(1).take_while(|a| *a != "--");
+    while let Some(arg) = args.next() {
+        if arg == "--profile" {
+            profile = args.next().cloned();
+        } else if let Some(name) = arg.strip_prefix("--profile=") {
+            profile = Some(name.to_string());
         }
-        Err(e) => eprintln!("TUI Error: {}", e),
     }
+    profile
+        .or_else(|| std::env::var("TODO_PROFILE").ok())
+        .filter(|name| !name.is_empty() && name != DEFAULT_PROFILE)
 }
 
-fn load_todos_from_sqlite(conn: &Connection) -> Vec<Todo> {
-    let mut stmt = conn
-        .prepare("SELECT id, text, done, due_date, reminder FROM todos ORDER BY id ASC")
-        .unwrap();
+/// The SQLite database named on the command line or implied by the config, or `None`
+/// when the JSON store is in use
+fn peek_db(args: &[String], config_sqlite: bool) -> Option<PathBuf> {
+    let mut sqlite = config_sqlite;
+    let mut db = None;
+    let mut args = args.iter().skip(1).take_while(|a| *a != "--");
+    while let Some(arg) = args.next() {
+        if arg == "--sqlite" {
+            sqlite = true;
+        } else if arg == "--db" {
+            db = args.next().map(PathBuf::from);
+        } else if let Some(path) = arg.strip_prefix("--db=") {
+            db = Some(PathBuf::from(path));
+        }
+    }
+    db.or_else(|| sqlite.then(paths::db_path))
+}
 
-    let rows = stmt
-        .query_map([], |row| {
-            Ok(Todo {
-                id: row.get(0)?,
-                text: row.get(1)?,
-                done: row.get(2)?,
-                due_date: row.get(3)?,
-                reminder: row.get(4)?,
-            })
-        })
-        .unwrap();
+/// Point at the working directory's todos.db when the data directory's database is empty,
+/// since that is where SQLite stores used to live
+fn hint_legacy_db(db: &Path) {
+    let legacy = Path::new(paths::LEGACY_DB_PATH);
+    if same_file(legacy, db) {
+        return;
+    }
+    let count = Connection::open_with_flags(legacy, rusqlite::OpenFlags::SQLITE_OPEN_READ_ONLY)
+        .and_then(|conn| conn.query_row("SELECT COUNT(*) FROM todos", [], |row| row.get::<_, usize>(0)));
+    if let Ok(count) = count
+        && count > 0
+    {
+        messages::say(Msg::LegacyDb { db, legacy: paths::LEGACY_DB_PATH, count });
+    }
+}
+
+/// Offer to copy the todos at the old hard-coded JSON path into the current store, which is
+/// empty, returning whether they were. Costs only a stat unless the old file is there. The
+/// question is asked once, its answer kept in the state file; `migrate` copies without
+/// asking, and where nothing can be asked or saved there is only a hint
+fn offer_legacy_json(migrate: bool, can_write: bool) -> bool {
+    let legacy = &paths::legacy_json_path();
+    if !legacy.is_file() {
+        if migrate {
+            messages::say(Msg::NothingToMigrate { legacy });
+        }
+        return false;
+    }
+    let json = paths::json_path();
+    if same_file(legacy, &json) || (last::legacy_json().is_some() && !migrate) {
+        return false;
+    }
+    let Some(stored) = fs::read_to_string(legacy).ok().and_then(|data| json_file::parse(&data).ok()) else {
+        if migrate {
+            messages::say(Msg::LegacyUnreadable { legacy });
+        }
+        return false;
+    };
+    let count = stored.todos.len();
+    if count == 0 {
+        return false;
+    }
+    if let Some(why) = json_file::too_new(stored.version) {
+        messages::say(Msg::LegacyTooNew { legacy, count, why: &why });
+        return false;
+    }
+    if !can_write || (!migrate && !commands::can_ask()) {
+        messages::say(Msg::LegacyJson { json: &json, legacy, count });
+        return false;
+    }
+    if !migrate && !confirm(&format!("No todos in {}, but {} has {}; copy them over?", json.display(), legacy.display(), count)) {
+        if let Err(e) = last::record_legacy_json(last::Answer::Declined) {
+            messages::say(Msg::AnswerNotNoted { error: &e });
+        }
+        println!("Left them where they are; todo --migrate-legacy copies them later");
+        return false;
+    }
+    let result = (|| -> io::Result<String> {
+        if let Some(dir) = json.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let _lock = lock::exclusive(&paths::lock_path())?;
+        write_todos(&json, &stored.todos)?;
+        let kept = format!("{}.pre-migrate-{}", legacy.display(), Local::now().format("%Y%m%d%H%M%S"));
+        fs::copy(legacy, &kept)?;
+        Ok(kept)
+    })();
+    match result {
+        Ok(kept) => {
+            let _ = last::record_legacy_json(last::Answer::Accepted);
+            messages::say(Msg::LegacyCopied { count, from: legacy, to: &json, kept: &kept });
+            true
+        }
+        Err(e) => {
+            messages::say(Msg::LegacyNotCopied { legacy, error: &e });
+            false
+        }
+    }
+}
 
-    rows.filter_map(Result::ok).collect()
+/// Point at the SQLite database when the JSON store is empty but it isn't, as the two are
+/// kept apart and it is easy to forget --sqlite. The database is only opened if it exists,
+/// and then read-only, so this never creates it
+fn hint_sqlite_store() {
+    let db = paths::db_path();
+    if !db.is_file() {
+        return;
+    }
+    let count = Connection::open_with_flags(&db, rusqlite::OpenFlags::SQLITE_OPEN_READ_ONLY)
+        .and_then(|conn| conn.query_row("SELECT COUNT(*) FROM todos", [], |row| row.get::<_, usize>(0)));
+    if let Ok(count) = count
+        && count > 0
+    {
+        messages::say(Msg::SqliteHasTodos { count, config: &paths::config_path() });
+    }
 }
 
-fn save_todos_to_sqlite(conn: &mut Connection, todos: &[Todo]) {
-    let tx = conn.transaction().unwrap();
-    tx.execute("DELETE FROM todos", []).unwrap();
+/// Point at the JSON store when the SQLite database is empty but it isn't; `flag` says
+/// whether --sqlite or the config chose SQLite, for the hint on switching back
+fn hint_json_store(flag: bool) {
+    let count = fs::read_to_string(paths::json_path())
+        .ok()
+        .and_then(|data| json_file::parse_todos(&data).ok())
+        .map_or(0, |todos| todos.len());
+    if count == 0 {
+        return;
+    }
+    messages::say(Msg::JsonHasTodos { count });
+    if flag {
+        eprintln!("   Leave out --sqlite to use it");
+    } else {
+        eprintln!("   backend = \"sqlite\" in {} chose SQLite; remove it to use the JSON store", paths::config_path().display());
+    }
+}
 
-    for todo in todos {
-        tx.execute(
-            "INSERT INTO todos (id, text, done, due_date, reminder) VALUES (?1, ?2, ?3, ?4, ?5)",
-            params![todo.id, todo.text, todo.done, todo.due_date, todo.reminder],
-        )
-        .unwrap();
+/// Rewrite the JSON store laid out as `to`, keeping the old file beside it
+fn migrate_json(to: json_file::Format, mode: WriteMode) {
+    let from = paths::json_path();
+    let target = paths::json_path_as(to);
+    if from == target {
+        println!("The JSON store is already {}", from.display());
+        return;
+    }
+    let mut out = commands::Output::default();
+    if !commands::may_write(mode, || format!("would move the todos from {} to {}", from.display(), target.display()), &mut out) {
+        return out.emit();
+    }
+    let result = (|| -> io::Result<Option<String>> {
+        if let Some(dir) = target.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let _lock = lock::exclusive(&paths::lock_path())?;
+        // Its todos lose what this version doesn't know about in the rewrite
+        let version = fs::read_to_string(&from).ok().and_then(|data| json_file::version(&data));
+        if let Some(why) = version.and_then(json_file::too_new) {
+            return Err(io::Error::new(io::ErrorKind::Unsupported, why));
+        }
+        let todos = load_todos()?;
+        write_todos(&target, &todos)?;
+        let Ok(metadata) = fs::metadata(&from) else {
+            return Ok(None);
+        };
+        fs::set_permissions(&target, metadata.permissions())?;
+        let kept = format!("{}.pre-migrate-{}", from.display(), Local::now().format("%Y%m%d%H%M%S"));
+        fs::rename(&from, &kept)?;
+        Ok(Some(kept))
+    })();
+    match result {
+        Ok(Some(kept)) => messages::say(Msg::StoreMoved { to: &target, kept: &kept }),
+        Ok(None) => messages::say(Msg::StoreStarted { at: &target }),
+        Err(e) => {
+            messages::say(Msg::NotMigrated { error: &e });
+            shell::exit(1);
+        }
     }
+}
 
-    tx.commit().unwrap();
+/// Which backend is in use and what chose it: --db, --sqlite, the config, or the default
+fn print_backend(sqlite_flag: bool, db: Option<&Path>, config_backend: Option<config::Backend>) {
+    let in_config = |name: &str| format!("backend = \"{}\" in {}", name, paths::config_path().display());
+    let (backend, why, store) = match (db, config_backend) {
+        (Some(db), _) => ("sqlite", "--db was given".to_string(), db.to_path_buf()),
+        (None, _) if sqlite_flag => ("sqlite", "--sqlite was given".to_string(), paths::db_path()),
+        (None, Some(config::Backend::Sqlite)) => ("sqlite", in_config("sqlite"), paths::db_path()),
+        (None, Some(config::Backend::Json)) => ("json", in_config("json"), paths::json_path()),
+        (None, None) => ("json", "the default; neither --sqlite nor backend in the config was given".to_string(), paths::json_path()),
+    };
+    println!("Backend: {}", backend);
+    println!("Why:     {}", why);
+    println!("Store:   {}{}", store.display(), if store.exists() { "" } else { " (not created yet)" });
 }
 
-fn init_db() -> Connection {
-    let conn = Connection::open("todos.db").unwrap();
-    conn.execute(
-        "CREATE TABLE IF NOT EXISTS todos (
-            id INTEGER PRIMARY KEY,
-            text TEXT NOT NULL,
-            done BOOLEAN NOT NULL DEFAULT 0,
-            due_date TEXT,
-            reminder TEXT
-        )",
-        [],
-    )
-    .unwrap();
-    conn
+fn same_file(a: &Path, b: &Path) -> bool {
+    matches!((fs::canonicalize(a), fs::canonicalize(b)), (Ok(a), Ok(b)) if a == b)
 }
 
-fn load_todos() -> Vec<Todo> {
-    if !Path::new(FILE_PATH).exists() {
-        return vec![];
+fn merge_strategy(ours: bool, theirs: bool, newest: bool) -> Option<merge::Strategy> {
+    match (ours, theirs, newest) {
+        (true, _, _) => Some(merge::Strategy::Ours),
+        (_, true, _) => Some(merge::Strategy::Theirs),
+        (_, _, true) => Some(merge::Strategy::Newest),
+        _ => None,
     }
-    let data = fs::read_to_string(FILE_PATH).unwrap_or_default();
-    serde_json::from_str(&data).unwrap_or_else(|_| vec![])
 }
 
-fn save_todos(todos: &Vec<Todo>) -> io::Result<()> {
-    let json = serde_json::to_string_pretty(todos)?;
-    let mut file = File::create(FILE_PATH)?;
-    file.write_all(json.as_bytes())?;
-    Ok(())
+/// Resolved locations of everything the app reads and writes, for `todo where`
+fn print_where(backend: &str, store: &Path) {
+    let exists = |path: &Path| if path.exists() { "" } else { " (not created yet)" };
+    println!("Profile:  {}", paths::profile().unwrap_or(DEFAULT_PROFILE));
+    println!("Backend:  {}", backend);
+    println!("Store:    {}{}", store.display(), exists(store));
+    let config = paths::config_path();
+    println!("Config:   {}{}", config.display(), exists(&config));
+    if let Some(config) = paths::profile_config_path() {
+        println!("          {}{}", config.display(), exists(&config));
+    }
+    println!("Data dir: {}", paths::data_dir().display());
 }
 
-fn validate_date(date_str: &str) -> Result<NaiveDate, ParseError> {
-    NaiveDate::parse_from_str(date_str, "%Y-%m-%d")
+/// A file kept beside the database, such as its lock or the last-touched ids. An
+/// in-memory database has none, since there is nothing on disk to keep them beside
+fn db_side_file(db: &Path, extension: &str) -> Option<PathBuf> {
+    (db != Path::new(MEMORY_DB)).then(|| db.with_extension(extension))
 }
 
-fn validate_time(time_str: &str) -> Result<NaiveTime, ParseError> {
-    NaiveTime::parse_from_str(time_str, "%H:%M")
+/// Note what a saved command changed: the last-touched ids, the trash, what `notify`
+/// remembers of reminders it cancelled and, when `log_events` is set, the events log. The
+/// change itself is already saved, so any of these failing only earns a warning
+fn record_changes(before: &[Todo], after: &[Todo], command: &str, log_events: bool, files: &notify::Files) {
+    if let Err(e) = last::record(changes::touched(before, after)) {
+        messages::say(Msg::CouldNotUpdate { what: "the last-touched ids", error: &e });
+    }
+    if let Err(e) = trash::record(before, after) {
+        messages::say(Msg::CouldNotUpdate { what: "the trash", error: &e });
+    }
+    if let Err(e) = notify::forget(files, &notify::silenced(before, after)) {
+        messages::say(Msg::CouldNotUpdate { what: "the record of announced reminders", error: &e });
+    }
+    if log_events && let Err(e) = events::append(&events::diff(before, after, command)) {
+        messages::say(Msg::EventsNotLogged { error: &e });
+    }
 }
 
-fn validate_datetime(date_str: &str, time_str: &str) -> Result<NaiveDateTime, ParseError> {
-    let date = validate_date(date_str)?;
-    let time = validate_time(time_str)?;
-    Ok(NaiveDateTime::new(date, time))
+/// Stamp `updated_at` on the rows a command changed, as the JSON backend does before saving
+fn stamp_sqlite(conn: &Connection, before: &[Todo], after: &mut [Todo]) -> rusqlite::Result<()> {
+    for id in changes::stamp(before, after) {
+        let updated_at = after.iter().find(|t| t.id == id).and_then(|t| t.updated_at.as_deref());
+        conn.execute("UPDATE todos SET updated_at = ?1 WHERE id = ?2", params![updated_at, id])?;
+    }
+    Ok(())
 }
 
-fn format_datetime(dt: &NaiveDateTime) -> String {
-    dt.format("%Y-%m-%d %H:%M").to_string()
+/// Finish a command run without saving. One that changed nothing prints what it reported
+/// as usual. One that did has its report held back, as it would tell of changes that were
+/// never made: a dry run lists what would have changed instead, and read-only mode refuses
+/// the change as an error, so scripts notice the write was refused. The note that a dry run
+/// changed nothing goes to stderr, as what the command printed itself may be piped on
+fn finish_unsaved(output: commands::Output, before: &[Todo], after: &[Todo], mode: WriteMode) {
+    let changes = changes::describe(before, after);
+    if changes.is_empty() {
+        if mode == WriteMode::DryRun && output.is_empty() {
+            messages::say(Msg::DryRunNoChanges);
+        }
+        return output.emit();
+    }
+    if mode == WriteMode::ReadOnly {
+        messages::say(Msg::ReadOnlyStore);
+        shell::exit(1);
+    }
+    messages::say(Msg::DryRun);
+    for change in changes {
+        println!("  {}", change);
+    }
+    if output.exit_code() != 0 {
+        shell::exit(output.exit_code());
+    }
 }
 
+/// Explain a database failure, calling out lock contention separately since that is
+/// usually another process (or a sync client) holding the file rather than a real fault
+fn report_db_error(err: &rusqlite::Error, busy_timeout: Duration) {
+    match err.sqlite_error_code() {
+        Some(rusqlite::ErrorCode::DatabaseBusy | rusqlite::ErrorCode::DatabaseLocked) => {
+            messages::say(Msg::DatabaseLocked { waited: busy_timeout.as_secs_f64() })
+        }
+        _ => {
+            messages::say(Msg::DatabaseError { error: err });
+            if let rusqlite::Error::SqliteFailure(failure, _) = err {
+                eprintln!("   SQLite code: {:?} (extended {})", failure.code, failure.extended_code);
+            }
+        }
+    }
+}
+
+/// Never squeeze the text column below this, even on absurdly narrow terminals
+const MIN_TEXT_WIDTH: usize = 8;
+
+/// Format a todo as one line, or fit it to `width` columns by truncating the text
+/// (or wrapping it with a hanging indent when `wrap` is set). The id is right-aligned in
+/// `id_width` columns so the lines of a list stay in step
+fn format_todo(todo: &Todo, id_width: usize, width: Option<usize>, wrap: bool, display: DateDisplay) -> String {
+    let status = if todo.done { "✓" } else { " " };
+    let due_date = match (todo.due_date.as_deref(), todo.recurrence.as_deref()) {
+        (Some(due), Some(recurrence)) => format!("{} ({})", dates::display_date(due, display), recurrence),
+        (Some(due), None) => dates::display_date(due, display),
+        (None, _) => "No due date".to_string(),
+    };
+    let reminder = match (todo.reminder.as_deref(), todo.reminder_repeat.as_deref()) {
+        (Some(reminder), Some(repeat)) => format!("{} ({})", dates::display_reminder(reminder, display), repeat),
+        (Some(reminder), None) => dates::display_reminder(reminder, display),
+        (None, _) => "No reminder".to_string(),
+    };
+    let text = text::escape(&todo.text);
+    let prefix = format!("[{}] {:>id_width$}: ", status, todo.id);
+    let tags: String = todo.tags.iter().map(|tag| format!(" #{}", tag)).collect();
+    let priority = match todo.priority.as_deref() {
+        Some(priority) => format!(", Priority: {}", priority),
+        None => String::new(),
+    };
+    let suffix = format!("{} (Due: {}, Reminder: {}{})", tags, due_date, reminder, priority);
+
+    let Some(width) = width else {
+        return format!("{}{}{}", prefix, text, suffix);
+    };
+    let prefix_width = prefix.width();
+    let suffix_width = suffix.width();
+
+    if wrap {
+        let text_width = width.saturating_sub(prefix_width).max(MIN_TEXT_WIDTH);
+        let mut lines = wrap_text(&text, text_width);
+        let last = lines.pop().unwrap_or_default();
+        let indent = " ".repeat(prefix_width);
+        let mut out = prefix;
+        for line in lines {
+            out.push_str(&line);
+            out.push('\n');
+            out.push_str(&indent);
+        }
+        out.push_str(&last);
+        if last.width() + suffix_width > text_width {
+            out.push('\n');
+            out.push_str(&indent);
+            out.push_str(suffix.trim_start());
+        } else {
+            out.push_str(&suffix);
+        }
+        out
+    } else {
+        let text_width = width
+            .saturating_sub(prefix_width + suffix_width)
+            .max(MIN_TEXT_WIDTH);
+        let text = truncate_text(&text, text_width);
+        let padding = " ".repeat(text_width.saturating_sub(text.width()));
+        format!("{}{}{}{}", prefix, text, padding, suffix)
+    }
+}
+
+/// How many columns the longest of `ids` takes, and at least `min`
+fn id_width(ids: impl IntoIterator<Item = usize>, min: usize) -> usize {
+    ids.into_iter().map(|id| id.to_string().len()).max().unwrap_or(1).max(min)
+}
+
+/// How wide ids are padded in lists of `todos`, so every list of the store lines up alike
+fn store_id_width(todos: &[Todo]) -> usize {
+    id_width(todos.iter().map(|t| t.id), config::load().id_width.unwrap_or(0))
+}
+
+/// Shorten `text` to `width` terminal columns, marking the cut with an ellipsis. Wide
+/// characters such as CJK and most emoji take two columns each
+fn truncate_text(text: &str, width: usize) -> String {
+    if text.width() <= width {
+        return text.to_string();
+    }
+    let room = width.saturating_sub(1);
+    let mut out = String::new();
+    let mut used = 0;
+    for c in text.chars() {
+        let w = c.width().unwrap_or(0);
+        if used + w > room {
+            break;
+        }
+        used += w;
+        out.push(c);
+    }
+    out.push('…');
+    out
+}
+
+/// Greedily break `text` into lines of at most `width` columns, splitting overlong words
+fn wrap_text(text: &str, width: usize) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut line = String::new();
+    for word in text.split_whitespace() {
+        let mut word = word.to_string();
+        while word.width() > width {
+            if !line.is_empty() {
+                lines.push(std::mem::take(&mut line));
+            }
+            // At least one character per line, however wide, so this always moves on
+            let mut used = 0;
+            let cut = word
+                .char_indices()
+                .find(|&(i, c)| {
+                    used += c.width().unwrap_or(0);
+                    i > 0 && used > width
+                })
+                .map_or(word.len(), |(i, _)| i);
+            lines.push(word[..cut].to_string());
+            word = word[cut..].to_string();
+        }
+        let line_width = line.width();
+        if line_width > 0 && line_width + 1 + word.width() > width {
+            lines.push(std::mem::take(&mut line));
+        }
+        if !line.is_empty() {
+            line.push(' ');
+        }
+        line.push_str(&word);
+    }
+    lines.push(line);
+    lines
+}
+
+/// Width to fit list output to, or `None` when stdout isn't a terminal
+fn output_width() -> Option<usize> {
+    if !io::stdout().is_terminal() {
+        return None;
+    }
+    terminal_size::terminal_size().map(|(terminal_size::Width(w), _)| w as usize)
+}
+
+/// A day given on the command line, in any form a quick-add due date takes
+fn parse_day(value: &str) -> Result<NaiveDate, String> {
+    quick_add::parse_due(value, Local::now().date_naive())
+        .map(|due| due.date)
+        .ok_or_else(|| format!("'{}' is not a date; use YYYY-MM-DD, today, tomorrow, a weekday or a span like 3d", value))
+}
+
+/// A moment to look back to: a span back from now like 2d or 12h, or the start of a date
+fn parse_cutoff(value: &str) -> Result<DateTime<Utc>, String> {
+    match dates::parse_duration(value) {
+        Ok(span) => {
+            return Utc::now()
+                .checked_sub_signed(span)
+                .ok_or_else(|| format!("'{}' goes back further than dates do", value));
+        }
+        Err(e) if value.trim().ends_with(['m', 'h', 'd', 'w']) => return Err(e),
+        Err(_) => {}
+    }
+    let date = dates::parse_date(value.trim())
+        .map_err(|_| format!("'{}' is neither a date nor a span like 2d or 12h", value))?;
+    date.and_time(NaiveTime::MIN)
+        .and_local_timezone(Local)
+        .earliest()
+        .map(|at| at.with_timezone(&Utc))
+        .ok_or_else(|| format!("midnight on {} doesn't exist in the local time zone", value))
+}
+
+/// A month given as YYYY-MM, as its first day
+fn parse_month(value: &str) -> Result<NaiveDate, String> {
+    NaiveDate::parse_from_str(&format!("{}-01", value), "%Y-%m-%d")
+        .map_err(|_| format!("'{}' is not a month; use YYYY-MM", value))
+}
+
+fn list_layout(tree: bool, recursive: bool, depth: Option<usize>, pin_overdue: bool, sort: commands::list::Sort) -> commands::list::Layout {
+    commands::list::Layout {
+        tree: tree.then_some(commands::list::Tree { recursive, depth }),
+        pin_overdue: pin_overdue || config::load().pin_overdue.unwrap_or(false),
+        sort,
+    }
+}
+
+/// What `add` does about a done todo with the same text, from --reopen, --clear-dates and --new
+fn add_repeat(reopen: bool, clear_dates: bool, new: bool) -> commands::add::Repeat {
+    match (reopen, new) {
+        (true, _) => commands::add::Repeat::Reopen { clear_dates },
+        (_, true) => commands::add::Repeat::New,
+        _ => commands::add::Repeat::Ask,
+    }
+}
+
+/// `filter` narrowed to completed todos when `done` is set
+fn done_only(filter: Option<Filter>, done: bool) -> Option<Filter> {
+    if !done {
+        return filter;
+    }
+    Some(filter.map_or(Filter::Done(true), |filter| Filter::Done(true).and(filter)))
+}
+
+/// How to show dates, from the command's flags and the config file. Relative dates count
+/// from today in whichever timezone the times are shown in
+fn date_display(relative_dates: bool, utc: bool) -> DateDisplay {
+    let relative = relative_dates || config::load().relative_dates.unwrap_or(false);
+    let today = if utc { Utc::now().date_naive() } else { Local::now().date_naive() };
+    DateDisplay {
+        relative_to: relative.then_some(today),
+        utc,
+    }
+}
+
+/// Placeholders a `list --format` template may use
+const LIST_FIELDS: &[&str] = &[
+    "id", "text", "done", "status_icon", "due", "due_date", "due_relative", "reminder", "reminder_repeat",
+    "recurrence", "created_at", "completed_at", "updated_at", "age_days", "completion_note", "tags", "priority", "uuid", "progress", "urgency",
+];
+
+/// The --format template, or list_format from the config; exits on a malformed one
+fn list_template(format: Option<String>) -> Option<Template> {
+    let source = format.or_else(|| config::load().list_format.clone())?;
+    match Template::parse(&source, LIST_FIELDS) {
+        Ok(template) => Some(template),
+        Err(e) => {
+            messages::say(Msg::InvalidListFormat { error: &e });
+            shell::exit(1);
+        }
+    }
+}
+
+/// The value of one `list --format` placeholder for a todo; empty when the todo has none
+fn list_field(todo: &Todo, field: &str, display: DateDisplay, now: DateTime<Utc>, progress: Option<subtasks::Progress>) -> String {
+    let text = |value: &Option<String>| value.as_deref().map(|v| text::escape(v).into_owned()).unwrap_or_default();
+    match field {
+        "id" => todo.id.to_string(),
+        "text" => text::escape(&todo.text).into_owned(),
+        "done" => todo.done.to_string(),
+        "status_icon" => if todo.done { "✓" } else { " " }.to_string(),
+        "due" => todo.due_date.as_deref().map(|due| dates::display_date(due, display)).unwrap_or_default(),
+        "due_relative" => {
+            let relative = DateDisplay { relative_to: Some(now.with_timezone(&Local).date_naive()), ..display };
+            todo.due_date.as_deref().map(|due| dates::display_date(due, relative)).unwrap_or_default()
+        }
+        "reminder" => todo.reminder.as_deref().map(|r| dates::display_reminder(r, display)).unwrap_or_default(),
+        "age_days" => todo_age(todo, now).map(|age| age.num_days().to_string()).unwrap_or_default(),
+        "tags" => todo.tags.iter().map(|tag| format!("#{}", tag)).collect::<Vec<_>>().join(" "),
+        "due_date" => text(&todo.due_date),
+        "reminder_repeat" => text(&todo.reminder_repeat),
+        "recurrence" => text(&todo.recurrence),
+        "created_at" => text(&todo.created_at),
+        "completed_at" => text(&todo.completed_at),
+        "updated_at" => text(&todo.updated_at),
+        "completion_note" => text(&todo.completion_note),
+        "priority" => text(&todo.priority),
+        "uuid" => todo.uuid.clone(),
+        "progress" => progress.map(|p| p.to_string()).unwrap_or_default(),
+        "urgency" if todo.done => String::new(),
+        "urgency" => {
+            let score = urgency::score(todo, now.with_timezone(&Local).naive_local(), &config::load().urgency);
+            format!("{:.2}", score)
+        }
+        _ => String::new(),
+    }
+}
+
+/// Pending todos created longer than `threshold` ago
+fn stale_filter(threshold: chrono::Duration) -> Filter {
+    let cutoff = Utc::now().checked_sub_signed(threshold).unwrap_or(DateTime::<Utc>::MIN_UTC);
+    Filter::Done(false).and(Filter::CreatedBefore(cutoff))
+}
+
+/// How long ago a todo was created, if it records that
+fn todo_age(todo: &Todo, now: DateTime<Utc>) -> Option<chrono::Duration> {
+    let created = DateTime::parse_from_rfc3339(todo.created_at.as_deref()?).ok()?;
+    Some(now - created.with_timezone(&Utc))
+}
+
+/// How long a done todo took from being created to being completed, if it records both
+fn lead_time(todo: &Todo) -> Option<chrono::Duration> {
+    let created = DateTime::parse_from_rfc3339(todo.created_at.as_deref()?).ok()?;
+    let completed = DateTime::parse_from_rfc3339(todo.completed_at.as_deref().filter(|_| todo.done)?).ok()?;
+    Some(completed - created)
+}
+
+/// Progress on every goal in the current period, counting reopened todos' completions
+/// from `events` if given
+fn goal_progress(todos: &[Todo], events: Option<&[events::Event]>) -> Vec<goals::Progress> {
+    let today = Local::now().date_naive();
+    goals::progress(&goals::read(), todos, events, today, config::load().first_weekday())
+}
+
+fn print_goals(todos: &[Todo], count_reopens: bool) {
+    if goals::read().is_empty() {
+        println!("No goals; set one with `todo goal set TAG 3/week`");
+        return;
+    }
+    let events = if count_reopens {
+        if config::load().events_log != Some(true) {
+            messages::say(Msg::NeedsEventsLog { config: &paths::config_path() });
+        }
+        match events::read() {
+            Ok((events, _)) => Some(events),
+            Err(e) => {
+                messages::say(Msg::EventsUnreadable { error: &e });
+                shell::exit(1);
+            }
+        }
+    } else {
+        None
+    };
+    for line in goals::render(&goal_progress(todos, events.as_deref())) {
+        println!("{}", line);
+    }
+}
+
+fn set_goal(tag: &str, goal: goals::Goal, mode: WriteMode) {
+    let tag = match normalize_tags([tag]) {
+        Ok(tags) if tags.len() == 1 => tags[0].clone(),
+        Ok(_) => {
+            messages::say(Msg::GoalNeedsTag);
+            shell::exit(1);
+        }
+        Err(e) => {
+            messages::say(Msg::Failed { error: &e });
+            shell::exit(1);
+        }
+    };
+    let mut out = commands::Output::default();
+    if !commands::may_write(mode, || format!("would set the goal for #{} to {}", tag, goal), &mut out) {
+        return out.emit();
+    }
+    match goals::set(&tag, goal) {
+        Ok(()) => messages::say(Msg::GoalSet { tag: &tag, goal: &goal }),
+        Err(e) => {
+            messages::say(Msg::CouldNotSave { what: "the goal", error: &e });
+            shell::exit(1);
+        }
+    }
+}
+
+fn delete_goal(tag: &str, mode: WriteMode) {
+    let tag = tag.trim().trim_start_matches('#').to_lowercase();
+    if !goals::read().contains_key(&tag) {
+        messages::say(Msg::NoGoal { tag: &tag });
+        shell::exit(1);
+    }
+    let mut out = commands::Output::default();
+    if !commands::may_write(mode, || format!("would delete the goal for #{}", tag), &mut out) {
+        return out.emit();
+    }
+    match goals::delete(&tag) {
+        Ok(_) => messages::say(Msg::GoalDeleted { tag: &tag }),
+        Err(e) => {
+            messages::say(Msg::CouldNotDelete { what: "the goal", error: &e });
+            shell::exit(1);
+        }
+    }
+}
+
+/// Open todos per day over the last `days` days, optionally only those with every given tag
+fn print_burndown(todos: &[Todo], tags: &[String], days: u32, output: burndown::Output) {
+    let Some(tags) = parse_tags(tags) else {
+        shell::exit(1);
+    };
+    let matching = todos.iter().filter(|todo| tags.iter().all(|tag| todo.tags.contains(tag)));
+    let series = burndown::series(matching, Local::now().date_naive(), days);
+    match output {
+        burndown::Output::Json => match serde_json::to_string_pretty(&series) {
+            Ok(json) => println!("{}", json),
+            Err(e) => messages::say(Msg::CouldNotWrite { what: &"the burndown", error: &e }),
+        },
+        burndown::Output::Text => {
+            let scope = if tags.is_empty() { String::new() } else { format!(" tagged {}", tag_summary(&tags)) };
+            messages::say(Msg::Burndown { scope: &scope, days });
+            for line in burndown::render(&series) {
+                println!("  {}", line);
+            }
+        }
+    }
+}
+
+/// A todo as `show --json` prints it: as stored, plus its urgency score while pending
+#[derive(Serialize)]
+struct ShownTodo<'a> {
+    #[serde(flatten)]
+    todo: &'a Todo,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    urgency: Option<f64>,
+}
+
+fn print_todo_json(todo: &Todo) {
+    let urgency = (!todo.done)
+        .then(|| urgency::rounded(urgency::score(todo, Local::now().naive_local(), &config::load().urgency)));
+    match serde_json::to_string_pretty(&ShownTodo { todo, urgency }) {
+        Ok(json) => println!("{}", json),
+        Err(e) => messages::say(Msg::CouldNotWrite { what: &"the todo", error: &e }),
+    }
+}
+
+fn print_todo_details(todo: &Todo, todos: &[Todo], display: DateDisplay) {
+    println!("#{} {}", todo.id, text::escape(&todo.text));
+    println!("  Status:    {}", if todo.done { "done" } else { "pending" });
+    println!(
+        "  Due:       {}",
+        todo.due_date.as_deref().map_or("-".to_string(), |due| dates::display_date(due, display))
+    );
+    if let Some(recurrence) = &todo.recurrence {
+        println!("  Repeats:   {}", recurrence);
+    }
+    match (todo.reminder.as_deref(), todo.reminder_repeat.as_deref()) {
+        (Some(reminder), Some(repeat)) => {
+            println!("  Reminder:  {} (repeats {})", dates::display_reminder(reminder, display), repeat)
+        }
+        (Some(reminder), None) => println!("  Reminder:  {}", dates::display_reminder(reminder, display)),
+        (None, _) => println!("  Reminder:  -"),
+    }
+    println!("  Priority:  {}", todo.priority.as_deref().unwrap_or("-"));
+    if todo.tags.is_empty() {
+        println!("  Tags:      -");
+    } else {
+        println!("  Tags:      {}", todo.tags.join(", "));
+    }
+    if let Some(parent) = todo.parent {
+        match todos.iter().find(|t| t.id == parent) {
+            Some(parent) => println!("  Parent:    #{} {}", parent.id, text::escape(&parent.text)),
+            None => println!("  Parent:    #{} (missing)", parent),
+        }
+    }
+    if let Some(progress) = subtasks::progress(todos, false).get(&todo.id) {
+        let all = subtasks::progress(todos, true)[&todo.id];
+        if all == *progress {
+            println!("  Subtasks:  {}/{} done", progress.done, progress.total);
+        } else {
+            println!("  Subtasks:  {}/{} done ({}/{} including theirs)", progress.done, progress.total, all.done, all.total);
+        }
+    }
+    println!("  Created:   {}", todo.created_at.as_deref().unwrap_or("-"));
+    if let Some(completed_at) = &todo.completed_at {
+        println!("  Completed: {}", completed_at);
+    }
+    if todo.done {
+        let lead = lead_time(todo).map_or("unknown".to_string(), |lead| format!("completed in {}", dates::format_duration(lead)));
+        println!("  Took:      {}", lead);
+    }
+    if let Some(note) = &todo.completion_note {
+        let mut lines = note.lines();
+        println!("  Note:      {}", lines.next().unwrap_or(""));
+        for line in lines {
+            println!("             {}", line);
+        }
+    }
+}
+
+/// Run a command against the loaded JSON todos, returning what it has to report
+fn run_json(todos: &mut Vec<Todo>, command: impl FnOnce(&mut dyn Store) -> store::Result<commands::Output>) -> commands::Output {
+    match command(&mut JsonStore::new(todos)) {
+        Ok(output) => output,
+        Err(e) => unreachable!("the JSON store has no database to fail: {}", e),
+    }
+}
+
+/// Run a command against the JSON file's todos. `before` is the store as the command found
+/// it, which the TUI moves on to what is on disk when it saves
+fn handle_json_commands(cmd: Commands, todos: &mut Vec<Todo>, before: &mut Vec<Todo>, mode: WriteMode) -> commands::Output {
+    match cmd {
+        Commands::Tui { filter, pin_overdue, plain, watch } => {
+            handle_tui_command_json(todos, before, filter.as_deref(), pin_overdue, plain, watch);
+        }
+        Commands::Notify { reset } => {
+            run_notify(todos, &json_notify_files(), reset, mode);
+        }
+        Commands::Watch { interval, bell } => {
+            watch::run(|| load_todos().map_err(|e| e.to_string()), &json_notify_files(), &watch_options(interval, bell));
+        }
+        Commands::Backup { path } => {
+            write_backup(&path, todos, "json", "");
+        }
+        Commands::Restore { path, force } => {
+            return run_json(todos, |store| commands::restore::run(store, &path, force, || backup::safety_copy(&paths::json_path())));
+        }
+        Commands::DiffBackup { n, bundle, json } => {
+            let (backup, name) = read_backup(n, bundle.as_deref(), &paths::json_path(), false);
+            return run_json(todos, |store| commands::diff_backup::run(store, &backup, &name, json));
+        }
+        Commands::Doctor { fix } => {
+            return run_json(todos, |store| commands::doctor::run(store, fix, Vec::new()));
+        }
+        cmd => return run_json(todos, |store| handle_commands(store, cmd, mode)),
+    }
+    commands::Output::default()
+}
+
+/// Run a command against the database; `before` is as for `handle_json_commands`
+fn handle_sqlite_commands(
+    conn: &mut Connection,
+    cmd: Commands,
+    db: &Path,
+    before: &mut Vec<Todo>,
+    mode: WriteMode,
+) -> rusqlite::Result<commands::Output> {
+    match cmd {
+        Commands::Tui { filter, pin_overdue, plain, watch } => {
+            doctor::warn_unreadable(before);
+            if watch && db == Path::new(MEMORY_DB) {
+                messages::say(Msg::NothingToWatch);
+            }
+            let store = RefCell::new(TuiStore { store: SqliteStore::new(conn), originals: BTreeMap::new() });
+            let mut plain = plain;
+            loop {
+                let exit = if plain {
+                    run_plain(&mut *store.borrow_mut(), filter.as_deref(), pin_overdue, tui_location("SQLite", db))?
+                } else {
+                    let todos = store.borrow_mut().todos()?;
+                    let watch = if watch { sqlite_watch(db) } else { None };
+                    let mut settings = tui_settings(&todos, filter.as_deref(), pin_overdue, tui_location("SQLite", db), watch);
+                    settings.palette = Some(sqlite_palette(&store, &todos, settings.location.read_only));
+                    match tui::run_tui(tui_todos(&todos), settings, Some(&mut &store)) {
+                        Ok((_, exit)) => exit,
+                        Err(e) => {
+                            messages::say(Msg::TuiFailed { error: &e });
+                            tui::Exit::Quit
+                        }
+                    }
+                };
+                if exit == tui::Exit::Quit {
+                    break;
+                }
+                plain = !plain;
+            }
+            let originals = store.into_inner().originals;
+            *before = tui_baseline(&load_todos_from_sqlite(conn)?, originals);
+        }
+        Commands::Notify { reset } => {
+            run_notify(&load_todos_from_sqlite(conn)?, &db_notify_files(db), reset, mode);
+        }
+        Commands::Watch { interval, bell } => {
+            let load = || load_todos_from_sqlite(conn).map_err(|e| e.to_string());
+            watch::run(load, &db_notify_files(db), &watch_options(interval, bell));
+        }
+        Commands::Backup { path } => {
+            write_backup(&path, &load_todos_from_sqlite(conn)?, "sqlite", store::SQLITE_LABEL);
+        }
+        Commands::Restore { path, force } => {
+            // Fold the WAL back into the main file so the safety copy is complete on its own
+            conn.query_row("PRAGMA wal_checkpoint(TRUNCATE)", [], |_| Ok(()))?;
+            let safety_copy = || if db == Path::new(MEMORY_DB) { Ok(None) } else { backup::safety_copy(db) };
+            return commands::restore::run(&mut SqliteStore::new(conn), &path, force, safety_copy);
+        }
+        Commands::DiffBackup { n, bundle, json } => {
+            let (backup, name) = read_backup(n, bundle.as_deref(), db, true);
+            return commands::diff_backup::run(&mut SqliteStore::new(conn), &backup, &name, json);
+        }
+        Commands::Doctor { fix } => {
+            let mut findings = doctor::check_columns(&table_columns(conn)?, &expected_columns());
+            findings.extend(doctor::check_done_column(&dirty_done_rows(conn)?));
+            return commands::doctor::run(&mut SqliteStore::new(conn), fix, findings);
+        }
+        cmd => return handle_commands(&mut SqliteStore::new(conn), cmd, mode),
+    }
+    Ok(commands::Output::default())
+}
+
+/// Run a command that works the same on either backend against `store`. What only one
+/// backend can do, such as the TUI, backups and reminders, is left to its own handler
+fn handle_commands(store: &mut dyn Store, cmd: Commands, mode: WriteMode) -> store::Result<commands::Output> {
+    match cmd {
+        Commands::Add { template: Some(name), var, print_id, parent, .. } => {
+            commands::add::run_template(store, &name, &var, print_id, parent)
+        }
+        Commands::Template { action: TemplateAction::Save { name, id, text } } => {
+            commands::template::save(store, &name, id, text.as_deref(), mode)
+        }
+        Commands::Add { text, print_id, parent, infer_dates, reopen, clear_dates, new, .. } => {
+            let repeat = add_repeat(reopen, clear_dates, new);
+            commands::add::run(store, &text, print_id, parent, infer_dates, repeat)
+        }
+        Commands::Done { id: Some(id), message, hint, keep_reminder, .. } => {
+            commands::done::run(store, id, &message, &hint, keep_reminder)
+        }
+        Commands::Done { text: Some(text), first, message, keep_reminder, .. } => {
+            commands::with_text(store, &text, first, |store, id| commands::done::run(store, id, &message, &[], keep_reminder))
+        }
+        Commands::Done { id: None, tag, filter, force, message, keep_reminder, .. } => {
+            let filter = list_filter(None, filter.as_deref(), &tag, None).expect("clap requires --tag or --filter");
+            commands::done::run_bulk(store, &filter, &message, force, keep_reminder)
+        }
+        Commands::Edit { id: None, .. } => commands::edit::run_batch(store),
+        Commands::Edit { id: Some(id), full: true, hint, .. } => commands::edit::run_full(store, id, &hint),
+        Commands::Edit { id: Some(id), hint, .. } => commands::edit::run(store, id, &hint),
+        Commands::Delete { text: Some(text), first, .. } => {
+            commands::with_text(store, &text, first, |store, id| commands::delete::run(store, id, &[]))
+        }
+        Commands::Delete { id: None, done, all, before, filter, force, .. } => {
+            commands::delete::run_bulk(store, done, all, before.as_deref(), filter.as_deref(), force)
+        }
+        Commands::Delete { id: Some(id), hint, .. } => commands::delete::run(store, id, &hint),
+        Commands::Trash { action: TrashAction::Restore { id } } => commands::trash::restore(store, id),
+        Commands::Get { id, field } => commands::get::run(store, id, &field),
+        Commands::Show { id, utc, json } => {
+            let todos = store.todos()?;
+            match todos.iter().find(|t| t.id == id) {
+                Some(todo) if json => print_todo_json(todo),
+                Some(todo) => print_todo_details(todo, &todos, date_display(false, utc)),
+                None => not_found(id, &todos, &[]),
+            }
+            Ok(commands::Output::default())
+        }
+        Commands::List { count: true, name, filter, tag, stale, done, .. } => {
+            let filter = done_only(list_filter(name.as_deref(), filter.as_deref(), &tag, stale), done);
+            commands::list::count(store, filter.as_ref())
+        }
+        Commands::List { changed_since: Some(cutoff), name, wrap, filter, tag, stale, relative_dates, utc, done, .. } => {
+            let filter = done_only(list_filter(name.as_deref(), filter.as_deref(), &tag, stale), done);
+            let display = date_display(relative_dates, utc);
+            commands::list::changed_since(store, cutoff, wrap, filter.as_ref(), display)
+        }
+        Commands::List { name, wrap, filter, tag, stale, relative_dates, utc, format, tree, recursive, depth, pin_overdue, done, sort, .. } => {
+            let filter = done_only(list_filter(name.as_deref(), filter.as_deref(), &tag, stale), done);
+            let template = list_template(format);
+            let display = date_display(relative_dates, utc);
+            let layout = list_layout(tree, recursive, depth, pin_overdue, sort);
+            commands::list::run(store, wrap, filter.as_ref(), display, template.as_ref(), layout)
+        }
+        Commands::Has { overdue, due, filter, tag } => {
+            let filter = list_filter(None, filter.as_deref(), &tag, None);
+            commands::has::run(store, overdue, due, filter.as_ref())
+        }
+        Commands::Due { id, date, time, no_auto_remind, end_of_week, cascade } => {
+            commands::due::run(store, id, &date, time.as_deref(), no_auto_remind, end_of_week, cascade)
+        }
+        Commands::Remind { id, date, time, repeat } => commands::remind::run(store, id, &date, time.as_deref(), repeat),
+        Commands::Upcoming { next, short, format, relative_dates, utc, by_urgency } => {
+            let template = format.as_deref().or(short.then_some(commands::upcoming::SHORT_FORMAT));
+            let display = date_display(relative_dates, utc);
+            commands::upcoming::run(store, display, next, template, by_urgency)
+        }
+        Commands::ClearReminder { id, hint } => commands::remind::clear(store, id, &hint),
+        Commands::Tag { id, tags, remove } => commands::tag::run(store, id, &tags, remove),
+        Commands::Priority { id, level, .. } => commands::priority::run(store, id, level),
+        Commands::Repeat { id, rule, .. } => commands::repeat::run(store, id, rule),
+        Commands::Merge { path, ours, theirs, newest } => commands::merge::run(store, &path, merge_strategy(ours, theirs, newest)),
+        Commands::Search { query } => commands::search::run(store, &query),
+        Commands::Grep { pattern, ids_only } => commands::grep::run(store, &pattern, ids_only),
+        Commands::Stats { burndown: false, .. } => commands::stats::run(store),
+        Commands::Stats { burndown: true, tag, days, output } => {
+            print_burndown(&store.todos()?, &tag, days, output);
+            Ok(commands::Output::default())
+        }
+        Commands::Goal { action: GoalAction::Status { count_reopens } } => {
+            print_goals(&store.todos()?, count_reopens);
+            Ok(commands::Output::default())
+        }
+        Commands::Today => commands::today::run(store),
+        Commands::Plan => commands::plan::run(store),
+        Commands::Week { offset } => commands::week::run(store, offset),
+        Commands::Cal { month, week } => commands::cal::run(store, month, week),
+        Commands::Conflicts { max_per_day, weekdays_only, json } => {
+            commands::conflicts::run(store, max_per_day as usize, weekdays_only, json)
+        }
+        Commands::Import { path, format, headings_as_tags, partial, no_date_sanity } => {
+            commands::import::run(store, &path, format, headings_as_tags, partial, !no_date_sanity)
+        }
+        Commands::Export { format, output, group_by } => {
+            write_export(&store.todos()?, format, group_by, output.as_deref());
+            Ok(commands::Output::default())
+        }
+        Commands::Digest { period, html, mail } => {
+            let todos = store.todos()?;
+            digest::run(&todos, period, html, mail, goal_progress(&todos, None));
+            Ok(commands::Output::default())
+        }
+        Commands::Tui { .. }
+        | Commands::Notify { .. }
+        | Commands::Watch { .. }
+        | Commands::Backup { .. }
+        | Commands::Restore { .. }
+        | Commands::DiffBackup { .. }
+        | Commands::Doctor { .. } => {
+            unreachable!("run by the backend's own handler")
+        }
+        Commands::Alias
+        | Commands::Filter { .. }
+        | Commands::Template { action: TemplateAction::List | TemplateAction::Delete { .. } }
+        | Commands::Last { .. }
+        | Commands::History { .. }
+        | Commands::Trash { action: TrashAction::List | TrashAction::Empty { .. } }
+        | Commands::Goal { action: GoalAction::Set { .. } | GoalAction::Delete { .. } }
+        | Commands::Where
+        | Commands::Backend
+        | Commands::Migrate { .. }
+        | Commands::Profile { .. } => {
+            unreachable!("run before the store is opened")
+        }
+        Commands::Shell => unreachable!("the shell runs its commands one at a time through here"),
+    }
+}
+
+/// Write a backup bundle of `todos`, saying which backend they came from with `label`
+fn write_backup(path: &Path, todos: &[Todo], backend: &str, label: &str) {
+    match backup::write_bundle(path, todos, backend) {
+        Ok(()) => messages::say(Msg::BackedUp { count: todos.len(), to: path, label }),
+        Err(e) => messages::say(Msg::BackupFailed { error: &e }),
+    }
+}
+
+fn tui_todos(todos: &[Todo]) -> Vec<TuiTodo> {
+    let stale = stale_filter(config::load().stale_after());
+    todos
+        .iter()
+        .map(|t| TuiTodo {
+            id: t.id,
+            text: t.text.clone(),
+            done: t.done,
+            due_date: t.due_date.clone(),
+            reminder: t.reminder.as_deref().map(local_reminder),
+            tags: t.tags.clone(),
+            priority: t.priority.clone(),
+            parent: t.parent,
+            stale: stale.matches(t),
+        })
+        .collect()
+}
+
+/// Settings for the TUI, with the named filters it can cycle through. The filters see the
+/// TUI's edits by folding each row back into the full todo it came from
+fn tui_settings<'a>(
+    todos: &'a [Todo],
+    start_filter: Option<&str>,
+    pin_overdue: bool,
+    location: tui::Location,
+    watch: Option<tui::Watch<'a>>,
+) -> tui::Settings<'a> {
+    let config = config::load();
+    let mut filters = Vec::new();
+    for (name, (expr, _)) in saved_filters::all() {
+        let filter = match filter::parse(&expr) {
+            Ok(filter) => filter,
+            Err(e) => {
+                messages::say(Msg::SkippingFilter { name: &name, error: &e.render(&expr) });
+                continue;
+            }
+        };
+        filters.push(tui_filter(name, filter, todos));
+    }
+    let start_filter = start_filter.map(|name| {
+        filters.iter().position(|f| f.name == name).unwrap_or_else(|| unknown_filter(name))
+    });
+    tui::Settings {
+        default_reminder_time: config.default_reminder_time(),
+        filters,
+        start_filter,
+        pin_overdue: pin_overdue || config.pin_overdue.unwrap_or(false),
+        bell: config.tui_bell.unwrap_or(true),
+        min_id_width: config.id_width.unwrap_or(0),
+        location,
+        watch,
+        palette: None,
+    }
+}
+
+/// `filter` as one the TUI can show the list through, matching each row as the full todo
+/// it came from
+fn tui_filter<'a>(name: String, filter: Filter, todos: &'a [Todo]) -> tui::SavedFilter<'a> {
+    let todos = changes::by_id(todos);
+    tui::SavedFilter {
+        name,
+        matches: Box::new(move |row: &TuiTodo| filter.matches(&merge_tui_todo(todos.get(&row.id).copied(), row.clone()))),
+    }
+}
+
+/// The TUI's `:` prompt for the JSON file, running each line on `full`, the todos with
+/// the TUI's edits folded in, which starts out as `todos` until the first line is run
+fn json_palette<'a>(full: &'a mut Option<Vec<Todo>>, todos: &'a [Todo], read_only: Option<&'static str>) -> tui::Palette<'a> {
+    Box::new(move |line, rows| {
+        let merged = merge_tui_todos(full.as_deref().unwrap_or(todos), rows.to_vec());
+        palette_line(&mut JsonStore::new(full.insert(merged)), line, todos, read_only)
+    })
+}
+
+/// The TUI's `:` prompt for the database, which the TUI has already saved its edits to
+fn sqlite_palette<'a>(store: &'a RefCell<TuiStore<'_>>, todos: &'a [Todo], read_only: Option<&'static str>) -> tui::Palette<'a> {
+    Box::new(move |line, _| palette_line(&mut *store.borrow_mut(), line, todos, read_only))
+}
+
+/// Run a line typed at the TUI's `:` prompt: `filter EXPR` shows the list through a
+/// filter, and add, done, delete, due and remind run on `store` as they would from the
+/// command line, except that they ask nothing and what they say goes on the status line
+fn palette_line<'a>(store: &mut dyn Store, line: &str, todos: &'a [Todo], read_only: Option<&str>) -> tui::Ran<'a> {
+    let said = |msg: Msg| tui::Ran { todos: None, filter: None, said: Some((msg.level(), msg.to_string())) };
+    let words = match aliases::split(line) {
+        Ok(words) => words,
+        Err(e) => return said(Msg::BadCommand { error: &e }),
+    };
+    if words.first().map(String::as_str) == Some("filter") {
+        let expr = words[1..].join(" ");
+        return match filter::parse(&expr) {
+            Ok(filter) => {
+                let mut ran = said(Msg::Filtered { filter: &expr });
+                ran.filter = Some(tui_filter(expr, filter, todos));
+                ran
+            }
+            Err(e) => said(Msg::BadCommand { error: &format!("Invalid filter: {}", e.message) }),
+        };
+    }
+    if let Some(reason) = read_only {
+        return said(Msg::ReadOnly { reason });
+    }
+    let is_builtin = |name: &str| Cli::command().find_subcommand(name).is_some();
+    let args = std::iter::once("todo".to_string()).chain(words).collect();
+    let args = match aliases::expand(args, &config::load().alias, is_builtin) {
+        Ok(args) => args,
+        Err(e) => return said(Msg::BadCommand { error: &e }),
+    };
+    let parsed = Cli::command().try_get_matches_from(args).and_then(|matches| {
+        let name = matches.subcommand_name().unwrap_or_default().to_string();
+        Ok((Cli::from_arg_matches(&matches)?.command, name))
+    });
+    let (command, name) = match parsed {
+        Ok((Some(command), name)) => (command, name),
+        Ok((None, _)) => return said(Msg::BadCommand { error: "Type a command after the colon, e.g. add buy milk" }),
+        Err(e) => {
+            // clap's message opens with "error: " and goes on to a usage block
+            let e = e.to_string();
+            let first = e.lines().next().unwrap_or_default();
+            return said(Msg::BadCommand { error: first.strip_prefix("error: ").unwrap_or(first) });
+        }
+    };
+    commands::set_no_questions(true);
+    let result = run_palette_command(store, command);
+    commands::set_no_questions(false);
+    let output = match result {
+        None => return said(Msg::CantRunHere { command: &name }),
+        Some(Err(e)) => return said(Msg::NotSaved { error: &e }),
+        Some(Ok(output)) => output,
+    };
+    match store.todos() {
+        Ok(after) => tui::Ran { todos: Some(tui_todos(&after)), filter: None, said: output.summary() },
+        Err(e) => said(Msg::NotSaved { error: &e }),
+    }
+}
+
+/// Run `command` on `store` if it is one the TUI's `:` prompt takes
+fn run_palette_command(store: &mut dyn Store, command: Commands) -> Option<store::Result<commands::Output>> {
+    Some(match command {
+        Commands::Add { template: Some(name), var, print_id, parent, .. } => {
+            commands::add::run_template(store, &name, &var, print_id, parent)
+        }
+        Commands::Add { text, print_id, parent, infer_dates, reopen, clear_dates, new, .. } => {
+            commands::add::run(store, &text, print_id, parent, infer_dates, add_repeat(reopen, clear_dates, new))
+        }
+        Commands::Done { id: Some(id), message, hint, keep_reminder, .. } => {
+            commands::done::run(store, id, &message, &hint, keep_reminder)
+        }
+        Commands::Done { text: Some(text), first, message, keep_reminder, .. } => {
+            commands::with_text(store, &text, first, |store, id| commands::done::run(store, id, &message, &[], keep_reminder))
+        }
+        Commands::Done { id: None, tag, filter, force, message, keep_reminder, .. } => {
+            let parsed = match filter.as_deref().map(filter::parse).transpose() {
+                Ok(parsed) => parsed,
+                Err(e) => {
+                    let mut out = commands::Output::default();
+                    out.msg(Msg::InvalidFilter { error: &e.message });
+                    out.fail();
+                    return Some(Ok(out));
+                }
+            };
+            let filter = parsed.into_iter().chain(tag.iter().map(|tag| Filter::tag(tag))).reduce(Filter::and);
+            commands::done::run_bulk(store, &filter.expect("clap requires --tag or --filter"), &message, force, keep_reminder)
+        }
+        Commands::Delete { text: Some(text), first, .. } => {
+            commands::with_text(store, &text, first, |store, id| commands::delete::run(store, id, &[]))
+        }
+        Commands::Delete { id: None, done, all, before, filter, force, .. } => {
+            commands::delete::run_bulk(store, done, all, before.as_deref(), filter.as_deref(), force)
+        }
+        Commands::Delete { id: Some(id), hint, .. } => commands::delete::run(store, id, &hint),
+        Commands::Due { id, date, time, no_auto_remind, end_of_week, cascade } => {
+            commands::due::run(store, id, &date, time.as_deref(), no_auto_remind, end_of_week, cascade)
+        }
+        Commands::Remind { id, date, time, repeat } => commands::remind::run(store, id, &date, time.as_deref(), repeat),
+        _ => return None,
+    })
+}
+
+/// `tui --watch` for the JSON file: its todos whenever its fingerprint changes, each read
+/// also kept in `synced` so the TUI's edits can be saved against what it last showed
+fn json_watch(synced: Rc<RefCell<Option<Vec<Todo>>>>) -> tui::Watch<'static> {
+    let path = paths::json_path();
+    let mut seen = watch::file_fingerprint(&path);
+    Box::new(move || {
+        let fingerprint = watch::file_fingerprint(&path);
+        if fingerprint == seen {
+            return None;
+        }
+        seen = fingerprint;
+        let todos = load_todos().ok()?;
+        let rows = tui_todos(&todos);
+        *synced.borrow_mut() = Some(todos);
+        Some(rows)
+    })
+}
+
+/// `tui --watch` for the database at `db`, through a connection of its own, as SQLite only
+/// moves data_version on for commits made by other connections. `None` when there is
+/// nothing another process could change, or it can't be opened
+fn sqlite_watch(db: &Path) -> Option<tui::Watch<'static>> {
+    if db == Path::new(MEMORY_DB) {
+        return None;
+    }
+    let conn = match Connection::open_with_flags(db, rusqlite::OpenFlags::SQLITE_OPEN_READ_ONLY) {
+        Ok(conn) => conn,
+        Err(e) => {
+            messages::say(Msg::NotWatching { error: &e });
+            return None;
+        }
+    };
+    let mut seen = watch::data_version(&conn).ok();
+    Some(Box::new(move || {
+        let version = watch::data_version(&conn).ok();
+        if version == seen {
+            return None;
+        }
+        seen = version;
+        load_todos_from_sqlite(&conn).ok().map(|todos| tui_todos(&todos))
+    }))
+}
+
+/// The store at `path` for the TUI's footer, with whether it is read-only
+fn tui_location(backend: &'static str, path: &Path) -> tui::Location {
+    let read_only = if config::load().readonly() {
+        Some("readonly mode is on")
+    } else if !store_writable(path) {
+        Some("the store can't be written to")
+    } else {
+        None
+    };
+    tui::Location {
+        backend,
+        path: path.to_path_buf(),
+        profile: paths::profile().unwrap_or(DEFAULT_PROFILE).to_string(),
+        read_only,
+    }
+}
+
+/// Whether the store at `path` can be saved: the file opens for writing or, where there is
+/// none yet, a file can be made beside it
+fn store_writable(path: &Path) -> bool {
+    if path == Path::new(MEMORY_DB) {
+        return true;
+    }
+    match fs::OpenOptions::new().append(true).open(path) {
+        Ok(_) => true,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => {
+            let dir = path.parent().filter(|dir| !dir.as_os_str().is_empty()).unwrap_or(Path::new("."));
+            !dir.exists() || tempfile::NamedTempFile::new_in(dir).is_ok()
+        }
+        Err(_) => false,
+    }
+}
+
+fn handle_tui_command_json(
+    todos: &mut Vec<Todo>,
+    before: &mut Vec<Todo>,
+    filter: Option<&str>,
+    pin_overdue: bool,
+    plain: bool,
+    watch: bool,
+) {
+    doctor::warn_unreadable(todos);
+    let mut edited = todos.clone();
+    let mut plain = plain;
+    // The store as --watch last read it, which the edits are then saved against
+    let synced: Rc<RefCell<Option<Vec<Todo>>>> = Rc::default();
+    loop {
+        let location = tui_location("JSON", &paths::json_path());
+        let exit = if plain {
+            // The JSON store can't fail
+            run_plain(&mut JsonStore::new(&mut edited), filter, pin_overdue, location).unwrap_or(tui::Exit::Quit)
+        } else {
+            let watch = watch.then(|| json_watch(synced.clone()));
+            // The todos as the commands run at the `:` prompt leave them, once one has run
+            let mut full = None;
+            let mut settings = tui_settings(&edited, filter, pin_overdue, location, watch);
+            settings.palette = Some(json_palette(&mut full, &edited, settings.location.read_only));
+            match tui::run_tui(tui_todos(&edited), settings, None) {
+                Ok((updated_todos, exit)) => {
+                    // The fields the TUI doesn't show can only have been edited there by commands
+                    // run at its `:` prompt, so the rest come from the store as --watch last read
+                    // it where it has
+                    edited = match synced.borrow().clone() {
+                        None => merge_tui_todos(full.as_deref().unwrap_or(&edited), updated_todos),
+                        Some(mut known) => {
+                            let before = changes::by_id(&edited);
+                            let read: HashMap<usize, usize> = known.iter().enumerate().map(|(i, t)| (t.id, i)).collect();
+                            let mut added = Vec::new();
+                            for todo in full.unwrap_or_else(|| edited.clone()) {
+                                match read.get(&todo.id) {
+                                    Some(&i) if before.get(&todo.id) != Some(&&todo) => known[i] = todo,
+                                    Some(_) => {}
+                                    None => added.push(todo),
+                                }
+                            }
+                            known.extend(added);
+                            merge_tui_todos(&known, updated_todos)
+                        }
+                    };
+                    exit
+                }
+                Err(e) => {
+                    messages::say(Msg::TuiFailed { error: &e });
+                    return;
+                }
+            }
+        };
+        if exit == tui::Exit::Quit {
+            break;
+        }
+        plain = !plain;
+    }
+    let current = load_todos().unwrap_or_else(|e| {
+        messages::say(Msg::StoreUnreadable { error: &e });
+        messages::say(Msg::TuiNotSaved);
+        shell::exit(1);
+    });
+    let base = synced.take().unwrap_or_else(|| todos.clone());
+    *todos = rebase_tui_edits(&base, edited, &current).unwrap_or_else(|| current.clone());
+    *before = current;
+}
+
+/// The TUI's plain mode on `store`, showing what the TUI would
+fn run_plain(
+    store: &mut dyn Store,
+    filter: Option<&str>,
+    pin_overdue: bool,
+    location: tui::Location,
+) -> rusqlite::Result<tui::Exit> {
+    let filter = filter.map(|name| parse_filter(&saved_filters::lookup(name).unwrap_or_else(|| unknown_filter(name))));
+    let options = plain::Options {
+        filter,
+        pin_overdue: pin_overdue || config::load().pin_overdue.unwrap_or(false),
+        min_id_width: config::load().id_width.unwrap_or(0),
+        read_only: location.read_only,
+    };
+    plain::run(store, &options)
+}
+
+/// The store the TUI saves each change to as it is made, so quitting uncleanly loses
+/// nothing already done. Keeps each todo it writes as it was first, so what the TUI changed
+/// can be told apart from what other commands changed meanwhile
+struct TuiStore<'a> {
+    store: SqliteStore<'a>,
+    /// Todos as they were before the TUI's first write to each; `None` for ones it added
+    originals: BTreeMap<usize, Option<Todo>>,
+}
+
+impl TuiStore<'_> {
+    fn stored(&mut self, id: usize) -> rusqlite::Result<Option<Todo>> {
+        Ok(self.store.todos()?.into_iter().find(|t| t.id == id))
+    }
+}
+
+/// For the plain mode, which runs the CLI's commands on it, noting what they change
+impl Store for TuiStore<'_> {
+    fn todos(&mut self) -> rusqlite::Result<Vec<Todo>> {
+        self.store.todos()
+    }
+
+    fn insert(&mut self, todo: Todo) -> rusqlite::Result<usize> {
+        let id = self.store.insert(todo)?;
+        self.originals.insert(id, None);
+        Ok(id)
+    }
+
+    fn insert_with_id(&mut self, todo: Todo) -> rusqlite::Result<()> {
+        let id = todo.id;
+        self.store.insert_with_id(todo)?;
+        self.originals.entry(id).or_insert(None);
+        Ok(())
+    }
+
+    fn insert_all(&mut self, todos: Vec<Todo>) -> rusqlite::Result<bool> {
+        let ids: Vec<usize> = todos.iter().map(|t| t.id).collect();
+        let kept = self.store.insert_all(todos)?;
+        if kept {
+            for id in ids {
+                self.originals.entry(id).or_insert(None);
+            }
+        }
+        Ok(kept)
+    }
+
+    fn update(&mut self, todo: &Todo) -> rusqlite::Result<()> {
+        let previous = self.stored(todo.id)?;
+        self.store.update(todo)?;
+        self.originals.entry(todo.id).or_insert(previous);
+        Ok(())
+    }
+
+    fn delete(&mut self, ids: &[usize]) -> rusqlite::Result<usize> {
+        let todos = self.store.todos()?;
+        let deleted = self.store.delete(ids)?;
+        for todo in todos.into_iter().filter(|t| ids.contains(&t.id)) {
+            self.originals.entry(todo.id).or_insert(Some(todo));
+        }
+        Ok(deleted)
+    }
+
+    fn replace(&mut self, todos: Vec<Todo>) -> rusqlite::Result<()> {
+        for todo in self.store.todos()? {
+            self.originals.entry(todo.id).or_insert(Some(todo));
+        }
+        for todo in &todos {
+            self.originals.entry(todo.id).or_insert(None);
+        }
+        self.store.replace(todos)
+    }
+
+    fn label(&self) -> &'static str {
+        self.store.label()
+    }
+}
+
+impl tui::Persist for TuiStore<'_> {
+    fn save(&mut self, row: &TuiTodo) -> Result<(), String> {
+        let previous = self.stored(row.id).map_err(|e| e.to_string())?.ok_or_else(|| format!("todo {} has been deleted elsewhere", row.id))?;
+        let todo = merge_tui_todo(Some(&previous), row.clone());
+        self.store.update(&todo).map_err(|e| e.to_string())?;
+        self.originals.entry(row.id).or_insert(Some(previous));
+        Ok(())
+    }
+
+    fn add(&mut self, row: &TuiTodo) -> Result<usize, String> {
+        let todo = merge_tui_todo(None, row.clone());
+        let id = self.store.insert(todo).map_err(|e| e.to_string())?;
+        self.originals.insert(id, None);
+        Ok(id)
+    }
+
+    fn delete(&mut self, id: usize) -> Result<(), String> {
+        let previous = self.stored(id).map_err(|e| e.to_string())?;
+        self.store.delete(&[id]).map_err(|e| e.to_string())?;
+        self.originals.entry(id).or_insert(previous);
+        Ok(())
+    }
+
+    fn reload(&mut self, id: usize) -> Result<Option<TuiTodo>, String> {
+        Ok(self.stored(id).map_err(|e| e.to_string())?.map(|todo| tui_todos(&[todo]).remove(0)))
+    }
+}
+
+/// Lets the TUI save to the store its `:` prompt also runs commands on
+impl tui::Persist for &RefCell<TuiStore<'_>> {
+    fn save(&mut self, row: &TuiTodo) -> Result<(), String> {
+        tui::Persist::save(&mut *self.borrow_mut(), row)
+    }
+
+    fn add(&mut self, row: &TuiTodo) -> Result<usize, String> {
+        tui::Persist::add(&mut *self.borrow_mut(), row)
+    }
+
+    fn delete(&mut self, id: usize) -> Result<(), String> {
+        tui::Persist::delete(&mut *self.borrow_mut(), id)
+    }
+
+    fn reload(&mut self, id: usize) -> Result<Option<TuiTodo>, String> {
+        tui::Persist::reload(&mut *self.borrow_mut(), id)
+    }
+}
+
+/// `current` with the todos the TUI wrote put back as they were, so comparing the two
+/// shows only the TUI's changes
+fn tui_baseline(current: &[Todo], originals: BTreeMap<usize, Option<Todo>>) -> Vec<Todo> {
+    let mut baseline: Vec<Todo> = current.iter().filter(|t| !originals.contains_key(&t.id)).cloned().collect();
+    baseline.extend(originals.into_values().flatten());
+    baseline.sort_by_key(|t| t.id);
+    baseline
+}
+
+/// The TUI's edits to `base` carried over to `current`, the store as it is now, which
+/// other commands may have changed while the TUI was open. Edits to different todos are
+/// merged; where both sides changed the same todo the user picks whose changes to keep,
+/// or `None` to drop the TUI's edits and leave the store as it is
+fn rebase_tui_edits(base: &[Todo], edited: Vec<Todo>, current: &[Todo]) -> Option<Vec<Todo>> {
+    if current == base {
+        return Some(edited);
+    }
+    let rebased = merge::rebase(base, edited, current.to_vec());
+    if rebased.conflicts.is_empty() {
+        messages::say(Msg::TuiChangesKept);
+        return Some(rebased.todos);
+    }
+    messages::say(Msg::TuiConflicts { count: rebased.conflicts.len() });
+    let display = DateDisplay::default();
+    let show = |todo: &Option<Todo>| todo.as_ref().map_or("(deleted)".to_string(), |t| format_todo(t, 0, None, false, display));
+    for conflict in &rebased.conflicts {
+        eprintln!("   Yours:  {}", show(&conflict.ours));
+        eprintln!("   Theirs: {}", show(&conflict.theirs));
+    }
+    let choice = dialoguer::Select::new()
+        .with_prompt("Keep which changes to these?")
+        .items(&["Yours, from the TUI", "Theirs, already saved", "Neither; drop everything done in the TUI"])
+        .default(0)
+        .interact();
+    match choice {
+        Ok(0) => Some(rebased.settle(merge::Side::Ours)),
+        Ok(2) => None,
+        _ => Some(rebased.settle(merge::Side::Theirs)),
+    }
+}
+
+/// Report an unknown id with nearby ids and fuzzy text matches, then exit non-zero
+fn not_found(id: usize, todos: &[Todo], hint: &[String]) -> ! {
+    commands::not_found(id, todos, hint).emit();
+    shell::exit(1);
+}
+
+/// Parse a filter expression, pointing at the offending token and exiting if it is malformed
+fn parse_filter(expr: &str) -> Filter {
+    match filter::parse(expr) {
+        Ok(filter) => filter,
+        Err(e) => {
+            messages::say(Msg::InvalidFilter { error: &e.render(expr) });
+            shell::exit(1);
+        }
+    }
+}
+
+/// The filter `list` applies from a saved filter's name and its --filter, --tag and --stale
+/// flags, if any
+fn list_filter(name: Option<&str>, expr: Option<&str>, tags: &[String], stale: Option<chrono::Duration>) -> Option<Filter> {
+    let saved = name.map(|name| saved_filters::lookup(name).unwrap_or_else(|| unknown_filter(name)));
+    saved
+        .as_deref()
+        .map(parse_filter)
+        .into_iter()
+        .chain(expr.map(parse_filter))
+        .chain(tags.iter().map(|tag| Filter::tag(tag)))
+        .chain(stale.map(stale_filter))
+        .reduce(Filter::and)
+}
+
+/// Report a filter name that is neither in the config nor saved, then exit non-zero
+fn unknown_filter(name: &str) -> ! {
+    messages::say(Msg::NoFilter { name });
+    let names: Vec<String> = saved_filters::all().into_keys().collect();
+    if !names.is_empty() {
+        eprintln!("   Known filters: {}", names.join(", "));
+    }
+    shell::exit(1);
+}
+
+fn json_notify_files() -> notify::Files {
+    notify::Files { markers: Some(paths::notified_path()), lock: Some(paths::lock_path()) }
+}
+
+fn db_notify_files(db: &Path) -> notify::Files {
+    notify::Files { markers: db_side_file(db, "notified.json"), lock: db_side_file(db, "lock") }
+}
+
+fn watch_options(interval: u64, bell: bool) -> watch::Options {
+    let config = config::load();
+    let mut notifiers = delivery::targets(config);
+    notifiers.extend(delivery::reminder_command(config));
+    watch::Options { interval: Duration::from_secs(interval), bell, notifiers }
+}
+
+/// Run `notify`; a dry run says what it would announce or reset, leaving the markers be
+fn run_notify(todos: &[Todo], files: &notify::Files, reset: Option<usize>, mode: WriteMode) {
+    doctor::warn_unreadable(todos);
+    let dry_run = mode == WriteMode::DryRun;
+    let result = match reset {
+        Some(id) => {
+            let found = if dry_run { Ok(notify::announced(files, id)) } else { notify::reset(files, id) };
+            found.map(|found| {
+                if found {
+                    messages::say(Msg::ReminderReset { id });
+                } else {
+                    println!("The reminder for todo {} has not been announced", id);
+                }
+            })
+        }
+        None if dry_run => {
+            notify::preview(todos, files, Utc::now());
+            Ok(())
+        }
+        None => notify::run(todos, files, Utc::now(), &delivery::targets(config::load())),
+    };
+    if let Err(e) = result {
+        messages::say(Msg::CouldNotCheckReminders { error: &e });
+        shell::exit(1);
+    }
+}
+
+/// Print a remembered id, exiting non-zero when there is none
+fn print_last(which: LastKind) {
+    let state = last::read();
+    let id = match which {
+        LastKind::Added => state.added,
+        LastKind::Done => state.done,
+        LastKind::Modified => state.modified,
+    };
+    match id {
+        Some(id) => println!("{}", id),
+        None => shell::exit(1),
+    }
+}
+
+fn print_trash() {
+    let trashed = read_trash_or_exit();
+    if trashed.is_empty() {
+        messages::say(Msg::TrashEmpty);
+    }
+    for entry in trashed {
+        let deleted = DateTime::parse_from_rfc3339(&entry.deleted_at)
+            .map(|at| dates::display_datetime(&at.with_timezone(&Utc), DateDisplay::default()))
+            .unwrap_or(entry.deleted_at);
+        println!("#{} {} (deleted {})", entry.todo.id, text::escape(&entry.todo.text), deleted);
+    }
+}
+
+fn empty_trash(force: bool, mode: WriteMode) {
+    let count = read_trash_or_exit().len();
+    if count == 0 {
+        messages::say(Msg::TrashEmpty);
+        return;
+    }
+    let todos = if count == 1 { "1 todo".to_string() } else { format!("{} todos", count) };
+    let mut out = commands::Output::default();
+    if !commands::may_write(mode, || format!("would delete the {} in the trash for good", todos), &mut out) {
+        return out.emit();
+    }
+    if !force && !confirm(&format!("Delete the {} in the trash for good?", todos)) {
+        println!("Nothing deleted");
+        return;
+    }
+    match trash::write(&[]) {
+        Ok(()) => messages::say(Msg::TrashEmptied { count }),
+        Err(e) => {
+            messages::say(Msg::TrashNotEmptied { error: &e });
+            shell::exit(1);
+        }
+    }
+}
+
+/// What is in the trash, exiting when it can't be read
+fn read_trash_or_exit() -> Vec<trash::Trashed> {
+    trash::read().unwrap_or_else(|e| {
+        messages::say(Msg::TrashUnreadable { error: &e });
+        shell::exit(1);
+    })
+}
+
+fn print_history(id: Option<usize>) {
+    let (events, unreadable) = match events::read() {
+        Ok(read) => read,
+        Err(e) => {
+            messages::say(Msg::EventsUnreadable { error: &e });
+            shell::exit(1);
+        }
+    };
+    if unreadable > 0 {
+        messages::say(Msg::SkippedEvents { count: unreadable });
+    }
+    let shown: Vec<&events::Event> = events.iter().filter(|e| id.is_none_or(|id| e.id == id)).collect();
+    if shown.is_empty() {
+        match id {
+            Some(id) => println!("No recorded changes to todo {}", id),
+            None if config::load().events_log != Some(true) => {
+                println!("No recorded changes; set events_log = true in {} to start", paths::config_path().display())
+            }
+            None => println!("No recorded changes"),
+        }
+    }
+    for event in shown {
+        println!("{}", events::describe(event));
+    }
+}
+
+fn print_aliases() {
+    let aliases = &config::load().alias;
+    if aliases.is_empty() {
+        println!("No aliases; add them under [alias] in {}", paths::config_path().display());
+    }
+    let cli = Cli::command();
+    for (name, value) in aliases {
+        let shadowed = if cli.find_subcommand(name).is_some() { " (ignored, a built-in command)" } else { "" };
+        println!("{} = {}{}", name, value, shadowed);
+    }
+}
+
+fn handle_profile_command(action: ProfileAction, mode: WriteMode) {
+    match action {
+        ProfileAction::List => {
+            let profiles = match paths::profiles() {
+                Ok(profiles) => profiles,
+                Err(e) => {
+                    messages::say(Msg::CouldNotRead { what: &"the profiles", error: &e });
+                    shell::exit(1);
+                }
+            };
+            let current = paths::profile().unwrap_or(DEFAULT_PROFILE);
+            for name in std::iter::once(DEFAULT_PROFILE).chain(profiles.iter().map(String::as_str)) {
+                let marker = if name == current { "*" } else { " " };
+                println!("{} {}", marker, name);
+            }
+        }
+        ProfileAction::Create { name } => {
+            if !paths::valid_profile(&name) || name == DEFAULT_PROFILE {
+                messages::say(Msg::BadProfileName { default: DEFAULT_PROFILE });
+                shell::exit(1);
+            }
+            let dir = paths::profile_dir(&name);
+            if dir.exists() {
+                messages::say(Msg::ProfileExists { name: &name });
+                shell::exit(1);
+            }
+            let mut out = commands::Output::default();
+            if !commands::may_write(mode, || format!("would create profile '{}' in {}", name, dir.display()), &mut out) {
+                return out.emit();
+            }
+            match fs::create_dir_all(&dir) {
+                Ok(()) => messages::say(Msg::ProfileCreated { name: &name, dir: &dir }),
+                Err(e) => {
+                    messages::say(Msg::CouldNotCreate { path: &dir, error: &e });
+                    shell::exit(1);
+                }
+            }
+        }
+    }
+}
+
+fn handle_filter_command(action: FilterAction, mode: WriteMode) {
+    match action {
+        FilterAction::Save { name, expr } => {
+            let expr = expr.join(" ");
+            if !saved_filters::valid_name(&name) {
+                messages::say(Msg::BadFilterName);
+                shell::exit(1);
+            }
+            parse_filter(&expr);
+            if saved_filters::all().get(&name).is_some_and(|(_, source)| *source == saved_filters::Source::Config) {
+                messages::say(Msg::FilterInConfig { name: &name, config: &paths::config_path(), action: "change" });
+                shell::exit(1);
+            }
+            let mut out = commands::Output::default();
+            if !commands::may_write(mode, || format!("would save filter '{}': {}", name, expr), &mut out) {
+                return out.emit();
+            }
+            match saved_filters::save(&name, &expr) {
+                Ok(()) => messages::say(Msg::FilterSaved { name: &name, expr: &expr }),
+                Err(e) => {
+                    messages::say(Msg::CouldNotSave { what: "filter", error: &e });
+                    shell::exit(1);
+                }
+            }
+        }
+        FilterAction::List => {
+            let filters = saved_filters::all();
+            if filters.is_empty() {
+                println!("No saved filters");
+            }
+            for (name, (expr, source)) in filters {
+                let origin = match source {
+                    saved_filters::Source::Config => " (config)",
+                    saved_filters::Source::Saved => "",
+                };
+                println!("{}: {}{}", name, expr, origin);
+            }
+        }
+        FilterAction::Delete { name } => {
+            if !saved_filters::is_saved(&name) {
+                if saved_filters::lookup(&name).is_some() {
+                    messages::say(Msg::FilterInConfig { name: &name, config: &paths::config_path(), action: "remove" });
+                    shell::exit(1);
+                }
+                unknown_filter(&name);
+            }
+            let mut out = commands::Output::default();
+            if !commands::may_write(mode, || format!("would delete filter '{}'", name), &mut out) {
+                return out.emit();
+            }
+            match saved_filters::delete(&name) {
+                Ok(_) => messages::say(Msg::FilterDeleted { name: &name }),
+                Err(e) => {
+                    messages::say(Msg::CouldNotDelete { what: "filter", error: &e });
+                    shell::exit(1);
+                }
+            }
+        }
+    }
+}
+
+fn print_templates() {
+    let templates = saved_templates::all();
+    if templates.is_empty() {
+        println!("No saved templates");
+    }
+    for (name, template) in templates {
+        let tags = template.tags.iter().map(|t| format!("#{}", t));
+        let details: Vec<String> = tags
+            .chain(template.priority.map(|p| format!("priority {}", p)))
+            .chain(template.due.map(|due| format!("due {}", due)))
+            .chain(template.reminder.map(|reminder| format!("reminder {}", reminder)))
+            .collect();
+        if details.is_empty() {
+            println!("{}: {}", name, text::escape(&template.text));
+        } else {
+            println!("{}: {} ({})", name, text::escape(&template.text), details.join(", "));
+        }
+    }
+}
+
+fn delete_template(name: &str, mode: WriteMode) {
+    if !saved_templates::all().contains_key(name) {
+        messages::say(Msg::NoTemplate { name });
+        shell::exit(1);
+    }
+    let mut out = commands::Output::default();
+    if !commands::may_write(mode, || format!("would delete template '{}'", name), &mut out) {
+        return out.emit();
+    }
+    match saved_templates::delete(name) {
+        Ok(true) => messages::say(Msg::TemplateDeleted { name }),
+        Ok(false) => {
+            messages::say(Msg::NoTemplate { name });
+            shell::exit(1);
+        }
+        Err(e) => messages::say(Msg::CouldNotDelete { what: "template", error: &e }),
+    }
+}
+
+/// The todos `diff-backup` compares the store at `store` with, and what to call them: the
+/// bundle at `bundle`, or else the `nth` newest safety copy `restore` made of the store
+fn read_backup(nth: usize, bundle: Option<&Path>, store: &Path, sqlite: bool) -> (Vec<Todo>, String) {
+    if let Some(path) = bundle {
+        return match backup::read_bundle(path) {
+            Ok(mut bundle) => {
+                reminders::migrate_reminders(&mut bundle.todos);
+                let name = format!("the backup at {} ({})", path.display(), bundle.manifest.created_at);
+                (bundle.todos, name)
+            }
+            Err(e) => {
+                messages::say(Msg::BackupUnreadable { error: &e });
+                shell::exit(1);
+            }
+        };
+    }
+    let copies = backup::safety_copies(store);
+    let Some(copy) = nth.checked_sub(1).and_then(|i| copies.get(i)) else {
+        match copies.len() {
+            0 => messages::say(Msg::NoSafetyCopies { store }),
+            count => messages::say(Msg::SafetyCopyOutOfRange { count, store }),
+        }
+        shell::exit(1);
+    };
+    let todos = if sqlite {
+        Connection::open_with_flags(copy, rusqlite::OpenFlags::SQLITE_OPEN_READ_ONLY)
+            .and_then(|conn| load_todos_from_sqlite(&conn))
+            .map_err(|e| e.to_string())
+    } else {
+        fs::read_to_string(copy)
+            .map_err(|e| e.to_string())
+            .and_then(|data| json_file::parse_todos(&data).map_err(|e| e.to_string()))
+    };
+    match todos {
+        Ok(mut todos) => {
+            reminders::migrate_reminders(&mut todos);
+            (todos, copy.display().to_string())
+        }
+        Err(e) => {
+            messages::say(Msg::CouldNotRead { what: &copy.display(), error: &e });
+            shell::exit(1);
+        }
+    }
+}
+
+fn write_export(todos: &[Todo], format: exchange::Format, group_by: Option<exchange::GroupBy>, output: Option<&Path>) {
+    if format == exchange::Format::GoogleTasks {
+        messages::say(Msg::CantExportGoogleTasks);
+        shell::exit(1);
+    }
+    let rendered = exchange::render(format, todos, group_by);
+    let _hold = cancel::hold();
+    match output {
+        Some(path) => match fs::write(path, rendered) {
+            Ok(()) => messages::say(Msg::Exported { count: todos.len(), to: path }),
+            Err(e) => messages::say(Msg::CouldNotWrite { what: &path.display(), error: &e }),
+        },
+        None => print!("{}", rendered),
+    }
+}
+
+/// Characters tags can't contain, since they are stored comma-separated and used in filters
+const TAG_FORBIDDEN: &str = ",():=<>\"";
+
+fn valid_tag(tag: &str) -> bool {
+    !tag.is_empty() && !tag.contains(|c: char| c.is_whitespace() || TAG_FORBIDDEN.contains(c))
+}
+
+/// Normalize tags given on the command line: lowercase, without a leading #
+fn parse_tags(raw: &[String]) -> Option<Vec<String>> {
+    match normalize_tags(raw.iter().map(String::as_str)) {
+        Ok(tags) => Some(tags),
+        Err(e) => {
+            messages::say(Msg::Failed { error: &e });
+            None
+        }
+    }
+}
+
+/// Tags typed as a comma-separated list, as the TUI edits them
+fn read_tags(raw: &str) -> Result<Vec<String>, String> {
+    normalize_tags(raw.split(','))
+}
+
+/// Trimmed, lowercase and without a leading #, dropping blanks and repeats
+fn normalize_tags<'a>(raw: impl IntoIterator<Item = &'a str>) -> Result<Vec<String>, String> {
+    let mut tags = Vec::new();
+    for tag in raw {
+        let tag = tag.trim().trim_start_matches('#').to_lowercase();
+        if tag.is_empty() || tags.contains(&tag) {
+            continue;
+        }
+        if !valid_tag(&tag) {
+            return Err(format!("Invalid tag '{}'; tags can't contain spaces, commas or ():=<>\"", tag));
+        }
+        tags.push(tag);
+    }
+    Ok(tags)
+}
+
+fn tag_summary(tags: &[String]) -> String {
+    if tags.is_empty() {
+        "none".to_string()
+    } else {
+        tags.iter().map(|t| format!("#{}", t)).collect::<Vec<_>>().join(" ")
+    }
+}
+
+/// Tags as stored in the SQLite `tags` column: comma-separated, NULL when there are none
+fn join_tags(tags: &[String]) -> Option<String> {
+    (!tags.is_empty()).then(|| tags.join(","))
+}
+
+fn split_tags(column: Option<String>) -> Vec<String> {
+    column
+        .map(|tags| tags.split(',').filter(|t| !t.is_empty()).map(str::to_string).collect())
+        .unwrap_or_default()
+}
+
+/// Ask a yes/no question, treating a non-interactive terminal as "no"
+fn confirm(prompt: &str) -> bool {
+    if !commands::can_ask() {
+        return false;
+    }
+    dialoguer::Confirm::new()
+        .with_prompt(prompt)
+        .default(false)
+        .interact()
+        .unwrap_or(false)
+}
+
+/// Ids are never renumbered, and a new todo gets one past the highest the store has ever
+/// given out, including to todos since deleted and emptied from the trash
+fn next_id(todos: &[Todo]) -> usize {
+    ids::next_after(todos.iter().map(|t| t.id).max().unwrap_or(0))
+}
+
+/// A stored reminder as the local wall-clock time the TUI shows and edits
+fn local_reminder(reminder: &str) -> String {
+    dates::display_reminder(reminder, DateDisplay::default())
+}
+
+/// Fold the TUI's edited rows back into full todos, keeping the fields the TUI doesn't show
+fn merge_tui_todos(original: &[Todo], updated: Vec<TuiTodo>) -> Vec<Todo> {
+    let original = changes::by_id(original);
+    updated.into_iter().map(|t| merge_tui_todo(original.get(&t.id).copied(), t)).collect()
+}
+
+/// One of the TUI's rows folded back into `previous`, the todo it was made from, if any
+fn merge_tui_todo(previous: Option<&Todo>, t: TuiTodo) -> Todo {
+    let created_at = match previous {
+        Some(p) => p.created_at.clone(),
+        None => Some(now_timestamp()),
+    };
+    let completed_at = match previous {
+        _ if !t.done => None,
+        Some(p) if p.done => p.completed_at.clone(),
+        _ => Some(now_timestamp()),
+    };
+    let reminder_repeat = match previous {
+        Some(p) if t.reminder.is_some() => p.reminder_repeat.clone(),
+        _ => None,
+    };
+    let uuid = previous.map_or_else(uuid::new_v4, |p| p.uuid.clone());
+    Todo {
+        id: t.id,
+        text: t.text,
+        done: t.done,
+        due_date: t.due_date,
+        reminder: t.reminder.map(|r| reminders::upgrade_reminder(&r).unwrap_or(r)),
+        created_at,
+        completed_at,
+        reminder_repeat,
+        tags: t.tags,
+        priority: t.priority,
+        uuid,
+        ..previous.cloned().unwrap_or_default()
+    }
+}
+
+fn load_todos_from_sqlite(conn: &Connection) -> rusqlite::Result<Vec<Todo>> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, text, done, due_date, reminder, created_at, completed_at, reminder_repeat,
+                    completion_note, tags, priority, uuid, parent, updated_at, recurrence
+             FROM todos ORDER BY id ASC",
+        )?;
+
+    let mut dirty = Vec::new();
+    let rows = stmt
+        .query_map([], |row| {
+            let id = row.get(0)?;
+            let done = match done_value(row.get_ref(2)?) {
+                Ok(done) => done,
+                Err(raw) => {
+                    dirty.push((id, raw.clone()));
+                    doctor::coerce_done(&raw).unwrap_or(false)
+                }
+            };
+            Ok(Todo {
+                id,
+                text: row.get(1)?,
+                done,
+                due_date: row.get(3)?,
+                reminder: row.get(4)?,
+                created_at: row.get(5)?,
+                completed_at: row.get(6)?,
+                reminder_repeat: row.get(7)?,
+                completion_note: row.get(8)?,
+                tags: split_tags(row.get(9)?),
+                priority: row.get(10)?,
+                uuid: row.get::<_, Option<String>>(11)?.unwrap_or_default(),
+                parent: row.get(12)?,
+                updated_at: row.get(13)?,
+                recurrence: row.get(14)?,
+            })
+        })?;
+
+    let todos = rows.collect();
+    doctor::warn_dirty_done(&dirty);
+    todos
+}
+
+/// A `done` value as this program writes it, 0 or 1, or else the value as text to report
+fn done_value(value: ValueRef) -> Result<bool, String> {
+    match value {
+        ValueRef::Integer(0) => Ok(false),
+        ValueRef::Integer(1) => Ok(true),
+        ValueRef::Null => Err("NULL".to_string()),
+        ValueRef::Integer(n) => Err(n.to_string()),
+        ValueRef::Real(n) => Err(n.to_string()),
+        ValueRef::Text(text) => Err(format!("'{}'", String::from_utf8_lossy(text))),
+        ValueRef::Blob(_) => Err("a blob".to_string()),
+    }
+}
+
+/// The rows whose `done` value `load_todos_from_sqlite` had to coerce, for doctor
+fn dirty_done_rows(conn: &Connection) -> rusqlite::Result<Vec<(usize, String)>> {
+    let mut stmt = conn.prepare("SELECT id, done FROM todos ORDER BY id ASC")?;
+    let rows = stmt.query_map([], |row| Ok((row.get::<_, usize>(0)?, done_value(row.get_ref(1)?))))?;
+    let mut dirty = Vec::new();
+    for row in rows {
+        if let (id, Err(raw)) = row? {
+            dirty.push((id, raw));
+        }
+    }
+    Ok(dirty)
+}
+
+fn save_todos_to_sqlite(conn: &mut Connection, todos: &[Todo]) -> rusqlite::Result<()> {
+    // A savepoint rather than a transaction so this also works inside a dry run's transaction
+    let tx = conn.savepoint()?;
+    tx.execute("DELETE FROM todos", [])?;
+
+    for todo in todos {
+        tx.execute(
+            "INSERT INTO todos (id, text, done, due_date, reminder, created_at, completed_at, reminder_repeat,
+                                completion_note, tags, priority, uuid, parent, updated_at, recurrence)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15)",
+            params![
+                todo.id,
+                todo.text,
+                todo.done,
+                todo.due_date,
+                todo.reminder,
+                todo.created_at,
+                todo.completed_at,
+                todo.reminder_repeat,
+                todo.completion_note,
+                join_tags(&todo.tags),
+                todo.priority,
+                todo.uuid,
+                todo.parent,
+                todo.updated_at,
+                todo.recurrence
+            ],
+        )?;
+    }
+
+    tx.commit()
+}
+
+/// Open the database at `path` in WAL mode, waiting out other writers for up to
+/// `busy_timeout` instead of failing straight away. `:memory:` gets a fresh database
+/// that no other process can see, so it needs neither
+fn init_db(path: &Path, busy_timeout: Duration) -> rusqlite::Result<Connection> {
+    let conn = if path == Path::new(MEMORY_DB) {
+        Connection::open_in_memory()?
+    } else {
+        if let Some(dir) = path.parent()
+            && !dir.as_os_str().is_empty()
+        {
+            // A failure here shows up as the open failing just below
+            let _ = fs::create_dir_all(dir);
+        }
+        let conn = Connection::open(path)?;
+        conn.busy_timeout(busy_timeout)?;
+        conn.query_row("PRAGMA journal_mode=WAL", [], |row| row.get::<_, String>(0))?;
+        conn
+    };
+    // A database this version has already set up needs none of the checks below
+    let version: u32 = conn.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+    if version == SCHEMA_VERSION {
+        raise_id_floor(&conn)?;
+        return Ok(conn);
+    }
+    conn.execute(&format!("CREATE TABLE IF NOT EXISTS todos ({})", TODOS_TABLE), [])?;
+    for (name, definition) in ADDED_COLUMNS {
+        ensure_column(&conn, name, definition)?;
+    }
+    migrate_autoincrement(&conn)?;
+    migrate_reminder_column(&conn)?;
+    if version < 12 {
+        migrate_recurrence_column(&conn)?;
+    }
+    backfill_uuid_column(&conn)?;
+    // After migrate_autoincrement, as rebuilding the table drops its indexes
+    for column in INDEXED_COLUMNS {
+        conn.execute(&format!("CREATE INDEX IF NOT EXISTS todos_{0} ON todos ({0})", column), [])?;
+    }
+    // Never lower it, so a newer binary's database isn't taken for an older one's
+    if version < SCHEMA_VERSION {
+        conn.execute_batch(&format!("PRAGMA user_version = {}", SCHEMA_VERSION))?;
+    }
+    raise_id_floor(&conn)?;
+    Ok(conn)
+}
+
+/// Keep ids handed out by hand, as an import or a merge does, above every id AUTOINCREMENT
+/// has already given out, so they don't take one back that SQLite never would
+fn raise_id_floor(conn: &Connection) -> rusqlite::Result<()> {
+    let given_out: Option<i64> = conn
+        .query_row("SELECT seq FROM sqlite_sequence WHERE name = 'todos'", [], |row| row.get(0))
+        .optional()?;
+    ids::set_floor(given_out.map_or(0, |seq| seq.max(0) as usize) + 1);
+    Ok(())
+}
+
+/// The todos table. AUTOINCREMENT keeps SQLite from handing out the id of the newest todo
+/// again once it is deleted, which a restore from the trash would want back
+const TODOS_TABLE: &str = "
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    text TEXT NOT NULL,
+    done BOOLEAN NOT NULL DEFAULT 0,
+    due_date TEXT,
+    reminder TEXT,
+    created_at TEXT,
+    completed_at TEXT,
+    reminder_repeat TEXT,
+    completion_note TEXT,
+    tags TEXT,
+    priority TEXT,
+    uuid TEXT,
+    parent INTEGER,
+    updated_at TEXT,
+    recurrence TEXT
+";
+
+/// Rebuild a table made before ids were AUTOINCREMENT, keeping every row and its id
+fn migrate_autoincrement(conn: &Connection) -> rusqlite::Result<()> {
+    let sql: String = conn.query_row("SELECT sql FROM sqlite_master WHERE type = 'table' AND name = 'todos'", [], |row| {
+        row.get(0)
+    })?;
+    if sql.to_uppercase().contains("AUTOINCREMENT") {
+        return Ok(());
+    }
+    let columns = expected_columns().join(", ");
+    conn.execute_batch(&format!(
+        "SAVEPOINT rebuild_todos;
+         CREATE TABLE todos_autoincrement ({table});
+         INSERT INTO todos_autoincrement ({columns}) SELECT {columns} FROM todos;
+         DROP TABLE todos;
+         ALTER TABLE todos_autoincrement RENAME TO todos;
+         RELEASE rebuild_todos;",
+        table = TODOS_TABLE,
+        columns = columns
+    ))
+}
+
+/// Give rows from before todos had UUIDs one each; runs once, as filled rows are skipped
+fn backfill_uuid_column(conn: &Connection) -> rusqlite::Result<()> {
+    let ids: Vec<i64> = conn
+        .prepare("SELECT id FROM todos WHERE uuid IS NULL OR uuid = ''")?
+        .query_map([], |row| row.get(0))?
+        .collect::<rusqlite::Result<_>>()?;
+    for id in ids {
+        conn.execute("UPDATE todos SET uuid = ?1 WHERE id = ?2", params![uuid::new_v4(), id])?;
+    }
+    Ok(())
+}
+
+/// Give todos from before recurrence was its own column the recurrence they had then, as
+/// `reminders::carry_recurrence` does for JSON stores
+fn migrate_recurrence_column(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute(
+        "UPDATE todos SET recurrence = reminder_repeat
+         WHERE due_date IS NOT NULL AND recurrence IS NULL AND reminder_repeat IS NOT NULL",
+        [],
+    )?;
+    Ok(())
+}
+
+/// Rewrite reminders stored as naive local times into RFC 3339 with an offset
+fn migrate_reminder_column(conn: &Connection) -> rusqlite::Result<()> {
+    let mut stmt = conn.prepare("SELECT id, reminder FROM todos WHERE reminder IS NOT NULL")?;
+    let upgrades: Vec<(i64, String)> = stmt
+        .query_map([], |row| Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?)))?
+        .collect::<rusqlite::Result<Vec<_>>>()?
+        .into_iter()
+        .filter_map(|(id, reminder)| reminders::upgrade_reminder(&reminder).map(|r| (id, r)))
+        .collect();
+    for (id, reminder) in &upgrades {
+        conn.execute("UPDATE todos SET reminder = ?1 WHERE id = ?2", params![reminder, id])?;
+    }
+    report_migrated_reminders(upgrades.len());
+    Ok(())
+}
+
+/// Columns commands filter on, indexed so those filters needn't read every row
+const INDEXED_COLUMNS: &[&str] = &["done", "due_date", "reminder"];
+
+/// Columns added after the original schema, in the order they were introduced
+const ADDED_COLUMNS: &[(&str, &str)] = &[
+    ("created_at", "TEXT"),
+    ("completed_at", "TEXT"),
+    ("reminder_repeat", "TEXT"),
+    ("completion_note", "TEXT"),
+    ("tags", "TEXT"),
+    ("priority", "TEXT"),
+    ("uuid", "TEXT"),
+    ("parent", "INTEGER"),
+    ("updated_at", "TEXT"),
+    ("recurrence", "TEXT"),
+];
+
+fn expected_columns() -> Vec<&'static str> {
+    let mut columns = vec!["id", "text", "done", "due_date", "reminder"];
+    columns.extend(ADDED_COLUMNS.iter().map(|(name, _)| *name));
+    columns
+}
+
+fn table_columns(conn: &Connection) -> rusqlite::Result<Vec<String>> {
+    let mut stmt = conn.prepare("PRAGMA table_info(todos)")?;
+    stmt.query_map([], |row| row.get::<_, String>(1))?
+        .collect()
+}
+
+/// Add a column to databases created before it existed
+fn ensure_column(conn: &Connection, name: &str, definition: &str) -> rusqlite::Result<()> {
+    if !table_columns(conn)?.iter().any(|column| column == name) {
+        conn.execute(&format!("ALTER TABLE todos ADD COLUMN {} {}", name, definition), [])?;
+    }
+    Ok(())
+}
+
+/// The JSON store's todos, none when there is no store yet. A store that can't be read is
+/// an error rather than no todos, so that nothing saves over the todos it still holds
+fn load_todos() -> io::Result<Vec<Todo>> {
+    let path = paths::json_path();
+    if !path.exists() {
+        return Ok(vec![]);
+    }
+    let unreadable = |e: &dyn std::fmt::Display| io::Error::new(io::ErrorKind::InvalidData, format!("{}: {}", path.display(), e));
+    let data = fs::read_to_string(&path).map_err(|e| unreadable(&e))?;
+    let stored = json_file::parse(&data).map_err(|e| unreadable(&e))?;
+    json_file::warn_too_new(&path, stored.version);
+    json_file::warn_torn(&path, stored.torn);
+    let mut todos = stored.todos;
+    ids::set_floor(stored.next_id);
+    let repaired = repair_ids(&mut todos);
+    for (old, new, text) in &repaired {
+        messages::say(Msg::IdReassigned { old: *old, text, new: *new });
+    }
+    let migrated = reminders::migrate_reminders(&mut todos);
+    report_migrated_reminders(migrated);
+    // Saved with the rest of the store, so each todo gets its UUID only once
+    let filled = uuid::backfill(&mut todos);
+    let outdated = stored.version < json_file::VERSION || stored.torn > 0;
+    json_file::set_rewrite(outdated || !repaired.is_empty() || migrated > 0 || filled > 0);
+    Ok(todos)
+}
+
+/// `load_todos`, exiting when the store can't be read
+fn load_todos_or_exit() -> Vec<Todo> {
+    load_todos().unwrap_or_else(|e| {
+        messages::say(Msg::StoreUnreadable { error: &e });
+        shell::exit(1);
+    })
+}
+
+/// One-time notice for reminders stored before they carried a UTC offset
+fn report_migrated_reminders(count: usize) {
+    if count > 0 {
+        messages::say(Msg::RemindersConverted { count, offset: &Local::now().format("%:z").to_string() });
+    }
+}
+
+/// Give later duplicates and zero ids fresh ids, keeping the first todo with each id untouched.
+/// Returns `(old, new, text)` for every reassignment so the caller can report it.
+fn repair_ids(todos: &mut [Todo]) -> Vec<(usize, usize, String)> {
+    let mut seen = std::collections::HashSet::new();
+    let mut next = next_id(todos);
+    let mut repaired = Vec::new();
+    for todo in todos.iter_mut() {
+        if todo.id != 0 && seen.insert(todo.id) {
+            continue;
+        }
+        repaired.push((todo.id, next, todo.text.clone()));
+        todo.id = next;
+        seen.insert(next);
+        next += 1;
+    }
+    repaired
+}
+
+/// Run the auto-archive sweep over the JSON file, holding the lock from reading it to
+/// writing it back so no other process saves in between
+fn sweep_json(config: &config::Config) {
+    let result = (|| -> io::Result<()> {
+        if let Some(dir) = paths::lock_path().parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let _lock = lock::exclusive(&paths::lock_path())?;
+        let mut todos = load_todos()?;
+        let len_before = todos.len();
+        archive::sweep(&mut JsonStore::new(&mut todos), config).map_err(io::Error::other)?;
+        if todos.len() != len_before {
+            write_todos(&paths::json_path(), &todos)?;
+        }
+        Ok(())
+    })();
+    if let Err(e) = result {
+        messages::say(Msg::SweepFailed { error: &e });
+    }
+}
+
+/// Save the todos, which were `before` when loaded. Todos only added since then are
+/// appended to a JSONL store, and any other change writes the whole file
+fn save_todos(before: &[Todo], todos: &[Todo]) -> io::Result<()> {
+    if todos == before && !json_file::must_rewrite() {
+        return Ok(());
+    }
+    let path = paths::json_path();
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let _lock = lock::exclusive(&paths::lock_path())?;
+    if todos.len() > before.len() && todos[..before.len()] == *before && json_file::can_append(&path) {
+        return append_todos(&path, &todos[before.len()..]);
+    }
+    write_todos(&path, todos)
+}
+
+/// Append `added` to the JSONL store at `path`, a line each; the caller holds the lock.
+/// Their ids were given out when the store was read, so if another process has since
+/// appended todos under them, nothing is written rather than two todos sharing an id
+fn append_todos(path: &Path, added: &[Todo]) -> io::Result<()> {
+    if let (Some(first), Some(last)) = (added.first(), json_file::last_id(path)?)
+        && first.id <= last
+    {
+        return Err(io::Error::other(format!(
+            "{} was saved by another process since it was read, under the ids given here; run the command again",
+            path.display()
+        )));
+    }
+    let mut lines = String::new();
+    for todo in added {
+        lines.push_str(&json_file::line(todo)?);
+    }
+    let _hold = cancel::hold();
+    fs::OpenOptions::new().append(true).open(path)?.write_all(lines.as_bytes())
+}
+
+/// Write the JSON file at `path`, laid out as its extension says; the caller holds the
+/// lock. It is written beside the old one and renamed over it, and Ctrl-C waits for that,
+/// so the file is never left half written. A file in an older format is copied aside
+/// first, and one in a newer format is refused
+fn write_todos(path: &Path, todos: &[Todo]) -> io::Result<()> {
+    if let Some(version) = fs::read_to_string(path).ok().and_then(|data| json_file::version(&data)) {
+        if let Some(why) = json_file::too_new(version) {
+            return Err(io::Error::new(io::ErrorKind::Unsupported, why));
+        }
+        if version < json_file::VERSION {
+            let copy = format!("{}.v{}-{}", path.display(), version, Local::now().format("%Y%m%d%H%M%S"));
+            fs::copy(path, &copy)?;
+            messages::say(Msg::FormatUpgraded { path, version: json_file::VERSION, kept: &copy });
+        }
+    }
+    let json = json_file::to_string(todos, json_file::Format::of(path))?;
+    let dir = path.parent().filter(|dir| !dir.as_os_str().is_empty()).unwrap_or(Path::new("."));
+    let _hold = cancel::hold();
+    let mut file = tempfile::NamedTempFile::new_in(dir)?;
+    file.write_all(json.as_bytes())?;
+    // Keep the file readable by whoever could read it, as a fresh temporary file is private
+    if let Ok(metadata) = fs::metadata(path) {
+        file.as_file().set_permissions(metadata.permissions())?;
+    }
+    file.persist(path)?;
+    json_file::set_rewrite(false);
+    Ok(())
+}
+
+fn validate_date(date_str: &str) -> Result<NaiveDate, ParseError> {
+    dates::parse_date(date_str)
+}
+
+fn validate_time(time_str: &str) -> Result<NaiveTime, ParseError> {
+    NaiveTime::parse_from_str(time_str, "%H:%M")
+}
+
+/// A due date with its optional time of day. The date can be anything a quick-add due
+/// date can, with week shortcuts such as w29 landing on `week_day`
+fn validate_due(date_str: &str, time_str: Option<&str>, week_day: Weekday) -> Option<Due> {
+    let date = quick_add::parse_due_on(date_str, Local::now().date_naive(), week_day)?.date;
+    let time = time_str.map(validate_time).transpose().ok()?;
+    Some(Due { date, time })
+}
+
+/// A reminder time, falling back to the configured default time when only a date is given
+fn validate_reminder(date_str: &str, time_str: Option<&str>) -> Result<NaiveDateTime, ParseError> {
+    let date = validate_date(date_str)?;
+    let time = match time_str {
+        Some(time_str) => validate_time(time_str)?,
+        None => config::load().default_reminder_time(),
+    };
+    Ok(NaiveDateTime::new(date, time))
+}
+
+fn validate_datetime(date_str: &str, time_str: &str) -> Result<NaiveDateTime, ParseError> {
+    let date = validate_date(date_str)?;
+    let time = validate_time(time_str)?;
+    Ok(NaiveDateTime::new(date, time))
+}
+
+fn now_timestamp() -> String {
+    Local::now().to_rfc3339_opts(SecondsFormat::Secs, false)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SUFFIX: &str = " (Due: No due date, Reminder: No reminder)";
+
+    fn todo(text: &str) -> Todo {
+        Todo { id: 7, text: text.to_string(), ..Todo::default() }
+    }
+
+    fn formatted(text: &str, width: Option<usize>, wrap: bool) -> String {
+        format_todo(&todo(text), 2, width, wrap, DateDisplay::default())
+    }
+
+    #[test]
+    fn piped_output_keeps_the_whole_text() {
+        let text = "Call the plumber about the leak under the sink before the weekend";
+        assert_eq!(formatted(text, None, false), format!("[ ]  7: {}{}", text, SUFFIX));
+    }
+
+    #[test]
+    fn short_text_is_padded_so_the_dates_line_up() {
+        let line = formatted("Buy milk", Some(80), false);
+        assert_eq!(line.width(), 80);
+        assert!(line.ends_with(SUFFIX), "{}", line);
+        assert!(line.starts_with("[ ]  7: Buy milk "), "{}", line);
+    }
+
+    #[test]
+    fn long_text_is_cut_with_an_ellipsis_to_fit() {
+        for width in [60, 70, 100] {
+            let line = formatted("Call the plumber about the leak under the sink before the weekend", Some(width), false);
+            assert_eq!(line.width(), width, "{}", line);
+            assert!(line.contains('…'), "{}", line);
+            assert!(line.ends_with(SUFFIX), "{}", line);
+        }
+    }
+
+    #[test]
+    fn a_narrow_terminal_still_shows_some_text() {
+        let line = formatted("Call the plumber about the leak", Some(20), false);
+        assert_eq!(line, format!("[ ]  7: Call th…{}", SUFFIX));
+    }
+
+    #[test]
+    fn wide_characters_are_measured_in_columns() {
+        for width in [60, 61] {
+            let line = formatted("日本語のテキストはとても長いのでここで切れます", Some(width), false);
+            assert_eq!(line.width(), width, "{}", line);
+            assert!(line.contains('…'), "{}", line);
+        }
+        assert_eq!(truncate_text("日本語", 4), "日…");
+        assert_eq!(truncate_text("日本語", 5), "日本…");
+        assert_eq!(truncate_text("日本語", 6), "日本語");
+        assert_eq!(truncate_text("🎉🎉🎉🎉", 6), "🎉🎉…");
+    }
+
+    #[test]
+    fn wrapped_lines_fit_and_hang_under_the_text() {
+        let text = "Call the plumber about the leak under the sink before the weekend";
+        for width in [50, 60, 80] {
+            let lines = formatted(text, Some(width), true);
+            for line in lines.lines() {
+                assert!(line.width() <= width, "{:?} is wider than {}", line, width);
+            }
+            for line in lines.lines().skip(1) {
+                assert!(line.starts_with("        ") && !line.starts_with("         "), "{:?}", line);
+            }
+        }
+    }
+
+    #[test]
+    fn wrapping_splits_words_by_columns() {
+        assert_eq!(wrap_text("abcdefghij", 4), ["abcd", "efgh", "ij"]);
+        assert_eq!(wrap_text("日本語のテキスト", 5), ["日本", "語の", "テキ", "スト"]);
+        assert_eq!(wrap_text("a 日本語", 4), ["a", "日本", "語"]);
+    }
+
+    #[test]
+    fn ids_pad_to_the_widest_id_or_the_configured_minimum() {
+        assert_eq!(id_width([], 0), 1);
+        assert_eq!(id_width([7, 42, 9], 0), 2);
+        assert_eq!(id_width([7, 100], 0), 3);
+        assert_eq!(id_width([7, 99], 0), 2);
+        assert_eq!(id_width([7, 42], 4), 4);
+        assert_eq!(id_width([12345], 4), 5);
+    }
+
+    #[test]
+    fn ids_are_right_aligned_in_their_column() {
+        let line = |id: usize| format_todo(&Todo { id, ..todo("x") }, 3, None, false, DateDisplay::default());
+        assert_eq!(line(7), format!("[ ]   7: x{}", SUFFIX));
+        assert_eq!(line(42), format!("[ ]  42: x{}", SUFFIX));
+        assert_eq!(line(120), format!("[ ] 120: x{}", SUFFIX));
+    }
+}
@@ -0,0 +1,272 @@
+use crate::Todo;
+use chrono::DateTime;
+use std::collections::HashMap;
+
+/// Which copy of a todo wins when both stores changed it
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Side {
+    Ours,
+    Theirs,
+}
+
+/// How conflicting todos are settled
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Strategy {
+    Ours,
+    Theirs,
+    /// The copy with the later created or completed time; ours on a tie
+    Newest,
+}
+
+impl Strategy {
+    pub fn pick(self, ours: &Todo, theirs: &Todo) -> Side {
+        match self {
+            Strategy::Ours => Side::Ours,
+            Strategy::Theirs => Side::Theirs,
+            Strategy::Newest if last_touched(theirs) > last_touched(ours) => Side::Theirs,
+            Strategy::Newest => Side::Ours,
+        }
+    }
+}
+
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Summary {
+    pub added: usize,
+    pub identical: usize,
+    pub kept_ours: usize,
+    pub took_theirs: usize,
+}
+
+/// The latest timestamp a todo records, which is as close to "last modified" as the
+/// store gets
+fn last_touched(todo: &Todo) -> Option<i64> {
+    [todo.created_at.as_deref(), todo.completed_at.as_deref()]
+        .into_iter()
+        .flatten()
+        .filter_map(|ts| DateTime::parse_from_rfc3339(ts).ok())
+        .map(|dt| dt.timestamp())
+        .max()
+}
+
+/// Whether two todos are the same apart from their ids, which each store hands out itself,
+/// and UUIDs, which two stores may have filled in separately for the same old todo. Parent
+/// ids are store-local too, so they are left out as well
+fn same_content(a: &Todo, b: &Todo) -> bool {
+    a == &Todo { id: a.id, uuid: a.uuid.clone(), parent: a.parent, ..b.clone() }
+}
+
+/// Find our copy of `theirs`. Ids differ between stores, so a todo is recognised by its
+/// UUID, or failing that by its creation time, with the text telling apart todos created in
+/// the same second; todos without a creation time only match on their text
+fn counterpart(ours: &[Todo], theirs: &Todo, matched: &[bool]) -> Option<usize> {
+    if !theirs.uuid.is_empty()
+        && let Some(i) = (0..ours.len()).find(|&i| !matched[i] && ours[i].uuid == theirs.uuid)
+    {
+        return Some(i);
+    }
+    let candidates: Vec<usize> = (0..ours.len())
+        .filter(|&i| !matched[i])
+        .filter(|&i| match (&ours[i].created_at, &theirs.created_at) {
+            (Some(a), Some(b)) => a == b,
+            (None, None) => ours[i].text == theirs.text,
+            _ => false,
+        })
+        .collect();
+    match candidates.as_slice() {
+        [only] => Some(*only),
+        _ => candidates.into_iter().find(|&i| ours[i].text == theirs.text),
+    }
+}
+
+/// Fold `theirs` into `ours`: todos only they have are added with fresh ids, identical ones
+/// are skipped and `resolve` settles the ones both sides changed. Parent ids in the todos
+/// taken from theirs are translated to our ids, or dropped when the parent didn't come across
+pub fn merge(ours: &mut Vec<Todo>, theirs: Vec<Todo>, mut resolve: impl FnMut(&Todo, &Todo) -> Side) -> Summary {
+    let mut summary = Summary::default();
+    let mut matched = vec![false; ours.len()];
+    let mut added = Vec::new();
+    // Our id for each of theirs, and which of our todos now carry their parent ids
+    let mut our_ids = HashMap::new();
+    let mut taken = Vec::new();
+    for todo in theirs {
+        let Some(i) = counterpart(ours, &todo, &matched) else {
+            added.push(todo);
+            continue;
+        };
+        matched[i] = true;
+        our_ids.insert(todo.id, ours[i].id);
+        if same_content(&ours[i], &todo) {
+            summary.identical += 1;
+            continue;
+        }
+        match resolve(&ours[i], &todo) {
+            Side::Ours => summary.kept_ours += 1,
+            Side::Theirs => {
+                ours[i] = Todo { id: ours[i].id, ..todo };
+                taken.push(i);
+                summary.took_theirs += 1;
+            }
+        }
+    }
+    for todo in added {
+        let id = crate::next_id(ours);
+        our_ids.insert(todo.id, id);
+        taken.push(ours.len());
+        ours.push(Todo { id, ..todo });
+        summary.added += 1;
+    }
+    for i in taken {
+        ours[i].parent = ours[i].parent.and_then(|parent| our_ids.get(&parent).copied());
+    }
+    summary
+}
+
+/// A todo both sides of a rebase changed, as each left it; `None` where it was deleted
+pub struct Conflict {
+    pub id: usize,
+    pub ours: Option<Todo>,
+    pub theirs: Option<Todo>,
+}
+
+/// Our edits replayed on top of theirs, with theirs kept wherever both sides changed a todo
+pub struct Rebased {
+    pub todos: Vec<Todo>,
+    pub conflicts: Vec<Conflict>,
+}
+
+impl Rebased {
+    /// The todos with every conflict settled for `side`
+    pub fn settle(self, side: Side) -> Vec<Todo> {
+        let mut todos = self.todos;
+        if side == Side::Ours {
+            for conflict in self.conflicts {
+                todos.retain(|t| t.id != conflict.id);
+                todos.extend(conflict.ours);
+            }
+            todos.sort_by_key(|t| t.id);
+        }
+        todos
+    }
+}
+
+/// Combine two sets of edits made to the same `base`, as when the TUI saves over a store
+/// another command changed in the meantime. Ids are stable within a store, so todos are
+/// matched by id; a todo only one side changed takes that change, and todos we added get
+/// a fresh id if theirs took it first
+pub fn rebase(base: &[Todo], ours: Vec<Todo>, theirs: Vec<Todo>) -> Rebased {
+    let find = |todos: &[Todo], id| todos.iter().find(|t| t.id == id).cloned();
+    let mut conflicts = Vec::new();
+    let mut todos: Vec<Todo> = theirs.iter().filter(|t| !base.iter().any(|b| b.id == t.id)).cloned().collect();
+    for old in base {
+        let (mine, other) = (find(&ours, old.id), find(&theirs, old.id));
+        let unchanged = |todo: &Option<Todo>| todo.as_ref() == Some(old);
+        let kept = if unchanged(&mine) || mine == other {
+            other
+        } else if unchanged(&other) {
+            mine
+        } else {
+            conflicts.push(Conflict { id: old.id, ours: mine, theirs: other.clone() });
+            other
+        };
+        todos.extend(kept);
+    }
+    for todo in ours.into_iter().filter(|t| !base.iter().any(|b| b.id == t.id)) {
+        let id = if todos.iter().any(|t| t.id == todo.id) { crate::next_id(&todos) } else { todo.id };
+        todos.push(Todo { id, ..todo });
+    }
+    todos.sort_by_key(|t| t.id);
+    Rebased { todos, conflicts }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn todo(id: usize, text: &str) -> Todo {
+        Todo { id, text: text.to_string(), ..Default::default() }
+    }
+
+    fn texts(todos: &[Todo]) -> Vec<(usize, &str, bool)> {
+        todos.iter().map(|t| (t.id, t.text.as_str(), t.done)).collect()
+    }
+
+    /// What the TUI loaded: "buy milk" (#1) and "call mum" (#2)
+    fn base() -> Vec<Todo> {
+        vec![todo(1, "buy milk"), todo(2, "call mum")]
+    }
+
+    #[test]
+    fn a_todo_added_elsewhere_survives_the_tui_saving() {
+        // The TUI renames #1 while another terminal adds #3 and finishes #2
+        let mut ours = base();
+        ours[0].text = "buy oat milk".to_string();
+        let mut theirs = base();
+        theirs[1].done = true;
+        theirs.push(todo(3, "pay rent"));
+        let rebased = rebase(&base(), ours, theirs);
+        assert!(rebased.conflicts.is_empty());
+        assert_eq!(texts(&rebased.todos), [(1, "buy oat milk", false), (2, "call mum", true), (3, "pay rent", false)]);
+    }
+
+    #[test]
+    fn todos_both_sides_add_under_one_id_both_keep() {
+        let mut ours = base();
+        ours.push(todo(3, "water plants"));
+        let mut theirs = base();
+        theirs.push(todo(3, "pay rent"));
+        let rebased = rebase(&base(), ours, theirs);
+        assert!(rebased.conflicts.is_empty());
+        assert_eq!(texts(&rebased.todos), [(1, "buy milk", false), (2, "call mum", false), (3, "pay rent", false), (4, "water plants", false)]);
+    }
+
+    #[test]
+    fn deletions_on_either_side_carry_over() {
+        let ours = vec![todo(2, "call mum")];
+        let theirs = vec![todo(1, "buy milk")];
+        let rebased = rebase(&base(), ours, theirs);
+        assert!(rebased.conflicts.is_empty());
+        assert!(rebased.todos.is_empty());
+    }
+
+    #[test]
+    fn the_same_change_on_both_sides_is_no_conflict() {
+        let mut ours = base();
+        ours[0].done = true;
+        let rebased = rebase(&base(), ours.clone(), ours);
+        assert!(rebased.conflicts.is_empty());
+        assert_eq!(texts(&rebased.todos), [(1, "buy milk", true), (2, "call mum", false)]);
+    }
+
+    #[test]
+    fn a_todo_both_sides_changed_is_a_conflict_settled_either_way() {
+        let mut ours = base();
+        ours[0].text = "buy oat milk".to_string();
+        let mut theirs = base();
+        theirs[0].done = true;
+        theirs.push(todo(3, "pay rent"));
+        let rebased = rebase(&base(), ours.clone(), theirs.clone());
+        assert_eq!(rebased.conflicts.len(), 1);
+        assert_eq!(rebased.conflicts[0].id, 1);
+        assert_eq!(rebased.conflicts[0].ours.as_ref().map(|t| t.text.as_str()), Some("buy oat milk"));
+        assert_eq!(
+            texts(&rebased.settle(Side::Theirs)),
+            [(1, "buy milk", true), (2, "call mum", false), (3, "pay rent", false)]
+        );
+        let rebased = rebase(&base(), ours, theirs);
+        assert_eq!(
+            texts(&rebased.settle(Side::Ours)),
+            [(1, "buy oat milk", false), (2, "call mum", false), (3, "pay rent", false)]
+        );
+    }
+
+    #[test]
+    fn a_todo_deleted_on_one_side_and_edited_on_the_other_is_a_conflict() {
+        let ours = vec![todo(2, "call mum")];
+        let mut theirs = base();
+        theirs[0].done = true;
+        let rebased = rebase(&base(), ours, theirs);
+        assert_eq!(rebased.conflicts.len(), 1);
+        assert!(rebased.conflicts[0].ours.is_none());
+        assert_eq!(texts(&rebased.settle(Side::Ours)), [(2, "call mum", false)]);
+    }
+}
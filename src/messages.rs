@@ -0,0 +1,1172 @@
+use std::{
+    fmt,
+    path::Path,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+/// Whether messages lead with their emoji; `emoji = false` in the config turns them off
+static EMOJI: AtomicBool = AtomicBool::new(true);
+
+pub fn set_emoji(on: bool) {
+    EMOJI.store(on, Ordering::Relaxed);
+}
+
+/// How a message is shown: what went well and what is only news on stdout, the rest on
+/// stderr; in the TUI, each in its own colour
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Level {
+    Success,
+    Info,
+    /// News that is said on stderr, so it stays out of output piped elsewhere
+    Note,
+    /// Needs noticing now, like a reminder going off
+    Alert,
+    Warning,
+    Error,
+}
+
+impl Level {
+    pub fn on_stderr(self) -> bool {
+        matches!(self, Level::Note | Level::Warning | Level::Error)
+    }
+}
+
+/// What the commands and the TUI report about what they did, each worded in one place so
+/// it reads the same wherever it is said. `label` is the store's suffix, e.g. " (SQLite)"
+pub enum Msg<'a> {
+    Added { id: usize, label: &'a str },
+    AddedSubtask { id: usize, parent: usize, label: &'a str },
+    AddedFromTemplate { id: usize, template: &'a str, label: &'a str },
+    Reopened { id: usize, label: &'a str },
+    /// Done with no more said, of a todo named by id alone when `id` is `None`
+    MarkedDone { id: Option<usize>, label: &'a str },
+    Completed { id: usize, text: &'a str },
+    CompletedMany { count: usize, label: &'a str },
+    NextOccurrence { id: usize, due: &'a str, label: &'a str },
+    Deleted { id: usize, label: &'a str },
+    DeletedMany { count: usize, label: &'a str },
+    DueSet { id: usize, label: &'a str },
+    ReminderSet { id: usize, label: &'a str },
+    ReminderSetAt { id: usize, at: &'a str },
+    Planned { count: usize, skipped: usize, label: &'a str },
+    ReminderCleared { id: usize, label: &'a str },
+    /// A done todo's reminder, which would have gone off `at`
+    ReminderCancelled { at: &'a str },
+    TagsSet { id: usize, tags: &'a str, label: &'a str },
+    PrioritySet { id: usize, level: &'a str, label: &'a str },
+    PriorityCleared { id: usize, label: &'a str },
+    /// The text alone was edited when `id` is `None`
+    Updated { id: Option<usize>, label: &'a str },
+    NotFound { id: usize },
+    Imported { count: usize, details: &'a str, from: &'a Path, label: &'a str },
+    BackedUp { count: usize, to: &'a Path, label: &'a str },
+    BackupFailed { error: &'a dyn fmt::Display },
+    BackupUnreadable { error: &'a dyn fmt::Display },
+    /// `store` is what holds the todos, "store" or "database"
+    NotEmpty { count: usize, store: &'a str },
+    SafetyCopy { path: &'a str },
+    SafetyCopyFailed { error: &'a dyn fmt::Display },
+    Restored { count: usize, backend: &'a str, label: &'a str },
+    ReminderDue { id: usize, text: &'a str, more: usize },
+    /// The TUI's word diff of an edited todo's text
+    Edited { summary: &'a str },
+    /// Something quick-add text held that it couldn't take as meant
+    AddWarning { warning: &'a str },
+    Refreshed,
+    ReadOnly { reason: &'a str },
+    TagsCleared,
+    Tags { tags: &'a str },
+    NotSaved { error: &'a dyn fmt::Display },
+    NotAdded { error: &'a dyn fmt::Display },
+    NotDeleted { error: &'a dyn fmt::Display },
+    InvalidDue { value: &'a str },
+    InvalidReminder { value: &'a str },
+    CantMoveDue { value: &'a str },
+    /// The TUI's due date pushed by a day or a week
+    DueMoved { from: &'a str, to: &'a str },
+    /// A due date or reminder set on, or cleared from (`value` `None`), several todos at once
+    BulkSet { field: &'a str, value: Option<&'a str>, count: usize },
+    /// Subtasks' due dates moved along with their parent's; `by` is e.g. "3 days later"
+    SubtasksMoved { count: usize, by: &'a str, label: &'a str },
+    NothingToWatch,
+    /// A line typed at the TUI's `:` prompt that couldn't be read as a command
+    BadCommand { error: &'a str },
+    /// A command the TUI's `:` prompt doesn't take
+    CantRunHere { command: &'a str },
+    /// The list shown through a filter typed at the `:` prompt
+    Filtered { filter: &'a str },
+    TuiFailed { error: &'a dyn fmt::Display },
+    /// The JSON store couldn't be read, so nothing is saved over it
+    StoreUnreadable { error: &'a dyn fmt::Display },
+    TuiNotSaved,
+    TrashEmpty,
+    TrashEmptied { count: usize },
+    TrashUnreadable { error: &'a dyn fmt::Display },
+    TrashNotEmptied { error: &'a dyn fmt::Display },
+    /// An error that says all there is to say, or a message of the TUI's worded as a failure
+    Failed { error: &'a dyn fmt::Display },
+    Warned { warning: &'a dyn fmt::Display },
+    /// A file of settings, filters, templates or goals that couldn't be read
+    Ignoring { what: &'a dyn fmt::Display, error: &'a dyn fmt::Display },
+    IgnoringSetting { key: &'a str, file: &'a Path, error: &'a dyn fmt::Display },
+    CouldNotRead { what: &'a dyn fmt::Display, error: &'a dyn fmt::Display },
+    CouldNotWrite { what: &'a dyn fmt::Display, error: &'a dyn fmt::Display },
+    CouldNotSave { what: &'a str, error: &'a dyn fmt::Display },
+    CouldNotDelete { what: &'a str, error: &'a dyn fmt::Display },
+    /// One of the files kept beside the store, after the change itself was saved
+    CouldNotUpdate { what: &'a str, error: &'a dyn fmt::Display },
+    /// When something last happened, kept so it isn't done too often
+    CouldNotNote { what: &'a str, error: &'a dyn fmt::Display },
+    CouldNotLock { error: &'a dyn fmt::Display },
+    CouldNotCreate { path: &'a Path, error: &'a dyn fmt::Display },
+    /// The store is read-only, from the config or TODO_READONLY
+    ReadOnlyStore,
+    /// Heads the changes a dry run would have made
+    DryRun,
+    DryRunNoChanges,
+    InvalidFilter { error: &'a dyn fmt::Display },
+    InvalidListFormat { error: &'a dyn fmt::Display },
+    InvalidFormat { error: &'a dyn fmt::Display },
+    InvalidDateFormat,
+    InvalidDueFormat,
+    InvalidReminderFormat,
+    DueNotSet { error: &'a dyn fmt::Display },
+    ReminderNotSet { error: &'a dyn fmt::Display },
+    DueAfterParent { id: usize, due: &'a str, parent: usize, parent_due: &'a str },
+    AutoReminderPassed { id: usize },
+    Repeats { id: usize, rule: &'a str, label: &'a str },
+    NoLongerRepeats { id: usize, label: &'a str },
+    NothingMatched,
+    NoPendingText { text: &'a str },
+    /// Followed by the todos, one per line
+    AmbiguousText { count: usize, text: &'a str },
+    JoinedLines { count: usize },
+    SameText { id: usize },
+    DoneSameText { id: usize },
+    UnknownField { field: &'a str, nearest: Option<&'a str>, fields: &'a str },
+    EditedMany { updated: usize, added: usize, deleted: usize, label: &'a str },
+    EditAborted { reason: &'a str },
+    EmptyEdit,
+    NoTemplate { name: &'a str },
+    BadTemplateName,
+    BadTemplateSource { error: &'a dyn fmt::Display },
+    TemplateTextInvalid { name: &'a str, error: &'a dyn fmt::Display },
+    MissingVars { names: &'a str, first: &'a str },
+    TemplateSaved { name: &'a str, text: &'a str },
+    TemplateDeleted { name: &'a str },
+    IgnoringTemplateDue { offset: &'a str },
+    IgnoringTemplateReminder { error: &'a dyn fmt::Display },
+    TemplateReminderPassed { id: usize },
+    NotInTrash { id: usize },
+    Untrashed { id: usize, label: &'a str },
+    UntrashedAs { id: usize, new_id: usize, label: &'a str },
+    IdTaken { id: usize, text: &'a str, new_id: usize },
+    NotAStore { path: &'a Path, error: &'a dyn fmt::Display },
+    MergeNeedsStrategy,
+    Merged { from: &'a Path, added: usize, identical: usize, kept_ours: usize, took_theirs: usize, label: &'a str },
+    SkippedLine { unit: &'a str, line: usize, reason: &'a str },
+    SkippedHeadings { count: usize },
+    UnreadableTasks { count: usize },
+    DueLooksWrong { text: &'a str, error: &'a dyn fmt::Display },
+    AbsurdDates,
+    PlanNeedsTerminal,
+    Planning { count: usize, week: &'a str },
+    NoProblems,
+    Finding { finding: &'a str },
+    Problems { count: usize },
+    Fixed { id: usize },
+    /// Problems found in passing, and the doctor command that deals with them
+    RunDoctor { problems: &'a str, fix: bool },
+    Overdue { count: usize },
+    MissingParent { id: usize, parent: usize },
+    ParentLoop { ids: &'a str },
+    ReopensUncounted { error: &'a dyn fmt::Display },
+    NoSuchProfile { name: &'a str },
+    BadProfileName { default: &'a str },
+    ProfileExists { name: &'a str },
+    ProfileCreated { name: &'a str, dir: &'a Path },
+    BadFilterName,
+    /// `action` is what to do to it there, "change" or "remove"
+    FilterInConfig { name: &'a str, config: &'a Path, action: &'a str },
+    FilterSaved { name: &'a str, expr: &'a str },
+    FilterDeleted { name: &'a str },
+    SkippingFilter { name: &'a str, error: &'a dyn fmt::Display },
+    /// Followed by the saved filters, if there are any
+    NoFilter { name: &'a str },
+    GoalNeedsTag,
+    GoalSet { tag: &'a str, goal: &'a dyn fmt::Display },
+    NoGoal { tag: &'a str },
+    GoalDeleted { tag: &'a str },
+    JsonOnly { flag: &'a str },
+    SqliteOnly { flag: &'a str },
+    MigrateNeedsJson,
+    ShellOnlyFlags,
+    AlreadyInShell,
+    ShellFailed { error: &'a dyn fmt::Display },
+    HistoryNotSaved { error: &'a dyn fmt::Display },
+    ReadAgain,
+    NotUnderstood { line: &'a str, help: &'a str },
+    DueSoon { names: &'a str },
+    LegacyDb { db: &'a Path, legacy: &'a str, count: usize },
+    NothingToMigrate { legacy: &'a Path },
+    LegacyUnreadable { legacy: &'a Path },
+    LegacyTooNew { legacy: &'a Path, count: usize, why: &'a str },
+    LegacyJson { json: &'a Path, legacy: &'a Path, count: usize },
+    LegacyNotEmpty,
+    AnswerNotNoted { error: &'a dyn fmt::Display },
+    LegacyCopied { count: usize, from: &'a Path, to: &'a Path, kept: &'a str },
+    LegacyNotCopied { legacy: &'a Path, error: &'a dyn fmt::Display },
+    /// The other backend's store has the todos this one lacks
+    SqliteHasTodos { count: usize, config: &'a Path },
+    JsonHasTodos { count: usize },
+    StoreMoved { to: &'a Path, kept: &'a str },
+    StoreStarted { at: &'a Path },
+    NotMigrated { error: &'a dyn fmt::Display },
+    FormatUpgraded { path: &'a Path, version: u32, kept: &'a str },
+    NewerFormat { path: &'a Path, why: &'a str },
+    TornLine { path: &'a Path, bytes: usize },
+    IdReassigned { old: usize, text: &'a str, new: usize },
+    RemindersConverted { count: usize, offset: &'a str },
+    DatabaseLocked { waited: f64 },
+    DatabaseError { error: &'a dyn fmt::Display },
+    /// A query and how long it took, for --explain
+    Query { ms: f64, sql: &'a str },
+    EventsUnreadable { error: &'a dyn fmt::Display },
+    EventsNotLogged { error: &'a dyn fmt::Display },
+    NeedsEventsLog { config: &'a Path },
+    SkippedEvents { count: usize },
+    Stats { count: usize, pending: usize, done: usize },
+    Burndown { scope: &'a str, days: u32 },
+    NothingDueToday,
+    CantExportGoogleTasks,
+    Exported { count: usize, to: &'a Path },
+    NoSafetyCopies { store: &'a Path },
+    SafetyCopyOutOfRange { count: usize, store: &'a Path },
+    Archived { count: usize, age: &'a str, label: &'a str },
+    ArchiveNotWritten { error: &'a dyn fmt::Display },
+    TrashSwept { count: usize, age: &'a str },
+    TrashNotSwept { error: &'a dyn fmt::Display },
+    SweepFailed { error: &'a dyn fmt::Display },
+    TuiChangesKept,
+    /// Followed by each todo changed in both places
+    TuiConflicts { count: usize },
+    ReminderReset { id: usize },
+    CouldNotCheckReminders { error: &'a dyn fmt::Display },
+    NotWatching { error: &'a dyn fmt::Display },
+    Watching { every: u64 },
+    /// `watch` carries on after one failed check; `what` is what it couldn't do
+    WatchFailed { what: &'a str, error: &'a dyn fmt::Display },
+    /// A reminder announced by `notify` or `watch`
+    ReminderFired { id: usize, text: &'a str, at: &'a str },
+    BadNotifyTarget { target: &'a str },
+    NotDelivered { via: &'a str, id: usize, error: &'a dyn fmt::Display },
+    DigestSent { to: &'a str },
+    DigestNotSent { error: &'a dyn fmt::Display },
+    NoCtrlC { error: &'a dyn fmt::Display },
+    Cancelled,
+}
+
+impl Msg<'_> {
+    pub fn level(&self) -> Level {
+        match self {
+            Msg::NotFound { .. }
+            | Msg::BackupFailed { .. }
+            | Msg::BackupUnreadable { .. }
+            | Msg::NotEmpty { .. }
+            | Msg::SafetyCopyFailed { .. }
+            | Msg::ReadOnly { .. }
+            | Msg::NotSaved { .. }
+            | Msg::NotAdded { .. }
+            | Msg::NotDeleted { .. }
+            | Msg::InvalidDue { .. }
+            | Msg::InvalidReminder { .. }
+            | Msg::CantMoveDue { .. }
+            | Msg::BadCommand { .. }
+            | Msg::CantRunHere { .. }
+            | Msg::TuiFailed { .. }
+            | Msg::StoreUnreadable { .. }
+            | Msg::TuiNotSaved
+            | Msg::TrashUnreadable { .. }
+            | Msg::TrashNotEmptied { .. }
+            | Msg::Failed { .. }
+            | Msg::CouldNotRead { .. }
+            | Msg::CouldNotWrite { .. }
+            | Msg::CouldNotSave { .. }
+            | Msg::CouldNotDelete { .. }
+            | Msg::CouldNotLock { .. }
+            | Msg::CouldNotCreate { .. }
+            | Msg::ReadOnlyStore
+            | Msg::InvalidFilter { .. }
+            | Msg::InvalidListFormat { .. }
+            | Msg::InvalidFormat { .. }
+            | Msg::InvalidDateFormat
+            | Msg::InvalidDueFormat
+            | Msg::InvalidReminderFormat
+            | Msg::DueNotSet { .. }
+            | Msg::ReminderNotSet { .. }
+            | Msg::NothingMatched
+            | Msg::NoPendingText { .. }
+            | Msg::AmbiguousText { .. }
+            | Msg::UnknownField { .. }
+            | Msg::EditAborted { .. }
+            | Msg::EmptyEdit
+            | Msg::NoTemplate { .. }
+            | Msg::BadTemplateName
+            | Msg::BadTemplateSource { .. }
+            | Msg::TemplateTextInvalid { .. }
+            | Msg::MissingVars { .. }
+            | Msg::NotInTrash { .. }
+            | Msg::NotAStore { .. }
+            | Msg::MergeNeedsStrategy
+            | Msg::UnreadableTasks { .. }
+            | Msg::DueLooksWrong { .. }
+            | Msg::AbsurdDates
+            | Msg::PlanNeedsTerminal
+            | Msg::Problems { .. }
+            | Msg::NoSuchProfile { .. }
+            | Msg::BadProfileName { .. }
+            | Msg::ProfileExists { .. }
+            | Msg::BadFilterName
+            | Msg::FilterInConfig { .. }
+            | Msg::NoFilter { .. }
+            | Msg::GoalNeedsTag
+            | Msg::NoGoal { .. }
+            | Msg::MigrateNeedsJson
+            | Msg::AlreadyInShell
+            | Msg::ShellFailed { .. }
+            | Msg::NotUnderstood { .. }
+            | Msg::LegacyUnreadable { .. }
+            | Msg::LegacyNotCopied { .. }
+            | Msg::NotMigrated { .. }
+            | Msg::DatabaseLocked { .. }
+            | Msg::DatabaseError { .. }
+            | Msg::EventsUnreadable { .. }
+            | Msg::CantExportGoogleTasks
+            | Msg::NoSafetyCopies { .. }
+            | Msg::SafetyCopyOutOfRange { .. }
+            | Msg::CouldNotCheckReminders { .. }
+            | Msg::DigestNotSent { .. }
+            | Msg::Cancelled => Level::Error,
+            Msg::ReminderDue { .. } | Msg::ReminderFired { .. } => Level::Alert,
+            Msg::Warned { .. }
+            | Msg::Ignoring { .. }
+            | Msg::IgnoringSetting { .. }
+            | Msg::CouldNotUpdate { .. }
+            | Msg::CouldNotNote { .. }
+            | Msg::DueAfterParent { .. }
+            | Msg::AutoReminderPassed { .. }
+            | Msg::JoinedLines { .. }
+            | Msg::SameText { .. }
+            | Msg::DoneSameText { .. }
+            | Msg::IgnoringTemplateDue { .. }
+            | Msg::IgnoringTemplateReminder { .. }
+            | Msg::TemplateReminderPassed { .. }
+            | Msg::IdTaken { .. }
+            | Msg::SkippedLine { .. }
+            | Msg::SkippedHeadings { .. }
+            | Msg::RunDoctor { .. }
+            | Msg::MissingParent { .. }
+            | Msg::ParentLoop { .. }
+            | Msg::ReopensUncounted { .. }
+            | Msg::SkippingFilter { .. }
+            | Msg::JsonOnly { .. }
+            | Msg::SqliteOnly { .. }
+            | Msg::ShellOnlyFlags
+            | Msg::HistoryNotSaved { .. }
+            | Msg::LegacyDb { .. }
+            | Msg::NothingToMigrate { .. }
+            | Msg::LegacyTooNew { .. }
+            | Msg::LegacyJson { .. }
+            | Msg::LegacyNotEmpty
+            | Msg::AnswerNotNoted { .. }
+            | Msg::SqliteHasTodos { .. }
+            | Msg::JsonHasTodos { .. }
+            | Msg::NewerFormat { .. }
+            | Msg::TornLine { .. }
+            | Msg::IdReassigned { .. }
+            | Msg::RemindersConverted { .. }
+            | Msg::EventsNotLogged { .. }
+            | Msg::NeedsEventsLog { .. }
+            | Msg::SkippedEvents { .. }
+            | Msg::ArchiveNotWritten { .. }
+            | Msg::TrashNotSwept { .. }
+            | Msg::SweepFailed { .. }
+            | Msg::TuiChangesKept
+            | Msg::TuiConflicts { .. }
+            | Msg::NotWatching { .. }
+            | Msg::WatchFailed { .. }
+            | Msg::BadNotifyTarget { .. }
+            | Msg::NotDelivered { .. }
+            | Msg::NoCtrlC { .. } => Level::Warning,
+            Msg::DryRunNoChanges
+            | Msg::ReadAgain
+            | Msg::FormatUpgraded { .. }
+            | Msg::Query { .. }
+            | Msg::Archived { .. }
+            | Msg::TrashSwept { .. } => Level::Note,
+            Msg::DryRun
+            | Msg::Finding { .. }
+            | Msg::Planning { .. }
+            | Msg::DueSoon { .. }
+            | Msg::Stats { .. }
+            | Msg::Burndown { .. }
+            | Msg::NothingDueToday
+            | Msg::Overdue { .. }
+            | Msg::Watching { .. } => Level::Info,
+            Msg::AddWarning { .. } | Msg::NothingToWatch => Level::Warning,
+            Msg::Refreshed | Msg::SafetyCopy { .. } | Msg::Filtered { .. } | Msg::TrashEmpty => Level::Info,
+            _ => Level::Success,
+        }
+    }
+
+    /// The emoji the message leads with, if any
+    fn emoji(&self) -> Option<&'static str> {
+        Some(match self {
+            Msg::Added { .. } | Msg::AddedSubtask { .. } | Msg::AddedFromTemplate { .. } | Msg::Reopened { .. } => "✅",
+            Msg::CompletedMany { .. } => "✅",
+            Msg::Completed { .. } | Msg::MarkedDone { .. } => "🎉",
+            Msg::NextOccurrence { .. } => "🔁",
+            Msg::Deleted { .. } | Msg::DeletedMany { .. } | Msg::ReminderCleared { .. } | Msg::TrashEmptied { .. } => "🗑️",
+            Msg::DueSet { .. } | Msg::Planned { .. } | Msg::SubtasksMoved { .. } => "📅",
+            Msg::ReminderSet { .. } | Msg::ReminderSetAt { .. } | Msg::ReminderDue { .. } => "⏰",
+            Msg::ReminderCancelled { .. } => "🔕",
+            Msg::TagsSet { .. } => "🏷️",
+            Msg::PrioritySet { .. } | Msg::PriorityCleared { .. } => "🔺",
+            Msg::Updated { .. } => "📝",
+            Msg::Imported { .. } => "📥",
+            Msg::BackedUp { .. } => "💾",
+            Msg::SafetyCopy { .. } => "🛟",
+            Msg::Restored { .. } => "♻️",
+            Msg::Refreshed => "↻",
+            Msg::NothingToWatch => "⚠️",
+            Msg::DryRun
+            | Msg::DryRunNoChanges => "🧪",
+            Msg::Repeats { .. }
+            | Msg::NoLongerRepeats { .. } => "🔁",
+            Msg::EditedMany { .. } => "📝",
+            Msg::TemplateSaved { .. }
+            | Msg::NoProblems
+            | Msg::ProfileCreated { .. }
+            | Msg::FilterSaved { .. }
+            | Msg::GoalSet { .. }
+            | Msg::ReminderReset { .. } => "✅",
+            Msg::TemplateDeleted { .. }
+            | Msg::FilterDeleted { .. }
+            | Msg::GoalDeleted { .. }
+            | Msg::TrashSwept { .. } => "🗑️",
+            Msg::Untrashed { .. }
+            | Msg::UntrashedAs { .. } => "♻️",
+            Msg::Merged { .. } => "🔀",
+            Msg::Planning { .. } => "🗓️",
+            Msg::Fixed { .. } => "🔧",
+            Msg::ReadAgain => "↻",
+            Msg::DueSoon { .. } => "⏳",
+            Msg::LegacyCopied { .. } => "📥",
+            Msg::StoreMoved { .. }
+            | Msg::StoreStarted { .. }
+            | Msg::FormatUpgraded { .. } => "📦",
+            Msg::Query { .. } => "🔎",
+            Msg::Stats { .. }
+            | Msg::Burndown { .. } => "📊",
+            Msg::NothingDueToday => "🎉",
+            Msg::Exported { .. } => "📤",
+            Msg::Archived { .. } => "🗄️",
+            Msg::Watching { .. } => "👀",
+            Msg::ReminderFired { .. } => "⏰",
+            Msg::DigestSent { .. } => "📧",
+            Msg::Warned { .. }
+            | Msg::Ignoring { .. }
+            | Msg::IgnoringSetting { .. }
+            | Msg::CouldNotUpdate { .. }
+            | Msg::CouldNotNote { .. }
+            | Msg::DueAfterParent { .. }
+            | Msg::AutoReminderPassed { .. }
+            | Msg::JoinedLines { .. }
+            | Msg::SameText { .. }
+            | Msg::DoneSameText { .. }
+            | Msg::IgnoringTemplateDue { .. }
+            | Msg::IgnoringTemplateReminder { .. }
+            | Msg::TemplateReminderPassed { .. }
+            | Msg::IdTaken { .. }
+            | Msg::SkippedLine { .. }
+            | Msg::SkippedHeadings { .. }
+            | Msg::RunDoctor { .. }
+            | Msg::MissingParent { .. }
+            | Msg::ParentLoop { .. }
+            | Msg::ReopensUncounted { .. }
+            | Msg::SkippingFilter { .. }
+            | Msg::JsonOnly { .. }
+            | Msg::SqliteOnly { .. }
+            | Msg::ShellOnlyFlags
+            | Msg::HistoryNotSaved { .. }
+            | Msg::LegacyDb { .. }
+            | Msg::NothingToMigrate { .. }
+            | Msg::LegacyTooNew { .. }
+            | Msg::LegacyJson { .. }
+            | Msg::LegacyNotEmpty
+            | Msg::AnswerNotNoted { .. }
+            | Msg::SqliteHasTodos { .. }
+            | Msg::JsonHasTodos { .. }
+            | Msg::NewerFormat { .. }
+            | Msg::TornLine { .. }
+            | Msg::IdReassigned { .. }
+            | Msg::RemindersConverted { .. }
+            | Msg::EventsNotLogged { .. }
+            | Msg::NeedsEventsLog { .. }
+            | Msg::SkippedEvents { .. }
+            | Msg::ArchiveNotWritten { .. }
+            | Msg::TrashNotSwept { .. }
+            | Msg::SweepFailed { .. }
+            | Msg::TuiChangesKept
+            | Msg::TuiConflicts { .. }
+            | Msg::NotWatching { .. }
+            | Msg::WatchFailed { .. }
+            | Msg::BadNotifyTarget { .. }
+            | Msg::NotDelivered { .. }
+            | Msg::NoCtrlC { .. }
+            | Msg::Finding { .. }
+            | Msg::Overdue { .. } => "⚠️",
+            Msg::NotFound { .. }
+            | Msg::BackupFailed { .. }
+            | Msg::BackupUnreadable { .. }
+            | Msg::NotEmpty { .. }
+            | Msg::SafetyCopyFailed { .. }
+            | Msg::BadCommand { .. }
+            | Msg::CantRunHere { .. }
+            | Msg::StoreUnreadable { .. }
+            | Msg::TuiNotSaved
+            | Msg::TrashUnreadable { .. }
+            | Msg::TrashNotEmptied { .. }
+            | Msg::Failed { .. }
+            | Msg::CouldNotRead { .. }
+            | Msg::CouldNotWrite { .. }
+            | Msg::CouldNotSave { .. }
+            | Msg::CouldNotDelete { .. }
+            | Msg::CouldNotLock { .. }
+            | Msg::CouldNotCreate { .. }
+            | Msg::ReadOnlyStore
+            | Msg::InvalidFilter { .. }
+            | Msg::InvalidListFormat { .. }
+            | Msg::InvalidFormat { .. }
+            | Msg::InvalidDateFormat
+            | Msg::InvalidDueFormat
+            | Msg::InvalidReminderFormat
+            | Msg::DueNotSet { .. }
+            | Msg::ReminderNotSet { .. }
+            | Msg::NothingMatched
+            | Msg::NoPendingText { .. }
+            | Msg::AmbiguousText { .. }
+            | Msg::UnknownField { .. }
+            | Msg::EditAborted { .. }
+            | Msg::EmptyEdit
+            | Msg::NoTemplate { .. }
+            | Msg::BadTemplateName
+            | Msg::BadTemplateSource { .. }
+            | Msg::TemplateTextInvalid { .. }
+            | Msg::MissingVars { .. }
+            | Msg::NotInTrash { .. }
+            | Msg::NotAStore { .. }
+            | Msg::MergeNeedsStrategy
+            | Msg::UnreadableTasks { .. }
+            | Msg::DueLooksWrong { .. }
+            | Msg::AbsurdDates
+            | Msg::PlanNeedsTerminal
+            | Msg::Problems { .. }
+            | Msg::NoSuchProfile { .. }
+            | Msg::BadProfileName { .. }
+            | Msg::ProfileExists { .. }
+            | Msg::BadFilterName
+            | Msg::FilterInConfig { .. }
+            | Msg::NoFilter { .. }
+            | Msg::GoalNeedsTag
+            | Msg::NoGoal { .. }
+            | Msg::MigrateNeedsJson
+            | Msg::AlreadyInShell
+            | Msg::ShellFailed { .. }
+            | Msg::NotUnderstood { .. }
+            | Msg::LegacyUnreadable { .. }
+            | Msg::LegacyNotCopied { .. }
+            | Msg::NotMigrated { .. }
+            | Msg::DatabaseLocked { .. }
+            | Msg::DatabaseError { .. }
+            | Msg::EventsUnreadable { .. }
+            | Msg::CantExportGoogleTasks
+            | Msg::NoSafetyCopies { .. }
+            | Msg::SafetyCopyOutOfRange { .. }
+            | Msg::CouldNotCheckReminders { .. }
+            | Msg::DigestNotSent { .. }
+            | Msg::Cancelled => "❌",
+            _ => return None,
+        })
+    }
+
+    fn text(&self) -> String {
+        match self {
+            Msg::Added { id, label } => format!("Todo {} added{}!", id, label),
+            Msg::AddedSubtask { id, parent, label } => format!("Todo {} added as a subtask of #{}{}!", id, parent, label),
+            Msg::AddedFromTemplate { id, template, label } => format!("Todo {} added from template '{}'{}!", id, template, label),
+            Msg::Reopened { id, label } => format!("Todo {} reopened{}!", id, label),
+            Msg::MarkedDone { id: Some(id), label } => format!("Todo {} marked as done{}!", id, label),
+            Msg::MarkedDone { id: None, label } => format!("Todo marked as done{}!", label),
+            Msg::Completed { id, text } => format!("Todo {} marked as done: {}", id, text),
+            Msg::CompletedMany { count, label } => format!("Marked {} todos done{}", count, label),
+            Msg::NextOccurrence { id, due, label } => format!("Next occurrence is todo {}, due {}{}", id, due, label),
+            Msg::Deleted { id, label } => format!("Deleted todo with id {}{}", id, label),
+            Msg::DeletedMany { count, label } => format!("Deleted {} todos{}", count, label),
+            Msg::DueSet { id, label } => format!("Due date set for todo {}{}!", id, label),
+            Msg::ReminderSet { id, label } => format!("Reminder set for todo {}{}!", id, label),
+            Msg::ReminderSetAt { id, at } => format!("Reminder set for todo {} at {}", id, at),
+            Msg::Planned { count, skipped, label } => format!("Planned {} todos{}, skipped {}", count, label, skipped),
+            Msg::ReminderCleared { id, label } => format!("Reminder cleared for todo {}{}!", id, label),
+            Msg::ReminderCancelled { at } => format!("Reminder for {} cancelled", at),
+            Msg::TagsSet { id, tags, label } => format!("Tags for todo {} are now: {}{}", id, tags, label),
+            Msg::PrioritySet { id, level, label } => format!("Priority for todo {} set to {}{}!", id, level, label),
+            Msg::PriorityCleared { id, label } => format!("Priority cleared for todo {}{}!", id, label),
+            Msg::Updated { id: Some(id), label } => format!("Todo {} updated{}!", id, label),
+            Msg::Updated { id: None, label } => format!("Todo updated{}!", label),
+            Msg::NotFound { id } => format!("Todo with id {} not found", id),
+            Msg::Imported { count, details, from, label } => {
+                format!("Imported {} todos ({}) from {}{}", count, details, from.display(), label)
+            }
+            Msg::BackedUp { count, to, label } => format!("Backed up {} todos to {}{}", count, to.display(), label),
+            Msg::BackupFailed { error } => format!("Backup failed: {}", error),
+            Msg::BackupUnreadable { error } => format!("Could not read backup: {}", error),
+            Msg::NotEmpty { count, store } => format!("The {} already has {} todos; pass --force to overwrite it", store, count),
+            Msg::SafetyCopy { path } => format!("Saved a safety copy to {}", path),
+            Msg::SafetyCopyFailed { error } => format!("Could not make a safety copy: {}", error),
+            Msg::Restored { count, backend, label } => format!("Restored {} todos from a {} backup{}", count, backend, label),
+            Msg::ReminderDue { id, text, more: 0 } => format!("Reminder: #{} {}", id, text),
+            Msg::ReminderDue { id, text, more } => format!("Reminder: #{} {} and {} more", id, text, more),
+            Msg::Edited { summary } => summary.to_string(),
+            Msg::AddWarning { warning } => warning.to_string(),
+            Msg::Refreshed => "Refreshed with changes saved elsewhere".to_string(),
+            Msg::ReadOnly { reason } => format!("Read-only ({}); nothing can be changed", reason),
+            Msg::TagsCleared => "Tags cleared".to_string(),
+            Msg::Tags { tags } => format!("Tags: {}", tags),
+            Msg::NotSaved { error } => format!("Not saved: {}", error),
+            Msg::NotAdded { error } => format!("Not added: {}", error),
+            Msg::NotDeleted { error } => format!("Not deleted: {}", error),
+            Msg::InvalidDue { value } => format!("Invalid due date '{}': use YYYY-MM-DD [HH:MM]", value),
+            Msg::InvalidReminder { value } => format!("Invalid reminder '{}': use YYYY-MM-DD [HH:MM]", value),
+            Msg::CantMoveDue { value } => format!("Can't move due date '{}': use YYYY-MM-DD [HH:MM]", value),
+            Msg::DueMoved { from, to } => format!("due: {} → {}", from, to),
+            Msg::BulkSet { field, value: Some(value), count } => format!("set {} {} on {} todos", field, value, count),
+            Msg::BulkSet { field, value: None, count } => format!("cleared {} on {} todos", field, count),
+            Msg::SubtasksMoved { count: 1, by, label } => format!("Moved 1 subtask's due date {}{}", by, label),
+            Msg::SubtasksMoved { count, by, label } => format!("Moved {} subtasks' due dates {}{}", count, by, label),
+            Msg::NothingToWatch => "Nothing else can change an in-memory database, so there is nothing to watch".to_string(),
+            Msg::BadCommand { error } => error.to_string(),
+            Msg::CantRunHere { command } => {
+                format!("`{}` can't be run from the TUI; it takes add, done, delete, due, remind and filter", command)
+            }
+            Msg::Filtered { filter } => format!("Showing todos matching {}; f goes on to the next view", filter),
+            Msg::TuiFailed { error } => format!("TUI Error: {}", error),
+            Msg::StoreUnreadable { error } => {
+                format!("Could not read the todos, so nothing was saved: {}\n   Fix the file, or bring back a backup with `todo restore`", error)
+            }
+            Msg::TuiNotSaved => "The TUI's changes were not saved".to_string(),
+            Msg::TrashEmpty => "The trash is empty".to_string(),
+            Msg::TrashEmptied { count: 1 } => "Emptied the trash of 1 todo".to_string(),
+            Msg::TrashEmptied { count } => format!("Emptied the trash of {} todos", count),
+            Msg::TrashUnreadable { error } => format!("Could not read the trash: {}", error),
+            Msg::TrashNotEmptied { error } => format!("Could not empty the trash: {}", error),
+            Msg::Failed { error } => error.to_string(),
+            Msg::Warned { warning } => warning.to_string(),
+            Msg::Ignoring { what, error } => format!("Ignoring {}: {}", what, error),
+            Msg::IgnoringSetting { key, file, error } => format!("Ignoring {} in {}: {}", key, file.display(), error),
+            Msg::CouldNotRead { what, error } => format!("Could not read {}: {}", what, error),
+            Msg::CouldNotWrite { what, error } => format!("Could not write {}: {}", what, error),
+            Msg::CouldNotSave { what, error } => format!("Could not save {}: {}", what, error),
+            Msg::CouldNotDelete { what, error } => format!("Could not delete {}: {}", what, error),
+            Msg::CouldNotUpdate { what, error } => format!("Could not update {}: {}", what, error),
+            Msg::CouldNotNote { what, error } => format!("Could not note when {}: {}", what, error),
+            Msg::CouldNotLock { error } => format!("Could not lock the todos: {}", error),
+            Msg::CouldNotCreate { path, error } => format!("Could not create {}: {}", path.display(), error),
+            Msg::ReadOnlyStore => {
+                "The todo list is read-only (readonly in the config or TODO_READONLY); nothing was saved".to_string()
+            }
+            Msg::DryRun => "Dry run, nothing was saved:".to_string(),
+            Msg::DryRunNoChanges => "Dry run: no changes".to_string(),
+            Msg::InvalidFilter { error } => format!("Invalid filter: {}", error),
+            Msg::InvalidListFormat { error } => format!("Invalid list format: {}", error),
+            Msg::InvalidFormat { error } => format!("Invalid format: {}", error),
+            Msg::InvalidDateFormat => "Invalid date format. Please use YYYY-MM-DD".to_string(),
+            Msg::InvalidDueFormat => {
+                "Invalid date or time format. Please use YYYY-MM-DD (or today, fri, 3d, w29, q3, eom, eoy) and HH:MM".to_string()
+            }
+            Msg::InvalidReminderFormat => "Invalid date/time format. Please use YYYY-MM-DD HH:MM".to_string(),
+            Msg::DueNotSet { error } => format!("Due date not set: {}", error),
+            Msg::ReminderNotSet { error } => format!("Reminder not set: {}", error),
+            Msg::DueAfterParent { id, due, parent, parent_due } => {
+                format!("Todo {} is due {}, after its parent #{} ({})", id, due, parent, parent_due)
+            }
+            Msg::AutoReminderPassed { id } => {
+                format!("The auto reminder for todo {} would already have passed; reminding in 1 hour instead", id)
+            }
+            Msg::Repeats { id, rule, label } => format!("Todo {} repeats {}{}", id, rule, label),
+            Msg::NoLongerRepeats { id, label } => format!("Todo {} no longer repeats{}", id, label),
+            Msg::NothingMatched => "Nothing matched; no todos were marked done".to_string(),
+            Msg::NoPendingText { text } => format!("No pending todo has the text '{}'", text),
+            Msg::AmbiguousText { count, text } => {
+                format!("{} pending todos have the text '{}'; give an id, or --first for the oldest:", count, text)
+            }
+            Msg::JoinedLines { count } => format!("Joined {} lines into one; todo text can't hold line breaks", count),
+            Msg::SameText { id } => format!("Todo {} already has this text", id),
+            Msg::DoneSameText { id } => format!("Done todo {} has the same text; add --reopen to reopen it instead", id),
+            Msg::UnknownField { field, nearest: Some(nearest), .. } => {
+                format!("Unknown field '{}'; did you mean '{}'?", field, nearest)
+            }
+            Msg::UnknownField { field, nearest: None, fields } => format!("Unknown field '{}'; use one of {}", field, fields),
+            Msg::EditedMany { updated, added, deleted, label } => {
+                format!("Updated {}, added {} and deleted {} todos{}", updated, added, deleted, label)
+            }
+            Msg::EditAborted { reason } => format!("Edit aborted: {}", reason),
+            Msg::EmptyEdit => "Refusing to save an empty todo; edit aborted".to_string(),
+            Msg::NoTemplate { name } => format!("No template named '{}'", name),
+            Msg::BadTemplateName => "Template names may only use letters, digits, - and _".to_string(),
+            Msg::BadTemplateSource { error } => format!("Bad template text: {}; give the text with --text", error),
+            Msg::TemplateTextInvalid { name, error } => format!("Template '{}' has bad text: {}", name, error),
+            Msg::MissingVars { names, first } => format!("Missing values for {}; give them with --var {}=...", names, first),
+            Msg::TemplateSaved { name, text } => format!("Saved template '{}': {}", name, text),
+            Msg::TemplateDeleted { name } => format!("Deleted template '{}'", name),
+            Msg::IgnoringTemplateDue { offset } => format!("Ignoring template due date '{}'; use an offset such as +3d", offset),
+            Msg::IgnoringTemplateReminder { error } => format!("Ignoring template reminder: {}", error),
+            Msg::TemplateReminderPassed { id } => {
+                format!("The template's reminder for todo {} would already have passed; none set", id)
+            }
+            Msg::NotInTrash { id } => format!("No todo with id {} in the trash; `todo trash list` shows what is there", id),
+            Msg::Untrashed { id, label } => format!("Restored todo {}{}", id, label),
+            Msg::UntrashedAs { id, new_id, label } => format!("Restored todo {} as todo {}{}", id, new_id, label),
+            Msg::IdTaken { id, text, new_id } => format!("Id {} is now '{}', so the restored todo got id {}", id, text, new_id),
+            Msg::NotAStore { path, error } => format!("{} is not a todo store: {}", path.display(), error),
+            Msg::MergeNeedsStrategy => "Pass --ours, --theirs or --newest to merge without a terminal to ask on".to_string(),
+            Msg::Merged { from, added, identical, kept_ours, took_theirs, label } => format!(
+                "Merged {}: {} added, {} already here, {} changed on both sides ({} kept ours, {} took theirs){}",
+                from.display(),
+                added,
+                identical,
+                kept_ours + took_theirs,
+                kept_ours,
+                took_theirs,
+                label
+            ),
+            Msg::SkippedLine { unit, line, reason } => format!("Skipped {} {}: {}", unit, line, reason),
+            Msg::SkippedHeadings { count } => format!("Skipped {} headings that aren't TODO or DONE", count),
+            Msg::UnreadableTasks { count } => {
+                format!("Nothing imported, as {} tasks couldn't be read; pass --partial to import the rest", count)
+            }
+            Msg::DueLooksWrong { text, error } => format!("'{}' has a due date that looks wrong: {}", text, error),
+            Msg::AbsurdDates => "Nothing imported; fix the dates, or pass --no-date-sanity to import them as they are".to_string(),
+            Msg::PlanNeedsTerminal => "plan asks for a key for each todo, so it needs a terminal".to_string(),
+            Msg::Planning { count, week } => format!("Planning {} todos without a due date for the week of {}", count, week),
+            Msg::NoProblems => "No problems found".to_string(),
+            Msg::Finding { finding } => finding.to_string(),
+            Msg::Problems { count } => format!("{} problem(s) found", count),
+            Msg::Fixed { id } => format!("Fixed #{}", id),
+            Msg::RunDoctor { problems, fix: true } => format!("{} — run `todo doctor --fix`", problems),
+            Msg::RunDoctor { problems, fix: false } => format!("{} — run `todo doctor`", problems),
+            Msg::Overdue { count } => format!("{} overdue", count),
+            Msg::MissingParent { id, parent } => {
+                format!("Todo {}'s parent #{} doesn't exist; it is shown at the top level", id, parent)
+            }
+            Msg::ParentLoop { ids } => format!("The parents of {} go round in a loop; they are shown at the top level", ids),
+            Msg::ReopensUncounted { error } => format!("Could not read the events log: {}", error),
+            Msg::NoSuchProfile { name } => format!("No profile named '{}'; create it with `todo profile create {}`", name, name),
+            Msg::BadProfileName { default } => {
+                format!("Profile names may only use letters, digits, - and _, and can't be '{}'", default)
+            }
+            Msg::ProfileExists { name } => format!("Profile '{}' already exists", name),
+            Msg::ProfileCreated { name, dir } => format!(
+                "Created profile '{}' in {}\n   Use it with `todo --profile {}` or TODO_PROFILE={}",
+                name,
+                dir.display(),
+                name,
+                name
+            ),
+            Msg::BadFilterName => "Filter names may only use letters, digits, - and _".to_string(),
+            Msg::FilterInConfig { name, config, action } => {
+                format!("'{}' is defined in {}; {} it there", name, config.display(), action)
+            }
+            Msg::FilterSaved { name, expr } => format!("Saved filter '{}': {}", name, expr),
+            Msg::FilterDeleted { name } => format!("Deleted filter '{}'", name),
+            Msg::SkippingFilter { name, error } => format!("Skipping filter '{}': {}", name, error),
+            Msg::NoFilter { name } => format!("No filter named '{}'", name),
+            Msg::GoalNeedsTag => "A goal needs a tag".to_string(),
+            Msg::GoalSet { tag, goal } => format!("Goal for #{}: {}", tag, goal),
+            Msg::NoGoal { tag } => format!("No goal for #{}", tag),
+            Msg::GoalDeleted { tag } => format!("Deleted the goal for #{}", tag),
+            Msg::JsonOnly { flag } => format!("{} only applies to the JSON store", flag),
+            Msg::SqliteOnly { flag } => format!("{} only applies to the SQLite backend", flag),
+            Msg::MigrateNeedsJson => "migrate rewrites the JSON store; leave out --sqlite to use it".to_string(),
+            Msg::ShellOnlyFlags => "--sqlite, --db and --profile only apply when starting the shell".to_string(),
+            Msg::AlreadyInShell => "Already in the shell".to_string(),
+            Msg::ShellFailed { error } => format!("Could not start the shell: {}", error),
+            Msg::HistoryNotSaved { error } => format!("Could not save the shell history: {}", error),
+            Msg::ReadAgain => "Read the todos again, as they were saved elsewhere".to_string(),
+            Msg::NotUnderstood { line, help } => format!("Didn't understand '{}'. {}", line, help),
+            Msg::DueSoon { names } => format!("due soon: {}", names),
+            Msg::LegacyDb { db, legacy, count } => format!(
+                "No todos in {}, but ./{} has {}\n   Use it with `todo --db ./{}`, or move it to {}",
+                db.display(),
+                legacy,
+                count,
+                legacy,
+                db.display()
+            ),
+            Msg::NothingToMigrate { legacy } => format!("Nothing to migrate: there is no {}", legacy.display()),
+            Msg::LegacyUnreadable { legacy } => format!("Could not read the todos in {}", legacy.display()),
+            Msg::LegacyTooNew { legacy, count, why } => {
+                format!("{} has {} todos, but can't be copied: {}", legacy.display(), count, why)
+            }
+            Msg::LegacyJson { json, legacy, count } => format!(
+                "No todos in {}, but {} has {}\n   Run todo --migrate-legacy to copy them over",
+                json.display(),
+                legacy.display(),
+                count
+            ),
+            Msg::LegacyNotEmpty => "The JSON store already has todos; --migrate-legacy only fills an empty one".to_string(),
+            Msg::AnswerNotNoted { error } => format!("Could not note the answer, so this will be asked again: {}", error),
+            Msg::LegacyCopied { count, from, to, kept } => format!(
+                "Copied {} todos from {} to {}; a backup of the original is at {}",
+                count,
+                from.display(),
+                to.display(),
+                kept
+            ),
+            Msg::LegacyNotCopied { legacy, error } => format!("Could not copy the todos in {}: {}", legacy.display(), error),
+            Msg::SqliteHasTodos { count, config } => format!(
+                "No todos in the JSON store, but your SQLite database has {}; did you mean to pass --sqlite?\n   Set backend = \"sqlite\" in {} to use it by default",
+                count,
+                config.display()
+            ),
+            Msg::JsonHasTodos { count } => format!("No todos in the SQLite database, but your JSON store has {}", count),
+            Msg::StoreMoved { to, kept } => format!("Moved the todos to {}; the old file is kept as {}", to.display(), kept),
+            Msg::StoreStarted { at } => format!("Started an empty JSON store at {}", at.display()),
+            Msg::NotMigrated { error } => format!("Could not migrate the JSON store: {}", error),
+            Msg::FormatUpgraded { path, version, kept } => {
+                format!("Upgraded {} to format {}; the old file is kept as {}", path.display(), version, kept)
+            }
+            Msg::NewerFormat { path, why } => format!("{}: {}", path.display(), why),
+            Msg::TornLine { path, bytes } => {
+                format!("{}: left out a last line an add was cut off in ({} bytes); saving drops it", path.display(), bytes)
+            }
+            Msg::IdReassigned { old, text, new } => format!("Duplicate or invalid id {} for '{}' reassigned to {}", old, text, new),
+            Msg::RemindersConverted { count, offset } => {
+                format!("Converted {} reminders to timestamps, reading them as local time (UTC{})", count, offset)
+            }
+            Msg::DatabaseLocked { waited } => format!("database is locked by another process; retried for {}s", waited),
+            Msg::DatabaseError { error } => format!("Database error: {}", error),
+            Msg::Query { ms, sql } => format!("{:.3}ms  {}", ms, sql),
+            Msg::EventsUnreadable { error } => format!("Could not read the events log: {}", error),
+            Msg::EventsNotLogged { error } => format!("Could not write the events log: {}", error),
+            Msg::NeedsEventsLog { config } => {
+                format!("--count-reopens needs the events log; set events_log = true in {} to start one", config.display())
+            }
+            Msg::SkippedEvents { count } => format!("Skipped {} unreadable lines in the events log", count),
+            Msg::Stats { count, pending, done } => format!("{} todos: {} pending, {} done", count, pending, done),
+            Msg::Burndown { scope, days } => format!("Open todos{} over the last {} days", scope, days),
+            Msg::NothingDueToday => "Nothing due today".to_string(),
+            Msg::CantExportGoogleTasks => "google-tasks can only be imported; export to markdown or org instead".to_string(),
+            Msg::Exported { count, to } => format!("Exported {} todos to {}", count, to.display()),
+            Msg::NoSafetyCopies { store } => {
+                format!("No safety copies of {}; `restore` makes one, or give a bundle with --bundle", store.display())
+            }
+            Msg::SafetyCopyOutOfRange { count, store } => {
+                format!("There are {} safety copies of {}; pick one from 1 to {}", count, store.display(), count)
+            }
+            Msg::Archived { count, age, label } => format!("Archived {} todos done more than {} ago{}", count, age, label),
+            Msg::ArchiveNotWritten { error } => format!("Could not write the archive, so nothing was archived: {}", error),
+            Msg::TrashSwept { count, age } => format!("Emptied {} todos deleted more than {} ago from the trash", count, age),
+            Msg::TrashNotSwept { error } => format!("Could not empty old todos from the trash: {}", error),
+            Msg::SweepFailed { error } => format!("The auto-archive sweep failed: {}", error),
+            Msg::TuiChangesKept => "The todos changed while the TUI was open; kept those changes as well as yours".to_string(),
+            Msg::TuiConflicts { count } => {
+                format!("The todos changed while the TUI was open, and {} were changed both there and here:", count)
+            }
+            Msg::ReminderReset { id } => format!("The reminder for todo {} will be announced again", id),
+            Msg::CouldNotCheckReminders { error } => format!("Could not check reminders: {}", error),
+            Msg::NotWatching { error } => format!("Not watching for changes: {}", error),
+            Msg::Watching { every } => format!("Watching for reminders every {}s; press Ctrl-C to stop", every),
+            Msg::WatchFailed { what, error } => format!("Could not {}: {}", what, error),
+            Msg::ReminderFired { id, text, at } => format!("#{} {} ({})", id, text, at),
+            Msg::BadNotifyTarget { target } => format!(
+                "Ignoring notify target '{}'; use \"desktop\", \"ntfy:<topic URL>\" or \"email:<address>\"",
+                target
+            ),
+            Msg::NotDelivered { via, id, error } => format!("{} failed for todo {}: {}", via, id, error),
+            Msg::DigestSent { to } => format!("Digest sent to {}", to),
+            Msg::DigestNotSent { error } => format!("Could not send digest: {}", error),
+            Msg::NoCtrlC { error } => format!("Ctrl-C will stop todo wherever it is: {}", error),
+            Msg::Cancelled => "Cancelled".to_string(),
+        }
+    }
+}
+
+impl Msg<'_> {
+    /// The message as shown, led by its emoji when `emoji` is set
+    fn render(&self, emoji: bool) -> String {
+        match self.emoji() {
+            Some(lead) if emoji => format!("{} {}", lead, self.text()),
+            _ => self.text(),
+        }
+    }
+}
+
+impl fmt::Display for Msg<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.render(EMOJI.load(Ordering::Relaxed)))
+    }
+}
+
+/// Print `msg` on stdout, or on stderr if something went wrong or it is only a note
+pub fn say(msg: Msg) {
+    if msg.level().on_stderr() {
+        eprintln!("{}", msg);
+    } else {
+        println!("{}", msg);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Every message as it reads by default, so rewording one is a deliberate change
+    #[test]
+    fn messages_read_as_they_always_have() {
+        let cases = [
+            (Msg::Added { id: 3, label: " (SQLite)" }, "✅ Todo 3 added (SQLite)!"),
+            (Msg::AddedSubtask { id: 3, parent: 1, label: " (SQLite)" }, "✅ Todo 3 added as a subtask of #1 (SQLite)!"),
+            (Msg::AddedFromTemplate { id: 3, template: "weekly", label: " (SQLite)" }, "✅ Todo 3 added from template 'weekly' (SQLite)!"),
+            (Msg::Reopened { id: 3, label: " (SQLite)" }, "✅ Todo 3 reopened (SQLite)!"),
+            (Msg::MarkedDone { id: Some(3), label: " (SQLite)" }, "🎉 Todo 3 marked as done (SQLite)!"),
+            (Msg::Completed { id: 3, text: "buy milk" }, "🎉 Todo 3 marked as done: buy milk"),
+            (Msg::CompletedMany { count: 2, label: " (SQLite)" }, "✅ Marked 2 todos done (SQLite)"),
+            (Msg::NextOccurrence { id: 3, due: "2026-10-20", label: " (SQLite)" }, "🔁 Next occurrence is todo 3, due 2026-10-20 (SQLite)"),
+            (Msg::Deleted { id: 3, label: " (SQLite)" }, "🗑\u{fe0f} Deleted todo with id 3 (SQLite)"),
+            (Msg::DeletedMany { count: 2, label: " (SQLite)" }, "🗑\u{fe0f} Deleted 2 todos (SQLite)"),
+            (Msg::DueSet { id: 3, label: " (SQLite)" }, "📅 Due date set for todo 3 (SQLite)!"),
+            (Msg::ReminderSet { id: 3, label: " (SQLite)" }, "⏰ Reminder set for todo 3 (SQLite)!"),
+            (Msg::ReminderSetAt { id: 3, at: "2026-10-20 09:00" }, "⏰ Reminder set for todo 3 at 2026-10-20 09:00"),
+            (Msg::Planned { count: 2, skipped: 1, label: " (SQLite)" }, "📅 Planned 2 todos (SQLite), skipped 1"),
+            (Msg::ReminderCleared { id: 3, label: " (SQLite)" }, "🗑\u{fe0f} Reminder cleared for todo 3 (SQLite)!"),
+            (Msg::ReminderCancelled { at: "2026-10-20 09:00" }, "🔕 Reminder for 2026-10-20 09:00 cancelled"),
+            (Msg::TagsSet { id: 3, tags: "#home #errands", label: " (SQLite)" }, "🏷\u{fe0f} Tags for todo 3 are now: #home #errands (SQLite)"),
+            (Msg::PrioritySet { id: 3, level: "high", label: " (SQLite)" }, "🔺 Priority for todo 3 set to high (SQLite)!"),
+            (Msg::PriorityCleared { id: 3, label: " (SQLite)" }, "🔺 Priority cleared for todo 3 (SQLite)!"),
+            (Msg::Updated { id: Some(3), label: " (SQLite)" }, "📝 Todo 3 updated (SQLite)!"),
+            (Msg::NotFound { id: 3 }, "❌ Todo with id 3 not found"),
+            (Msg::Imported { count: 2, details: "2 pending, 1 done", from: Path::new("/tmp/todos.md"), label: " (SQLite)" }, "📥 Imported 2 todos (2 pending, 1 done) from /tmp/todos.md (SQLite)"),
+            (Msg::BackedUp { count: 2, to: Path::new("/tmp/todos.tar.gz"), label: " (SQLite)" }, "💾 Backed up 2 todos to /tmp/todos.tar.gz (SQLite)"),
+            (Msg::BackupFailed { error: &"disk full" }, "❌ Backup failed: disk full"),
+            (Msg::BackupUnreadable { error: &"disk full" }, "❌ Could not read backup: disk full"),
+            (Msg::NotEmpty { count: 2, store: "store" }, "❌ The store already has 2 todos; pass --force to overwrite it"),
+            (Msg::SafetyCopy { path: "/tmp/data/todo/todos.json.safety-1" }, "🛟 Saved a safety copy to /tmp/data/todo/todos.json.safety-1"),
+            (Msg::SafetyCopyFailed { error: &"disk full" }, "❌ Could not make a safety copy: disk full"),
+            (Msg::Restored { count: 2, backend: "JSON", label: " (SQLite)" }, "♻\u{fe0f} Restored 2 todos from a JSON backup (SQLite)"),
+            (Msg::ReminderDue { id: 3, text: "buy milk", more: 2 }, "⏰ Reminder: #3 buy milk and 2 more"),
+            (Msg::Edited { summary: "buy [-milk-]{+oat milk+}" }, "buy [-milk-]{+oat milk+}"),
+            (Msg::AddWarning { warning: "no date after \"by\"" }, "no date after \"by\""),
+            (Msg::Refreshed, "↻ Refreshed with changes saved elsewhere"),
+            (Msg::ReadOnly { reason: "TODO_READONLY is set" }, "Read-only (TODO_READONLY is set); nothing can be changed"),
+            (Msg::TagsCleared, "Tags cleared"),
+            (Msg::Tags { tags: "#home #errands" }, "Tags: #home #errands"),
+            (Msg::NotSaved { error: &"disk full" }, "Not saved: disk full"),
+            (Msg::NotAdded { error: &"disk full" }, "Not added: disk full"),
+            (Msg::NotDeleted { error: &"disk full" }, "Not deleted: disk full"),
+            (Msg::InvalidDue { value: "2026-13-01" }, "Invalid due date '2026-13-01': use YYYY-MM-DD [HH:MM]"),
+            (Msg::InvalidReminder { value: "2026-13-01" }, "Invalid reminder '2026-13-01': use YYYY-MM-DD [HH:MM]"),
+            (Msg::CantMoveDue { value: "2026-13-01" }, "Can't move due date '2026-13-01': use YYYY-MM-DD [HH:MM]"),
+            (Msg::DueMoved { from: "2026-10-20", to: "2026-10-21" }, "due: 2026-10-20 → 2026-10-21"),
+            (Msg::BulkSet { field: "due", value: Some("2026-10-20"), count: 2 }, "set due 2026-10-20 on 2 todos"),
+            (Msg::SubtasksMoved { count: 2, by: "3 days later", label: " (SQLite)" }, "📅 Moved 2 subtasks' due dates 3 days later (SQLite)"),
+            (Msg::NothingToWatch, "⚠\u{fe0f} Nothing else can change an in-memory database, so there is nothing to watch"),
+            (Msg::BadCommand { error: "unexpected argument '--x'" }, "❌ unexpected argument '--x'"),
+            (Msg::CantRunHere { command: "export" }, "❌ `export` can't be run from the TUI; it takes add, done, delete, due, remind and filter"),
+            (Msg::Filtered { filter: "due<+3d" }, "Showing todos matching due<+3d; f goes on to the next view"),
+            (Msg::TuiFailed { error: &"disk full" }, "TUI Error: disk full"),
+            (Msg::StoreUnreadable { error: &"disk full" }, "❌ Could not read the todos, so nothing was saved: disk full\n   Fix the file, or bring back a backup with `todo restore`"),
+            (Msg::TuiNotSaved, "❌ The TUI's changes were not saved"),
+            (Msg::TrashEmpty, "The trash is empty"),
+            (Msg::TrashEmptied { count: 2 }, "🗑\u{fe0f} Emptied the trash of 2 todos"),
+            (Msg::TrashUnreadable { error: &"disk full" }, "❌ Could not read the trash: disk full"),
+            (Msg::TrashNotEmptied { error: &"disk full" }, "❌ Could not empty the trash: disk full"),
+            (Msg::Failed { error: &"disk full" }, "❌ disk full"),
+            (Msg::Warned { warning: &"no date after \"by\"" }, "⚠\u{fe0f} no date after \"by\""),
+            (Msg::Ignoring { what: &"/tmp/data/todo/filters.json", error: &"expected value at line 1 column 1" }, "⚠\u{fe0f} Ignoring /tmp/data/todo/filters.json: expected value at line 1 column 1"),
+            (Msg::IgnoringSetting { key: "nag_every", file: Path::new("/tmp/config/todo/config.toml"), error: &"disk full" }, "⚠\u{fe0f} Ignoring nag_every in /tmp/config/todo/config.toml: disk full"),
+            (Msg::CouldNotRead { what: &"/tmp/todos.md", error: &"disk full" }, "❌ Could not read /tmp/todos.md: disk full"),
+            (Msg::CouldNotWrite { what: &"the burndown", error: &"disk full" }, "❌ Could not write the burndown: disk full"),
+            (Msg::CouldNotSave { what: "the goal", error: &"disk full" }, "❌ Could not save the goal: disk full"),
+            (Msg::CouldNotDelete { what: "filter", error: &"disk full" }, "❌ Could not delete filter: disk full"),
+            (Msg::CouldNotUpdate { what: "the trash", error: &"disk full" }, "⚠\u{fe0f} Could not update the trash: disk full"),
+            (Msg::CouldNotNote { what: "the archive sweep ran", error: &"disk full" }, "⚠\u{fe0f} Could not note when the archive sweep ran: disk full"),
+            (Msg::CouldNotLock { error: &"disk full" }, "❌ Could not lock the todos: disk full"),
+            (Msg::CouldNotCreate { path: Path::new("/tmp/data/todo/work"), error: &"disk full" }, "❌ Could not create /tmp/data/todo/work: disk full"),
+            (Msg::ReadOnlyStore, "❌ The todo list is read-only (readonly in the config or TODO_READONLY); nothing was saved"),
+            (Msg::DryRun, "🧪 Dry run, nothing was saved:"),
+            (Msg::DryRunNoChanges, "🧪 Dry run: no changes"),
+            (Msg::InvalidFilter { error: &"unknown field 'colour'" }, "❌ Invalid filter: unknown field 'colour'"),
+            (Msg::InvalidListFormat { error: &"unknown field {colour}" }, "❌ Invalid list format: unknown field {colour}"),
+            (Msg::InvalidFormat { error: &"unknown field {colour}" }, "❌ Invalid format: unknown field {colour}"),
+            (Msg::InvalidDateFormat, "❌ Invalid date format. Please use YYYY-MM-DD"),
+            (Msg::InvalidDueFormat, "❌ Invalid date or time format. Please use YYYY-MM-DD (or today, fri, 3d, w29, q3, eom, eoy) and HH:MM"),
+            (Msg::InvalidReminderFormat, "❌ Invalid date/time format. Please use YYYY-MM-DD HH:MM"),
+            (Msg::DueNotSet { error: &"disk full" }, "❌ Due date not set: disk full"),
+            (Msg::ReminderNotSet { error: &"disk full" }, "❌ Reminder not set: disk full"),
+            (Msg::DueAfterParent { id: 3, due: "2026-10-20", parent: 1, parent_due: "2026-10-18" }, "⚠\u{fe0f} Todo 3 is due 2026-10-20, after its parent #1 (2026-10-18)"),
+            (Msg::AutoReminderPassed { id: 3 }, "⚠\u{fe0f} The auto reminder for todo 3 would already have passed; reminding in 1 hour instead"),
+            (Msg::Repeats { id: 3, rule: "weekly", label: " (SQLite)" }, "🔁 Todo 3 repeats weekly (SQLite)"),
+            (Msg::NoLongerRepeats { id: 3, label: " (SQLite)" }, "🔁 Todo 3 no longer repeats (SQLite)"),
+            (Msg::NothingMatched, "❌ Nothing matched; no todos were marked done"),
+            (Msg::NoPendingText { text: "buy milk" }, "❌ No pending todo has the text 'buy milk'"),
+            (Msg::AmbiguousText { count: 2, text: "buy milk" }, "❌ 2 pending todos have the text 'buy milk'; give an id, or --first for the oldest:"),
+            (Msg::JoinedLines { count: 2 }, "⚠\u{fe0f} Joined 2 lines into one; todo text can't hold line breaks"),
+            (Msg::SameText { id: 3 }, "⚠\u{fe0f} Todo 3 already has this text"),
+            (Msg::DoneSameText { id: 3 }, "⚠\u{fe0f} Done todo 3 has the same text; add --reopen to reopen it instead"),
+            (Msg::UnknownField { field: "txt", nearest: Some("text"), fields: "text, due, done" }, "❌ Unknown field 'txt'; did you mean 'text'?"),
+            (Msg::EditedMany { updated: 1, added: 2, deleted: 1, label: " (SQLite)" }, "📝 Updated 1, added 2 and deleted 1 todos (SQLite)"),
+            (Msg::EditAborted { reason: "the editor exited with status 1" }, "❌ Edit aborted: the editor exited with status 1"),
+            (Msg::EmptyEdit, "❌ Refusing to save an empty todo; edit aborted"),
+            (Msg::NoTemplate { name: "weekly" }, "❌ No template named 'weekly'"),
+            (Msg::BadTemplateName, "❌ Template names may only use letters, digits, - and _"),
+            (Msg::BadTemplateSource { error: &"disk full" }, "❌ Bad template text: disk full; give the text with --text"),
+            (Msg::TemplateTextInvalid { name: "weekly", error: &"disk full" }, "❌ Template 'weekly' has bad text: disk full"),
+            (Msg::MissingVars { names: "{who}, {when}", first: "who" }, "❌ Missing values for {who}, {when}; give them with --var who=..."),
+            (Msg::TemplateSaved { name: "weekly", text: "review the week" }, "✅ Saved template 'weekly': review the week"),
+            (Msg::TemplateDeleted { name: "weekly" }, "🗑\u{fe0f} Deleted template 'weekly'"),
+            (Msg::IgnoringTemplateDue { offset: "+3d" }, "⚠\u{fe0f} Ignoring template due date '+3d'; use an offset such as +3d"),
+            (Msg::IgnoringTemplateReminder { error: &"disk full" }, "⚠\u{fe0f} Ignoring template reminder: disk full"),
+            (Msg::TemplateReminderPassed { id: 3 }, "⚠\u{fe0f} The template's reminder for todo 3 would already have passed; none set"),
+            (Msg::NotInTrash { id: 3 }, "❌ No todo with id 3 in the trash; `todo trash list` shows what is there"),
+            (Msg::Untrashed { id: 3, label: " (SQLite)" }, "♻\u{fe0f} Restored todo 3 (SQLite)"),
+            (Msg::UntrashedAs { id: 3, new_id: 5, label: " (SQLite)" }, "♻\u{fe0f} Restored todo 3 as todo 5 (SQLite)"),
+            (Msg::IdTaken { id: 3, text: "buy milk", new_id: 5 }, "⚠\u{fe0f} Id 3 is now 'buy milk', so the restored todo got id 5"),
+            (Msg::NotAStore { path: Path::new("/tmp/notes.txt"), error: &"expected value at line 1 column 1" }, "❌ /tmp/notes.txt is not a todo store: expected value at line 1 column 1"),
+            (Msg::MergeNeedsStrategy, "❌ Pass --ours, --theirs or --newest to merge without a terminal to ask on"),
+            (Msg::Merged { from: Path::new("/tmp/laptop.json"), added: 2, identical: 4, kept_ours: 1, took_theirs: 2, label: " (SQLite)" }, "🔀 Merged /tmp/laptop.json: 2 added, 4 already here, 3 changed on both sides (1 kept ours, 2 took theirs) (SQLite)"),
+            (Msg::SkippedLine { unit: "line", line: 7, reason: "not a task" }, "⚠\u{fe0f} Skipped line 7: not a task"),
+            (Msg::SkippedHeadings { count: 2 }, "⚠\u{fe0f} Skipped 2 headings that aren't TODO or DONE"),
+            (Msg::UnreadableTasks { count: 2 }, "❌ Nothing imported, as 2 tasks couldn't be read; pass --partial to import the rest"),
+            (Msg::DueLooksWrong { text: "buy milk", error: &"2126-10-20 is more than 10 years away" }, "❌ 'buy milk' has a due date that looks wrong: 2126-10-20 is more than 10 years away"),
+            (Msg::AbsurdDates, "❌ Nothing imported; fix the dates, or pass --no-date-sanity to import them as they are"),
+            (Msg::PlanNeedsTerminal, "❌ plan asks for a key for each todo, so it needs a terminal"),
+            (Msg::Planning { count: 2, week: "2026-10-19" }, "🗓\u{fe0f} Planning 2 todos without a due date for the week of 2026-10-19"),
+            (Msg::NoProblems, "✅ No problems found"),
+            (Msg::Finding { finding: "todo 3 has an invalid due date '2026-13-01'" }, "⚠\u{fe0f} todo 3 has an invalid due date '2026-13-01'"),
+            (Msg::Problems { count: 2 }, "❌ 2 problem(s) found"),
+            (Msg::Fixed { id: 3 }, "🔧 Fixed #3"),
+            (Msg::RunDoctor { problems: "todo 3 has an invalid due date '2026-13-01'", fix: true }, "⚠\u{fe0f} todo 3 has an invalid due date '2026-13-01' — run `todo doctor --fix`"),
+            (Msg::Overdue { count: 2 }, "⚠\u{fe0f} 2 overdue"),
+            (Msg::MissingParent { id: 3, parent: 1 }, "⚠\u{fe0f} Todo 3's parent #1 doesn't exist; it is shown at the top level"),
+            (Msg::ParentLoop { ids: "#1, #2" }, "⚠\u{fe0f} The parents of #1, #2 go round in a loop; they are shown at the top level"),
+            (Msg::ReopensUncounted { error: &"disk full" }, "⚠\u{fe0f} Could not read the events log: disk full"),
+            (Msg::NoSuchProfile { name: "work" }, "❌ No profile named 'work'; create it with `todo profile create work`"),
+            (Msg::BadProfileName { default: "default" }, "❌ Profile names may only use letters, digits, - and _, and can't be 'default'"),
+            (Msg::ProfileExists { name: "work" }, "❌ Profile 'work' already exists"),
+            (Msg::ProfileCreated { name: "work", dir: Path::new("/tmp/data/todo/work") }, "✅ Created profile 'work' in /tmp/data/todo/work\n   Use it with `todo --profile work` or TODO_PROFILE=work"),
+            (Msg::BadFilterName, "❌ Filter names may only use letters, digits, - and _"),
+            (Msg::FilterInConfig { name: "urgent", config: Path::new("/tmp/config/todo/config.toml"), action: "change" }, "❌ 'urgent' is defined in /tmp/config/todo/config.toml; change it there"),
+            (Msg::FilterSaved { name: "urgent", expr: "due<+3d" }, "✅ Saved filter 'urgent': due<+3d"),
+            (Msg::FilterDeleted { name: "urgent" }, "🗑\u{fe0f} Deleted filter 'urgent'"),
+            (Msg::SkippingFilter { name: "urgent", error: &"unknown field 'colour'" }, "⚠\u{fe0f} Skipping filter 'urgent': unknown field 'colour'"),
+            (Msg::NoFilter { name: "urgent" }, "❌ No filter named 'urgent'"),
+            (Msg::GoalNeedsTag, "❌ A goal needs a tag"),
+            (Msg::GoalSet { tag: "home", goal: &"3 per week" }, "✅ Goal for #home: 3 per week"),
+            (Msg::NoGoal { tag: "home" }, "❌ No goal for #home"),
+            (Msg::GoalDeleted { tag: "home" }, "🗑\u{fe0f} Deleted the goal for #home"),
+            (Msg::JsonOnly { flag: "--migrate-legacy" }, "⚠\u{fe0f} --migrate-legacy only applies to the JSON store"),
+            (Msg::SqliteOnly { flag: "--explain" }, "⚠\u{fe0f} --explain only applies to the SQLite backend"),
+            (Msg::MigrateNeedsJson, "❌ migrate rewrites the JSON store; leave out --sqlite to use it"),
+            (Msg::ShellOnlyFlags, "⚠\u{fe0f} --sqlite, --db and --profile only apply when starting the shell"),
+            (Msg::AlreadyInShell, "❌ Already in the shell"),
+            (Msg::ShellFailed { error: &"disk full" }, "❌ Could not start the shell: disk full"),
+            (Msg::HistoryNotSaved { error: &"disk full" }, "⚠\u{fe0f} Could not save the shell history: disk full"),
+            (Msg::ReadAgain, "↻ Read the todos again, as they were saved elsewhere"),
+            (Msg::NotUnderstood { line: "frobnicate", help: "Commands: add, done, quit" }, "❌ Didn't understand 'frobnicate'. Commands: add, done, quit"),
+            (Msg::DueSoon { names: "#3 buy milk (2h), #4 call mum (5h)" }, "⏳ due soon: #3 buy milk (2h), #4 call mum (5h)"),
+            (Msg::LegacyDb { db: Path::new("/tmp/data/todo/todos.db"), legacy: "todos.db", count: 2 }, "⚠\u{fe0f} No todos in /tmp/data/todo/todos.db, but ./todos.db has 2\n   Use it with `todo --db ./todos.db`, or move it to /tmp/data/todo/todos.db"),
+            (Msg::NothingToMigrate { legacy: Path::new("/home/me/Projects/todo/todos.json") }, "⚠\u{fe0f} Nothing to migrate: there is no /home/me/Projects/todo/todos.json"),
+            (Msg::LegacyUnreadable { legacy: Path::new("/home/me/Projects/todo/todos.json") }, "❌ Could not read the todos in /home/me/Projects/todo/todos.json"),
+            (Msg::LegacyTooNew { legacy: Path::new("/home/me/Projects/todo/todos.json"), count: 2, why: "it was written by a newer todo" }, "⚠\u{fe0f} /home/me/Projects/todo/todos.json has 2 todos, but can't be copied: it was written by a newer todo"),
+            (Msg::LegacyJson { json: Path::new("/tmp/data/todo/todos.json"), legacy: Path::new("/home/me/Projects/todo/todos.json"), count: 2 }, "⚠\u{fe0f} No todos in /tmp/data/todo/todos.json, but /home/me/Projects/todo/todos.json has 2\n   Run todo --migrate-legacy to copy them over"),
+            (Msg::LegacyNotEmpty, "⚠\u{fe0f} The JSON store already has todos; --migrate-legacy only fills an empty one"),
+            (Msg::AnswerNotNoted { error: &"disk full" }, "⚠\u{fe0f} Could not note the answer, so this will be asked again: disk full"),
+            (Msg::LegacyCopied { count: 2, from: Path::new("/home/me/Projects/todo/todos.json"), to: Path::new("/tmp/data/todo/todos.json"), kept: "/home/me/Projects/todo/todos.json.pre-migrate-20261016120000" }, "📥 Copied 2 todos from /home/me/Projects/todo/todos.json to /tmp/data/todo/todos.json; a backup of the original is at /home/me/Projects/todo/todos.json.pre-migrate-20261016120000"),
+            (Msg::LegacyNotCopied { legacy: Path::new("/home/me/Projects/todo/todos.json"), error: &"disk full" }, "❌ Could not copy the todos in /home/me/Projects/todo/todos.json: disk full"),
+            (Msg::SqliteHasTodos { count: 2, config: Path::new("/tmp/config/todo/config.toml") }, "⚠\u{fe0f} No todos in the JSON store, but your SQLite database has 2; did you mean to pass --sqlite?\n   Set backend = \"sqlite\" in /tmp/config/todo/config.toml to use it by default"),
+            (Msg::JsonHasTodos { count: 2 }, "⚠\u{fe0f} No todos in the SQLite database, but your JSON store has 2"),
+            (Msg::StoreMoved { to: Path::new("/tmp/data/todo/todos.jsonl"), kept: "/tmp/data/todo/todos.json.pre-jsonl" }, "📦 Moved the todos to /tmp/data/todo/todos.jsonl; the old file is kept as /tmp/data/todo/todos.json.pre-jsonl"),
+            (Msg::StoreStarted { at: Path::new("/tmp/data/todo/todos.json") }, "📦 Started an empty JSON store at /tmp/data/todo/todos.json"),
+            (Msg::NotMigrated { error: &"disk full" }, "❌ Could not migrate the JSON store: disk full"),
+            (Msg::FormatUpgraded { path: Path::new("/tmp/data/todo/todos.json"), version: 3, kept: "/tmp/data/todo/todos.json.v2-20261016120000" }, "📦 Upgraded /tmp/data/todo/todos.json to format 3; the old file is kept as /tmp/data/todo/todos.json.v2-20261016120000"),
+            (Msg::NewerFormat { path: Path::new("/tmp/data/todo/todos.json"), why: "it was written by a newer todo" }, "⚠\u{fe0f} /tmp/data/todo/todos.json: it was written by a newer todo"),
+            (Msg::TornLine { path: Path::new("/tmp/data/todo/todos.json"), bytes: 42 }, "⚠\u{fe0f} /tmp/data/todo/todos.json: left out a last line an add was cut off in (42 bytes); saving drops it"),
+            (Msg::IdReassigned { old: 3, text: "buy milk", new: 4 }, "⚠\u{fe0f} Duplicate or invalid id 3 for 'buy milk' reassigned to 4"),
+            (Msg::RemindersConverted { count: 2, offset: "+01:00" }, "⚠\u{fe0f} Converted 2 reminders to timestamps, reading them as local time (UTC+01:00)"),
+            (Msg::DatabaseLocked { waited: 5.0 }, "❌ database is locked by another process; retried for 5s"),
+            (Msg::DatabaseError { error: &"disk I/O error" }, "❌ Database error: disk I/O error"),
+            (Msg::Query { ms: 1.25, sql: "SELECT * FROM todos" }, "🔎 1.250ms  SELECT * FROM todos"),
+            (Msg::EventsUnreadable { error: &"disk full" }, "❌ Could not read the events log: disk full"),
+            (Msg::EventsNotLogged { error: &"disk full" }, "⚠\u{fe0f} Could not write the events log: disk full"),
+            (Msg::NeedsEventsLog { config: Path::new("/tmp/config/todo/config.toml") }, "⚠\u{fe0f} --count-reopens needs the events log; set events_log = true in /tmp/config/todo/config.toml to start one"),
+            (Msg::SkippedEvents { count: 2 }, "⚠\u{fe0f} Skipped 2 unreadable lines in the events log"),
+            (Msg::Stats { count: 2, pending: 3, done: 1 }, "📊 2 todos: 3 pending, 1 done"),
+            (Msg::Burndown { scope: " tagged #home", days: 14 }, "📊 Open todos tagged #home over the last 14 days"),
+            (Msg::NothingDueToday, "🎉 Nothing due today"),
+            (Msg::CantExportGoogleTasks, "❌ google-tasks can only be imported; export to markdown or org instead"),
+            (Msg::Exported { count: 2, to: Path::new("/tmp/todos.md") }, "📤 Exported 2 todos to /tmp/todos.md"),
+            (Msg::NoSafetyCopies { store: Path::new("/tmp/data/todo/todos.json") }, "❌ No safety copies of /tmp/data/todo/todos.json; `restore` makes one, or give a bundle with --bundle"),
+            (Msg::SafetyCopyOutOfRange { count: 2, store: Path::new("/tmp/data/todo/todos.json") }, "❌ There are 2 safety copies of /tmp/data/todo/todos.json; pick one from 1 to 2"),
+            (Msg::Archived { count: 2, age: "30 days", label: " (SQLite)" }, "🗄\u{fe0f} Archived 2 todos done more than 30 days ago (SQLite)"),
+            (Msg::ArchiveNotWritten { error: &"disk full" }, "⚠\u{fe0f} Could not write the archive, so nothing was archived: disk full"),
+            (Msg::TrashSwept { count: 2, age: "30 days" }, "🗑\u{fe0f} Emptied 2 todos deleted more than 30 days ago from the trash"),
+            (Msg::TrashNotSwept { error: &"disk full" }, "⚠\u{fe0f} Could not empty old todos from the trash: disk full"),
+            (Msg::SweepFailed { error: &"disk full" }, "⚠\u{fe0f} The auto-archive sweep failed: disk full"),
+            (Msg::TuiChangesKept, "⚠\u{fe0f} The todos changed while the TUI was open; kept those changes as well as yours"),
+            (Msg::TuiConflicts { count: 2 }, "⚠\u{fe0f} The todos changed while the TUI was open, and 2 were changed both there and here:"),
+            (Msg::ReminderReset { id: 3 }, "✅ The reminder for todo 3 will be announced again"),
+            (Msg::CouldNotCheckReminders { error: &"disk full" }, "❌ Could not check reminders: disk full"),
+            (Msg::NotWatching { error: &"disk full" }, "⚠\u{fe0f} Not watching for changes: disk full"),
+            (Msg::Watching { every: 60 }, "👀 Watching for reminders every 60s; press Ctrl-C to stop"),
+            (Msg::WatchFailed { what: "read the todos", error: &"disk full" }, "⚠\u{fe0f} Could not read the todos: disk full"),
+            (Msg::ReminderFired { id: 3, text: "buy milk", at: "2026-10-20 09:00" }, "⏰ #3 buy milk (2026-10-20 09:00)"),
+            (Msg::BadNotifyTarget { target: "pager" }, "⚠\u{fe0f} Ignoring notify target 'pager'; use \"desktop\", \"ntfy:<topic URL>\" or \"email:<address>\""),
+            (Msg::NotDelivered { via: "desktop", id: 3, error: &"disk full" }, "⚠\u{fe0f} desktop failed for todo 3: disk full"),
+            (Msg::DigestSent { to: "Ada <ada@example.com>" }, "📧 Digest sent to Ada <ada@example.com>"),
+            (Msg::DigestNotSent { error: &"disk full" }, "❌ Could not send digest: disk full"),
+            (Msg::NoCtrlC { error: &"disk full" }, "⚠\u{fe0f} Ctrl-C will stop todo wherever it is: disk full"),
+            (Msg::Cancelled, "❌ Cancelled"),
+            (Msg::MarkedDone { id: None, label: "" }, "🎉 Todo marked as done!"),
+            (Msg::Updated { id: None, label: "" }, "📝 Todo updated!"),
+            (Msg::ReminderDue { id: 3, text: "buy milk", more: 0 }, "⏰ Reminder: #3 buy milk"),
+            (Msg::BulkSet { field: "due", value: None, count: 2 }, "cleared due on 2 todos"),
+            (Msg::SubtasksMoved { count: 1, by: "3 days later", label: "" }, "📅 Moved 1 subtask's due date 3 days later"),
+            (Msg::TrashEmptied { count: 1 }, "🗑\u{fe0f} Emptied the trash of 1 todo"),
+            (Msg::UnknownField { field: "colour", nearest: None, fields: "text, due, done" }, "❌ Unknown field 'colour'; use one of text, due, done"),
+            (Msg::RunDoctor { problems: "todo 3 has an invalid due date '2026-13-01'", fix: false }, "⚠\u{fe0f} todo 3 has an invalid due date '2026-13-01' — run `todo doctor`"),
+            (Msg::Failed { error: &Msg::ReadOnly { reason: "readonly in the config" } }, "❌ Read-only (readonly in the config); nothing can be changed"),
+        ];
+        for (msg, expected) in cases {
+            assert_eq!(msg.render(true), expected);
+        }
+    }
+
+    #[test]
+    fn emoji_can_be_left_out() {
+        assert_eq!(Msg::Added { id: 3, label: "" }.render(false), "Todo 3 added!");
+        assert_eq!(Msg::DryRun.render(false), "Dry run, nothing was saved:");
+        assert_eq!(Msg::Failed { error: &"disk full" }.render(false), "disk full");
+        assert_eq!(Msg::Edited { summary: "buy milk" }.render(false), Msg::Edited { summary: "buy milk" }.render(true));
+    }
+
+    #[test]
+    fn what_went_wrong_or_is_only_a_note_goes_to_stderr() {
+        assert!(!Msg::Added { id: 3, label: "" }.level().on_stderr());
+        assert!(!Msg::ReminderFired { id: 3, text: "buy milk", at: "09:00" }.level().on_stderr());
+        assert!(!Msg::Stats { count: 2, pending: 1, done: 1 }.level().on_stderr());
+        assert!(Msg::DryRunNoChanges.level().on_stderr());
+        assert!(Msg::SameText { id: 3 }.level().on_stderr());
+        assert!(Msg::NotFound { id: 3 }.level().on_stderr());
+    }
+}
@@ -0,0 +1,153 @@
+use crate::{changes, cancel, dates::{self, DateDisplay}, delivery::{self, Notifier}, lock, messages::{self, Msg}, reminders, text, Todo};
+use chrono::{DateTime, Duration, SecondsFormat, Utc};
+use std::{
+    collections::BTreeMap,
+    fs, io,
+    path::PathBuf,
+};
+
+/// The files `notify` keeps beside a store: when each todo's reminder was last announced,
+/// and the lock shared with writes to the store. A store with nothing on disk (an
+/// in-memory database) has neither, and every due reminder is announced
+pub struct Files {
+    pub markers: Option<PathBuf>,
+    pub lock: Option<PathBuf>,
+}
+
+/// For each todo id, the moment (RFC 3339) up to which its reminders have been announced
+type Markers = BTreeMap<usize, String>;
+
+/// Announce reminders that have come due since they were last announced, and send them
+/// to `notifiers`
+pub fn run(todos: &[Todo], files: &Files, now: DateTime<Utc>, notifiers: &[Box<dyn Notifier>]) -> io::Result<()> {
+    announce_each(fire(todos, files, now)?, files, |todo, at| {
+        let at = dates::display_datetime(&at, DateDisplay::default());
+        messages::say(Msg::ReminderFired { id: todo.id, text: &text::escape(&todo.text), at: &at });
+        delivery::deliver(notifiers, todo, &at);
+    })
+}
+
+/// Announce each of the reminders `fire` returned, one at a time so deliveries don't
+/// overlap. Ctrl-C stops it once the one under way is delivered, and the rest are
+/// forgotten as announced, so the next check fires them
+pub fn announce_each(fired: Vec<(&Todo, DateTime<Utc>)>, files: &Files, mut announce: impl FnMut(&Todo, DateTime<Utc>)) -> io::Result<()> {
+    let _hold = cancel::hold();
+    for (done, &(todo, at)) in fired.iter().enumerate() {
+        if cancel::requested() {
+            let left: Vec<usize> = fired[done..].iter().map(|(todo, _)| todo.id).collect();
+            return forget(files, &left);
+        }
+        announce(todo, at);
+    }
+    Ok(())
+}
+
+/// The todos whose reminders have come due since they were last announced, with when they
+/// were due, marking them as announced. The lock is held from reading the markers until the
+/// new ones are written, so overlapping runs (say, two cron jobs on a slow disk, or cron and
+/// `watch`) never announce the same reminder twice
+pub fn fire<'a>(todos: &'a [Todo], files: &Files, now: DateTime<Utc>) -> io::Result<Vec<(&'a Todo, DateTime<Utc>)>> {
+    let _lock = files.lock.as_deref().map(lock::exclusive).transpose()?;
+    let mut markers = read_markers(files);
+    let fired = come_due(todos, &mut markers, now);
+    write_markers(files, &markers)?;
+    Ok(fired)
+}
+
+/// Announce what `run` would, without sending it anywhere or marking it as announced
+pub fn preview(todos: &[Todo], files: &Files, now: DateTime<Utc>) {
+    for (todo, at) in come_due(todos, &mut read_markers(files), now) {
+        let at = dates::display_datetime(&at, DateDisplay::default());
+        messages::say(Msg::ReminderFired { id: todo.id, text: &text::escape(&todo.text), at: &at });
+    }
+}
+
+/// The todos whose reminders have come due since `markers` last had them announced,
+/// updating `markers` to match and dropping those of todos that are gone
+fn come_due<'a>(todos: &'a [Todo], markers: &mut Markers, now: DateTime<Utc>) -> Vec<(&'a Todo, DateTime<Utc>)> {
+    markers.retain(|id, _| todos.iter().any(|t| t.id == *id));
+    let mut fired = Vec::new();
+    for todo in todos {
+        let Some(first) = todo.reminder.as_deref().and_then(reminders::parse_reminder) else {
+            continue;
+        };
+        let rule = todo.reminder_repeat.as_deref().and_then(reminders::RepeatRule::parse);
+        let mut since = markers
+            .get(&todo.id)
+            .and_then(|at| reminders::parse_reminder(at))
+            .unwrap_or(first - Duration::seconds(1));
+        // A done todo only still has a reminder when `done --keep-reminder` kept it, and
+        // then only goes off for what comes due after it was done
+        if todo.done {
+            let Some(completed) = todo.completed_at.as_deref().and_then(reminders::parse_reminder) else {
+                continue;
+            };
+            since = since.max(completed);
+        }
+        match reminders::next_occurrence(first, rule, since) {
+            Some(at) if at <= now => {
+                markers.insert(todo.id, now.to_rfc3339_opts(SecondsFormat::Secs, true));
+                fired.push((todo, at));
+            }
+            _ => {}
+        }
+    }
+    fired
+}
+
+/// The todos `after` marks done and whose reminders it cancelled, compared with `before`
+pub fn silenced(before: &[Todo], after: &[Todo]) -> Vec<usize> {
+    let before = changes::by_id(before);
+    after
+        .iter()
+        .filter(|new| new.done && new.reminder.is_none())
+        .filter(|new| before.get(&new.id).is_some_and(|old| !old.done && old.reminder.is_some()))
+        .map(|new| new.id)
+        .collect()
+}
+
+/// Forget what was announced of the reminders of the todos `ids`, which have none now
+pub fn forget(files: &Files, ids: &[usize]) -> io::Result<()> {
+    if ids.is_empty() {
+        return Ok(());
+    }
+    let _lock = files.lock.as_deref().map(lock::exclusive).transpose()?;
+    let mut markers = read_markers(files);
+    let len_before = markers.len();
+    markers.retain(|id, _| !ids.contains(id));
+    if markers.len() == len_before {
+        return Ok(());
+    }
+    write_markers(files, &markers)
+}
+
+/// Whether a todo's reminder has been announced, as `reset` would report it
+pub fn announced(files: &Files, id: usize) -> bool {
+    read_markers(files).contains_key(&id)
+}
+
+/// Forget that a todo's reminder was announced so it fires again, returning whether it had been
+pub fn reset(files: &Files, id: usize) -> io::Result<bool> {
+    let _lock = files.lock.as_deref().map(lock::exclusive).transpose()?;
+    let mut markers = read_markers(files);
+    let found = markers.remove(&id).is_some();
+    write_markers(files, &markers)?;
+    Ok(found)
+}
+
+fn read_markers(files: &Files) -> Markers {
+    files
+        .markers
+        .as_ref()
+        .and_then(|path| fs::read_to_string(path).ok())
+        .and_then(|data| serde_json::from_str(&data).ok())
+        .unwrap_or_default()
+}
+
+fn write_markers(files: &Files, markers: &Markers) -> io::Result<()> {
+    let _hold = cancel::hold();
+    match &files.markers {
+        Some(path) => fs::write(path, serde_json::to_string_pretty(markers)?),
+        None => Ok(()),
+    }
+}
@@ -0,0 +1,284 @@
+use crate::json_file::Format;
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    process::Command,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        OnceLock,
+    },
+};
+use tempfile::TempPath;
+
+const APP_DIR: &str = "todo";
+
+/// Where one profile keeps its files. The default profile uses the app's data directory
+/// itself, so it keeps the paths from before profiles existed; any other profile uses a
+/// directory of the same name inside it. Every path below is worked out from this
+pub struct Paths {
+    profile: Option<String>,
+    data_dir: PathBuf,
+}
+
+/// The profile picked for this run, set once at startup
+static PATHS: OnceLock<Paths> = OnceLock::new();
+
+impl Paths {
+    fn new(profile: Option<String>) -> Paths {
+        let root = root_dir();
+        let data_dir = match &profile {
+            Some(name) => root.join(name),
+            None => root,
+        };
+        Paths { profile, data_dir }
+    }
+}
+
+fn current() -> &'static Paths {
+    PATHS.get_or_init(|| Paths::new(None))
+}
+
+/// Resolve every path for profile `name` from now on; `None` is the default profile
+pub fn set_profile(name: Option<String>) -> Result<(), String> {
+    if let Some(name) = &name
+        && !valid_profile(name)
+    {
+        return Err(format!("'{}' is not a valid profile name; use letters, digits, - and _", name));
+    }
+    let _ = PATHS.set(Paths::new(name));
+    Ok(())
+}
+
+pub fn valid_profile(name: &str) -> bool {
+    !name.is_empty() && name.chars().all(|c| c.is_alphanumeric() || c == '-' || c == '_')
+}
+
+/// The profile in use, or `None` for the default one
+pub fn profile() -> Option<&'static str> {
+    current().profile.as_deref()
+}
+
+/// The profiles made with `todo profile create`, by name
+pub fn profiles() -> io::Result<Vec<String>> {
+    let entries = match fs::read_dir(root_dir()) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(vec![]),
+        Err(e) => return Err(e),
+    };
+    let mut names = Vec::new();
+    for entry in entries {
+        let entry = entry?;
+        if entry.file_type()?.is_dir()
+            && let Some(name) = entry.file_name().to_str().filter(|name| valid_profile(name))
+        {
+            names.push(name.to_string());
+        }
+    }
+    names.sort();
+    Ok(names)
+}
+
+/// Directory profile `name` keeps its files in
+pub fn profile_dir(name: &str) -> PathBuf {
+    root_dir().join(name)
+}
+
+/// The app's data directory, e.g. `~/.local/share/todo` or `%APPDATA%\todo`
+fn root_dir() -> PathBuf {
+    dirs::data_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join(APP_DIR)
+}
+
+/// Directory holding the todo store: the app's data directory, or the profile's inside it
+pub fn data_dir() -> PathBuf {
+    current().data_dir.clone()
+}
+
+/// Whether a JSON store not yet made starts as JSONL, from the config's json_format
+static NEW_JSONL: AtomicBool = AtomicBool::new(false);
+
+pub fn set_json_format(format: Format) {
+    NEW_JSONL.store(format == Format::Jsonl, Ordering::Relaxed);
+}
+
+/// The JSON store laid out as `format`
+pub fn json_path_as(format: Format) -> PathBuf {
+    data_dir().join(format.file_name())
+}
+
+/// The JSON store: todos.jsonl if there is one, else todos.json if there is one, else
+/// whichever json_format starts a new store as
+pub fn json_path() -> PathBuf {
+    let lines = json_path_as(Format::Jsonl);
+    let document = json_path_as(Format::Json);
+    if lines.exists() || (!document.exists() && NEW_JSONL.load(Ordering::Relaxed)) {
+        lines
+    } else {
+        document
+    }
+}
+
+/// The SQLite store, kept beside the JSON one so it doesn't depend on the working directory
+pub fn db_path() -> PathBuf {
+    data_dir().join("todos.db")
+}
+
+/// Where the JSON store lived before it moved to the data directory, e.g.
+/// `/home/varun/Projects/todo/todos.json`
+pub fn legacy_json_path() -> PathBuf {
+    dirs::home_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("Projects")
+        .join("todo")
+        .join("todos.json")
+}
+
+/// Where the SQLite store lived before it moved to the data directory: the working directory
+pub const LEGACY_DB_PATH: &str = "todos.db";
+
+/// Todo templates saved with `todo template save`, beside the config file
+pub fn templates_path() -> PathBuf {
+    config_path().with_file_name("templates.toml")
+}
+
+/// Filters saved with `todo filter save`
+pub fn filters_path() -> PathBuf {
+    data_dir().join("filters.json")
+}
+
+/// Per-tag goals set with `todo goal set`
+pub fn goals_path() -> PathBuf {
+    data_dir().join("goals.json")
+}
+
+/// Lines typed into `todo shell`, for its history
+pub fn shell_history_path() -> PathBuf {
+    data_dir().join("shell_history")
+}
+
+/// Ids of the todos last added, completed and changed, kept next to the JSON store
+pub fn last_path() -> PathBuf {
+    data_dir().join("last.json")
+}
+
+/// Every change made to the JSON store, one JSON object per line, when events_log is on
+pub fn events_path() -> PathBuf {
+    data_dir().join("events.ndjson")
+}
+
+/// Todos deleted from the JSON store, until the trash is emptied
+pub fn trash_path() -> PathBuf {
+    data_dir().join("trash.json")
+}
+
+/// Done todos the auto-archive sweep moved out of the JSON store
+pub fn archive_path() -> PathBuf {
+    data_dir().join("archive.json")
+}
+
+/// Lock held while the JSON store is written, and by `notify` while it checks reminders
+pub fn lock_path() -> PathBuf {
+    data_dir().join("todos.lock")
+}
+
+/// When `notify` last announced each todo's reminder
+pub fn notified_path() -> PathBuf {
+    data_dir().join("notified.json")
+}
+
+/// Where user settings live, e.g. `~/.config/todo/config.toml`
+pub fn config_path() -> PathBuf {
+    dirs::config_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join(APP_DIR)
+        .join("config.toml")
+}
+
+/// Settings for the profile in use that take precedence over `config_path`'s; the
+/// default profile has none
+pub fn profile_config_path() -> Option<PathBuf> {
+    profile().map(|_| data_dir().join("config.toml"))
+}
+
+/// A fresh, uniquely named file for an editor buffer, deleted again when dropped
+pub fn edit_buffer(prefix: &str) -> io::Result<TempPath> {
+    tempfile::Builder::new()
+        .prefix(prefix)
+        .suffix(".txt")
+        .tempfile()
+        .map(|file| file.into_temp_path())
+}
+
+/// `$EDITOR`, falling back to an editor that ships with the platform
+pub fn editor() -> String {
+    std::env::var("EDITOR").unwrap_or_else(|_| {
+        if cfg!(windows) { "notepad" } else { "nano" }.to_string()
+    })
+}
+
+/// `editor()` ready to open `file`. It may carry arguments, as in `code --wait`: the shell
+/// reads it, as it does for other tools, and on Windows it is split at spaces
+pub fn editor_command(file: &Path) -> Command {
+    let editor = editor();
+    if cfg!(windows) {
+        let mut words = editor.split_whitespace();
+        let mut command = Command::new(words.next().unwrap_or("notepad"));
+        command.args(words).arg(file);
+        command
+    } else {
+        let mut command = Command::new("sh");
+        command.arg("-c").arg(format!("{} \"$@\"", editor)).arg(&editor).arg(file);
+        command
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn profiles_live_inside_the_data_directory() {
+        let root = root_dir();
+        assert_eq!(root.file_name(), Some(APP_DIR.as_ref()));
+        assert_eq!(Paths::new(None).data_dir, root);
+        assert_eq!(Paths::new(Some("work".to_string())).data_dir, root.join("work"));
+        assert_eq!(profile_dir("work"), root.join("work"));
+    }
+
+    #[test]
+    fn profile_names_are_one_path_component() {
+        assert!(valid_profile("work-2_b"));
+        for name in ["", ".", "..", "a/b", "a\\b", "a b", "~"] {
+            assert!(!valid_profile(name), "{:?}", name);
+        }
+    }
+
+    #[test]
+    fn store_files_share_a_directory() {
+        let dir = data_dir();
+        assert_eq!(json_path_as(Format::Json), dir.join("todos.json"));
+        assert_eq!(json_path_as(Format::Jsonl), dir.join("todos.jsonl"));
+        for path in [db_path(), lock_path(), trash_path(), last_path(), filters_path()] {
+            assert_eq!(path.parent(), Some(dir.as_path()), "{}", path.display());
+        }
+    }
+
+    #[test]
+    fn templates_sit_beside_the_config() {
+        assert_eq!(templates_path().parent(), config_path().parent());
+        assert_eq!(config_path().parent().and_then(Path::file_name), Some(APP_DIR.as_ref()));
+    }
+
+    #[test]
+    fn edit_buffers_are_temporary() {
+        let buffer = edit_buffer("todo_test").unwrap();
+        let path = buffer.to_path_buf();
+        assert!(path.starts_with(std::env::temp_dir()));
+        let name = path.file_name().unwrap().to_string_lossy().into_owned();
+        assert!(name.starts_with("todo_test") && name.ends_with(".txt"), "{}", name);
+        assert!(path.exists());
+        drop(buffer);
+        assert!(!path.exists());
+    }
+}
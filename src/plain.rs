@@ -0,0 +1,143 @@
+use crate::{
+    agenda, aliases,
+    commands::{self, add::Repeat, Output},
+    dates::DateDisplay,
+    filter::Filter,
+    format_todo, id_width, last,
+    messages::{self, Msg},
+    output_width,
+    store::{Result, Store},
+    tui::Exit,
+};
+use chrono::Local;
+use std::io::{self, BufRead, Write};
+
+/// How many todos a page of the list holds
+const PAGE_SIZE: usize = 20;
+
+const HELP: &str = "Commands: list, next page, previous page, add <text>, done <id> [note], \
+delete <id>, due <id> <date> [time], remind <id> <date> [time], tui, help, quit";
+
+/// What the plain mode shows, as the TUI would
+pub struct Options {
+    pub filter: Option<Filter>,
+    pub pin_overdue: bool,
+    /// Pad ids to at least this many digits
+    pub min_id_width: usize,
+    /// Why nothing can be saved, if it can't
+    pub read_only: Option<&'static str>,
+}
+
+/// The TUI as a numbered list and a prompt, for screen readers and terminals where raw
+/// mode misbehaves. Each line is a command run as the CLI would run it, after which the
+/// list is printed again. Ends on `quit` or end of input, or on `tui` to go back to the
+/// full screen
+pub fn run(store: &mut dyn Store, options: &Options) -> Result<Exit> {
+    println!("Plain mode. {}", HELP);
+    let mut page = 0;
+    print_page(store, options, &mut page)?;
+    let stdin = io::stdin();
+    loop {
+        print!("> ");
+        let _ = io::stdout().flush();
+        let mut line = String::new();
+        match stdin.lock().read_line(&mut line) {
+            Ok(0) => {
+                println!();
+                return Ok(Exit::Quit);
+            }
+            Ok(_) => {}
+            Err(e) => {
+                messages::say(Msg::CouldNotRead { what: &"the command", error: &e });
+                return Ok(Exit::Quit);
+            }
+        }
+        let words = match aliases::split(&line) {
+            Ok(words) => words,
+            Err(e) => {
+                messages::say(Msg::Failed { error: &e });
+                continue;
+            }
+        };
+        let Some((command, args)) = words.split_first() else {
+            print_page(store, options, &mut page)?;
+            continue;
+        };
+        let changes = matches!(command.as_str(), "add" | "a" | "done" | "x" | "delete" | "d" | "due" | "remind");
+        if changes && let Some(reason) = options.read_only {
+            messages::say(Msg::Failed { error: &Msg::ReadOnly { reason } });
+            continue;
+        }
+        let out = match (command.as_str(), args) {
+            ("quit" | "q" | "exit", _) => return Ok(Exit::Quit),
+            ("tui", _) => return Ok(Exit::Switch),
+            ("help" | "h" | "?", _) => {
+                println!("{}", HELP);
+                continue;
+            }
+            ("list" | "l", _) => {
+                page = 0;
+                None
+            }
+            ("next" | "n", _) => {
+                page += 1;
+                None
+            }
+            ("previous" | "prev" | "p", _) => {
+                page = page.saturating_sub(1);
+                None
+            }
+            ("add" | "a", text) if !text.is_empty() => Some(commands::add::run(store, text, false, None, false, Repeat::New)),
+            ("done" | "x", [id, note @ ..]) => with_id(id, |id| commands::done::run(store, id, note, &[], false)),
+            ("delete" | "d", [id]) => with_id(id, |id| commands::delete::run(store, id, &[])),
+            ("due", [id, date]) => with_id(id, |id| commands::due::run(store, id, date, None, false, false, false)),
+            ("due", [id, date, time]) => with_id(id, |id| commands::due::run(store, id, date, Some(time), false, false, false)),
+            ("remind", [id, date]) => with_id(id, |id| commands::remind::run(store, id, date, None, None)),
+            ("remind", [id, date, time]) => with_id(id, |id| commands::remind::run(store, id, date, Some(time), None)),
+            _ => {
+                messages::say(Msg::NotUnderstood { line: line.trim(), help: HELP });
+                continue;
+            }
+        };
+        match out {
+            Some(Ok(mut out)) => out.flush(),
+            Some(Err(e)) => messages::say(Msg::Failed { error: &e }),
+            None => {}
+        }
+        print_page(store, options, &mut page)?;
+    }
+}
+
+/// Run `command` on the todo `id` names, or say why it names none
+fn with_id(id: &str, command: impl FnOnce(usize) -> Result<Output>) -> Option<Result<Output>> {
+    match last::parse_id(id) {
+        Ok(id) => Some(command(id)),
+        Err(e) => {
+            messages::say(Msg::Failed { error: &e });
+            None
+        }
+    }
+}
+
+/// Print page `page` of the todos, keeping it within the pages there are
+fn print_page(store: &mut dyn Store, options: &Options, page: &mut usize) -> Result<()> {
+    let todos = store.todos()?;
+    let now = Local::now().naive_local();
+    let mut shown: Vec<_> = todos.iter().filter(|t| options.filter.as_ref().is_none_or(|f| f.matches(t))).collect();
+    if options.pin_overdue {
+        // Stable, so both blocks keep their order
+        shown.sort_by_key(|t| !agenda::is_overdue(*t, now));
+    }
+    if shown.is_empty() {
+        println!("No todos");
+        return Ok(());
+    }
+    let id_width = id_width(todos.iter().map(|t| t.id), options.min_id_width);
+    let pages = shown.len().div_ceil(PAGE_SIZE);
+    *page = (*page).min(pages - 1);
+    println!("Page {} of {}, {} todos", *page + 1, pages, shown.len());
+    for todo in shown.iter().skip(*page * PAGE_SIZE).take(PAGE_SIZE) {
+        println!("{}", format_todo(todo, id_width, output_width(), false, DateDisplay::default()));
+    }
+    Ok(())
+}
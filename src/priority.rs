@@ -0,0 +1,23 @@
+use clap::ValueEnum;
+
+/// How urgent a todo is; ordered so `Low < Medium < High`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, ValueEnum)]
+pub enum Priority {
+    Low,
+    Medium,
+    High,
+}
+
+impl Priority {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Priority::Low => "low",
+            Priority::Medium => "medium",
+            Priority::High => "high",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Priority> {
+        Priority::from_str(value, true).ok()
+    }
+}
@@ -0,0 +1,367 @@
+use crate::{dates::{self, Due}, priority::Priority, valid_tag};
+use chrono::{Datelike, Duration, Months, NaiveDate, Weekday};
+
+/// What the markers in a quick-add line asked for
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Meta {
+    pub due: Option<Due>,
+    pub tags: Vec<String>,
+    pub priority: Option<Priority>,
+    /// Markers that looked like dates but weren't, kept in the text as typed
+    pub warnings: Vec<String>,
+}
+
+/// Pull the markers out of a new todo's text, wherever they appear: `due:<date>` or
+/// `!<date>` sets the due date, `@tag` adds a tag and `p1`/`p2`/`p3` set high, medium or
+/// low priority. A leading backslash (`\@home`) keeps a word as text. Dates are those
+/// `parse_due` reads; anything else stays in the text with a warning. The last of repeated
+/// markers wins
+pub fn parse_quick_add(input: &str, today: NaiveDate) -> (String, Meta) {
+    let mut meta = Meta::default();
+    let mut words = Vec::new();
+    let mut changed = false;
+    for word in input.split_whitespace() {
+        if let Some(escaped) = word.strip_prefix('\\').filter(|rest| is_marker(rest)) {
+            words.push(escaped);
+            changed = true;
+            continue;
+        }
+        if let Some(value) = word.strip_prefix("due:").or_else(|| word.strip_prefix('!')) {
+            match parse_due(value, today).map(|due| (due, dates::check_window(due.date, today))) {
+                Some((_, Err(e))) => {
+                    meta.warnings.push(format!("'{}' stays in the text: {}", word, e));
+                    words.push(word);
+                }
+                Some((due, Ok(()))) => {
+                    meta.due = Some(due);
+                    changed = true;
+                }
+                None => {
+                    if !value.is_empty() && value.starts_with(|c: char| c.is_alphanumeric()) {
+                        meta.warnings.push(format!("'{}' is not a date, so it stays in the text", word));
+                    }
+                    words.push(word);
+                }
+            }
+            continue;
+        }
+        if let Some(tag) = word.strip_prefix('@').map(str::to_lowercase).filter(|tag| valid_tag(tag)) {
+            if !meta.tags.contains(&tag) {
+                meta.tags.push(tag);
+            }
+            changed = true;
+            continue;
+        }
+        if let Some(priority) = parse_priority(word) {
+            meta.priority = Some(priority);
+            changed = true;
+            continue;
+        }
+        words.push(word);
+    }
+    // Leave the spacing of text without markers exactly as typed
+    let text = if changed { words.join(" ") } else { input.to_string() };
+    (text, meta)
+}
+
+/// Whether `word` would be read as a marker, and so needs escaping to stay in the text
+fn is_marker(word: &str) -> bool {
+    word.starts_with("due:")
+        || word.starts_with('!')
+        || word.strip_prefix('@').is_some_and(valid_tag)
+        || parse_priority(word).is_some()
+}
+
+fn parse_priority(word: &str) -> Option<Priority> {
+    match word {
+        "p1" => Some(Priority::High),
+        "p2" => Some(Priority::Medium),
+        "p3" => Some(Priority::Low),
+        _ => None,
+    }
+}
+
+/// A due date as typed after `due:`, also accepted wherever a day is asked for:
+/// YYYY-MM-DD, today, tomorrow, a weekday (the next one after `today`), a span such as 3d
+/// or 2w, an ISO week of this year such as w29 (its Monday), a quarter such as q3 (its
+/// first day), or eom and eoy for the end of this month or year
+pub fn parse_due(value: &str, today: NaiveDate) -> Option<Due> {
+    parse_due_on(value, today, Weekday::Mon)
+}
+
+/// `parse_due`, with week shortcuts such as w29 landing on `week_day` of that week
+pub fn parse_due_on(value: &str, today: NaiveDate, week_day: Weekday) -> Option<Due> {
+    if let Ok(date) = dates::parse_date(value) {
+        return Some(Due { date, time: None });
+    }
+    let date = match value.to_lowercase().as_str() {
+        "today" | "tod" => today,
+        "tomorrow" | "tmr" | "tom" => today.succ_opt()?,
+        "eom" => today.with_day(1)?.checked_add_months(Months::new(1))?.pred_opt()?,
+        "eoy" => NaiveDate::from_ymd_opt(today.year(), 12, 31)?,
+        word => match word.parse::<Weekday>() {
+            Ok(weekday) => {
+                let ahead = (weekday.num_days_from_monday() + 7 - today.weekday().num_days_from_monday()) % 7;
+                today + Duration::days(if ahead == 0 { 7 } else { ahead.into() })
+            }
+            Err(_) if word.ends_with(['d', 'w']) => today.checked_add_signed(dates::parse_duration(word).ok()?)?,
+            Err(_) => week_or_quarter(word, today.year(), week_day)?,
+        },
+    };
+    Some(Due { date, time: None })
+}
+
+/// "w29" as `week_day` of ISO week 29 of `year`, or "q3" as the first day of its third
+/// quarter. ISO weeks start on Monday and week 1 is the one holding the year's first
+/// Thursday, so it can start in the December before and w53 only exists in some years
+fn week_or_quarter(word: &str, year: i32, week_day: Weekday) -> Option<NaiveDate> {
+    let (kind, number) = word.split_at_checked(1)?;
+    if number.is_empty() || !number.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let number: u32 = number.parse().ok()?;
+    match kind {
+        "w" => NaiveDate::from_isoywd_opt(year, number, week_day),
+        "q" if (1..=4).contains(&number) => NaiveDate::from_ymd_opt(year, number * 3 - 2, 1),
+        _ => None,
+    }
+}
+
+/// A due date read from a phrase in a todo's text
+#[derive(Debug, PartialEq, Eq)]
+pub struct Inferred {
+    pub due: Due,
+    /// The words it was read from, as typed
+    pub phrase: String,
+}
+
+/// Words that make a following weekday or "the 1st" a deadline rather than part of the task
+const LEADS: [&str; 4] = ["on", "by", "before", "until"];
+
+/// Find the first date-like phrase in `text`, leaving the text itself alone. Only whole
+/// words that can't mean anything else count: today, tomorrow, a date (YYYY-MM-DD or the
+/// configured format), "on friday", "by the 1st", "july 3" (with an optional year) and
+/// "in 3 days". Bare weekdays and abbreviations such as "fri" are too easily something
+/// else, so they are left for the due: marker
+pub fn infer_due(text: &str, today: NaiveDate) -> Option<Inferred> {
+    let words: Vec<&str> = text.split_whitespace().collect();
+    let bare: Vec<String> =
+        words.iter().map(|w| w.trim_matches(|c: char| !c.is_alphanumeric()).to_lowercase()).collect();
+    (0..words.len()).find_map(|start| {
+        let (date, len) = date_at(&bare[start..], today)?;
+        let phrase = words[start..start + len].join(" ");
+        let phrase = phrase.trim_matches(|c: char| !c.is_alphanumeric()).to_string();
+        Some(Inferred { due: Due { date, time: None }, phrase })
+    })
+}
+
+/// The date the words at the start of `words` name, and how many words that took
+fn date_at(words: &[String], today: NaiveDate) -> Option<(NaiveDate, usize)> {
+    let word = |i: usize| words.get(i).map_or("", String::as_str);
+    match word(0) {
+        "today" | "tomorrow" => return parse_due(word(0), today).map(|due| (due.date, 1)),
+        "in" => {
+            let count: i64 = word(1).parse().ok()?;
+            let days = match word(2) {
+                "day" | "days" => count,
+                "week" | "weeks" => count.checked_mul(7)?,
+                _ => return None,
+            };
+            return Some((today.checked_add_signed(Duration::try_days(days)?)?, 3));
+        }
+        lead if LEADS.contains(&lead) => {
+            if word(1).len() > 3 && word(1).parse::<Weekday>().is_ok() {
+                return parse_due(word(1), today).map(|due| (due.date, 2));
+            }
+            if word(1) == "the" {
+                return Some((next_day_of_month(ordinal(word(2))?, today)?, 3));
+            }
+            // "by 2025-07-01", reported with the word that led to it
+            if LEADS.contains(&word(1)) {
+                return None;
+            }
+            return date_at(&words[1..], today).map(|(date, len)| (date, len + 1));
+        }
+        _ => {}
+    }
+    if let Some(month) = month(word(0))
+        && let Some(day) = day_number(word(1))
+    {
+        if let Ok(year) = word(2).parse::<i32>()
+            && word(2).len() == 4
+        {
+            return Some((NaiveDate::from_ymd_opt(year, month, day)?, 3));
+        }
+        // "may 3" and "march 3" read as often as not as a verb and a count
+        if matches!(word(0), "may" | "march" | "mar") && ordinal(word(1)).is_none() {
+            return None;
+        }
+        let this_year = NaiveDate::from_ymd_opt(today.year(), month, day);
+        let date = match this_year {
+            Some(date) if date >= today => date,
+            _ => NaiveDate::from_ymd_opt(today.year() + 1, month, day)?,
+        };
+        return Some((date, 2));
+    }
+    // Dates are checked last so "2025" alone, or a number in the configured format, needs a full match
+    dates::parse_date(word(0)).ok().map(|date| (date, 1))
+}
+
+fn month(word: &str) -> Option<u32> {
+    let month = match word {
+        "january" | "jan" => 1,
+        "february" | "feb" => 2,
+        "march" | "mar" => 3,
+        "april" | "apr" => 4,
+        "may" => 5,
+        "june" | "jun" => 6,
+        "july" | "jul" => 7,
+        "august" | "aug" => 8,
+        "september" | "sep" | "sept" => 9,
+        "october" | "oct" => 10,
+        "november" | "nov" => 11,
+        "december" | "dec" => 12,
+        _ => return None,
+    };
+    Some(month)
+}
+
+/// A day of the month written as a number, with or without its suffix ("3", "3rd")
+fn day_number(word: &str) -> Option<u32> {
+    if word.ends_with(|c: char| c.is_ascii_digit()) {
+        word.parse().ok().filter(|day| (1..=31).contains(day))
+    } else {
+        ordinal(word)
+    }
+}
+
+/// A day of the month written as an ordinal with the right suffix: 1st, 2nd, 23rd, 15th
+fn ordinal(word: &str) -> Option<u32> {
+    let digits = word.get(..word.len().checked_sub(2)?)?;
+    let day: u32 = digits.parse().ok().filter(|day| (1..=31).contains(day))?;
+    let suffix = match day {
+        11..=13 => "th",
+        _ if day % 10 == 1 => "st",
+        _ if day % 10 == 2 => "nd",
+        _ if day % 10 == 3 => "rd",
+        _ => "th",
+    };
+    word.ends_with(suffix).then_some(day)
+}
+
+/// The next date from `today` on that falls on day `day` of its month, skipping months too short for it
+fn next_day_of_month(day: u32, today: NaiveDate) -> Option<NaiveDate> {
+    let mut month = today.with_day(1)?;
+    for _ in 0..12 {
+        if let Some(date) = month.with_day(day)
+            && date >= today
+        {
+            return Some(date);
+        }
+        month = month.checked_add_months(Months::new(1))?;
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn day(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    #[test]
+    fn spans_count_from_today() {
+        let today = day(2024, 2, 27);
+        assert_eq!(parse_due("3d", today).map(|due| due.date), Some(day(2024, 3, 1)));
+        assert_eq!(parse_due("2w", today).map(|due| due.date), Some(day(2024, 3, 12)));
+    }
+
+    #[test]
+    fn spans_past_the_calendar_are_not_dates() {
+        let today = day(2024, 2, 27);
+        assert!(parse_due("99999999999d", today).is_none());
+        let (text, meta) = parse_quick_add("call mum due:99999999999d", today);
+        assert_eq!(text, "call mum due:99999999999d");
+        assert!(meta.due.is_none());
+        assert_eq!(meta.warnings.len(), 1);
+    }
+
+    #[test]
+    fn weeks_follow_iso_numbering_across_year_ends() {
+        // 2026's week 1 holds Thursday 1 January, so it starts in December 2025
+        assert_eq!(parse_due("w1", day(2026, 6, 1)).map(|due| due.date), Some(day(2025, 12, 29)));
+        assert_eq!(parse_due_on("w1", day(2026, 6, 1), Weekday::Fri).map(|due| due.date), Some(day(2026, 1, 2)));
+        // 2021's first Thursday is 7 January, so its week 1 starts on the 4th
+        assert_eq!(parse_due("w1", day(2021, 6, 1)).map(|due| due.date), Some(day(2021, 1, 4)));
+        assert_eq!(parse_due("w53", day(2020, 6, 1)).map(|due| due.date), Some(day(2020, 12, 28)));
+        assert!(parse_due("w53", day(2021, 6, 1)).is_none());
+        assert!(parse_due("w0", day(2021, 6, 1)).is_none());
+    }
+
+    #[test]
+    fn quarters_and_month_ends() {
+        let today = day(2024, 2, 10);
+        assert_eq!(parse_due("q1", today).map(|due| due.date), Some(day(2024, 1, 1)));
+        assert_eq!(parse_due("q4", today).map(|due| due.date), Some(day(2024, 10, 1)));
+        assert!(parse_due("q5", today).is_none());
+        assert_eq!(parse_due("eom", today).map(|due| due.date), Some(day(2024, 2, 29)));
+        assert_eq!(parse_due("eoy", today).map(|due| due.date), Some(day(2024, 12, 31)));
+    }
+
+    /// What `infer_due` reads from `text` on Wednesday 25 June 2025, as (date, phrase)
+    fn inferred(text: &str) -> Option<(NaiveDate, String)> {
+        infer_due(text, day(2025, 6, 25)).map(|inferred| (inferred.due.date, inferred.phrase))
+    }
+
+    #[test]
+    fn inferred_phrases_and_their_dates() {
+        let cases = [
+            ("call bob tomorrow", day(2025, 6, 26), "tomorrow"),
+            ("water plants today!", day(2025, 6, 25), "today"),
+            ("pay rent on the 1st", day(2025, 7, 1), "on the 1st"),
+            ("submit report on Friday", day(2025, 6, 27), "on Friday"),
+            ("renew passport by wednesday", day(2025, 7, 2), "by wednesday"),
+            ("party july 3", day(2025, 7, 3), "july 3"),
+            ("party July 3rd, 2026", day(2026, 7, 3), "July 3rd, 2026"),
+            ("file taxes by 2025-07-01", day(2025, 7, 1), "by 2025-07-01"),
+            ("renew in 2 weeks", day(2025, 7, 9), "in 2 weeks"),
+            ("chase invoice in 3 days", day(2025, 6, 28), "in 3 days"),
+        ];
+        for (text, date, phrase) in cases {
+            assert_eq!(inferred(text), Some((date, phrase.to_string())), "{}", text);
+        }
+    }
+
+    #[test]
+    fn month_days_already_past_roll_over() {
+        assert_eq!(inferred("book june 3").map(|(date, _)| date), Some(day(2026, 6, 3)));
+        // June has no 31st, so the next one is in July
+        assert_eq!(inferred("invoice by the 31st").map(|(date, _)| date), Some(day(2025, 7, 31)));
+    }
+
+    #[test]
+    fn the_first_phrase_wins() {
+        assert_eq!(inferred("tomorrow or on friday"), Some((day(2025, 6, 26), "tomorrow".to_string())));
+    }
+
+    #[test]
+    fn ambiguous_words_are_not_dates() {
+        for text in [
+            "friday drinks",
+            "fix fri bug",
+            "on fri",
+            "may 3 people come",
+            "march 2 miles",
+            "read chapter 2025",
+            "todays news",
+            "meet the 1st team",
+            "in 3 hours",
+            "by by the way",
+            "the 32nd of never",
+        ] {
+            assert_eq!(inferred(text), None, "{}", text);
+        }
+    }
+}
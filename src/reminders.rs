@@ -0,0 +1,432 @@
+use crate::{dates::Due, Todo};
+use chrono::{
+    DateTime, Datelike, Duration, Local, NaiveDate, NaiveDateTime, NaiveTime, SecondsFormat, TimeZone, Utc, Weekday,
+};
+use std::fmt;
+
+/// How often something comes round again: as a reminder's repeat, it fires again after its
+/// stored time has passed; as a todo's recurrence, completing it schedules the next occurrence
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RepeatRule {
+    Hourly,
+    Daily,
+    Weekly,
+    /// On the chosen days of the week
+    Days(Weekdays),
+}
+
+/// A set of days of the week, one bit per day from Monday
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Weekdays(u8);
+
+const DAY_NAMES: [&str; 7] = ["mon", "tue", "wed", "thu", "fri", "sat", "sun"];
+const FULL_DAY_NAMES: [&str; 7] = ["monday", "tuesday", "wednesday", "thursday", "friday", "saturday", "sunday"];
+
+impl Weekdays {
+    const WEEKDAYS: Weekdays = Weekdays(0b0011111);
+    const WEEKENDS: Weekdays = Weekdays(0b1100000);
+
+    pub fn contains(self, day: Weekday) -> bool {
+        self.0 & (1 << day.num_days_from_monday()) != 0
+    }
+
+    /// A comma-separated list of days such as "mon,wed,fri" or "monday,friday"
+    fn parse(value: &str) -> Result<Weekdays, String> {
+        let mut bits = 0;
+        for name in value.split(',') {
+            let name = name.trim().to_lowercase();
+            let day = (0..7)
+                .find(|&i| name == DAY_NAMES[i] || name == FULL_DAY_NAMES[i])
+                .ok_or_else(|| format!("'{}' is not a day of the week (use mon, tue, ... sun)", name))?;
+            bits |= 1 << day;
+        }
+        Ok(Weekdays(bits))
+    }
+}
+
+impl fmt::Display for Weekdays {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Weekdays::WEEKDAYS => write!(f, "weekdays"),
+            Weekdays::WEEKENDS => write!(f, "weekends"),
+            days => {
+                let names: Vec<&str> = (0..7).filter(|i| days.0 & (1 << i) != 0).map(|i| DAY_NAMES[i]).collect();
+                write!(f, "{}", names.join(","))
+            }
+        }
+    }
+}
+
+impl fmt::Display for RepeatRule {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RepeatRule::Hourly => write!(f, "hourly"),
+            RepeatRule::Daily => write!(f, "daily"),
+            RepeatRule::Weekly => write!(f, "weekly"),
+            RepeatRule::Days(days) => write!(f, "{}", days),
+        }
+    }
+}
+
+impl RepeatRule {
+    pub fn parse(value: &str) -> Option<RepeatRule> {
+        RepeatRule::from_arg(value).ok()
+    }
+
+    /// Parse a rule as typed on the command line: hourly, daily, weekly, weekdays,
+    /// weekends or a list of days such as "mon,wed,fri"
+    pub fn from_arg(value: &str) -> Result<RepeatRule, String> {
+        match value.trim().to_lowercase().as_str() {
+            "hourly" => Ok(RepeatRule::Hourly),
+            "daily" => Ok(RepeatRule::Daily),
+            "weekly" => Ok(RepeatRule::Weekly),
+            "weekdays" => Ok(RepeatRule::Days(Weekdays::WEEKDAYS)),
+            "weekends" => Ok(RepeatRule::Days(Weekdays::WEEKENDS)),
+            days => Weekdays::parse(days).map(RepeatRule::Days),
+        }
+    }
+
+    /// Parse a rule for a todo to recur by, which has to fall on days
+    pub fn recurrence_from_arg(value: &str) -> Result<RepeatRule, String> {
+        match RepeatRule::from_arg(value)? {
+            RepeatRule::Hourly => Err("a todo can't recur hourly; `todo remind --repeat hourly` repeats its reminder".to_string()),
+            rule => Ok(rule),
+        }
+    }
+
+    /// The first day the rule lands on after both `from` (the day it last fell on) and
+    /// `after`. Hourly rules have no day of their own
+    pub fn next_date(self, from: NaiveDate, after: NaiveDate) -> Option<NaiveDate> {
+        match self {
+            RepeatRule::Hourly => None,
+            RepeatRule::Daily => Some(from.max(after) + Duration::days(1)),
+            RepeatRule::Weekly => {
+                let weeks = if from > after { 1 } else { (after - from).num_days() / 7 + 1 };
+                Some(from + Duration::weeks(weeks))
+            }
+            RepeatRule::Days(days) => {
+                let start = from.max(after);
+                (1..=7).map(|n| start + Duration::days(n)).find(|date| days.contains(date.weekday()))
+            }
+        }
+    }
+}
+
+/// The first time a reminder fires strictly after `now`, stepping repeating reminders
+/// forward from their stored time so they never go stale. Hourly reminders step by the
+/// hour; the rest keep the reminder's local wall-clock time, whatever DST changes lie in
+/// between
+pub fn next_occurrence(first: DateTime<Utc>, rule: Option<RepeatRule>, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+    next_occurrence_in(&Local, first, rule, now)
+}
+
+/// `next_occurrence` with wall-clock times read in `tz`
+fn next_occurrence_in<Tz: TimeZone>(
+    tz: &Tz,
+    first: DateTime<Utc>,
+    rule: Option<RepeatRule>,
+    now: DateTime<Utc>,
+) -> Option<DateTime<Utc>> {
+    if first > now {
+        return Some(first);
+    }
+    let rule = rule?;
+    if rule == RepeatRule::Hourly {
+        let missed = (now - first).num_seconds() / 3600 + 1;
+        return Some(first + Duration::hours(missed));
+    }
+    let local = first.with_timezone(tz).naive_local();
+    let today = now.with_timezone(tz).date_naive();
+    // Today still counts when its time has not come yet
+    let mut date = rule.next_date(local.date(), today - Duration::days(1))?;
+    loop {
+        let at = local_instant(tz, date.and_time(local.time())).with_timezone(&Utc);
+        if at > now {
+            return Some(at);
+        }
+        date = rule.next_date(date, date)?;
+    }
+}
+
+/// A reminder moved on by `days`, at the same local wall-clock time, as a recurring todo's
+/// next occurrence takes its reminder along
+pub fn shift_reminder(at: DateTime<Utc>, days: Duration) -> String {
+    shift_reminder_in(&Local, at, days).to_rfc3339_opts(SecondsFormat::Secs, false)
+}
+
+/// `shift_reminder` with wall-clock times read in `tz`
+fn shift_reminder_in<Tz: TimeZone>(tz: &Tz, at: DateTime<Utc>, days: Duration) -> DateTime<Tz> {
+    local_instant(tz, at.with_timezone(tz).naive_local() + days)
+}
+
+/// The instant a wall-clock time in `tz` stands for: the first, when a DST change repeats
+/// it, and the first real instant after it, when a DST change skips it
+fn local_instant<Tz: TimeZone>(tz: &Tz, local: NaiveDateTime) -> DateTime<Tz> {
+    tz.from_local_datetime(&local)
+        .earliest()
+        .or_else(|| tz.from_local_datetime(&(local + Duration::hours(1))).earliest())
+        .unwrap_or_else(|| tz.from_utc_datetime(&local))
+}
+
+/// Next firing time of a pending todo's reminder, if it has one still to come
+pub fn next_reminder(todo: &Todo, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+    if todo.done {
+        return None;
+    }
+    let first = parse_reminder(todo.reminder.as_deref()?)?;
+    let rule = todo.reminder_repeat.as_deref().and_then(RepeatRule::parse);
+    next_occurrence(first, rule, now)
+}
+
+/// Format of reminders written before they carried a UTC offset; read as local time
+const LEGACY_FORMAT: &str = "%Y-%m-%d %H:%M";
+
+/// Turn a wall-clock time in the local timezone into the stored form: RFC 3339 with the
+/// offset in force at that moment, so the instant survives a change of timezone
+pub fn to_stored(local: NaiveDateTime) -> String {
+    local_instant(&Local, local).to_rfc3339_opts(SecondsFormat::Secs, false)
+}
+
+/// Read a stored reminder, accepting the legacy offset-less format as local time
+pub fn parse_reminder(value: &str) -> Option<DateTime<Utc>> {
+    if let Ok(datetime) = DateTime::parse_from_rfc3339(value) {
+        return Some(datetime.with_timezone(&Utc));
+    }
+    let local = NaiveDateTime::parse_from_str(value, LEGACY_FORMAT).ok()?;
+    DateTime::parse_from_rfc3339(&to_stored(local))
+        .ok()
+        .map(|datetime| datetime.with_timezone(&Utc))
+}
+
+/// The stored form of a legacy or hand-typed local reminder, or None when `value` is
+/// already RFC 3339 or is not a reminder at all
+pub fn upgrade_reminder(value: &str) -> Option<String> {
+    if DateTime::parse_from_rfc3339(value).is_ok() {
+        return None;
+    }
+    NaiveDateTime::parse_from_str(value, LEGACY_FORMAT).ok().map(to_stored)
+}
+
+/// Rewrite legacy reminders in place, returning how many were converted
+pub fn migrate_reminders(todos: &mut [Todo]) -> usize {
+    let mut migrated = 0;
+    for todo in todos.iter_mut() {
+        if let Some(upgraded) = todo.reminder.as_deref().and_then(upgrade_reminder) {
+            todo.reminder = Some(upgraded);
+            migrated += 1;
+        }
+    }
+    migrated
+}
+
+/// Give todos from before recurrence was kept apart from the reminder's repeat rule the
+/// recurrence they had then: a todo with a due date recurred by that rule
+pub fn carry_recurrence(todos: &mut [Todo]) {
+    for todo in todos.iter_mut().filter(|t| t.due_date.is_some() && t.recurrence.is_none()) {
+        todo.recurrence = todo.reminder_repeat.clone();
+    }
+}
+
+/// How far before the due date `auto_remind` may remind
+const MAX_AUTO_REMIND_DAYS: i64 = 366;
+
+/// When to remind about a todo relative to its due date, from the `auto_remind` setting
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AutoRemind {
+    /// `-Nd HH:MM`: at a time of day, N days before the due date
+    DaysBefore(i64, NaiveTime),
+    /// `-Nh`: N hours before the due time, or before the due date starts
+    HoursBefore(i64),
+}
+
+impl AutoRemind {
+    /// Parse `-Nd HH:MM` or `-Nh`, reminding at most a year before the due date
+    pub fn parse(value: &str) -> Result<AutoRemind, String> {
+        let value = value.trim();
+        let invalid = || format!("'{}' should look like \"-1d 09:00\" or \"-2h\"", value);
+        let too_far = || format!("'{}' is more than a year before the due date", value);
+        let offset = value.strip_prefix('-').ok_or_else(invalid)?;
+        let count = |digits: &str| -> Result<i64, String> {
+            if digits.is_empty() || !digits.chars().all(|c| c.is_ascii_digit()) {
+                return Err(invalid());
+            }
+            digits.parse().map_err(|_| too_far())
+        };
+        if let Some((days, time)) = offset.split_once(char::is_whitespace) {
+            let days = count(days.strip_suffix('d').ok_or_else(invalid)?)?;
+            if days > MAX_AUTO_REMIND_DAYS {
+                return Err(too_far());
+            }
+            let time = NaiveTime::parse_from_str(time.trim(), "%H:%M").map_err(|_| invalid())?;
+            return Ok(AutoRemind::DaysBefore(days, time));
+        }
+        match count(offset.strip_suffix('h').ok_or_else(invalid)?)? {
+            0 => Err(invalid()),
+            hours if hours > MAX_AUTO_REMIND_DAYS * 24 => Err(too_far()),
+            hours => Ok(AutoRemind::HoursBefore(hours)),
+        }
+    }
+
+    /// The local time to remind at for a todo due at `due`; hours count back from its due
+    /// time, or from the start of the day when it only has a date
+    pub fn reminder_for(self, due: Due) -> NaiveDateTime {
+        match self {
+            AutoRemind::DaysBefore(days, time) => (due.date - Duration::days(days)).and_time(time),
+            AutoRemind::HoursBefore(hours) => {
+                due.date.and_time(due.time.unwrap_or(NaiveTime::MIN)) - Duration::hours(hours)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{FixedOffset, LocalResult};
+
+    fn date(value: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(value, "%Y-%m-%d").unwrap()
+    }
+
+    #[test]
+    fn next_date_lands_on_the_rules_next_day() {
+        // (rule, from, after, next); the March and October dates straddle DST changes
+        let cases = [
+            ("daily", "2025-12-31", "2025-12-31", Some("2026-01-01")),
+            ("daily", "2026-03-28", "2026-03-28", Some("2026-03-29")),
+            ("daily", "2026-01-01", "2026-01-10", Some("2026-01-11")),
+            ("weekly", "2026-03-07", "2026-03-07", Some("2026-03-14")),
+            ("weekly", "2026-10-20", "2026-10-20", Some("2026-10-27")),
+            ("weekly", "2026-01-01", "2026-01-20", Some("2026-01-22")),
+            ("weekly", "2026-02-01", "2026-01-01", Some("2026-02-08")),
+            ("mon,wed,fri", "2026-01-02", "2026-01-02", Some("2026-01-05")),
+            ("mon,wed,fri", "2026-01-05", "2026-01-15", Some("2026-01-16")),
+            ("weekdays", "2026-03-06", "2026-03-06", Some("2026-03-09")),
+            ("weekends", "2026-03-29", "2026-03-29", Some("2026-04-04")),
+            ("sun", "2026-10-18", "2026-10-18", Some("2026-10-25")),
+            ("hourly", "2026-01-01", "2026-01-01", None),
+        ];
+        for (rule, from, after, next) in cases {
+            let rule = RepeatRule::parse(rule).unwrap();
+            assert_eq!(rule.next_date(date(from), date(after)), next.map(date), "{} from {} after {}", rule, from, after);
+        }
+    }
+
+    /// London in 2026: UTC until 01:00 UTC on 29 March, UTC+1 until 01:00 UTC on 25 October
+    #[derive(Clone, Copy, Debug)]
+    struct London;
+
+    impl TimeZone for London {
+        type Offset = FixedOffset;
+
+        fn from_offset(_: &FixedOffset) -> London {
+            London
+        }
+
+        fn offset_from_utc_datetime(&self, utc: &NaiveDateTime) -> FixedOffset {
+            let summer = *utc >= utc_at("2026-03-29 01:00").naive_utc() && *utc < utc_at("2026-10-25 01:00").naive_utc();
+            FixedOffset::east_opt(if summer { 3600 } else { 0 }).unwrap()
+        }
+
+        fn offset_from_utc_date(&self, utc: &NaiveDate) -> FixedOffset {
+            self.offset_from_utc_datetime(&utc.and_time(NaiveTime::MIN))
+        }
+
+        fn offset_from_local_datetime(&self, local: &NaiveDateTime) -> LocalResult<FixedOffset> {
+            // Each offset the zone could have at `local`, the earliest instant first
+            let fits: Vec<FixedOffset> = [3600, 0]
+                .into_iter()
+                .map(|seconds| FixedOffset::east_opt(seconds).unwrap())
+                .filter(|offset| self.offset_from_utc_datetime(&(*local - Duration::seconds(offset.local_minus_utc().into()))) == *offset)
+                .collect();
+            match fits[..] {
+                [] => LocalResult::None,
+                [offset] => LocalResult::Single(offset),
+                [earlier, later, ..] => LocalResult::Ambiguous(earlier, later),
+            }
+        }
+
+        fn offset_from_local_date(&self, local: &NaiveDate) -> LocalResult<FixedOffset> {
+            self.offset_from_local_datetime(&local.and_time(NaiveTime::MIN))
+        }
+    }
+
+    fn utc_at(value: &str) -> DateTime<Utc> {
+        NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M").unwrap().and_utc()
+    }
+
+    fn london_at(value: &str) -> DateTime<Utc> {
+        local_instant(&London, NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M").unwrap()).with_timezone(&Utc)
+    }
+
+    #[test]
+    fn repeating_reminders_keep_their_wall_clock_time_across_dst() {
+        // (rule, first, now, next), all as London wall-clock times
+        let cases = [
+            ("daily", "2026-03-27 09:00", "2026-03-30 12:00", "2026-03-31 09:00"),
+            ("daily", "2026-03-28 09:00", "2026-03-28 10:00", "2026-03-29 09:00"),
+            ("daily", "2026-10-20 09:00", "2026-10-26 08:00", "2026-10-26 09:00"),
+            ("weekly", "2026-03-23 09:00", "2026-03-24 09:00", "2026-03-30 09:00"),
+            ("weekly", "2026-10-20 09:00", "2026-10-21 09:00", "2026-10-27 09:00"),
+            ("weekdays", "2026-03-27 09:00", "2026-03-27 10:00", "2026-03-30 09:00"),
+        ];
+        for (rule, first, now, next) in cases {
+            let rule = RepeatRule::parse(rule);
+            let at = next_occurrence_in(&London, london_at(first), rule, london_at(now));
+            assert_eq!(at, Some(london_at(next)), "{:?} from {} at {}", rule, first, now);
+        }
+    }
+
+    #[test]
+    fn hourly_reminders_step_by_the_hour_across_dst() {
+        let at = next_occurrence_in(&London, utc_at("2026-03-29 00:30"), Some(RepeatRule::Hourly), utc_at("2026-03-29 02:10"));
+        assert_eq!(at, Some(utc_at("2026-03-29 02:30")));
+    }
+
+    #[test]
+    fn a_recurring_todos_reminder_keeps_its_wall_clock_time_across_dst() {
+        let spring = shift_reminder_in(&London, london_at("2026-03-28 09:00"), Duration::days(1));
+        assert_eq!(spring.with_timezone(&Utc), london_at("2026-03-29 09:00"));
+        assert_eq!(spring.with_timezone(&Utc), utc_at("2026-03-29 08:00"));
+        let autumn = shift_reminder_in(&London, london_at("2026-10-20 09:00"), Duration::weeks(1));
+        assert_eq!(autumn.with_timezone(&Utc), utc_at("2026-10-27 09:00"));
+    }
+
+    #[test]
+    fn wall_clock_times_a_dst_change_skips_or_repeats_resolve_to_one_instant() {
+        assert_eq!(london_at("2026-03-29 01:30"), utc_at("2026-03-29 01:30"));
+        assert_eq!(london_at("2026-10-25 01:30"), utc_at("2026-10-25 00:30"));
+    }
+
+    #[test]
+    fn a_todo_cannot_recur_hourly() {
+        assert!(RepeatRule::recurrence_from_arg("hourly").is_err());
+        assert_eq!(RepeatRule::recurrence_from_arg("weekdays"), RepeatRule::from_arg("weekdays"));
+    }
+
+    #[test]
+    fn auto_remind_offsets_parse_and_are_bounded() {
+        let nine = NaiveTime::from_hms_opt(9, 0, 0).unwrap();
+        assert_eq!(AutoRemind::parse("-1d 09:00"), Ok(AutoRemind::DaysBefore(1, nine)));
+        assert_eq!(AutoRemind::parse(" -0d  09:00 "), Ok(AutoRemind::DaysBefore(0, nine)));
+        assert_eq!(AutoRemind::parse("-2h"), Ok(AutoRemind::HoursBefore(2)));
+        assert_eq!(AutoRemind::parse("-366d 09:00"), Ok(AutoRemind::DaysBefore(366, nine)));
+        for invalid in ["1d 09:00", "--1d 09:00", "-+1d 09:00", "-1d 25:00", "-1d", "-0h", "-h", "-1w", "-1.5h", ""] {
+            let error = AutoRemind::parse(invalid).unwrap_err();
+            assert!(error.ends_with("should look like \"-1d 09:00\" or \"-2h\""), "{}: {}", invalid, error);
+        }
+        for too_far in ["-367d 09:00", "-8785h", "-99999999999d 09:00", "-99999999999999999999h"] {
+            assert_eq!(AutoRemind::parse(too_far), Err(format!("'{}' is more than a year before the due date", too_far)));
+        }
+    }
+
+    #[test]
+    fn auto_reminders_count_back_from_the_due_date() {
+        let at = |rule: &str, due: &str| AutoRemind::parse(rule).unwrap().reminder_for(Due::parse(due).unwrap()).to_string();
+        assert_eq!(at("-1d 09:00", "2026-01-01"), "2025-12-31 09:00:00");
+        assert_eq!(at("-1d 09:00", "2026-01-01 17:00"), "2025-12-31 09:00:00");
+        assert_eq!(at("-2h", "2026-01-01 17:00"), "2026-01-01 15:00:00");
+        assert_eq!(at("-2h", "2026-01-01"), "2025-12-31 22:00:00");
+        assert_eq!(at("-8760h", "2026-01-01"), "2025-01-01 00:00:00");
+    }
+}
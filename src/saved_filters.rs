@@ -0,0 +1,72 @@
+use crate::{config, messages::{self, Msg}, paths};
+use std::{collections::BTreeMap, fs, io};
+
+/// Where the config file defines a filter, or `filter save` stored it
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Source {
+    Config,
+    Saved,
+}
+
+/// Every named filter: those from the `[filters]` table in the config, then those saved
+/// with `filter save`. A name in the config wins over a saved one
+pub fn all() -> BTreeMap<String, (String, Source)> {
+    let mut filters: BTreeMap<String, (String, Source)> = read_saved()
+        .into_iter()
+        .map(|(name, expr)| (name, (expr, Source::Saved)))
+        .collect();
+    for (name, expr) in &config::load().filters {
+        filters.insert(name.clone(), (expr.clone(), Source::Config));
+    }
+    filters
+}
+
+/// Whether `filter save` stored a filter under `name`, whatever the config defines
+pub fn is_saved(name: &str) -> bool {
+    read_saved().contains_key(name)
+}
+
+/// The expression saved under `name`
+pub fn lookup(name: &str) -> Option<String> {
+    all().remove(name).map(|(expr, _)| expr)
+}
+
+/// Names may only use letters, digits, `-` and `_`, so they read as plain words on the
+/// command line
+pub fn valid_name(name: &str) -> bool {
+    !name.is_empty() && name.chars().all(|c| c.is_alphanumeric() || c == '-' || c == '_')
+}
+
+pub fn save(name: &str, expr: &str) -> io::Result<()> {
+    let mut saved = read_saved();
+    saved.insert(name.to_string(), expr.to_string());
+    write_saved(&saved)
+}
+
+/// Remove a saved filter, returning whether there was one
+pub fn delete(name: &str) -> io::Result<bool> {
+    let mut saved = read_saved();
+    if saved.remove(name).is_none() {
+        return Ok(false);
+    }
+    write_saved(&saved)?;
+    Ok(true)
+}
+
+fn read_saved() -> BTreeMap<String, String> {
+    let Ok(data) = fs::read_to_string(paths::filters_path()) else {
+        return BTreeMap::new();
+    };
+    serde_json::from_str(&data).unwrap_or_else(|e| {
+        messages::say(Msg::Ignoring { what: &paths::filters_path().display(), error: &e });
+        BTreeMap::new()
+    })
+}
+
+fn write_saved(saved: &BTreeMap<String, String>) -> io::Result<()> {
+    let path = paths::filters_path();
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(path, serde_json::to_string_pretty(saved)?)
+}
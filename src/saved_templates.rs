@@ -0,0 +1,65 @@
+use crate::{messages::{self, Msg}, paths, saved_filters};
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, fs, io};
+
+/// A todo to add again and again, saved with `template save` and used with `add --template`
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct SavedTemplate {
+    /// The todo's text, with `{placeholders}` filled in from --var
+    pub text: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub priority: Option<String>,
+    /// When it falls due, counted from the day it is added: "+3d", "+2w", or anything
+    /// `due` takes, such as "fri"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub due: Option<String>,
+    /// When to remind before the due date, as auto_remind takes it: "-1d 09:00" or "-2h"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reminder: Option<String>,
+}
+
+/// Every saved template, by name
+pub fn all() -> BTreeMap<String, SavedTemplate> {
+    let Ok(data) = fs::read_to_string(paths::templates_path()) else {
+        return BTreeMap::new();
+    };
+    toml::from_str(&data).unwrap_or_else(|e| {
+        messages::say(Msg::Ignoring { what: &paths::templates_path().display(), error: &e });
+        BTreeMap::new()
+    })
+}
+
+/// Names follow the rules for saved filters
+pub fn valid_name(name: &str) -> bool {
+    saved_filters::valid_name(name)
+}
+
+pub fn lookup(name: &str) -> Option<SavedTemplate> {
+    all().remove(name)
+}
+
+pub fn save(name: &str, template: SavedTemplate) -> io::Result<()> {
+    let mut saved = all();
+    saved.insert(name.to_string(), template);
+    write(&saved)
+}
+
+/// Remove a saved template, returning whether there was one
+pub fn delete(name: &str) -> io::Result<bool> {
+    let mut saved = all();
+    if saved.remove(name).is_none() {
+        return Ok(false);
+    }
+    write(&saved)?;
+    Ok(true)
+}
+
+fn write(saved: &BTreeMap<String, SavedTemplate>) -> io::Result<()> {
+    let path = paths::templates_path();
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(path, toml::to_string_pretty(saved).map_err(io::Error::other)?)
+}
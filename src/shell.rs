@@ -0,0 +1,144 @@
+use crate::{aliases, messages::{self, Msg}, paths, Cli};
+use clap::CommandFactory;
+use rustyline::{
+    completion::Completer, error::ReadlineError, highlight::Highlighter, hint::Hinter, history::DefaultHistory,
+    validate::Validator, Context, Editor, Helper,
+};
+use std::{
+    panic::{self, AssertUnwindSafe},
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+/// Set while `run` reads commands, so that a failing one ends only itself
+static ACTIVE: AtomicBool = AtomicBool::new(false);
+
+/// What `exit` unwinds with inside the shell, caught by `catch`
+struct Exit(i32);
+
+/// End the run with `code`, or inside the shell just the command being run
+pub fn exit(code: i32) -> ! {
+    if ACTIVE.load(Ordering::Relaxed) {
+        panic::panic_any(Exit(code));
+    }
+    std::process::exit(code)
+}
+
+/// Run `command`, returning the code it exited with if it failed
+pub fn catch<T>(command: impl FnOnce() -> T) -> Result<T, i32> {
+    panic::catch_unwind(AssertUnwindSafe(command)).map_err(|payload| match payload.downcast::<Exit>() {
+        Ok(exit) => exit.0,
+        Err(payload) => panic::resume_unwind(payload),
+    })
+}
+
+/// Completes subcommand names as the first word and todo ids after it
+struct Completion {
+    commands: Vec<String>,
+    ids: Vec<usize>,
+}
+
+impl Completer for Completion {
+    type Candidate = String;
+
+    fn complete(&self, line: &str, pos: usize, _: &Context<'_>) -> rustyline::Result<(usize, Vec<String>)> {
+        let start = line[..pos].rfind(char::is_whitespace).map_or(0, |i| i + 1);
+        let word = &line[start..pos];
+        if line[..start].trim().is_empty() {
+            let names = self.commands.iter().filter(|name| name.starts_with(word)).cloned().collect();
+            return Ok((start, names));
+        }
+        let (hash, digits) = word.strip_prefix('#').map_or(("", word), |digits| ("#", digits));
+        if !digits.chars().all(|c| c.is_ascii_digit()) {
+            return Ok((start, vec![]));
+        }
+        let ids = self
+            .ids
+            .iter()
+            .map(|id| id.to_string())
+            .filter(|id| id.starts_with(digits))
+            .map(|id| format!("{}{}", hash, id))
+            .collect();
+        Ok((start, ids))
+    }
+}
+
+impl Hinter for Completion {
+    type Hint = String;
+}
+
+impl Highlighter for Completion {}
+
+impl Validator for Completion {}
+
+impl Helper for Completion {}
+
+/// Read commands at a prompt until exit, quit or end of input, handing each line's words
+/// to `each`, which returns the todo ids there are afterwards to complete. Lines are kept
+/// in a history beside the store
+pub fn run(ids: Vec<usize>, mut each: impl FnMut(Vec<String>) -> Vec<usize>) {
+    let mut editor: Editor<Completion, DefaultHistory> = match Editor::new() {
+        Ok(editor) => editor,
+        Err(e) => {
+            messages::say(Msg::ShellFailed { error: &e });
+            std::process::exit(1);
+        }
+    };
+    let commands = Cli::command()
+        .get_subcommands()
+        .filter(|command| command.get_name() != "shell")
+        .map(|command| command.get_name().to_string())
+        .chain(["exit", "quit", "help"].map(String::from))
+        .collect();
+    editor.set_helper(Some(Completion { commands, ids }));
+    let history = paths::shell_history_path();
+    let _ = editor.load_history(&history);
+
+    // Failed commands unwind back here instead of ending the process; they have already
+    // said what went wrong, so the unwinding itself stays quiet
+    let default_hook = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        if !info.payload().is::<Exit>() {
+            default_hook(info);
+        }
+    }));
+    ACTIVE.store(true, Ordering::Relaxed);
+    println!("Type a command without the `todo` in front, e.g. add buy milk; help lists them and exit quits");
+    loop {
+        let line = match editor.readline("todo> ") {
+            Ok(line) => line,
+            // Ctrl-C drops the line being typed, as in a shell
+            Err(ReadlineError::Interrupted) => continue,
+            Err(ReadlineError::Eof) => break,
+            Err(e) => {
+                messages::say(Msg::CouldNotRead { what: &"the command", error: &e });
+                break;
+            }
+        };
+        if !line.trim().is_empty() {
+            let _ = editor.add_history_entry(line.as_str());
+        }
+        let words = match aliases::split(&line) {
+            Ok(words) => words,
+            Err(e) => {
+                messages::say(Msg::Failed { error: &e });
+                continue;
+            }
+        };
+        match words.first().map(String::as_str) {
+            None => continue,
+            Some("exit" | "quit") => break,
+            _ => {}
+        }
+        let ids = each(words);
+        if let Some(helper) = editor.helper_mut() {
+            helper.ids = ids;
+        }
+    }
+    ACTIVE.store(false, Ordering::Relaxed);
+    if let Some(dir) = history.parent() {
+        let _ = std::fs::create_dir_all(dir);
+    }
+    if let Err(e) = editor.save_history(&history) {
+        messages::say(Msg::HistoryNotSaved { error: &e });
+    }
+}
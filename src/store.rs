@@ -0,0 +1,311 @@
+use crate::{
+    cancel,
+    filter::{Cmp, Filter},
+    join_tags,
+    messages::{self, Msg}, load_todos_from_sqlite, next_id, save_todos_to_sqlite, Todo, INDEXED_COLUMNS, MEMORY_DB,
+};
+use rusqlite::{params, params_from_iter, types::Value, Connection, OpenFlags};
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::OnceLock,
+    time::Duration,
+};
+
+pub type Result<T> = rusqlite::Result<T>;
+
+/// Where a command reads and writes its todos, so it can be written once for both backends
+pub trait Store {
+    /// Every todo, in id order
+    fn todos(&mut self) -> Result<Vec<Todo>>;
+    /// Add `todo` under the next free id and return that id
+    fn insert(&mut self, todo: Todo) -> Result<usize>;
+    /// Add `todo` under its own id, which the caller has checked is free
+    fn insert_with_id(&mut self, todo: Todo) -> Result<()>;
+    /// Add `todos` under their own ids, as `insert_with_id` does, keeping all of them or, if
+    /// Ctrl-C stops it part way, none; returns whether they were kept
+    fn insert_all(&mut self, todos: Vec<Todo>) -> Result<bool>;
+    /// Overwrite the stored todo that has the same id
+    fn update(&mut self, todo: &Todo) -> Result<()>;
+    /// Remove the todos with these ids, returning how many there were
+    fn delete(&mut self, ids: &[usize]) -> Result<usize>;
+    /// Swap every todo for `todos`
+    fn replace(&mut self, todos: Vec<Todo>) -> Result<()>;
+    /// Mark every todo `filter` matches done, stamped with `completed_at` and given `note` if
+    /// there is one, returning how many; `None` when the backend can only do it todo by todo
+    fn complete_matching(&mut self, _filter: &Filter, _completed_at: &str, _note: Option<&str>) -> Result<Option<usize>> {
+        Ok(None)
+    }
+    /// Appended to messages so it's clear which backend they came from
+    fn label(&self) -> &'static str;
+}
+
+/// What messages about the database end with, so they can't be taken for the JSON store's
+pub const SQLITE_LABEL: &str = " (SQLite)";
+
+/// The JSON file's todos, already loaded; saving them afterwards is up to the caller
+pub struct JsonStore<'a> {
+    todos: &'a mut Vec<Todo>,
+}
+
+impl<'a> JsonStore<'a> {
+    pub fn new(todos: &'a mut Vec<Todo>) -> Self {
+        JsonStore { todos }
+    }
+}
+
+impl Store for JsonStore<'_> {
+    fn todos(&mut self) -> Result<Vec<Todo>> {
+        Ok(self.todos.clone())
+    }
+
+    fn insert(&mut self, todo: Todo) -> Result<usize> {
+        let id = next_id(self.todos);
+        self.todos.push(Todo { id, ..todo });
+        Ok(id)
+    }
+
+    fn insert_with_id(&mut self, todo: Todo) -> Result<()> {
+        let position = self.todos.partition_point(|t| t.id < todo.id);
+        self.todos.insert(position, todo);
+        Ok(())
+    }
+
+    /// Nothing is on disk until the caller saves, which Ctrl-C either waits for or forestalls
+    fn insert_all(&mut self, todos: Vec<Todo>) -> Result<bool> {
+        for todo in todos {
+            self.insert_with_id(todo)?;
+        }
+        Ok(true)
+    }
+
+    fn update(&mut self, todo: &Todo) -> Result<()> {
+        if let Some(stored) = self.todos.iter_mut().find(|t| t.id == todo.id) {
+            *stored = todo.clone();
+        }
+        Ok(())
+    }
+
+    fn delete(&mut self, ids: &[usize]) -> Result<usize> {
+        let len_before = self.todos.len();
+        self.todos.retain(|todo| !ids.contains(&todo.id));
+        Ok(len_before - self.todos.len())
+    }
+
+    fn replace(&mut self, todos: Vec<Todo>) -> Result<()> {
+        *self.todos = todos;
+        Ok(())
+    }
+
+    fn label(&self) -> &'static str {
+        ""
+    }
+}
+
+/// The SQLite database, written row by row as the command goes
+pub struct SqliteStore<'a> {
+    conn: &'a mut Connection,
+}
+
+impl<'a> SqliteStore<'a> {
+    pub fn new(conn: &'a mut Connection) -> Self {
+        SqliteStore { conn }
+    }
+}
+
+/// Insert `todo` under `id`, or under the next id SQLite hands out when that is `None`
+fn insert_row(conn: &Connection, id: Option<usize>, todo: &Todo) -> Result<()> {
+    conn.execute(
+        "INSERT INTO todos (id, text, done, due_date, reminder, created_at, completed_at, reminder_repeat,
+                            completion_note, tags, priority, uuid, parent, updated_at, recurrence)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15)",
+        params![
+            id,
+            todo.text,
+            todo.done,
+            todo.due_date,
+            todo.reminder,
+            todo.created_at,
+            todo.completed_at,
+            todo.reminder_repeat,
+            todo.completion_note,
+            join_tags(&todo.tags),
+            todo.priority,
+            todo.uuid,
+            todo.parent,
+            todo.updated_at,
+            todo.recurrence
+        ],
+    )?;
+    Ok(())
+}
+
+impl Store for SqliteStore<'_> {
+    fn todos(&mut self) -> Result<Vec<Todo>> {
+        load_todos_from_sqlite(self.conn)
+    }
+
+    fn insert(&mut self, todo: Todo) -> Result<usize> {
+        insert_row(self.conn, None, &todo)?;
+        Ok(self.conn.last_insert_rowid() as usize)
+    }
+
+    fn insert_with_id(&mut self, todo: Todo) -> Result<()> {
+        insert_row(self.conn, Some(todo.id), &todo)
+    }
+
+    /// In a savepoint, which is rolled back as it is dropped unless every row made it in
+    fn insert_all(&mut self, todos: Vec<Todo>) -> Result<bool> {
+        let tx = self.conn.savepoint()?;
+        for todo in &todos {
+            if cancel::requested() {
+                return Ok(false);
+            }
+            insert_row(&tx, Some(todo.id), todo)?;
+        }
+        tx.commit()?;
+        Ok(true)
+    }
+
+    fn update(&mut self, todo: &Todo) -> Result<()> {
+        self.conn.execute(
+            "UPDATE todos
+             SET text = ?1, done = ?2, due_date = ?3, reminder = ?4, created_at = ?5, completed_at = ?6,
+                 reminder_repeat = ?7, completion_note = ?8, tags = ?9, priority = ?10, uuid = ?11,
+                 parent = ?12, updated_at = ?13, recurrence = ?14
+             WHERE id = ?15",
+            params![
+                todo.text,
+                todo.done,
+                todo.due_date,
+                todo.reminder,
+                todo.created_at,
+                todo.completed_at,
+                todo.reminder_repeat,
+                todo.completion_note,
+                join_tags(&todo.tags),
+                todo.priority,
+                todo.uuid,
+                todo.parent,
+                todo.updated_at,
+                todo.recurrence,
+                todo.id
+            ],
+        )?;
+        Ok(())
+    }
+
+    fn delete(&mut self, ids: &[usize]) -> Result<usize> {
+        let tx = self.conn.savepoint()?;
+        let mut deleted = 0;
+        for id in ids {
+            deleted += tx.execute("DELETE FROM todos WHERE id = ?1", params![id])?;
+        }
+        tx.commit()?;
+        Ok(deleted)
+    }
+
+    fn replace(&mut self, todos: Vec<Todo>) -> Result<()> {
+        save_todos_to_sqlite(self.conn, &todos)
+    }
+
+    /// A single UPDATE, when the filter only uses conditions SQL can check as `matches` does
+    fn complete_matching(&mut self, filter: &Filter, completed_at: &str, note: Option<&str>) -> Result<Option<usize>> {
+        let mut values = vec![Value::from(completed_at.to_string()), Value::from(note.map(str::to_string))];
+        let Some(condition) = sql_condition(filter, &mut values) else {
+            return Ok(None);
+        };
+        let sql = format!(
+            "UPDATE todos SET done = 1, completed_at = ?1, completion_note = COALESCE(?2, completion_note) WHERE {}",
+            condition
+        );
+        Ok(Some(self.conn.execute(&sql, params_from_iter(values))?))
+    }
+
+    fn label(&self) -> &'static str {
+        SQLITE_LABEL
+    }
+}
+
+/// The database --explain looks up query plans in, set when it is given
+static EXPLAIN_DB: OnceLock<PathBuf> = OnceLock::new();
+
+/// For --explain: report every statement run on `conn` from now on to stderr, with how long
+/// it took and its query plan. The plan is looked up on a connection of its own, as the
+/// report is made from inside SQLite while `conn` is still busy
+pub fn explain(conn: &mut Connection, db: &Path) {
+    let _ = EXPLAIN_DB.set(db.to_path_buf());
+    conn.profile(Some(report_statement));
+}
+
+fn report_statement(sql: &str, took: Duration) {
+    let sql = sql.split_whitespace().collect::<Vec<_>>().join(" ");
+    messages::say(Msg::Query { ms: took.as_secs_f64() * 1000.0, sql: &sql });
+    let steps = match query_plan(&sql) {
+        Ok(steps) => steps,
+        Err(e) => {
+            eprintln!("   (no query plan: {})", e);
+            return;
+        }
+    };
+    for (depth, detail) in &steps {
+        eprintln!("   {}{}", "  ".repeat(*depth), detail);
+    }
+    let full_scan = steps.iter().any(|(_, detail)| detail.starts_with("SCAN todos") && !detail.contains("INDEX"));
+    let lowered = sql.to_lowercase();
+    let Some((_, condition)) = lowered.split_once(" where ") else {
+        return;
+    };
+    let words: Vec<&str> = condition.split(|c: char| !c.is_alphanumeric() && c != '_').collect();
+    let scanned: Vec<&str> = INDEXED_COLUMNS.iter().copied().filter(|column| words.contains(column)).collect();
+    if full_scan && !scanned.is_empty() {
+        eprintln!("   ⚠️ Full table scan filtering on {}, which should be indexed", scanned.join(", "));
+    }
+}
+
+/// The steps of the plan SQLite picks for `sql`, each with how deeply it is nested
+fn query_plan(sql: &str) -> std::result::Result<Vec<(usize, String)>, String> {
+    let db = EXPLAIN_DB.get().ok_or("no database")?;
+    if db == Path::new(MEMORY_DB) {
+        return Err("an in-memory database can't be opened twice".to_string());
+    }
+    let conn = Connection::open_with_flags(db, OpenFlags::SQLITE_OPEN_READ_ONLY).map_err(|e| e.to_string())?;
+    let mut stmt = conn.prepare(&format!("EXPLAIN QUERY PLAN {}", sql)).map_err(|e| e.to_string())?;
+    // The plan doesn't depend on the values, which the report isn't given
+    let nulls = vec![Value::Null; stmt.parameter_count()];
+    let rows = stmt
+        .query_map(params_from_iter(nulls), |row| Ok((row.get::<_, i64>(0)?, row.get::<_, i64>(1)?, row.get::<_, String>(3)?)))
+        .and_then(|rows| rows.collect::<Result<Vec<_>>>())
+        .map_err(|e| e.to_string())?;
+    let mut depths: HashMap<i64, usize> = HashMap::new();
+    let mut steps = Vec::new();
+    for (id, parent, detail) in rows {
+        let depth = depths.get(&parent).map_or(0, |depth| depth + 1);
+        depths.insert(id, depth);
+        steps.push((depth, detail));
+    }
+    Ok(steps)
+}
+
+/// `filter` as an SQL condition over the todos table, with its values pushed onto `values`
+/// as numbered parameters. Every part is NULL-safe so NOT agrees with `Filter::matches`;
+/// `None` for conditions that need Rust to check, like text and dates
+fn sql_condition(filter: &Filter, values: &mut Vec<Value>) -> Option<String> {
+    let mut param = |value: Value| {
+        values.push(value);
+        format!("?{}", values.len())
+    };
+    let condition = match filter {
+        Filter::And(a, b) => format!("({} AND {})", sql_condition(a, values)?, sql_condition(b, values)?),
+        Filter::Or(a, b) => format!("({} OR {})", sql_condition(a, values)?, sql_condition(b, values)?),
+        Filter::Not(inner) => format!("NOT {}", sql_condition(inner, values)?),
+        Filter::Tag(tag) => format!("instr(',' || lower(IFNULL(tags, '')) || ',', {}) > 0", param(Value::from(format!(",{},", tag)))),
+        Filter::Done(done) => format!("done = {}", param(Value::from(*done))),
+        Filter::NoDue => "due_date IS NULL".to_string(),
+        Filter::Priority(Cmp::Eq, level) => {
+            format!("lower(IFNULL(priority, '')) = {}", param(Value::from(level.as_str().to_string())))
+        }
+        _ => return None,
+    };
+    Some(condition)
+}
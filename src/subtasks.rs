@@ -0,0 +1,341 @@
+use crate::Todo;
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+};
+
+/// What subtask progress needs from a todo, so the CLI and the TUI can use their own types
+pub trait Node {
+    fn id(&self) -> usize;
+    fn parent(&self) -> Option<usize>;
+    fn is_done(&self) -> bool;
+}
+
+impl<T: Node> Node for &T {
+    fn id(&self) -> usize {
+        (*self).id()
+    }
+
+    fn parent(&self) -> Option<usize> {
+        (*self).parent()
+    }
+
+    fn is_done(&self) -> bool {
+        (*self).is_done()
+    }
+}
+
+impl Node for Todo {
+    fn id(&self) -> usize {
+        self.id
+    }
+
+    fn parent(&self) -> Option<usize> {
+        self.parent
+    }
+
+    fn is_done(&self) -> bool {
+        self.done
+    }
+}
+
+/// How many of a todo's subtasks are done
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Progress {
+    pub done: usize,
+    pub total: usize,
+}
+
+impl Progress {
+    pub fn is_complete(self) -> bool {
+        self.done == self.total
+    }
+}
+
+impl fmt::Display for Progress {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "[{}/{}]", self.done, self.total)
+    }
+}
+
+/// Progress of every todo that has subtasks, keyed by id: over its direct children, or over
+/// all its descendants when `recursive` is set. Parent ids that point at missing todos, or
+/// round in a loop, are followed no further
+pub fn progress<T: Node>(todos: &[T], recursive: bool) -> HashMap<usize, Progress> {
+    let parents: HashMap<usize, Option<usize>> = todos.iter().map(|t| (t.id(), t.parent())).collect();
+    let mut progress: HashMap<usize, Progress> = HashMap::new();
+    for todo in todos {
+        let mut seen = vec![todo.id()];
+        let mut parent = todo.parent();
+        while let Some(id) = parent {
+            if seen.contains(&id) || !parents.contains_key(&id) {
+                break;
+            }
+            let entry = progress.entry(id).or_default();
+            entry.total += 1;
+            if todo.is_done() {
+                entry.done += 1;
+            }
+            if !recursive {
+                break;
+            }
+            seen.push(id);
+            parent = parents[&id];
+        }
+    }
+    progress
+}
+
+/// Ids of the todos below `id`: its subtasks, theirs, and so on down. Parent ids that
+/// round in a loop are followed no further
+pub fn descendants<T: Node>(todos: &[T], id: usize) -> Vec<usize> {
+    let mut found = Vec::new();
+    let mut parents = vec![id];
+    while let Some(parent) = parents.pop() {
+        for todo in todos.iter().filter(|t| t.parent() == Some(parent)) {
+            if todo.id() != id && !found.contains(&todo.id()) {
+                found.push(todo.id());
+                parents.push(todo.id());
+            }
+        }
+    }
+    found
+}
+
+/// A todo's place in the tree
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Row {
+    /// Position in the slice the tree was built from
+    pub index: usize,
+    pub depth: usize,
+    /// What is drawn before it: the lines down from its ancestors, then `├── ` or `└── `
+    /// as it is or isn't the last of its siblings; empty at the top level
+    pub prefix: String,
+    /// What is drawn before the lines it wraps onto, carrying the lines down past it,
+    /// including one to its own subtasks
+    pub continuation: String,
+}
+
+/// Parent ids the tree can't follow, found in the data rather than a filtered view of it,
+/// so they point at a real problem such as a hand-edited file
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Broken {
+    /// (todo, parent) pairs whose parent doesn't exist
+    pub missing: Vec<(usize, usize)>,
+    /// Todos whose parents lead round in a loop back to themselves
+    pub looped: Vec<usize>,
+}
+
+/// The parent ids among `todos` that point at a missing todo or round in a loop
+pub fn broken<T: Node>(todos: &[T]) -> Broken {
+    let parents: HashMap<usize, Option<usize>> = todos.iter().map(|t| (t.id(), t.parent())).collect();
+    let mut broken = Broken::default();
+    for todo in todos {
+        match todo.parent() {
+            Some(parent) if !parents.contains_key(&parent) => broken.missing.push((todo.id(), parent)),
+            _ if in_loop(todo.id(), &parents) => broken.looped.push(todo.id()),
+            _ => {}
+        }
+    }
+    broken
+}
+
+/// Whether following parents up from `id` comes back round to it
+fn in_loop(id: usize, parents: &HashMap<usize, Option<usize>>) -> bool {
+    let mut seen = HashSet::new();
+    let mut parent = parents.get(&id).copied().flatten();
+    while let Some(next) = parent {
+        if next == id {
+            return true;
+        }
+        if !seen.insert(next) {
+            return false;
+        }
+        parent = parents.get(&next).copied().flatten();
+    }
+    false
+}
+
+/// `todos` in tree order, each followed by its subtasks, which otherwise keep their order,
+/// and drawn with box-drawing connectors. Todos whose parent isn't among `todos`, or whose
+/// parents go round in a loop, start at the top level. With `max_depth`, subtasks nested
+/// deeper than that are left out
+pub fn tree<T: Node>(todos: &[T], max_depth: Option<usize>) -> Vec<Row> {
+    let parents: HashMap<usize, Option<usize>> = todos.iter().map(|t| (t.id(), t.parent())).collect();
+    let mut children: HashMap<usize, Vec<usize>> = HashMap::new();
+    let mut roots = Vec::new();
+    for (i, todo) in todos.iter().enumerate() {
+        match todo.parent() {
+            Some(parent) if parents.contains_key(&parent) && !in_loop(todo.id(), &parents) => {
+                children.entry(parent).or_default().push(i);
+            }
+            _ => roots.push(i),
+        }
+    }
+
+    let mut rows = Vec::with_capacity(todos.len());
+    // (index, depth, lines down from its ancestors, whether it is the last of its siblings)
+    let mut stack: Vec<(usize, usize, String, bool)> = roots.iter().rev().map(|&i| (i, 0, String::new(), true)).collect();
+    while let Some((i, depth, lines, last)) = stack.pop() {
+        let (prefix, below) = match depth {
+            0 => (String::new(), String::new()),
+            _ if last => (format!("{}└── ", lines), format!("{}    ", lines)),
+            _ => (format!("{}├── ", lines), format!("{}│   ", lines)),
+        };
+        let kids = children.get(&todos[i].id()).map_or(&[][..], Vec::as_slice);
+        let shows_kids = max_depth.is_none_or(|max| depth < max);
+        let continuation = if shows_kids && !kids.is_empty() { format!("{}│   ", below) } else { below.clone() };
+        rows.push(Row { index: i, depth, prefix, continuation });
+        if shows_kids {
+            for (n, &kid) in kids.iter().enumerate().rev() {
+                stack.push((kid, depth + 1, below.clone(), n + 1 == kids.len()));
+            }
+        }
+    }
+    rows
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// (id, parent, done)
+    struct Item(usize, Option<usize>, bool);
+
+    impl Node for Item {
+        fn id(&self) -> usize {
+            self.0
+        }
+
+        fn parent(&self) -> Option<usize> {
+            self.1
+        }
+
+        fn is_done(&self) -> bool {
+            self.2
+        }
+    }
+
+    fn progress_of(todos: &[Item], id: usize, recursive: bool) -> Option<Progress> {
+        progress(todos, recursive).get(&id).copied()
+    }
+
+    #[test]
+    fn a_todo_without_children_has_no_progress() {
+        let todos = [Item(1, None, false), Item(2, None, true)];
+        assert!(progress(&todos, false).is_empty());
+        assert!(progress(&todos, true).is_empty());
+    }
+
+    #[test]
+    fn progress_counts_done_direct_children() {
+        let todos = [Item(1, None, false), Item(2, Some(1), true), Item(3, Some(1), false), Item(4, None, true)];
+        let progress = progress_of(&todos, 1, false).unwrap();
+        assert_eq!(progress, Progress { done: 1, total: 2 });
+        assert!(!progress.is_complete());
+        assert_eq!(progress.to_string(), "[1/2]");
+    }
+
+    #[test]
+    fn all_children_done_is_complete_whatever_the_parent() {
+        let todos = [Item(1, None, false), Item(2, Some(1), true), Item(3, Some(1), true)];
+        let progress = progress_of(&todos, 1, false).unwrap();
+        assert_eq!(progress, Progress { done: 2, total: 2 });
+        assert!(progress.is_complete());
+    }
+
+    #[test]
+    fn recursive_progress_rolls_up_grandchildren() {
+        // 1 ── 2 ── 3 (done)
+        //   └─ 4
+        let todos = [Item(1, None, false), Item(2, Some(1), false), Item(3, Some(2), true), Item(4, Some(1), false)];
+        assert_eq!(progress_of(&todos, 1, false), Some(Progress { done: 0, total: 2 }));
+        assert_eq!(progress_of(&todos, 1, true), Some(Progress { done: 1, total: 3 }));
+        assert_eq!(progress_of(&todos, 2, false), Some(Progress { done: 1, total: 1 }));
+        assert_eq!(progress_of(&todos, 2, true), Some(Progress { done: 1, total: 1 }));
+    }
+
+    #[test]
+    fn missing_parents_and_loops_are_not_followed() {
+        let todos = [Item(1, Some(9), true), Item(2, Some(3), false), Item(3, Some(2), true)];
+        let progress = progress(&todos, true);
+        assert!(!progress.contains_key(&9));
+        assert_eq!(progress.get(&2), Some(&Progress { done: 1, total: 1 }));
+        assert_eq!(progress.get(&3), Some(&Progress { done: 0, total: 1 }));
+    }
+
+    /// The tree of `todos` as `list --tree` lays it out, with ids for text and `|` where a
+    /// wrapped line would carry on
+    fn drawn(todos: &[Item], max_depth: Option<usize>) -> String {
+        tree(todos, max_depth)
+            .iter()
+            .map(|row| format!("{}{}\n{}|\n", row.prefix, todos[row.index].0, row.continuation))
+            .collect()
+    }
+
+    /// 1 with subtasks 2 and 3, 2 with 4 and 5, 4 with 6; 7 on its own. Listed out of tree
+    /// order, as sorting may leave them
+    fn nested() -> Vec<Item> {
+        vec![
+            Item(6, Some(4), false),
+            Item(1, None, false),
+            Item(2, Some(1), false),
+            Item(3, Some(1), true),
+            Item(4, Some(2), false),
+            Item(7, None, false),
+            Item(5, Some(2), false),
+        ]
+    }
+
+    #[test]
+    fn a_deep_tree_carries_its_lines_down_past_each_level() {
+        assert_eq!(
+            drawn(&nested(), None),
+            "\
+1
+│   |
+├── 2
+│   │   |
+│   ├── 4
+│   │   │   |
+│   │   └── 6
+│   │       |
+│   └── 5
+│       |
+└── 3
+    |
+7
+|
+"
+        );
+    }
+
+    #[test]
+    fn depth_leaves_out_deeper_subtasks() {
+        assert_eq!(drawn(&nested(), Some(1)), "1\n│   |\n├── 2\n│   |\n└── 3\n    |\n7\n|\n");
+        assert_eq!(drawn(&nested(), Some(0)), "1\n|\n7\n|\n");
+    }
+
+    #[test]
+    fn loops_and_missing_parents_start_at_the_top_level() {
+        // 1 and 2 are each other's parent, 3's parent is gone and 4 is 3's subtask
+        let todos = [Item(1, Some(2), false), Item(2, Some(1), false), Item(3, Some(9), false), Item(4, Some(3), false)];
+        assert_eq!(drawn(&todos, None), "1\n|\n2\n|\n3\n│   |\n└── 4\n    |\n");
+        assert_eq!(broken(&todos), Broken { missing: vec![(3, 9)], looped: vec![1, 2] });
+    }
+
+    #[test]
+    fn a_todo_that_is_its_own_parent_is_a_loop() {
+        let todos = [Item(1, Some(1), false), Item(2, Some(1), false)];
+        assert_eq!(drawn(&todos, None), "1\n│   |\n└── 2\n    |\n");
+        assert_eq!(broken(&todos), Broken { missing: vec![], looped: vec![1] });
+        assert_eq!(descendants(&todos, 1), [2]);
+    }
+
+    #[test]
+    fn a_parent_left_out_of_the_view_puts_its_subtasks_at_the_top_level() {
+        let todos = [Item(2, Some(1), false), Item(3, Some(2), false)];
+        assert_eq!(drawn(&todos, None), "2\n│   |\n└── 3\n    |\n");
+        assert_eq!(broken(&todos).missing, [(2, 1)]);
+    }
+}
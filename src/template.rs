@@ -0,0 +1,140 @@
+/// A line format such as `"{id}. {text} [{due}]"`, parsed once and rendered per todo.
+/// `{{` and `}}` stand for literal braces
+pub struct Template {
+    pieces: Vec<Piece>,
+}
+
+enum Piece {
+    Literal(String),
+    Field(String),
+}
+
+impl Template {
+    /// Parse `source`, rejecting unbalanced braces and any placeholder not in `fields`
+    pub fn parse(source: &str, fields: &[&str]) -> Result<Template, String> {
+        Template::parse_with(source, Some(fields))
+    }
+
+    /// Parse `source` taking any placeholder name, for text whose placeholders are
+    /// whatever its author chose
+    pub fn parse_open(source: &str) -> Result<Template, String> {
+        Template::parse_with(source, None)
+    }
+
+    /// The placeholders used, in order of first use
+    pub fn fields(&self) -> Vec<&str> {
+        let mut fields = Vec::new();
+        for piece in &self.pieces {
+            if let Piece::Field(name) = piece
+                && !fields.contains(&name.as_str())
+            {
+                fields.push(name.as_str());
+            }
+        }
+        fields
+    }
+
+    fn parse_with(source: &str, fields: Option<&[&str]>) -> Result<Template, String> {
+        let mut pieces = Vec::new();
+        let mut literal = String::new();
+        let mut chars = source.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '{' if chars.peek() == Some(&'{') => {
+                    chars.next();
+                    literal.push('{');
+                }
+                '}' if chars.peek() == Some(&'}') => {
+                    chars.next();
+                    literal.push('}');
+                }
+                '{' => {
+                    let mut name = String::new();
+                    loop {
+                        match chars.next() {
+                            Some('}') => break,
+                            Some(c) => name.push(c),
+                            None => return Err(format!("'{{{}' is missing its closing }}", name)),
+                        }
+                    }
+                    if fields.is_none() && name.trim().is_empty() {
+                        return Err("a placeholder needs a name, as in {person}".to_string());
+                    }
+                    if let Some(fields) = fields
+                        && !fields.contains(&name.as_str())
+                    {
+                        let known: Vec<String> = fields.iter().map(|f| format!("{{{}}}", f)).collect();
+                        return Err(format!("unknown placeholder {{{}}}; use one of {}", name, known.join(", ")));
+                    }
+                    if !literal.is_empty() {
+                        pieces.push(Piece::Literal(std::mem::take(&mut literal)));
+                    }
+                    pieces.push(Piece::Field(name));
+                }
+                '}' => return Err("a lone } must be written as }}".to_string()),
+                c => literal.push(c),
+            }
+        }
+        if !literal.is_empty() {
+            pieces.push(Piece::Literal(literal));
+        }
+        Ok(Template { pieces })
+    }
+
+    /// Fill in the placeholders with what `value` returns for each field name
+    pub fn render(&self, value: impl Fn(&str) -> String) -> String {
+        self.pieces
+            .iter()
+            .map(|piece| match piece {
+                Piece::Literal(text) => text.clone(),
+                Piece::Field(name) => value(name),
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FIELDS: &[&str] = &["time", "date", "text", "id"];
+
+    fn rendered(source: &str) -> String {
+        let template = Template::parse(source, FIELDS).unwrap();
+        template.render(|field| match field {
+            "time" => "14:30".to_string(),
+            "date" => "2025-07-01".to_string(),
+            "text" => "call dentist".to_string(),
+            _ => "4".to_string(),
+        })
+    }
+
+    #[test]
+    fn placeholders_are_filled_in_where_they_stand() {
+        assert_eq!(rendered("{time} {text}"), "14:30 call dentist");
+        assert_eq!(rendered("#{id}: {text} on {date} at {time}"), "#4: call dentist on 2025-07-01 at 14:30");
+        assert_eq!(rendered("{text}{text}"), "call dentistcall dentist");
+        assert_eq!(rendered("no placeholders"), "no placeholders");
+        assert_eq!(rendered(""), "");
+    }
+
+    #[test]
+    fn doubled_braces_are_literal() {
+        assert_eq!(rendered("{{{id}}} {{text}}"), "{4} {text}");
+    }
+
+    #[test]
+    fn malformed_templates_are_refused() {
+        let error = |source: &str| Template::parse(source, FIELDS).err().unwrap();
+        assert_eq!(error("{when}"), "unknown placeholder {when}; use one of {time}, {date}, {text}, {id}");
+        assert_eq!(error("{time"), "'{time' is missing its closing }");
+        assert_eq!(error("time}"), "a lone } must be written as }}");
+        assert_eq!(Template::parse_open("{}").err().unwrap(), "a placeholder needs a name, as in {person}");
+    }
+
+    #[test]
+    fn fields_are_listed_once_in_order_of_first_use() {
+        let template = Template::parse_open("{text} {id} {text} {person}").unwrap();
+        assert_eq!(template.fields(), ["text", "id", "person"]);
+    }
+}
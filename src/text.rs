@@ -0,0 +1,66 @@
+use std::{borrow::Cow, cmp::Ordering};
+use unicode_normalization::{char::is_combining_mark, UnicodeNormalization};
+
+/// Longest todo text accepted, in characters
+pub const MAX_LEN: usize = 1000;
+
+/// Todo text ready to store, and how many lines were joined to make it
+pub struct Normalized {
+    pub text: String,
+    pub joined_lines: usize,
+}
+
+/// Turn raw input into todo text: line breaks, and the indentation around them, collapse to
+/// single spaces so every todo stays on one line in lists and exports. Fails on empty or
+/// overlong text
+pub fn normalize(raw: &str) -> Result<Normalized, String> {
+    let lines: Vec<&str> = raw.lines().map(str::trim).filter(|line| !line.is_empty()).collect();
+    let text = lines.join(" ");
+    let len = text.chars().count();
+    if len == 0 {
+        return Err("todo text can't be empty".to_string());
+    }
+    if len > MAX_LEN {
+        return Err(format!("todo text is {} characters long; the limit is {}", len, MAX_LEN));
+    }
+    Ok(Normalized { text, joined_lines: lines.len() })
+}
+
+/// Whether two todo texts say the same thing, ignoring case and spacing; how `add` spots
+/// a todo it already has
+pub fn same_text(a: &str, b: &str) -> bool {
+    let words = |text: &str| text.split_whitespace().map(str::to_lowercase).collect::<Vec<_>>();
+    words(a) == words(b)
+}
+
+/// The order todo texts sort in, shared by `list --sort text` and the TUI: letter by letter
+/// ignoring case and accents, so "Älgar" sorts among the a's rather than after "zebra".
+/// Texts differing only in those compare equal, and a stable sort keeps them as they were
+pub fn collate(a: &str, b: &str) -> Ordering {
+    sort_key(a).cmp(&sort_key(b))
+}
+
+/// `text` with accents taken off its letters and every letter in lower case
+fn sort_key(text: &str) -> String {
+    text.nfd().filter(|c| !is_combining_mark(*c)).flat_map(char::to_lowercase).collect()
+}
+
+/// Text safe to write to a terminal: control characters such as newlines or ESC are shown
+/// as escapes (`\n`, `\x1b`) rather than being interpreted
+pub fn escape(text: &str) -> Cow<'_, str> {
+    if !text.chars().any(char::is_control) {
+        return Cow::Borrowed(text);
+    }
+    let mut escaped = String::with_capacity(text.len() + 8);
+    for c in text.chars() {
+        match c {
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            c if c.is_control() && (c as u32) < 0x100 => escaped.push_str(&format!("\\x{:02x}", c as u32)),
+            c if c.is_control() => escaped.push_str(&format!("\\u{{{:x}}}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+    Cow::Owned(escaped)
+}
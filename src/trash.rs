@@ -0,0 +1,92 @@
+use crate::{changes, now_timestamp, Todo};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::{fs, io, path::PathBuf, sync::OnceLock};
+
+/// A deleted todo, kept until the trash is emptied
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Trashed {
+    /// RFC 3339 timestamp
+    pub deleted_at: String,
+    #[serde(flatten)]
+    pub todo: Todo,
+}
+
+/// The trash file for the backend in use, set once at startup
+static PATH: OnceLock<PathBuf> = OnceLock::new();
+
+pub fn set_path(path: PathBuf) {
+    let _ = PATH.set(path);
+}
+
+/// What is in the trash, oldest deletion first; nothing when there is no trash file yet.
+/// One that can't be read is an error, so that nothing writes over what it still holds
+pub fn read() -> io::Result<Vec<Trashed>> {
+    let Some(path) = PATH.get() else {
+        return Ok(vec![]);
+    };
+    let data = match fs::read_to_string(path) {
+        Ok(data) => data,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(vec![]),
+        Err(e) => return Err(e),
+    };
+    serde_json::from_str(&data).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, format!("{}: {}", path.display(), e)))
+}
+
+/// Replace the trash with `trashed`, by a rename so a reader never sees it half written
+pub fn write(trashed: &[Trashed]) -> io::Result<()> {
+    let Some(path) = PATH.get() else {
+        return Ok(());
+    };
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir.to_path_buf(),
+        _ => PathBuf::from("."),
+    };
+    fs::create_dir_all(&dir)?;
+    let file = tempfile::NamedTempFile::new_in(&dir)?;
+    serde_json::to_writer_pretty(&file, trashed)?;
+    file.persist(path)?;
+    Ok(())
+}
+
+/// Drop what was deleted before `cutoff` from the trash, returning how many todos that was
+pub fn empty_before(cutoff: DateTime<Utc>) -> io::Result<usize> {
+    let mut trashed = read()?;
+    let len_before = trashed.len();
+    trashed.retain(|t| DateTime::parse_from_rfc3339(&t.deleted_at).map_or(true, |at| at >= cutoff));
+    let emptied = len_before - trashed.len();
+    if emptied > 0 {
+        write(&trashed)?;
+    }
+    Ok(emptied)
+}
+
+/// The highest id in the trash, so new todos don't take an id a restore will want back;
+/// it reads the trash, so it is called once at startup rather than per new todo
+pub fn max_id() -> io::Result<usize> {
+    Ok(read()?.iter().map(|t| t.todo.id).max().unwrap_or(0))
+}
+
+/// Move the todos a command removed into the trash, and take out the ones it brought
+/// back (matched by UUID, as a restore may have had to give one a new id)
+pub fn record(before: &[Todo], after: &[Todo]) -> io::Result<()> {
+    let (old_ids, new_ids) = (changes::by_id(before), changes::by_id(after));
+    let removed: Vec<&Todo> = before.iter().filter(|old| !new_ids.contains_key(&old.id)).collect();
+    let added: Vec<&str> = after
+        .iter()
+        .filter(|new| !old_ids.contains_key(&new.id) && !new.uuid.is_empty())
+        .map(|new| new.uuid.as_str())
+        .collect();
+    if removed.is_empty() && added.is_empty() {
+        return Ok(());
+    }
+    let mut trashed = read()?;
+    let len_before = trashed.len();
+    trashed.retain(|t| !added.contains(&t.todo.uuid.as_str()));
+    if removed.is_empty() && trashed.len() == len_before {
+        return Ok(());
+    }
+    let deleted_at = now_timestamp();
+    trashed.extend(removed.into_iter().map(|todo| Trashed { deleted_at: deleted_at.clone(), todo: todo.clone() }));
+    write(&trashed)
+}
@@ -1,201 +1,1354 @@
 use crossterm::{
-    event::{self, DisableMouseCapture, EnableMouseCapture, Event as CEvent, KeyCode},
+    event::{self, DisableMouseCapture, EnableMouseCapture, Event as CEvent, KeyCode, KeyEvent, KeyModifiers},
     execute,
-    terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
+    terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen, SetTitle},
 };
 use std::{
+    collections::HashSet,
     fs,
     io::{self, Write},
-    time::Duration,
+    path::{Path, PathBuf},
+    process::ExitStatus,
+    time::{Duration, Instant},
 };
 use tui::{
     backend::{CrosstermBackend},
     layout::{Constraint, Direction, Layout},
     style::{Color, Modifier, Style},
     text::{Span, Spans},
-    widgets::{Block, Borders, List, ListItem, ListState},
+    widgets::{Block, Borders, Cell, List, ListItem, ListState, Paragraph, Row, Table, TableState},
     Terminal,
 };
+use chrono::{Local, NaiveDate, NaiveDateTime, NaiveTime};
+use crate::{agenda, dates::{self, Due}, diff, id_width, ids, messages::{Level, Msg}, paths, quick_add::parse_quick_add, read_tags, subtasks, text};
 
-#[derive(Clone)]
+#[derive(Clone, PartialEq)]
 pub struct Todo {
     pub id: usize,
     pub text: String,
     pub done: bool,
     pub due_date: Option<String>,
     pub reminder: Option<String>,
+    pub tags: Vec<String>,
+    pub priority: Option<String>,
+    pub parent: Option<usize>,
+    /// Pending for longer than the configured stale threshold
+    pub stale: bool,
 }
 
-fn format_todo(todo: &Todo) -> String {
+impl agenda::Item for Todo {
+    fn id(&self) -> usize {
+        self.id
+    }
+
+    fn is_done(&self) -> bool {
+        self.done
+    }
+
+    fn due(&self) -> Option<Due> {
+        Due::parse(self.due_date.as_deref()?)
+    }
+
+    fn reminder_at(&self) -> Option<NaiveDateTime> {
+        NaiveDateTime::parse_from_str(self.reminder.as_deref()?, "%Y-%m-%d %H:%M").ok()
+    }
+}
+
+impl subtasks::Node for Todo {
+    fn id(&self) -> usize {
+        self.id
+    }
+
+    fn parent(&self) -> Option<usize> {
+        self.parent
+    }
+
+    fn is_done(&self) -> bool {
+        self.done
+    }
+}
+
+/// Settings from the config file that the TUI needs
+pub struct Settings<'a> {
+    /// Used when a reminder is typed without a time
+    pub default_reminder_time: NaiveTime,
+    /// Named filters `f` cycles through after the built-in views
+    pub filters: Vec<SavedFilter<'a>>,
+    /// The filter to open on, as a position in `filters`
+    pub start_filter: Option<usize>,
+    /// Show overdue todos above the rest, whatever the view
+    pub pin_overdue: bool,
+    /// Ring the terminal bell when a reminder goes off
+    pub bell: bool,
+    /// Pad ids to at least this many digits
+    pub min_id_width: usize,
+    /// The store being edited, for the footer
+    pub location: Location,
+    /// With --watch, checks whether another process has saved the store, returning its
+    /// todos when it has
+    pub watch: Option<Watch<'a>>,
+    /// Runs what is typed at the `:` prompt, which is only offered when this is set
+    pub palette: Option<Palette<'a>>,
+}
+
+/// Checks the store for changes saved elsewhere, as `Settings::watch`
+pub type Watch<'a> = Box<dyn FnMut() -> Option<Vec<Todo>> + 'a>;
+
+/// Runs a line typed at the `:` prompt on the list as it stands, as `Settings::palette`
+pub type Palette<'a> = Box<dyn FnMut(&str, &[Todo]) -> Ran<'a> + 'a>;
+
+/// What a line typed at the `:` prompt did
+pub struct Ran<'a> {
+    /// The list as the command left it, when it ran on it
+    pub todos: Option<Vec<Todo>>,
+    /// A filter to show the list through from now on
+    pub filter: Option<SavedFilter<'a>>,
+    /// What to say about it on the status line, and how it went
+    pub said: Option<(Level, String)>,
+}
+
+/// How often --watch checks the store
+const WATCH_EVERY: Duration = Duration::from_secs(1);
+
+/// Which store the TUI is editing
+pub struct Location {
+    /// "JSON" or "SQLite"
+    pub backend: &'static str,
+    pub path: PathBuf,
+    pub profile: String,
+    /// Why nothing can be saved, if it can't: readonly mode, or a store that can't be written
+    pub read_only: Option<&'static str>,
+}
+
+/// Where the TUI saves each change as soon as it is made, rather than leaving them all to be
+/// saved on quit
+pub trait Persist {
+    /// Save `todo` over the stored todo with its id
+    fn save(&mut self, todo: &Todo) -> Result<(), String>;
+    /// Store `todo` as a new todo, returning the id it was given
+    fn add(&mut self, todo: &Todo) -> Result<usize, String>;
+    fn delete(&mut self, id: usize) -> Result<(), String>;
+    /// The todo with `id` as it is stored, or `None` if it isn't
+    fn reload(&mut self, id: usize) -> Result<Option<Todo>, String>;
+}
+
+/// Why the TUI, or its plain mode, was left
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Exit {
+    Quit,
+    /// `p` in the TUI or `tui` in plain mode: carry on in the other one
+    Switch,
+}
+
+/// How long a row flashes once its reminder goes off, and how fast
+const FLASH_FOR: Duration = Duration::from_secs(3);
+const FLASH_EVERY_MS: u128 = 500;
+
+/// Reminders going off while the TUI is open. Each is noted with the time it was set for,
+/// so it goes off once however many ticks pass, and again only if it is moved later
+struct Alerts {
+    fired: HashSet<(usize, NaiveDateTime)>,
+    /// Rows flashing for a reminder that just went off, with when they stop
+    flashing: Vec<(usize, Instant)>,
+    /// The terminal title as last set
+    title: String,
+}
+
+impl Alerts {
+    /// Reminders already past when the TUI opens have gone off before, so don't go off again
+    fn new(todos: &[Todo], now: NaiveDateTime) -> Alerts {
+        let fired = todos
+            .iter()
+            .filter_map(|todo| Some((todo.id, agenda::Item::reminder_at(todo)?)))
+            .filter(|(_, at)| *at <= now)
+            .collect();
+        Alerts { fired, flashing: Vec::new(), title: String::new() }
+    }
+
+    /// The pending todos whose reminder has gone off since the last check, setting their rows
+    /// flashing
+    fn check(&mut self, todos: &[Todo], now: NaiveDateTime) -> Vec<usize> {
+        let started = Instant::now();
+        self.flashing.retain(|(_, until)| *until > started);
+        let mut fired = Vec::new();
+        for todo in todos.iter().filter(|t| !t.done) {
+            if let Some(at) = agenda::Item::reminder_at(todo)
+                && at <= now
+                && self.fired.insert((todo.id, at))
+            {
+                fired.push(todo.id);
+                self.flashing.push((todo.id, started + FLASH_FOR));
+            }
+        }
+        fired
+    }
+
+    /// Whether the row for `id` is in the lit half of its flash
+    fn lit(&self, id: usize) -> bool {
+        self.flashing.iter().any(|(flashing, until)| {
+            *flashing == id && (until.saturating_duration_since(Instant::now()).as_millis() / FLASH_EVERY_MS).is_multiple_of(2)
+        })
+    }
+
+    /// The title for the terminal, counting the pending todos whose reminder has passed;
+    /// `None` when it is already showing
+    fn title(&mut self, todos: &[Todo], now: NaiveDateTime) -> Option<String> {
+        let due = todos.iter().filter(|t| !t.done && agenda::Item::reminder_at(*t).is_some_and(|at| at <= now)).count();
+        let title = if due > 0 { format!("todo ({} due!)", due) } else { "todo".to_string() };
+        if title == self.title {
+            return None;
+        }
+        self.title = title.clone();
+        Some(title)
+    }
+}
+
+/// A named filter, deciding for each row whether it is shown
+pub struct SavedFilter<'a> {
+    pub name: String,
+    pub matches: Box<dyn Fn(&Todo) -> bool + 'a>,
+}
+
+/// Which todos the list shows; `f` cycles through them
+#[derive(Clone, PartialEq, Eq)]
+enum View {
+    All,
+    Pending,
+    Done,
+    /// Todos with the tag, asked for when the cycle reaches it; empty while asking
+    Tagged(String),
+    Saved(usize),
+}
+
+impl View {
+    fn next(&self, saved: usize) -> View {
+        match self {
+            View::All => View::Pending,
+            View::Pending => View::Done,
+            View::Done => View::Tagged(String::new()),
+            View::Tagged(_) if saved > 0 => View::Saved(0),
+            View::Saved(i) if i + 1 < saved => View::Saved(i + 1),
+            _ => View::All,
+        }
+    }
+
+    fn shows(&self, todo: &Todo, filters: &[SavedFilter]) -> bool {
+        match self {
+            View::All => true,
+            View::Pending => !todo.done,
+            View::Done => todo.done,
+            View::Tagged(tag) => tag.is_empty() || todo.tags.contains(tag),
+            View::Saved(i) => filters.get(*i).is_none_or(|f| (f.matches)(todo)),
+        }
+    }
+
+    fn label(&self, filters: &[SavedFilter]) -> String {
+        match self {
+            View::All => "all".to_string(),
+            View::Pending => "pending".to_string(),
+            View::Done => "done".to_string(),
+            View::Tagged(tag) => format!("#{}", tag),
+            View::Saved(i) => filters.get(*i).map_or("all", |f| f.name.as_str()).to_string(),
+        }
+    }
+}
+
+/// The tag being typed for the "by tag" view, which Tab completes from `known`
+struct TagPrompt {
+    input: String,
+    known: Vec<String>,
+}
+
+impl TagPrompt {
+    fn new(todos: &[Todo]) -> TagPrompt {
+        let mut known: Vec<String> = todos.iter().flat_map(|t| t.tags.iter().cloned()).collect();
+        known.sort();
+        known.dedup();
+        TagPrompt { input: String::new(), known }
+    }
+
+    fn matches(&self) -> Vec<&str> {
+        let typed = self.input.trim_start_matches('#').to_lowercase();
+        self.known.iter().map(String::as_str).filter(|tag| tag.starts_with(&typed)).collect()
+    }
+
+    /// Extend the input as far as every matching tag agrees, as a shell would
+    fn complete(&mut self) {
+        let matches = self.matches();
+        let Some((first, rest)) = matches.split_first() else {
+            return;
+        };
+        let mut common = first.to_string();
+        for tag in rest {
+            let shared = common.chars().zip(tag.chars()).take_while(|(a, b)| a == b).count();
+            common = common.chars().take(shared).collect();
+        }
+        self.input = common;
+    }
+
+    fn line(&self) -> Spans<'static> {
+        let matches = self.matches();
+        let hint = match matches.len() {
+            0 => " (no such tag)".to_string(),
+            n if n > 8 => format!(" (Tab completes: {}, … {} more)", matches[..8].join(", "), n - 8),
+            _ => format!(" (Tab completes: {})", matches.join(", ")),
+        };
+        Spans::from(vec![
+            Span::styled("Tag: ", Style::default().fg(Color::Cyan)),
+            Span::raw(format!("{}▏", self.input)),
+            Span::styled(hint, Style::default().fg(Color::DarkGray)),
+        ])
+    }
+}
+
+/// The `:` prompt at the foot of the screen. Up and Down go back through the lines run
+/// at it since the TUI opened
+#[derive(Default)]
+struct CommandLine {
+    /// What has been typed, while the prompt is open
+    input: Option<String>,
+    history: Vec<String>,
+    /// The line of `history` Up and Down have got to, while going through it
+    recalled: Option<usize>,
+}
+
+impl CommandLine {
+    fn open(&mut self) {
+        self.input = Some(String::new());
+        self.recalled = None;
+    }
+
+    fn back(&mut self) {
+        let Some(i) = self.recalled.unwrap_or(self.history.len()).checked_sub(1) else {
+            return;
+        };
+        self.recalled = Some(i);
+        self.input = Some(self.history[i].clone());
+    }
+
+    /// Past the newest line, back to an empty one
+    fn forward(&mut self) {
+        let Some(i) = self.recalled else {
+            return;
+        };
+        self.recalled = (i + 1 < self.history.len()).then_some(i + 1);
+        self.input = Some(self.recalled.map_or_else(String::new, |i| self.history[i].clone()));
+    }
+
+    /// Close the prompt, returning the line typed and keeping it in the history unless
+    /// it was blank or the same as the last
+    fn enter(&mut self) -> Option<String> {
+        let line = self.input.take()?.trim().to_string();
+        if line.is_empty() {
+            return None;
+        }
+        if self.history.last() != Some(&line) {
+            self.history.push(line.clone());
+        }
+        Some(line)
+    }
+
+    fn line(&self) -> Spans<'static> {
+        Spans::from(vec![
+            Span::styled(":", Style::default().fg(Color::Cyan)),
+            Span::raw(format!("{}▏", self.input.as_deref().unwrap_or_default())),
+        ])
+    }
+}
+
+/// The order the list is shown in, switched with `s`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Order {
+    Id,
+    Text,
+}
+
+impl Order {
+    fn next(self) -> Order {
+        match self {
+            Order::Id => Order::Text,
+            Order::Text => Order::Id,
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            Order::Id => "",
+            Order::Text => " • by text",
+        }
+    }
+}
+
+/// Read a typed reminder, "YYYY-MM-DD HH:MM" or just a date, into the local form the list
+/// holds. A bare date gets `default_time`; anything else is rejected
+fn parse_reminder_input(input: &str, default_time: NaiveTime) -> Option<String> {
+    let mut parts = input.split_whitespace();
+    let date = dates::parse_date(parts.next()?).ok()?;
+    let time = match parts.next() {
+        Some(time) => NaiveTime::parse_from_str(time, "%H:%M").ok()?,
+        None => default_time,
+    };
+    if parts.next().is_some() {
+        return None;
+    }
+    Some(date.and_time(time).format("%Y-%m-%d %H:%M").to_string())
+}
+
+/// Read a typed due date, a date with an optional "HH:MM", into its stored form
+fn parse_due_input(input: &str) -> Option<String> {
+    let mut parts = input.split_whitespace();
+    let date = dates::parse_date(parts.next()?).ok()?;
+    let time = match parts.next() {
+        Some(time) => Some(NaiveTime::parse_from_str(time, "%H:%M").ok()?),
+        None => None,
+    };
+    if parts.next().is_some() {
+        return None;
+    }
+    Some(Due { date, time }.to_stored())
+}
+
+/// `msg` for the status line, coloured by how it went
+fn flash(msg: Msg) -> Span<'static> {
+    said(msg.level(), msg.to_string())
+}
+
+/// `text` for the status line in the colour of `level`
+fn said(level: Level, text: String) -> Span<'static> {
+    let style = match level {
+        Level::Success => Style::default().fg(Color::Green),
+        Level::Info | Level::Note => Style::default().fg(Color::Cyan),
+        Level::Alert => Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD),
+        Level::Warning => Style::default().fg(Color::Yellow),
+        Level::Error => Style::default().fg(Color::Red),
+    };
+    Span::styled(text, style)
+}
+
+/// The status line after setting or clearing a field on `count` todos at once
+fn bulk_status(field: &str, value: Option<&str>, count: usize) -> Option<Span<'static>> {
+    Some(flash(Msg::BulkSet { field, value, count }))
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Screen {
+    List,
+    Dashboard,
+}
+
+/// How many upcoming reminders the dashboard shows
+const DASHBOARD_REMINDERS: usize = 5;
+
+/// The positions in `todos` of the rows `view` shows, in `order`, with overdue todos
+/// first when `pin_overdue` is set
+fn rows(todos: &[Todo], view: &View, filters: &[SavedFilter], order: Order, pin_overdue: bool, now: NaiveDateTime) -> Vec<usize> {
+    let mut visible: Vec<usize> = (0..todos.len()).filter(|&i| view.shows(&todos[i], filters)).collect();
+    // Sorts are stable, so switching order back and forth keeps ties as they were
+    match order {
+        Order::Id => visible.sort_by_key(|&i| todos[i].id),
+        Order::Text => visible.sort_by(|&a, &b| text::collate(&todos[a].text, &todos[b].text)),
+    }
+    if pin_overdue {
+        // Stable, so both blocks keep their order
+        visible.sort_by_key(|&i| !agenda::is_overdue(&todos[i], now));
+    }
+    visible
+}
+
+/// The row to highlight among `visible`: the one showing the todo `follow` names if it is
+/// shown, or else `selected`, kept within the list
+fn row_of(todos: &[Todo], visible: &[usize], follow: Option<usize>, selected: usize) -> usize {
+    follow
+        .and_then(|id| visible.iter().position(|&i| todos[i].id == id))
+        .unwrap_or(selected)
+        .min(visible.len().saturating_sub(1))
+}
+
+/// The dashboard's sections, each holding positions in the todo list
+struct Dashboard {
+    total: usize,
+    done: usize,
+    sections: [(&'static str, Vec<usize>); 3],
+}
+
+impl Dashboard {
+    fn new(todos: &[Todo], now: NaiveDateTime) -> Dashboard {
+        let position = |todo: &Todo| todos.iter().position(|t| t.id == todo.id).unwrap_or(0);
+        let today = agenda::today(todos, now);
+        let reminders = agenda::next_reminders(todos, now, DASHBOARD_REMINDERS);
+        Dashboard {
+            total: todos.len(),
+            done: todos.iter().filter(|t| t.done).count(),
+            sections: [
+                ("Today", today.due_today.into_iter().map(position).collect()),
+                ("Overdue", today.overdue.into_iter().map(position).collect()),
+                ("Next reminders", reminders.into_iter().map(position).collect()),
+            ],
+        }
+    }
+
+    /// Every selectable line, in display order, as a position in the todo list
+    fn entries(&self) -> Vec<usize> {
+        self.sections.iter().flat_map(|(_, items)| items.iter().copied()).collect()
+    }
+
+    fn summary(&self) -> String {
+        format!(
+            "{} todos • {} pending • {} done • {} overdue • {} due today",
+            self.total,
+            self.total - self.done,
+            self.done,
+            self.sections[1].1.len(),
+            self.sections[0].1.len()
+        )
+    }
+}
+
+/// The dashboard's lines, with the row each selectable entry sits on
+fn dashboard_items(dashboard: &Dashboard, todos: &[Todo], now: NaiveDateTime) -> (Vec<ListItem<'static>>, Vec<usize>) {
+    let mut items = Vec::new();
+    let mut entry_rows = Vec::new();
+    for (i, (heading, positions)) in dashboard.sections.iter().enumerate() {
+        if i > 0 {
+            items.push(ListItem::new(""));
+        }
+        items.push(ListItem::new(Span::styled(
+            format!("{} ({})", heading, positions.len()),
+            Style::default().add_modifier(Modifier::UNDERLINED),
+        )));
+        if positions.is_empty() {
+            items.push(ListItem::new(Span::styled("  nothing", Style::default().add_modifier(Modifier::DIM))));
+        }
+        for &position in positions {
+            let todo = &todos[position];
+            let when = if *heading == "Next reminders" {
+                todo.reminder.as_deref().map(reminder_label)
+            } else {
+                todo.due_date.as_deref().map(|due| relative_due(due, now).0)
+            };
+            let status = if todo.done { "[x]" } else { "[ ]" };
+            entry_rows.push(items.len());
+            items.push(ListItem::new(format!(
+                "{} #{} {} ({})",
+                status,
+                todo.id,
+                text::escape(&todo.text),
+                when.unwrap_or_default()
+            )));
+        }
+    }
+    (items, entry_rows)
+}
+
+const STATUS_WIDTH: u16 = 3;
+const DUE_WIDTH: u16 = 16;
+const REMINDER_WIDTH: u16 = 16;
+/// Narrowest the text column may get before the reminder column is dropped
+const MIN_TEXT_WIDTH: u16 = 20;
+
+/// Shorten `text` to at most `width` characters, marking the cut with an ellipsis
+fn truncate(text: &str, width: usize) -> String {
+    if text.chars().count() <= width {
+        return text.to_string();
+    }
+    if width == 0 {
+        return String::new();
+    }
+    let mut out: String = text.chars().take(width - 1).collect();
+    out.push('…');
+    out
+}
+
+/// Render a due date relative to today ("today", "in 3 days 17:00", "2 days ago"), flagging
+/// overdue ones
+fn relative_due(due: &str, now: NaiveDateTime) -> (String, bool) {
+    let Some(parsed) = Due::parse(due) else {
+        return (due.to_string(), false);
+    };
+    let label = dates::humanize_date(parsed.date, now.date());
+    let label = match parsed.time {
+        Some(time) => format!("{} {}", label, time.format("%H:%M")),
+        None => label,
+    };
+    (label, parsed.is_overdue(now))
+}
+
+/// A local "YYYY-MM-DD HH:MM" reminder with its date in the configured format
+/// Tag badges take colors from this, each tag always the same one
+const BADGE_COLORS: [Color; 6] = [Color::Red, Color::Green, Color::Yellow, Color::Blue, Color::Magenta, Color::Cyan];
+
+/// The color of `tag`'s badge, from a hash of its name so it is the same every run
+fn badge_color(tag: &str) -> Color {
+    let hash = tag.bytes().fold(5381u32, |hash, b| hash.wrapping_mul(33) ^ u32::from(b));
+    BADGE_COLORS[hash as usize % BADGE_COLORS.len()]
+}
+
+/// `tag` drawn as a badge: black on its color, padded by a space either side
+fn badge(tag: &str) -> Span<'static> {
+    Span::styled(format!(" {} ", tag), Style::default().fg(Color::Black).bg(badge_color(tag)))
+}
+
+fn reminder_label(reminder: &str) -> String {
+    match NaiveDateTime::parse_from_str(reminder, "%Y-%m-%d %H:%M") {
+        Ok(at) => format!("{} {}", dates::format_date(at.date()), at.format("%H:%M")),
+        Err(_) => reminder.to_string(),
+    }
+}
+
+fn todo_row(
+    todo: &Todo,
+    progress: Option<subtasks::Progress>,
+    id_width: usize,
+    text_width: u16,
+    show_reminder: bool,
+    marked: bool,
+    now: NaiveDateTime,
+) -> Row<'static> {
     let status = if todo.done { "[x]" } else { "[ ]" };
-    let due_date = todo.due_date.as_deref().unwrap_or("No due date");
-    let reminder = todo.reminder.as_deref().unwrap_or("No reminder");
-    format!("{} {} (Due: {}, Reminder: {})", status, todo.text, due_date, reminder)
+    let due_cell = match todo.due_date.as_deref() {
+        Some(due) => {
+            let (label, overdue) = relative_due(due, now);
+            let style = if overdue && !todo.done {
+                Style::default().fg(Color::Red)
+            } else {
+                Style::default()
+            };
+            Cell::from(truncate(&label, DUE_WIDTH as usize)).style(style)
+        }
+        None => Cell::from(""),
+    };
+
+    let progress = progress.map(|progress| format!(" {}", progress)).unwrap_or_default();
+    let mut width = (text_width as usize).saturating_sub(progress.chars().count());
+    // Badges go after the text while they leave it at least half the column
+    let badges: Vec<&String> = todo
+        .tags
+        .iter()
+        .scan(0, |used, tag| {
+            *used += tag.chars().count() + 3;
+            Some((tag, *used))
+        })
+        .take_while(|&(_, used)| used <= width / 2)
+        .map(|(tag, _)| tag)
+        .collect();
+    width -= badges.iter().map(|tag| tag.chars().count() + 3).sum::<usize>();
+    let mut text_spans = vec![Span::raw(format!("{}{}", truncate(&text::escape(&todo.text), width), progress))];
+    for tag in badges {
+        text_spans.push(Span::raw(" "));
+        text_spans.push(badge(tag));
+    }
+
+    let mut cells = vec![
+        Cell::from(status),
+        Cell::from(format!("{:>width$}", todo.id, width = id_width)),
+        Cell::from(Spans::from(text_spans)),
+        due_cell,
+    ];
+    if show_reminder {
+        cells.push(Cell::from(todo.reminder.as_deref().map(reminder_label).unwrap_or_default()));
+    }
+    let row = Row::new(cells);
+    if marked {
+        row.style(Style::default().add_modifier(Modifier::REVERSED))
+    } else if todo.stale && !todo.done {
+        row.style(Style::default().add_modifier(Modifier::DIM))
+    } else {
+        row
+    }
 }
 
-pub fn run_tui(mut todos: Vec<Todo>) -> Result<Vec<Todo>, Box<dyn std::error::Error>> {
+/// Show the todos until `q` (or `p` for plain mode), returning them as edited. With a
+/// `store`, every change is saved to it as it is made
+pub fn run_tui(
+    mut todos: Vec<Todo>,
+    mut settings: Settings,
+    mut store: Option<&mut dyn Persist>,
+) -> Result<(Vec<Todo>, Exit), Box<dyn std::error::Error>> {
     enable_raw_mode()?;
     let mut stdout = io::stdout();
     execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
     let backend = CrosstermBackend::new(stdout);
     let mut terminal = Terminal::new(backend)?;
 
+    // Position of the highlighted row among the rows the current view shows
     let mut selected = 0;
+    let mut view = settings.start_filter.map_or(View::All, View::Saved);
+    let mut screen = Screen::List;
+    let mut dashboard_selected = 0;
+    // A message about the last action, shown above the list until the next key
+    let mut status: Option<Span> = None;
+    // A parent whose subtasks are all done, which `y` marks done too
+    let mut asking_about: Option<usize> = None;
+    // Ids of the todos marked with `m`, which `t` and `r` then change all at once
+    let mut marked: Vec<usize> = Vec::new();
+    let mut alerts = Alerts::new(&todos, Local::now().naive_local());
+    let mut exit = Exit::Quit;
+    let mut order = Order::Id;
+    // The todos as last read from the store, to tell the rows changed here from the rest
+    // when --watch brings in changes saved elsewhere; without --watch nothing needs them
+    let mut synced = if settings.watch.is_some() { todos.clone() } else { Vec::new() };
+    let mut checked = Instant::now();
+    // The highlighted todo, which stays highlighted when a refresh moves the rows around
+    let mut current_id: Option<usize> = None;
+    let mut follow: Option<usize> = None;
+    // Set while the "by tag" view asks which tag
+    let mut tag_prompt: Option<TagPrompt> = None;
+    let mut command_line = CommandLine::default();
+    // Where in `settings.filters` the last filter typed at the `:` prompt went, so the
+    // next one replaces it
+    let mut typed_filter: Option<usize> = None;
+    let help = format!(
+        "↑↓ move • Space toggle • a add • e edit • g tags • d delete • t due date • +/- due ±1 day (Shift+↑↓ week) • r reminder (time [{}]) • c clear reminder • m mark (Esc unmark) • f filter • s sort • Tab dashboard • p plain mode{} • q quit",
+        settings.default_reminder_time.format("%H:%M"),
+        if settings.palette.is_some() { " • : command" } else { "" }
+    );
 
     loop {
+        let now = Local::now().naive_local();
+        let fired = alerts.check(&todos, now);
+        if let Some(&id) = fired.first()
+            && let Some(todo) = todos.iter().find(|t| t.id == id)
+        {
+            status = Some(flash(Msg::ReminderDue { id, text: &text::escape(&todo.text), more: fired.len() - 1 }));
+            if settings.bell {
+                terminal.backend_mut().write_all(b"\x07")?;
+                terminal.backend_mut().flush()?;
+            }
+        }
+        if let Some(watch) = settings.watch.as_mut()
+            && checked.elapsed() >= WATCH_EVERY
+        {
+            checked = Instant::now();
+            if let Some(remote) = watch() {
+                let changed = if store.is_some() {
+                    // Every change is saved as it is made when there is a store, so nothing here is unsaved
+                    let changed = todos != remote;
+                    todos = remote.clone();
+                    changed
+                } else {
+                    refresh(&mut todos, &synced, remote.clone())
+                };
+                if changed {
+                    status = Some(flash(Msg::Refreshed));
+                    follow = current_id;
+                }
+                synced = remote;
+            }
+        }
+        if let Some(title) = alerts.title(&todos, now) {
+            execute!(terminal.backend_mut(), SetTitle(title))?;
+        }
+        let dashboard = Dashboard::new(&todos, now);
+        let entries = dashboard.entries();
+        dashboard_selected = dashboard_selected.min(entries.len().saturating_sub(1));
+        let visible = rows(&todos, &view, &settings.filters, order, settings.pin_overdue, now);
+        let overdue = visible.iter().filter(|&&i| agenda::is_overdue(&todos[i], now)).count();
+        selected = row_of(&todos, &visible, follow.take(), selected);
+        let current = visible.get(selected).copied();
+        current_id = current.map(|i| todos[i].id);
+        marked.retain(|id| todos.iter().any(|t| t.id == *id));
+        // What `t` and `r` change: every marked todo, or else the highlighted one
+        let targets: Vec<usize> = if marked.is_empty() {
+            current.into_iter().collect()
+        } else {
+            (0..todos.len()).filter(|&i| marked.contains(&todos[i].id)).collect()
+        };
+
         terminal.draw(|f| {
             let size = f.size();
 
             let chunks = Layout::default()
                 .direction(Direction::Vertical)
                 .margin(2)
-                .constraints([Constraint::Length(1), Constraint::Min(1)].as_ref())
+                .constraints([Constraint::Length(1), Constraint::Min(1), Constraint::Length(1)].as_ref())
                 .split(size);
+            if command_line.input.is_some() {
+                f.render_widget(Paragraph::new(command_line.line()), chunks[2]);
+            } else {
+                f.render_widget(Paragraph::new(footer(&settings.location, chunks[2].width)), chunks[2]);
+            }
+
+            if screen == Screen::Dashboard {
+                let block = Block::default()
+                    .borders(Borders::ALL)
+                    .title(Spans::from(vec![Span::styled(
+                        "↑↓ move • Enter go to todo • Space toggle • Tab list • q quit",
+                        Style::default().fg(Color::Yellow),
+                    )]));
+                f.render_widget(Paragraph::new(dashboard.summary()), chunks[0]);
+                let (items, entry_rows) = dashboard_items(&dashboard, &todos, now);
+                let mut state = ListState::default();
+                state.select(entry_rows.get(dashboard_selected).copied());
+                let list = List::new(items)
+                    .block(block)
+                    .highlight_style(Style::default().add_modifier(Modifier::BOLD))
+                    .highlight_symbol(">> ");
+                f.render_stateful_widget(list, chunks[1], &mut state);
+                return;
+            }
+
+            if let Some(prompt) = &tag_prompt {
+                f.render_widget(Paragraph::new(prompt.line()), chunks[0]);
+            } else if let Some(message) = &status {
+                f.render_widget(Paragraph::new(Spans::from(message.clone())), chunks[0]);
+            }
 
             let title_block = Block::default()
                 .borders(Borders::ALL)
-                .title(Spans::from(vec![Span::styled(
-                    "↑↓ move • Space toggle • a add • e edit • d delete • t due date • r reminder • c clear reminder • q quit",
-                    Style::default().fg(Color::Yellow),
-                )]));
+                .title(Spans::from(vec![
+                    Span::styled(help.clone(), Style::default().fg(Color::Yellow)),
+                    Span::styled(format!(" [{}{}] ", view.label(&settings.filters), order.label()), Style::default().fg(Color::Cyan)),
+                    Span::styled(
+                        if settings.pin_overdue && overdue > 0 { format!("⚠ {} overdue ", overdue) } else { String::new() },
+                        Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+                    ),
+                ]));
 
-            let items: Vec<ListItem> = todos
+            let id_width = id_width(todos.iter().map(|t| t.id), settings.min_id_width);
+            // Borders, the highlight symbol and the gaps between columns eat into the width
+            let inner_width = chunks[1].width.saturating_sub(2 + 3);
+            let fixed = STATUS_WIDTH + id_width as u16 + DUE_WIDTH + 3;
+            let show_reminder = inner_width.saturating_sub(fixed + REMINDER_WIDTH + 1) >= MIN_TEXT_WIDTH;
+            let text_width = if show_reminder {
+                inner_width.saturating_sub(fixed + REMINDER_WIDTH + 1)
+            } else {
+                inner_width.saturating_sub(fixed)
+            };
+
+            let progress = subtasks::progress(&todos, false);
+            let rows: Vec<Row> = visible
                 .iter()
-                .map(|t| {
-                    ListItem::new(vec![Spans::from(Span::raw(format_todo(t)))])
+                .map(|&i| {
+                    let marked = marked.contains(&todos[i].id);
+                    let row = todo_row(&todos[i], progress.get(&todos[i].id).copied(), id_width, text_width, show_reminder, marked, now);
+                    if alerts.lit(todos[i].id) {
+                        row.style(Style::default().fg(Color::Black).bg(Color::Yellow))
+                    } else {
+                        row
+                    }
                 })
                 .collect();
 
-            let mut state = ListState::default();
+            let mut header = vec!["", "#", "Todo", "Due"];
+            let mut widths = vec![
+                Constraint::Length(STATUS_WIDTH),
+                Constraint::Length(id_width as u16),
+                Constraint::Length(text_width),
+                Constraint::Length(DUE_WIDTH),
+            ];
+            if show_reminder {
+                header.push("Reminder");
+                widths.push(Constraint::Length(REMINDER_WIDTH));
+            }
+
+            let mut state = TableState::default();
             state.select(Some(selected));
 
-            let list = List::new(items)
+            let table = Table::new(rows)
+                .header(Row::new(header).style(Style::default().add_modifier(Modifier::UNDERLINED)))
                 .block(title_block)
+                .widths(&widths)
+                .column_spacing(1)
                 .highlight_style(Style::default().add_modifier(Modifier::BOLD))
                 .highlight_symbol(">> ");
 
-            f.render_stateful_widget(list, chunks[1], &mut state);
+            f.render_stateful_widget(table, chunks[1], &mut state);
         })?;
 
-        if event::poll(Duration::from_millis(100))? {
-            if let CEvent::Key(key) = event::read()? {
+        if event::poll(Duration::from_millis(100))?
+            && let CEvent::Key(key) = event::read()?
+        {
+            status = None;
+            // Raw mode turns Ctrl-C into a key press, so it quits like q rather than stopping the process
+            if key.code == KeyCode::Char('c') && key.modifiers.contains(KeyModifiers::CONTROL) {
+                break;
+            }
+            if let Some(prompt) = &mut tag_prompt {
                 match key.code {
-                    KeyCode::Char('q') => break,
-                    KeyCode::Down => {
-                        if selected < todos.len().saturating_sub(1) {
-                            selected += 1;
+                    KeyCode::Char(c) => prompt.input.push(c),
+                    KeyCode::Backspace => {
+                        prompt.input.pop();
+                    }
+                    KeyCode::Tab => prompt.complete(),
+                    KeyCode::Enter => {
+                        let tag = read_tags(&prompt.input).ok().and_then(|tags| tags.into_iter().next());
+                        view = match tag {
+                            Some(tag) => View::Tagged(tag),
+                            None => view.next(settings.filters.len()),
+                        };
+                        tag_prompt = None;
+                    }
+                    // Skips the "by tag" view, on to the next
+                    KeyCode::Esc => {
+                        view = view.next(settings.filters.len());
+                        tag_prompt = None;
+                    }
+                    _ => {}
+                }
+                continue;
+            }
+            if let Some(input) = &mut command_line.input {
+                match key.code {
+                    KeyCode::Char(c) => input.push(c),
+                    // Deleting past the start closes the prompt, as in vi
+                    KeyCode::Backspace if input.is_empty() => command_line.input = None,
+                    KeyCode::Backspace => {
+                        input.pop();
+                    }
+                    KeyCode::Up => command_line.back(),
+                    KeyCode::Down => command_line.forward(),
+                    KeyCode::Esc => command_line.input = None,
+                    KeyCode::Enter => {
+                        if let Some(line) = command_line.enter()
+                            && let Some(palette) = settings.palette.as_mut()
+                        {
+                            let ran = palette(&line, &todos);
+                            if let Some(updated) = ran.todos {
+                                todos = updated;
+                                follow = current_id;
+                            }
+                            if let Some(filter) = ran.filter {
+                                let i = match typed_filter {
+                                    Some(i) => {
+                                        settings.filters[i] = filter;
+                                        i
+                                    }
+                                    None => {
+                                        settings.filters.push(filter);
+                                        settings.filters.len() - 1
+                                    }
+                                };
+                                typed_filter = Some(i);
+                                view = View::Saved(i);
+                                selected = 0;
+                            }
+                            status = ran.said.map(|(level, text)| said(level, text));
                         }
                     }
+                    _ => {}
+                }
+                continue;
+            }
+            if let Some(reason) = settings.location.read_only
+                && changes_todos(key)
+            {
+                status = Some(flash(Msg::ReadOnly { reason }));
+                continue;
+            }
+            if let Some(parent) = asking_about.take()
+                && key.code == KeyCode::Char('y')
+            {
+                if let Some(position) = todos.iter().position(|t| t.id == parent) {
+                    todos[position].done = true;
+                    (status, asking_about) = match persist(store.as_deref_mut(), &mut todos, &[position]) {
+                        Some(failure) => (Some(failure), None),
+                        None => offer_parent(&todos, position),
+                    };
+                }
+                continue;
+            }
+            if screen == Screen::Dashboard {
+                match key.code {
+                    KeyCode::Char('q') => break,
+                    KeyCode::Tab | KeyCode::Char('1') => screen = Screen::List,
+                    KeyCode::Down if dashboard_selected + 1 < entries.len() => {
+                        dashboard_selected += 1;
+                    }
                     KeyCode::Up => {
-                        if selected > 0 {
-                            selected -= 1;
+                        dashboard_selected = dashboard_selected.saturating_sub(1);
+                    }
+                    KeyCode::Enter => {
+                        if let Some(&position) = entries.get(dashboard_selected) {
+                            // Show everything so the todo is certain to be in the list, and
+                            // find its row there once the list is sorted
+                            view = View::All;
+                            follow = Some(todos[position].id);
+                            screen = Screen::List;
                         }
                     }
                     KeyCode::Char(' ') => {
-                        if let Some(todo) = todos.get_mut(selected) {
-                            todo.done = !todo.done;
+                        if let Some(&position) = entries.get(dashboard_selected) {
+                            (status, asking_about) = toggle(store.as_deref_mut(), &mut todos, position);
                         }
                     }
-                    KeyCode::Char('d') => {
-                        if selected < todos.len() {
-                            todos.remove(selected);
-                            if selected > 0 {
-                                selected -= 1;
+                    _ => {}
+                }
+                continue;
+            }
+            match key.code {
+                KeyCode::Char('q') => break,
+                KeyCode::Char('p') => {
+                    exit = Exit::Switch;
+                    break;
+                }
+                KeyCode::Tab | KeyCode::Char('2') => {
+                    screen = Screen::Dashboard;
+                    dashboard_selected = 0;
+                }
+                KeyCode::Char('f') => {
+                    view = view.next(settings.filters.len());
+                    if view == View::Tagged(String::new()) {
+                        tag_prompt = Some(TagPrompt::new(&todos));
+                    }
+                    selected = 0;
+                }
+                KeyCode::Char('s') => {
+                    order = order.next();
+                    selected = 0;
+                }
+                KeyCode::Char(':') if settings.palette.is_some() => command_line.open(),
+                KeyCode::Up | KeyCode::Down if key.modifiers.contains(KeyModifiers::SHIFT) => {
+                    if let Some(position) = current {
+                        let days = if key.code == KeyCode::Up { 7 } else { -7 };
+                        status = nudge_due(&mut todos[position], days, now.date());
+                        status = persist(store.as_deref_mut(), &mut todos, &[position]).or(status);
+                    }
+                }
+                KeyCode::Down if selected < visible.len().saturating_sub(1) => {
+                    selected += 1;
+                }
+                KeyCode::Up => {
+                    selected = selected.saturating_sub(1);
+                }
+                KeyCode::Char(' ') => {
+                    if let Some(position) = current {
+                        (status, asking_about) = toggle(store.as_deref_mut(), &mut todos, position);
+                    }
+                }
+                KeyCode::Char('d') => {
+                    if let Some(position) = current {
+                        match store.as_deref_mut().map_or(Ok(()), |store| store.delete(todos[position].id)) {
+                            Ok(()) => {
+                                todos.remove(position);
+                                selected = selected.saturating_sub(1);
                             }
+                            Err(e) => status = Some(flash(Msg::NotDeleted { error: &e })),
                         }
                     }
-                    KeyCode::Char('e') => {
-                        if let Some(todo) = todos.get_mut(selected) {
-                            let tmp_path = "/tmp/todo_edit.txt";
-                            fs::write(tmp_path, &todo.text)?;
-
-                            if run_editor(tmp_path, &mut terminal).is_ok() {
-                                let updated = fs::read_to_string(tmp_path)?;
-                                if !updated.trim().is_empty() {
-                                    todo.text = updated.trim().to_string();
-                                }
+                }
+                KeyCode::Char('e') => {
+                    if let Some(position) = current
+                        && let Some(updated) = edit_field(&todos[position].text, "todo_edit", &mut terminal)?
+                        && !updated.is_empty()
+                    {
+                        match text::normalize(&updated) {
+                            Ok(normalized) => {
+                                let summary = diff::summary(&todos[position].text, &normalized.text);
+                                status = Some(flash(Msg::Edited { summary: &summary }));
+                                todos[position].text = normalized.text;
+                                status = persist(store.as_deref_mut(), &mut todos, &[position]).or(status);
                             }
+                            Err(e) => status = Some(flash(Msg::NotSaved { error: &e })),
                         }
                     }
-                    KeyCode::Char('t') => {
-                        if let Some(todo) = todos.get_mut(selected) {
-                            let tmp_path = "/tmp/todo_due.txt";
-                            fs::write(tmp_path, todo.due_date.as_deref().unwrap_or(""))?;
-
-                            if run_editor(tmp_path, &mut terminal).is_ok() {
-                                let updated = fs::read_to_string(tmp_path)?;
-                                let updated = updated.trim();
-                                if !updated.is_empty() {
-                                    todo.due_date = Some(updated.to_string());
-                                } else {
-                                    todo.due_date = None;
-                                }
+                }
+                KeyCode::Char('g') => {
+                    if let Some(position) = current
+                        && let Some(updated) = edit_field(&todos[position].tags.join(", "), "todo_tags", &mut terminal)?
+                    {
+                        match read_tags(&updated) {
+                            Ok(tags) => {
+                                let summary = tags.iter().map(|t| format!("#{}", t)).collect::<Vec<_>>().join(" ");
+                                status = Some(flash(if tags.is_empty() { Msg::TagsCleared } else { Msg::Tags { tags: &summary } }));
+                                todos[position].tags = tags;
+                                status = persist(store.as_deref_mut(), &mut todos, &[position]).or(status);
                             }
+                            Err(e) => status = Some(flash(Msg::NotSaved { error: &e })),
                         }
                     }
-                    KeyCode::Char('r') => {
-                        if let Some(todo) = todos.get_mut(selected) {
-                            let tmp_path = "/tmp/todo_reminder.txt";
-                            fs::write(tmp_path, todo.reminder.as_deref().unwrap_or(""))?;
-
-                            if run_editor(tmp_path, &mut terminal).is_ok() {
-                                let updated = fs::read_to_string(tmp_path)?;
-                                let updated = updated.trim();
-                                if !updated.is_empty() {
-                                    todo.reminder = Some(updated.to_string());
-                                } else {
-                                    todo.reminder = None;
-                                }
+                }
+                KeyCode::Char('m') => {
+                    if let Some(position) = current {
+                        let id = todos[position].id;
+                        match marked.iter().position(|&m| m == id) {
+                            Some(i) => {
+                                marked.remove(i);
                             }
+                            None => marked.push(id),
                         }
+                        selected += 1;
                     }
-                    KeyCode::Char('c') => {
-                        if let Some(todo) = todos.get_mut(selected) {
-                            todo.reminder = None;
+                }
+                KeyCode::Esc => marked.clear(),
+                KeyCode::Char('t') if !targets.is_empty() => {
+                    // A single todo starts from its own date; several start from nothing
+                    let current = match targets[..] {
+                        [position] => todos[position].due_date.clone().unwrap_or_default(),
+                        _ => String::new(),
+                    };
+                    if let Some(updated) = edit_field(&current, "todo_due", &mut terminal)? {
+                        // Checked before anything changes, so a typo leaves every target as it was
+                        let due_date = match updated.as_str() {
+                            "" => None,
+                            typed => match parse_due_input(typed) {
+                                Some(due) => Some(due),
+                                None => {
+                                    status = Some(flash(Msg::InvalidDue { value: &updated }));
+                                    continue;
+                                }
+                            },
+                        };
+                        for &position in &targets {
+                            todos[position].due_date = due_date.clone();
+                        }
+                        if targets.len() > 1 {
+                            status = bulk_status("due", due_date.as_deref(), targets.len());
+                            marked.clear();
                         }
+                        status = persist(store.as_deref_mut(), &mut todos, &targets).or(status);
+                    }
+                }
+                KeyCode::Char('r') if !targets.is_empty() => {
+                    let current = match targets[..] {
+                        [position] => todos[position].reminder.clone().unwrap_or_default(),
+                        _ => String::new(),
+                    };
+                    if let Some(updated) = edit_field(&current, "todo_reminder", &mut terminal)? {
+                        let reminder = match updated.as_str() {
+                            "" => None,
+                            typed => match parse_reminder_input(typed, settings.default_reminder_time) {
+                                Some(reminder) => Some(reminder),
+                                None => {
+                                    status = Some(flash(Msg::InvalidReminder { value: &updated }));
+                                    continue;
+                                }
+                            },
+                        };
+                        for &position in &targets {
+                            todos[position].reminder = reminder.clone();
+                        }
+                        if targets.len() > 1 {
+                            status = bulk_status("reminder", reminder.as_deref(), targets.len());
+                            marked.clear();
+                        }
+                        status = persist(store.as_deref_mut(), &mut todos, &targets).or(status);
+                    }
+                }
+                KeyCode::Char('c') => {
+                    if let Some(position) = current {
+                        todos[position].reminder = None;
+                        status = persist(store.as_deref_mut(), &mut todos, &[position]);
+                    }
+                }
+                KeyCode::Char(c @ ('+' | '-')) => {
+                    if let Some(position) = current {
+                        let days = if c == '+' { 1 } else { -1 };
+                        status = nudge_due(&mut todos[position], days, now.date());
+                        status = persist(store.as_deref_mut(), &mut todos, &[position]).or(status);
                     }
-                    KeyCode::Char('a') => {
-                        let tmp_path = "/tmp/todo_new.txt";
-                        fs::write(tmp_path, "")?;
-
-                        if run_editor(tmp_path, &mut terminal).is_ok() {
-                            let new_text = fs::read_to_string(tmp_path)?;
-                            let new_text = new_text.trim();
-                            if !new_text.is_empty() {
-                                todos.push(Todo {
-                                    id: todos.len() + 1,
-                                    text: new_text.to_string(),
-                                    done: false,
-                                    due_date: None,
-                                    reminder: None,
-                                });
-                                selected = todos.len().saturating_sub(1);
+                }
+                KeyCode::Char('a') => {
+                    if let Some(new_text) = edit_field("", "todo_new", &mut terminal)?
+                        && !new_text.is_empty()
+                    {
+                        let (new_text, meta) = parse_quick_add(&new_text, Local::now().date_naive());
+                        let new_text = match text::normalize(&new_text) {
+                            Ok(normalized) => normalized.text,
+                            Err(e) => {
+                                status = Some(flash(Msg::NotAdded { error: &e }));
+                                continue;
                             }
+                        };
+                        if let Some(warning) = meta.warnings.first() {
+                            status = Some(flash(Msg::AddWarning { warning }));
                         }
+                        let mut todo = Todo {
+                            id: ids::next_after(todos.iter().map(|t| t.id).max().unwrap_or(0)),
+                            text: new_text,
+                            done: false,
+                            due_date: meta.due.map(|due| due.to_stored()),
+                            reminder: None,
+                            tags: meta.tags,
+                            priority: meta.priority.map(|p| p.as_str().to_string()),
+                            parent: None,
+                            stale: false,
+                        };
+                        if let Some(store) = store.as_deref_mut() {
+                            match store.add(&todo) {
+                                Ok(id) => todo.id = id,
+                                Err(e) => {
+                                    status = Some(flash(Msg::NotAdded { error: &e }));
+                                    continue;
+                                }
+                            }
+                        }
+                        // Deleting it before the store is saved mustn't free its id
+                        ids::set_floor(todo.id + 1);
+                        // Lands on the new todo wherever the order puts it, or the last row
+                        // if the view hides it
+                        follow = Some(todo.id);
+                        selected = usize::MAX;
+                        todos.push(todo);
                     }
-                    _ => {}
                 }
+                _ => {}
             }
         }
     }
 
     disable_raw_mode()?;
-    execute!(terminal.backend_mut(), LeaveAlternateScreen, DisableMouseCapture)?;
+    // An empty title hands the title back to the shell, or the terminal's default
+    execute!(terminal.backend_mut(), LeaveAlternateScreen, DisableMouseCapture, SetTitle(""))?;
     terminal.show_cursor()?;
 
-    Ok(todos)
+    Ok((todos, exit))
+}
+
+/// Bring `todos` up to date with `remote`, the store as another process left it. Rows that
+/// differ from `synced`, the store as last read, were changed here and not yet saved, so
+/// they are kept as they are, deletions included; one added here moves to a free id if the
+/// store has given its id to another todo. Returns whether anything changed
+fn refresh(todos: &mut Vec<Todo>, synced: &[Todo], remote: Vec<Todo>) -> bool {
+    let unsaved: Vec<&Todo> = todos.iter().filter(|t| !synced.contains(t)).collect();
+    let known = |id: usize| synced.iter().any(|t| t.id == id);
+    let deleted_here = |id: usize| known(id) && !todos.iter().any(|t| t.id == id);
+    // A todo added elsewhere under the id of one added here is kept; ours moves instead
+    let edited_here = |id: usize| known(id) && unsaved.iter().any(|u| u.id == id);
+    let mut merged: Vec<Todo> = remote.into_iter().filter(|t| !deleted_here(t.id) && !edited_here(t.id)).collect();
+    for todo in unsaved {
+        let mut todo = todo.clone();
+        if !known(todo.id) && merged.iter().any(|t| t.id == todo.id) {
+            todo.id = ids::next_after(merged.iter().chain(todos.iter()).map(|t| t.id).max().unwrap_or(0));
+            ids::set_floor(todo.id + 1);
+        }
+        merged.push(todo);
+    }
+    merged.sort_by_key(|t| t.id);
+    let changed = merged != *todos;
+    *todos = merged;
+    changed
+}
+
+/// Whether `key` is one of the keys that edit, add or remove todos
+fn changes_todos(key: KeyEvent) -> bool {
+    matches!(key.code, KeyCode::Char(' ' | 'a' | 'c' | 'd' | 'e' | 'g' | 'r' | 't' | 'y' | '+' | '-'))
+        || matches!(key.code, KeyCode::Up | KeyCode::Down) && key.modifiers.contains(KeyModifiers::SHIFT)
+}
+
+/// The store's backend, profile and path, with a badge when it is read-only. A path too
+/// long for `width` loses its start, as the end says more about which store it is
+fn footer(location: &Location, width: u16) -> Spans<'static> {
+    let mut spans = Vec::new();
+    if location.read_only.is_some() {
+        spans.push(Span::styled(" READ-ONLY ", Style::default().fg(Color::Black).bg(Color::Red)));
+        spans.push(Span::raw(" "));
+    }
+    let label = format!("{} • profile {} • ", location.backend, location.profile);
+    let used = spans.iter().map(|s| s.content.chars().count()).sum::<usize>() + label.chars().count();
+    let path = location.path.display().to_string();
+    let room = (width as usize).saturating_sub(used);
+    let path = match path.chars().count() {
+        len if len <= room => path,
+        len => {
+            let tail: String = path.chars().skip(len + 1 - room.max(1)).collect();
+            format!("…{}", tail)
+        }
+    };
+    spans.push(Span::styled(label, Style::default().fg(Color::Cyan)));
+    spans.push(Span::raw(path));
+    Spans::from(spans)
+}
+
+/// Mark the todo at `position` done, or pending again if it was done, and save it
+fn toggle(store: Option<&mut (dyn Persist + '_)>, todos: &mut Vec<Todo>, position: usize) -> (Option<Span<'static>>, Option<usize>) {
+    todos[position].done = !todos[position].done;
+    match persist(store, todos, &[position]) {
+        Some(failure) => (Some(failure), None),
+        None => offer_parent(todos, position),
+    }
+}
+
+/// Save the todos at `positions` to `store`, when there is one. A todo that fails to save
+/// is read back, so the list goes on showing what is really stored; returns the message
+/// for the status line if any failed
+fn persist(store: Option<&mut (dyn Persist + '_)>, todos: &mut Vec<Todo>, positions: &[usize]) -> Option<Span<'static>> {
+    let store = store?;
+    let mut positions = positions.to_vec();
+    // Last first, so dropping a todo that has gone doesn't move the ones still to save
+    positions.sort_unstable_by(|a, b| b.cmp(a));
+    let mut failure = None;
+    for position in positions {
+        let Err(e) = store.save(&todos[position]) else {
+            continue;
+        };
+        match store.reload(todos[position].id) {
+            Ok(Some(stored)) => todos[position] = stored,
+            Ok(None) => {
+                todos.remove(position);
+            }
+            // Nothing better to show than what was there; the message says it wasn't saved
+            Err(_) => {}
+        }
+        failure = Some(flash(Msg::NotSaved { error: &e }));
+    }
+    failure
+}
+
+/// When marking the todo at `position` done finished its parent's subtasks, ask whether to
+/// mark the parent done as well
+fn offer_parent(todos: &[Todo], position: usize) -> (Option<Span<'static>>, Option<usize>) {
+    let todo = &todos[position];
+    let Some(parent) = todo.parent.filter(|_| todo.done) else {
+        return (None, None);
+    };
+    let pending = todos.iter().any(|t| t.id == parent && !t.done);
+    if !pending || !subtasks::progress(todos, false).get(&parent).is_some_and(|p| p.is_complete()) {
+        return (None, None);
+    }
+    let question = format!("All subtasks complete — mark #{} done? [y/N]", parent);
+    (Some(Span::styled(question, Style::default().fg(Color::Yellow))), Some(parent))
+}
+
+/// Move a todo's due date by `days`, keeping any time of day. A todo without one gets
+/// today's date when pushed later; returns the message for the status line
+fn nudge_due(todo: &mut Todo, days: i64, today: NaiveDate) -> Option<Span<'static>> {
+    let before = todo.due_date.clone();
+    let due = match before.as_deref() {
+        Some(value) => match Due::parse(value) {
+            Some(due) => due.shifted(days),
+            None => {
+                return Some(flash(Msg::CantMoveDue { value }));
+            }
+        },
+        None if days > 0 => Due { date: today, time: None },
+        None => return None,
+    };
+    let after = due.to_stored();
+    let message = flash(Msg::DueMoved { from: before.as_deref().unwrap_or("none"), to: &after });
+    todo.due_date = Some(after);
+    Some(message)
+}
+
+/// Seed a temp file with `original` and let the user edit it, returning the trimmed result
+/// only when the editor exited cleanly and the content actually changed
+fn edit_field(
+    original: &str,
+    prefix: &str,
+    terminal: &mut Terminal<CrosstermBackend<std::io::Stdout>>,
+) -> io::Result<Option<String>> {
+    let temp_file = paths::edit_buffer(prefix)?;
+    fs::write(&temp_file, original)?;
+    match run_editor(&temp_file, terminal) {
+        Ok(status) if status.success() => {}
+        _ => return Ok(None),
+    }
+    let updated = fs::read_to_string(&temp_file)?;
+    let updated = updated.trim();
+    if updated == original.trim() {
+        return Ok(None);
+    }
+    Ok(Some(updated.to_string()))
 }
 
 /// Temporarily leave TUI to run $EDITOR and refresh screen after
 fn run_editor(
-    temp_file: &str,
+    temp_file: &Path,
     terminal: &mut Terminal<CrosstermBackend<std::io::Stdout>>,
-) -> io::Result<()> {
+) -> io::Result<ExitStatus> {
     disable_raw_mode()?;
     execute!(io::stdout(), LeaveAlternateScreen, DisableMouseCapture)?;
 
-    let editor = std::env::var("EDITOR").unwrap_or_else(|_| "nano".to_string());
-    let status = std::process::Command::new(editor).arg(temp_file).status();
+    let status = paths::editor_command(temp_file).status();
 
     // Restore screen
     execute!(
@@ -210,9 +1363,122 @@ fn run_editor(
     // Redraw screen immediately
     terminal.draw(|_| {})?;
 
-    match status {
-        Ok(_) => Ok(()),
-        Err(e) => Err(e),
-    }
+    status
 }
 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn todo(id: usize, text: &str, due_date: Option<&str>) -> Todo {
+        Todo {
+            id,
+            text: text.to_string(),
+            done: false,
+            due_date: due_date.map(str::to_string),
+            reminder: None,
+            tags: Vec::new(),
+            priority: None,
+            parent: None,
+            stale: false,
+        }
+    }
+
+    #[test]
+    fn a_todo_picked_on_the_dashboard_is_selected_in_the_list_sorted_by_text() {
+        let todos = vec![
+            todo(1, "Älgar", Some("2026-10-16")),
+            todo(2, "banana", None),
+            todo(3, "apple", Some("2026-10-10")),
+            todo(4, "Cherry", None),
+        ];
+        let now = NaiveDate::from_ymd_opt(2026, 10, 16).unwrap().and_hms_opt(12, 0, 0).unwrap();
+        let entries = Dashboard::new(&todos, now).entries();
+        // "Today" comes first, then "Overdue"
+        assert_eq!(entries.iter().map(|&i| todos[i].id).collect::<Vec<_>>(), [1, 3]);
+        for (order, pin_overdue) in [(Order::Text, false), (Order::Text, true), (Order::Id, true)] {
+            let visible = rows(&todos, &View::All, &[], order, pin_overdue, now);
+            for &entry in &entries {
+                let picked = todos[entry].id;
+                let row = row_of(&todos, &visible, Some(picked), 0);
+                assert_eq!(todos[visible[row]].id, picked, "sorted by {:?}, pinned {}", order, pin_overdue);
+            }
+        }
+        let visible = rows(&todos, &View::All, &[], Order::Text, false, now);
+        assert_eq!(visible.iter().map(|&i| todos[i].id).collect::<Vec<_>>(), [1, 3, 2, 4]);
+    }
+
+    #[test]
+    fn a_todo_the_view_hides_leaves_the_selected_row_where_it_was() {
+        let todos = vec![todo(1, "apple", None), todo(2, "banana", None)];
+        let now = NaiveDate::from_ymd_opt(2026, 10, 16).unwrap().and_hms_opt(12, 0, 0).unwrap();
+        let visible = rows(&todos, &View::Done, &[], Order::Text, false, now);
+        assert_eq!(row_of(&todos, &visible, Some(2), 5), 0);
+        let visible = rows(&todos, &View::All, &[], Order::Text, false, now);
+        assert_eq!(row_of(&todos, &visible, Some(9), 1), 1);
+        assert_eq!(row_of(&todos, &visible, None, usize::MAX), 1);
+    }
+
+    #[test]
+    fn shift_with_up_or_down_moves_the_due_date_so_read_only_mode_refuses_it() {
+        let key = |code, modifiers| KeyEvent::new(code, modifiers);
+        assert!(changes_todos(key(KeyCode::Up, KeyModifiers::SHIFT)));
+        assert!(changes_todos(key(KeyCode::Down, KeyModifiers::SHIFT)));
+        assert!(changes_todos(key(KeyCode::Char('+'), KeyModifiers::NONE)));
+        assert!(!changes_todos(key(KeyCode::Up, KeyModifiers::NONE)));
+        assert!(!changes_todos(key(KeyCode::Char('>'), KeyModifiers::NONE)));
+    }
+
+    /// (id, text, done) of each todo, to compare refreshed lists by
+    fn summary(todos: &[Todo]) -> Vec<(usize, &str, bool)> {
+        todos.iter().map(|t| (t.id, t.text.as_str(), t.done)).collect()
+    }
+
+    #[test]
+    fn refresh_takes_changes_made_elsewhere_to_rows_not_edited_here() {
+        let synced = vec![todo(1, "apple", None), todo(2, "banana", None), todo(3, "cherry", None)];
+        let mut todos = synced.clone();
+        let mut remote = synced.clone();
+        remote[0].done = true;
+        remote.remove(1);
+        remote.push(todo(4, "date", None));
+        assert!(refresh(&mut todos, &synced, remote));
+        assert_eq!(summary(&todos), [(1, "apple", true), (3, "cherry", false), (4, "date", false)]);
+    }
+
+    #[test]
+    fn refresh_keeps_a_row_edited_here_that_was_also_edited_elsewhere() {
+        let synced = vec![todo(1, "apple", None), todo(2, "banana", None)];
+        let mut todos = synced.clone();
+        todos[0].text = "green apple".to_string();
+        let mut remote = synced.clone();
+        remote[0].done = true;
+        remote[1].text = "ripe banana".to_string();
+        assert!(refresh(&mut todos, &synced, remote));
+        assert_eq!(summary(&todos), [(1, "green apple", false), (2, "ripe banana", false)]);
+    }
+
+    #[test]
+    fn refresh_keeps_unsaved_deletions_and_moves_an_added_todo_off_a_taken_id() {
+        let synced = vec![todo(1, "apple", None), todo(2, "banana", None)];
+        let mut todos = vec![todo(2, "banana", None), todo(3, "mine", None)];
+        let mut remote = synced.clone();
+        remote[0].done = true;
+        remote.push(todo(3, "theirs", None));
+        assert!(refresh(&mut todos, &synced, remote));
+        let summary = summary(&todos);
+        assert_eq!(summary[..2], [(2, "banana", false), (3, "theirs", false)]);
+        assert_eq!(summary[2].1, "mine");
+        assert!(summary[2].0 > 3, "{:?}", summary);
+    }
+
+    #[test]
+    fn refresh_with_nothing_new_changes_nothing() {
+        let synced = vec![todo(1, "apple", None)];
+        let mut todos = synced.clone();
+        todos[0].done = true;
+        assert!(!refresh(&mut todos, &synced, synced.clone()));
+        assert_eq!(summary(&todos), [(1, "apple", true)]);
+    }
+}
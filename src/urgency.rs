@@ -0,0 +1,168 @@
+use crate::{agenda::Item, priority::Priority, Todo};
+use chrono::{DateTime, Local, NaiveDateTime};
+use serde::Deserialize;
+
+/// How far ahead of its deadline a todo starts counting as due soon
+const DUE_HORIZON_DAYS: f64 = 14.0;
+/// How old a todo is when age stops adding to its urgency
+const AGE_CAP_DAYS: f64 = 30.0;
+
+/// How much each part of a todo counts towards its urgency, from `[urgency]` in the config
+#[derive(Deserialize, Debug, Clone, Copy)]
+#[serde(default)]
+pub struct Weights {
+    pub due: f64,
+    pub priority: f64,
+    pub age: f64,
+    pub overdue: f64,
+}
+
+impl Default for Weights {
+    fn default() -> Self {
+        Weights { due: 12.0, priority: 6.0, age: 2.0, overdue: 4.0 }
+    }
+}
+
+/// How urgent a pending todo is at `now`, local time; higher is more urgent and a done
+/// todo scores 0. Each part is scaled to between 0 and 1 and then weighted:
+///
+/// - due: 0 two weeks or more before the deadline, rising evenly to 1 at it
+/// - overdue: 1 once the deadline has passed
+/// - priority: 1 for high, 0.65 for medium and 0.3 for low
+/// - age: 0 when created, rising evenly to 1 at 30 days old
+///
+/// A todo without a due date, priority or creation time gets 0 for that part, so a
+/// missing field neither raises nor lowers its score
+pub fn score(todo: &Todo, now: NaiveDateTime, weights: &Weights) -> f64 {
+    if todo.done {
+        return 0.0;
+    }
+    let days = |span: chrono::Duration| span.num_seconds() as f64 / 86_400.0;
+    let (due, overdue) = match todo.due() {
+        Some(due) => {
+            let left = days(due.deadline() - now);
+            ((1.0 - left / DUE_HORIZON_DAYS).clamp(0.0, 1.0), if left < 0.0 { 1.0 } else { 0.0 })
+        }
+        None => (0.0, 0.0),
+    };
+    let priority = match todo.priority.as_deref().and_then(Priority::parse) {
+        Some(Priority::High) => 1.0,
+        Some(Priority::Medium) => 0.65,
+        Some(Priority::Low) => 0.3,
+        None => 0.0,
+    };
+    let age = todo
+        .created_at
+        .as_deref()
+        .and_then(|at| DateTime::parse_from_rfc3339(at).ok())
+        .map_or(0.0, |created| (days(now - created.with_timezone(&Local).naive_local()) / AGE_CAP_DAYS).clamp(0.0, 1.0));
+    weights.due * due + weights.overdue * overdue + weights.priority * priority + weights.age * age
+}
+
+/// A score as it is shown and put in JSON, to two decimal places
+pub fn rounded(score: f64) -> f64 {
+    (score * 100.0).round() / 100.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Duration, NaiveDate, TimeZone};
+
+    /// Noon on 1 July 2025
+    fn now() -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2025, 7, 1).unwrap().and_hms_opt(12, 0, 0).unwrap()
+    }
+
+    /// A pending todo due `days` from `now`, at the same time of day
+    fn due_in(days: i64) -> Todo {
+        let due = now() + Duration::days(days);
+        Todo { due_date: Some(due.format("%Y-%m-%d %H:%M").to_string()), ..Default::default() }
+    }
+
+    /// A pending todo created `days` before `now`
+    fn created(days: i64) -> Todo {
+        let at = Local.from_local_datetime(&(now() - Duration::days(days))).unwrap();
+        Todo { created_at: Some(at.to_rfc3339()), ..Default::default() }
+    }
+
+    fn only(part: &str) -> Weights {
+        let mut weights = Weights { due: 0.0, priority: 0.0, age: 0.0, overdue: 0.0 };
+        match part {
+            "due" => weights.due = 1.0,
+            "priority" => weights.priority = 1.0,
+            "age" => weights.age = 1.0,
+            _ => weights.overdue = 1.0,
+        }
+        weights
+    }
+
+    #[test]
+    fn a_todo_without_due_date_priority_or_creation_time_scores_nothing() {
+        assert_eq!(score(&Todo::default(), now(), &Weights::default()), 0.0);
+        let unreadable = Todo { due_date: Some("soon".to_string()), priority: Some("urgent".to_string()), created_at: Some("yesterday".to_string()), ..Default::default() };
+        assert_eq!(score(&unreadable, now(), &Weights::default()), 0.0);
+    }
+
+    #[test]
+    fn due_rises_from_two_weeks_out_to_the_deadline() {
+        let weights = only("due");
+        assert_eq!(score(&due_in(30), now(), &weights), 0.0);
+        assert_eq!(score(&due_in(14), now(), &weights), 0.0);
+        assert_eq!(score(&due_in(7), now(), &weights), 0.5);
+        assert_eq!(score(&due_in(0), now(), &weights), 1.0);
+        assert_eq!(score(&due_in(-3), now(), &weights), 1.0);
+    }
+
+    #[test]
+    fn overdue_counts_only_past_the_deadline() {
+        let weights = only("overdue");
+        assert_eq!(score(&due_in(0), now(), &weights), 0.0);
+        assert_eq!(score(&due_in(-1), now(), &weights), 1.0);
+        // A date without a time is on time until the day ends
+        let today = Todo { due_date: Some("2025-07-01".to_string()), ..Default::default() };
+        assert_eq!(score(&today, now(), &weights), 0.0);
+    }
+
+    #[test]
+    fn priorities_score_high_above_medium_above_low() {
+        let weights = only("priority");
+        let with = |priority: &str| Todo { priority: Some(priority.to_string()), ..Default::default() };
+        assert_eq!(score(&with("high"), now(), &weights), 1.0);
+        assert_eq!(score(&with("medium"), now(), &weights), 0.65);
+        assert_eq!(score(&with("low"), now(), &weights), 0.3);
+    }
+
+    #[test]
+    fn age_rises_to_its_cap_at_thirty_days() {
+        let weights = only("age");
+        assert_eq!(score(&created(0), now(), &weights), 0.0);
+        assert_eq!(score(&created(15), now(), &weights), 0.5);
+        assert_eq!(score(&created(30), now(), &weights), 1.0);
+        assert_eq!(score(&created(300), now(), &weights), 1.0);
+        // Created after `now`, as clock skew between machines can leave it
+        assert_eq!(score(&created(-2), now(), &weights), 0.0);
+    }
+
+    #[test]
+    fn parts_are_weighted_and_added() {
+        let todo = Todo { priority: Some("high".to_string()), ..due_in(-1) };
+        assert_eq!(score(&todo, now(), &Weights::default()), 12.0 + 4.0 + 6.0);
+        let weights = Weights { due: 1.0, priority: 0.5, age: 0.0, overdue: 0.0 };
+        assert_eq!(score(&todo, now(), &weights), 1.5);
+        assert_eq!(score(&todo, now(), &Weights { due: 0.0, priority: 0.0, age: 0.0, overdue: 0.0 }), 0.0);
+    }
+
+    #[test]
+    fn done_todos_score_nothing() {
+        let todo = Todo { done: true, priority: Some("high".to_string()), ..due_in(-1) };
+        assert_eq!(score(&todo, now(), &Weights::default()), 0.0);
+    }
+
+    #[test]
+    fn scores_round_to_two_places() {
+        assert_eq!(rounded(1.234_9), 1.23);
+        assert_eq!(rounded(1.235_1), 1.24);
+        assert_eq!(rounded(0.0), 0.0);
+    }
+}
@@ -0,0 +1,20 @@
+/// A random (version 4) UUID in its usual hyphenated lowercase form, giving a todo an
+/// identity that survives renumbering and copying between stores
+pub fn new_v4() -> String {
+    let mut bytes = [0u8; 16];
+    getrandom::fill(&mut bytes).expect("the operating system has no random number source");
+    bytes[6] = (bytes[6] & 0x0f) | 0x40;
+    bytes[8] = (bytes[8] & 0x3f) | 0x80;
+    let hex: String = bytes.iter().map(|b| format!("{:02x}", b)).collect();
+    format!("{}-{}-{}-{}-{}", &hex[0..8], &hex[8..12], &hex[12..16], &hex[16..20], &hex[20..32])
+}
+
+/// Give every todo without a UUID a fresh one, returning how many were filled in
+pub fn backfill(todos: &mut [crate::Todo]) -> usize {
+    let mut filled = 0;
+    for todo in todos.iter_mut().filter(|t| t.uuid.is_empty()) {
+        todo.uuid = new_v4();
+        filled += 1;
+    }
+    filled
+}
@@ -0,0 +1,96 @@
+use crate::{dates::{self, DateDisplay}, delivery::{self, Notifier}, doctor, messages::{self, Msg}, notify, text, Todo};
+use chrono::Utc;
+use rusqlite::Connection;
+use std::{
+    fs,
+    io::{self, Write},
+    path::Path,
+    thread,
+    time::{Duration, SystemTime},
+};
+
+/// What `watch` does besides printing when a reminder fires
+pub struct Options {
+    pub interval: Duration,
+    /// Ring the terminal bell, for sessions (like SSH) without desktop notifications
+    pub bell: bool,
+    /// The configured notify targets, and reminder_command
+    pub notifiers: Vec<Box<dyn Notifier>>,
+}
+
+/// Check for due reminders every `interval` until interrupted. `load` reads the store
+/// afresh each time so todos added or changed elsewhere are picked up; a tick whose load
+/// fails is skipped rather than treated as an empty list
+pub fn run(mut load: impl FnMut() -> Result<Vec<Todo>, String>, files: &notify::Files, options: &Options) -> ! {
+    messages::say(Msg::Watching { every: options.interval.as_secs() });
+    loop {
+        match load() {
+            Ok(todos) => {
+                doctor::warn_unreadable(&todos);
+                let announced = notify::fire(&todos, files, Utc::now()).and_then(|fired| {
+                    notify::announce_each(fired, files, |todo, at| {
+                        announce(todo, &dates::display_datetime(&at, DateDisplay::default()), options)
+                    })
+                });
+                if let Err(e) = announced {
+                    messages::say(Msg::WatchFailed { what: "check reminders", error: &e });
+                }
+            }
+            Err(e) => messages::say(Msg::WatchFailed { what: "read the todos", error: &e }),
+        }
+        thread::sleep(options.interval);
+    }
+}
+
+/// What `tui --watch` compares from one check to the next to notice another process saving
+/// the JSON file: its modification time and size, or `None` while there is no file
+pub fn file_fingerprint(path: &Path) -> Option<(SystemTime, u64)> {
+    let metadata = fs::metadata(path).ok()?;
+    Some((metadata.modified().ok()?, metadata.len()))
+}
+
+/// The SQLite counterpart: a number that moves on whenever another connection commits
+pub fn data_version(conn: &Connection) -> rusqlite::Result<i64> {
+    conn.query_row("PRAGMA data_version", [], |row| row.get(0))
+}
+
+fn announce(todo: &Todo, at: &str, options: &Options) {
+    messages::say(Msg::ReminderFired { id: todo.id, text: &text::escape(&todo.text), at });
+    if options.bell {
+        print!("\x07");
+        let _ = io::stdout().flush();
+    }
+    delivery::deliver(&options.notifiers, todo, at);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn a_file_rewritten_by_another_process_changes_its_fingerprint() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("todos.json");
+        assert_eq!(file_fingerprint(&path), None);
+        fs::write(&path, "[]").unwrap();
+        let first = file_fingerprint(&path);
+        assert!(first.is_some());
+        assert_eq!(file_fingerprint(&path), first);
+        fs::write(&path, "[{}]").unwrap();
+        assert_ne!(file_fingerprint(&path), first);
+    }
+
+    #[test]
+    fn data_version_moves_on_for_commits_by_other_connections_only() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("todos.db");
+        let ours = Connection::open(&path).unwrap();
+        ours.execute_batch("CREATE TABLE todos (id INTEGER PRIMARY KEY)").unwrap();
+        let seen = data_version(&ours).unwrap();
+        ours.execute("INSERT INTO todos (id) VALUES (1)", []).unwrap();
+        assert_eq!(data_version(&ours).unwrap(), seen);
+        let theirs = Connection::open(&path).unwrap();
+        theirs.execute("INSERT INTO todos (id) VALUES (2)", []).unwrap();
+        assert_ne!(data_version(&ours).unwrap(), seen);
+    }
+}
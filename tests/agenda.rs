@@ -0,0 +1,25 @@
+#![cfg(unix)]
+
+mod common;
+
+use common::Sandbox;
+
+#[test]
+fn week_offsets_stay_within_a_century() {
+    let sandbox = Sandbox::new();
+    sandbox.ok(&["week", "--offset", "-5218"]);
+    sandbox.ok(&["week", "--offset", "5218"]);
+    let run = sandbox.run(&["week", "--offset", "-99999999999"]);
+    assert_eq!(run.code, 2);
+    assert!(run.stderr.contains("-5218..=5218"), "{}", run.stderr);
+}
+
+#[test]
+fn burndown_covers_at_most_ten_years() {
+    let sandbox = Sandbox::new();
+    sandbox.ok(&["add", "pay rent"]);
+    sandbox.ok(&["stats", "--burndown", "--days", "3650", "--output", "json"]);
+    let run = sandbox.run(&["stats", "--burndown", "--days", "4000000000"]);
+    assert_eq!(run.code, 2);
+    assert!(run.stderr.contains("1..=3650"), "{}", run.stderr);
+}
@@ -0,0 +1,71 @@
+#![cfg(unix)]
+
+//! Built-in shorthands and the aliases defined in the config
+
+mod common;
+
+use common::Sandbox;
+
+const ALIASES: &str = "\
+[alias]
+work = \"list --filter 'tag:work and not done' --format {text}\"
+w = \"work\"
+ls = \"list --tree\"
+loop1 = \"loop2\"
+loop2 = \"loop1\"
+";
+
+fn sandbox() -> Sandbox {
+    let sandbox = Sandbox::new();
+    sandbox.config(ALIASES);
+    sandbox
+}
+
+#[test]
+fn shorthands_run_their_commands() {
+    let sandbox = sandbox();
+    assert_eq!(sandbox.ok(&["a", "file report"]).stdout, "✅ Todo 1 added!\n");
+    sandbox.ok(&["a", "buy milk"]);
+    sandbox.ok(&["remind", "1", "2030-01-01", "09:00"]);
+    assert_eq!(sandbox.ok(&["cr", "1"]).stdout, "🗑️ Reminder cleared for todo 1!\n");
+    assert_eq!(sandbox.ok(&["x", "2"]).stdout, "🎉 Todo marked as done!\n");
+    assert_eq!(sandbox.ok(&["ls", "--format", "{id}"]).stdout, "1\n2\n");
+    assert_eq!(sandbox.ok(&["rm", "2", "--force"]).stdout, "🗑️ Deleted todo with id 2\n");
+    assert_eq!(sandbox.ok(&["d", "1", "--force"]).stdout, "🗑️ Deleted todo with id 1\n");
+}
+
+#[test]
+fn a_multi_word_alias_takes_extra_arguments_after_it() {
+    let sandbox = sandbox();
+    for text in ["file report", "buy milk", "plan offsite"] {
+        sandbox.ok(&["add", text]);
+    }
+    sandbox.ok(&["tag", "1", "work"]);
+    sandbox.ok(&["tag", "3", "work"]);
+    assert_eq!(sandbox.ok(&["work"]).stdout, "file report\nplan offsite\n");
+    assert_eq!(sandbox.ok(&["w", "--sort", "text"]).stdout, "file report\nplan offsite\n");
+    assert_eq!(sandbox.ok(&["--sqlite", "work"]).stdout, "");
+    sandbox.ok(&["done", "1"]);
+    assert_eq!(sandbox.ok(&["w", "--tag", "work"]).stdout, "plan offsite\n");
+}
+
+#[test]
+fn an_alias_loop_is_an_error() {
+    let run = sandbox().run(&["loop1"]);
+    assert_eq!(run.code, 1);
+    assert_eq!(run.stderr, "❌ alias loop: loop1 → loop2 → loop1\n");
+}
+
+#[test]
+fn alias_lists_the_configured_aliases() {
+    assert_eq!(
+        sandbox().ok(&["alias"]).stdout,
+        "\
+loop1 = loop2
+loop2 = loop1
+ls = list --tree (ignored, a built-in command)
+w = work
+work = list --filter 'tag:work and not done' --format {text}
+"
+    );
+}
@@ -0,0 +1,147 @@
+#![cfg(unix)]
+
+//! The once-a-day sweeps set up by `auto_archive_done_after` and `auto_trash_empty_after`
+
+mod common;
+
+use chrono::{Duration, SecondsFormat, Utc};
+use common::Sandbox;
+use rusqlite::{params, Connection};
+use std::{fs, path::PathBuf};
+
+/// An RFC 3339 timestamp `age` ago
+fn ago(age: Duration) -> String {
+    (Utc::now() - age).to_rfc3339_opts(SecondsFormat::Secs, true)
+}
+
+/// A sandbox with "old" (#1) done 40 days ago, "recent" (#2) done now and "pending" (#3),
+/// on the database when `sqlite` is set. The sweeps are configured afterwards, so setting
+/// it up doesn't sweep
+fn finished(sqlite: bool) -> (Sandbox, Vec<&'static str>) {
+    let sandbox = Sandbox::new();
+    let flags = if sqlite { vec!["--sqlite"] } else { vec![] };
+    for args in [&["add", "old"][..], &["add", "recent"], &["add", "pending"], &["done", "1"], &["done", "2"]] {
+        sandbox.ok(&[&flags[..], args].concat());
+    }
+    set_completed(&sandbox, sqlite, 1, Duration::days(40));
+    sandbox.config("auto_archive_done_after = \"30d\"\n");
+    (sandbox, flags)
+}
+
+/// Move todo `id`'s completion `age` into the past
+fn set_completed(sandbox: &Sandbox, sqlite: bool, id: usize, age: Duration) {
+    if sqlite {
+        let conn = Connection::open(sandbox.db_path()).unwrap();
+        conn.execute("UPDATE todos SET completed_at = ?1 WHERE id = ?2", params![ago(age), id]).unwrap();
+    } else {
+        let mut store: serde_json::Value = serde_json::from_slice(&fs::read(sandbox.json_path()).unwrap()).unwrap();
+        let todo = store["todos"].as_array_mut().unwrap().iter_mut().find(|t| t["id"] == id).unwrap();
+        todo["completed_at"] = ago(age).into();
+        fs::write(sandbox.json_path(), store.to_string()).unwrap();
+    }
+}
+
+/// The state file that records when the sweep last ran, which each backend keeps apart
+fn state_path(sandbox: &Sandbox, sqlite: bool) -> PathBuf {
+    sandbox.data_dir().join(if sqlite { "todos.last.json" } else { "last.json" })
+}
+
+fn swept_at(sandbox: &Sandbox, sqlite: bool) -> Option<String> {
+    let state: serde_json::Value = serde_json::from_slice(&fs::read(state_path(sandbox, sqlite)).ok()?).ok()?;
+    state["swept_at"].as_str().map(str::to_string)
+}
+
+/// Pretend the sweep last ran `age` ago
+fn set_swept(sandbox: &Sandbox, sqlite: bool, age: Duration) {
+    let path = state_path(sandbox, sqlite);
+    let mut state: serde_json::Value = fs::read(&path).map_or(serde_json::json!({}), |data| serde_json::from_slice(&data).unwrap());
+    state["swept_at"] = ago(age).into();
+    fs::write(path, state.to_string()).unwrap();
+}
+
+fn texts(sandbox: &Sandbox, flags: &[&str]) -> String {
+    sandbox.ok(&[flags, &["list", "--format", "{text}"]].concat()).stdout
+}
+
+#[test]
+fn the_sweep_archives_only_todos_done_long_enough_ago() {
+    for sqlite in [false, true] {
+        let (sandbox, flags) = finished(sqlite);
+        let run = sandbox.ok(&[&flags[..], &["list", "--format", "{text}"]].concat());
+        let label = if sqlite { " (SQLite)" } else { "" };
+        assert_eq!(run.stderr, format!("🗄️ Archived 1 todos done more than 30d ago{}\n", label));
+        assert_eq!(run.stdout, "recent\npending\n");
+        let archive = sandbox.data_dir().join(if sqlite { "todos.archive.json" } else { "archive.json" });
+        let archived: serde_json::Value = serde_json::from_slice(&fs::read(archive).unwrap()).unwrap();
+        assert_eq!(archived.as_array().unwrap().len(), 1);
+        assert_eq!(archived[0]["text"], "old");
+        assert!(archived[0]["archived_at"].is_string());
+        assert!(swept_at(&sandbox, sqlite).is_some());
+    }
+}
+
+#[test]
+fn the_sweep_runs_at_most_once_a_day() {
+    for sqlite in [false, true] {
+        let (sandbox, flags) = finished(sqlite);
+        texts(&sandbox, &flags);
+        set_completed(&sandbox, sqlite, 2, Duration::days(31));
+        let run = sandbox.ok(&[&flags[..], &["list"]].concat());
+        assert!(!run.stderr.contains("Archived"), "{}", run.stderr);
+        assert_eq!(texts(&sandbox, &flags), "recent\npending\n");
+
+        set_swept(&sandbox, sqlite, Duration::hours(23));
+        assert_eq!(texts(&sandbox, &flags), "recent\npending\n");
+        set_swept(&sandbox, sqlite, Duration::hours(25));
+        assert_eq!(texts(&sandbox, &flags), "pending\n");
+    }
+}
+
+#[test]
+fn pending_todos_are_never_archived() {
+    // As a todo reopened after a hand edit might be, pending with an old completion time
+    let (sandbox, _) = finished(false);
+    set_completed(&sandbox, false, 3, Duration::days(400));
+    assert_eq!(texts(&sandbox, &[]), "recent\npending\n");
+}
+
+#[test]
+fn without_the_keys_nothing_is_swept_or_recorded() {
+    for sqlite in [false, true] {
+        let (sandbox, flags) = finished(sqlite);
+        sandbox.config("");
+        assert_eq!(texts(&sandbox, &flags), "old\nrecent\npending\n");
+        assert_eq!(swept_at(&sandbox, sqlite), None);
+    }
+}
+
+#[test]
+fn dry_runs_and_read_only_runs_dont_sweep() {
+    for sqlite in [false, true] {
+        let (sandbox, flags) = finished(sqlite);
+        let before = sandbox.snapshot();
+        sandbox.ok(&[&flags[..], &["--dry-run", "done", "3"]].concat());
+        let run = sandbox.run_with(&[&flags[..], &["list"]].concat(), &[("TODO_READONLY", "1")]);
+        assert_eq!(run.code, 0, "{}", run.stderr);
+        assert!(!run.stderr.contains("Archived"), "{}", run.stderr);
+        assert_eq!(sandbox.snapshot(), before);
+    }
+}
+
+#[test]
+fn the_trash_sweep_empties_what_was_deleted_long_enough_ago() {
+    let sandbox = Sandbox::new();
+    for args in [&["add", "old"][..], &["add", "new"], &["delete", "1", "--force"], &["delete", "2", "--force"]] {
+        sandbox.ok(args);
+    }
+    let trash_path = sandbox.data_dir().join("trash.json");
+    let mut trash: serde_json::Value = serde_json::from_slice(&fs::read(&trash_path).unwrap()).unwrap();
+    trash[0]["deleted_at"] = ago(Duration::days(8)).into();
+    fs::write(&trash_path, trash.to_string()).unwrap();
+    sandbox.config("auto_trash_empty_after = \"1w\"\n");
+    let run = sandbox.ok(&["list"]);
+    assert!(run.stderr.starts_with("🗑️ "), "{}", run.stderr);
+    let trash: serde_json::Value = serde_json::from_slice(&fs::read(&trash_path).unwrap()).unwrap();
+    let left: Vec<&str> = trash.as_array().unwrap().iter().map(|t| t["text"].as_str().unwrap()).collect();
+    assert_eq!(left, ["new"]);
+}
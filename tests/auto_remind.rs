@@ -0,0 +1,80 @@
+#![cfg(unix)]
+
+//! The reminder `due` derives from the `auto_remind` setting
+
+mod common;
+
+use chrono::{Duration, Local, NaiveDateTime};
+use common::Sandbox;
+
+/// The date `days` from today as `due` takes it
+fn from_today(days: i64) -> String {
+    (Local::now().date_naive() + Duration::days(days)).format("%Y-%m-%d").to_string()
+}
+
+/// A sandbox reminding the day before at 09:00, with three todos, on the database when
+/// `sqlite` is set
+fn configured(sqlite: bool) -> (Sandbox, Vec<&'static str>) {
+    let sandbox = Sandbox::new();
+    sandbox.config("auto_remind = \"-1d 09:00\"\n");
+    let flags = if sqlite { vec!["--sqlite"] } else { vec![] };
+    for text in ["file taxes", "renew passport", "water plants"] {
+        sandbox.ok(&[&flags[..], &["add", text]].concat());
+    }
+    (sandbox, flags)
+}
+
+fn reminders(sandbox: &Sandbox, flags: &[&str]) -> String {
+    sandbox.ok(&[flags, &["list", "--format", "{id} {reminder}"]].concat()).stdout
+}
+
+#[test]
+fn due_sets_a_reminder_before_the_due_date_and_says_so() {
+    for sqlite in [false, true] {
+        let (sandbox, flags) = configured(sqlite);
+        let due = from_today(10);
+        let run = sandbox.ok(&[&flags[..], &["due", "1", &due]].concat());
+        let label = if sqlite { " (SQLite)" } else { "" };
+        assert_eq!(
+            run.stdout,
+            format!("📅 Due date set for todo 1{}!\n⏰ Reminder set for todo 1 at {} 09:00\n", label, from_today(9))
+        );
+        assert_eq!(reminders(&sandbox, &flags), format!("1 {} 09:00\n2 \n3 \n", from_today(9)));
+    }
+}
+
+#[test]
+fn an_existing_reminder_or_no_auto_remind_is_left_alone() {
+    for sqlite in [false, true] {
+        let (sandbox, flags) = configured(sqlite);
+        sandbox.ok(&[&flags[..], &["remind", "1", &from_today(2), "18:00"]].concat());
+        sandbox.ok(&[&flags[..], &["due", "1", &from_today(10)]].concat());
+        let run = sandbox.ok(&[&flags[..], &["due", "2", &from_today(10), "--no-auto-remind"]].concat());
+        assert!(!run.stdout.contains("Reminder set"), "{}", run.stdout);
+        assert_eq!(reminders(&sandbox, &flags), format!("1 {} 18:00\n2 \n3 \n", from_today(2)));
+    }
+}
+
+#[test]
+fn a_reminder_that_would_have_passed_is_an_hour_away_instead() {
+    let (sandbox, _) = configured(false);
+    let run = sandbox.ok(&["due", "3", &from_today(0)]);
+    assert_eq!(run.stderr, "⚠️ The auto reminder for todo 3 would already have passed; reminding in 1 hour instead\n");
+    let reminders = reminders(&sandbox, &[]);
+    let set = reminders.lines().find_map(|line| line.strip_prefix("3 ")).unwrap();
+    let ahead = NaiveDateTime::parse_from_str(set, "%Y-%m-%d %H:%M").unwrap() - Local::now().naive_local();
+    assert!(ahead > Duration::minutes(58) && ahead <= Duration::minutes(61), "{}", set);
+}
+
+#[test]
+fn no_setting_or_a_bad_one_sets_no_reminder() {
+    let (sandbox, _) = configured(false);
+    sandbox.config("");
+    let run = sandbox.ok(&["due", "1", &from_today(10)]);
+    assert!(!run.stdout.contains("Reminder set"), "{}", run.stdout);
+    sandbox.config("auto_remind = \"-400d 09:00\"\n");
+    let run = sandbox.ok(&["due", "2", &from_today(10)]);
+    assert!(run.stderr.contains("Ignoring auto_remind in "), "{}", run.stderr);
+    assert!(run.stderr.contains("'-400d 09:00' is more than a year before the due date"), "{}", run.stderr);
+    assert_eq!(reminders(&sandbox, &[]), "1 \n2 \n3 \n");
+}
@@ -0,0 +1,79 @@
+#![cfg(unix)]
+
+//! `done --text` and `delete --text`, picking a pending todo by its exact text
+
+mod common;
+
+use common::Sandbox;
+
+/// A sandbox with "deploy v2" twice (#1, #2) and "Deploy v3" (#3), on the database
+/// when `sqlite` is set
+fn deploys(sqlite: bool) -> (Sandbox, Vec<&'static str>) {
+    let sandbox = Sandbox::new();
+    let flags = if sqlite { vec!["--sqlite"] } else { vec![] };
+    let todo = |args: &[&str]| sandbox.ok(&[&flags[..], args].concat());
+    todo(&["add", "deploy v2"]);
+    todo(&["add", "deploy v2"]);
+    todo(&["add", "Deploy v3"]);
+    (sandbox, flags)
+}
+
+#[test]
+fn one_match_is_acted_on() {
+    for sqlite in [false, true] {
+        let (sandbox, flags) = deploys(sqlite);
+        sandbox.ok(&[&flags[..], &["done", "--text", "Deploy v3"]].concat());
+        let run = sandbox.ok(&[&flags[..], &["list", "--format", "{id} {status_icon} {text}"]].concat());
+        assert_eq!(run.stdout, "1   deploy v2\n2   deploy v2\n3 ✓ Deploy v3\n");
+        sandbox.ok(&[&flags[..], &["add", "tag release"]].concat());
+        let run = sandbox.ok(&[&flags[..], &["delete", "--text", "tag release"]].concat());
+        assert!(run.stdout.contains("Deleted todo with id 4"), "{}", run.stdout);
+    }
+}
+
+#[test]
+fn no_match_exits_1_and_changes_nothing() {
+    for sqlite in [false, true] {
+        let (sandbox, flags) = deploys(sqlite);
+        let before = sandbox.snapshot();
+        // Matching is exact and case-sensitive
+        for text in ["deploy", "deploy v3"] {
+            for command in ["done", "delete"] {
+                let run = sandbox.run(&[&flags[..], &[command, "--text", text]].concat());
+                assert_eq!(run.code, 1, "{} {}", command, text);
+                assert_eq!(run.stderr, format!("❌ No pending todo has the text '{}'\n", text));
+            }
+        }
+        assert_eq!(sandbox.snapshot(), before);
+    }
+}
+
+#[test]
+fn several_matches_list_the_candidates_and_exit_2() {
+    for sqlite in [false, true] {
+        let (sandbox, flags) = deploys(sqlite);
+        let before = sandbox.snapshot();
+        for command in ["done", "delete"] {
+            let run = sandbox.run(&[&flags[..], &[command, "--text", "deploy v2"]].concat());
+            assert_eq!(run.code, 2);
+            assert_eq!(
+                run.stderr,
+                "❌ 2 pending todos have the text 'deploy v2'; give an id, or --first for the oldest:\n   #1 deploy v2\n   #2 deploy v2\n"
+            );
+        }
+        assert_eq!(sandbox.snapshot(), before);
+    }
+}
+
+#[test]
+fn first_takes_the_oldest_match_and_done_ones_no_longer_match() {
+    for sqlite in [false, true] {
+        let (sandbox, flags) = deploys(sqlite);
+        sandbox.ok(&[&flags[..], &["done", "--text", "deploy v2", "--first"]].concat());
+        let run = sandbox.ok(&[&flags[..], &["list", "--format", "{id} {status_icon}"]].concat());
+        assert_eq!(run.stdout, "1 ✓\n2  \n3  \n");
+        // Only #2 is still pending with that text, so it matches alone
+        let run = sandbox.ok(&[&flags[..], &["delete", "--text", "deploy v2"]].concat());
+        assert!(run.stdout.contains("Deleted todo with id 2"), "{}", run.stdout);
+    }
+}
@@ -0,0 +1,112 @@
+#![cfg(unix)]
+
+//! Ctrl-C part way through a command, sent as SIGINT to the running binary
+
+mod common;
+
+use chrono::Local;
+use common::Sandbox;
+use std::{
+    fs,
+    path::Path,
+    process::{Command, Stdio},
+    thread,
+    time::Duration,
+};
+
+const IMPORTED: usize = 2_000;
+
+/// Start `todo import` of `IMPORTED` todos on top of "buy milk", interrupt it after
+/// `delay`, and return how it exited and the todos left, as "{id} {text}" lines
+fn interrupted_import(sandbox: &Sandbox, sqlite: bool, delay: Duration) -> (i32, Vec<String>) {
+    let backend: &[&str] = if sqlite { &["--sqlite"] } else { &[] };
+    sandbox.ok(&[backend, &["add", "buy milk"]].concat());
+    let file = sandbox.data_dir().with_file_name("list.md");
+    let list: String = (1..=IMPORTED).map(|n| format!("- [ ] task {}\n", n)).collect();
+    fs::write(&file, list).unwrap();
+
+    let child = sandbox
+        .command(&[backend, &["import", file.to_str().unwrap()]].concat())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    thread::sleep(delay);
+    Command::new("kill").args(["-INT", &child.id().to_string()]).status().unwrap();
+    let output = child.wait_with_output().unwrap();
+    let code = output.status.code().unwrap_or(-1);
+    if code == 130 {
+        assert!(String::from_utf8_lossy(&output.stderr).ends_with("❌ Cancelled\n"));
+    }
+    let todos = sandbox.ok(&[backend, &["list", "--format", "{id} {text}"]].concat()).stdout;
+    (code, todos.lines().map(str::to_string).collect())
+}
+
+/// Whether `todos` is "buy milk" followed by the first of the imported todos in order
+fn imported_in_order(todos: &[String]) -> bool {
+    todos.first().map(String::as_str) == Some("1 buy milk")
+        && todos[1..].iter().enumerate().all(|(i, todo)| *todo == format!("{} task {}", i + 2, i + 1))
+}
+
+fn leftover_files(dir: &Path) -> Vec<String> {
+    fs::read_dir(dir)
+        .unwrap()
+        .flatten()
+        .map(|entry| entry.file_name().to_string_lossy().into_owned())
+        .filter(|name| name.contains(".tmp") || name.starts_with('.'))
+        .collect()
+}
+
+#[test]
+fn an_interrupted_import_leaves_the_json_store_whole() {
+    for delay in [10, 100, 200, 400] {
+        let sandbox = Sandbox::new();
+        let (code, todos) = interrupted_import(&sandbox, false, Duration::from_millis(delay));
+        assert!(code == 130 || code == 0, "exited with {}", code);
+        // The store is saved in one go, so it holds all of the import or none of it
+        assert!(todos.len() == 1 || todos.len() == IMPORTED + 1, "{} todos after {}ms", todos.len(), delay);
+        assert!(imported_in_order(&todos));
+        assert_eq!(leftover_files(&sandbox.data_dir()), Vec::<String>::new());
+    }
+}
+
+#[test]
+fn an_interrupted_import_leaves_the_database_consistent() {
+    for delay in [10, 100, 200, 400] {
+        let sandbox = Sandbox::new();
+        let (code, todos) = interrupted_import(&sandbox, true, Duration::from_millis(delay));
+        assert!(code == 130 || code == 0, "exited with {}", code);
+        // The rows go in under one savepoint, so the import is kept whole or not at all
+        assert!(todos.len() == 1 || todos.len() == IMPORTED + 1, "{} todos after {}ms", todos.len(), delay);
+        assert!(imported_in_order(&todos));
+        let conn = rusqlite::Connection::open(sandbox.db_path()).unwrap();
+        let check: String = conn.query_row("PRAGMA integrity_check", [], |row| row.get(0)).unwrap();
+        assert_eq!(check, "ok");
+    }
+}
+
+#[test]
+fn reminders_left_unannounced_by_ctrl_c_go_off_next_time() {
+    let sandbox = Sandbox::new();
+    sandbox.config("reminder_command = \"sleep 1\"\n");
+    for text in ["call mum", "water plants"] {
+        sandbox.ok(&["add", text]);
+    }
+    let yesterday = (Local::now() - chrono::Duration::days(1)).format("%Y-%m-%d").to_string();
+    for id in ["1", "2"] {
+        sandbox.ok(&["remind", id, &yesterday, "09:00"]);
+    }
+
+    let child = sandbox.command(&["watch"]).stdout(Stdio::piped()).stderr(Stdio::piped()).spawn().unwrap();
+    // Part way through running reminder_command for the first reminder
+    thread::sleep(Duration::from_millis(500));
+    Command::new("kill").args(["-INT", &child.id().to_string()]).status().unwrap();
+    let output = child.wait_with_output().unwrap();
+    assert_eq!(output.status.code(), Some(130));
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("#1 call mum") && !stdout.contains("water plants"), "{}", stdout);
+
+    let run = sandbox.ok(&["notify"]);
+    assert!(!run.stdout.contains("call mum"), "{}", run.stdout);
+    assert!(run.stdout.contains("⏰ #2 water plants"), "{}", run.stdout);
+}
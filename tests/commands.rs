@@ -0,0 +1,364 @@
+#![cfg(unix)]
+
+//! The commands written once over the store, run on both backends to check they say and
+//! do the same
+
+mod common;
+
+use common::{Run, Sandbox};
+use std::fs;
+
+/// A sandbox whose commands all run on one backend
+struct Backend {
+    sandbox: Sandbox,
+    sqlite: bool,
+}
+
+impl Backend {
+    fn both() -> [Backend; 2] {
+        [false, true].map(|sqlite| Backend { sandbox: Sandbox::new(), sqlite })
+    }
+
+    fn args<'a>(&self, args: &[&'a str]) -> Vec<&'a str> {
+        let mut all = if self.sqlite { vec!["--sqlite"] } else { vec![] };
+        all.extend(args);
+        all
+    }
+
+    fn run(&self, args: &[&str]) -> Run {
+        self.sandbox.run(&self.args(args))
+    }
+
+    fn ok(&self, args: &[&str]) -> Run {
+        self.sandbox.ok(&self.args(args))
+    }
+
+    /// A message as this backend words it, which for SQLite ends with its label
+    fn said(&self, message: &str) -> String {
+        let label = if self.sqlite { " (SQLite)" } else { "" };
+        match message.strip_suffix('!') {
+            Some(message) => format!("{}{}!\n", message, label),
+            None => format!("{}{}\n", message, label),
+        }
+    }
+
+    fn list(&self, format: &str) -> String {
+        self.ok(&["list", "--format", format]).stdout
+    }
+}
+
+#[test]
+fn tag() {
+    for backend in Backend::both() {
+        backend.ok(&["add", "buy milk"]);
+        assert_eq!(backend.ok(&["tag", "1", "Home", "#errands"]).stdout, backend.said("🏷️ Tags for todo 1 are now: #home #errands"));
+        assert_eq!(backend.ok(&["tag", "1", "home", "--remove"]).stdout, backend.said("🏷️ Tags for todo 1 are now: #errands"));
+        assert_eq!(backend.list("{tags}"), "#errands\n");
+        let run = backend.run(&["tag", "1", "a b"]);
+        assert_eq!(run.code, 1);
+        assert!(run.stderr.contains("Invalid tag 'a b'"), "{}", run.stderr);
+        let run = backend.run(&["tag", "9", "x"]);
+        assert_eq!(run.code, 1);
+        assert_eq!(run.stderr, "❌ Todo with id 9 not found\n   Nearest existing ids: #1\n");
+    }
+}
+
+#[test]
+fn priority() {
+    for backend in Backend::both() {
+        backend.ok(&["add", "buy milk"]);
+        assert_eq!(backend.ok(&["priority", "1", "high"]).stdout, backend.said("🔺 Priority for todo 1 set to high!"));
+        assert_eq!(backend.list("{priority}"), "high\n");
+        assert_eq!(backend.ok(&["priority", "1", "--clear"]).stdout, backend.said("🔺 Priority cleared for todo 1!"));
+        assert_eq!(backend.list("{priority}"), "\n");
+        assert_eq!(backend.run(&["priority", "9", "low"]).code, 1);
+    }
+}
+
+#[test]
+fn repeat() {
+    for backend in Backend::both() {
+        backend.ok(&["add", "water the plants"]);
+        let run = backend.ok(&["repeat", "1", "mon,wed,fri"]);
+        assert_eq!(
+            run.stdout,
+            backend.said("🔁 Todo 1 repeats mon,wed,fri")
+                + "   It has no due date, so it recurs only once one is set with `todo due`\n"
+        );
+        backend.ok(&["due", "1", "2030-01-01"]);
+        assert_eq!(backend.ok(&["repeat", "1", "daily"]).stdout, backend.said("🔁 Todo 1 repeats daily"));
+        assert_eq!(backend.ok(&["repeat", "1", "--clear"]).stdout, backend.said("🔁 Todo 1 no longer repeats"));
+        assert_eq!(backend.run(&["repeat", "9", "daily"]).code, 1);
+        assert_eq!(backend.run(&["repeat", "1", "hourly"]).code, 2);
+    }
+}
+
+#[test]
+fn recurrence_is_apart_from_the_reminders_repeat() {
+    for backend in Backend::both() {
+        backend.ok(&["add", "water the plants"]);
+        backend.ok(&["due", "1", "2030-01-01"]);
+        backend.ok(&["remind", "1", "2029-12-31", "09:00", "--repeat", "daily"]);
+        backend.ok(&["done", "1"]);
+        assert_eq!(backend.list("{id} [{reminder}] [{reminder_repeat}]"), "1 [] []\n");
+
+        backend.ok(&["add", "pay rent"]);
+        backend.ok(&["due", "2", "2030-01-01"]);
+        backend.ok(&["repeat", "2", "weekly"]);
+        backend.ok(&["remind", "2", "2029-12-31", "09:00", "--repeat", "daily"]);
+        backend.ok(&["done", "2"]);
+        assert_eq!(
+            backend.list("{id} {due_date} [{reminder_repeat}] [{recurrence}]"),
+            "1 2030-01-01 [] []\n2 2030-01-01 [] [weekly]\n3 2030-01-08 [daily] [weekly]\n"
+        );
+    }
+}
+
+#[test]
+fn an_older_store_keeps_its_todos_recurring() {
+    let sandbox = Sandbox::new();
+    fs::create_dir_all(sandbox.data_dir()).unwrap();
+    fs::write(
+        sandbox.json_path(),
+        r#"{"version": 2, "todos": [
+            {"id": 1, "text": "pay rent", "done": false, "due_date": "2030-01-01", "reminder": null, "reminder_repeat": "weekly"},
+            {"id": 2, "text": "stretch", "done": false, "due_date": null, "reminder": null, "reminder_repeat": "daily"}
+        ]}"#,
+    )
+    .unwrap();
+    let list = || sandbox.ok(&["list", "--format", "{id} [{reminder_repeat}] [{recurrence}]"]).stdout;
+    assert_eq!(list(), "1 [weekly] [weekly]\n2 [daily] []\n");
+    sandbox.ok(&["done", "1"]);
+    assert!(fs::read_to_string(sandbox.json_path()).unwrap().contains("\"version\": 3"));
+    assert_eq!(list(), "1 [] [weekly]\n2 [daily] []\n3 [] [weekly]\n");
+
+    sandbox.ok(&["--sqlite", "add", "pay rent"]);
+    let conn = rusqlite::Connection::open(sandbox.db_path()).unwrap();
+    conn.execute_batch(
+        "UPDATE todos SET due_date = '2030-01-01', reminder_repeat = 'weekly', recurrence = NULL;
+         PRAGMA user_version = 11;",
+    )
+    .unwrap();
+    drop(conn);
+    let run = sandbox.ok(&["--sqlite", "list", "--format", "{id} [{reminder_repeat}] [{recurrence}]"]);
+    assert_eq!(run.stdout, "1 [weekly] [weekly]\n");
+}
+
+#[test]
+fn clear_reminder() {
+    for backend in Backend::both() {
+        backend.ok(&["add", "buy milk"]);
+        backend.ok(&["remind", "1", "2030-01-01", "09:00", "--repeat", "daily"]);
+        assert_eq!(backend.ok(&["clear-reminder", "1"]).stdout, backend.said("🗑️ Reminder cleared for todo 1!"));
+        assert_eq!(backend.list("[{reminder}] [{reminder_repeat}]"), "[] []\n");
+        let run = backend.run(&["cr", "9", "buy"]);
+        assert_eq!(run.code, 1);
+        assert_eq!(run.stderr, "❌ Todo with id 9 not found\n   Did you mean:\n     #1 buy milk\n");
+    }
+}
+
+#[test]
+fn import() {
+    for backend in Backend::both() {
+        let file = backend.sandbox.data_dir().with_file_name("list.md");
+        fs::create_dir_all(file.parent().unwrap()).unwrap();
+        fs::write(&file, "- [ ] paint fence\n- [x] buy paint\n").unwrap();
+        backend.ok(&["add", "buy milk"]);
+        let run = backend.ok(&["import", file.to_str().unwrap()]);
+        assert_eq!(run.stdout, backend.said(&format!("📥 Imported 2 todos (1 done) from {}", file.display())));
+        assert_eq!(backend.list("{id} {text} {done}"), "1 buy milk false\n2 paint fence false\n3 buy paint true\n");
+        let run = backend.run(&["import", "/nonexistent/list.md"]);
+        assert_eq!(run.code, 1);
+        assert!(run.stderr.starts_with("❌ Could not read /nonexistent/list.md"), "{}", run.stderr);
+    }
+}
+
+#[test]
+fn backup_and_restore() {
+    for backend in Backend::both() {
+        let bundle = backend.sandbox.data_dir().with_file_name("backup.tar.gz");
+        let bundle = bundle.to_str().unwrap();
+        backend.ok(&["add", "buy milk"]);
+        backend.ok(&["add", "call mum"]);
+        backend.ok(&["backup", bundle]);
+        let run = backend.run(&["restore", bundle]);
+        assert_eq!(run.code, 1);
+        let store = if backend.sqlite { "database" } else { "store" };
+        assert_eq!(run.stderr, format!("❌ The {} already has 2 todos; pass --force to overwrite it\n", store));
+        backend.ok(&["delete", "1", "--force"]);
+        let run = backend.ok(&["restore", bundle, "--force"]);
+        let kind = if backend.sqlite { "sqlite" } else { "json" };
+        assert!(run.stdout.starts_with("🛟 Saved a safety copy to "), "{}", run.stdout);
+        assert!(run.stdout.ends_with(&backend.said(&format!("♻️ Restored 2 todos from a {} backup", kind))), "{}", run.stdout);
+        assert_eq!(backend.list("{id} {text}"), "1 buy milk\n2 call mum\n");
+    }
+}
+
+#[test]
+fn merge() {
+    for backend in Backend::both() {
+        let theirs = backend.sandbox.data_dir().with_file_name("theirs.json");
+        fs::create_dir_all(theirs.parent().unwrap()).unwrap();
+        fs::write(
+            &theirs,
+            r#"[{"id":1,"text":"from the laptop","done":false,"due_date":null,"reminder":null,"created_at":"2024-01-01T00:00:00+00:00"}]"#,
+        )
+        .unwrap();
+        let path = theirs.to_str().unwrap();
+        backend.ok(&["add", "buy milk"]);
+        let run = backend.ok(&["merge", path, "--ours"]);
+        assert_eq!(
+            run.stdout,
+            backend.said(&format!(
+                "🔀 Merged {}: 1 added, 0 already here, 0 changed on both sides (0 kept ours, 0 took theirs)",
+                path
+            ))
+        );
+        assert_eq!(backend.list("{text}"), "buy milk\nfrom the laptop\n");
+        let run = backend.run(&["merge", "/nonexistent/todos.json", "--ours"]);
+        assert_eq!(run.code, 1);
+        assert!(run.stderr.starts_with("❌ Could not read /nonexistent/todos.json"), "{}", run.stderr);
+        let run = backend.run(&["merge", path]);
+        assert!(run.stderr.contains("Pass --ours, --theirs or --newest"), "{}", run.stderr);
+    }
+}
+
+#[test]
+fn doctor() {
+    for backend in Backend::both() {
+        backend.ok(&["add", "buy milk"]);
+        assert_eq!(backend.ok(&["doctor"]).stdout, "✅ No problems found\n");
+    }
+}
+
+#[test]
+fn doctor_finds_unreadable_dates() {
+    let sandbox = Sandbox::new();
+    sandbox.ok(&["add", "buy milk"]);
+    let store = fs::read_to_string(sandbox.json_path()).unwrap();
+    fs::write(sandbox.json_path(), store.replacen("\"due_date\": null", "\"due_date\": \"garbage\"", 1)).unwrap();
+    let run = sandbox.run(&["doctor"]);
+    assert_eq!(run.code, 1);
+    assert_eq!(run.stdout, "⚠️ #1: due date 'garbage' is not a date (fix: clear it)\n");
+    assert_eq!(run.stderr, "❌ 1 problem(s) found\n");
+}
+
+#[test]
+fn doctor_fix_saves_the_fixes() {
+    let sandbox = Sandbox::new();
+    sandbox.ok(&["add", "buy milk"]);
+    let store = fs::read_to_string(sandbox.json_path()).unwrap();
+    fs::write(sandbox.json_path(), store.replacen("\"priority\": null", "\"priority\": \"urgent\"", 1)).unwrap();
+    let run = sandbox.run(&["doctor", "--fix"]);
+    assert_eq!(run.code, 1);
+    assert!(run.stdout.contains("🔧 Fixed #1"), "{}", run.stdout);
+    assert!(!fs::read_to_string(sandbox.json_path()).unwrap().contains("urgent"));
+    assert_eq!(sandbox.ok(&["doctor"]).stdout, "✅ No problems found\n");
+}
+
+#[test]
+fn uuids_are_filled_in_once() {
+    let sandbox = Sandbox::new();
+    fs::create_dir_all(sandbox.data_dir()).unwrap();
+    fs::write(
+        sandbox.json_path(),
+        r#"{"version": 3, "todos": [{"id": 1, "text": "buy milk", "done": false, "due_date": null, "reminder": null}]}"#,
+    )
+    .unwrap();
+    let uuid = sandbox.ok(&["list", "--format", "{uuid}"]).stdout;
+    assert_eq!(uuid.trim().len(), 36);
+    assert!(fs::read_to_string(sandbox.json_path()).unwrap().contains(uuid.trim()));
+    let saved = sandbox.snapshot();
+    assert_eq!(sandbox.ok(&["list", "--format", "{uuid}"]).stdout, uuid);
+    assert_eq!(sandbox.snapshot(), saved);
+    sandbox.ok(&["add", "water the plants"]);
+    assert!(sandbox.ok(&["list", "--format", "{uuid}"]).stdout.starts_with(&uuid));
+
+    sandbox.ok(&["--sqlite", "add", "buy milk"]);
+    let conn = rusqlite::Connection::open(sandbox.db_path()).unwrap();
+    conn.execute_batch("UPDATE todos SET uuid = NULL; PRAGMA user_version = 11;").unwrap();
+    drop(conn);
+    let uuid = sandbox.ok(&["--sqlite", "list", "--format", "{uuid}"]).stdout;
+    assert_eq!(uuid.trim().len(), 36);
+    let conn = rusqlite::Connection::open(sandbox.db_path()).unwrap();
+    let stored: String = conn.query_row("SELECT uuid FROM todos WHERE id = 1", [], |row| row.get(0)).unwrap();
+    drop(conn);
+    assert_eq!(stored, uuid.trim());
+    let saved = sandbox.snapshot();
+    assert_eq!(sandbox.ok(&["--sqlite", "list", "--format", "{uuid}"]).stdout, uuid);
+    assert_eq!(sandbox.snapshot(), saved);
+}
+
+#[test]
+fn saved_filters_take_dates_relative_to_today() {
+    for backend in Backend::both() {
+        backend.ok(&["add", "pay rent due:tomorrow"]);
+        backend.ok(&["add", "renew passport due:30d"]);
+        backend.ok(&["add", "file taxes due:yesterday"]);
+        backend.ok(&["done", "3"]);
+        backend.ok(&["filter", "save", "urgent", "due<+3d and not done"]);
+        assert_eq!(backend.ok(&["list", "urgent", "--format", "{text}"]).stdout, "pay rent\n");
+        assert_eq!(backend.ok(&["list", "--filter", "due>=today", "--format", "{text}"]).stdout, "pay rent\nrenew passport\n");
+    }
+}
+
+#[test]
+fn get() {
+    for backend in Backend::both() {
+        backend.ok(&["add", "buy milk"]);
+        backend.ok(&["tag", "1", "errands", "home"]);
+        assert_eq!(backend.ok(&["get", "1", "text"]).stdout, "buy milk\n");
+        assert_eq!(backend.ok(&["get", "1", "DONE"]).stdout, "false\n");
+        assert_eq!(backend.ok(&["get", "1", "tags"]).stdout, "errands\nhome\n");
+        let run = backend.run(&["get", "1", "due"]);
+        assert_eq!((run.code, run.stdout.as_str(), run.stderr.as_str()), (2, "", ""));
+        let run = backend.run(&["get", "1", "txt"]);
+        assert_eq!(run.code, 2);
+        assert_eq!(run.stderr, "❌ Unknown field 'txt'; did you mean 'text'?\n");
+        let run = backend.run(&["get", "9", "text"]);
+        assert_eq!(run.code, 1);
+        assert!(run.stderr.starts_with("❌ Todo with id 9 not found"), "{}", run.stderr);
+    }
+}
+
+#[test]
+fn piped_list_keeps_long_text_whole() {
+    let text = "call the plumber about the leak under the sink before the weekend, and ask about the boiler too";
+    for backend in Backend::both() {
+        backend.ok(&["add", text]);
+        let listed = backend.ok(&["list"]).stdout;
+        assert!(listed.contains(&format!("1: {} (Due: No due date", text)), "{}", listed);
+        assert!(!listed.contains('…'), "{}", listed);
+        let listed = backend.ok(&["list", "--wrap"]).stdout;
+        assert!(listed.contains(text), "{}", listed);
+    }
+}
+
+#[test]
+fn agenda_views_read_the_same_on_both_backends() {
+    let views: [&[&str]; 5] = [&["today"], &["week"], &["cal"], &["search", "tag:work"], &["stats"]];
+    let [json, sqlite] = Backend::both().map(|backend| {
+        backend.ok(&["add", "file the report"]);
+        backend.ok(&["add", "water the plants"]);
+        backend.ok(&["tag", "1", "work"]);
+        backend.ok(&["due", "1", "today"]);
+        views.map(|view| backend.ok(view).stdout)
+    });
+    assert_eq!(json, sqlite);
+    let [today, week, cal, search, stats] = json;
+    assert!(today.starts_with("Today:\n") && today.contains("file the report"), "{}", today);
+    assert!(week.contains("(today)\n") && week.contains("file the report"), "{}", week);
+    assert!(cal.contains("(today)\n") && !cal.contains("water the plants"), "{}", cal);
+    assert!(search.contains("file the report") && !search.contains("water the plants"), "{}", search);
+    assert!(stats.contains("2 todos"), "{}", stats);
+}
+
+#[test]
+fn search_rejects_a_malformed_query() {
+    for backend in Backend::both() {
+        let run = backend.run(&["search", "tag:"]);
+        assert_eq!(run.code, 1);
+        assert!(run.stdout.is_empty(), "{}", run.stdout);
+        assert!(!run.stderr.is_empty());
+    }
+}
@@ -0,0 +1,132 @@
+//! Runs the built `todo` binary against a store of its own, with the data and config
+//! directories in a temporary directory that is removed afterwards. Windows has no
+//! environment variable that moves them, so the suites using this only run on Unix
+
+#![allow(dead_code)]
+
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+};
+use tempfile::TempDir;
+
+pub struct Sandbox {
+    dir: TempDir,
+}
+
+/// What one run of `todo` printed and how it exited
+pub struct Run {
+    pub code: i32,
+    pub stdout: String,
+    pub stderr: String,
+}
+
+impl Sandbox {
+    pub fn new() -> Sandbox {
+        Sandbox { dir: TempDir::new().expect("temporary directory") }
+    }
+
+    /// `todo` with `args`, not yet started, reading nothing from stdin
+    pub fn command(&self, args: &[&str]) -> Command {
+        let mut command = Command::new(env!("CARGO_BIN_EXE_todo"));
+        command
+            .args(args)
+            .env("HOME", self.dir.path())
+            .env("XDG_DATA_HOME", self.dir.path().join("data"))
+            .env("XDG_CONFIG_HOME", self.dir.path().join("config"))
+            .env_remove("TODO_READONLY")
+            .env_remove("TODO_PROFILE")
+            .env_remove("EDITOR")
+            .stdin(Stdio::null());
+        command
+    }
+
+    pub fn run(&self, args: &[&str]) -> Run {
+        self.run_with(args, &[])
+    }
+
+    /// `run` with the environment variables `env` set too
+    pub fn run_with(&self, args: &[&str], env: &[(&str, &str)]) -> Run {
+        let output = self.command(args).envs(env.iter().copied()).output().expect("run todo");
+        Run {
+            code: output.status.code().unwrap_or(-1),
+            stdout: String::from_utf8_lossy(&output.stdout).into_owned(),
+            stderr: String::from_utf8_lossy(&output.stderr).into_owned(),
+        }
+    }
+
+    /// `run`, failing the test unless it exits 0
+    pub fn ok(&self, args: &[&str]) -> Run {
+        let run = self.run(args);
+        assert_eq!(run.code, 0, "todo {:?} failed:\n{}{}", args, run.stdout, run.stderr);
+        run
+    }
+
+    /// The home directory `todo` runs with
+    pub fn home(&self) -> &Path {
+        self.dir.path()
+    }
+
+    /// Where the store and the files beside it live
+    pub fn data_dir(&self) -> PathBuf {
+        if cfg!(target_os = "macos") {
+            self.dir.path().join("Library/Application Support/todo")
+        } else {
+            self.dir.path().join("data").join("todo")
+        }
+    }
+
+    pub fn json_path(&self) -> PathBuf {
+        self.data_dir().join("todos.json")
+    }
+
+    pub fn db_path(&self) -> PathBuf {
+        self.data_dir().join("todos.db")
+    }
+
+    /// An executable shell script called `name` running `body`, for standing in for an
+    /// editor or other program `todo` starts
+    pub fn script(&self, name: &str, body: &str) -> PathBuf {
+        use std::os::unix::fs::PermissionsExt;
+        let path = self.dir.path().join(name);
+        fs::write(&path, format!("#!/bin/sh\n{}\n", body)).expect("script");
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o755)).expect("script permissions");
+        path
+    }
+
+    /// Every file in the sandbox with its contents, to compare before and after a command
+    /// that must leave them alone. SQLite's shared-memory index and an empty write-ahead
+    /// log are left out, as a connection that exits without closing leaves them behind
+    pub fn snapshot(&self) -> BTreeMap<PathBuf, Vec<u8>> {
+        fn walk(dir: &Path, files: &mut BTreeMap<PathBuf, Vec<u8>>) {
+            for entry in fs::read_dir(dir).into_iter().flatten().flatten() {
+                let path = entry.path();
+                let name = path.to_string_lossy();
+                if path.is_dir() {
+                    walk(&path, files);
+                } else if !name.ends_with("-shm") {
+                    let contents = fs::read(&path).unwrap_or_default();
+                    if !(name.ends_with("-wal") && contents.is_empty()) {
+                        files.insert(path.clone(), contents);
+                    }
+                }
+            }
+        }
+        let mut files = BTreeMap::new();
+        walk(self.dir.path(), &mut files);
+        files
+    }
+
+    /// Replace the config file with `toml`
+    pub fn config(&self, toml: &str) {
+        let dir = if cfg!(target_os = "macos") {
+            self.data_dir()
+        } else {
+            self.dir.path().join("config").join("todo")
+        };
+        fs::create_dir_all(&dir).expect("config directory");
+        fs::write(dir.join("config.toml"), toml).expect("config file");
+    }
+}
@@ -0,0 +1,68 @@
+#![cfg(unix)]
+
+//! Commands run at the same moment against one store, as cron jobs and a shell can
+
+mod common;
+
+use chrono::{Duration, Local};
+use common::Sandbox;
+use std::{collections::BTreeSet, process::Output, thread};
+
+/// Run `todo args` `count` times at once, returning what each run printed
+fn at_once(sandbox: &Sandbox, args: &[&str], count: usize) -> Vec<Output> {
+    thread::scope(|scope| {
+        let runs: Vec<_> = (0..count).map(|_| scope.spawn(|| sandbox.command(args).output().expect("run todo"))).collect();
+        runs.into_iter().map(|run| run.join().unwrap()).collect()
+    })
+}
+
+/// Add a todo `count` times at once, checking every add landed under an id of its own
+/// and that each printed the id it was saved under
+fn adds_all_land(sandbox: &Sandbox, backend: &[&str]) {
+    let count = 16;
+    let mut list = backend.to_vec();
+    list.extend(["list", "--format", "{id}"]);
+    let ids = |sandbox: &Sandbox| -> BTreeSet<String> { sandbox.ok(&list).stdout.lines().map(str::to_string).collect() };
+    let existing = ids(sandbox);
+    let mut args = backend.to_vec();
+    args.extend(["add", "buy milk", "--print-id"]);
+    let mut printed = BTreeSet::new();
+    for run in at_once(sandbox, &args, count) {
+        assert!(run.status.success(), "{}", String::from_utf8_lossy(&run.stderr));
+        printed.insert(String::from_utf8_lossy(&run.stdout).trim().to_string());
+    }
+    let added: BTreeSet<String> = ids(sandbox).difference(&existing).cloned().collect();
+    assert_eq!(added.len(), count);
+    assert_eq!(printed, added);
+}
+
+#[test]
+fn adds_at_once_all_land_in_the_json_store() {
+    adds_all_land(&Sandbox::new(), &[]);
+}
+
+#[test]
+fn adds_at_once_all_land_in_the_jsonl_store() {
+    let sandbox = Sandbox::new();
+    sandbox.config("json_format = \"jsonl\"\n");
+    sandbox.ok(&["add", "call mum"]);
+    adds_all_land(&sandbox, &[]);
+}
+
+#[test]
+fn adds_at_once_all_land_in_the_database() {
+    adds_all_land(&Sandbox::new(), &["--sqlite"]);
+}
+
+#[test]
+fn overlapping_notify_runs_announce_a_reminder_once() {
+    let sandbox = Sandbox::new();
+    sandbox.ok(&["add", "buy milk"]);
+    let due = Local::now() - Duration::minutes(5);
+    sandbox.ok(&["remind", "1", &due.format("%Y-%m-%d").to_string(), &due.format("%H:%M").to_string()]);
+    let announced: usize = at_once(&sandbox, &["notify"], 8)
+        .iter()
+        .map(|run| String::from_utf8_lossy(&run.stdout).matches("⏰ #1 buy milk").count())
+        .sum();
+    assert_eq!(announced, 1);
+}
@@ -0,0 +1,25 @@
+#![cfg(unix)]
+
+mod common;
+
+use common::Sandbox;
+
+#[test]
+fn an_unreadable_config_is_reported_once() {
+    let sandbox = Sandbox::new();
+    sandbox.config("busy_timeout_ms = [\n");
+    sandbox.ok(&["add", "buy milk"]);
+    let run = sandbox.ok(&["list"]);
+    assert_eq!(run.stderr.matches("⚠️ Ignoring").count(), 1, "{}", run.stderr);
+    let run = sandbox.ok(&["--sqlite", "list"]);
+    assert_eq!(run.stderr.matches("⚠️ Ignoring").count(), 1, "{}", run.stderr);
+}
+
+#[test]
+fn a_setting_of_the_wrong_type_is_reported_once() {
+    let sandbox = Sandbox::new();
+    sandbox.config("emoji = \"sometimes\"\n");
+    let run = sandbox.ok(&["add", "buy milk"]);
+    assert_eq!(run.stderr.matches("⚠️ Ignoring").count(), 1, "{}", run.stderr);
+    assert_eq!(run.stdout, "✅ Todo 1 added!\n");
+}
@@ -0,0 +1,60 @@
+#![cfg(unix)]
+
+mod common;
+
+use common::Sandbox;
+
+#[test]
+fn due_fails_on_a_date_that_does_not_exist() {
+    let sandbox = Sandbox::new();
+    sandbox.ok(&["add", "pay rent"]);
+    let run = sandbox.run(&["due", "1", "2024-02-30"]);
+    assert_eq!(run.code, 1);
+    assert!(run.stderr.contains("Invalid date"), "{}", run.stderr);
+    assert!(!sandbox.ok(&["show", "1"]).stdout.contains("2024"));
+}
+
+#[test]
+fn due_fails_on_a_span_past_the_calendar() {
+    let sandbox = Sandbox::new();
+    sandbox.ok(&["add", "pay rent"]);
+    assert_eq!(sandbox.run(&["due", "1", "99999999999d"]).code, 1);
+    let run = sandbox.ok(&["add", "call mum due:99999999999d"]);
+    assert!(run.stderr.contains("is not a date"), "{}", run.stderr);
+}
+
+#[test]
+fn due_fails_outside_the_date_window() {
+    let sandbox = Sandbox::new();
+    sandbox.ok(&["add", "pay rent"]);
+    let run = sandbox.run(&["due", "1", "0205-07-01"]);
+    assert_eq!(run.code, 1);
+    assert!(run.stderr.contains("got 0205-07-01"), "{}", run.stderr);
+}
+
+#[test]
+fn remind_fails_on_a_time_that_does_not_exist() {
+    let sandbox = Sandbox::new();
+    sandbox.ok(&["add", "pay rent"]);
+    assert_eq!(sandbox.run(&["remind", "1", "2024-03-01", "25:00"]).code, 1);
+}
+
+#[test]
+fn spans_past_the_calendar_are_refused() {
+    let sandbox = Sandbox::new();
+    sandbox.ok(&["add", "pay rent"]);
+    let run = sandbox.run(&["list", "--stale", "99999999999d"]);
+    assert_eq!(run.code, 2);
+    assert!(run.stderr.contains("more than 10000 years"), "{}", run.stderr);
+    assert!(!sandbox.ok(&["list", "--stale", "9999w"]).stdout.contains("pay rent"));
+}
+
+#[test]
+fn changed_since_refuses_spans_past_the_calendar() {
+    let sandbox = Sandbox::new();
+    sandbox.ok(&["add", "pay rent"]);
+    assert!(sandbox.ok(&["list", "--changed-since", "2d"]).stdout.contains("pay rent"));
+    let run = sandbox.run(&["list", "--changed-since", "99999999999d"]);
+    assert_eq!(run.code, 2);
+    assert!(run.stderr.contains("more than 10000 years"), "{}", run.stderr);
+}
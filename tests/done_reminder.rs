@@ -0,0 +1,80 @@
+#![cfg(unix)]
+
+//! `done` cancelling the reminder of the todo it completes, unless `--keep-reminder`
+
+mod common;
+
+use chrono::{Duration, Local};
+use common::Sandbox;
+use std::{collections::BTreeMap, fs};
+
+/// The date `days` from today as `remind` takes it
+fn from_today(days: i64) -> String {
+    (Local::now().date_naive() + Duration::days(days)).format("%Y-%m-%d").to_string()
+}
+
+/// A sandbox with "call mum" and "water plants" both reminded at 09:00 `days` from today,
+/// on the database when `sqlite` is set
+fn reminded(sqlite: bool, days: i64) -> (Sandbox, Vec<&'static str>) {
+    let sandbox = Sandbox::new();
+    let flags = if sqlite { vec!["--sqlite"] } else { vec![] };
+    for text in ["call mum", "water plants"] {
+        sandbox.ok(&[&flags[..], &["add", text]].concat());
+    }
+    for id in ["1", "2"] {
+        sandbox.ok(&[&flags[..], &["remind", id, &from_today(days), "09:00"]].concat());
+    }
+    (sandbox, flags)
+}
+
+fn reminders(sandbox: &Sandbox, flags: &[&str]) -> String {
+    sandbox.ok(&[flags, &["list", "--format", "{id} {reminder}"]].concat()).stdout
+}
+
+#[test]
+fn a_done_todo_is_no_longer_listed_by_notify() {
+    for sqlite in [false, true] {
+        let (sandbox, flags) = reminded(sqlite, -1);
+        let run = sandbox.ok(&[&flags[..], &["notify", "--dry-run"]].concat());
+        assert!(run.stdout.contains("⏰ #1 call mum") && run.stdout.contains("⏰ #2 water plants"), "{}", run.stdout);
+
+        sandbox.ok(&[&flags[..], &["done", "1"]].concat());
+        let run = sandbox.ok(&[&flags[..], &["notify", "--dry-run"]].concat());
+        assert!(!run.stdout.contains("call mum"), "{}", run.stdout);
+        assert!(run.stdout.contains("⏰ #2 water plants"), "{}", run.stdout);
+        assert_eq!(reminders(&sandbox, &flags), format!("1 \n2 {} 09:00\n", from_today(-1)));
+    }
+}
+
+#[test]
+fn done_forgets_what_notify_announced_of_the_reminder() {
+    for sqlite in [false, true] {
+        let (sandbox, flags) = reminded(sqlite, -1);
+        sandbox.ok(&[&flags[..], &["notify"]].concat());
+        sandbox.ok(&[&flags[..], &["done", "1"]].concat());
+
+        let markers = if sqlite { sandbox.db_path().with_extension("notified.json") } else { sandbox.data_dir().join("notified.json") };
+        let markers: BTreeMap<String, String> = serde_json::from_str(&fs::read_to_string(markers).unwrap()).unwrap();
+        assert_eq!(markers.keys().collect::<Vec<_>>(), ["2"]);
+    }
+}
+
+#[test]
+fn a_reminder_still_to_go_off_is_reported_cancelled() {
+    for sqlite in [false, true] {
+        let (sandbox, flags) = reminded(sqlite, 3);
+        let run = sandbox.ok(&[&flags[..], &["done", "1"]].concat());
+        assert!(run.stdout.contains(&format!("🔕 Reminder for {} 09:00 cancelled", from_today(3))), "{}", run.stdout);
+        assert_eq!(reminders(&sandbox, &flags), format!("1 \n2 {} 09:00\n", from_today(3)));
+    }
+}
+
+#[test]
+fn keep_reminder_leaves_the_reminder_in_place() {
+    for sqlite in [false, true] {
+        let (sandbox, flags) = reminded(sqlite, 3);
+        let run = sandbox.ok(&[&flags[..], &["done", "1", "--keep-reminder"]].concat());
+        assert!(!run.stdout.contains("cancelled"), "{}", run.stdout);
+        assert_eq!(reminders(&sandbox, &flags), format!("1 {0} 09:00\n2 {0} 09:00\n", from_today(3)));
+    }
+}
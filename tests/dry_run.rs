@@ -0,0 +1,121 @@
+#![cfg(unix)]
+
+mod common;
+
+use common::Sandbox;
+
+/// Run `args` as a dry run and in read-only mode, checking neither touches a file and that
+/// the dry run says what it `would` do, and nothing else
+fn held_back(sandbox: &Sandbox, args: &[&str], would: &str) {
+    let files = sandbox.snapshot();
+    let mut dry = vec!["--dry-run"];
+    dry.extend(args);
+    let run = sandbox.ok(&dry);
+    assert!(run.stdout.contains(would), "todo {:?} said:\n{}{}", dry, run.stdout, run.stderr);
+    let mut lines = run.stdout.lines();
+    assert_eq!(lines.next(), Some("🧪 Dry run, nothing was saved:"), "todo {:?}", dry);
+    assert!(lines.all(|line| line.starts_with("  would ")), "todo {:?} said:\n{}", dry, run.stdout);
+    assert!(sandbox.snapshot() == files, "todo {:?} changed a file", dry);
+
+    let run = sandbox.run_with(args, &[("TODO_READONLY", "1")]);
+    assert_eq!(run.code, 1, "todo {:?} in read-only mode:\n{}{}", args, run.stdout, run.stderr);
+    assert!(run.stderr.contains("read-only"), "{}", run.stderr);
+    assert!(sandbox.snapshot() == files, "todo {:?} changed a file in read-only mode", args);
+}
+
+#[test]
+fn templates() {
+    let sandbox = Sandbox::new();
+    sandbox.ok(&["add", "water the plants"]);
+    held_back(&sandbox, &["template", "save", "plants", "1"], "would save todo 1 as template 'plants'");
+    assert!(sandbox.ok(&["template", "list"]).stdout.contains("No saved templates"));
+
+    sandbox.ok(&["template", "save", "plants", "1"]);
+    held_back(&sandbox, &["template", "delete", "plants"], "would delete template 'plants'");
+    assert!(sandbox.ok(&["template", "list"]).stdout.contains("plants: water the plants"));
+}
+
+#[test]
+fn goals() {
+    let sandbox = Sandbox::new();
+    held_back(&sandbox, &["goal", "set", "gym", "3/week"], "would set the goal for #gym to 3/week");
+    sandbox.ok(&["goal", "set", "gym", "3/week"]);
+    held_back(&sandbox, &["goal", "delete", "gym"], "would delete the goal for #gym");
+    assert_eq!(sandbox.run(&["goal", "delete", "reading"]).code, 1);
+    sandbox.ok(&["goal", "delete", "gym"]);
+}
+
+#[test]
+fn filters() {
+    let sandbox = Sandbox::new();
+    held_back(&sandbox, &["filter", "save", "urgent", "priority:high"], "would save filter 'urgent': priority:high");
+    assert!(sandbox.ok(&["filter", "list"]).stdout.contains("No saved filters"));
+    sandbox.ok(&["filter", "save", "urgent", "priority:high"]);
+    held_back(&sandbox, &["filter", "delete", "urgent"], "would delete filter 'urgent'");
+    sandbox.ok(&["filter", "delete", "urgent"]);
+    assert_eq!(sandbox.run(&["filter", "delete", "urgent"]).code, 1);
+}
+
+#[test]
+fn store_commands() {
+    for backend in [None, Some("--sqlite")] {
+        let sandbox = Sandbox::new();
+        let args = |args: &[&'static str]| backend.into_iter().chain(args.iter().copied()).collect::<Vec<_>>();
+        sandbox.ok(&args(&["add", "buy milk"]));
+        sandbox.ok(&args(&["add", "call mum"]));
+        held_back(&sandbox, &args(&["add", "water the plants"]), "would add #3 'water the plants'");
+        held_back(&sandbox, &args(&["done", "1"]), "would mark #1 'buy milk' done");
+        held_back(&sandbox, &args(&["delete", "2", "--force"]), "would delete #2 'call mum'");
+        held_back(&sandbox, &args(&["tag", "1", "home"]), "would set the tags of #1 'buy milk' to #home");
+        held_back(&sandbox, &args(&["delete", "--all", "--force"]), "would delete #1 'buy milk'");
+        assert_eq!(sandbox.ok(&args(&["list", "--format", "{id} {text} {done} {tags}"])).stdout, "1 buy milk false \n2 call mum false \n");
+    }
+}
+
+#[test]
+fn commands_that_change_nothing_still_report() {
+    let sandbox = Sandbox::new();
+    sandbox.ok(&["add", "buy milk"]);
+    let run = sandbox.ok(&["--dry-run", "list", "--format", "{text}"]);
+    assert_eq!(run.stdout, "buy milk\n");
+    let run = sandbox.run(&["--dry-run", "done", "9"]);
+    assert_eq!(run.code, 1);
+    assert!(run.stderr.starts_with("❌ Todo with id 9 not found"), "{}", run.stderr);
+    let run = sandbox.ok(&["--dry-run", "export"]);
+    assert_eq!(run.stdout, sandbox.ok(&["export"]).stdout);
+    assert_eq!(run.stderr, "🧪 Dry run: no changes\n");
+}
+
+#[test]
+fn an_unreadable_config_stays_read_only() {
+    for config in ["readonly = true\nbackend = \"nope\"\n", "readonly = true\nnot toml\n", "readonly = \"yes\"\n"] {
+        let sandbox = Sandbox::new();
+        sandbox.config(config);
+        let run = sandbox.run(&["add", "buy milk"]);
+        assert_eq!(run.code, 1, "{:?}:\n{}{}", config, run.stdout, run.stderr);
+        assert!(run.stderr.contains("read-only"), "{}", run.stderr);
+    }
+    let sandbox = Sandbox::new();
+    sandbox.config("readonly = false\nnot toml\n");
+    sandbox.ok(&["add", "buy milk"]);
+}
+
+#[test]
+fn notify_lists_due_reminders_without_marking_them_announced() {
+    for backend in [None, Some("--sqlite")] {
+        let sandbox = Sandbox::new();
+        let args = |args: &[&'static str]| backend.into_iter().chain(args.iter().copied()).collect::<Vec<_>>();
+        sandbox.ok(&args(&["add", "call mum"]));
+        let yesterday = (chrono::Local::now() - chrono::Duration::days(1)).format("%Y-%m-%d").to_string();
+        sandbox.ok(&[args(&["remind", "1"]), vec![&yesterday[..], "09:00"]].concat());
+        let files = sandbox.snapshot();
+        for _ in 0..2 {
+            let run = sandbox.ok(&args(&["--dry-run", "notify"]));
+            assert!(run.stdout.contains("⏰ #1 call mum"), "{}", run.stdout);
+        }
+        let run = sandbox.ok(&args(&["--dry-run", "notify", "--reset", "1"]));
+        assert_eq!(run.stdout, "The reminder for todo 1 has not been announced\n");
+        assert!(sandbox.snapshot() == files, "notify --dry-run changed a file");
+        assert!(sandbox.ok(&args(&["notify"])).stdout.contains("⏰ #1 call mum"));
+    }
+}
@@ -0,0 +1,69 @@
+#![cfg(unix)]
+
+//! Due dates with a time of day, and date-only ones counting as due at the end of the day
+
+mod common;
+
+use chrono::{Duration, Local};
+use common::Sandbox;
+
+/// The date `days` from today as `due` takes it
+fn from_today(days: i64) -> String {
+    (Local::now().date_naive() + Duration::days(days)).format("%Y-%m-%d").to_string()
+}
+
+/// A sandbox with a todo due today, one due at the first minute of today and one due
+/// tomorrow at 17:00, on the database when `sqlite` is set
+fn timed(sqlite: bool) -> (Sandbox, Vec<&'static str>) {
+    let sandbox = Sandbox::new();
+    let flags = if sqlite { vec!["--sqlite"] } else { vec![] };
+    let todo = |args: &[&str]| sandbox.ok(&[&flags[..], args].concat());
+    for text in ["today sometime", "just after midnight", "tomorrow evening", "undated"] {
+        todo(&["add", text]);
+    }
+    todo(&["due", "1", "today"]);
+    todo(&["due", "2", "today", "00:00"]);
+    todo(&["due", "3", "tomorrow", "17:00"]);
+    (sandbox, flags)
+}
+
+#[test]
+fn a_time_is_stored_and_shown_after_the_date() {
+    for sqlite in [false, true] {
+        let (sandbox, flags) = timed(sqlite);
+        let listed = sandbox.ok(&[&flags[..], &["list", "--format", "{id} {due_date}"]].concat()).stdout;
+        assert_eq!(listed, format!("1 {0}\n2 {0} 00:00\n3 {1} 17:00\n4 \n", from_today(0), from_today(1)));
+        let listed = sandbox.ok(&[&flags[..], &["list"]].concat()).stdout;
+        assert!(listed.contains(&format!("3: tomorrow evening (Due: {} 17:00, ", from_today(1))), "{}", listed);
+    }
+}
+
+#[test]
+fn a_date_only_todo_due_today_is_not_overdue() {
+    for sqlite in [false, true] {
+        let (sandbox, flags) = timed(sqlite);
+        let today = sandbox.ok(&[&flags[..], &["today"]].concat()).stdout;
+        assert!(today.starts_with("Overdue:\n  [ ] 2: just after midnight"), "{}", today);
+        assert!(today.contains("Today:\n  [ ] 1: today sometime"), "{}", today);
+        assert!(!today.contains("tomorrow evening"), "{}", today);
+        sandbox.ok(&[&flags[..], &["done", "2"]].concat());
+        assert_eq!(sandbox.run(&[&flags[..], &["has", "--overdue"]].concat()).code, 1);
+    }
+}
+
+#[test]
+fn doctor_reads_both_stored_forms() {
+    let (sandbox, _) = timed(false);
+    assert_eq!(sandbox.ok(&["doctor"]).stdout, "✅ No problems found\n");
+}
+
+#[test]
+fn a_time_that_does_not_exist_is_refused() {
+    let (sandbox, _) = timed(false);
+    for time in ["25:00", "17:60", "5pm"] {
+        let run = sandbox.run(&["due", "4", "today", time]);
+        assert_eq!(run.code, 1, "{}", time);
+        assert!(run.stderr.starts_with("❌ Invalid date or time format"), "{}", run.stderr);
+    }
+    assert_eq!(sandbox.ok(&["list", "--format", "{due_date}", "--filter", "text:undated"]).stdout, "\n");
+}
@@ -0,0 +1,127 @@
+#![cfg(unix)]
+
+mod common;
+
+use common::{Run, Sandbox};
+
+/// `todo edit 1` with `editor` as $EDITOR
+fn edit(sandbox: &Sandbox, editor: &str) -> Run {
+    sandbox.run_with(&["edit", "1"], &[("EDITOR", editor)])
+}
+
+fn text(sandbox: &Sandbox) -> String {
+    sandbox.ok(&["get", "1", "text"]).stdout.trim().to_string()
+}
+
+#[test]
+fn an_editor_exiting_non_zero_aborts_the_edit() {
+    let sandbox = Sandbox::new();
+    sandbox.ok(&["add", "buy milk"]);
+    let editor = sandbox.script("editor", "echo 'buy oat milk' > \"$1\"; exit 1");
+    let run = edit(&sandbox, editor.to_str().unwrap());
+    assert_eq!(run.code, 1);
+    assert!(run.stderr.contains("Edit aborted"), "{}", run.stderr);
+    assert_eq!(text(&sandbox), "buy milk");
+}
+
+#[test]
+fn an_editor_can_take_arguments() {
+    let sandbox = Sandbox::new();
+    sandbox.ok(&["add", "buy milk"]);
+    let editor = sandbox.script("editor", "[ \"$1\" = --wait ] || exit 1; echo 'buy oat milk' > \"$2\"");
+    let run = edit(&sandbox, &format!("{} --wait", editor.display()));
+    assert_eq!(run.code, 0, "{}", run.stderr);
+    assert_eq!(text(&sandbox), "buy oat milk");
+}
+
+#[test]
+fn an_unchanged_buffer_saves_nothing() {
+    let sandbox = Sandbox::new();
+    sandbox.ok(&["add", "buy milk"]);
+    let run = edit(&sandbox, "true");
+    assert_eq!(run.code, 0);
+    assert!(run.stdout.contains("Text unchanged"), "{}", run.stdout);
+}
+
+#[test]
+fn an_emptied_buffer_is_refused() {
+    let sandbox = Sandbox::new();
+    sandbox.ok(&["add", "buy milk"]);
+    let editor = sandbox.script("editor", ": > \"$1\"");
+    let run = edit(&sandbox, editor.to_str().unwrap());
+    assert_eq!(run.code, 1);
+    assert_eq!(text(&sandbox), "buy milk");
+}
+
+/// An editor for `edit --full` that saves a copy of each buffer it is shown under `seen/`,
+/// renames the todo and gives it a due date that doesn't exist the first time, and fixes
+/// the date the second
+fn two_pass_editor(sandbox: &Sandbox) -> String {
+    let seen = sandbox.home().join("seen");
+    std::fs::create_dir_all(&seen).unwrap();
+    let body = format!(
+        "n=$(ls '{seen}' | wc -l | tr -d ' ')\n\
+         cp \"$1\" '{seen}'/$n\n\
+         if [ \"$n\" = 0 ]; then\n\
+           sed -i.bak 's/^text = .*/text = \"buy oat milk\"/' \"$1\"\n\
+           echo 'due_date = \"2030-02-30\"' >> \"$1\"\n\
+         else\n\
+           sed -i.bak 's/2030-02-30/2030-02-28 17:00/' \"$1\"\n\
+         fi",
+        seen = seen.display()
+    );
+    sandbox.script("editor", &body).to_str().unwrap().to_string()
+}
+
+#[test]
+fn full_edit_reopens_an_invalid_document_with_the_error_and_the_edits_kept() {
+    for sqlite in [false, true] {
+        let sandbox = Sandbox::new();
+        let flags = if sqlite { vec!["--sqlite"] } else { vec![] };
+        sandbox.ok(&[&flags[..], &["add", "buy milk"]].concat());
+        sandbox.ok(&[&flags[..], &["tag", "1", "home"]].concat());
+        let editor = two_pass_editor(&sandbox);
+        let run = sandbox.run_with(&[&flags[..], &["edit", "1", "--full"]].concat(), &[("EDITOR", &editor)]);
+        assert_eq!(run.code, 0, "{}", run.stderr);
+        assert!(run.stdout.ends_with("   Changed: text, due_date\n"), "{}", run.stdout);
+
+        let seen = |n: usize| std::fs::read_to_string(sandbox.home().join("seen").join(n.to_string())).unwrap();
+        let first = seen(0);
+        assert!(first.contains("\ntext = \"buy milk\"\ndone = false\ntags = [\"home\"]\n"), "{}", first);
+        assert!(!first.contains("\ndue_date"), "{}", first);
+        let second = seen(1);
+        assert!(second.starts_with("# ❌ due_date: '2030-02-30' is not a date, optionally with HH:MM\n"), "{}", second);
+        assert!(second.contains("text = \"buy oat milk\""), "{}", second);
+
+        let run = sandbox.ok(&[&flags[..], &["list", "--format", "{text}|{due_date}|{tags}"]].concat());
+        assert_eq!(run.stdout, "buy oat milk|2030-02-28 17:00|#home\n");
+    }
+}
+
+#[test]
+fn full_edit_sets_every_field_it_shows() {
+    for sqlite in [false, true] {
+        let sandbox = Sandbox::new();
+        let flags = if sqlite { vec!["--sqlite"] } else { vec![] };
+        sandbox.ok(&[&flags[..], &["add", "buy milk"]].concat());
+        sandbox.ok(&[&flags[..], &["tag", "1", "home"]].concat());
+        let document = "text = \"buy milk\"\ndone = true\nreminder = \"2030-03-01 09:00\"\ntags = [\"#Shop\", \"shop\"]\nnotes = \"oat\"";
+        let editor = sandbox.script("editor", &format!("printf '%s\\n' '{}' > \"$1\"", document));
+        let run = sandbox.run_with(&[&flags[..], &["edit", "1", "--full"]].concat(), &[("EDITOR", editor.to_str().unwrap())]);
+        assert_eq!(run.code, 0, "{}", run.stderr);
+        assert!(run.stdout.ends_with("   Changed: done, reminder, tags, notes\n"), "{}", run.stdout);
+        let run = sandbox.ok(&[&flags[..], &["list", "--format", "{done}|{reminder}|{tags}|{completion_note}"]].concat());
+        assert_eq!(run.stdout, "true|2030-03-01 09:00|#shop|oat\n");
+    }
+}
+
+#[test]
+fn an_unchanged_full_edit_saves_nothing() {
+    let sandbox = Sandbox::new();
+    sandbox.ok(&["add", "buy milk"]);
+    let before = sandbox.snapshot();
+    let run = sandbox.run_with(&["edit", "1", "--full"], &[("EDITOR", "true")]);
+    assert_eq!(run.code, 0);
+    assert!(run.stdout.contains("Text unchanged"), "{}", run.stdout);
+    assert_eq!(sandbox.snapshot(), before);
+}
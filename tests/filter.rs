@@ -0,0 +1,78 @@
+#![cfg(unix)]
+
+//! `--filter` expressions, shared by `list`, `search` and `delete`
+
+mod common;
+
+use common::Sandbox;
+
+/// A sandbox with a pending work todo due on 2026-10-20, a high-priority errand and a done
+/// work todo, on the database when `sqlite` is set
+fn todos(sqlite: bool) -> (Sandbox, Vec<&'static str>) {
+    let sandbox = Sandbox::new();
+    let flags = if sqlite { vec!["--sqlite"] } else { vec![] };
+    let todo = |args: &[&str]| sandbox.ok(&[&flags[..], args].concat());
+    todo(&["add", "write report"]);
+    todo(&["add", "buy milk"]);
+    todo(&["add", "plan trip"]);
+    todo(&["tag", "1", "work"]);
+    todo(&["tag", "3", "work"]);
+    todo(&["due", "1", "2026-10-20"]);
+    todo(&["priority", "2", "high"]);
+    todo(&["done", "3"]);
+    (sandbox, flags)
+}
+
+fn ids(sandbox: &Sandbox, flags: &[&str], args: &[&str]) -> String {
+    let run = sandbox.ok(&[flags, args, &["--format", "{id}"]].concat());
+    run.stdout.lines().collect::<Vec<_>>().join(" ")
+}
+
+#[test]
+fn list_takes_and_or_not_and_date_comparisons() {
+    for sqlite in [false, true] {
+        let (sandbox, flags) = todos(sqlite);
+        let cases = [
+            ("tag:work and not done and due<2026-11-01", "1"),
+            ("tag:work", "1 3"),
+            ("done or priority:high", "2 3"),
+            ("not tag:work", "2"),
+            ("text:MILK", "2"),
+            ("due>=2026-10-20 and due<=2026-10-20", "1"),
+            ("due>2026-10-20", ""),
+            ("(tag:work or priority:high) and not done", "1 2"),
+        ];
+        for (expr, expected) in cases {
+            assert_eq!(ids(&sandbox, &flags, &["list", "--filter", expr]), expected, "{}", expr);
+        }
+    }
+}
+
+#[test]
+fn search_and_delete_take_the_same_expressions() {
+    for sqlite in [false, true] {
+        let (sandbox, flags) = todos(sqlite);
+        let found = sandbox.ok(&[&flags[..], &["search", "text:milk", "or", "tag:work"]].concat()).stdout;
+        assert_eq!(found.lines().count(), 3, "{}", found);
+        sandbox.ok(&[&flags[..], &["delete", "--filter", "tag:work and done", "--force"]].concat());
+        assert_eq!(ids(&sandbox, &flags, &["list"]), "1 2");
+    }
+}
+
+#[test]
+fn a_malformed_expression_points_at_the_offending_token() {
+    let (sandbox, _) = todos(false);
+    let run = sandbox.run(&["list", "--filter", "tag:work andd done"]);
+    assert_eq!(run.code, 1);
+    assert_eq!(
+        run.stderr,
+        "❌ Invalid filter: expected 'and', 'or' or the end of the expression, found 'andd'\n    tag:work andd done\n             ^\n"
+    );
+    let run = sandbox.run(&["list", "--filter", "(done or tag:work"]);
+    assert_eq!(run.code, 1);
+    assert!(run.stderr.contains("expected ')'"), "{}", run.stderr);
+    assert!(run.stderr.ends_with("    (done or tag:work\n                     ^\n"), "{}", run.stderr);
+    let before = sandbox.snapshot();
+    assert_eq!(sandbox.run(&["delete", "--filter", "due<soon", "--force"]).code, 1);
+    assert_eq!(sandbox.snapshot(), before);
+}
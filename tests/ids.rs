@@ -0,0 +1,71 @@
+#![cfg(unix)]
+
+//! Ids padded to a common width in lists, and taken as `#42` or `42:` as lists print them
+
+mod common;
+
+use common::Sandbox;
+use rusqlite::{params, Connection};
+
+/// A sandbox with "buy milk" as #7, "call mum" as #42 and "pay rent" as #120, renumbered
+/// in the store as if many todos had come and gone, on the database when `sqlite` is set
+fn renumbered(sqlite: bool) -> (Sandbox, Vec<&'static str>) {
+    let sandbox = Sandbox::new();
+    let flags = if sqlite { vec!["--sqlite"] } else { vec![] };
+    let ids = [7, 42, 120];
+    for text in ["buy milk", "call mum", "pay rent"] {
+        sandbox.ok(&[&flags[..], &["add", text]].concat());
+    }
+    if sqlite {
+        let conn = Connection::open(sandbox.db_path()).unwrap();
+        for (old, new) in ids.iter().enumerate().rev() {
+            conn.execute("UPDATE todos SET id = ?1 WHERE id = ?2", params![new, old + 1]).unwrap();
+        }
+    } else {
+        let mut store: serde_json::Value = serde_json::from_slice(&std::fs::read(sandbox.json_path()).unwrap()).unwrap();
+        for (todo, id) in store["todos"].as_array_mut().unwrap().iter_mut().zip(ids) {
+            todo["id"] = id.into();
+        }
+        std::fs::write(sandbox.json_path(), store.to_string()).unwrap();
+    }
+    (sandbox, flags)
+}
+
+#[test]
+fn ids_are_right_aligned_to_the_widest() {
+    for sqlite in [false, true] {
+        let (sandbox, flags) = renumbered(sqlite);
+        let stdout = sandbox.ok(&[&flags[..], &["list"]].concat()).stdout;
+        let starts: Vec<&str> = stdout.lines().map(|line| &line[..9]).collect();
+        assert_eq!(starts, ["[ ]   7: ", "[ ]  42: ", "[ ] 120: "]);
+    }
+}
+
+#[test]
+fn id_width_in_the_config_sets_the_narrowest_column() {
+    let (sandbox, _) = renumbered(false);
+    sandbox.config("id_width = 5\n");
+    let stdout = sandbox.ok(&["list"]).stdout;
+    assert!(stdout.starts_with("[ ]     7: buy milk "), "{}", stdout);
+    assert!(stdout.contains("\n[ ]   120: pay rent "), "{}", stdout);
+}
+
+#[test]
+fn ids_copied_from_a_list_are_taken_as_they_are() {
+    for sqlite in [false, true] {
+        let (sandbox, flags) = renumbered(sqlite);
+        sandbox.ok(&[&flags[..], &["done", "#42"]].concat());
+        sandbox.ok(&[&flags[..], &["priority", "120:", "high"]].concat());
+        sandbox.ok(&[&flags[..], &["delete", " #7: ", "--force"]].concat());
+        let run = sandbox.ok(&[&flags[..], &["list", "--format", "{id} {done} {priority}"]].concat());
+        assert_eq!(run.stdout, "42 true \n120 false high\n");
+    }
+}
+
+#[test]
+fn a_malformed_id_is_refused_by_name() {
+    let (sandbox, _) = renumbered(false);
+    let run = sandbox.run(&["done", "##42"]);
+    assert_eq!(run.code, 2);
+    assert!(run.stderr.contains("'##42' is not a todo id"), "{}", run.stderr);
+}
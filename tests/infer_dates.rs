@@ -0,0 +1,56 @@
+#![cfg(unix)]
+
+//! `add --infer-dates` and the `infer_dates` config key reading a due date from the text
+
+mod common;
+
+use chrono::{Duration, Local};
+use common::Sandbox;
+
+/// The date `days` from today as the list shows it
+fn from_today(days: i64) -> String {
+    (Local::now().date_naive() + Duration::days(days)).format("%Y-%m-%d").to_string()
+}
+
+#[test]
+fn infer_dates_sets_the_due_date_and_says_where_it_came_from() {
+    for sqlite in [false, true] {
+        let sandbox = Sandbox::new();
+        let flags = if sqlite { vec!["--sqlite"] } else { vec![] };
+        let run = sandbox.ok(&[&flags[..], &["add", "call bob tomorrow", "--infer-dates"]].concat());
+        assert!(
+            run.stdout.contains("   (due date read from \"tomorrow\"; change it with `todo due 1 ...`)\n"),
+            "{}",
+            run.stdout
+        );
+        let list = sandbox.ok(&[&flags[..], &["list"]].concat()).stdout;
+        assert_eq!(list, format!("[ ] 1: call bob tomorrow (Due: {}, Reminder: No reminder)\n", from_today(1)));
+    }
+}
+
+#[test]
+fn dates_are_only_inferred_when_asked() {
+    let sandbox = Sandbox::new();
+    let run = sandbox.ok(&["add", "call bob tomorrow"]);
+    assert!(!run.stdout.contains("due date read from"), "{}", run.stdout);
+    assert_eq!(sandbox.ok(&["list"]).stdout, "[ ] 1: call bob tomorrow (Due: No due date, Reminder: No reminder)\n");
+}
+
+#[test]
+fn the_config_key_turns_inference_on() {
+    let sandbox = Sandbox::new();
+    sandbox.config("infer_dates = true\n");
+    sandbox.ok(&["add", "renew passport in 2 weeks"]);
+    assert_eq!(
+        sandbox.ok(&["list"]).stdout,
+        format!("[ ] 1: renew passport in 2 weeks (Due: {}, Reminder: No reminder)\n", from_today(14))
+    );
+}
+
+#[test]
+fn text_without_a_confident_date_gets_no_due_date() {
+    let sandbox = Sandbox::new();
+    let run = sandbox.ok(&["add", "friday drinks", "--infer-dates"]);
+    assert!(!run.stdout.contains("due date read from"), "{}", run.stdout);
+    assert_eq!(sandbox.ok(&["list"]).stdout, "[ ] 1: friday drinks (Due: No due date, Reminder: No reminder)\n");
+}
@@ -0,0 +1,55 @@
+#![cfg(unix)]
+
+mod common;
+
+use common::Sandbox;
+use std::fs;
+
+#[test]
+fn a_corrupt_store_is_left_untouched() {
+    let sandbox = Sandbox::new();
+    sandbox.ok(&["add", "buy milk"]);
+    sandbox.ok(&["add", "call mum"]);
+    let store = fs::read_to_string(sandbox.json_path()).unwrap();
+    let truncated = &store[..store.len() / 2];
+    fs::write(sandbox.json_path(), truncated).unwrap();
+    let saved = sandbox.snapshot();
+    for args in [&["add", "x"][..], &["list"], &["done", "1"], &["tui", "--plain"]] {
+        let run = sandbox.run(args);
+        assert_eq!(run.code, 1, "todo {:?}", args);
+        assert!(run.stderr.starts_with("❌ Could not read the todos, so nothing was saved: "), "{}", run.stderr);
+        assert_eq!(sandbox.snapshot(), saved, "todo {:?}", args);
+    }
+    let run = sandbox.run(&["migrate", "--to", "jsonl"]);
+    assert_eq!(run.code, 1);
+    assert!(run.stderr.starts_with("❌ Could not migrate the JSON store: "), "{}", run.stderr);
+    assert_eq!(sandbox.snapshot(), saved);
+}
+
+#[test]
+fn a_jsonl_store_with_a_bad_line_is_left_untouched() {
+    let sandbox = Sandbox::new();
+    sandbox.ok(&["migrate", "--to", "jsonl"]);
+    sandbox.ok(&["add", "buy milk"]);
+    sandbox.ok(&["add", "call mum"]);
+    let path = sandbox.data_dir().join("todos.jsonl");
+    let store = fs::read_to_string(&path).unwrap();
+    fs::write(&path, store.replacen("\"buy milk\"", "\"buy milk", 1)).unwrap();
+    let saved = sandbox.snapshot();
+    let run = sandbox.run(&["add", "x"]);
+    assert_eq!(run.code, 1);
+    assert!(run.stderr.contains("line 2"), "{}", run.stderr);
+    assert_eq!(sandbox.snapshot(), saved);
+}
+
+#[test]
+fn a_backup_can_be_restored_over_a_corrupt_store() {
+    let sandbox = Sandbox::new();
+    let bundle = sandbox.data_dir().with_file_name("backup.tar.gz");
+    sandbox.ok(&["add", "buy milk"]);
+    sandbox.ok(&["backup", bundle.to_str().unwrap()]);
+    fs::write(sandbox.json_path(), "{\"version\": 3, \"todos\": [").unwrap();
+    let run = sandbox.ok(&["restore", bundle.to_str().unwrap()]);
+    assert!(run.stdout.contains("Restored 1 todos"), "{}", run.stdout);
+    assert_eq!(sandbox.ok(&["list", "--format", "{id} {text}"]).stdout, "1 buy milk\n");
+}
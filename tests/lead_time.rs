@@ -0,0 +1,91 @@
+#![cfg(unix)]
+
+//! Lead time, from creation to completion, in `show`, `stats` and `list --sort lead-time`
+
+mod common;
+
+use common::Sandbox;
+use rusqlite::{params, Connection};
+
+/// (text, tag, created_at, completed_at) for four done todos and a pending one; "unknown
+/// start" has lost its creation time
+const TODOS: [(&str, &str, Option<&str>, Option<&str>); 5] = [
+    ("ship release", "work", Some("2025-01-01T00:00:00+00:00"), Some("2025-01-05T03:00:00+00:00")),
+    ("reply to boss", "work", Some("2025-01-01T09:00:00+00:00"), Some("2025-01-01T09:25:00+00:00")),
+    ("unknown start", "home", None, Some("2025-01-03T00:00:00+00:00")),
+    ("fix the tap", "home", Some("2025-01-02T12:00:00+00:00"), Some("2025-01-03T12:00:00+00:00")),
+    ("still pending", "home", Some("2025-01-02T12:00:00+00:00"), None),
+];
+
+/// A sandbox holding `TODOS`, on the database when `sqlite` is set
+fn timed(sqlite: bool) -> (Sandbox, Vec<&'static str>) {
+    let sandbox = Sandbox::new();
+    let flags = if sqlite { vec!["--sqlite"] } else { vec![] };
+    for (id, (text, tag, _, completed)) in TODOS.iter().enumerate() {
+        let id = (id + 1).to_string();
+        sandbox.ok(&[&flags[..], &["add", text]].concat());
+        sandbox.ok(&[&flags[..], &["tag", &id, tag]].concat());
+        if completed.is_some() {
+            sandbox.ok(&[&flags[..], &["done", &id]].concat());
+        }
+    }
+    if sqlite {
+        let conn = Connection::open(sandbox.db_path()).unwrap();
+        for (id, (_, _, created, completed)) in TODOS.iter().enumerate() {
+            conn.execute("UPDATE todos SET created_at = ?1, completed_at = ?2 WHERE id = ?3", params![created, completed, id + 1])
+                .unwrap();
+        }
+    } else {
+        let mut store: serde_json::Value = serde_json::from_slice(&std::fs::read(sandbox.json_path()).unwrap()).unwrap();
+        for (todo, (_, _, created, completed)) in store["todos"].as_array_mut().unwrap().iter_mut().zip(TODOS) {
+            todo["created_at"] = created.into();
+            todo["completed_at"] = completed.into();
+        }
+        std::fs::write(sandbox.json_path(), store.to_string()).unwrap();
+    }
+    (sandbox, flags)
+}
+
+#[test]
+fn show_says_how_long_a_done_todo_took() {
+    for sqlite in [false, true] {
+        let (sandbox, flags) = timed(sqlite);
+        let took = |id: &str| {
+            let stdout = sandbox.ok(&[&flags[..], &["show", id]].concat()).stdout;
+            stdout.lines().find(|line| line.starts_with("  Took:")).map(str::to_string)
+        };
+        assert_eq!(took("1").as_deref(), Some("  Took:      completed in 4d 3h"));
+        assert_eq!(took("2").as_deref(), Some("  Took:      completed in 25m"));
+        assert_eq!(took("3").as_deref(), Some("  Took:      unknown"));
+        assert_eq!(took("5"), None);
+    }
+}
+
+#[test]
+fn stats_give_median_and_mean_lead_time_overall_and_per_tag() {
+    for sqlite in [false, true] {
+        let (sandbox, flags) = timed(sqlite);
+        let stdout = sandbox.ok(&[&flags[..], &["stats"]].concat()).stdout;
+        // 25m, 1d and 4d 3h: "unknown start" is left out of every figure
+        assert!(stdout.contains("   Lead time:           median 1d, mean 1d 17h over 3 todos\n"), "{}", stdout);
+        assert!(stdout.contains("     #home               median 1d, mean 1d over 1 todo\n"), "{}", stdout);
+        assert!(stdout.contains("     #work               median 2d 1h, mean 2d 1h over 2 todos\n"), "{}", stdout);
+    }
+}
+
+#[test]
+fn done_todos_sort_by_lead_time_with_unknown_last() {
+    for sqlite in [false, true] {
+        let (sandbox, flags) = timed(sqlite);
+        let run = sandbox.ok(&[&flags[..], &["list", "--done", "--sort", "lead-time", "--format", "{text}"]].concat());
+        assert_eq!(run.stdout, "reply to boss\nfix the tap\nship release\nunknown start\n");
+    }
+}
+
+#[test]
+fn stats_leave_lead_time_out_until_something_is_done() {
+    let sandbox = Sandbox::new();
+    sandbox.ok(&["add", "buy milk"]);
+    let stdout = sandbox.ok(&["stats"]).stdout;
+    assert!(!stdout.contains("Lead time"), "{}", stdout);
+}
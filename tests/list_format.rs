@@ -0,0 +1,64 @@
+#![cfg(unix)]
+
+//! `list --format` line templates and the `list_format` config default
+
+mod common;
+
+use common::Sandbox;
+
+/// A sandbox with a tagged todo due in 2030 and a done one with braces in its text,
+/// on the database when `sqlite` is set
+fn listed(sqlite: bool) -> (Sandbox, Vec<&'static str>) {
+    let sandbox = Sandbox::new();
+    let flags = if sqlite { vec!["--sqlite"] } else { vec![] };
+    let todo = |args: &[&str]| sandbox.ok(&[&flags[..], args].concat());
+    todo(&["add", "buy milk"]);
+    todo(&["add", "pay {rent}"]);
+    todo(&["due", "1", "2030-01-02"]);
+    todo(&["tag", "1", "home"]);
+    todo(&["done", "2"]);
+    (sandbox, flags)
+}
+
+#[test]
+fn format_fills_in_todo_and_computed_fields() {
+    for sqlite in [false, true] {
+        let (sandbox, flags) = listed(sqlite);
+        let run = sandbox.ok(&[&flags[..], &["list", "--format", "{id}. {text} [{due}] {status_icon}|{tags}|{age_days}"]].concat());
+        assert_eq!(run.stdout, "1. buy milk [2030-01-02]  |#home|0\n2. pay {rent} [] ✓||0\n");
+    }
+}
+
+#[test]
+fn doubled_braces_are_literal() {
+    let (sandbox, _) = listed(false);
+    let run = sandbox.ok(&["list", "--format", "{{{id}}} {{text}}"]);
+    assert_eq!(run.stdout, "{1} {text}\n{2} {text}\n");
+}
+
+#[test]
+fn an_unknown_placeholder_lists_the_valid_ones() {
+    let (sandbox, _) = listed(false);
+    let run = sandbox.run(&["list", "--format", "{when}"]);
+    assert_eq!(run.code, 1);
+    assert!(run.stdout.is_empty(), "{}", run.stdout);
+    assert!(run.stderr.starts_with("❌ Invalid list format: unknown placeholder {when}; use one of {id}, {text}, {done}, {status_icon},"), "{}", run.stderr);
+    assert!(run.stderr.contains("{due_relative}") && run.stderr.contains("{age_days}"), "{}", run.stderr);
+}
+
+#[test]
+fn list_format_in_the_config_is_the_default() {
+    let (sandbox, _) = listed(false);
+    sandbox.config("list_format = \"{id}:{text}\"\n");
+    assert_eq!(sandbox.ok(&["list"]).stdout, "1:buy milk\n2:pay {rent}\n");
+    assert_eq!(sandbox.ok(&["list", "--format", "{text}"]).stdout, "buy milk\npay {rent}\n");
+}
+
+#[test]
+fn a_malformed_list_format_in_the_config_is_reported() {
+    let (sandbox, _) = listed(false);
+    sandbox.config("list_format = \"{id\"\n");
+    let run = sandbox.run(&["list"]);
+    assert_eq!(run.code, 1);
+    assert!(run.stderr.starts_with("❌ Invalid list format:"), "{}", run.stderr);
+}
@@ -0,0 +1,65 @@
+#![cfg(unix)]
+
+//! The `date_format` and `first_weekday` settings: dates shown and typed in a local format
+//! while the store keeps ISO dates, and weeks starting on another day
+
+mod common;
+
+use common::Sandbox;
+use std::fs;
+
+const GERMAN: &str = "date_format = \"%d.%m.%Y\"\nfirst_weekday = \"sunday\"\n";
+
+#[test]
+fn dates_are_shown_in_the_configured_format() {
+    for flags in [&[][..], &["--sqlite"]] {
+        let sandbox = Sandbox::new();
+        sandbox.config(GERMAN);
+        let todo = |args: &[&str]| sandbox.ok(&[flags, args].concat()).stdout;
+        todo(&["add", "file taxes"]);
+        todo(&["due", "1", "2027-03-05"]);
+        todo(&["remind", "1", "2027-03-04", "09:00"]);
+        assert_eq!(todo(&["list"]), "[ ] 1: file taxes (Due: 05.03.2027, Reminder: 04.03.2027 09:00)\n");
+        let shown = todo(&["show", "1"]);
+        assert!(shown.contains("  Due:       05.03.2027\n  Reminder:  04.03.2027 09:00\n"), "{}", shown);
+        assert_eq!(todo(&["upcoming"]), "Upcoming reminders:\n[1] file taxes - Due: 04.03.2027 09:00\n");
+    }
+}
+
+#[test]
+fn the_configured_format_is_accepted_but_stored_as_iso() {
+    let sandbox = Sandbox::new();
+    sandbox.config(GERMAN);
+    sandbox.ok(&["add", "file taxes"]);
+    sandbox.ok(&["add", "book flights"]);
+    sandbox.ok(&["due", "1", "05.03.2027"]);
+    sandbox.ok(&["due", "2", "2027-03-06"]);
+    let store = fs::read_to_string(sandbox.json_path()).unwrap();
+    assert!(store.contains("\"due_date\": \"2027-03-05\""), "{}", store);
+    assert!(store.contains("\"due_date\": \"2027-03-06\""), "{}", store);
+    assert_eq!(sandbox.run(&["due", "1", "03/05/2027"]).code, 1);
+}
+
+#[test]
+fn iso_comes_first_when_the_format_is_ambiguous() {
+    let sandbox = Sandbox::new();
+    sandbox.config("date_format = \"%m/%d/%Y\"\n");
+    sandbox.ok(&["add", "file taxes"]);
+    sandbox.ok(&["due", "1", "03/05/2027"]);
+    assert_eq!(sandbox.ok(&["list", "--format", "{due}"]).stdout, "03/05/2027\n");
+    assert!(fs::read_to_string(sandbox.json_path()).unwrap().contains("\"due_date\": \"2027-03-05\""));
+    sandbox.ok(&["due", "1", "2027-05-03"]);
+    assert_eq!(sandbox.ok(&["list", "--format", "{due}"]).stdout, "05/03/2027\n");
+}
+
+#[test]
+fn weeks_start_on_the_configured_day() {
+    let sandbox = Sandbox::new();
+    let days = |week: &str| -> Vec<String> { week.lines().filter(|line| !line.starts_with(' ')).map(|line| line[..3].to_string()).collect() };
+    assert_eq!(days(&sandbox.ok(&["week"]).stdout), ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"]);
+    sandbox.config(GERMAN);
+    let week = sandbox.ok(&["week"]).stdout;
+    assert_eq!(days(&week), ["Sun", "Mon", "Tue", "Wed", "Thu", "Fri", "Sat"]);
+    let cal = sandbox.ok(&["cal"]).stdout;
+    assert!(cal.lines().any(|line| line.trim_start_matches(['|', ' ']).starts_with("Sun")), "{}", cal);
+}
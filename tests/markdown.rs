@@ -0,0 +1,69 @@
+#![cfg(unix)]
+
+//! `import --format markdown` and `export --format markdown`
+
+mod common;
+
+use common::Sandbox;
+use std::fs;
+
+const PLAN: &str = "\
+# Trip
+- [ ] book flights (due: 2026-11-01)
+  - [x] compare prices
+  - [ ] pick seats
+- [X] renew passport
+not a checklist line
+- [ ] pack (due: 2026-02-30)
+
+## Packing
+* [ ] socks
+";
+
+/// `PLAN` written into the sandbox, and its path
+fn plan(sandbox: &Sandbox) -> String {
+    let path = sandbox.home().join("plan.md");
+    fs::write(&path, PLAN).unwrap();
+    path.to_str().unwrap().to_string()
+}
+
+#[test]
+fn nested_items_become_subtasks_and_skipped_lines_are_numbered() {
+    for sqlite in [false, true] {
+        let sandbox = Sandbox::new();
+        let flags = if sqlite { vec!["--sqlite"] } else { vec![] };
+        let path = plan(&sandbox);
+        let run = sandbox.ok(&[&flags[..], &["import", "--format", "markdown", &path, "--headings-as-tags"]].concat());
+        assert_eq!(
+            run.stderr,
+            "⚠️ Skipped line 6: not a checklist item\n⚠️ Skipped line 7: due date '2026-02-30' is not in YYYY-MM-DD [HH:MM] format\n"
+        );
+        assert!(run.stdout.starts_with("📥 Imported 5 todos (2 done, 2 subtasks) from "), "{}", run.stdout);
+        let listed = sandbox.ok(&[&flags[..], &["list", "--tree"]].concat()).stdout;
+        assert_eq!(
+            listed,
+            "\
+[ ] 1: book flights [1/2] #trip (Due: 2026-11-01, Reminder: No reminder)
+├── [✓] 2: compare prices #trip (Due: No due date, Reminder: No reminder)
+└── [ ] 3: pick seats #trip (Due: No due date, Reminder: No reminder)
+[✓] 4: renew passport #trip (Due: No due date, Reminder: No reminder)
+[ ] 5: socks #trip #packing (Due: No due date, Reminder: No reminder)
+"
+        );
+    }
+}
+
+#[test]
+fn an_export_imports_back_the_same() {
+    let sandbox = Sandbox::new();
+    let path = plan(&sandbox);
+    sandbox.ok(&["import", "--format", "markdown", &path]);
+    sandbox.ok(&["tag", "5", "clothes"]);
+    let exported = sandbox.home().join("exported.md");
+    sandbox.ok(&["export", "--format", "markdown", "--output", exported.to_str().unwrap()]);
+
+    let copy = Sandbox::new();
+    copy.ok(&["--sqlite", "import", "--format", "markdown", exported.to_str().unwrap()]);
+    let format = ["list", "--tree", "--format", "{id} {text} {done} {due_date} {tags} {uuid} {progress}"];
+    assert_eq!(copy.ok(&[&["--sqlite"][..], &format].concat()).stdout, sandbox.ok(&format).stdout);
+}
@@ -0,0 +1,75 @@
+#![cfg(unix)]
+
+mod common;
+
+use common::Sandbox;
+use std::{fs, path::PathBuf};
+
+/// A sandbox whose todos are at the old hard-coded JSON path, with the store itself empty
+fn legacy_store(texts: &[&str]) -> (Sandbox, PathBuf) {
+    let sandbox = Sandbox::new();
+    for text in texts {
+        sandbox.ok(&["add", text]);
+    }
+    let legacy = sandbox.home().join("Projects/todo/todos.json");
+    fs::create_dir_all(legacy.parent().unwrap()).unwrap();
+    fs::rename(sandbox.json_path(), &legacy).unwrap();
+    fs::remove_file(sandbox.data_dir().join("last.json")).ok();
+    (sandbox, legacy)
+}
+
+fn listed(sandbox: &Sandbox) -> Vec<String> {
+    sandbox.ok(&["list", "--format", "{id} {text}"]).stdout.lines().map(str::to_string).collect()
+}
+
+#[test]
+fn migrate_legacy_copies_the_todos_and_backs_up_the_original() {
+    let (sandbox, legacy) = legacy_store(&["Buy milk", "Walk the dog"]);
+    let original = fs::read(&legacy).unwrap();
+
+    let run = sandbox.ok(&["--migrate-legacy", "list"]);
+    assert!(run.stdout.contains("Copied 2 todos from"), "{}", run.stdout);
+    assert!(run.stdout.contains(&legacy.display().to_string()), "{}", run.stdout);
+    assert!(run.stdout.contains(&sandbox.json_path().display().to_string()), "{}", run.stdout);
+    assert_eq!(listed(&sandbox), ["1 Buy milk", "2 Walk the dog"]);
+
+    assert_eq!(fs::read(&legacy).unwrap(), original, "the original is left in place");
+    let backups: Vec<_> = fs::read_dir(legacy.parent().unwrap())
+        .unwrap()
+        .flatten()
+        .filter(|entry| entry.file_name().to_string_lossy().starts_with("todos.json.pre-migrate-"))
+        .collect();
+    assert_eq!(backups.len(), 1);
+    assert_eq!(fs::read(backups[0].path()).unwrap(), original);
+}
+
+#[test]
+fn without_a_terminal_the_legacy_todos_are_only_pointed_at() {
+    let (sandbox, legacy) = legacy_store(&["Buy milk"]);
+
+    let run = sandbox.ok(&["list"]);
+    assert!(run.stderr.contains(&format!("{} has 1", legacy.display())), "{}", run.stderr);
+    assert!(run.stderr.contains("todo --migrate-legacy"), "{}", run.stderr);
+    assert!(!sandbox.json_path().exists(), "nothing is copied without being asked");
+}
+
+#[test]
+fn an_answer_already_given_is_not_asked_again() {
+    let (sandbox, legacy) = legacy_store(&["Buy milk"]);
+    fs::write(sandbox.data_dir().join("last.json"), r#"{"added":null,"done":null,"modified":null,"legacy_json":"declined"}"#).unwrap();
+
+    let run = sandbox.ok(&["list"]);
+    assert!(!run.stderr.contains(&legacy.display().to_string()), "{}", run.stderr);
+    assert!(!sandbox.json_path().exists());
+
+    // --migrate-legacy still copies them once declined
+    sandbox.ok(&["--migrate-legacy", "list"]);
+    assert_eq!(listed(&sandbox), ["1 Buy milk"]);
+}
+
+#[test]
+fn migrate_legacy_without_a_legacy_store_says_so() {
+    let sandbox = Sandbox::new();
+    let run = sandbox.ok(&["--migrate-legacy", "list"]);
+    assert!(run.stderr.contains("Nothing to migrate"), "{}", run.stderr);
+}
@@ -0,0 +1,105 @@
+#![cfg(unix)]
+
+//! The overdue nag that `nag = true` adds after a command, at most once every `nag_every`
+
+mod common;
+
+use chrono::{Duration, Local, SecondsFormat, Utc};
+use common::Sandbox;
+use std::fs;
+
+/// A sandbox with "late" (#1) overdue since the day before yesterday and "fine" (#2) due
+/// next week, with the nag switched on
+fn overdue() -> Sandbox {
+    let sandbox = Sandbox::new();
+    let day = |days: i64| (Local::now().date_naive() + Duration::days(days)).format("%Y-%m-%d").to_string();
+    for args in [&["add", "late"][..], &["add", "fine"], &["due", "1", &day(-2)], &["due", "2", &day(7)]] {
+        sandbox.ok(args);
+    }
+    sandbox.config("nag = true\nnag_every = \"30m\"\n");
+    sandbox
+}
+
+fn nagged_at(sandbox: &Sandbox) -> Option<String> {
+    let state: serde_json::Value = serde_json::from_slice(&fs::read(sandbox.data_dir().join("last.json")).ok()?).ok()?;
+    state["nagged_at"].as_str().map(str::to_string)
+}
+
+#[test]
+fn output_that_isnt_a_terminal_is_never_nagged() {
+    let sandbox = overdue();
+    let run = sandbox.ok(&["list"]);
+    assert!(!run.stdout.contains("overdue"), "{}", run.stdout);
+    assert!(!run.stderr.contains("overdue"), "{}", run.stderr);
+    assert_eq!(nagged_at(&sandbox), None);
+}
+
+/// The nag needs stdout to be a terminal, which util-linux's `script` gives a command
+#[cfg(target_os = "linux")]
+mod on_a_terminal {
+    use super::*;
+    use std::process::Command;
+
+    /// What `todo args` prints when its output is a terminal
+    fn on_terminal(sandbox: &Sandbox, args: &[&str]) -> String {
+        let todo = sandbox.command(args);
+        let quoted: Vec<String> = std::iter::once(todo.get_program())
+            .chain(todo.get_args())
+            .map(|arg| format!("'{}'", arg.to_str().unwrap().replace('\'', r"'\''")))
+            .collect();
+        let mut script = Command::new("script");
+        script.args(["-qec", &quoted.join(" "), "/dev/null"]);
+        for (key, value) in todo.get_envs() {
+            match value {
+                Some(value) => script.env(key, value),
+                None => script.env_remove(key),
+            };
+        }
+        let output = script.output().expect("run script");
+        assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+        String::from_utf8_lossy(&output.stdout).replace("\r\n", "\n")
+    }
+
+    const NAG: &str = "(1 overdue — `todo today`)";
+
+    /// Pretend the nag was last shown `age` ago
+    fn set_nagged(sandbox: &Sandbox, age: Duration) {
+        let path = sandbox.data_dir().join("last.json");
+        let mut state: serde_json::Value = serde_json::from_slice(&fs::read(&path).unwrap()).unwrap();
+        state["nagged_at"] = (Utc::now() - age).to_rfc3339_opts(SecondsFormat::Secs, true).into();
+        fs::write(path, state.to_string()).unwrap();
+    }
+
+    #[test]
+    fn the_nag_follows_the_output_once_per_window() {
+        let sandbox = overdue();
+        let shown = on_terminal(&sandbox, &["list"]);
+        assert!(shown.trim_end().lines().last().unwrap().contains(NAG), "{}", shown);
+        assert!(nagged_at(&sandbox).is_some());
+        assert!(!on_terminal(&sandbox, &["list"]).contains(NAG));
+
+        set_nagged(&sandbox, Duration::minutes(29));
+        assert!(!on_terminal(&sandbox, &["list"]).contains(NAG));
+        set_nagged(&sandbox, Duration::minutes(31));
+        assert!(on_terminal(&sandbox, &["list"]).contains(NAG));
+    }
+
+    #[test]
+    fn quiet_and_json_output_are_never_nagged() {
+        let sandbox = overdue();
+        assert!(!on_terminal(&sandbox, &["--quiet", "list"]).contains(NAG));
+        assert!(!on_terminal(&sandbox, &["show", "1", "--json"]).contains(NAG));
+        assert_eq!(nagged_at(&sandbox), None);
+    }
+
+    #[test]
+    fn no_nag_without_the_setting_or_anything_overdue() {
+        let sandbox = overdue();
+        sandbox.config("");
+        assert!(!on_terminal(&sandbox, &["list"]).contains("overdue"));
+        sandbox.config("nag = true\n");
+        sandbox.ok(&["done", "1"]);
+        assert!(!on_terminal(&sandbox, &["list"]).contains("overdue"));
+        assert_eq!(nagged_at(&sandbox), None);
+    }
+}
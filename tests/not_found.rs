@@ -0,0 +1,48 @@
+#![cfg(unix)]
+
+//! What `todo` says about an id that doesn't exist
+
+mod common;
+
+use common::Sandbox;
+
+/// A sandbox with a few todos, on the database when `sqlite` is set
+fn shopping(sqlite: bool) -> (Sandbox, Vec<&'static str>) {
+    let sandbox = Sandbox::new();
+    let flags = if sqlite { vec!["--sqlite"] } else { vec![] };
+    for text in ["buy milk", "call mum", "fix bike", "buy bread"] {
+        sandbox.ok(&[&flags[..], &["add", text]].concat());
+    }
+    (sandbox, flags)
+}
+
+#[test]
+fn hint_words_suggest_pending_todos_with_similar_text() {
+    for sqlite in [false, true] {
+        let (sandbox, flags) = shopping(sqlite);
+        let run = sandbox.run(&[&flags[..], &["done", "42", "milk"]].concat());
+        assert_eq!(run.code, 1);
+        assert_eq!(run.stderr, "❌ Todo with id 42 not found\n   Did you mean:\n     #1 buy milk\n");
+        assert!(run.stdout.is_empty(), "{}", run.stdout);
+    }
+}
+
+#[test]
+fn without_hint_words_the_nearest_ids_are_suggested() {
+    for sqlite in [false, true] {
+        let (sandbox, flags) = shopping(sqlite);
+        let run = sandbox.run(&[&flags[..], &["delete", "6"]].concat());
+        assert_eq!(run.code, 1);
+        assert_eq!(run.stderr, "❌ Todo with id 6 not found\n   Nearest existing ids: #4, #3, #2\n");
+    }
+}
+
+#[test]
+fn done_todos_are_not_suggested() {
+    let (sandbox, _) = shopping(false);
+    sandbox.ok(&["done", "1"]);
+    let run = sandbox.run(&["edit", "42", "milk"]);
+    assert_eq!(run.code, 1);
+    assert!(!run.stderr.contains("buy milk"), "{}", run.stderr);
+    assert!(run.stderr.contains("Nearest existing ids"), "{}", run.stderr);
+}
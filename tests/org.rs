@@ -0,0 +1,87 @@
+#![cfg(unix)]
+
+//! `export --format org` and `import --format org`
+
+mod common;
+
+use common::Sandbox;
+use std::fs;
+
+const AGENDA: &str = "\
+* Work
+** TODO [#A] call bank :money:
+   DEADLINE: <2030-07-02 Tue 09:30>
+   ask about the fee
+** DONE file taxes
+* Notes
+random text
+";
+
+/// A sandbox with "send report" (#1), due in 2030 and tagged, and a done "groceries" (#2),
+/// on the database when `sqlite` is set
+fn planned(sqlite: bool) -> (Sandbox, Vec<&'static str>) {
+    let sandbox = Sandbox::new();
+    let flags = if sqlite { vec!["--sqlite"] } else { vec![] };
+    let todo = |args: &[&str]| sandbox.ok(&[&flags[..], args].concat());
+    todo(&["add", "send report"]);
+    todo(&["add", "groceries"]);
+    todo(&["due", "1", "2030-07-01"]);
+    todo(&["tag", "1", "work", "q3"]);
+    todo(&["done", "2"]);
+    (sandbox, flags)
+}
+
+/// The export's heading and planning lines, leaving out the ids and completion times
+/// that differ from run to run
+fn headings(org: &str) -> Vec<&str> {
+    org.lines().filter(|line| !line.trim().starts_with(':') && !line.contains("CLOSED:")).collect()
+}
+
+#[test]
+fn todos_export_as_todo_and_done_headings() {
+    for sqlite in [false, true] {
+        let (sandbox, flags) = planned(sqlite);
+        let org = sandbox.ok(&[&flags[..], &["export", "--format", "org"]].concat()).stdout;
+        assert_eq!(headings(&org), ["* TODO send report :work:q3:", "  DEADLINE: <2030-07-01 Mon>", "* DONE groceries"]);
+        assert!(org.contains("  CLOSED: ["), "{}", org);
+        assert_eq!(org.matches("  :ID: ").count(), 2, "{}", org);
+    }
+}
+
+#[test]
+fn group_by_tag_nests_under_a_heading_per_tag() {
+    let (sandbox, _) = planned(false);
+    let org = sandbox.ok(&["export", "--format", "org", "--group-by", "tag"]).stdout;
+    assert_eq!(
+        headings(&org),
+        ["* work", "** TODO send report :work:q3:", "   DEADLINE: <2030-07-01 Mon>", "* untagged", "** DONE groceries"]
+    );
+}
+
+#[test]
+fn import_reads_todo_headings_and_counts_the_others() {
+    for sqlite in [false, true] {
+        let sandbox = Sandbox::new();
+        let flags = if sqlite { vec!["--sqlite"] } else { vec![] };
+        let path = sandbox.home().join("agenda.org");
+        fs::write(&path, AGENDA).unwrap();
+        let run = sandbox.ok(&[&flags[..], &["import", "--format", "org", path.to_str().unwrap(), "--headings-as-tags"]].concat());
+        assert_eq!(run.stderr, "⚠️ Skipped 2 headings that aren't TODO or DONE\n");
+        assert!(run.stdout.starts_with("📥 Imported 2 todos (1 done) from "), "{}", run.stdout);
+        let listed = sandbox.ok(&[&flags[..], &["list", "--format", "{text}|{done}|{due_date}|{tags}|{priority}"]].concat()).stdout;
+        assert_eq!(listed, "call bank|false|2030-07-02 09:30|#work #money|high\nfile taxes|true||#work|\n");
+    }
+}
+
+#[test]
+fn an_export_imports_back_the_same() {
+    let (sandbox, _) = planned(false);
+    sandbox.ok(&["priority", "1", "high"]);
+    let exported = sandbox.home().join("exported.org");
+    sandbox.ok(&["export", "--format", "org", "--output", exported.to_str().unwrap()]);
+
+    let copy = Sandbox::new();
+    copy.ok(&["--sqlite", "import", "--format", "org", exported.to_str().unwrap()]);
+    let format = ["list", "--format", "{id} {text} {done} {due_date} {tags} {priority} {uuid}"];
+    assert_eq!(copy.ok(&[&["--sqlite"][..], &format].concat()).stdout, sandbox.ok(&format).stdout);
+}
@@ -0,0 +1,58 @@
+#![cfg(unix)]
+
+//! `--relative-dates` and the `relative_dates` setting in `list` and `upcoming`
+
+mod common;
+
+use chrono::{Duration, Local};
+use common::Sandbox;
+
+/// The date `days` from today as `due` and `remind` take it
+fn from_today(days: i64) -> String {
+    (Local::now().date_naive() + Duration::days(days)).format("%Y-%m-%d").to_string()
+}
+
+/// A sandbox with todos due tomorrow, in five days and three days ago, the first with a
+/// reminder in two days, on the database when `sqlite` is set
+fn dated(sqlite: bool) -> (Sandbox, Vec<&'static str>) {
+    let sandbox = Sandbox::new();
+    let flags = if sqlite { vec!["--sqlite"] } else { vec![] };
+    let todo = |args: &[&str]| sandbox.ok(&[&flags[..], args].concat());
+    for (text, days) in [("pay rent", 1), ("book flights", 5), ("return books", -3)] {
+        todo(&["add", text]);
+        let id = todo(&["last", "added"]).stdout;
+        todo(&["due", id.trim(), &from_today(days)]);
+    }
+    todo(&["remind", "1", &from_today(2), "09:00"]);
+    (sandbox, flags)
+}
+
+#[test]
+fn list_shows_dates_relative_to_today() {
+    for sqlite in [false, true] {
+        let (sandbox, flags) = dated(sqlite);
+        let listed = sandbox.ok(&[&flags[..], &["list", "--relative-dates"]].concat()).stdout;
+        assert!(listed.contains("pay rent (Due: tomorrow, Reminder: in 2 days 09:00)"), "{}", listed);
+        assert!(listed.contains("book flights (Due: in 5 days, Reminder: No reminder)"), "{}", listed);
+        assert!(listed.contains("return books (Due: 3 days ago, Reminder: No reminder)"), "{}", listed);
+    }
+}
+
+#[test]
+fn upcoming_shows_dates_relative_to_today() {
+    for sqlite in [false, true] {
+        let (sandbox, flags) = dated(sqlite);
+        let upcoming = sandbox.ok(&[&flags[..], &["upcoming", "--relative-dates"]].concat()).stdout;
+        assert_eq!(upcoming, "Upcoming reminders:\n[1] pay rent - Due: in 2 days 09:00\n");
+    }
+}
+
+#[test]
+fn dates_stay_absolute_unless_asked_for() {
+    let (sandbox, _) = dated(false);
+    let listed = sandbox.ok(&["list"]).stdout;
+    assert!(listed.contains(&format!("pay rent (Due: {}, Reminder: {} 09:00)", from_today(1), from_today(2))), "{}", listed);
+    sandbox.config("relative_dates = true\n");
+    let listed = sandbox.ok(&["list"]).stdout;
+    assert!(listed.contains("pay rent (Due: tomorrow, Reminder: in 2 days 09:00)"), "{}", listed);
+}
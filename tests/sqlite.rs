@@ -0,0 +1,108 @@
+#![cfg(unix)]
+
+mod common;
+
+use common::Sandbox;
+use rusqlite::Connection;
+use std::{process::Stdio, thread, time::Duration};
+
+/// Run `todo --sqlite add` while another connection holds the database's write lock for
+/// `held`, returning how the command exited and what it said on stderr
+fn add_while_locked(sandbox: &Sandbox, held: Duration) -> (i32, String) {
+    sandbox.ok(&["--sqlite", "add", "buy milk"]);
+    let conn = Connection::open(sandbox.db_path()).unwrap();
+    conn.execute_batch("BEGIN IMMEDIATE; UPDATE todos SET text = 'call mum' WHERE id = 1;").unwrap();
+    let child = sandbox
+        .command(&["--sqlite", "add", "water the plants"])
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    thread::sleep(held);
+    conn.execute_batch("COMMIT").unwrap();
+    let output = child.wait_with_output().unwrap();
+    (output.status.code().unwrap_or(-1), String::from_utf8_lossy(&output.stderr).into_owned())
+}
+
+#[test]
+fn a_locked_database_is_waited_for() {
+    let sandbox = Sandbox::new();
+    sandbox.config("busy_timeout_ms = 10000\n");
+    let (code, stderr) = add_while_locked(&sandbox, Duration::from_millis(500));
+    assert_eq!(code, 0, "{}", stderr);
+    let run = sandbox.ok(&["--sqlite", "list", "--format", "{id} {text}"]);
+    assert_eq!(run.stdout, "1 call mum\n2 water the plants\n");
+}
+
+#[test]
+fn a_database_locked_for_too_long_is_reported() {
+    let sandbox = Sandbox::new();
+    sandbox.config("busy_timeout_ms = 100\n");
+    let (code, stderr) = add_while_locked(&sandbox, Duration::from_millis(1500));
+    assert_eq!(code, 1);
+    assert_eq!(stderr, "❌ database is locked by another process; retried for 0.1s\n");
+    let run = sandbox.ok(&["--sqlite", "list", "--format", "{id} {text}"]);
+    assert_eq!(run.stdout, "1 call mum\n");
+}
+
+#[test]
+fn commands_that_read_no_todos_leave_the_database_unopened() {
+    let sandbox = Sandbox::new();
+    for args in [&["--sqlite", "where"][..], &["--sqlite", "alias"], &["--sqlite", "backend"], &["--sqlite", "trash", "list"]] {
+        sandbox.ok(args);
+    }
+    assert!(!sandbox.db_path().exists());
+}
+
+/// A database whose `done` column another tool has filled with 2, 'yes', 'maybe',
+/// 'false' and 0.5 for todos 1, 2, 4, 5 and 6; todo 3 is left pending as 0
+fn dirty_done() -> Sandbox {
+    let sandbox = Sandbox::new();
+    for text in ["a", "b", "c", "d", "e", "f"] {
+        sandbox.ok(&["--sqlite", "add", text]);
+    }
+    let conn = Connection::open(sandbox.db_path()).unwrap();
+    conn.execute_batch(
+        "UPDATE todos SET done = 2 WHERE id = 1;
+         UPDATE todos SET done = 'yes' WHERE id = 2;
+         UPDATE todos SET done = 'maybe' WHERE id = 4;
+         UPDATE todos SET done = 'false' WHERE id = 5;
+         UPDATE todos SET done = 0.5 WHERE id = 6;",
+    )
+    .unwrap();
+    sandbox
+}
+
+#[test]
+fn rows_with_a_dirty_done_value_are_read_and_named_rather_than_dropped() {
+    let sandbox = dirty_done();
+    let run = sandbox.ok(&["--sqlite", "list", "--format", "{id} {done}"]);
+    assert_eq!(run.stdout, "1 true\n2 true\n3 false\n4 false\n5 false\n6 true\n");
+    assert_eq!(
+        run.stderr,
+        "⚠️ todo 1's done column holds 2 (read as done), todo 2's done column holds 'yes' (read as done), \
+         todo 4's done column holds 'maybe' (read as pending, as it means neither), \
+         todo 5's done column holds 'false' (read as pending), todo 6's done column holds 0.5 (read as done) \
+         — run `todo doctor --fix`\n"
+    );
+    assert!(sandbox.ok(&["--sqlite", "--quiet", "list"]).stderr.is_empty());
+}
+
+#[test]
+fn doctor_flags_dirty_done_values_and_fix_rewrites_them() {
+    let sandbox = dirty_done();
+    let run = sandbox.run(&["--sqlite", "--quiet", "doctor"]);
+    assert_eq!(run.code, 1);
+    assert!(run.stdout.contains("#2: done column holds 'yes' rather than 1 (fix: rewrite as 1)"), "{}", run.stdout);
+    assert!(run.stdout.contains("#4: done column holds 'maybe', which is neither true nor false; read as pending"), "{}", run.stdout);
+    assert_eq!(run.stderr, "❌ 5 problem(s) found\n");
+
+    sandbox.run(&["--sqlite", "--quiet", "doctor", "--fix"]);
+    let conn = Connection::open(sandbox.db_path()).unwrap();
+    let mut stmt = conn.prepare("SELECT done FROM todos ORDER BY id").unwrap();
+    let stored: Vec<i64> = stmt.query_map([], |row| row.get(0)).unwrap().map(Result::unwrap).collect();
+    assert_eq!(stored, [1, 1, 0, 0, 0, 1]);
+    let run = sandbox.run(&["--sqlite", "doctor"]);
+    assert_eq!(run.code, 0, "{}{}", run.stdout, run.stderr);
+    assert!(run.stderr.is_empty(), "{}", run.stderr);
+}
@@ -0,0 +1,83 @@
+#![cfg(unix)]
+
+//! How old pending todos are: `list --stale`, the `stale_after` setting and `stats`
+
+mod common;
+
+use chrono::{Duration, SecondsFormat, Utc};
+use common::Sandbox;
+use rusqlite::{params, Connection};
+
+/// `created_at` for a todo made `age` ago
+fn created(age: Duration) -> String {
+    (Utc::now() - age).to_rfc3339_opts(SecondsFormat::Secs, true)
+}
+
+/// A sandbox with pending todos made 40 days, 30 days and an hour, and 30 days less an hour
+/// ago, and one made 90 days ago that is done, on the database when `sqlite` is set
+fn aged(sqlite: bool) -> (Sandbox, Vec<&'static str>) {
+    let sandbox = Sandbox::new();
+    let flags = if sqlite { vec!["--sqlite"] } else { vec![] };
+    let ages = [
+        ("file taxes", Duration::days(40)),
+        ("just past", Duration::days(30) + Duration::hours(1)),
+        ("not yet", Duration::days(30) - Duration::hours(1)),
+        ("old but done", Duration::days(90)),
+    ];
+    for (text, _) in ages {
+        sandbox.ok(&[&flags[..], &["add", text]].concat());
+    }
+    sandbox.ok(&[&flags[..], &["done", "4"]].concat());
+    if sqlite {
+        let conn = Connection::open(sandbox.db_path()).unwrap();
+        for (id, (_, age)) in ages.iter().enumerate() {
+            conn.execute("UPDATE todos SET created_at = ?1 WHERE id = ?2", params![created(*age), id + 1]).unwrap();
+        }
+    } else {
+        let mut store: serde_json::Value = serde_json::from_slice(&std::fs::read(sandbox.json_path()).unwrap()).unwrap();
+        for (todo, (_, age)) in store["todos"].as_array_mut().unwrap().iter_mut().zip(ages) {
+            todo["created_at"] = created(age).into();
+        }
+        std::fs::write(sandbox.json_path(), store.to_string()).unwrap();
+    }
+    (sandbox, flags)
+}
+
+fn texts(sandbox: &Sandbox, flags: &[&str], args: &[&str]) -> Vec<String> {
+    let run = sandbox.ok(&[flags, args, &["--format", "{text}"]].concat());
+    run.stdout.lines().map(str::to_string).collect()
+}
+
+#[test]
+fn stale_lists_pending_todos_older_than_the_threshold() {
+    for sqlite in [false, true] {
+        let (sandbox, flags) = aged(sqlite);
+        assert_eq!(texts(&sandbox, &flags, &["list", "--stale", "30d"]), ["file taxes", "just past"]);
+        assert_eq!(texts(&sandbox, &flags, &["list", "--stale", "29d"]), ["file taxes", "just past", "not yet"]);
+        assert_eq!(texts(&sandbox, &flags, &["list", "--stale", "6w"]), Vec::<String>::new());
+    }
+}
+
+#[test]
+fn stale_takes_a_span() {
+    let (sandbox, _) = aged(false);
+    for span in ["30", "thirty days", "3y"] {
+        let run = sandbox.run(&["list", "--stale", span]);
+        assert_eq!(run.code, 2, "{}", span);
+        assert!(run.stdout.is_empty(), "{}", run.stdout);
+    }
+}
+
+#[test]
+fn stats_reports_the_age_of_pending_todos() {
+    for sqlite in [false, true] {
+        let (sandbox, flags) = aged(sqlite);
+        let stats = sandbox.ok(&[&flags[..], &["stats"]].concat()).stdout;
+        assert!(stats.starts_with("📊 4 todos: 3 pending, 1 done\n"), "{}", stats);
+        assert!(stats.contains("   Oldest pending:      40d (#1 file taxes)\n"), "{}", stats);
+        assert!(stats.contains("   Stale:               2\n"), "{}", stats);
+        sandbox.config("stale_after = \"45d\"\n");
+        let stats = sandbox.ok(&[&flags[..], &["stats"]].concat()).stdout;
+        assert!(!stats.contains("Stale:"), "{}", stats);
+    }
+}
@@ -0,0 +1,67 @@
+#![cfg(unix)]
+
+//! Subtask progress on parents in `list --tree` and `show`, and the nudge once the
+//! last subtask is done
+
+mod common;
+
+use common::Sandbox;
+
+/// A sandbox with "move house" (#1) split into "pack" (#2, itself split into a done
+/// "boxes", #3) and "book van" (#4), on the database when `sqlite` is set
+fn nested(sqlite: bool) -> (Sandbox, Vec<&'static str>) {
+    let sandbox = Sandbox::new();
+    let flags = if sqlite { vec!["--sqlite"] } else { vec![] };
+    let todo = |args: &[&str]| sandbox.ok(&[&flags[..], args].concat());
+    todo(&["add", "move house"]);
+    todo(&["add", "pack", "--parent", "1"]);
+    todo(&["add", "boxes", "--parent", "2"]);
+    todo(&["add", "book van", "--parent", "1"]);
+    todo(&["done", "3"]);
+    (sandbox, flags)
+}
+
+#[test]
+fn tree_shows_progress_over_direct_children() {
+    for sqlite in [false, true] {
+        let (sandbox, flags) = nested(sqlite);
+        let stdout = sandbox.ok(&[&flags[..], &["list", "--tree"]].concat()).stdout;
+        let lines: Vec<&str> = stdout.lines().collect();
+        assert!(lines[0].starts_with("[ ] 1: move house [0/2] "), "{}", stdout);
+        assert!(lines[1].starts_with("├── [ ] 2: pack [1/1 ✓] "), "{}", stdout);
+        assert!(lines[2].starts_with("│   └── [✓] 3: boxes ("), "{}", stdout);
+        assert!(lines[3].starts_with("└── [ ] 4: book van ("), "{}", stdout);
+    }
+}
+
+#[test]
+fn recursive_tree_rolls_up_every_descendant() {
+    for sqlite in [false, true] {
+        let (sandbox, flags) = nested(sqlite);
+        let stdout = sandbox.ok(&[&flags[..], &["list", "--tree", "--recursive"]].concat()).stdout;
+        assert!(stdout.starts_with("[ ] 1: move house [1/3] "), "{}", stdout);
+        assert!(stdout.contains("2: pack [1/1 ✓] "), "{}", stdout);
+    }
+}
+
+#[test]
+fn show_gives_direct_and_recursive_progress() {
+    let (sandbox, _) = nested(false);
+    let stdout = sandbox.ok(&["show", "1"]).stdout;
+    assert!(stdout.contains("  Subtasks:  0/2 done (1/3 including theirs)\n"), "{}", stdout);
+    let stdout = sandbox.ok(&["show", "4"]).stdout;
+    assert!(!stdout.contains("Subtasks:"), "{}", stdout);
+}
+
+#[test]
+fn finishing_the_last_subtask_points_at_the_parent() {
+    for sqlite in [false, true] {
+        let (sandbox, flags) = nested(sqlite);
+        let run = sandbox.ok(&[&flags[..], &["done", "4"]].concat());
+        assert!(!run.stdout.contains("All subtasks"), "{}", run.stdout);
+        let run = sandbox.ok(&[&flags[..], &["done", "2"]].concat());
+        assert!(run.stdout.ends_with("   All subtasks of #1 are done; finish it with `todo done 1`\n"), "{}", run.stdout);
+        let stdout = sandbox.ok(&[&flags[..], &["list", "--tree"]].concat()).stdout;
+        assert!(stdout.starts_with("[ ] 1: move house [2/2 ✓] "), "{}", stdout);
+    }
+}
@@ -0,0 +1,102 @@
+#![cfg(unix)]
+
+mod common;
+
+use common::Sandbox;
+use std::fs;
+
+/// `todo` with `args`, on the database in the sandbox when `sqlite` is set
+fn todo(sandbox: &Sandbox, sqlite: bool, args: &[&str]) -> String {
+    let mut all = if sqlite { vec!["--sqlite"] } else { vec![] };
+    all.extend(args);
+    sandbox.ok(&all).stdout
+}
+
+fn listed(sandbox: &Sandbox, sqlite: bool) -> Vec<String> {
+    todo(sandbox, sqlite, &["list", "--format", "{id} {text}"]).lines().map(str::to_string).collect()
+}
+
+fn restore_after_the_newest_id_is_reused(sqlite: bool) {
+    let sandbox = Sandbox::new();
+    todo(&sandbox, sqlite, &["add", "first"]);
+    todo(&sandbox, sqlite, &["add", "second"]);
+    todo(&sandbox, sqlite, &["delete", "2", "--force"]);
+    todo(&sandbox, sqlite, &["add", "third"]);
+    todo(&sandbox, sqlite, &["trash", "restore", "2"]);
+    assert_eq!(listed(&sandbox, sqlite), ["1 first", "2 second", "3 third"]);
+}
+
+#[test]
+fn restore_keeps_ids_distinct_in_json() {
+    restore_after_the_newest_id_is_reused(false);
+}
+
+#[test]
+fn restore_keeps_ids_distinct_in_sqlite() {
+    restore_after_the_newest_id_is_reused(true);
+}
+
+fn ids_emptied_from_the_trash_are_not_given_out_again(sandbox: Sandbox, sqlite: bool) {
+    for text in ["first", "second", "third"] {
+        todo(&sandbox, sqlite, &["add", text]);
+    }
+    todo(&sandbox, sqlite, &["delete", "3", "--force"]);
+    todo(&sandbox, sqlite, &["delete", "2", "--force"]);
+    todo(&sandbox, sqlite, &["trash", "empty", "--force"]);
+    todo(&sandbox, sqlite, &["add", "fourth"]);
+    todo(&sandbox, sqlite, &["add", "fifth"]);
+    assert_eq!(listed(&sandbox, sqlite), ["1 first", "4 fourth", "5 fifth"]);
+}
+
+#[test]
+fn emptying_the_trash_frees_no_ids_in_json() {
+    ids_emptied_from_the_trash_are_not_given_out_again(Sandbox::new(), false);
+}
+
+#[test]
+fn emptying_the_trash_frees_no_ids_in_jsonl() {
+    let sandbox = Sandbox::new();
+    sandbox.config("json_format = \"jsonl\"\n");
+    ids_emptied_from_the_trash_are_not_given_out_again(sandbox, false);
+}
+
+#[test]
+fn emptying_the_trash_frees_no_ids_in_sqlite() {
+    ids_emptied_from_the_trash_are_not_given_out_again(Sandbox::new(), true);
+}
+
+#[test]
+fn emptying_the_trash_is_held_back_by_dry_run_and_read_only_mode() {
+    let sandbox = Sandbox::new();
+    sandbox.ok(&["add", "first"]);
+    sandbox.ok(&["delete", "1", "--force"]);
+    let trash = sandbox.data_dir().join("trash.json");
+    let kept = fs::read(&trash).unwrap();
+
+    let run = sandbox.ok(&["--dry-run", "trash", "empty", "--force"]);
+    assert!(run.stdout.contains("would delete the 1 todo in the trash"), "{}", run.stdout);
+    let run = sandbox.run_with(&["trash", "empty", "--force"], &[("TODO_READONLY", "1")]);
+    assert_eq!(run.code, 1);
+    assert!(run.stderr.contains("read-only"), "{}", run.stderr);
+    assert_eq!(fs::read(&trash).unwrap(), kept);
+
+    let run = sandbox.ok(&["trash", "empty", "--force"]);
+    assert!(run.stdout.contains("🗑️ Emptied the trash of 1 todo\n"), "{}", run.stdout);
+    assert!(sandbox.ok(&["trash", "list"]).stdout.contains("The trash is empty"));
+}
+
+#[test]
+fn a_corrupt_trash_is_reported_and_left_alone() {
+    let sandbox = Sandbox::new();
+    sandbox.ok(&["add", "first"]);
+    let trash = sandbox.data_dir().join("trash.json");
+    fs::write(&trash, "[{\"deleted_at\":").unwrap();
+    for args in [&["trash", "list"][..], &["trash", "empty", "--force"], &["trash", "restore", "1"]] {
+        let run = sandbox.run(args);
+        assert_eq!(run.code, 1, "{:?}", args);
+        assert!(run.stderr.contains("❌ Could not read the trash: "), "{}", run.stderr);
+    }
+    let run = sandbox.ok(&["delete", "1", "--force"]);
+    assert!(run.stderr.contains("⚠️ Could not update the trash: "), "{}", run.stderr);
+    assert_eq!(fs::read_to_string(&trash).unwrap(), "[{\"deleted_at\":");
+}
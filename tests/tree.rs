@@ -0,0 +1,69 @@
+#![cfg(unix)]
+
+//! `list --tree` drawing, `--depth`, and parent ids a hand edit has broken
+
+mod common;
+
+use common::Sandbox;
+use rusqlite::{params, Connection};
+
+/// A sandbox holding todos "a" to "d" with the parent ids `parents`, written straight into
+/// the store as a hand edit would, on the database when `sqlite` is set
+fn with_parents(sqlite: bool, parents: [Option<usize>; 4]) -> (Sandbox, Vec<&'static str>) {
+    let sandbox = Sandbox::new();
+    let flags = if sqlite { vec!["--sqlite"] } else { vec![] };
+    for text in ["a", "b", "c", "d"] {
+        sandbox.ok(&[&flags[..], &["add", text]].concat());
+    }
+    if sqlite {
+        let conn = Connection::open(sandbox.db_path()).unwrap();
+        for (id, parent) in parents.iter().enumerate() {
+            conn.execute("UPDATE todos SET parent = ?1 WHERE id = ?2", params![parent, id + 1]).unwrap();
+        }
+    } else {
+        let mut store: serde_json::Value = serde_json::from_slice(&std::fs::read(sandbox.json_path()).unwrap()).unwrap();
+        for (todo, parent) in store["todos"].as_array_mut().unwrap().iter_mut().zip(parents) {
+            todo["parent"] = parent.into();
+        }
+        std::fs::write(sandbox.json_path(), store.to_string()).unwrap();
+    }
+    (sandbox, flags)
+}
+
+#[test]
+fn nested_subtasks_are_drawn_with_connectors() {
+    for sqlite in [false, true] {
+        let (sandbox, flags) = with_parents(sqlite, [None, Some(1), Some(2), Some(1)]);
+        let run = sandbox.ok(&[&flags[..], &["list", "--tree", "--format", "{id} {text}"]].concat());
+        assert_eq!(run.stdout, "1 a\n├── 2 b\n│   └── 3 c\n└── 4 d\n");
+        assert!(run.stderr.is_empty(), "{}", run.stderr);
+        let run = sandbox.ok(&[&flags[..], &["list", "--tree", "--depth", "1", "--format", "{id} {text}"]].concat());
+        assert_eq!(run.stdout, "1 a\n├── 2 b\n└── 4 d\n");
+    }
+}
+
+#[test]
+fn loops_and_missing_parents_are_shown_at_the_top_level_with_a_warning() {
+    for sqlite in [false, true] {
+        let (sandbox, flags) = with_parents(sqlite, [Some(2), Some(1), Some(9), Some(3)]);
+        let run = sandbox.ok(&[&flags[..], &["list", "--tree", "--format", "{id} {text}"]].concat());
+        assert_eq!(run.stdout, "1 a\n2 b\n3 c\n└── 4 d\n");
+        assert_eq!(
+            run.stderr,
+            "⚠️ Todo 3's parent #9 doesn't exist; it is shown at the top level\n\
+             ⚠️ The parents of #1, #2 go round in a loop; they are shown at the top level\n"
+        );
+        // The flat list doesn't follow parents, so has nothing to warn about
+        assert!(sandbox.ok(&[&flags[..], &["list"]].concat()).stderr.is_empty());
+    }
+}
+
+#[test]
+fn rolled_up_progress_marks_a_parent_whose_subtasks_are_all_done() {
+    let (sandbox, _) = with_parents(false, [None, Some(1), Some(1), None]);
+    sandbox.ok(&["done", "2"]);
+    sandbox.ok(&["done", "3"]);
+    let stdout = sandbox.ok(&["list", "--tree"]).stdout;
+    assert!(stdout.starts_with("[ ] 1: a [2/2 ✓] "), "{}", stdout);
+    assert!(stdout.contains("\n└── [✓] 3: c "), "{}", stdout);
+}
@@ -0,0 +1,67 @@
+#![cfg(unix)]
+
+//! `upcoming --next --short` and `--format`, for a shell prompt or status bar
+
+mod common;
+
+use chrono::{Duration, Local};
+use common::Sandbox;
+
+/// The date `days` from today as `remind` takes it
+fn from_today(days: i64) -> String {
+    (Local::now().date_naive() + Duration::days(days)).format("%Y-%m-%d").to_string()
+}
+
+/// A sandbox with reminders to call the dentist in three days and pay the rent in two,
+/// on the database when `sqlite` is set
+fn reminded(sqlite: bool) -> (Sandbox, Vec<&'static str>) {
+    let sandbox = Sandbox::new();
+    let flags = if sqlite { vec!["--sqlite"] } else { vec![] };
+    let todo = |args: &[&str]| sandbox.ok(&[&flags[..], args].concat());
+    todo(&["add", "call dentist"]);
+    todo(&["add", "pay rent"]);
+    todo(&["add", "no reminder"]);
+    todo(&["remind", "1", &from_today(3), "14:30"]);
+    todo(&["remind", "2", &from_today(2), "08:00"]);
+    (sandbox, flags)
+}
+
+#[test]
+fn next_short_prints_exactly_the_soonest_reminder() {
+    for sqlite in [false, true] {
+        let (sandbox, flags) = reminded(sqlite);
+        let run = sandbox.ok(&[&flags[..], &["upcoming", "--next", "--short"]].concat());
+        assert_eq!(run.stdout, "08:00 pay rent\n");
+        assert!(run.stderr.is_empty(), "{}", run.stderr);
+        let run = sandbox.ok(&[&flags[..], &["upcoming", "--short"]].concat());
+        assert_eq!(run.stdout, "08:00 pay rent\n14:30 call dentist\n");
+    }
+}
+
+#[test]
+fn format_fills_in_every_placeholder() {
+    let (sandbox, _) = reminded(false);
+    let run = sandbox.ok(&["upcoming", "--next", "--format", "{date} {time} #{id} {{{text}}}"]);
+    assert_eq!(run.stdout, format!("{} 08:00 #2 {{pay rent}}\n", from_today(2)));
+}
+
+#[test]
+fn nothing_upcoming_prints_nothing_and_exits_1() {
+    for sqlite in [false, true] {
+        let sandbox = Sandbox::new();
+        let flags = if sqlite { vec!["--sqlite"] } else { vec![] };
+        sandbox.ok(&[&flags[..], &["add", "no reminder"]].concat());
+        let run = sandbox.run(&[&flags[..], &["upcoming", "--next", "--short"]].concat());
+        assert_eq!(run.code, 1);
+        assert!(run.stdout.is_empty(), "{}", run.stdout);
+    }
+}
+
+#[test]
+fn an_unknown_placeholder_is_refused() {
+    let (sandbox, _) = reminded(false);
+    let run = sandbox.run(&["upcoming", "--format", "{when}"]);
+    assert_eq!(run.code, 1);
+    assert!(run.stdout.is_empty(), "{}", run.stdout);
+    assert_eq!(run.stderr, "❌ Invalid format: unknown placeholder {when}; use one of {time}, {date}, {text}, {id}\n");
+}
@@ -0,0 +1,80 @@
+#![cfg(unix)]
+
+//! The urgency score: `list --sort urgency`, `upcoming --by-urgency`, `show --json` and
+//! the `[urgency]` weights
+
+mod common;
+
+use chrono::{Duration, Local};
+use common::Sandbox;
+
+/// The date `days` from today as `due` and `remind` take it
+fn from_today(days: i64) -> String {
+    (Local::now().date_naive() + Duration::days(days)).format("%Y-%m-%d").to_string()
+}
+
+/// A sandbox with "no rush" (#1), "due soon" (#2) due tomorrow, "important" (#3) at high
+/// priority and a done "finished" (#4) due yesterday, on the database when `sqlite` is set
+fn ranked(sqlite: bool) -> (Sandbox, Vec<&'static str>) {
+    let sandbox = Sandbox::new();
+    let flags = if sqlite { vec!["--sqlite"] } else { vec![] };
+    let todo = |args: &[&str]| sandbox.ok(&[&flags[..], args].concat());
+    for text in ["no rush", "due soon", "important", "finished"] {
+        todo(&["add", text]);
+    }
+    todo(&["due", "2", &from_today(1)]);
+    todo(&["priority", "3", "high"]);
+    todo(&["due", "4", &from_today(-1)]);
+    todo(&["done", "4"]);
+    (sandbox, flags)
+}
+
+#[test]
+fn list_sorts_by_urgency_with_done_todos_last_and_unscored() {
+    for sqlite in [false, true] {
+        let (sandbox, flags) = ranked(sqlite);
+        let run = sandbox.ok(&[&flags[..], &["list", "--sort", "urgency", "--format", "{text} [{urgency}]"]].concat());
+        let lines: Vec<&str> = run.stdout.lines().collect();
+        assert_eq!(lines.len(), 4, "{}", run.stdout);
+        assert!(lines[0].starts_with("due soon ["), "{}", run.stdout);
+        assert_eq!(lines[1..], ["important [6.00]", "no rush [0.00]", "finished []"]);
+    }
+}
+
+#[test]
+fn weights_from_the_config_change_the_order() {
+    let (sandbox, _) = ranked(false);
+    sandbox.config("[urgency]\ndue = 1.0\npriority = 20.0\n");
+    let run = sandbox.ok(&["list", "--sort", "urgency", "--format", "{text} {urgency}"]);
+    let order: Vec<&str> = run.stdout.lines().map(|line| line.rsplit_once(' ').unwrap().0).collect();
+    assert_eq!(order, ["important", "due soon", "no rush", "finished"]);
+    assert!(run.stdout.starts_with("important 20.00\n"), "{}", run.stdout);
+}
+
+#[test]
+fn upcoming_by_urgency_puts_the_most_urgent_reminder_first() {
+    for sqlite in [false, true] {
+        let (sandbox, flags) = ranked(sqlite);
+        sandbox.ok(&[&flags[..], &["remind", "1", &from_today(2), "09:00"]].concat());
+        sandbox.ok(&[&flags[..], &["remind", "3", &from_today(3), "09:00"]].concat());
+        let ids = |args: &[&str]| -> Vec<String> {
+            let run = sandbox.ok(&[&flags[..], args, &["--format", "{id}"]].concat());
+            run.stdout.lines().map(str::to_string).collect()
+        };
+        assert_eq!(ids(&["upcoming"]), ["1", "3"]);
+        assert_eq!(ids(&["upcoming", "--by-urgency"]), ["3", "1"]);
+    }
+}
+
+#[test]
+fn show_json_carries_the_score_of_a_pending_todo() {
+    for sqlite in [false, true] {
+        let (sandbox, flags) = ranked(sqlite);
+        let shown = |id: &str| -> serde_json::Value {
+            serde_json::from_str(&sandbox.ok(&[&flags[..], &["show", id, "--json"]].concat()).stdout).unwrap()
+        };
+        assert_eq!(shown("3")["urgency"], 6.0);
+        assert_eq!(shown("1")["urgency"], 0.0);
+        assert!(shown("4").get("urgency").is_none());
+    }
+}